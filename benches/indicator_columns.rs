@@ -0,0 +1,66 @@
+//! SMA, EMA and RSI series over 1M candles: the `&[Candle]` functions against the
+//! `*_f64` variants reading `CandleBuffer::closes()`. Each group benches both, so
+//! the report shows the speedup side by side.
+//!
+//! cargo bench --bench indicator_columns
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use cct::indicators::buffer::CandleBuffer;
+use cct::indicators::candle::Candle;
+use cct::indicators::momentum::{rsi_series, rsi_series_f64};
+use cct::indicators::moving_averages::{ema_series, ema_series_f64, sma_series, sma_series_f64};
+
+const CANDLES: usize = 1_000_000;
+const PERIOD: usize = 50;
+// A run takes milliseconds at this size; criterion's default of 100 samples is slow to no benefit
+const SAMPLES: usize = 20;
+
+/// One-minute candles zigzagging around 100.
+fn candles() -> Vec<Candle> {
+    (0..CANDLES)
+        .map(|i| {
+            let close = 100.0 + ((i * 7919) % 200) as f64 * 0.05 - 5.0;
+            Candle::new(i as u64 * 60_000, close - 0.1, close + 0.5, close - 0.5, close, 1.0 + (i % 13) as f64)
+        })
+        .collect()
+}
+
+/// Benches one indicator both ways in its own group.
+fn compare(
+    c: &mut Criterion,
+    name: &str,
+    candles: &[Candle],
+    buffer: &CandleBuffer,
+    from_candles: impl Fn(&[Candle]) -> Vec<f64>,
+    from_closes: impl Fn(&[f64]) -> Vec<f64>,
+) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(SAMPLES);
+    group.bench_with_input(BenchmarkId::new("candles", CANDLES), candles, |b, candles| {
+        b.iter(|| from_candles(black_box(candles)))
+    });
+    group.bench_with_input(BenchmarkId::new("closes", CANDLES), buffer.closes(), |b, closes| {
+        b.iter(|| from_closes(black_box(closes)))
+    });
+    group.finish();
+}
+
+fn bench_columns(c: &mut Criterion) {
+    let candles = candles();
+    let buffer = CandleBuffer::from_candles(&candles);
+
+    let mut group = c.benchmark_group("CandleBuffer");
+    group.sample_size(SAMPLES);
+    group.bench_function("from_candles", |b| b.iter(|| CandleBuffer::from_candles(black_box(&candles))));
+    group.finish();
+
+    compare(c, "sma_series", &candles, &buffer, |c| sma_series(c, PERIOD), |closes| sma_series_f64(closes, PERIOD));
+    compare(c, "ema_series", &candles, &buffer, |c| ema_series(c, PERIOD), |closes| ema_series_f64(closes, PERIOD));
+    compare(c, "rsi_series", &candles, &buffer, |c| rsi_series(c, None), |closes| rsi_series_f64(closes, None));
+}
+
+criterion_group!(benches, bench_columns);
+criterion_main!(benches);
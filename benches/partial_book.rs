@@ -0,0 +1,66 @@
+//! Per-update cost of keeping a top-20 book from `@depth20`-style full snapshots:
+//! `PartialBook::replace` against `LocalOrderBook::apply`.
+//!
+//! cargo bench --bench partial_book
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use cct::market::market_data::{OrderBookUpdate, PriceLevel};
+use cct::market::{BookView, LocalOrderBook, PartialBook};
+
+const DEPTH: usize = 20;
+const UPDATES: usize = 1_000;
+const ROUNDS: usize = 200;
+
+/// Top-20 snapshots drifting around 100, the way consecutive partial depth messages do.
+fn updates() -> Vec<OrderBookUpdate> {
+    (0..UPDATES)
+        .map(|i| {
+            let mid = 100.0 + (i % 50) as f64 * 0.01;
+            let quantity = |offset: usize| 1.0 + ((i + offset) % 7) as f64;
+            let bids = (1..=DEPTH).map(|offset| PriceLevel::new(mid - offset as f64 * 0.01, quantity(offset)));
+            let asks = (1..=DEPTH).map(|offset| PriceLevel::new(mid + offset as f64 * 0.01, quantity(offset)));
+            OrderBookUpdate::snapshot(i as u64, "BTCUSDT", bids.collect(), asks.collect())
+        })
+        .collect()
+}
+
+/// Feeds every update `ROUNDS` times; returns the elapsed time.
+fn run(updates: &[OrderBookUpdate], mut apply: impl FnMut(&OrderBookUpdate) -> Option<f64>) -> Duration {
+    let started = Instant::now();
+    for _ in 0..ROUNDS {
+        for update in updates {
+            black_box(apply(update));
+        }
+    }
+    started.elapsed()
+}
+
+fn main() {
+    let updates = updates();
+    let mut partial = PartialBook::with_depth(DEPTH);
+    let mut local = LocalOrderBook::new();
+
+    let runs = [
+        (
+            "PartialBook",
+            run(&updates, |update| {
+                partial.replace(update);
+                partial.mid_price()
+            }),
+        ),
+        (
+            "LocalOrderBook",
+            run(&updates, |update| {
+                local.apply(update);
+                local.mid_price()
+            }),
+        ),
+    ];
+    let total = (UPDATES * ROUNDS) as f64;
+    for (label, elapsed) in &runs {
+        println!("{:<15} {:>9} updates  {:>7.0} ns/update", label, UPDATES * ROUNDS, elapsed.as_nanos() as f64 / total);
+    }
+    println!("PartialBook is {:.1}x faster", runs[1].1.as_secs_f64() / runs[0].1.as_secs_f64());
+}
@@ -0,0 +1,81 @@
+//! Heap allocations per parsed message when replaying the golden Binance capture
+//! (trades and klines), with the parser's symbol cache on and off.
+//!
+//! cargo bench --bench symbol_interning
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use cct::market::providers::binance::BinanceParser;
+use cct::market::{MessageParser, SymbolCache};
+
+const CAPTURE: &str = include_str!("../tests/fixtures/golden/binance_pipeline.ndjson");
+const ROUNDS: usize = 200;
+
+/// Counts allocations and reallocations, then defers to the system allocator.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Parses every frame `ROUNDS` times; returns (allocations, elapsed, items).
+fn replay(parser: &BinanceParser, frames: &[&str]) -> (usize, Duration, usize) {
+    // Warm-up pass fills the symbol cache
+    for frame in frames {
+        black_box(parser.parse_messages(frame));
+    }
+
+    let mut items = 0;
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..ROUNDS {
+        for frame in frames {
+            let parsed = black_box(parser.parse_messages(frame));
+            items += parsed.len();
+        }
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before, started.elapsed(), items)
+}
+
+fn main() {
+    let frames: Vec<&str> = CAPTURE.lines().filter(|line| !line.is_empty()).collect();
+
+    let runs = [
+        ("not interned", BinanceParser::new().with_symbol_cache(SymbolCache::new(0))),
+        ("interned", BinanceParser::new()),
+    ];
+    let mut per_item = Vec::new();
+    for (label, parser) in &runs {
+        let (allocations, elapsed, items) = replay(parser, &frames);
+        let allocations_per_item = allocations as f64 / items as f64;
+        println!(
+            "{:<13} {:>9} items  {:>6.2} allocations/item  {:>7.0} ns/item",
+            label,
+            items,
+            allocations_per_item,
+            elapsed.as_nanos() as f64 / items as f64
+        );
+        per_item.push(allocations_per_item);
+    }
+    println!("interning saves {:.2} allocations per item", per_item[0] - per_item[1]);
+}
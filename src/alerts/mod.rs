@@ -1 +1,226 @@
-// Alerts module placeholder
+//! Alert conditions evaluated on closed candles.
+//!
+//! Conditions are user closures. A condition that panics is quarantined: it is
+//! reported once, marked broken and skipped from then on, while the other alerts
+//! keep firing. Set `PanicPolicy::Propagate` to fail fast instead.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::engine::callback::{CallbackPanicked, PanicPolicy, call_isolated};
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+
+/// What a condition sees when a candle closes.
+#[derive(Debug, Clone, Copy)]
+pub struct AlertContext<'a> {
+    pub symbol: &'a str,
+    pub timeframe: Timeframe,
+    /// The candle that just closed
+    pub candle: &'a Candle,
+    /// Closed candles so far, oldest first, ending with `candle`
+    pub history: &'a [Candle],
+}
+
+type AlertCondition = Arc<dyn Fn(&AlertContext<'_>) -> bool + Send + Sync>;
+
+/// A named condition. Fires every closed candle it returns true for.
+#[derive(Clone)]
+pub struct Alert {
+    name: String,
+    condition: AlertCondition,
+}
+
+impl Alert {
+    pub fn new(
+        name: impl Into<String>,
+        condition: impl Fn(&AlertContext<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            condition: Arc::new(condition),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Debug for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Alert").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+/// Result of evaluating alerts on a closed candle.
+#[derive(Debug, Clone)]
+pub enum AlertEvent {
+    /// The condition returned true.
+    Triggered {
+        name: String,
+        symbol: String,
+        timeframe: Timeframe,
+        candle: Candle,
+    },
+    /// The condition panicked and has been quarantined. Reported once per alert.
+    Quarantined(CallbackPanicked),
+}
+
+#[derive(Debug)]
+struct AlertSlot {
+    alert: Alert,
+    broken: bool,
+}
+
+/// Evaluates a set of alerts, isolating panicking conditions.
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    alerts: Vec<AlertSlot>,
+    panic_policy: PanicPolicy,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how condition panics are handled. Default: `PanicPolicy::Isolate`.
+    pub fn with_panic_policy(mut self, panic_policy: PanicPolicy) -> Self {
+        self.panic_policy = panic_policy;
+        self
+    }
+
+    pub fn add(&mut self, alert: Alert) -> &mut Self {
+        self.alerts.push(AlertSlot { alert, broken: false });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.alerts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    /// Runs every healthy alert against a closed candle.
+    pub fn evaluate(&mut self, ctx: &AlertContext<'_>) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+
+        for slot in self.alerts.iter_mut().filter(|slot| !slot.broken) {
+            let context = format!("alert '{}'", slot.alert.name);
+            match call_isolated(self.panic_policy, &context, || (slot.alert.condition)(ctx)) {
+                Ok(true) => events.push(AlertEvent::Triggered {
+                    name: slot.alert.name.clone(),
+                    symbol: ctx.symbol.to_string(),
+                    timeframe: ctx.timeframe,
+                    candle: *ctx.candle,
+                }),
+                Ok(false) => {}
+                Err(panicked) => {
+                    eprintln!("[Alerts] {}; alert quarantined", panicked);
+                    slot.broken = true;
+                    events.push(AlertEvent::Quarantined(panicked));
+                }
+            }
+        }
+
+        events
+    }
+
+    /// True if the named alert was quarantined after a panic.
+    pub fn is_broken(&self, name: &str) -> bool {
+        self.alerts
+            .iter()
+            .any(|slot| slot.broken && slot.alert.name == name)
+    }
+
+    /// Names of quarantined alerts.
+    pub fn broken(&self) -> Vec<&str> {
+        self.alerts
+            .iter()
+            .filter(|slot| slot.broken)
+            .map(|slot| slot.alert.name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::AssertUnwindSafe;
+
+    fn closes(closes: &[f64]) -> Vec<Candle> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| Candle::new(i as u64 * 60_000, *close, *close, *close, *close, 1.0))
+            .collect()
+    }
+
+    fn evaluate_all(engine: &mut AlertEngine, candles: &[Candle]) -> Vec<AlertEvent> {
+        (0..candles.len())
+            .flat_map(|i| {
+                let ctx = AlertContext {
+                    symbol: "BTCUSDT",
+                    timeframe: Timeframe::M1,
+                    candle: &candles[i],
+                    history: &candles[..=i],
+                };
+                engine.evaluate(&ctx)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_panicking_alert_is_quarantined_and_healthy_keeps_firing() {
+        let mut engine = AlertEngine::new();
+        engine
+            .add(Alert::new("above_100", |ctx| ctx.candle.get_close() > 100.0))
+            .add(Alert::new("dip_check", |ctx| {
+                // Panics on the second candle
+                assert!(ctx.candle.get_close() >= 100.0, "unexpected dip");
+                false
+            }));
+
+        let candles = closes(&[101.0, 99.0, 102.0, 103.0]);
+        let events = evaluate_all(&mut engine, &candles);
+
+        let triggered: Vec<u64> = events
+            .iter()
+            .filter_map(|event| match event {
+                AlertEvent::Triggered { name, candle, .. } if name == "above_100" => {
+                    Some(candle.get_timestamp())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(triggered, vec![0, 120_000, 180_000]);
+
+        let quarantined: Vec<&CallbackPanicked> = events
+            .iter()
+            .filter_map(|event| match event {
+                AlertEvent::Quarantined(panicked) => Some(panicked),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(quarantined.len(), 1, "a broken alert is reported once");
+        assert_eq!(quarantined[0].context, "alert 'dip_check'");
+        assert!(quarantined[0].message.contains("unexpected dip"));
+
+        assert!(engine.is_broken("dip_check"));
+        assert!(!engine.is_broken("above_100"));
+        assert_eq!(engine.broken(), vec!["dip_check"]);
+    }
+
+    #[test]
+    fn test_propagate_policy_fails_fast() {
+        let mut engine = AlertEngine::new().with_panic_policy(PanicPolicy::Propagate);
+        engine.add(Alert::new("boom", |_| panic!("boom")));
+        let candles = closes(&[100.0]);
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| evaluate_all(&mut engine, &candles)));
+        assert!(result.is_err());
+    }
+}
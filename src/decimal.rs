@@ -0,0 +1,41 @@
+//! Exact decimal prices (`decimal` feature).
+//!
+//! Parsed prices and quantities stay `f64` everywhere, which is what the
+//! indicators want. With this feature, parsers that receive numbers as strings
+//! (Binance) also keep them as `Decimal`, so sums over millions of fills and
+//! tick sizes like 0.1 are exact. Read them with `Trade::price_decimal()`,
+//! `PriceLevel::price_decimal()` or `Candle::get_decimal_ohlcv()`; items without
+//! an exact value fall back to converting the `f64`.
+
+use serde::{Deserialize, Serialize};
+
+pub use rust_decimal::Decimal;
+
+/// Exact open/high/low/close/volume of a `Candle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecimalOhlcv {
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+}
+
+/// The shortest decimal that round-trips to `value` (what `{}` prints), e.g.
+/// 0.1 rather than 0.1000000000000000055511151231257827. NaN, infinities and
+/// values outside `Decimal`'s range become zero.
+pub fn from_f64(value: f64) -> Decimal {
+    value.to_string().parse().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_f64_uses_shortest_form() {
+        assert_eq!(from_f64(0.1).to_string(), "0.1");
+        assert_eq!(from_f64(50000.0), Decimal::from(50000));
+        assert_eq!(from_f64(f64::NAN), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,97 @@
+//! Panic isolation for user-supplied callbacks (alert conditions, rules, hooks).
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+// Design: callbacks run inside the task that drives the feed, so a panic in one
+// of them would otherwise end that task and everything it feeds. With
+// PanicPolicy::Isolate the panic is caught and returned as CallbackPanicked;
+// the caller is expected to quarantine the callback (never invoke it again),
+// which is why asserting unwind safety is acceptable here - whatever state the
+// closure left half-updated is only ever observed by that closure.
+//
+// The default panic hook still prints the panic to stderr; install your own
+// hook with std::panic::set_hook to silence or redirect it.
+
+/// What to do when a user callback panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Catch the panic, report it and quarantine the callback. Default.
+    #[default]
+    Isolate,
+    /// Let the panic unwind into the driving task (fail fast).
+    Propagate,
+}
+
+/// A user callback panicked and was caught.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackPanicked {
+    /// Which callback panicked, e.g. "alert 'rsi_oversold'"
+    pub context: String,
+    /// The panic message, if the payload was a string
+    pub message: String,
+}
+
+impl fmt::Display for CallbackPanicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} panicked: {}", self.context, self.message)
+    }
+}
+
+impl std::error::Error for CallbackPanicked {}
+
+/// Runs `f` according to `policy`.
+/// With `Isolate` a panic becomes `Err(CallbackPanicked)`; with `Propagate` it unwinds.
+pub fn call_isolated<R>(
+    policy: PanicPolicy,
+    context: &str,
+    f: impl FnOnce() -> R,
+) -> Result<R, CallbackPanicked> {
+    match policy {
+        PanicPolicy::Propagate => Ok(f()),
+        PanicPolicy::Isolate => panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+            CallbackPanicked {
+                context: context.to_string(),
+                message: panic_message(payload),
+            }
+        }),
+    }
+}
+
+/// Extracts the message from a panic payload.
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isolate_catches_panic() {
+        assert_eq!(call_isolated(PanicPolicy::Isolate, "ok", || 42), Ok(42));
+
+        let err = call_isolated(PanicPolicy::Isolate, "alert 'boom'", || -> u32 {
+            panic!("division by {}", 0)
+        })
+        .unwrap_err();
+        assert_eq!(err.context, "alert 'boom'");
+        assert_eq!(err.message, "division by 0");
+        assert_eq!(err.to_string(), "alert 'boom' panicked: division by 0");
+    }
+
+    #[test]
+    fn test_propagate_unwinds() {
+        let result = panic::catch_unwind(|| {
+            let _ = call_isolated(PanicPolicy::Propagate, "rule", || panic!("fail fast"));
+        });
+        assert!(result.is_err());
+    }
+}
@@ -1 +1,3 @@
-// Engine module placeholder
+pub mod callback;
+
+pub use callback::{CallbackPanicked, PanicPolicy, call_isolated};
@@ -0,0 +1,775 @@
+//! High-level facade that wires a client, candle history and indicators together.
+//!
+//! Everything here is built from the public `market` and `indicators` APIs; use
+//! those directly when you need more control than the facade offers.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::alerts::{Alert, AlertContext, AlertEngine, AlertEvent};
+use crate::engine::callback::PanicPolicy;
+use crate::indicators::candle::Candle;
+use crate::indicators::live::SeriesWithIndicators;
+use crate::indicators::series::CandleSeries;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::error::CctError;
+use crate::market::market_data::{MarketData, Trade, now_ms};
+use crate::market::message_parser::MessageParser;
+use crate::market::streams::Stream;
+use crate::market::symbol::Symbol;
+use crate::market::websocket_client::WebSocketClient;
+use crate::market::{
+    new_binance_client, new_binance_futures_client, new_binance_testnet_client, new_bitget_client,
+    new_deribit_client, new_gateio_client, new_kucoin_client, new_mexc_client,
+};
+
+pub use crate::indicators::live::Indicator;
+
+const DEFAULT_HISTORY: usize = 500;
+const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(60);
+const EVENT_CAPACITY: usize = 1024;
+// Closed candles backfilled per series, as a multiple of the longest indicator period
+const WARMUP_MULTIPLIER: usize = 3;
+
+// Design: the facade is what every consumer ends up writing by hand -
+// connect, subscribe symbol x timeframe, keep a bounded candle history per
+// series, recompute indicators when a candle closes, remember the last trade.
+// Each series is an indicators::live::SeriesWithIndicators, so in-progress vs
+// closed candles are handled by CandleSeries::apply as everywhere else.
+// State lives behind a RwLock so snapshot() is a cheap synchronous read, and
+// changes are published on a broadcast channel so several consumers can listen.
+//
+// Warm-up: after subscribing, each series is backfilled over REST
+// (MessageParser::fetch_candles) with the closed candles covering
+// Timeframe::required_history(longest period, WARMUP_MULTIPLIER), so indicators
+// have values from the first snapshot. Live data received meanwhile queues in
+// the feed and is applied on top; closed candles older than the backfill are dropped.
+
+/// Change notifications published by a running `App`.
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    /// A candle closed (final values).
+    CandleClosed {
+        symbol: String,
+        timeframe: Timeframe,
+        candle: Candle,
+    },
+    /// Indicators were recomputed after a candle close.
+    IndicatorsUpdated {
+        symbol: String,
+        timeframe: Timeframe,
+        values: Vec<(Indicator, f64)>,
+    },
+    /// A trade arrived for a tracked symbol.
+    Trade(Trade),
+    /// An alert fired, or its condition panicked and was quarantined.
+    Alert(AlertEvent),
+}
+
+/// Latest state of one timeframe for a symbol.
+#[derive(Debug, Clone)]
+pub struct TimeframeSnapshot {
+    /// Most recent candle (in-progress if `is_closed` is false).
+    pub candle: Option<Candle>,
+    pub is_closed: bool,
+    /// Number of closed candles held in history
+    pub history_len: usize,
+    /// Indicator values as of the last closed candle
+    pub indicators: Vec<(Indicator, f64)>,
+}
+
+impl TimeframeSnapshot {
+    pub fn indicator(&self, indicator: Indicator) -> Option<f64> {
+        self.indicators
+            .iter()
+            .find(|(i, _)| *i == indicator)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Point-in-time view of everything the facade knows about a symbol.
+#[derive(Debug, Clone)]
+pub struct SymbolSnapshot {
+    pub symbol: String,
+    pub timeframes: HashMap<Timeframe, TimeframeSnapshot>,
+    pub last_trade: Option<Trade>,
+    /// Time since the last message for this symbol (None = nothing received yet)
+    pub staleness: Option<Duration>,
+    /// True if nothing arrived within the configured `stale_after`
+    pub is_stale: bool,
+}
+
+impl SymbolSnapshot {
+    pub fn candle(&self, timeframe: Timeframe) -> Option<&Candle> {
+        self.timeframes.get(&timeframe)?.candle.as_ref()
+    }
+
+    pub fn indicator(&self, timeframe: Timeframe, indicator: Indicator) -> Option<f64> {
+        self.timeframes.get(&timeframe)?.indicator(indicator)
+    }
+}
+
+/// Builder for `App`. See `App::builder()`.
+#[derive(Debug, Clone)]
+pub struct AppBuilder {
+    exchange: String,
+    symbols: Vec<String>,
+    timeframes: Vec<Timeframe>,
+    indicators: Vec<Indicator>,
+    history: usize,
+    stale_after: Duration,
+    trades: bool,
+    backfill: bool,
+    alerts: Vec<Alert>,
+    panic_policy: PanicPolicy,
+}
+
+impl AppBuilder {
+    fn new() -> Self {
+        Self {
+            exchange: "binance".to_string(),
+            symbols: Vec::new(),
+            timeframes: vec![Timeframe::M1],
+            indicators: Vec::new(),
+            history: DEFAULT_HISTORY,
+            stale_after: DEFAULT_STALE_AFTER,
+            trades: true,
+            backfill: true,
+            alerts: Vec::new(),
+            panic_policy: PanicPolicy::default(),
+        }
+    }
+
+    /// Exchange to connect to by name: "binance", "binance-testnet", "binance-futures",
+    /// "bitget", "deribit", "gateio", "kucoin" or "mexc". Default: "binance".
+    pub fn exchange(mut self, exchange: impl Into<String>) -> Self {
+        self.exchange = exchange.into();
+        self
+    }
+
+    pub fn symbols<I, S>(mut self, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.symbols = symbols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Candle timeframes to subscribe to for every symbol. Default: [M1].
+    pub fn timeframes(mut self, timeframes: impl IntoIterator<Item = Timeframe>) -> Self {
+        self.timeframes = timeframes.into_iter().collect();
+        self
+    }
+
+    pub fn indicators(mut self, indicators: impl IntoIterator<Item = Indicator>) -> Self {
+        self.indicators = indicators.into_iter().collect();
+        self
+    }
+
+    /// Candles kept per (symbol, timeframe), the in-progress one included. Default: 500.
+    pub fn history(mut self, history: usize) -> Self {
+        debug_assert!(history > 0, "history must be greater than zero");
+        self.history = history;
+        self
+    }
+
+    /// Age after which a symbol's snapshot is flagged stale. Default: 60s.
+    pub fn stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Whether to subscribe to trades for the last-trade field. Default: true.
+    pub fn trades(mut self, trades: bool) -> Self {
+        self.trades = trades;
+        self
+    }
+
+    /// Whether `build()` warms up history over REST before applying live data, so
+    /// indicators don't wait for `period` live closes. Default: true. Exchanges
+    /// without a REST history hook start empty either way.
+    pub fn backfill(mut self, backfill: bool) -> Self {
+        self.backfill = backfill;
+        self
+    }
+
+    /// Adds an alert, evaluated on every closed candle of every tracked series.
+    /// Fires `AppEvent::Alert(AlertEvent::Triggered { .. })` when the condition is true.
+    pub fn alert(
+        mut self,
+        name: impl Into<String>,
+        condition: impl Fn(&AlertContext<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.alerts.push(Alert::new(name, condition));
+        self
+    }
+
+    /// How panics in alert conditions are handled. Default: `PanicPolicy::Isolate`,
+    /// which quarantines the alert and keeps the feed running. `Propagate` stops processing.
+    pub fn panic_policy(mut self, panic_policy: PanicPolicy) -> Self {
+        self.panic_policy = panic_policy;
+        self
+    }
+
+    /// Connects to the configured exchange, subscribes, and starts processing.
+    pub async fn build(self) -> Result<App, CctError> {
+        match self.exchange.to_lowercase().as_str() {
+            "binance" => self.build_with_client(new_binance_client()).await,
+            "binance-testnet" => self.build_with_client(new_binance_testnet_client()).await,
+            "binance-futures" => self.build_with_client(new_binance_futures_client()).await,
+            "bitget" => self.build_with_client(new_bitget_client()).await,
+            "deribit" => self.build_with_client(new_deribit_client()).await,
+            "gateio" => self.build_with_client(new_gateio_client()).await,
+            "kucoin" => self.build_with_client(new_kucoin_client()).await,
+            "mexc" => self.build_with_client(new_mexc_client()).await,
+            other => Err(CctError::InvalidConfig(format!("Unknown exchange: {}", other))),
+        }
+    }
+
+    /// Like `build()`, but with a client you constructed (any MessageParser).
+    pub async fn build_with_client<P: MessageParser>(
+        self,
+        mut client: WebSocketClient<P>,
+    ) -> Result<App, CctError> {
+        let rx = client.connect().await?;
+
+        let mut streams = Vec::new();
+        for symbol in &self.symbols {
+            for timeframe in &self.timeframes {
+                streams.push(Stream::candles(symbol.clone(), *timeframe));
+            }
+            if self.trades {
+                streams.push(Stream::trades(symbol.clone()));
+            }
+        }
+        client.subscribe_many(streams).await?;
+
+        let seeds = match self.fetch_warmup(client.parser()).await {
+            Ok(seeds) => seeds,
+            Err(e) => {
+                client.disconnect().await;
+                return Err(e);
+            }
+        };
+        let mut app = self.start(rx, seeds);
+
+        // The client task keeps the connection alive until the App shuts down
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        app.client_handle = Some(tokio::spawn(async move {
+            let _ = shutdown_rx.await; // Err = App dropped, also a shutdown
+            client.disconnect().await;
+        }));
+        app.shutdown = Some(shutdown_tx);
+
+        Ok(app)
+    }
+
+    /// Starts processing an existing MarketData feed (no connection is made).
+    /// Useful with your own client, a replay, or a scripted feed in tests.
+    pub fn build_with_receiver(self, rx: mpsc::Receiver<MarketData>) -> App {
+        self.start(rx, Vec::new())
+    }
+
+    /// Closed candles to backfill per series: enough to warm up the longest
+    /// indicator, capped at `history`. Zero without indicators.
+    fn warmup_candles(&self) -> usize {
+        let longest = self.indicators.iter().map(|indicator| indicator.period()).max().unwrap_or(0);
+        longest.saturating_mul(WARMUP_MULTIPLIER).min(self.history)
+    }
+
+    /// Fetches the warm-up history of every symbol x timeframe, ending at the
+    /// current (still open) candle.
+    async fn fetch_warmup<P: MessageParser>(
+        &self,
+        parser: &P,
+    ) -> Result<Vec<(String, Timeframe, Vec<Candle>)>, CctError> {
+        let count = self.warmup_candles();
+        if !self.backfill || count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let now = now_ms();
+        let mut seeds = Vec::new();
+        for symbol in &self.symbols {
+            for timeframe in &self.timeframes {
+                let start = timeframe.lookback_start(now, count as u64);
+                let candles = parser.fetch_candles(symbol, *timeframe, start, timeframe.align(now)).await?;
+                seeds.push((symbol.clone(), *timeframe, candles));
+            }
+        }
+        Ok(seeds)
+    }
+
+    fn start(self, mut rx: mpsc::Receiver<MarketData>, seeds: Vec<(String, Timeframe, Vec<Candle>)>) -> App {
+        let mut state = AppState::new(&self);
+        for (symbol, timeframe, candles) in seeds {
+            state.seed(symbol.into(), timeframe, candles);
+        }
+        let state = Arc::new(RwLock::new(state));
+        let (events_tx, _) = broadcast::channel(EVENT_CAPACITY);
+
+        let pump_state = Arc::clone(&state);
+        let pump_events = events_tx.clone();
+        let pump_handle = tokio::spawn(async move {
+            while let Some(data) = rx.recv().await {
+                let events = match pump_state.write() {
+                    Ok(mut state) => state.apply(data),
+                    Err(poisoned) => poisoned.into_inner().apply(data),
+                };
+                for event in events {
+                    // No subscribers is fine - snapshot() still works
+                    let _ = pump_events.send(event);
+                }
+            }
+        });
+
+        App {
+            state,
+            events: events_tx,
+            stale_after: self.stale_after,
+            pump_handle,
+            client_handle: None,
+            shutdown: None,
+        }
+    }
+}
+
+/// Running facade: snapshot access plus a change-event stream.
+pub struct App {
+    state: Arc<RwLock<AppState>>,
+    events: broadcast::Sender<AppEvent>,
+    stale_after: Duration,
+    pump_handle: JoinHandle<()>,
+    client_handle: Option<JoinHandle<()>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl App {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::new()
+    }
+
+    /// Returns the current state of a symbol, or None if nothing was received or
+    /// backfilled for it.
+    pub fn snapshot(&self, symbol: &str) -> Option<SymbolSnapshot> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        state.snapshot(symbol, self.stale_after)
+    }
+
+    /// Symbols that have data so far (received or backfilled).
+    pub fn symbols(&self) -> Vec<String> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        let mut symbols: Vec<String> = state.symbols.keys().map(|symbol| symbol.to_string()).collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Subscribes to change events. Each call returns an independent receiver.
+    pub fn events(&self) -> broadcast::Receiver<AppEvent> {
+        self.events.subscribe()
+    }
+
+    /// Waits until the feed closes and every message has been applied.
+    /// Meant for finite feeds (replays, scripted tests); a live feed only ends on disconnect.
+    pub async fn wait_for_feed_end(&mut self) {
+        if !self.pump_handle.is_finished() {
+            let _ = (&mut self.pump_handle).await;
+        }
+    }
+
+    /// Disconnects the client (if any) and stops processing.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.client_handle.take() {
+            let _ = handle.await;
+        }
+        self.pump_handle.abort();
+    }
+}
+
+#[derive(Debug, Default)]
+struct SymbolState {
+    series: HashMap<Timeframe, SeriesWithIndicators>,
+    last_trade: Option<Trade>,
+    last_update: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct AppState {
+    symbols_filter: Vec<String>,
+    timeframes: Vec<Timeframe>,
+    indicators: Vec<Indicator>,
+    history: usize,
+    symbols: HashMap<Symbol, SymbolState>,
+    alerts: AlertEngine,
+}
+
+impl AppState {
+    fn new(config: &AppBuilder) -> Self {
+        let mut alerts = AlertEngine::new().with_panic_policy(config.panic_policy);
+        for alert in &config.alerts {
+            alerts.add(alert.clone());
+        }
+        Self {
+            symbols_filter: config.symbols.clone(),
+            timeframes: config.timeframes.clone(),
+            indicators: config.indicators.clone(),
+            history: config.history,
+            symbols: HashMap::new(),
+            alerts,
+        }
+    }
+
+    fn tracks(&self, symbol: &str) -> bool {
+        // Exchanges may echo symbols in a different case than configured
+        self.symbols_filter.is_empty()
+            || self
+                .symbols_filter
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(symbol))
+    }
+
+    /// Warm-up history for a series: no events, and no effect on staleness.
+    fn seed(&mut self, symbol: Symbol, timeframe: Timeframe, candles: Vec<Candle>) {
+        if candles.is_empty() {
+            return;
+        }
+        let series = self.series(symbol, timeframe);
+        for candle in candles {
+            series.push(candle);
+        }
+        // Kept fresh after every change so snapshot() can read them without recomputing
+        series.latest_values();
+    }
+
+    fn series(&mut self, symbol: Symbol, timeframe: Timeframe) -> &mut SeriesWithIndicators {
+        let state = self.symbols.entry(symbol.clone()).or_default();
+        state.series.entry(timeframe).or_insert_with(|| {
+            let candles = CandleSeries::new(self.history).with_stream(symbol, timeframe);
+            SeriesWithIndicators::new(candles, &self.indicators)
+        })
+    }
+
+    fn apply(&mut self, data: MarketData) -> Vec<AppEvent> {
+        if !self.tracks(data.symbol()) {
+            return Vec::new();
+        }
+
+        match data {
+            MarketData::Candle { ref symbol, interval, .. } => {
+                if !self.timeframes.contains(&interval) {
+                    return Vec::new();
+                }
+                let symbol = symbol.clone();
+                self.apply_candle(symbol, interval, &data)
+            }
+            MarketData::Trade(trade) => {
+                let state = self.symbols.entry(trade.symbol.clone()).or_default();
+                state.last_update = Some(Instant::now());
+                state.last_trade = Some(trade.clone());
+                vec![AppEvent::Trade(trade)]
+            }
+            other => {
+                // Only counts towards staleness
+                if let Some(state) = self.symbols.get_mut(other.symbol()) {
+                    state.last_update = Some(Instant::now());
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    fn apply_candle(&mut self, symbol: Symbol, timeframe: Timeframe, data: &MarketData) -> Vec<AppEvent> {
+        self.symbols.entry(symbol.clone()).or_default().last_update = Some(Instant::now());
+        let series = self.series(symbol.clone(), timeframe);
+
+        // In-progress updates, resent closes and candles older than the backfill
+        // change nothing downstream; a close may be of the previous candle, when a
+        // newer one opened before its closing push arrived
+        if !series.apply(data).closed() {
+            return Vec::new();
+        }
+        let Some(candle) = series.closed_candles().last().copied() else {
+            return Vec::new();
+        };
+        let values = present(series.latest_values());
+
+        let mut events = vec![AppEvent::CandleClosed {
+            symbol: symbol.to_string(),
+            timeframe,
+            candle,
+        }];
+        if !values.is_empty() {
+            events.push(AppEvent::IndicatorsUpdated {
+                symbol: symbol.to_string(),
+                timeframe,
+                values,
+            });
+        }
+
+        // Looked up again: `series` borrowed all of self, the alert engine needs its own borrow
+        let history = self.symbols[&symbol].series[&timeframe].closed_candles();
+        let ctx = AlertContext {
+            symbol: &symbol,
+            timeframe,
+            candle: &candle,
+            history,
+        };
+        events.extend(self.alerts.evaluate(&ctx).into_iter().map(AppEvent::Alert));
+        events
+    }
+
+    fn snapshot(&self, symbol: &str, stale_after: Duration) -> Option<SymbolSnapshot> {
+        let (symbol, state) = self
+            .symbols
+            .iter()
+            .find(|(s, _)| s.eq_ignore_ascii_case(symbol))?;
+
+        let timeframes = state
+            .series
+            .iter()
+            .map(|(timeframe, series)| {
+                let snapshot = TimeframeSnapshot {
+                    candle: series.series().last().copied(),
+                    is_closed: series.series().is_last_closed(),
+                    history_len: series.closed_candles().len(),
+                    indicators: present(series.cached_values()),
+                };
+                (*timeframe, snapshot)
+            })
+            .collect();
+
+        let staleness = state.last_update.map(|t| t.elapsed());
+
+        Some(SymbolSnapshot {
+            symbol: symbol.to_string(),
+            timeframes,
+            last_trade: state.last_trade.clone(),
+            staleness,
+            is_stale: staleness.is_none_or(|age| age > stale_after),
+        })
+    }
+}
+
+/// The indicator values that are past their warm-up.
+fn present(values: &[(Indicator, Option<f64>)]) -> Vec<(Indicator, f64)> {
+    values.iter().filter_map(|(indicator, value)| value.map(|value| (*indicator, value))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::TradeSide;
+
+    fn candle_msg(symbol: &str, timeframe: Timeframe, ts: u64, close: f64, is_closed: bool) -> MarketData {
+        let candle = Candle::new(ts, close, close + 1.0, close - 1.0, close, 10.0);
+        MarketData::candle(symbol, timeframe, candle, is_closed)
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_after_scripted_feed() {
+        let (tx, rx) = mpsc::channel(64);
+        let mut app = App::builder()
+            .symbols(["BTCUSDT", "ETHUSDT"])
+            .timeframes([Timeframe::M1, Timeframe::H1])
+            .indicators([Indicator::Sma(3), Indicator::Rsi(2)])
+            .build_with_receiver(rx);
+        let mut events = app.events();
+
+        for (i, close) in [100.0, 102.0, 104.0, 103.0].iter().enumerate() {
+            tx.send(candle_msg("BTCUSDT", Timeframe::M1, i as u64 * 60_000, *close, true))
+                .await
+                .unwrap();
+        }
+        tx.send(candle_msg("BTCUSDT", Timeframe::M1, 240_000, 105.0, false)).await.unwrap();
+        tx.send(candle_msg("BTCUSDT", Timeframe::H1, 0, 100.0, true)).await.unwrap();
+        tx.send(MarketData::Trade(Trade::new(240_500, "BTCUSDT", 105.5, 0.1, "t1", TradeSide::Buy)))
+            .await
+            .unwrap();
+        // Untracked symbol and timeframe are ignored
+        tx.send(candle_msg("SOLUSDT", Timeframe::M1, 0, 20.0, true)).await.unwrap();
+        tx.send(candle_msg("BTCUSDT", Timeframe::M5, 0, 100.0, true)).await.unwrap();
+
+        drop(tx);
+        app.wait_for_feed_end().await;
+        let snapshot = app.snapshot("BTCUSDT").unwrap();
+
+        let m1 = &snapshot.timeframes[&Timeframe::M1];
+        assert_eq!(m1.history_len, 4);
+        assert!(!m1.is_closed);
+        assert_eq!(m1.candle.unwrap().get_close(), 105.0);
+        // SMA(3) over closed candles 102, 104, 103
+        assert_eq!(m1.indicator(Indicator::Sma(3)), Some(103.0));
+        assert!(m1.indicator(Indicator::Rsi(2)).is_some());
+
+        // H1 has one candle: SMA(3) not ready yet
+        assert_eq!(snapshot.indicator(Timeframe::H1, Indicator::Sma(3)), None);
+        assert!(snapshot.timeframes[&Timeframe::H1].is_closed);
+        assert!(!snapshot.timeframes.contains_key(&Timeframe::M5));
+
+        assert_eq!(snapshot.last_trade.as_ref().unwrap().price, 105.5);
+        assert!(!snapshot.is_stale);
+        assert!(app.snapshot("SOLUSDT").is_none());
+        assert!(app.snapshot("ETHUSDT").is_none());
+
+        // Events: first is the first M1 close
+        match events.recv().await.unwrap() {
+            AppEvent::CandleClosed { symbol, timeframe, candle } => {
+                assert_eq!(symbol, "BTCUSDT");
+                assert_eq!(timeframe, Timeframe::M1);
+                assert_eq!(candle.get_close(), 100.0);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        app.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_history_is_bounded_and_resent_closes_ignored() {
+        let (tx, rx) = mpsc::channel(64);
+        let mut app = App::builder()
+            .symbols(["BTCUSDT"])
+            .history(3)
+            .build_with_receiver(rx);
+        let mut events = app.events();
+
+        for i in 0..5u64 {
+            tx.send(candle_msg("BTCUSDT", Timeframe::M1, i * 60_000, 100.0 + i as f64, true))
+                .await
+                .unwrap();
+        }
+        // Same open time resent: the first close stands and nothing is published
+        tx.send(candle_msg("BTCUSDT", Timeframe::M1, 4 * 60_000, 110.0, true)).await.unwrap();
+        tx.send(candle_msg("BTCUSDT", Timeframe::M1, 5 * 60_000, 111.0, false)).await.unwrap();
+
+        drop(tx);
+        app.wait_for_feed_end().await;
+
+        let mut closes = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AppEvent::CandleClosed { candle, .. } = event {
+                closes.push(candle.get_close());
+            }
+        }
+        assert_eq!(closes, vec![100.0, 101.0, 102.0, 103.0, 104.0]);
+
+        // History 3 holds the in-progress candle and the two latest closed ones
+        let snapshot = app.snapshot("BTCUSDT").unwrap();
+        assert_eq!(snapshot.timeframes[&Timeframe::M1].history_len, 2);
+        assert_eq!(snapshot.candle(Timeframe::M1).unwrap().get_close(), 111.0);
+    }
+
+    #[test]
+    fn test_staleness_tracks_live_updates() {
+        let builder = App::builder().symbols(["BTCUSDT"]);
+        let mut state = AppState::new(&builder);
+        let backfill = (0..3).map(|i| Candle::new(i * 60_000, 100.0, 101.0, 99.0, 100.0, 10.0)).collect();
+        state.seed("BTCUSDT".into(), Timeframe::M1, backfill);
+
+        // Backfilled history alone is not live data
+        let seeded = state.snapshot("BTCUSDT", Duration::from_secs(60)).unwrap();
+        assert_eq!(seeded.staleness, None);
+        assert!(seeded.is_stale);
+
+        state.apply(candle_msg("BTCUSDT", Timeframe::M1, 3 * 60_000, 100.0, true));
+
+        let fresh = state.snapshot("btcusdt", Duration::from_secs(60)).unwrap();
+        assert!(!fresh.is_stale);
+        assert!(fresh.staleness.is_some());
+
+        let stale = state.snapshot("BTCUSDT", Duration::ZERO).unwrap();
+        assert!(stale.is_stale);
+    }
+
+    #[test]
+    fn test_backfill_warms_up_indicators_without_events() {
+        let builder = App::builder()
+            .symbols(["BTCUSDT"])
+            .indicators([Indicator::Sma(3)])
+            .history(4);
+        assert_eq!(builder.warmup_candles(), 4);
+        let mut state = AppState::new(&builder);
+        let backfill = [100.0, 101.0, 102.0, 103.0, 104.0]
+            .iter()
+            .enumerate()
+            .map(|(i, close)| Candle::new(i as u64 * 60_000, *close, *close + 1.0, *close - 1.0, *close, 10.0))
+            .collect();
+        state.seed("BTCUSDT".into(), Timeframe::M1, backfill);
+
+        let snapshot = state.snapshot("BTCUSDT", Duration::from_secs(60)).unwrap();
+        let m1 = &snapshot.timeframes[&Timeframe::M1];
+        // Trimmed to history; SMA(3) over 102, 103, 104 is ready before any live close
+        assert_eq!(m1.history_len, 4);
+        assert_eq!(m1.indicator(Indicator::Sma(3)), Some(103.0));
+
+        // Closed candles already covered by the backfill are dropped
+        assert!(state.apply(candle_msg("BTCUSDT", Timeframe::M1, 2 * 60_000, 50.0, true)).is_empty());
+        assert!(state.apply(candle_msg("BTCUSDT", Timeframe::M1, 4 * 60_000, 107.0, true)).is_empty());
+        let events = state.apply(candle_msg("BTCUSDT", Timeframe::M1, 5 * 60_000, 108.0, true));
+        assert_eq!(events.len(), 2);
+        let snapshot = state.snapshot("BTCUSDT", Duration::from_secs(60)).unwrap();
+        assert_eq!(snapshot.timeframes[&Timeframe::M1].history_len, 4);
+        assert_eq!(snapshot.indicator(Timeframe::M1, Indicator::Sma(3)), Some(105.0));
+    }
+
+    #[test]
+    fn test_no_warmup_without_indicators() {
+        assert_eq!(App::builder().warmup_candles(), 0);
+        let builder = App::builder().indicators([Indicator::Rsi(14), Indicator::Sma(50)]);
+        assert_eq!(builder.warmup_candles(), 150);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_alert_does_not_stop_the_feed() {
+        let (tx, rx) = mpsc::channel(64);
+        let mut app = App::builder()
+            .symbols(["BTCUSDT"])
+            .alert("above_100", |ctx| ctx.candle.get_close() > 100.0)
+            .alert("broken", |ctx| ctx.history[ctx.history.len()].get_close() > 0.0)
+            .build_with_receiver(rx);
+        let mut events = app.events();
+
+        for (i, close) in [101.0, 99.0, 102.0].iter().enumerate() {
+            tx.send(candle_msg("BTCUSDT", Timeframe::M1, i as u64 * 60_000, *close, true))
+                .await
+                .unwrap();
+        }
+        drop(tx);
+        app.wait_for_feed_end().await;
+
+        let mut fired = Vec::new();
+        let mut quarantined = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            match event {
+                AppEvent::Alert(AlertEvent::Triggered { name, candle, .. }) => {
+                    fired.push((name, candle.get_close()))
+                }
+                AppEvent::Alert(AlertEvent::Quarantined(panicked)) => quarantined.push(panicked.context),
+                _ => {}
+            }
+        }
+
+        assert_eq!(
+            fired,
+            vec![("above_100".to_string(), 101.0), ("above_100".to_string(), 102.0)]
+        );
+        assert_eq!(quarantined, vec!["alert 'broken'".to_string()]);
+        // The pump survived the panic and applied every candle
+        let snapshot = app.snapshot("BTCUSDT").unwrap();
+        assert_eq!(snapshot.timeframes[&Timeframe::M1].history_len, 3);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_exchange_fails() {
+        let result = App::builder().exchange("nope").build().await;
+        assert!(result.is_err());
+    }
+}
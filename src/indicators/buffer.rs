@@ -0,0 +1,192 @@
+//! Column-oriented candle storage for indicators over long histories.
+//!
+//! A `&[Candle]` interleaves all six fields, so an indicator reading only closes
+//! pulls five unused values through the cache per candle. `CandleBuffer` keeps one
+//! `Vec` per field; `closes()` and friends hand the `*_f64` indicator variants a
+//! contiguous slice.
+
+use crate::indicators::candle::Candle;
+
+/// Candles stored as parallel columns. All columns always have the same length.
+#[derive(Debug, Clone, Default)]
+pub struct CandleBuffer {
+    timestamps: Vec<u64>,
+    opens: Vec<f64>,
+    highs: Vec<f64>,
+    lows: Vec<f64>,
+    closes: Vec<f64>,
+    volumes: Vec<f64>,
+}
+
+impl CandleBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Preallocates room for `capacity` candles in every column.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            timestamps: Vec::with_capacity(capacity),
+            opens: Vec::with_capacity(capacity),
+            highs: Vec::with_capacity(capacity),
+            lows: Vec::with_capacity(capacity),
+            closes: Vec::with_capacity(capacity),
+            volumes: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn from_candles(candles: &[Candle]) -> Self {
+        let mut buffer = Self::with_capacity(candles.len());
+        buffer.extend_from_slice(candles);
+        buffer
+    }
+
+    /// Appends a candle. No ordering checks: the buffer stores what it is given.
+    pub fn push(&mut self, candle: Candle) {
+        self.timestamps.push(candle.get_timestamp());
+        self.opens.push(candle.get_open());
+        self.highs.push(candle.get_high());
+        self.lows.push(candle.get_low());
+        self.closes.push(candle.get_close());
+        self.volumes.push(candle.get_volume());
+    }
+
+    pub fn extend_from_slice(&mut self, candles: &[Candle]) {
+        for candle in candles {
+            self.push(*candle);
+        }
+    }
+
+    /// The candle at `index`, reassembled from the columns.
+    pub fn get(&self, index: usize) -> Option<Candle> {
+        let timestamp = *self.timestamps.get(index)?;
+        Some(Candle::new(
+            timestamp,
+            self.opens[index],
+            self.highs[index],
+            self.lows[index],
+            self.closes[index],
+            self.volumes[index],
+        ))
+    }
+
+    pub fn last(&self) -> Option<Candle> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn to_candles(&self) -> Vec<Candle> {
+        (0..self.len()).filter_map(|index| self.get(index)).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.timestamps.clear();
+        self.opens.clear();
+        self.highs.clear();
+        self.lows.clear();
+        self.closes.clear();
+        self.volumes.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    pub fn timestamps(&self) -> &[u64] {
+        &self.timestamps
+    }
+
+    pub fn opens(&self) -> &[f64] {
+        &self.opens
+    }
+
+    pub fn highs(&self) -> &[f64] {
+        &self.highs
+    }
+
+    pub fn lows(&self) -> &[f64] {
+        &self.lows
+    }
+
+    pub fn closes(&self) -> &[f64] {
+        &self.closes
+    }
+
+    pub fn volumes(&self) -> &[f64] {
+        &self.volumes
+    }
+}
+
+impl From<&[Candle]> for CandleBuffer {
+    fn from(candles: &[Candle]) -> Self {
+        Self::from_candles(candles)
+    }
+}
+
+impl FromIterator<Candle> for CandleBuffer {
+    fn from_iter<I: IntoIterator<Item = Candle>>(iter: I) -> Self {
+        let mut buffer = Self::new();
+        for candle in iter {
+            buffer.push(candle);
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::momentum::{rsi_series, rsi_series_f64};
+    use crate::indicators::moving_averages::{ema_series, ema_series_f64, sma, sma_series, sma_series_f64};
+
+    fn candles() -> Vec<Candle> {
+        [100.0, 102.0, 101.0, 105.0, 103.0, 99.0, 104.0, 108.0]
+            .iter()
+            .enumerate()
+            .map(|(i, close)| Candle::new(i as u64 * 60_000, close - 0.5, close + 1.0, close - 1.0, *close, i as f64))
+            .collect()
+    }
+
+    #[test]
+    fn test_columns_round_trip() {
+        let candles = candles();
+        let buffer = CandleBuffer::from_candles(&candles);
+        assert_eq!(buffer.len(), 8);
+        assert_eq!(buffer.closes()[3], 105.0);
+        assert_eq!(buffer.highs()[3], 106.0);
+        assert_eq!(buffer.timestamps()[7], 7 * 60_000);
+        assert_eq!(buffer.volumes()[2], 2.0);
+
+        let back = buffer.to_candles();
+        assert_eq!(back.len(), candles.len());
+        assert_eq!(back[5].get_open(), candles[5].get_open());
+        assert_eq!(buffer.last().unwrap().get_close(), 108.0);
+        assert!(buffer.get(8).is_none());
+
+        let mut incremental: CandleBuffer = candles[..4].iter().copied().collect();
+        incremental.push(candles[4]);
+        assert_eq!(incremental.closes(), &buffer.closes()[..5]);
+        incremental.clear();
+        assert!(incremental.is_empty() && incremental.last().is_none());
+    }
+
+    #[test]
+    fn test_column_series_match_candle_series() {
+        let candles = candles();
+        let buffer = CandleBuffer::from(candles.as_slice());
+        // The running sum agrees with summing each window from scratch
+        let smas = sma_series_f64(buffer.closes(), 3);
+        assert_eq!(smas.len(), 6);
+        for (i, value) in smas.iter().enumerate() {
+            let window = sma(&candles[..i + 3], 3).unwrap();
+            assert!((value - window).abs() < 1e-9);
+        }
+        assert_eq!(smas, sma_series(&candles, 3));
+        assert_eq!(ema_series_f64(buffer.closes(), 3), ema_series(&candles, 3));
+        assert_eq!(rsi_series_f64(buffer.closes(), Some(4)), rsi_series(&candles, Some(4)));
+        assert!(sma_series_f64(&[], 3).is_empty() && ema_series_f64(&[1.0], 0).is_empty());
+    }
+}
@@ -1,10 +1,21 @@
 //! Candle (OHLCV) data structure with timestamp
 
+pub mod io;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "decimal")]
+use crate::decimal::{self, DecimalOhlcv};
+use crate::indicators::series::{ContinuityError, validate_continuity};
+use crate::indicators::timeframe::Timeframe;
+
 /// Represents a single candlestick with OHLCV data and timestamp.
 ///
 /// The timestamp is stored as Unix time in milliseconds, which is the format
 /// used by most cryptocurrency exchanges (Binance, Coinbase, etc.).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Candle {
     /// Unix timestamp in milliseconds (candle open time)
     timestamp: u64,
@@ -13,6 +24,10 @@ pub struct Candle {
     low: f64,
     close: f64,
     volume: f64,
+    /// Exact OHLCV as the exchange sent it, see `get_decimal_ohlcv`
+    #[cfg(feature = "decimal")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exact: Option<DecimalOhlcv>,
 }
 
 impl Candle {
@@ -39,14 +54,48 @@ impl Candle {
             low,
             close,
             volume,
+            #[cfg(feature = "decimal")]
+            exact: None,
         }
     }
 
+    /// Attaches the exact OHLCV the f64 fields were parsed from.
+    #[cfg(feature = "decimal")]
+    pub fn with_exact(mut self, exact: DecimalOhlcv) -> Self {
+        self.exact = Some(exact);
+        self
+    }
+
+    /// Exact OHLCV when the parser kept it, otherwise converted from the f64 fields.
+    #[cfg(feature = "decimal")]
+    pub fn get_decimal_ohlcv(&self) -> DecimalOhlcv {
+        self.exact.unwrap_or_else(|| DecimalOhlcv {
+            open: decimal::from_f64(self.open),
+            high: decimal::from_f64(self.high),
+            low: decimal::from_f64(self.low),
+            close: decimal::from_f64(self.close),
+            volume: decimal::from_f64(self.volume),
+        })
+    }
+
     /// Returns the candle's timestamp (Unix time in milliseconds).
     pub fn get_timestamp(&self) -> u64 {
         self.timestamp
     }
 
+    /// Open time as a UTC date-time.
+    #[cfg(feature = "chrono")]
+    pub fn open_datetime(&self) -> DateTime<Utc> {
+        datetime(self.timestamp)
+    }
+
+    /// Close time as a UTC date-time, open + duration - 1ms as exchanges report it
+    /// (see `Timeframe::close_time`).
+    #[cfg(feature = "chrono")]
+    pub fn close_datetime(&self, timeframe: Timeframe) -> DateTime<Utc> {
+        datetime(timeframe.close_time(self.timestamp))
+    }
+
     pub fn get_open(&self) -> f64 {
         self.open
     }
@@ -126,4 +175,97 @@ impl Candle {
             self.body_abs() / range
         }
     }
+
+    /// Combines consecutive candles into one: open of the first, close of the last,
+    /// highest high, lowest low, summed volume, earliest timestamp. None if empty.
+    /// Order isn't checked; see `merge_contiguous`.
+    pub fn merge(candles: &[Candle]) -> Option<Candle> {
+        let (first, last) = (candles.first()?, candles.last()?);
+        let high = candles.iter().map(Candle::get_high).fold(f64::NEG_INFINITY, f64::max);
+        let low = candles.iter().map(Candle::get_low).fold(f64::INFINITY, f64::min);
+        let volume = candles.iter().map(Candle::get_volume).sum();
+        let timestamp = candles.iter().map(Candle::get_timestamp).min().unwrap_or(first.timestamp);
+        Some(Candle::new(timestamp, first.open, high, low, last.close, volume))
+    }
+
+    /// `merge`, after checking that `candles` are in order with one per `timeframe`
+    /// interval and none missing (see `validate_continuity`).
+    pub fn merge_contiguous(candles: &[Candle], timeframe: Timeframe) -> Result<Option<Candle>, ContinuityError> {
+        validate_continuity(candles, timeframe)?;
+        Ok(Self::merge(candles))
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn datetime(timestamp_ms: u64) -> DateTime<Utc> {
+    i64::try_from(timestamp_ms).ok().and_then(DateTime::from_timestamp_millis).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// `[timestamp] O:100 H:110 L:90 C:105 V:1000`
+impl std::fmt::Display for Candle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] O:{} H:{} L:{} C:{} V:{}",
+            self.timestamp, self.open, self.high, self.low, self.close, self.volume
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    fn candle(timestamp: u64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle::new(timestamp, open, high, low, close, 2.0)
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_open_and_close_datetime() {
+        // The kline example in Binance's API docs: "t": 1672515780000, "T": 1672515839999
+        let kline = candle(1_672_515_780_000, 100.0, 101.0, 99.0, 100.0);
+        assert_eq!(kline.open_datetime().to_rfc3339(), "2022-12-31T19:43:00+00:00");
+        assert_eq!(kline.close_datetime(Timeframe::M1).timestamp_millis(), 1_672_515_839_999);
+        let hour = kline.close_datetime(Timeframe::H1) - kline.open_datetime();
+        assert_eq!(hour, Timeframe::H1.to_chrono_duration() - chrono::TimeDelta::milliseconds(1));
+    }
+
+    #[test]
+    fn test_merge() {
+        let candles = [
+            candle(0, 100.0, 105.0, 99.0, 104.0),
+            candle(MINUTE, 104.0, 110.0, 103.0, 108.0),
+            candle(2 * MINUTE, 108.0, 109.0, 95.0, 96.0),
+        ];
+        let merged = Candle::merge(&candles).unwrap();
+        assert_eq!(merged.get_timestamp(), 0);
+        assert_eq!((merged.get_open(), merged.get_high(), merged.get_low(), merged.get_close()), (100.0, 110.0, 95.0, 96.0));
+        assert_eq!(merged.get_volume(), 6.0);
+        assert!(Candle::merge(&[]).is_none());
+
+        // Order isn't checked: open and close follow the slice, the timestamp is the earliest
+        let reversed = [candles[2], candles[1], candles[0]];
+        let merged = Candle::merge(&reversed).unwrap();
+        assert_eq!((merged.get_timestamp(), merged.get_open(), merged.get_close()), (0, 108.0, 104.0));
+    }
+
+    #[test]
+    fn test_merge_contiguous() {
+        let candles = [candle(0, 1.0, 2.0, 0.5, 1.5), candle(MINUTE, 1.5, 2.0, 1.0, 1.2), candle(3 * MINUTE, 1.2, 1.3, 1.0, 1.1)];
+        assert!(Candle::merge_contiguous(&candles[..2], Timeframe::M1).unwrap().is_some());
+        assert_eq!(
+            Candle::merge_contiguous(&candles, Timeframe::M1).unwrap_err(),
+            ContinuityError::Gap { expected: 2 * MINUTE, found: 3 * MINUTE }
+        );
+        assert_eq!(
+            Candle::merge_contiguous(&[candles[1], candles[0]], Timeframe::M1).unwrap_err(),
+            ContinuityError::Unordered { index: 1, timestamp: 0 }
+        );
+        // Two minutes apart is within one 5m interval
+        assert!(Candle::merge_contiguous(&candles[1..], Timeframe::M5).is_ok());
+        assert_eq!(Candle::merge_contiguous(&[], Timeframe::M1).unwrap().map(|c| c.get_close()), None);
+    }
 }
@@ -0,0 +1,351 @@
+//! Candles from and to CSV files, e.g. historical OHLCV downloads.
+//!
+//! `CsvSchema` describes the file: which column holds what, whether there is a
+//! header row, and how timestamps are written. `CsvSchema::binance()` reads the
+//! Binance public data dumps (data.binance.vision klines). Rows that don't make a
+//! valid candle are reported with their line number instead of being loaded.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::indicators::candle::Candle;
+use crate::market::timestamp::{TimestampUnit, days_from_civil, normalize_to_ms};
+
+/// What a CSV column holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    Timestamp,
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    /// Present in the file, not used
+    Skip,
+}
+
+/// Layout of a candle CSV file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvSchema {
+    columns: Vec<CsvColumn>,
+    has_header: bool,
+    timestamp_unit: TimestampUnit,
+    date_format: Option<String>,
+}
+
+impl Default for CsvSchema {
+    /// `timestamp,open,high,low,close,volume` with a header row and ms timestamps,
+    /// the format `write_candles_csv` produces.
+    fn default() -> Self {
+        use CsvColumn::*;
+        Self {
+            columns: vec![Timestamp, Open, High, Low, Close, Volume],
+            has_header: true,
+            timestamp_unit: TimestampUnit::Millis,
+            date_format: None,
+        }
+    }
+}
+
+impl CsvSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binance public kline dumps: open_time, open, high, low, close, volume,
+    /// close_time, quote volume, trades, taker buy base, taker buy quote, ignore;
+    /// no header, ms timestamps. Spot files from 2025 on are in microseconds: add
+    /// `.with_timestamp_unit(TimestampUnit::Micros)`.
+    pub fn binance() -> Self {
+        use CsvColumn::*;
+        Self {
+            columns: vec![Timestamp, Open, High, Low, Close, Volume, Skip, Skip, Skip, Skip, Skip, Skip],
+            has_header: false,
+            ..Self::default()
+        }
+    }
+
+    /// Column order. Timestamp, open, high, low and close are required; without a
+    /// volume column the volume is 0. Columns past the listed ones are ignored.
+    pub fn with_columns(mut self, columns: Vec<CsvColumn>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Whether the first line is a header to skip (default: true).
+    pub fn with_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Unit of integer timestamps (default: ms).
+    pub fn with_timestamp_unit(mut self, unit: TimestampUnit) -> Self {
+        self.timestamp_unit = unit;
+        self
+    }
+
+    /// Timestamps written as UTC dates instead of integers, e.g. "%Y-%m-%d %H:%M:%S".
+    /// Understands %Y, %m, %d, %H, %M, %S and %f (fractional seconds); any other
+    /// character must match literally.
+    pub fn with_date_format(mut self, format: impl Into<String>) -> Self {
+        self.date_format = Some(format.into());
+        self
+    }
+}
+
+/// Reading or writing a candle CSV failed.
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// `line` (1-based, counting the header) isn't a valid candle.
+    InvalidRow { line: usize, reason: String },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(e) => write!(f, "candle CSV I/O error: {}", e),
+            CsvError::InvalidRow { line, reason } => write!(f, "candle CSV line {}: {}", line, reason),
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io(e) => Some(e),
+            CsvError::InvalidRow { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(e: io::Error) -> Self {
+        CsvError::Io(e)
+    }
+}
+
+/// Loads every candle in the file at `path`. Blank lines are skipped.
+pub fn read_candles_csv(path: impl AsRef<Path>, schema: &CsvSchema) -> Result<Vec<Candle>, CsvError> {
+    read_candles(BufReader::new(File::open(path)?), schema)
+}
+
+/// `read_candles_csv` from any reader.
+pub fn read_candles(reader: impl BufRead, schema: &CsvSchema) -> Result<Vec<Candle>, CsvError> {
+    let mut candles = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if (index == 0 && schema.has_header) || line.trim().is_empty() {
+            continue;
+        }
+        let candle = parse_row(&line, schema).map_err(|reason| CsvError::InvalidRow { line: index + 1, reason })?;
+        candles.push(candle);
+    }
+    Ok(candles)
+}
+
+/// Writes `candles` with a `timestamp,open,high,low,close,volume` header and ms
+/// timestamps, readable back with `CsvSchema::default()`.
+pub fn write_candles_csv(path: impl AsRef<Path>, candles: &[Candle]) -> Result<(), CsvError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_candles(&mut writer, candles)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `write_candles_csv` to any writer.
+pub fn write_candles(mut writer: impl Write, candles: &[Candle]) -> Result<(), CsvError> {
+    writeln!(writer, "timestamp,open,high,low,close,volume")?;
+    for c in candles {
+        // f64 Display is the shortest string that parses back to the same value
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            c.get_timestamp(),
+            c.get_open(),
+            c.get_high(),
+            c.get_low(),
+            c.get_close(),
+            c.get_volume()
+        )?;
+    }
+    Ok(())
+}
+
+fn parse_row(line: &str, schema: &CsvSchema) -> Result<Candle, String> {
+    let fields: Vec<&str> = line.trim_end_matches('\r').split(',').map(str::trim).collect();
+    if fields.len() < schema.columns.len() {
+        return Err(format!("expected {} columns, found {}", schema.columns.len(), fields.len()));
+    }
+
+    let (mut timestamp, mut open, mut high, mut low, mut close, mut volume) = (None, None, None, None, None, 0.0);
+    for (column, field) in schema.columns.iter().zip(&fields) {
+        let number = || field.parse::<f64>().map_err(|_| format!("{:?} is not a number ({:?})", field, column));
+        match column {
+            CsvColumn::Timestamp => timestamp = Some(parse_time(field, schema)?),
+            CsvColumn::Open => open = Some(number()?),
+            CsvColumn::High => high = Some(number()?),
+            CsvColumn::Low => low = Some(number()?),
+            CsvColumn::Close => close = Some(number()?),
+            CsvColumn::Volume => volume = number()?,
+            CsvColumn::Skip => {}
+        }
+    }
+    let missing = |name: &str| format!("the schema has no {} column", name);
+    let timestamp = timestamp.ok_or_else(|| missing("timestamp"))?;
+    let (open, high, low, close) = (
+        open.ok_or_else(|| missing("open"))?,
+        high.ok_or_else(|| missing("high"))?,
+        low.ok_or_else(|| missing("low"))?,
+        close.ok_or_else(|| missing("close"))?,
+    );
+
+    if high < low {
+        return Err(format!("high {} is below low {}", high, low));
+    }
+    for (name, value) in [("open", open), ("close", close)] {
+        if !(low..=high).contains(&value) {
+            return Err(format!("{} {} is outside [low {}, high {}]", name, value, low, high));
+        }
+    }
+    Ok(Candle::new(timestamp, open, high, low, close, volume))
+}
+
+/// A timestamp field to ms, as an integer in the schema's unit or a date.
+fn parse_time(field: &str, schema: &CsvSchema) -> Result<u64, String> {
+    match &schema.date_format {
+        Some(format) => {
+            parse_date(field, format).ok_or_else(|| format!("{:?} doesn't match date format {:?}", field, format))
+        }
+        None => field
+            .parse::<u64>()
+            .map(|value| normalize_to_ms(value, schema.timestamp_unit))
+            .map_err(|_| format!("{:?} is not an integer timestamp", field)),
+    }
+}
+
+/// A UTC date in `format` (see `CsvSchema::with_date_format`) to ms.
+fn parse_date(value: &str, format: &str) -> Option<u64> {
+    let (mut year, mut month, mut day, mut hour, mut minute, mut second, mut millis) = (1970, 1, 1, 0, 0, 0, 0);
+    let mut rest = value;
+    let mut spec = format.chars();
+    while let Some(c) = spec.next() {
+        if c != '%' {
+            rest = rest.strip_prefix(c)?;
+            continue;
+        }
+        let directive = spec.next()?;
+        let width = match directive {
+            'Y' => 4,
+            'f' => rest.bytes().take_while(u8::is_ascii_digit).count(),
+            _ => 2,
+        };
+        if width == 0 || rest.len() < width || !rest.as_bytes()[..width].iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let (digits, tail) = rest.split_at(width);
+        rest = tail;
+        let number: i64 = digits.parse().ok()?;
+        match directive {
+            'Y' => year = number,
+            'm' => month = number,
+            'd' => day = number,
+            'H' => hour = number,
+            'M' => minute = number,
+            'S' => second = number,
+            'f' => millis = format!("{:0<3}", &digits[..digits.len().min(3)]).parse().ok()?,
+            _ => return None,
+        }
+    }
+    if !rest.is_empty() || !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let days = days_from_civil(year, month as u32, day as u32);
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    u64::try_from(secs).ok().map(|secs| secs * 1_000 + millis as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(csv: &str, schema: &CsvSchema) -> Result<Vec<Candle>, CsvError> {
+        read_candles(csv.as_bytes(), schema)
+    }
+
+    fn ohlcv(candle: &Candle) -> (u64, f64, f64, f64, f64, f64) {
+        let c = candle;
+        (c.get_timestamp(), c.get_open(), c.get_high(), c.get_low(), c.get_close(), c.get_volume())
+    }
+
+    #[test]
+    fn test_round_trip_through_a_file() {
+        let candles = vec![
+            Candle::new(1_700_000_000_000, 0.1, 0.3, 0.1, 0.2, 12.345_678_9),
+            Candle::new(1_700_000_060_000, 37_123.45, 37_200.0, 37_000.01, 37_111.11, 0.0),
+        ];
+        let path = std::env::temp_dir().join(format!("cct_candles_{}.csv", std::process::id()));
+        write_candles_csv(&path, &candles).unwrap();
+        let read_back = read_candles_csv(&path, &CsvSchema::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let expected: Vec<_> = candles.iter().map(ohlcv).collect();
+        assert_eq!(read_back.iter().map(ohlcv).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_binance_public_data_dump() {
+        // Two rows in the layout of a BTCUSDT-1m-YYYY-MM-DD.csv dump
+        let csv = "\
+1717200000000,67472.41000000,67489.98000000,67472.40000000,67489.98000000,10.12328000,1717200059999,683036.77862190,612,6.23584000,420728.03770160,0
+1717200060000,67489.98000000,67505.17000000,67475.75000000,67478.01000000,19.45713000,1717200119999,1312839.00327200,895,10.03211000,677052.09812530,0
+";
+        let candles = read(csv, &CsvSchema::binance()).unwrap();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(ohlcv(&candles[0]), (1_717_200_000_000, 67472.41, 67489.98, 67472.4, 67489.98, 10.12328));
+
+        let micros = csv.replace("1717200000000,", "1717200000000000,").replace("1717200060000,", "1717200060000000,");
+        let schema = CsvSchema::binance().with_timestamp_unit(TimestampUnit::Micros);
+        assert_eq!(read(&micros, &schema).unwrap()[1].get_timestamp(), 1_717_200_060_000);
+    }
+
+    #[test]
+    fn test_custom_columns_and_date_format() {
+        use CsvColumn::*;
+        let csv = "date,close,high,low,open,trades\n2024-06-01 00:00:03,101,102,99,100,7\n\n2024-06-01 00:01:00,100,101,99,101,3\n";
+        let schema = CsvSchema::new()
+            .with_columns(vec![Timestamp, Close, High, Low, Open, Skip])
+            .with_date_format("%Y-%m-%d %H:%M:%S");
+        let candles = read(csv, &schema).unwrap();
+        assert_eq!(ohlcv(&candles[0]), (1_717_200_003_000, 100.0, 102.0, 99.0, 101.0, 0.0));
+        assert_eq!(candles[1].get_timestamp(), 1_717_200_060_000);
+
+        assert_eq!(parse_date("2024-06-01T00:00:03.5", "%Y-%m-%dT%H:%M:%S.%f"), Some(1_717_200_003_500));
+        assert_eq!(parse_date("2024-06-01", "%Y-%m-%d %H"), None);
+        assert_eq!(parse_date("2024-13-01", "%Y-%m-%d"), None);
+
+        let seconds = CsvSchema::new().with_header(false).with_timestamp_unit(TimestampUnit::Seconds);
+        assert_eq!(read("1717200000,1,1,1,1,1", &seconds).unwrap()[0].get_timestamp(), 1_717_200_000_000);
+    }
+
+    #[test]
+    fn test_invalid_rows_report_the_line() {
+        let bad_close = "timestamp,open,high,low,close,volume\n0,1,2,0.5,1.5,1\n60000,100,110,90,111,1\n";
+        match read(bad_close, &CsvSchema::default()) {
+            Err(CsvError::InvalidRow { line, reason }) => {
+                assert_eq!(line, 3);
+                assert_eq!(reason, "close 111 is outside [low 90, high 110]");
+            }
+            other => panic!("expected an invalid row, got {:?}", other),
+        }
+
+        let inverted = read("timestamp,open,high,low,close,volume\n0,1,1,2,1,1\n", &CsvSchema::default());
+        assert_eq!(inverted.unwrap_err().to_string(), "candle CSV line 2: high 1 is below low 2");
+        assert!(read("ts\n0,1,2\n", &CsvSchema::default()).is_err());
+        assert!(read("ts\nabc,1,2,0,1,1\n", &CsvSchema::default()).is_err());
+        assert!(matches!(read_candles_csv("/nonexistent/candles.csv", &CsvSchema::default()), Err(CsvError::Io(_))));
+    }
+}
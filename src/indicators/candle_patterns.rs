@@ -11,6 +11,7 @@
 //! - `is_bullish()`, `is_bearish()` - candle direction
 
 use crate::indicators::candle::Candle;
+use crate::indicators::series::CandleSeries;
 use crate::indicators::timeframe::Timeframe;
 
 /// A collection of candles with associated timeframe for pattern detection.
@@ -25,6 +26,11 @@ impl CandlePatterns {
         Self { candles, timeframe }
     }
 
+    /// The candles currently in `series`, oldest first.
+    pub fn from_series(series: &CandleSeries, timeframe: Timeframe) -> Self {
+        Self::new(series.to_vec(), timeframe)
+    }
+
     pub fn get_candles(&self) -> &[Candle] {
         &self.candles
     }
@@ -262,6 +268,18 @@ mod tests {
         Candle::new(0, open, high, low, close, 1000.0)
     }
 
+    #[test]
+    fn test_from_series() {
+        let mut series = CandleSeries::new(2);
+        for (i, close) in [100.5, 108.0, 100.2].into_iter().enumerate() {
+            series.push(Candle::new(i as u64, 100.0, 110.0, 95.0, close, 1000.0));
+        }
+        let patterns = CandlePatterns::from_series(&series, Timeframe::H1);
+        assert_eq!(patterns.len(), 2);
+        assert!(!patterns.is_doji(0));
+        assert!(patterns.is_doji(1));
+    }
+
     #[test]
     fn test_is_doji() {
         // Doji: open and close are very close, but has range
@@ -0,0 +1,170 @@
+//! `SeriesWithIndicators`: a `CandleSeries` fed from live events, with a set of
+//! indicator values that are only recomputed after a candle closes.
+//!
+//! A candle stream updates the open candle several times a second, but indicators
+//! on closed candles only change once per interval. Recomputing `rsi_series` on
+//! every tick redoes the same work hundreds of times per candle.
+
+use std::fmt;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::momentum::rsi;
+use crate::indicators::moving_averages::{ema, sma};
+use crate::indicators::series::{AppliedUpdate, CandleSeries};
+use crate::indicators::volatility::atr;
+use crate::market::market_data::MarketData;
+
+/// An indicator kept by `SeriesWithIndicators` (and by the `facade`), with its period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Indicator {
+    Sma(usize),
+    Ema(usize),
+    Rsi(usize),
+    Atr(usize),
+}
+
+impl Indicator {
+    /// Latest value over `candles`, None during warm-up.
+    pub fn compute(self, candles: &[Candle]) -> Option<f64> {
+        match self {
+            Indicator::Sma(period) => sma(candles, period),
+            Indicator::Ema(period) => ema(candles, period),
+            Indicator::Rsi(period) => rsi(candles, Some(period)),
+            Indicator::Atr(period) => atr(candles, Some(period)),
+        }
+    }
+
+    pub fn period(self) -> usize {
+        match self {
+            Indicator::Sma(period) | Indicator::Ema(period) | Indicator::Rsi(period) | Indicator::Atr(period) => period,
+        }
+    }
+}
+
+impl fmt::Display for Indicator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Indicator::Sma(period) => write!(f, "sma_{}", period),
+            Indicator::Ema(period) => write!(f, "ema_{}", period),
+            Indicator::Rsi(period) => write!(f, "rsi_{}", period),
+            Indicator::Atr(period) => write!(f, "atr_{}", period),
+        }
+    }
+}
+
+/// A candle series plus the latest value of each configured indicator, computed
+/// over the closed candles only.
+///
+/// Values are recomputed lazily: a close marks them stale and the next
+/// `latest_values` call brings them up to date, so a replay that closes many
+/// candles between reads pays for one computation.
+#[derive(Debug, Clone)]
+pub struct SeriesWithIndicators {
+    series: CandleSeries,
+    values: Vec<(Indicator, Option<f64>)>,
+    stale: bool,
+}
+
+impl SeriesWithIndicators {
+    /// The series should hold enough candles for the longest warm-up (and for EMA
+    /// to settle, a few times its period).
+    pub fn new(series: CandleSeries, indicators: &[Indicator]) -> Self {
+        let values = indicators.iter().map(|indicator| (*indicator, None)).collect();
+        Self { series, values, stale: true }
+    }
+
+    /// `CandleSeries::apply`; a close marks the indicator values stale.
+    pub fn apply(&mut self, market_data: &MarketData) -> AppliedUpdate {
+        let applied = self.series.apply(market_data);
+        if applied.closed() {
+            self.stale = true;
+        }
+        applied
+    }
+
+    /// Pushes a closed candle directly (e.g. history loaded at startup).
+    pub fn push(&mut self, candle: Candle) -> bool {
+        let accepted = self.series.push(candle);
+        self.stale |= accepted;
+        accepted
+    }
+
+    /// Each indicator with its value as of the last closed candle, in the order
+    /// they were configured. Recomputes them if a candle closed since the last call.
+    pub fn latest_values(&mut self) -> &[(Indicator, Option<f64>)] {
+        if self.stale {
+            let candles = closed_candles(&self.series);
+            for (indicator, value) in &mut self.values {
+                *value = indicator.compute(candles);
+            }
+            self.stale = false;
+        }
+        &self.values
+    }
+
+    /// The values as of the last `latest_values` call, without recomputing; for
+    /// readers that only hold a shared reference and refresh after every close.
+    pub fn cached_values(&self) -> &[(Indicator, Option<f64>)] {
+        &self.values
+    }
+
+    /// Latest value of one configured indicator. None if it isn't configured or is
+    /// still warming up.
+    pub fn value(&mut self, indicator: Indicator) -> Option<f64> {
+        self.latest_values().iter().find(|(configured, _)| *configured == indicator).and_then(|(_, value)| *value)
+    }
+
+    pub fn series(&self) -> &CandleSeries {
+        &self.series
+    }
+
+    /// The held candles without the in-progress one: what the indicators are computed over.
+    pub fn closed_candles(&self) -> &[Candle] {
+        closed_candles(&self.series)
+    }
+}
+
+fn closed_candles(series: &CandleSeries) -> &[Candle] {
+    let candles = series.as_slice();
+    if series.is_last_closed() { candles } else { &candles[..candles.len().saturating_sub(1)] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::timeframe::Timeframe;
+
+    const MINUTE: u64 = 60_000;
+
+    fn event(symbol: &str, minute: u64, close: f64, is_closed: bool) -> MarketData {
+        let candle = Candle::new(minute * MINUTE, close, close + 1.0, close - 1.0, close, 1.0);
+        MarketData::candle(symbol, Timeframe::M1, candle, is_closed)
+    }
+
+    #[test]
+    fn test_indicators_recomputed_on_close_only() {
+        let mut live = SeriesWithIndicators::new(
+            CandleSeries::new(50),
+            &[Indicator::Sma(3), Indicator::Ema(3), Indicator::Rsi(14)],
+        );
+        for minute in 0..3 {
+            live.apply(&event("BTCUSDT", minute, 100.0 + minute as f64, true));
+        }
+        assert_eq!(live.value(Indicator::Sma(3)), Some(101.0));
+        // RSI 14 is still warming up, and ATR isn't configured
+        assert_eq!(live.value(Indicator::Rsi(14)), None);
+        assert_eq!(live.value(Indicator::Atr(14)), None);
+
+        // Ticks of the open candle leave the values on the closed candles
+        for close in [150.0, 90.0, 120.0] {
+            assert_eq!(live.apply(&event("BTCUSDT", 3, close, false)), AppliedUpdate::Updated);
+            assert_eq!(live.value(Indicator::Sma(3)), Some(101.0));
+        }
+        live.apply(&event("BTCUSDT", 3, 106.0, true));
+        // 101, 102 and 106
+        let values = live.latest_values();
+        assert_eq!(values[0], (Indicator::Sma(3), Some(103.0)));
+        assert_eq!(values[1].0.to_string(), "ema_3");
+        assert_eq!(live.series().len(), 4);
+    }
+}
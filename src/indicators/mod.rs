@@ -1,8 +1,19 @@
 //! Technical indicators for market analysis
 
+pub mod buffer;
 pub mod candle;
 pub mod candle_patterns;
+pub mod live;
 pub mod momentum;
 pub mod moving_averages;
+pub mod price_source;
+pub mod renko;
+pub mod rolling;
+pub mod series;
+pub mod signals;
+pub mod swings;
 pub mod timeframe;
+pub mod trend;
 pub mod volatility;
+
+pub use price_source::PriceSource;
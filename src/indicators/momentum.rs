@@ -1,8 +1,28 @@
-//! Momentum indicators: Relative Strength Index (RSI)
+//! Momentum indicators: Relative Strength Index (RSI), MACD, the Stochastic Oscillator,
+//! Rate of Change (ROC), Momentum, the Commodity Channel Index (CCI) and the Money
+//! Flow Index (MFI)
+
+use serde::{Deserialize, Serialize};
 
 use crate::indicators::candle::Candle;
+use crate::indicators::moving_averages::{CrossDirection, Crossover, crossovers, ema_series_f64, rma_series_values};
+use crate::indicators::price_source::PriceSource;
+use crate::indicators::rolling::{highest_high, lowest_low, rolling_mean, rolling_mean_deviation, rolling_sum};
+use crate::indicators::series::TimeSeries;
 
 const DEFAULT_RSI_PERIOD: usize = 14;
+const DEFAULT_MACD_FAST: usize = 12;
+const DEFAULT_MACD_SLOW: usize = 26;
+const DEFAULT_MACD_SIGNAL: usize = 9;
+const DEFAULT_STOCH_K: usize = 14;
+const DEFAULT_STOCH_D: usize = 3;
+const DEFAULT_STOCH_SMOOTH: usize = 3;
+const DEFAULT_ROC_PERIOD: usize = 10;
+const DEFAULT_MOMENTUM_PERIOD: usize = 12;
+const DEFAULT_CCI_PERIOD: usize = 20;
+// Lambert's scaling constant, chosen so most CCI values land within ±100
+const CCI_CONSTANT: f64 = 0.015;
+const DEFAULT_MFI_PERIOD: usize = 14;
 
 /// Calculates the Relative Strength Index (RSI) over a slice of candles.
 ///
@@ -19,7 +39,12 @@ const DEFAULT_RSI_PERIOD: usize = 14;
 /// Pass `None` to use the default period of 14, or `Some(n)` for a custom period.
 /// Returns `None` if there are not enough candles (need at least period + 1 candles).
 pub fn rsi(candles: &[Candle], period: Option<usize>) -> Option<f64> {
-    rsi_series(candles, period).last().copied()
+    rsi_with_source(candles, period, PriceSource::Close)
+}
+
+/// `rsi` of another price than the close.
+pub fn rsi_with_source(candles: &[Candle], period: Option<usize>, source: PriceSource) -> Option<f64> {
+    rsi_series_with_source(candles, period, source).last().copied()
 }
 
 /// Calculates the RSI series for all calculable points.
@@ -28,57 +53,385 @@ pub fn rsi(candles: &[Candle], period: Option<usize>) -> Option<f64> {
 /// where we have enough data (period + 1 candles).
 /// Returns an empty vector if there are not enough candles.
 pub fn rsi_series(candles: &[Candle], period: Option<usize>) -> Vec<f64> {
+    rsi_series_with_source(candles, period, PriceSource::Close)
+}
+
+/// `rsi_series` of another price than the close.
+pub fn rsi_series_with_source(candles: &[Candle], period: Option<usize>, source: PriceSource) -> Vec<f64> {
+    rsi_series_f64(&source.extract(candles), period)
+}
+
+/// `rsi_series` over a slice of prices (e.g. `CandleBuffer::closes()`).
+pub fn rsi_series_f64(values: &[f64], period: Option<usize>) -> Vec<f64> {
     let period = period.unwrap_or(DEFAULT_RSI_PERIOD);
 
-    if period == 0 || candles.len() < period + 1 {
+    if period == 0 || values.len() < period + 1 {
         return Vec::new();
     }
 
-    let changes = price_changes(candles);
+    let changes = price_changes(values);
     let (gains, losses) = gains_and_losses(&changes);
 
-    let mut rsi_values = Vec::with_capacity(changes.len() - period + 1);
-
-    // Calculate initial averages using simple average
-    let mut avg_gain: f64 = gains[..period].iter().sum::<f64>() / period as f64;
-    let mut avg_loss: f64 = losses[..period].iter().sum::<f64>() / period as f64;
-
-    // First RSI value
-    let first_rsi = if avg_loss == 0.0 {
-        100.0
-    } else {
-        let rs = avg_gain / avg_loss;
-        100.0 - (100.0 / (1.0 + rs))
-    };
-    rsi_values.push(first_rsi);
-
-    // Calculate subsequent RSI values using smoothed averages (Wilder's smoothing)
-    for i in period..changes.len() {
-        avg_gain = (avg_gain * (period - 1) as f64 + gains[i]) / period as f64;
-        avg_loss = (avg_loss * (period - 1) as f64 + losses[i]) / period as f64;
-
-        let rsi_val = if avg_loss == 0.0 {
-            100.0
-        } else {
-            let rs = avg_gain / avg_loss;
-            100.0 - (100.0 / (1.0 + rs))
-        };
-        rsi_values.push(rsi_val);
+    // Wilder's smoothing of both, seeded with the simple average of the first `period`
+    let avg_gains = rma_series_values(&gains, period);
+    let avg_losses = rma_series_values(&losses, period);
+
+    avg_gains
+        .iter()
+        .zip(&avg_losses)
+        .map(|(&avg_gain, &avg_loss)| {
+            if avg_loss == 0.0 {
+                100.0
+            } else {
+                let rs = avg_gain / avg_loss;
+                100.0 - (100.0 / (1.0 + rs))
+            }
+        })
+        .collect()
+}
+
+/// One MACD reading.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Macd {
+    /// Fast EMA minus slow EMA
+    pub macd: f64,
+    /// EMA of the MACD line
+    pub signal: f64,
+    /// `macd - signal`
+    pub histogram: f64,
+}
+
+/// MACD lines over time, index-aligned (entry `i` of each belongs to the same candle)
+/// and oldest first; the last entry is the latest candle.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MacdSeries {
+    pub macd: Vec<f64>,
+    pub signal: Vec<f64>,
+    pub histogram: Vec<f64>,
+}
+
+impl MacdSeries {
+    pub fn len(&self) -> usize {
+        self.macd.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.macd.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Macd> {
+        Some(Macd {
+            macd: *self.macd.get(index)?,
+            signal: *self.signal.get(index)?,
+            histogram: *self.histogram.get(index)?,
+        })
     }
 
-    rsi_values
+    pub fn last(&self) -> Option<Macd> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    /// Every cross of the MACD line over its signal line, oldest first (see
+    /// `moving_averages::crossovers`). `candles` are the ones the series was computed
+    /// from; they supply the timestamps.
+    pub fn signal_crosses(&self, candles: &[Candle]) -> Vec<Crossover> {
+        let macd = TimeSeries::from_indicator(candles, &self.macd);
+        let signal = TimeSeries::from_indicator(candles, &self.signal);
+        crossovers(&macd, &signal)
+    }
+
+    /// Whether the MACD line crossed the signal line on the latest candle, and which way.
+    pub fn crossed_signal(&self, candles: &[Candle]) -> Option<CrossDirection> {
+        let last = self.signal_crosses(candles).pop()?;
+        (Some(last.timestamp) == candles.last().map(Candle::get_timestamp)).then_some(last.direction)
+    }
 }
 
-/// Calculates price changes between consecutive candles.
+/// Calculates MACD (Moving Average Convergence Divergence) over a slice of candles.
+///
+/// MACD = EMA(fast) - EMA(slow)
+/// Signal = EMA(MACD, signal)
+/// Histogram = MACD - Signal
 ///
-/// Returns a vector of changes where each value is: current_close - previous_close
-fn price_changes(candles: &[Candle]) -> Vec<f64> {
-    candles
+/// Pass `None` for the defaults of 12, 26 and 9.
+/// Returns `None` if there are not enough candles (need `slow + signal - 1`).
+pub fn macd(candles: &[Candle], fast: Option<usize>, slow: Option<usize>, signal: Option<usize>) -> Option<Macd> {
+    macd_series(candles, fast, slow, signal).last()
+}
+
+/// Calculates the MACD series for all calculable points.
+///
+/// The MACD line starts once the slow EMA has `slow` candles, and the signal line
+/// `signal - 1` values after that, so the series has length
+/// `candles.len() - slow - signal + 2`. Empty if there are not enough candles, a
+/// period is 0, or `fast` is not shorter than `slow`.
+pub fn macd_series(
+    candles: &[Candle],
+    fast: Option<usize>,
+    slow: Option<usize>,
+    signal: Option<usize>,
+) -> MacdSeries {
+    let fast = fast.unwrap_or(DEFAULT_MACD_FAST);
+    let slow = slow.unwrap_or(DEFAULT_MACD_SLOW);
+    let signal = signal.unwrap_or(DEFAULT_MACD_SIGNAL);
+    if fast == 0 || signal == 0 || fast >= slow || candles.len() + 2 < slow + signal + 1 {
+        return MacdSeries::default();
+    }
+
+    let closes: Vec<f64> = candles.iter().map(Candle::get_close).collect();
+    let fast_ema = ema_series_f64(&closes, fast);
+    let slow_ema = ema_series_f64(&closes, slow);
+    // The fast EMA starts `slow - fast` candles earlier; line both up on the last candle
+    let macd_line: Vec<f64> = fast_ema[slow - fast..].iter().zip(&slow_ema).map(|(fast, slow)| fast - slow).collect();
+    let signal_line = ema_series_f64(&macd_line, signal);
+
+    let macd = macd_line[signal - 1..].to_vec();
+    let histogram = macd.iter().zip(&signal_line).map(|(macd, signal)| macd - signal).collect();
+    MacdSeries { macd, signal: signal_line, histogram }
+}
+
+/// One Stochastic Oscillator reading, both lines between 0 and 100.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stochastic {
+    /// %K: where the close sits in the window's high-low range (smoothed)
+    pub k: f64,
+    /// %D: SMA of %K
+    pub d: f64,
+}
+
+/// %K and %D over time, index-aligned and oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StochasticSeries {
+    pub k: Vec<f64>,
+    pub d: Vec<f64>,
+}
+
+impl StochasticSeries {
+    pub fn len(&self) -> usize {
+        self.d.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.d.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Stochastic> {
+        Some(Stochastic { k: *self.k.get(index)?, d: *self.d.get(index)? })
+    }
+
+    pub fn last(&self) -> Option<Stochastic> {
+        self.get(self.len().checked_sub(1)?)
+    }
+}
+
+/// Calculates the Stochastic Oscillator over a slice of candles.
+///
+/// Raw %K = 100 * (close - lowest low) / (highest high - lowest low) over `k_period`
+/// %K = SMA(raw %K, smooth_k)
+/// %D = SMA(%K, d_period)
+///
+/// A window where the highest high equals the lowest low has no range to place the
+/// close in; its raw %K is 50.
+///
+/// Common interpretation:
+/// - above 80: Overbought
+/// - below 20: Oversold
+///
+/// Pass `None` for the defaults of 14, 3 and 3 (the "slow" stochastic). A `smooth_k`
+/// of 1 gives the unsmoothed "fast" stochastic.
+/// Returns `None` if there are not enough candles (need `k_period + smooth_k + d_period - 2`).
+pub fn stochastic(
+    candles: &[Candle],
+    k_period: Option<usize>,
+    d_period: Option<usize>,
+    smooth_k: Option<usize>,
+) -> Option<Stochastic> {
+    stochastic_series(candles, k_period, d_period, smooth_k).last()
+}
+
+/// Calculates the Stochastic series for all calculable points.
+///
+/// Has length `candles.len() - k_period - smooth_k - d_period + 3`; %K is trimmed to
+/// start where %D does. Empty if there are not enough candles or a period is 0.
+pub fn stochastic_series(
+    candles: &[Candle],
+    k_period: Option<usize>,
+    d_period: Option<usize>,
+    smooth_k: Option<usize>,
+) -> StochasticSeries {
+    let k_period = k_period.unwrap_or(DEFAULT_STOCH_K);
+    let d_period = d_period.unwrap_or(DEFAULT_STOCH_D);
+    let smooth_k = smooth_k.unwrap_or(DEFAULT_STOCH_SMOOTH);
+    if k_period == 0 || d_period == 0 || smooth_k == 0 || candles.len() + 3 < k_period + smooth_k + d_period + 1 {
+        return StochasticSeries::default();
+    }
+
+    let highs = highest_high(candles, k_period);
+    let lows = lowest_low(candles, k_period);
+    let raw_k: Vec<f64> = candles[k_period - 1..]
+        .iter()
+        .zip(highs.iter().zip(&lows))
+        .map(|(candle, (high, low))| {
+            let range = high - low;
+            if range == 0.0 { 50.0 } else { 100.0 * (candle.get_close() - low) / range }
+        })
+        .collect();
+
+    let k = rolling_mean(&raw_k, smooth_k);
+    let d = rolling_mean(&k, d_period);
+    StochasticSeries { k: k[d_period - 1..].to_vec(), d }
+}
+
+/// Calculates the Rate of Change (ROC): the percent change of the close over `period` candles.
+///
+/// ROC = (close - close `period` candles ago) / close `period` candles ago * 100
+///
+/// A historical close of 0 gives 0 rather than infinity.
+/// Pass `None` to use the default period of 10.
+/// Returns `None` if there are not enough candles (need at least period + 1 candles).
+pub fn roc(candles: &[Candle], period: Option<usize>) -> Option<f64> {
+    roc_series(candles, period).last().copied()
+}
+
+/// Calculates the ROC series for all calculable points.
+///
+/// Entry `i` compares `candles[i + period]` with `candles[i]`, so the series has
+/// length `candles.len() - period`. Empty if there are not enough candles or the
+/// period is 0.
+pub fn roc_series(candles: &[Candle], period: Option<usize>) -> Vec<f64> {
+    lagged_closes(candles, period.unwrap_or(DEFAULT_ROC_PERIOD), |now, then| {
+        if then == 0.0 { 0.0 } else { (now - then) / then * 100.0 }
+    })
+}
+
+/// Calculates Momentum: the change of the close over `period` candles, in price units.
+///
+/// Momentum = close - close `period` candles ago
+///
+/// Pass `None` to use the default period of 12.
+/// Returns `None` if there are not enough candles (need at least period + 1 candles).
+pub fn momentum(candles: &[Candle], period: Option<usize>) -> Option<f64> {
+    momentum_series(candles, period).last().copied()
+}
+
+/// Calculates the Momentum series for all calculable points, aligned like `roc_series`.
+pub fn momentum_series(candles: &[Candle], period: Option<usize>) -> Vec<f64> {
+    lagged_closes(candles, period.unwrap_or(DEFAULT_MOMENTUM_PERIOD), |now, then| now - then)
+}
+
+/// Calculates the Commodity Channel Index (CCI) over a slice of candles.
+///
+/// CCI = (TP - SMA(TP)) / (0.015 * mean deviation of TP)
+/// where TP is the typical price (high + low + close) / 3 and the mean deviation is
+/// the average distance of TP from its SMA over the period
+///
+/// Common interpretation:
+/// - CCI > 100: Overbought / strong up-move
+/// - CCI < -100: Oversold / strong down-move
+///
+/// A window with no deviation (flat typical price) gives 0.
+/// Pass `None` to use the default period of 20.
+/// Returns `None` if there are not enough candles (need at least period candles).
+pub fn cci(candles: &[Candle], period: Option<usize>) -> Option<f64> {
+    cci_series(candles, period).last().copied()
+}
+
+/// Calculates the CCI series for all calculable points.
+///
+/// The first value covers the first `period` candles, so the series has length
+/// `candles.len() - period + 1`. Empty if there are not enough candles or the
+/// period is 0.
+pub fn cci_series(candles: &[Candle], period: Option<usize>) -> Vec<f64> {
+    let period = period.unwrap_or(DEFAULT_CCI_PERIOD);
+    if period == 0 || candles.len() < period {
+        return Vec::new();
+    }
+
+    let typical = PriceSource::Hlc3.extract(candles);
+    let means = rolling_mean(&typical, period);
+    let deviations = rolling_mean_deviation(&typical, period);
+    typical[period - 1..]
+        .iter()
+        .zip(means.iter().zip(&deviations))
+        .map(|(price, (mean, deviation))| {
+            if *deviation == 0.0 { 0.0 } else { (price - mean) / (CCI_CONSTANT * deviation) }
+        })
+        .collect()
+}
+
+/// Calculates the Money Flow Index (MFI) over a slice of candles: a volume-weighted RSI.
+///
+/// Each candle's money flow is its typical price (high + low + close) / 3 times its
+/// volume. It counts as positive flow when the typical price rose from the previous
+/// candle and negative when it fell; an unchanged typical price counts as neither.
+///
+/// MFI = 100 - (100 / (1 + positive flow / negative flow)), both summed over the period
+///
+/// Common interpretation:
+/// - MFI > 80: Overbought
+/// - MFI < 20: Oversold
+///
+/// No negative flow over the period gives 100, as `rsi` does with no losses.
+/// Pass `None` to use the default period of 14.
+/// Returns `None` if there are not enough candles (need at least period + 1 candles).
+pub fn mfi(candles: &[Candle], period: Option<usize>) -> Option<f64> {
+    mfi_series(candles, period).last().copied()
+}
+
+/// Calculates the MFI series for all calculable points.
+///
+/// The first value needs `period + 1` candles, so the series has length
+/// `candles.len() - period`. Empty if there are not enough candles or the period is 0.
+pub fn mfi_series(candles: &[Candle], period: Option<usize>) -> Vec<f64> {
+    let period = period.unwrap_or(DEFAULT_MFI_PERIOD);
+    if period == 0 || candles.len() < period + 1 {
+        return Vec::new();
+    }
+
+    let typical = PriceSource::Hlc3.extract(candles);
+    let (positive, negative): (Vec<f64>, Vec<f64>) = typical
         .windows(2)
-        .map(|pair| pair[1].get_close() - pair[0].get_close())
+        .zip(&candles[1..])
+        .map(|(pair, candle)| {
+            let flow = pair[1] * candle.get_volume();
+            if pair[1] > pair[0] {
+                (flow, 0.0)
+            } else if pair[1] < pair[0] {
+                (0.0, flow)
+            } else {
+                (0.0, 0.0)
+            }
+        })
+        .unzip();
+
+    rolling_sum(&positive, period)
+        .into_iter()
+        .zip(rolling_sum(&negative, period))
+        .map(|(positive, negative)| {
+            if negative == 0.0 {
+                100.0
+            } else {
+                100.0 - (100.0 / (1.0 + positive / negative))
+            }
+        })
         .collect()
 }
 
+/// `compare(close, close period candles earlier)` for every candle that has one.
+fn lagged_closes(candles: &[Candle], period: usize, compare: impl Fn(f64, f64) -> f64) -> Vec<f64> {
+    if period == 0 || candles.len() <= period {
+        return Vec::new();
+    }
+    candles[period..].iter().zip(candles).map(|(now, then)| compare(now.get_close(), then.get_close())).collect()
+}
+
+/// Calculates price changes between consecutive values.
+///
+/// Returns a vector of changes where each value is: current - previous
+fn price_changes(values: &[f64]) -> Vec<f64> {
+    values.windows(2).map(|pair| pair[1] - pair[0]).collect()
+}
+
 /// Separates price changes into gains and losses.
 ///
 /// Returns a tuple of (gains, losses) where:
@@ -98,6 +451,7 @@ fn gains_and_losses(changes: &[f64]) -> (Vec<f64>, Vec<f64>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::indicators::trend::fixtures::wave_candles;
 
     fn uptrend_candles() -> Vec<Candle> {
         // Strong uptrend: prices consistently rising
@@ -228,12 +582,13 @@ mod tests {
 
     #[test]
     fn test_price_changes() {
-        let candles = vec![
+        let candles = [
             Candle::new(0, 100.0, 105.0, 95.0, 100.0, 1000.0),
             Candle::new(0, 100.0, 108.0, 98.0, 105.0, 1000.0),
             Candle::new(0, 105.0, 110.0, 102.0, 103.0, 1000.0),
         ];
-        let changes = price_changes(&candles);
+        let closes: Vec<f64> = candles.iter().map(Candle::get_close).collect();
+        let changes = price_changes(&closes);
         assert_eq!(changes.len(), 2);
         assert_eq!(changes[0], 5.0); // 105 - 100
         assert_eq!(changes[1], -2.0); // 103 - 105
@@ -262,10 +617,235 @@ mod tests {
         // RSI should always be between 0 and 100
         let candles = uptrend_candles();
         let result = rsi(&candles, Some(14)).unwrap();
-        assert!(result >= 0.0 && result <= 100.0);
+        assert!((0.0..=100.0).contains(&result));
 
         let candles = downtrend_candles();
         let result = rsi(&candles, Some(14)).unwrap();
-        assert!(result >= 0.0 && result <= 100.0);
+        assert!((0.0..=100.0).contains(&result));
+    }
+
+    #[test]
+    fn test_rsi_series_unchanged_by_rma_refactor() {
+        // Output of the hand-rolled smoothing this replaced, compared bit for bit
+        let expected = [
+            66.66666666666666,
+            55.17241379310345,
+            66.12377850162866,
+            54.93910690121787,
+            65.78913573905226,
+            54.79437403069682,
+            65.57997017310795,
+            54.70355093523316,
+            65.44809504271663,
+            54.646145931419085,
+        ];
+        let series = rsi_series(&sideways_candles(), Some(5));
+        assert_eq!(series.iter().map(|v| v.to_bits()).collect::<Vec<_>>(), expected.map(f64::to_bits).to_vec());
+        assert_eq!(rsi_series(&sideways_candles(), None), vec![58.82352941176471]);
+        assert!(rsi_series(&uptrend_candles(), Some(5)).iter().all(|v| *v == 100.0));
+        assert!(rsi_series(&downtrend_candles(), Some(5)).iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_rsi_with_source() {
+        let candles = sideways_candles();
+        assert_eq!(rsi_with_source(&candles, Some(5), PriceSource::Close), rsi(&candles, Some(5)));
+        // Highs only rise in the uptrend fixture, so their RSI is pinned at 100 too
+        assert_eq!(rsi_with_source(&uptrend_candles(), None, PriceSource::High), Some(100.0));
+        assert_ne!(rsi_series_with_source(&candles, Some(5), PriceSource::Hl2), rsi_series(&candles, Some(5)));
+    }
+
+    #[test]
+    fn test_macd_series_reference_values() {
+        // From an independent implementation of the same definition (SMA-seeded EMAs)
+        let expected = [
+            (3.4889016253394516, 1.032340443087089, 2.4565611822523623),
+            (3.622691653606495, 1.5504106851909705, 2.0722809684155243),
+            (3.572154591381448, 1.9547594664290662, 1.617395124952382),
+            (3.3458688065454965, 2.2329813344523526, 1.1128874720931439),
+            (2.9645238890068413, 2.3792898453632505, 0.5852340436435908),
+            (2.4572647782400168, 2.3948848319386036, 0.06237994630141319),
+            (1.860326560080935, 2.28797317756707, -0.42764661748613486),
+        ];
+        let candles = wave_candles();
+        let series = macd_series(&candles, None, None, None);
+        assert_eq!(series.len(), candles.len() - 26 - 9 + 2);
+        for (i, (macd, signal, histogram)) in expected.into_iter().enumerate() {
+            let value = series.get(i).unwrap();
+            assert!((value.macd - macd).abs() < 1e-9, "macd[{}] = {}", i, value.macd);
+            assert!((value.signal - signal).abs() < 1e-9, "signal[{}] = {}", i, value.signal);
+            assert!((value.histogram - histogram).abs() < 1e-9, "histogram[{}] = {}", i, value.histogram);
+        }
+        assert_eq!(macd(&candles, None, None, None), series.last());
+
+        let short = macd_series(&candles, Some(3), Some(6), Some(4));
+        assert_eq!(short.len(), 40 - 6 - 4 + 2);
+        let last = short.last().unwrap();
+        assert!((last.macd - -1.9616712798093374).abs() < 1e-9);
+        assert!((last.signal - -1.1452685733605403).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_macd_insufficient_or_invalid() {
+        let candles = wave_candles();
+        assert!(macd_series(&candles[..33], None, None, None).is_empty());
+        assert_eq!(macd_series(&candles[..34], None, None, None).len(), 1);
+        assert!(macd(&candles, Some(26), Some(12), None).is_none());
+        assert!(macd(&candles, Some(0), None, None).is_none());
+        assert!(macd(&candles, None, None, Some(0)).is_none());
+    }
+
+    #[test]
+    fn test_macd_crossed_signal() {
+        let candles = wave_candles();
+        // The histogram turns negative on the last candle
+        let series = macd_series(&candles, None, None, None);
+        assert_eq!(series.crossed_signal(&candles), Some(CrossDirection::Down));
+        let crosses = series.signal_crosses(&candles);
+        assert_eq!(crosses.len(), 1);
+        assert_eq!(crosses[0].timestamp, candles.last().unwrap().get_timestamp());
+        // One candle earlier the MACD line was still above its signal
+        let earlier = &candles[..39];
+        assert_eq!(macd_series(earlier, None, None, None).crossed_signal(earlier), None);
+    }
+
+    #[test]
+    fn test_stochastic_uptrend_and_downtrend() {
+        // Defaults need 18 candles; the fixtures have 15
+        assert!(stochastic(&uptrend_candles(), None, None, None).is_none());
+
+        let up = stochastic_series(&uptrend_candles(), Some(5), None, None);
+        assert_eq!(up.len(), 15 - 5 - 3 - 3 + 3);
+        assert!(up.k.iter().chain(&up.d).all(|value| *value > 80.0), "{:?}", up);
+
+        let down = stochastic(&downtrend_candles(), Some(5), None, None).unwrap();
+        assert!(down.k < 20.0 && down.d < 20.0, "{:?}", down);
+    }
+
+    #[test]
+    fn test_stochastic_values() {
+        // Fast stochastic over 3 candles: the last close 106 in the range 104..109
+        let candles = sideways_candles();
+        let fast = stochastic_series(&candles, Some(3), Some(1), Some(1));
+        assert_eq!(fast.len(), 13);
+        assert!((fast.k[12] - 40.0).abs() < 1e-9);
+        assert_eq!(fast.k, fast.d);
+
+        let slow = stochastic_series(&candles, Some(3), Some(3), Some(2));
+        assert_eq!(slow.len(), 15 - 3 - 2 - 3 + 3);
+        // %D is the mean of the last three %K
+        let last = slow.last().unwrap();
+        let k = &slow.k;
+        assert_eq!(last.k, k[k.len() - 1]);
+        assert!((last.d - (k[k.len() - 3..].iter().sum::<f64>() / 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stochastic_flat_window_is_50() {
+        let flat = vec![Candle::new(0, 100.0, 100.0, 100.0, 100.0, 1.0); 20];
+        let value = stochastic(&flat, None, None, None).unwrap();
+        assert_eq!((value.k, value.d), (50.0, 50.0));
+        assert!(stochastic_series(&flat, Some(0), None, None).is_empty());
+        assert!(stochastic_series(&flat[..17], None, None, None).is_empty());
+        assert_eq!(stochastic_series(&flat[..18], None, None, None).len(), 1);
+    }
+
+    #[test]
+    fn test_roc_and_momentum() {
+        let up = uptrend_candles();
+        // 170 against 136 five candles back
+        assert_eq!(momentum(&up, Some(5)), Some(34.0));
+        assert!((roc(&up, Some(5)).unwrap() - 25.0).abs() < 1e-9);
+
+        let down = downtrend_candles();
+        let series = roc_series(&down, None);
+        assert_eq!(series.len(), 15 - 10);
+        assert!(series.iter().all(|value| *value < 0.0));
+        // 80 against 148
+        assert!((series[4] - (80.0 - 148.0) / 148.0 * 100.0).abs() < 1e-9);
+        assert_eq!(momentum_series(&down, None).len(), 15 - 12);
+        assert_eq!(momentum_series(&down, None)[2], 80.0 - 160.0);
+    }
+
+    #[test]
+    fn test_roc_edge_cases() {
+        let up = uptrend_candles();
+        assert!(roc(&up[..10], None).is_none());
+        assert_eq!(roc_series(&up[..11], None).len(), 1);
+        assert!(roc_series(&up, Some(0)).is_empty());
+        assert!(momentum(&up[..12], None).is_none());
+
+        let from_zero = vec![Candle::new(0, 0.0, 0.0, 0.0, 0.0, 1.0), Candle::new(0, 0.0, 5.0, 0.0, 5.0, 1.0)];
+        assert_eq!(roc(&from_zero, Some(1)), Some(0.0));
+        assert_eq!(momentum(&from_zero, Some(1)), Some(5.0));
+    }
+
+    #[test]
+    fn test_cci_hand_computed() {
+        // Typical prices 10, 12, 11, 14, 13 (high and low one either side of the close):
+        // SMA 12, mean deviation 1.2, so CCI = (13 - 12) / (0.015 * 1.2)
+        let candles: Vec<Candle> = [10.0, 12.0, 11.0, 14.0, 13.0]
+            .iter()
+            .map(|&tp| Candle::new(0, tp, tp + 1.0, tp - 1.0, tp, 1.0))
+            .collect();
+        let value = cci(&candles, Some(5)).unwrap();
+        assert!((value - 1.0 / 0.018).abs() < 1e-9, "{}", value);
+        assert!(cci(&candles, None).is_none());
+    }
+
+    #[test]
+    fn test_cci_strong_move_and_length() {
+        let up = cci_series(&uptrend_candles(), Some(5));
+        assert_eq!(up.len(), 15 - 5 + 1);
+        assert!(up.iter().all(|value| *value > 100.0), "{:?}", up);
+        assert!(cci(&downtrend_candles(), Some(5)).unwrap() < -100.0);
+        assert!(cci_series(&uptrend_candles(), Some(0)).is_empty());
+
+        let flat = vec![Candle::new(0, 100.0, 101.0, 99.0, 100.0, 1.0); 20];
+        assert_eq!(cci(&flat, None), Some(0.0));
+    }
+
+    fn typical_price_candles(prices: &[(f64, f64)]) -> Vec<Candle> {
+        prices.iter().map(|&(tp, volume)| Candle::new(0, tp, tp + 1.0, tp - 1.0, tp, volume)).collect()
+    }
+
+    #[test]
+    fn test_mfi_hand_computed() {
+        // Flows: +11 * 2, -10.5 * 4, +12 * 1, so MFI = 100 * 34 / (34 + 42)
+        let candles = typical_price_candles(&[(10.0, 1.0), (11.0, 2.0), (10.5, 4.0), (12.0, 1.0)]);
+        let series = mfi_series(&candles, Some(3));
+        assert_eq!(series.len(), 1);
+        assert!((series[0] - 3400.0 / 76.0).abs() < 1e-9, "{}", series[0]);
+        assert!(mfi(&candles, Some(4)).is_none());
+        assert!(mfi_series(&candles, Some(0)).is_empty());
+        assert_eq!(mfi_series(&uptrend_candles(), None).len(), 1);
+    }
+
+    #[test]
+    fn test_mfi_flow_edge_cases() {
+        assert_eq!(mfi(&uptrend_candles(), Some(5)), Some(100.0));
+        // The unchanged typical price is neither flow, leaving only the fall
+        let candles = typical_price_candles(&[(10.0, 1.0), (9.0, 1.0), (9.0, 5.0)]);
+        assert_eq!(mfi(&candles, Some(2)), Some(0.0));
+    }
+
+    #[test]
+    fn test_mfi_weighs_volume() {
+        // Same prices; one series trades heavily on the up candles, the other on the down candles
+        let reweigh = |up_volume: f64, down_volume: f64| -> Vec<Candle> {
+            sideways_candles()
+                .iter()
+                .map(|c| {
+                    let volume = if c.is_bullish() { up_volume } else { down_volume };
+                    Candle::new(0, c.get_open(), c.get_high(), c.get_low(), c.get_close(), volume)
+                })
+                .collect()
+        };
+        let (heavy_up, heavy_down) = (reweigh(3000.0, 500.0), reweigh(500.0, 3000.0));
+
+        let up = mfi(&heavy_up, Some(10)).unwrap();
+        let down = mfi(&heavy_down, Some(10)).unwrap();
+        assert!(up > 70.0 && down < 40.0, "{} vs {}", up, down);
+        assert_eq!(rsi(&heavy_up, Some(10)), rsi(&heavy_down, Some(10)));
     }
 }
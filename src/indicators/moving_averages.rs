@@ -1,6 +1,15 @@
-//! Moving Average indicators: Simple Moving Average (SMA) and Exponential Moving Average (EMA)
+//! Moving Average indicators: Simple (SMA), Exponential (EMA), Weighted (WMA), Hull (HMA),
+//! Wilder's smoothed (RMA), volume-weighted (VWMA), double and triple exponential (DEMA,
+//! TEMA) and Kaufman's adaptive (KAMA). `MaKind` picks one at runtime.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
 
 use crate::indicators::candle::Candle;
+use crate::indicators::price_source::PriceSource;
+use crate::indicators::rolling::{rolling_mean, rolling_sum};
+use crate::indicators::series::TimeSeries;
 
 /// Calculates the Simple Moving Average (SMA) over a slice of candles.
 ///
@@ -9,6 +18,11 @@ use crate::indicators::candle::Candle;
 /// Uses the closing prices of the most recent `period` candles.
 /// Returns `None` if there are not enough candles for the given period.
 pub fn sma(candles: &[Candle], period: usize) -> Option<f64> {
+    sma_with_source(candles, period, PriceSource::Close)
+}
+
+/// `sma` of another price than the close, e.g. `PriceSource::Hlc3`.
+pub fn sma_with_source(candles: &[Candle], period: usize, source: PriceSource) -> Option<f64> {
     if period == 0 || candles.len() < period {
         return None;
     }
@@ -16,7 +30,7 @@ pub fn sma(candles: &[Candle], period: usize) -> Option<f64> {
     let start_index = candles.len() - period;
     let sum: f64 = candles[start_index..]
         .iter()
-        .map(|c| c.get_close())
+        .map(|c| source.apply(c))
         .sum();
 
     Some(sum / period as f64)
@@ -31,8 +45,12 @@ pub fn sma(candles: &[Candle], period: usize) -> Option<f64> {
 /// The first EMA value is seeded with the SMA of the first `period` candles.
 /// Returns `None` if there are not enough candles for the given period.
 pub fn ema(candles: &[Candle], period: usize) -> Option<f64> {
-    let series = ema_series(candles, period);
-    series.last().copied()
+    ema_with_source(candles, period, PriceSource::Close)
+}
+
+/// `ema` of another price than the close.
+pub fn ema_with_source(candles: &[Candle], period: usize, source: PriceSource) -> Option<f64> {
+    ema_series_with_source(candles, period, source).last().copied()
 }
 
 /// Calculates the full EMA series for all candles.
@@ -43,28 +61,33 @@ pub fn ema(candles: &[Candle], period: usize) -> Option<f64> {
 ///
 /// Useful for crossover detection where you need historical EMA values.
 pub fn ema_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    ema_series_with_source(candles, period, PriceSource::Close)
+}
+
+/// `ema_series` of another price than the close.
+pub fn ema_series_with_source(candles: &[Candle], period: usize, source: PriceSource) -> Vec<f64> {
     if period == 0 || candles.len() < period {
         return Vec::new();
     }
+    ema_series_f64(&source.extract(candles), period)
+}
 
-    let multiplier = 2.0 / (period as f64 + 1.0);
-    let mut ema_values = Vec::with_capacity(candles.len() - period + 1);
+/// `ema_series` over a slice of values (e.g. `CandleBuffer::closes()`).
+pub fn ema_series_f64(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
 
-    // Seed the first EMA with SMA of the first `period` candles
-    let initial_sma: f64 = candles[..period]
-        .iter()
-        .map(|c| c.get_close())
-        .sum::<f64>()
-        / period as f64;
+    let multiplier = 2.0 / (period as f64 + 1.0);
+    let mut ema_values = Vec::with_capacity(values.len() - period + 1);
 
-    ema_values.push(initial_sma);
+    // Seed the first EMA with SMA of the first `period` values
+    let mut prev_ema = values[..period].iter().sum::<f64>() / period as f64;
+    ema_values.push(prev_ema);
 
-    // Calculate EMA for remaining candles
-    for i in period..candles.len() {
-        let close = candles[i].get_close();
-        let prev_ema = ema_values.last().unwrap();
-        let new_ema = close * multiplier + prev_ema * (1.0 - multiplier);
-        ema_values.push(new_ema);
+    for value in &values[period..] {
+        prev_ema = value * multiplier + prev_ema * (1.0 - multiplier);
+        ema_values.push(prev_ema);
     }
 
     ema_values
@@ -76,22 +99,505 @@ pub fn ema_series(candles: &[Candle], period: usize) -> Vec<f64> {
 /// The returned vector will have length `candles.len() - period + 1`.
 /// Returns an empty vector if there are not enough candles.
 pub fn sma_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    sma_series_with_source(candles, period, PriceSource::Close)
+}
+
+/// `sma_series` of another price than the close.
+pub fn sma_series_with_source(candles: &[Candle], period: usize, source: PriceSource) -> Vec<f64> {
     if period == 0 || candles.len() < period {
         return Vec::new();
     }
+    sma_series_f64(&source.extract(candles), period)
+}
 
-    let mut sma_values = Vec::with_capacity(candles.len() - period + 1);
+/// `sma_series` over a slice of values (e.g. `CandleBuffer::closes()`).
+///
+/// Keeps a running window sum, so it is O(n) whatever the period.
+pub fn sma_series_f64(values: &[f64], period: usize) -> Vec<f64> {
+    rolling_mean(values, period)
+}
 
-    for i in (period - 1)..candles.len() {
-        let start = i + 1 - period;
-        let sum: f64 = candles[start..=i]
-            .iter()
-            .map(|c| c.get_close())
-            .sum();
-        sma_values.push(sum / period as f64);
+/// Calculates the Weighted Moving Average (WMA) over a slice of candles.
+///
+/// WMA = (1 * C1 + 2 * C2 + ... + n * Cn) / (1 + 2 + ... + n)
+///
+/// The most recent close has the largest weight.
+/// Returns `None` if there are not enough candles for the given period.
+pub fn wma(candles: &[Candle], period: usize) -> Option<f64> {
+    wma_with_source(candles, period, PriceSource::Close)
+}
+
+/// `wma` of another price than the close.
+pub fn wma_with_source(candles: &[Candle], period: usize, source: PriceSource) -> Option<f64> {
+    wma_series_with_source(candles, period, source).last().copied()
+}
+
+/// Calculates the full WMA series for all candles.
+///
+/// The returned vector will have length `candles.len() - period + 1`.
+/// Returns an empty vector if there are not enough candles.
+pub fn wma_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    wma_series_with_source(candles, period, PriceSource::Close)
+}
+
+/// `wma_series` of another price than the close.
+pub fn wma_series_with_source(candles: &[Candle], period: usize, source: PriceSource) -> Vec<f64> {
+    if period == 0 || candles.len() < period {
+        return Vec::new();
+    }
+    wma_series_values(&source.extract(candles), period)
+}
+
+/// Calculates the Hull Moving Average (HMA) over a slice of candles.
+///
+/// HMA = WMA(2 * WMA(n / 2) - WMA(n), sqrt(n))
+///
+/// A WMA of the gap between a fast and a slow WMA, which cuts most of the lag
+/// while staying smooth. See `hma_series` for rounding and the candles needed.
+/// Returns `None` if there are not enough candles for the given period.
+pub fn hma(candles: &[Candle], period: usize) -> Option<f64> {
+    hma_series(candles, period).last().copied()
+}
+
+/// Calculates the full HMA series for all candles.
+///
+/// `n / 2` and `sqrt(n)` are rounded down (to at least 1), as on TradingView: a
+/// period of 10 uses WMAs of 5 and 10, then smooths with a WMA of 3. The first value
+/// needs `period + floor(sqrt(period)) - 1` candles, so the returned vector has
+/// length `candles.len() - period - floor(sqrt(period)) + 2`.
+/// Returns an empty vector if there are not enough candles.
+pub fn hma_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    if period == 0 {
+        return Vec::new();
+    }
+    let half = (period / 2).max(1);
+    let smoothing = (period as f64).sqrt().floor().max(1.0) as usize;
+    if candles.len() < period + smoothing - 1 {
+        return Vec::new();
+    }
+
+    let closes = closes(candles);
+    let slow = wma_series_values(&closes, period);
+    let fast = wma_series_values(&closes, half);
+    // Both series end on the last candle; the fast one starts `period - half` earlier
+    let gap: Vec<f64> = slow
+        .iter()
+        .zip(&fast[period - half..])
+        .map(|(slow, fast)| 2.0 * fast - slow)
+        .collect();
+    wma_series_values(&gap, smoothing)
+}
+
+/// Calculates Wilder's smoothed moving average (RMA, also SMMA) over a slice of candles.
+///
+/// RMA = RMA_prev + (Close - RMA_prev) / period
+///
+/// An EMA with multiplier `1 / period` instead of `2 / (period + 1)`, so it reacts
+/// more slowly. RSI, ATR and ADX smooth with it.
+/// Returns `None` if there are not enough candles for the given period.
+pub fn rma(candles: &[Candle], period: usize) -> Option<f64> {
+    rma_series(candles, period).last().copied()
+}
+
+/// Calculates the full RMA series for all candles.
+///
+/// The first value is the SMA of the first `period` candles. The returned vector
+/// will have length `candles.len() - period + 1`.
+/// Returns an empty vector if there are not enough candles.
+pub fn rma_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    if period == 0 || candles.len() < period {
+        return Vec::new();
+    }
+    rma_series_values(&closes(candles), period)
+}
+
+/// RMA of plain values (gains and losses for RSI, true ranges for ATR).
+///
+/// Each step is computed as `(prev * (period - 1) + value) / period`, the form
+/// Wilder wrote it in; RSI's output depends on this exact rounding.
+pub(crate) fn rma_series_values(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+
+    let mut rma_values = Vec::with_capacity(values.len() - period + 1);
+    let mut prev_rma = values[..period].iter().sum::<f64>() / period as f64;
+    rma_values.push(prev_rma);
+
+    for value in &values[period..] {
+        prev_rma = (prev_rma * (period - 1) as f64 + value) / period as f64;
+        rma_values.push(prev_rma);
+    }
+
+    rma_values
+}
+
+/// WMA of plain values, for composing weighted averages on derived series.
+///
+/// Keeps the weighted and plain window sums and updates both per value, so it is
+/// O(n) whatever the period.
+fn wma_series_values(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+
+    let weights = (period * (period + 1) / 2) as f64;
+    let mut weighted: f64 = values[..period].iter().enumerate().map(|(i, value)| (i + 1) as f64 * value).sum();
+    let mut sum: f64 = values[..period].iter().sum();
+    let mut wma_values = Vec::with_capacity(values.len() - period + 1);
+    wma_values.push(weighted / weights);
+
+    for (i, value) in values[period..].iter().enumerate() {
+        // Sliding one step lowers every weight by 1 and drops the oldest value
+        weighted += period as f64 * value - sum;
+        sum += value - values[i];
+        wma_values.push(weighted / weights);
+    }
+
+    wma_values
+}
+
+/// Calculates the Volume-Weighted Moving Average (VWMA) over a slice of candles.
+///
+/// VWMA = (C1 * V1 + ... + Cn * Vn) / (V1 + ... + Vn)
+///
+/// A window without volume falls back to the plain average of its closes.
+/// Returns `None` if there are not enough candles for the given period.
+pub fn vwma(candles: &[Candle], period: usize) -> Option<f64> {
+    vwma_series(candles, period).last().copied()
+}
+
+/// Calculates the full VWMA series for all candles.
+///
+/// The returned vector will have length `candles.len() - period + 1`.
+/// Returns an empty vector if there are not enough candles.
+pub fn vwma_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    if period == 0 || candles.len() < period {
+        return Vec::new();
+    }
+    let closes = closes(candles);
+    let volumes: Vec<f64> = candles.iter().map(Candle::get_volume).collect();
+    let weighted: Vec<f64> = closes.iter().zip(&volumes).map(|(close, volume)| close * volume).collect();
+
+    let means = rolling_mean(&closes, period);
+    rolling_sum(&weighted, period)
+        .into_iter()
+        .zip(rolling_sum(&volumes, period))
+        .zip(means)
+        .map(|((weighted, volume), mean)| if volume > 0.0 { weighted / volume } else { mean })
+        .collect()
+}
+
+/// Calculates the Double Exponential Moving Average (DEMA) over a slice of candles.
+///
+/// DEMA = 2 * EMA(n) - EMA(EMA(n))
+///
+/// Returns `None` if there are not enough candles (need `2 * period - 1`).
+pub fn dema(candles: &[Candle], period: usize) -> Option<f64> {
+    dema_series(candles, period).last().copied()
+}
+
+/// Calculates the full DEMA series for all candles.
+///
+/// The returned vector will have length `candles.len() - 2 * (period - 1)`.
+/// Returns an empty vector if there are not enough candles.
+pub fn dema_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    let ema1 = ema_series(candles, period);
+    let ema2 = ema_series_f64(&ema1, period);
+    if ema2.is_empty() {
+        return Vec::new();
+    }
+    // Each EMA starts `period - 1` values into its input; line both up on the last candle
+    ema1[period - 1..].iter().zip(&ema2).map(|(ema1, ema2)| 2.0 * ema1 - ema2).collect()
+}
+
+/// Calculates the Triple Exponential Moving Average (TEMA) over a slice of candles.
+///
+/// TEMA = 3 * EMA(n) - 3 * EMA(EMA(n)) + EMA(EMA(EMA(n)))
+///
+/// Returns `None` if there are not enough candles (need `3 * period - 2`).
+pub fn tema(candles: &[Candle], period: usize) -> Option<f64> {
+    tema_series(candles, period).last().copied()
+}
+
+/// Calculates the full TEMA series for all candles.
+///
+/// The returned vector will have length `candles.len() - 3 * (period - 1)`.
+/// Returns an empty vector if there are not enough candles.
+pub fn tema_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    let ema1 = ema_series(candles, period);
+    let ema2 = ema_series_f64(&ema1, period);
+    let ema3 = ema_series_f64(&ema2, period);
+    if ema3.is_empty() {
+        return Vec::new();
+    }
+    ema1[2 * (period - 1)..]
+        .iter()
+        .zip(&ema2[period - 1..])
+        .zip(&ema3)
+        .map(|((ema1, ema2), ema3)| 3.0 * ema1 - 3.0 * ema2 + ema3)
+        .collect()
+}
+
+/// Fastest and slowest smoothing of KAMA, as EMA periods (Kaufman's 2 and 30).
+const KAMA_FAST: f64 = 2.0 / (2.0 + 1.0);
+const KAMA_SLOW: f64 = 2.0 / (30.0 + 1.0);
+
+/// Calculates Kaufman's Adaptive Moving Average (KAMA) over a slice of candles.
+///
+/// ER = |C - C[n ago]| / (sum of |C - C_prev| over n candles)
+/// SC = (ER * (fast - slow) + slow)^2, with fast = 2/3 and slow = 2/31
+/// KAMA = KAMA_prev + SC * (C - KAMA_prev)
+///
+/// The efficiency ratio ER is 1 in a straight trend (KAMA moves like a 2-period EMA)
+/// and near 0 in chop (KAMA barely moves).
+/// Returns `None` if there are not enough candles for the given period.
+pub fn kama(candles: &[Candle], period: usize) -> Option<f64> {
+    kama_series(candles, period).last().copied()
+}
+
+/// Calculates the full KAMA series for all candles.
+///
+/// The first value is the close of candle `period - 1`, the first that has a full
+/// window behind the next one. The returned vector will have length
+/// `candles.len() - period + 1`.
+/// Returns an empty vector if there are not enough candles.
+pub fn kama_series(candles: &[Candle], period: usize) -> Vec<f64> {
+    if period == 0 || candles.len() < period {
+        return Vec::new();
+    }
+    let closes = closes(candles);
+    let moves: Vec<f64> = closes.windows(2).map(|pair| (pair[1] - pair[0]).abs()).collect();
+    // volatility[k] sums the moves into candles k + 1 ..= k + period
+    let volatility = rolling_sum(&moves, period);
+
+    let mut prev_kama = closes[period - 1];
+    let mut kama_values = Vec::with_capacity(closes.len() - period + 1);
+    kama_values.push(prev_kama);
+    for (i, volatility) in (period..closes.len()).zip(volatility) {
+        let change = (closes[i] - closes[i - period]).abs();
+        let efficiency = if volatility > 0.0 { change / volatility } else { 0.0 };
+        let smoothing = (efficiency * (KAMA_FAST - KAMA_SLOW) + KAMA_SLOW).powi(2);
+        prev_kama += smoothing * (closes[i] - prev_kama);
+        kama_values.push(prev_kama);
+    }
+
+    kama_values
+}
+
+/// Which moving average to compute, e.g. from a strategy config (see `FromStr`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaKind {
+    Sma,
+    Ema,
+    Wma,
+    Hma,
+    Rma,
+    Vwma,
+    Dema,
+    Tema,
+    Kama,
+}
+
+/// Parses a kind name, ignoring case: "sma", "ema", "wma", "hma", "rma", "vwma",
+/// "dema", "tema", "kama", or the long forms "simple", "exponential", "weighted",
+/// "hull", "wilder", "smma" (for RMA) and "kaufman".
+impl std::str::FromStr for MaKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "sma" | "simple" => Ok(MaKind::Sma),
+            "ema" | "exponential" => Ok(MaKind::Ema),
+            "wma" | "weighted" => Ok(MaKind::Wma),
+            "hma" | "hull" => Ok(MaKind::Hma),
+            "rma" | "smma" | "wilder" => Ok(MaKind::Rma),
+            "vwma" => Ok(MaKind::Vwma),
+            "dema" => Ok(MaKind::Dema),
+            "tema" => Ok(MaKind::Tema),
+            "kama" | "kaufman" => Ok(MaKind::Kama),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The latest `kind` moving average over `period` candles.
+/// Returns `None` if there are not enough candles for it.
+pub fn moving_average(candles: &[Candle], period: usize, kind: MaKind) -> Option<f64> {
+    moving_average_series(candles, period, kind).last().copied()
+}
+
+/// The full `kind` series, as the matching `*_series` function returns it.
+pub fn moving_average_series(candles: &[Candle], period: usize, kind: MaKind) -> Vec<f64> {
+    match kind {
+        MaKind::Sma => sma_series(candles, period),
+        MaKind::Ema => ema_series(candles, period),
+        MaKind::Wma => wma_series(candles, period),
+        MaKind::Hma => hma_series(candles, period),
+        MaKind::Rma => rma_series(candles, period),
+        MaKind::Vwma => vwma_series(candles, period),
+        MaKind::Dema => dema_series(candles, period),
+        MaKind::Tema => tema_series(candles, period),
+        MaKind::Kama => kama_series(candles, period),
+    }
+}
+
+/// Which way the fast series crossed the slow one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossDirection {
+    /// Fast crossed above slow (golden cross)
+    Up,
+    /// Fast crossed below slow (death cross)
+    Down,
+}
+
+/// A point where the fast series crossed the slow one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crossover {
+    /// Timestamp of the first value on the new side
+    pub timestamp: u64,
+    pub direction: CrossDirection,
+}
+
+/// Finds where `fast` crosses `slow`, oldest first.
+///
+/// Only timestamps present in both series are compared (see `TimeSeries::align_with`),
+/// so series of different periods or with gaps line up by time, not by position.
+///
+/// A cross is counted at the first value on the other side. Values where the two
+/// are equal (or NaN) sit on neither side: fast moving from below, through equal, to
+/// above crosses at the first value above, while touching and going back is no cross.
+pub fn crossovers(fast: &TimeSeries, slow: &TimeSeries) -> Vec<Crossover> {
+    let (fast, slow) = fast.align_with(slow);
+    let mut crosses = Vec::new();
+    let mut side = None;
+    for ((timestamp, fast), (_, slow)) in fast.iter().zip(slow.iter()) {
+        let current = if fast > slow {
+            CrossDirection::Up
+        } else if fast < slow {
+            CrossDirection::Down
+        } else {
+            continue;
+        };
+        if side.is_some_and(|side| side != current) {
+            crosses.push(Crossover { timestamp, direction: current });
+        }
+        side = Some(current);
     }
+    crosses
+}
+
+/// The most recent of `crossovers(fast, slow)`, if any.
+pub fn last_crossover(fast: &TimeSeries, slow: &TimeSeries) -> Option<Crossover> {
+    crossovers(fast, slow).pop()
+}
+
+/// Crosses of the `fast_period` moving average over the `slow_period` one, both of
+/// `kind`, e.g. EMA 50/200 golden and death crosses. Timestamps are those of `candles`.
+pub fn ma_crossovers(candles: &[Candle], fast_period: usize, slow_period: usize, kind: MaKind) -> Vec<Crossover> {
+    let fast = TimeSeries::from_indicator(candles, &moving_average_series(candles, fast_period, kind));
+    let slow = TimeSeries::from_indicator(candles, &moving_average_series(candles, slow_period, kind));
+    crossovers(&fast, &slow)
+}
 
-    sma_values
+/// EMA updated one value at a time, for live loops that would otherwise rerun
+/// `ema_series` over the whole history on every closed candle.
+///
+/// The first `period` values are averaged into the seed, as `ema_series` does; after
+/// that each update is O(1). Feeding the same closes gives the same values, bit for
+/// bit. Serializable, so the state can be checkpointed and restored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmaState {
+    period: usize,
+    /// Values seen while warming up, and their sum
+    seen: usize,
+    sum: f64,
+    value: Option<f64>,
+}
+
+impl EmaState {
+    pub fn new(period: usize) -> Self {
+        Self { period, seen: 0, sum: 0.0, value: None }
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Adds a value (usually a closed candle's close). Returns the EMA once `period`
+    /// values have been seen, None during warm-up or for a period of 0.
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        if self.period == 0 {
+            return None;
+        }
+        let ema = match self.value {
+            Some(prev_ema) => {
+                let multiplier = 2.0 / (self.period as f64 + 1.0);
+                value * multiplier + prev_ema * (1.0 - multiplier)
+            }
+            None => {
+                self.seen += 1;
+                self.sum += value;
+                if self.seen < self.period {
+                    return None;
+                }
+                self.sum / self.period as f64
+            }
+        };
+        self.value = Some(ema);
+        self.value
+    }
+
+    /// The current EMA, None during warm-up.
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+/// SMA updated one value at a time: the last `period` values in a ring buffer and
+/// their running sum, so each update is O(1). Matches `sma_series` bit for bit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmaState {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl SmaState {
+    pub fn new(period: usize) -> Self {
+        Self { period, window: VecDeque::with_capacity(period), sum: 0.0 }
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Adds a value. Returns the SMA once `period` values have been seen, None
+    /// during warm-up or for a period of 0.
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        if self.period == 0 {
+            return None;
+        }
+        if self.window.len() == self.period {
+            let leaving = self.window.pop_front().unwrap_or_default();
+            self.sum += value - leaving;
+        } else {
+            self.sum += value;
+        }
+        self.window.push_back(value);
+        self.value()
+    }
+
+    /// The current SMA, None during warm-up.
+    pub fn value(&self) -> Option<f64> {
+        (self.period > 0 && self.window.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+}
+
+fn closes(candles: &[Candle]) -> Vec<f64> {
+    candles.iter().map(Candle::get_close).collect()
 }
 
 #[cfg(test)]
@@ -123,6 +629,11 @@ mod tests {
         ]
     }
 
+    // One point per minute, starting `start` minutes in
+    fn minutes(start: u64, values: &[f64]) -> TimeSeries {
+        TimeSeries::from_points(values.iter().enumerate().map(|(i, &v)| ((start + i as u64) * 60_000, v)))
+    }
+
     #[test]
     fn test_sma_basic() {
         let candles = sample_candles();
@@ -210,4 +721,266 @@ mod tests {
         assert_eq!(series[1], 12.0);
         assert_eq!(series[2], 13.0);
     }
+
+    #[test]
+    fn test_wma_series_values() {
+        let candles = trending_up_candles();
+        let series = wma_series(&candles, 3);
+        assert_eq!(series.len(), 6);
+        // (1 * 100 + 2 * 105 + 3 * 110) / 6 and (1 * 120 + 2 * 126 + 3 * 133) / 6
+        assert!((series[0] - 640.0 / 6.0).abs() < 1e-9);
+        assert!((series[4] - 771.0 / 6.0).abs() < 1e-9);
+        assert_eq!(wma(&candles, 3), series.last().copied());
+        assert!(wma_series(&candles, 9).is_empty());
+    }
+
+    #[test]
+    fn test_hma_length_and_values() {
+        let candles = trending_up_candles();
+        // Period 4: WMAs of 2 and 4, smoothed by a WMA of 2; needs 4 + 2 - 1 candles
+        let series = hma_series(&candles, 4);
+        assert_eq!(series.len(), candles.len() - 4 - 2 + 2);
+        let expected = [120.0, 125.622_222_222, 132.422_222_222, 140.388_888_889];
+        for (value, expected) in series.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6, "{} != {}", value, expected);
+        }
+        assert_eq!(hma(&candles, 4), series.last().copied());
+
+        // Period 9: 9 + 3 - 1 = 11 candles, more than the fixture has
+        assert!(hma_series(&candles, 9).is_empty());
+        assert!(hma(&candles[..4], 4).is_none());
+        assert!(hma_series(&candles, 0).is_empty());
+    }
+
+    #[test]
+    fn test_hma_reacts_faster_than_sma() {
+        let candles = trending_up_candles();
+        let last_close = candles.last().unwrap().get_close();
+        let hma_val = hma(&candles, 4).unwrap();
+        let sma_val = sma(&candles, 4).unwrap();
+        // Closer to the latest price in a trend
+        assert!((last_close - hma_val).abs() < (last_close - sma_val).abs(), "HMA {} vs SMA {}", hma_val, sma_val);
+    }
+
+    #[test]
+    fn test_rma_seed_and_length() {
+        let candles = trending_up_candles();
+        let series = rma_series(&candles, 4);
+        assert_eq!(series.len(), candles.len() - 4 + 1);
+        // Seed: SMA of 100, 105, 110, 115; then 107.5 + (120 - 107.5) / 4
+        assert_eq!(series[0], 107.5);
+        assert_eq!(series[1], 110.625);
+        assert_eq!(rma(&candles, 4), series.last().copied());
+        assert_eq!(rma_series(&candles, 8), vec![sma(&candles, 8).unwrap()]);
+        assert!(rma_series(&candles, 9).is_empty());
+        assert!(rma(&candles, 0).is_none());
+
+        // Slower than EMA of the same period in a trend
+        assert!(rma(&candles, 4).unwrap() < ema(&candles, 4).unwrap());
+    }
+
+    #[test]
+    fn test_crossovers_two_known_crosses() {
+        let slow = minutes(0, &[10.0; 8]);
+        // Below, above at 2, touches at 4 and goes back up, equal at 5 then below at 6
+        let fast = minutes(0, &[8.0, 9.0, 11.0, 12.0, 10.0, 10.0, 9.0, 8.0]);
+        let crosses = crossovers(&fast, &slow);
+        assert_eq!(
+            crosses,
+            vec![
+                Crossover { timestamp: 120_000, direction: CrossDirection::Up },
+                Crossover { timestamp: 360_000, direction: CrossDirection::Down },
+            ]
+        );
+        assert_eq!(last_crossover(&fast, &slow), Some(crosses[1]));
+
+        // Touching and returning is not a cross
+        assert!(crossovers(&minutes(0, &[9.0, 10.0, 9.0]), &minutes(0, &[10.0; 3])).is_empty());
+        // Starting equal: the first separation isn't a cross either
+        assert!(crossovers(&minutes(0, &[10.0, 11.0]), &minutes(0, &[10.0; 2])).is_empty());
+        assert_eq!(last_crossover(&TimeSeries::new(), &minutes(0, &[1.0])), None);
+    }
+
+    #[test]
+    fn test_crossovers_align_by_timestamp() {
+        // Fast starts two minutes earlier: slow's first point lines up with fast's third
+        let fast = minutes(0, &[0.0, 100.0, 9.0, 11.0, 12.0]);
+        let slow = minutes(2, &[10.0, 10.0, 10.0]);
+        assert_eq!(crossovers(&fast, &slow), vec![Crossover { timestamp: 180_000, direction: CrossDirection::Up }]);
+        assert_eq!(crossovers(&slow, &fast), vec![Crossover { timestamp: 180_000, direction: CrossDirection::Down }]);
+
+        // A minute missing from one side is skipped, not shifted into its neighbour
+        let fast = TimeSeries::from_points([(0, 9.0), (60_000, 50.0), (120_000, 11.0)]);
+        let slow = TimeSeries::from_points([(0, 10.0), (120_000, 10.0)]);
+        assert_eq!(crossovers(&fast, &slow), vec![Crossover { timestamp: 120_000, direction: CrossDirection::Up }]);
+    }
+
+    #[test]
+    fn test_ma_crossovers_use_candle_timestamps() {
+        let closes = [12.0, 11.0, 10.0, 9.0, 13.0, 16.0, 16.0, 10.0, 4.0, 4.0];
+        let candles: Vec<Candle> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| Candle::new(i as u64 * 60_000, c, c, c, c, 1.0))
+            .collect();
+        let crosses = ma_crossovers(&candles, 2, 4, MaKind::Sma);
+        // SMA 2 rises above SMA 4 with the 13 close, falls below with the 10
+        assert_eq!(
+            crosses,
+            vec![
+                Crossover { timestamp: candles[4].get_timestamp(), direction: CrossDirection::Up },
+                Crossover { timestamp: candles[7].get_timestamp(), direction: CrossDirection::Down },
+            ]
+        );
+        assert!(ma_crossovers(&candles, 2, 20, MaKind::Ema).is_empty());
+    }
+
+    #[test]
+    fn test_vwma_weights_by_volume() {
+        let candles = [
+            Candle::new(0, 10.0, 10.0, 10.0, 10.0, 1.0),
+            Candle::new(0, 20.0, 20.0, 20.0, 20.0, 3.0),
+            Candle::new(0, 30.0, 30.0, 30.0, 30.0, 0.0),
+            Candle::new(0, 40.0, 40.0, 40.0, 40.0, 0.0),
+        ];
+        // (10 + 60) / 4, (60 + 0) / 3, then no volume: plain average of 30 and 40
+        assert_eq!(vwma_series(&candles, 2), vec![17.5, 20.0, 35.0]);
+        assert!(vwma(&candles, 5).is_none());
+    }
+
+    #[test]
+    fn test_dema_and_tema_lengths() {
+        let candles = trending_up_candles();
+        assert_eq!(dema_series(&candles, 3).len(), candles.len() - 4);
+        assert_eq!(tema_series(&candles, 3).len(), candles.len() - 6);
+        assert!(tema_series(&candles, 4).is_empty());
+        assert!(dema(&candles, 0).is_none());
+
+        // On a straight line EMA lags by a constant; DEMA and TEMA remove it
+        let line: Vec<Candle> = (0..20).map(|i| Candle::new(0, i as f64, i as f64, i as f64, i as f64, 1.0)).collect();
+        assert!((ema(&line, 3).unwrap() - 18.0).abs() < 1e-3);
+        assert!((dema(&line, 3).unwrap() - 19.0).abs() < 1e-3);
+        assert!((tema(&line, 3).unwrap() - 19.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_kama_adapts_to_efficiency() {
+        let candles = trending_up_candles();
+        let series = kama_series(&candles, 3);
+        assert_eq!(series.len(), candles.len() - 2);
+        // Seeded with the close of candle 2, then a 2-period-EMA-like chase in a clean trend
+        assert_eq!(series[0], 110.0);
+        assert!((series[1] - (110.0 + KAMA_FAST * KAMA_FAST * 5.0)).abs() < 1e-9);
+
+        // Pure chop: no net move over the window, so only the slowest smoothing
+        let chop: Vec<Candle> = [100.0, 110.0, 100.0, 110.0, 100.0]
+            .iter()
+            .map(|&c| Candle::new(0, c, c, c, c, 1.0))
+            .collect();
+        let kama_chop = kama_series(&chop, 2);
+        assert!((kama_chop[1] - (110.0 - KAMA_SLOW * KAMA_SLOW * 10.0)).abs() < 1e-9);
+        assert!(kama(&chop, 6).is_none());
+    }
+
+    #[test]
+    fn test_moving_average_dispatch() {
+        type Series = fn(&[Candle], usize) -> Vec<f64>;
+        let direct: [(MaKind, Series); 9] = [
+            (MaKind::Sma, sma_series),
+            (MaKind::Ema, ema_series),
+            (MaKind::Wma, wma_series),
+            (MaKind::Hma, hma_series),
+            (MaKind::Rma, rma_series),
+            (MaKind::Vwma, vwma_series),
+            (MaKind::Dema, dema_series),
+            (MaKind::Tema, tema_series),
+            (MaKind::Kama, kama_series),
+        ];
+        let candles = trending_up_candles();
+        for (kind, series) in direct {
+            assert_eq!(moving_average_series(&candles, 3, kind), series(&candles, 3), "{:?}", kind);
+            assert_eq!(moving_average(&candles, 3, kind), series(&candles, 3).last().copied(), "{:?}", kind);
+        }
+        assert_eq!(moving_average(&candles, 3, MaKind::Sma), sma(&candles, 3));
+    }
+
+    #[test]
+    fn test_ma_kind_from_str() {
+        let names = [
+            ("ema", MaKind::Ema),
+            ("EMA", MaKind::Ema),
+            (" hull ", MaKind::Hma),
+            ("smma", MaKind::Rma),
+            ("Wilder", MaKind::Rma),
+            ("vwma", MaKind::Vwma),
+            ("kaufman", MaKind::Kama),
+            ("simple", MaKind::Sma),
+        ];
+        for (name, kind) in names {
+            assert_eq!(name.parse::<MaKind>(), Ok(kind), "{:?}", name);
+        }
+        assert!("zlema".parse::<MaKind>().is_err());
+        assert_eq!(serde_json::to_string(&MaKind::Tema).unwrap(), r#""tema""#);
+    }
+
+    #[test]
+    fn test_ema_state_matches_ema_series() {
+        let candles = trending_up_candles();
+        let mut state = EmaState::new(3);
+        let streamed: Vec<Option<f64>> = candles.iter().map(|c| state.update(c.get_close())).collect();
+
+        // None while warming up, then exactly the batch values, seed included
+        assert_eq!(streamed[..2], [None, None]);
+        let batch = ema_series(&candles, 3);
+        assert_eq!(streamed[2].unwrap().to_bits(), ((100.0 + 105.0 + 110.0) / 3.0f64).to_bits());
+        let bits = |values: Vec<f64>| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(streamed[2..].iter().flatten().copied().collect()), bits(batch));
+        assert_eq!(state.value(), ema(&candles, 3));
+        assert_eq!(EmaState::new(0).update(1.0), None);
+    }
+
+    #[test]
+    fn test_sma_state_matches_sma_series() {
+        let candles = trending_up_candles();
+        let mut state = SmaState::new(4);
+        let streamed: Vec<f64> = candles.iter().filter_map(|c| state.update(c.get_close())).collect();
+        assert_eq!(streamed, sma_series(&candles, 4));
+        assert_eq!(state.period(), 4);
+        assert!(SmaState::new(0).update(1.0).is_none());
+    }
+
+    #[test]
+    fn test_states_survive_a_checkpoint() {
+        let candles = trending_up_candles();
+        let (head, tail) = candles.split_at(5);
+        let mut ema_state = EmaState::new(3);
+        let mut sma_state = SmaState::new(3);
+        for candle in head {
+            ema_state.update(candle.get_close());
+            sma_state.update(candle.get_close());
+        }
+
+        let mut ema_state: EmaState = serde_json::from_str(&serde_json::to_string(&ema_state).unwrap()).unwrap();
+        let mut sma_state: SmaState = serde_json::from_str(&serde_json::to_string(&sma_state).unwrap()).unwrap();
+        for candle in tail {
+            ema_state.update(candle.get_close());
+            sma_state.update(candle.get_close());
+        }
+        assert_eq!(ema_state.value(), ema(&candles, 3));
+        assert_eq!(sma_state.value(), sma_series(&candles, 3).last().copied());
+    }
+
+    #[test]
+    fn test_with_source() {
+        let candles = trending_up_candles();
+        assert_eq!(sma_with_source(&candles, 3, PriceSource::Close), sma(&candles, 3));
+        assert_eq!(ema_series_with_source(&candles, 3, PriceSource::Close), ema_series(&candles, 3));
+        assert_eq!(wma_with_source(&candles, 3, PriceSource::Close), wma(&candles, 3));
+
+        // Highs of the last two candles: 135 and 143
+        assert_eq!(sma_with_source(&candles, 2, PriceSource::High), Some(139.0));
+        // HL2 of the last two: (135 + 126) / 2 and (143 + 133) / 2
+        assert_eq!(sma_series_with_source(&candles, 2, PriceSource::Hl2).last(), Some(&134.25));
+        assert!(ema_with_source(&candles, 9, PriceSource::Hl2).is_none());
+    }
 }
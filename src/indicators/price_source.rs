@@ -0,0 +1,61 @@
+//! Which price of a candle an indicator reads
+
+use serde::{Deserialize, Serialize};
+
+use crate::indicators::candle::Candle;
+
+/// The value taken from each candle. Indicators default to `Close`; their
+/// `_with_source` variants take one of these.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    #[default]
+    Close,
+    Open,
+    High,
+    Low,
+    /// Median price: (high + low) / 2
+    Hl2,
+    /// Typical price: (high + low + close) / 3, used by CCI and MFI
+    Hlc3,
+    /// (open + high + low + close) / 4
+    Ohlc4,
+}
+
+impl PriceSource {
+    /// This source's price of `candle`.
+    pub fn apply(self, candle: &Candle) -> f64 {
+        match self {
+            PriceSource::Close => candle.get_close(),
+            PriceSource::Open => candle.get_open(),
+            PriceSource::High => candle.get_high(),
+            PriceSource::Low => candle.get_low(),
+            PriceSource::Hl2 => (candle.get_high() + candle.get_low()) / 2.0,
+            PriceSource::Hlc3 => (candle.get_high() + candle.get_low() + candle.get_close()) / 3.0,
+            PriceSource::Ohlc4 => {
+                (candle.get_open() + candle.get_high() + candle.get_low() + candle.get_close()) / 4.0
+            }
+        }
+    }
+
+    /// `apply` to every candle, oldest first.
+    pub fn extract(self, candles: &[Candle]) -> Vec<f64> {
+        candles.iter().map(|candle| self.apply(candle)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prices_of_a_candle() {
+        let candle = Candle::new(0, 101.0, 110.0, 90.0, 105.0, 1.0);
+        assert_eq!(PriceSource::Hl2.apply(&candle), 100.0);
+        assert_eq!(PriceSource::Hlc3.apply(&candle), 305.0 / 3.0);
+        assert_eq!(PriceSource::Ohlc4.apply(&candle), 101.5);
+        assert_eq!(PriceSource::default().apply(&candle), 105.0);
+        assert_eq!(PriceSource::Low.extract(&[candle, candle]), vec![90.0, 90.0]);
+        assert_eq!(serde_json::to_string(&PriceSource::Hlc3).unwrap(), r#""hlc3""#);
+    }
+}
@@ -0,0 +1,194 @@
+//! Renko bricks: fixed-size price moves with time and volume left out
+
+use serde::{Deserialize, Serialize};
+
+use crate::indicators::candle::Candle;
+use crate::indicators::volatility::atr;
+
+// Bricks are counted with a small tolerance so that prices landing exactly on a
+// brick boundary (100 + 3 * 0.1) aren't lost to float error.
+const BOUNDARY_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenkoDirection {
+    Up,
+    Down,
+}
+
+/// One brick: `close` is `open` plus or minus the brick size.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RenkoBrick {
+    pub open: f64,
+    pub close: f64,
+    pub direction: RenkoDirection,
+    /// Time of the price that completed the brick
+    pub timestamp: u64,
+}
+
+/// Builds bricks from a price stream (or candle closes).
+///
+/// The first price is the starting point. A brick forms each time the price moves
+/// a full brick size past the last brick's close in its direction. Reversing takes
+/// a move of two brick sizes from that close, i.e. one past the last brick's open,
+/// where the reversal brick starts.
+#[derive(Debug, Clone)]
+pub struct RenkoBuilder {
+    brick_size: f64,
+    start: Option<f64>,
+    bricks: Vec<RenkoBrick>,
+}
+
+impl RenkoBuilder {
+    /// Bricks of `brick_size` (in price units). A size that isn't positive makes no bricks.
+    pub fn new(brick_size: f64) -> Self {
+        Self { brick_size, start: None, bricks: Vec::new() }
+    }
+
+    /// Brick size set to the ATR of `candles` over `period` (default 14). None if
+    /// there are not enough candles or the ATR is 0.
+    pub fn with_atr_size(candles: &[Candle], period: Option<usize>) -> Option<Self> {
+        atr(candles, period).filter(|size| *size > 0.0).map(Self::new)
+    }
+
+    pub fn brick_size(&self) -> f64 {
+        self.brick_size
+    }
+
+    /// Adds a price. Returns the bricks it completed (none, one or several).
+    pub fn push_price(&mut self, price: f64, timestamp: u64) -> &[RenkoBrick] {
+        let before = self.bricks.len();
+        if self.brick_size.is_nan() || self.brick_size <= 0.0 {
+            return &[];
+        }
+        let size = self.brick_size;
+        let bricks = |distance: f64| (distance / size + BOUNDARY_EPSILON).floor().max(0.0) as usize;
+
+        // (first brick's open, direction, count)
+        let run = match self.bricks.last() {
+            None => {
+                let Some(start) = self.start else {
+                    self.start = Some(price);
+                    return &[];
+                };
+                if price > start {
+                    (start, RenkoDirection::Up, bricks(price - start))
+                } else {
+                    (start, RenkoDirection::Down, bricks(start - price))
+                }
+            }
+            Some(last) => match last.direction {
+                RenkoDirection::Up if price > last.close => (last.close, RenkoDirection::Up, bricks(price - last.close)),
+                RenkoDirection::Up => (last.open, RenkoDirection::Down, bricks(last.open - price)),
+                RenkoDirection::Down if price < last.close => {
+                    (last.close, RenkoDirection::Down, bricks(last.close - price))
+                }
+                RenkoDirection::Down => (last.open, RenkoDirection::Up, bricks(price - last.open)),
+            },
+        };
+
+        let (open, direction, count) = run;
+        let step = match direction {
+            RenkoDirection::Up => size,
+            RenkoDirection::Down => -size,
+        };
+        for k in 0..count {
+            let brick_open = open + k as f64 * step;
+            self.bricks.push(RenkoBrick { open: brick_open, close: brick_open + step, direction, timestamp });
+        }
+        &self.bricks[before..]
+    }
+
+    /// Feeds candle closes, at each candle's timestamp.
+    pub fn push_candles(&mut self, candles: &[Candle]) {
+        for candle in candles {
+            self.push_price(candle.get_close(), candle.get_timestamp());
+        }
+    }
+
+    /// Every brick so far, oldest first.
+    pub fn bricks(&self) -> &[RenkoBrick] {
+        &self.bricks
+    }
+
+    /// The bricks as candles (high and low at the brick's ends, zero volume), for the
+    /// candle-based indicators and pattern detection.
+    pub fn to_candles(&self) -> Vec<Candle> {
+        self.bricks
+            .iter()
+            .map(|brick| {
+                let (low, high) = (brick.open.min(brick.close), brick.open.max(brick.close));
+                Candle::new(brick.timestamp, brick.open, high, low, brick.close, 0.0)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(builder: &mut RenkoBuilder, prices: &[f64]) -> Vec<(f64, f64)> {
+        for (i, price) in prices.iter().enumerate() {
+            builder.push_price(*price, i as u64);
+        }
+        builder.bricks().iter().map(|brick| (brick.open, brick.close)).collect()
+    }
+
+    #[test]
+    fn test_continuation_bricks() {
+        let mut builder = RenkoBuilder::new(10.0);
+        assert_eq!(path(&mut builder, &[100.0, 109.0, 110.0, 135.0]), vec![(100.0, 110.0), (110.0, 120.0), (120.0, 130.0)]);
+        // The jump to 135 made two bricks at once, both stamped with its time
+        assert_eq!(builder.bricks()[2].timestamp, 3);
+        assert!(builder.bricks().iter().all(|brick| brick.direction == RenkoDirection::Up));
+
+        let mut builder = RenkoBuilder::new(0.1);
+        assert_eq!(path(&mut builder, &[100.0, 100.3]).len(), 3);
+    }
+
+    #[test]
+    fn test_reversal_needs_two_bricks() {
+        let mut builder = RenkoBuilder::new(10.0);
+        path(&mut builder, &[100.0, 120.0]);
+        // Last brick 110 -> 120: 105 is only 1.5 bricks back
+        assert!(builder.push_price(105.0, 2).is_empty());
+        let reversal = builder.push_price(100.0, 3);
+        assert_eq!(reversal.len(), 1);
+        assert_eq!((reversal[0].open, reversal[0].close, reversal[0].direction), (110.0, 100.0, RenkoDirection::Down));
+
+        // And back up: past the down brick's open (110) by a brick
+        assert!(builder.push_price(115.0, 4).is_empty());
+        assert_eq!(builder.push_price(120.0, 5)[0].close, 120.0);
+    }
+
+    #[test]
+    fn test_no_bricks_within_range() {
+        let mut builder = RenkoBuilder::new(10.0);
+        assert!(path(&mut builder, &[100.0, 105.0, 95.0, 109.9, 90.1, 100.0]).is_empty());
+        assert!(path(&mut RenkoBuilder::new(0.0), &[100.0, 200.0]).is_empty());
+    }
+
+    #[test]
+    fn test_bricks_from_candles() {
+        let candles: Vec<Candle> = [100.0, 102.0, 98.0, 104.0, 106.0, 95.0]
+            .iter()
+            .enumerate()
+            .map(|(i, close)| Candle::new(i as u64 * 60, *close, close + 1.0, close - 1.0, *close, 1.0))
+            .collect();
+        // True ranges 3, 5, 7, 3 and 12
+        let mut builder = RenkoBuilder::with_atr_size(&candles, Some(5)).unwrap();
+        assert!((builder.brick_size() - 6.0).abs() < 1e-9);
+        assert!(RenkoBuilder::with_atr_size(&candles, Some(10)).is_none());
+
+        builder = RenkoBuilder::new(2.0);
+        builder.push_candles(&candles);
+        let bricks = builder.to_candles();
+        // Up to 102, down from 100 to 98, up from 100 to 106, then down from 104 to 96
+        let closes: Vec<f64> = bricks.iter().map(Candle::get_close).collect();
+        assert_eq!(closes, vec![102.0, 98.0, 102.0, 104.0, 106.0, 102.0, 100.0, 98.0, 96.0]);
+        assert_eq!(bricks[5].get_timestamp(), 300);
+        assert!(bricks[5].is_bearish());
+        assert_eq!((bricks[0].get_high(), bricks[0].get_low()), (102.0, 100.0));
+    }
+}
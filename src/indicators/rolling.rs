@@ -0,0 +1,268 @@
+//! Rolling window helpers: max, min (or where they are), sum, mean, variance and
+//! mean deviation over a fixed period.
+//!
+//! Every function returns one value per full window, so the output has
+//! `values.len() - period + 1` entries and entry `i` covers `values[i..i + period]`,
+//! the same alignment as `sma_series`. Not enough values, or a period of 0, gives
+//! an empty vector. All of them run in O(n) regardless of the period, except
+//! `rolling_mean_deviation`.
+
+use std::collections::VecDeque;
+
+use crate::indicators::candle::Candle;
+
+/// Highest value of each window.
+///
+/// Keeps a deque of indices whose values decrease from front to back: the front is
+/// the window's max, and a new value drops every smaller one behind it since those
+/// can never be the max again. Each index enters and leaves once.
+pub fn rolling_max(values: &[f64], period: usize) -> Vec<f64> {
+    rolling_max_index(values, period).into_iter().map(|index| values[index]).collect()
+}
+
+/// Lowest value of each window. See `rolling_max`.
+pub fn rolling_min(values: &[f64], period: usize) -> Vec<f64> {
+    rolling_min_index(values, period).into_iter().map(|index| values[index]).collect()
+}
+
+/// Index into `values` of the highest value of each window. When the max appears
+/// more than once in a window, the latest one.
+pub fn rolling_max_index(values: &[f64], period: usize) -> Vec<usize> {
+    rolling_extreme(values, period, |kept, new| kept <= new)
+}
+
+/// Index into `values` of the lowest value of each window, the latest one on ties.
+pub fn rolling_min_index(values: &[f64], period: usize) -> Vec<usize> {
+    rolling_extreme(values, period, |kept, new| kept >= new)
+}
+
+/// `dominated(kept, new)` tells whether `new` makes `kept` irrelevant. Dominating on
+/// equal values is what makes the latest of equal extremes win.
+fn rolling_extreme(values: &[f64], period: usize, dominated: impl Fn(f64, f64) -> bool) -> Vec<usize> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+
+    let mut result = Vec::with_capacity(values.len() - period + 1);
+    let mut window: VecDeque<usize> = VecDeque::with_capacity(period);
+
+    for (i, &value) in values.iter().enumerate() {
+        while window.back().is_some_and(|&back| dominated(values[back], value)) {
+            window.pop_back();
+        }
+        window.push_back(i);
+        if window.front().is_some_and(|&front| front + period <= i) {
+            window.pop_front();
+        }
+        if i + 1 >= period {
+            result.push(window[0]);
+        }
+    }
+
+    result
+}
+
+/// Highest high of each `period`-candle window (Donchian upper band, Stochastic %K).
+pub fn highest_high(candles: &[Candle], period: usize) -> Vec<f64> {
+    let highs: Vec<f64> = candles.iter().map(Candle::get_high).collect();
+    rolling_max(&highs, period)
+}
+
+/// Lowest low of each `period`-candle window.
+pub fn lowest_low(candles: &[Candle], period: usize) -> Vec<f64> {
+    let lows: Vec<f64> = candles.iter().map(Candle::get_low).collect();
+    rolling_min(&lows, period)
+}
+
+/// Sum of each window, kept as a running sum.
+pub fn rolling_sum(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+
+    let mut result = Vec::with_capacity(values.len() - period + 1);
+    let mut sum: f64 = values[..period].iter().sum();
+    result.push(sum);
+    for (entering, leaving) in values[period..].iter().zip(values) {
+        sum += entering - leaving;
+        result.push(sum);
+    }
+
+    result
+}
+
+/// Mean of each window; `sma_series_f64` is this.
+pub fn rolling_mean(values: &[f64], period: usize) -> Vec<f64> {
+    rolling_sum(values, period).into_iter().map(|sum| sum / period as f64).collect()
+}
+
+/// Population variance (divided by `period`) of each window.
+///
+/// Slides the mean and the sum of squared deviations together (Welford's update
+/// for a value swapped out of the window), which avoids the cancellation of the
+/// `E[x²] - E[x]²` form on prices far from zero.
+pub fn rolling_variance(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+
+    let n = period as f64;
+    let mut mean = values[..period].iter().sum::<f64>() / n;
+    let mut squared: f64 = values[..period].iter().map(|value| (value - mean).powi(2)).sum();
+
+    let mut result = Vec::with_capacity(values.len() - period + 1);
+    result.push(squared / n);
+    for (&entering, &leaving) in values[period..].iter().zip(values) {
+        let previous_mean = mean;
+        mean += (entering - leaving) / n;
+        squared += (entering - leaving) * (entering - mean + leaving - previous_mean);
+        // Rounding can leave a tiny negative on a flat window
+        result.push((squared / n).max(0.0));
+    }
+
+    result
+}
+
+/// Mean absolute deviation from the mean of each window (CCI's denominator).
+///
+/// Unlike the variance there is no running update for this: every value's distance
+/// changes when the mean moves, so each window is rescanned, O(n * period).
+pub fn rolling_mean_deviation(values: &[f64], period: usize) -> Vec<f64> {
+    rolling_mean(values, period)
+        .into_iter()
+        .zip(values.windows(period.max(1)))
+        .map(|(mean, window)| window.iter().map(|value| (value - mean).abs()).sum::<f64>() / period as f64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference implementation: scan every window.
+    fn naive(values: &[f64], period: usize, reduce: impl Fn(&[f64]) -> f64) -> Vec<f64> {
+        if period == 0 {
+            return Vec::new();
+        }
+        values.windows(period).map(reduce).collect()
+    }
+
+    fn max(window: &[f64]) -> f64 {
+        window.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    fn min(window: &[f64]) -> f64 {
+        window.iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    fn variance(window: &[f64]) -> f64 {
+        let mean = window.iter().sum::<f64>() / window.len() as f64;
+        window.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / window.len() as f64
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9 * e.abs().max(1.0), "{actual:?} != {expected:?}");
+        }
+    }
+
+    fn zigzag() -> Vec<f64> {
+        (0..200).map(|i| 100.0 + ((i * 37) % 23) as f64 - ((i * 11) % 7) as f64 * 0.5).collect()
+    }
+
+    #[test]
+    fn test_max_min_match_naive_scan() {
+        let values = zigzag();
+        for period in [1, 2, 3, 7, 23, 200] {
+            assert_eq!(rolling_max(&values, period), naive(&values, period, max), "period {period}");
+            assert_eq!(rolling_min(&values, period), naive(&values, period, min), "period {period}");
+            assert_eq!(rolling_max(&values, period).len(), values.len() - period + 1);
+        }
+    }
+
+    #[test]
+    fn test_window_alignment() {
+        let values = [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        // Entry i covers values[i..i + 3]
+        assert_eq!(rolling_max(&values, 3), vec![4.0, 4.0, 5.0, 9.0, 9.0, 9.0]);
+        assert_eq!(rolling_min(&values, 3), vec![1.0, 1.0, 1.0, 1.0, 2.0, 2.0]);
+        assert_eq!(rolling_sum(&values, 3), vec![8.0, 6.0, 10.0, 15.0, 16.0, 17.0]);
+        assert_eq!(rolling_max(&values, 1), values.to_vec());
+    }
+
+    #[test]
+    fn test_extreme_indices_prefer_latest() {
+        let values = [3.0, 5.0, 1.0, 5.0, 1.0, 2.0];
+        // Windows of 4: [3 5 1 5], [5 1 5 1], [1 5 1 2]
+        assert_eq!(rolling_max_index(&values, 4), vec![3, 3, 3]);
+        assert_eq!(rolling_min_index(&values, 4), vec![2, 4, 4]);
+        assert_eq!(rolling_max_index(&[7.0; 5], 3), vec![2, 3, 4]);
+        assert!(rolling_min_index(&values, 7).is_empty());
+    }
+
+    #[test]
+    fn test_constant_and_monotone_inputs() {
+        let flat = [5.0; 10];
+        assert_eq!(rolling_max(&flat, 4), vec![5.0; 7]);
+        assert_eq!(rolling_min(&flat, 4), vec![5.0; 7]);
+        assert_eq!(rolling_variance(&flat, 4), vec![0.0; 7]);
+
+        let rising: Vec<f64> = (0..10).map(f64::from).collect();
+        // Max is each window's last value, min its first
+        assert_eq!(rolling_max(&rising, 4), (3..10).map(f64::from).collect::<Vec<_>>());
+        assert_eq!(rolling_min(&rising, 4), (0..7).map(f64::from).collect::<Vec<_>>());
+
+        let falling: Vec<f64> = rising.iter().rev().copied().collect();
+        assert_eq!(rolling_max(&falling, 4), (3..10).rev().map(f64::from).collect::<Vec<_>>());
+        assert_eq!(rolling_min(&falling, 4), (0..7).rev().map(f64::from).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_short_input_or_zero_period() {
+        let values = [1.0, 2.0, 3.0];
+        assert!(rolling_max(&values, 4).is_empty());
+        assert!(rolling_min(&values, 0).is_empty());
+        assert!(rolling_sum(&values, 4).is_empty());
+        assert!(rolling_mean(&[], 1).is_empty());
+        assert!(rolling_variance(&values, 0).is_empty());
+        assert!(rolling_mean_deviation(&values, 0).is_empty());
+        assert!(rolling_mean_deviation(&values, 4).is_empty());
+        assert_eq!(rolling_mean(&values, 3), vec![2.0]);
+    }
+
+    #[test]
+    fn test_sum_mean_variance_match_naive() {
+        // Prices near 30000 with small moves: where the E[x²] - E[x]² shortcut loses digits
+        let values: Vec<f64> = zigzag().iter().map(|value| 30_000.0 + value * 0.01).collect();
+        for period in [1, 5, 20] {
+            let sum = |window: &[f64]| window.iter().sum::<f64>();
+            assert_close(&rolling_sum(&values, period), &naive(&values, period, sum));
+            assert_close(&rolling_mean(&values, period), &naive(&values, period, |w| sum(w) / period as f64));
+            assert_close(&rolling_variance(&values, period), &naive(&values, period, variance));
+        }
+        assert_close(&rolling_variance(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0], 8), &[4.0]);
+    }
+
+    #[test]
+    fn test_mean_deviation() {
+        // Mean 12; distances 2, 0, 1, 2, 1. Then mean 13; distances 1, 2, 1, 0, 2
+        let values = [10.0, 12.0, 11.0, 14.0, 13.0, 15.0];
+        assert_close(&rolling_mean_deviation(&values, 5), &[1.2, 1.2]);
+        assert_eq!(rolling_mean_deviation(&[5.0; 4], 2), vec![0.0; 3]);
+        // Smaller than the standard deviation whenever the distances differ
+        assert!(rolling_mean_deviation(&values, 5)[0] < rolling_variance(&values, 5)[0].sqrt());
+    }
+
+    #[test]
+    fn test_highest_high_lowest_low() {
+        let candles: Vec<Candle> = [(10.0, 8.0), (12.0, 9.0), (11.0, 7.0), (13.0, 10.0), (9.0, 6.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(high, low))| Candle::new(i as u64, low, high, low, high, 1.0))
+            .collect();
+        assert_eq!(highest_high(&candles, 2), vec![12.0, 12.0, 13.0, 13.0]);
+        assert_eq!(lowest_low(&candles, 3), vec![7.0, 7.0, 6.0]);
+        assert!(highest_high(&candles, 6).is_empty());
+    }
+}
@@ -0,0 +1,790 @@
+//! Timestamped numeric series and the alignment rules shared by multi-series helpers
+
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::MarketData;
+use crate::market::symbol::Symbol;
+
+// Design: indicator functions return plain Vec<f64>s that are shorter than their
+// input by the warm-up, and order-flow series may have gaps where no trades
+// happened. Comparing two of those by index silently pairs the wrong bars.
+// TimeSeries carries the timestamp of every value, and every binary operation
+// works on the intersection of timestamps - that is the single alignment rule.
+//
+// Invariant: timestamps are strictly increasing.
+
+/// A series of (timestamp, value) points, oldest first.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TimeSeries {
+    points: Vec<(u64, f64)>,
+}
+
+impl TimeSeries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a series from unordered points. Sorts by timestamp; on duplicate
+    /// timestamps the last point given wins.
+    pub fn from_points(points: impl IntoIterator<Item = (u64, f64)>) -> Self {
+        let mut points: Vec<(u64, f64)> = points.into_iter().collect();
+        // Stable sort keeps input order within a timestamp, so dedup can keep the last
+        points.sort_by_key(|(ts, _)| *ts);
+        let mut deduped: Vec<(u64, f64)> = Vec::with_capacity(points.len());
+        for point in points {
+            match deduped.last_mut() {
+                Some(last) if last.0 == point.0 => *last = point,
+                _ => deduped.push(point),
+            }
+        }
+        Self { points: deduped }
+    }
+
+    /// Attaches candle timestamps to an indicator output.
+    ///
+    /// `values` is right-aligned with `candles` (the last value belongs to the last
+    /// candle), which is how every indicator here reports its warm-up. If `values` is
+    /// longer than `candles`, the surplus at its start is dropped.
+    /// Candles are expected in time order.
+    pub fn from_indicator(candles: &[Candle], values: &[f64]) -> Self {
+        let len = candles.len().min(values.len());
+        let candles = &candles[candles.len() - len..];
+        let values = &values[values.len() - len..];
+        Self::from_points(candles.iter().map(Candle::get_timestamp).zip(values.iter().copied()))
+    }
+
+    /// One value per candle, e.g. `TimeSeries::from_candles(&candles, Candle::get_close)`.
+    pub fn from_candles(candles: &[Candle], value: impl Fn(&Candle) -> f64) -> Self {
+        Self::from_points(candles.iter().map(|c| (c.get_timestamp(), value(c))))
+    }
+
+    /// Appends a point. Returns false (and ignores it) unless it is newer than the last one.
+    pub fn push(&mut self, timestamp: u64, value: f64) -> bool {
+        if self.points.last().is_some_and(|(last, _)| *last >= timestamp) {
+            return false;
+        }
+        self.points.push((timestamp, value));
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn points(&self) -> &[(u64, f64)] {
+        &self.points
+    }
+
+    pub fn timestamps(&self) -> Vec<u64> {
+        self.points.iter().map(|(ts, _)| *ts).collect()
+    }
+
+    /// Plain values, oldest first - the shape the indicator functions take and return.
+    pub fn values(&self) -> Vec<f64> {
+        self.points.iter().map(|(_, value)| *value).collect()
+    }
+
+    pub fn last(&self) -> Option<(u64, f64)> {
+        self.points.last().copied()
+    }
+
+    /// Value at exactly `timestamp`.
+    pub fn get(&self, timestamp: u64) -> Option<f64> {
+        self.points
+            .binary_search_by_key(&timestamp, |(ts, _)| *ts)
+            .ok()
+            .map(|i| self.points[i].1)
+    }
+
+    /// Lags the series by `n` points: each timestamp gets the value from `n` points
+    /// earlier. The first `n` timestamps have no earlier value and are dropped.
+    pub fn shift(&self, n: usize) -> Self {
+        if n >= self.points.len() {
+            return Self::new();
+        }
+        let points = self.points[n..]
+            .iter()
+            .zip(&self.points)
+            .map(|((ts, _), (_, value))| (*ts, *value))
+            .collect();
+        Self { points }
+    }
+
+    /// Points strictly after `timestamp`.
+    pub fn slice_after(&self, timestamp: u64) -> Self {
+        let start = self.points.partition_point(|(ts, _)| *ts <= timestamp);
+        Self {
+            points: self.points[start..].to_vec(),
+        }
+    }
+
+    /// Views of both series restricted to the timestamps they share.
+    /// The two views always have the same length and timestamps.
+    pub fn align_with<'a>(&'a self, other: &'a TimeSeries) -> (AlignedView<'a>, AlignedView<'a>) {
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        let (mut i, mut j) = (0, 0);
+        while i < self.points.len() && j < other.points.len() {
+            let (a, b) = (self.points[i].0, other.points[j].0);
+            if a == b {
+                left.push(i);
+                right.push(j);
+                i += 1;
+                j += 1;
+            } else if a < b {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        (
+            AlignedView { series: self, indices: left },
+            AlignedView { series: other, indices: right },
+        )
+    }
+
+    /// Combines two series point by point over their shared timestamps.
+    pub fn zip_with(&self, other: &TimeSeries, f: impl Fn(f64, f64) -> f64) -> TimeSeries {
+        let (left, right) = self.align_with(other);
+        let points = left
+            .iter()
+            .zip(right.iter())
+            .map(|((ts, a), (_, b))| (ts, f(a, b)))
+            .collect();
+        TimeSeries { points }
+    }
+
+    /// Applies `f` to every value, keeping timestamps.
+    pub fn map(&self, f: impl Fn(f64) -> f64) -> TimeSeries {
+        let points = self.points.iter().map(|(ts, value)| (*ts, f(*value))).collect();
+        TimeSeries { points }
+    }
+}
+
+/// Serializes as an array of `[timestamp, value]` pairs, oldest first.
+impl serde::Serialize for TimeSeries {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.points.serialize(serializer)
+    }
+}
+
+/// Goes through `from_points`, so unordered input is sorted and deduplicated
+/// rather than breaking the ordering invariant.
+impl<'de> serde::Deserialize<'de> for TimeSeries {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let points = Vec::<(u64, f64)>::deserialize(deserializer)?;
+        Ok(TimeSeries::from_points(points))
+    }
+}
+
+/// One side of an aligned pair, see `TimeSeries::align_with`.
+#[derive(Debug, Clone)]
+pub struct AlignedView<'a> {
+    series: &'a TimeSeries,
+    indices: Vec<usize>,
+}
+
+impl AlignedView<'_> {
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The i-th aligned point.
+    pub fn get(&self, i: usize) -> Option<(u64, f64)> {
+        self.indices.get(i).map(|&index| self.series.points[index])
+    }
+
+    /// Index of the i-th aligned point in the underlying series.
+    pub fn source_index(&self, i: usize) -> Option<usize> {
+        self.indices.get(i).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, f64)> + '_ {
+        self.indices.iter().map(|&index| self.series.points[index])
+    }
+
+    pub fn timestamps(&self) -> Vec<u64> {
+        self.iter().map(|(ts, _)| ts).collect()
+    }
+
+    pub fn values(&self) -> Vec<f64> {
+        self.iter().map(|(_, value)| value).collect()
+    }
+
+    pub fn to_series(&self) -> TimeSeries {
+        TimeSeries {
+            points: self.iter().collect(),
+        }
+    }
+}
+
+macro_rules! impl_series_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for &TimeSeries {
+            type Output = TimeSeries;
+
+            /// Applied over the shared timestamps only.
+            fn $method(self, rhs: &TimeSeries) -> TimeSeries {
+                self.zip_with(rhs, |a, b| a $op b)
+            }
+        }
+
+        impl $trait<f64> for &TimeSeries {
+            type Output = TimeSeries;
+
+            fn $method(self, rhs: f64) -> TimeSeries {
+                self.map(|a| a $op rhs)
+            }
+        }
+    };
+}
+
+impl_series_op!(Add, add, +);
+impl_series_op!(Sub, sub, -);
+impl_series_op!(Mul, mul, *);
+impl_series_op!(Div, div, /);
+
+/// The last `capacity` candles of a stream, oldest first: the bridge between
+/// candles arriving one at a time and the indicator functions that take `&[Candle]`.
+///
+/// Closed candles go in with `push`, in-progress ones with `push_update`, which
+/// replaces the last candle while its open time is unchanged:
+///
+/// ```
+/// use cct::indicators::candle::Candle;
+/// use cct::indicators::moving_averages::sma;
+/// use cct::indicators::series::CandleSeries;
+///
+/// let mut series = CandleSeries::new(500);
+/// series.push_update(Candle::new(0, 10.0, 11.0, 9.0, 10.5, 1.0));
+/// series.push(Candle::new(0, 10.0, 12.0, 9.0, 11.0, 2.0)); // closes it
+/// series.push_update(Candle::new(60_000, 11.0, 11.0, 11.0, 11.0, 0.1));
+/// assert_eq!(series.len(), 2);
+/// assert_eq!(sma(series.as_slice(), 2), Some(11.0));
+/// ```
+// Design: the buffer is a Vec allowed to grow to twice the capacity before the
+// evicted front is drained in one go, so the live window stays one contiguous
+// slice (what the indicators take) at amortized O(1) per push.
+#[derive(Debug, Clone)]
+pub struct CandleSeries {
+    buffer: Vec<Candle>,
+    capacity: usize,
+    /// Open time of the newest closed candle
+    last_closed: Option<u64>,
+    /// Symbol and interval `apply` takes candle events from
+    stream: Option<(Symbol, Timeframe)>,
+}
+
+impl CandleSeries {
+    /// Keeps at most `capacity` candles (at least 1).
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self { buffer: Vec::with_capacity(2 * capacity), capacity, last_closed: None, stream: None }
+    }
+
+    /// Makes `apply` take only candle events of `symbol` at `interval`. Without it
+    /// the series binds to the first candle event it is given.
+    pub fn with_stream(mut self, symbol: impl Into<Symbol>, interval: Timeframe) -> Self {
+        self.stream = Some((symbol.into(), interval));
+        self
+    }
+
+    /// The symbol and interval `apply` takes, once set or bound.
+    pub fn stream(&self) -> Option<(&Symbol, Timeframe)> {
+        self.stream.as_ref().map(|(symbol, interval)| (symbol, *interval))
+    }
+
+    /// Feeds a live event: an in-progress candle (`is_closed = false`) replaces the
+    /// last candle while its open time is unchanged, a closed one is pushed. When a
+    /// newer candle arrives while the last one was never closed (a missed closing
+    /// push), the last one is closed as it stands.
+    pub fn apply(&mut self, market_data: &MarketData) -> AppliedUpdate {
+        let Some((symbol, interval, candle, is_closed)) = market_data.as_candle() else {
+            return AppliedUpdate::Ignored;
+        };
+        match &self.stream {
+            Some((stream_symbol, stream_interval)) if *stream_symbol != symbol || *stream_interval != interval => {
+                return AppliedUpdate::Ignored;
+            }
+            Some(_) => {}
+            None => self.stream = Some((Symbol::from(symbol), interval)),
+        }
+
+        let missed_close = !self.is_last_closed()
+            && self.last().is_some_and(|last| last.get_timestamp() < candle.get_timestamp());
+        if missed_close && let Some(last) = self.last().copied() {
+            self.push(last);
+        }
+
+        if is_closed && self.push(*candle) {
+            AppliedUpdate::Closed
+        } else if !is_closed && self.push_update(*candle) {
+            if missed_close { AppliedUpdate::Closed } else { AppliedUpdate::Updated }
+        } else {
+            AppliedUpdate::Rejected
+        }
+    }
+
+    /// Adds a closed candle, evicting the oldest when full. A candle with the open
+    /// time of the in-progress last one replaces it. Returns false (and ignores the
+    /// candle) if it is not newer than the last closed candle, or older than the
+    /// in-progress one.
+    pub fn push(&mut self, candle: Candle) -> bool {
+        let accepted = self.insert(candle);
+        if accepted {
+            self.last_closed = Some(candle.get_timestamp());
+        }
+        accepted
+    }
+
+    /// Adds or updates the in-progress candle: replaces the last candle when the
+    /// open time matches, appends otherwise. Returns false (and ignores the candle)
+    /// if it is not newer than the last closed candle, or older than the last one.
+    pub fn push_update(&mut self, candle: Candle) -> bool {
+        self.insert(candle)
+    }
+
+    fn insert(&mut self, candle: Candle) -> bool {
+        let timestamp = candle.get_timestamp();
+        if self.last_closed.is_some_and(|closed| timestamp <= closed) {
+            return false;
+        }
+        match self.buffer.last_mut() {
+            Some(last) if last.get_timestamp() > timestamp => return false,
+            Some(last) if last.get_timestamp() == timestamp => *last = candle,
+            _ => {
+                if self.buffer.len() == 2 * self.capacity {
+                    self.buffer.drain(..self.capacity);
+                }
+                self.buffer.push(candle);
+            }
+        }
+        true
+    }
+
+    /// The candles held, oldest first.
+    pub fn as_slice(&self) -> &[Candle] {
+        &self.buffer[self.buffer.len().saturating_sub(self.capacity)..]
+    }
+
+    pub fn to_vec(&self) -> Vec<Candle> {
+        self.as_slice().to_vec()
+    }
+
+    /// The newest `n` candles (all of them if there are fewer), oldest first.
+    pub fn window(&self, n: usize) -> &[Candle] {
+        let candles = self.as_slice();
+        &candles[candles.len().saturating_sub(n)..]
+    }
+
+    /// The newest `n` candles merged into one (see `Candle::merge`), e.g. the last
+    /// hour of a 1m series. None while empty.
+    pub fn merge_last(&self, n: usize) -> Option<Candle> {
+        Candle::merge(self.window(n))
+    }
+
+    /// The newest candle, closed or not.
+    pub fn last(&self) -> Option<&Candle> {
+        self.buffer.last()
+    }
+
+    /// Whether the newest candle has been closed with `push`.
+    pub fn is_last_closed(&self) -> bool {
+        self.last().is_some_and(|last| Some(last.get_timestamp()) == self.last_closed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len().min(self.capacity)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Runs of missing candles as `[start, end)`: the open times `start`,
+    /// `start + interval`, ... up to the next candle present at `end`.
+    pub fn find_gaps(&self, timeframe: Timeframe) -> Vec<(u64, u64)> {
+        let step = timeframe.to_millis();
+        self.as_slice()
+            .windows(2)
+            .map(|pair| (pair[0].get_timestamp().saturating_add(step), pair[1].get_timestamp()))
+            .filter(|(expected, next)| expected < next)
+            .collect()
+    }
+
+    /// Finds the gaps (see `find_gaps`) and handles them as `policy` says. Returns
+    /// the gaps found either way. Filling can push the oldest candles out of the
+    /// window.
+    pub fn fill_gaps(&mut self, timeframe: Timeframe, policy: GapPolicy) -> Vec<(u64, u64)> {
+        let gaps = self.find_gaps(timeframe);
+        if policy == GapPolicy::Skip || gaps.is_empty() {
+            return gaps;
+        }
+
+        let step = timeframe.to_millis();
+        let mut filled: Vec<Candle> = Vec::with_capacity(self.len());
+        for candle in self.as_slice() {
+            if let Some(previous) = filled.last().copied() {
+                let close = previous.get_close();
+                let mut timestamp = previous.get_timestamp().saturating_add(step);
+                while timestamp < candle.get_timestamp() {
+                    filled.push(Candle::new(timestamp, close, close, close, close, 0.0));
+                    timestamp = timestamp.saturating_add(step);
+                }
+            }
+            filled.push(*candle);
+        }
+        let keep = filled.len().saturating_sub(self.capacity);
+        self.buffer = filled.split_off(keep);
+        gaps
+    }
+}
+
+/// What `CandleSeries::apply` did with an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppliedUpdate {
+    /// Not a candle, or a candle of another symbol or interval.
+    Ignored,
+    /// Older than the series allows (see `CandleSeries::push`); nothing changed.
+    Rejected,
+    /// The in-progress candle was added or replaced.
+    Updated,
+    /// A candle closed: this one, or the previous one when a newer candle opened
+    /// without its close having been seen.
+    Closed,
+}
+
+impl AppliedUpdate {
+    pub fn closed(self) -> bool {
+        self == AppliedUpdate::Closed
+    }
+}
+
+/// How `CandleSeries::fill_gaps` handles missing candles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapPolicy {
+    /// Insert flat candles at the previous close, with zero volume.
+    FlatFromPrevClose,
+    /// Leave the series as it is; the gaps are only reported.
+    Skip,
+}
+
+/// Why a candle slice is not one candle per interval. See `validate_continuity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContinuityError {
+    /// The candle at `index` is not newer than the one before it.
+    Unordered { index: usize, timestamp: u64 },
+    /// Candles opening at `expected` (and maybe later) are missing; the next one
+    /// present opens at `found`.
+    Gap { expected: u64, found: u64 },
+}
+
+impl fmt::Display for ContinuityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContinuityError::Unordered { index, timestamp } => {
+                write!(f, "candle {} (open time {}) is not after the previous one", index, timestamp)
+            }
+            ContinuityError::Gap { expected, found } => {
+                write!(f, "missing candles from open time {} until {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for ContinuityError {}
+
+/// Splits time-ordered candles into daily sessions starting at
+/// `session_start_utc_hour`:00 UTC (0 for UTC days; 13 for a 13:00 UTC open),
+/// e.g. to reset a session VWAP. Each slice holds one session's candles, by open
+/// time; sessions without candles produce no slice.
+pub fn split_sessions(candles: &[Candle], session_start_utc_hour: u8) -> Vec<&[Candle]> {
+    const DAY_MS: i64 = 86_400_000;
+    let offset = i64::from(session_start_utc_hour % 24) * 3_600_000;
+    let session = |candle: &Candle| (candle.get_timestamp() as i64 - offset).div_euclid(DAY_MS);
+    candles.chunk_by(|a, b| session(a) == session(b)).collect()
+}
+
+/// Checks that `candles` are in time order with none missing, e.g. before handing
+/// them to `rsi_series` or `ema_series`, which assume one candle per interval.
+/// Reports the first problem found.
+pub fn validate_continuity(candles: &[Candle], timeframe: Timeframe) -> Result<(), ContinuityError> {
+    let step = timeframe.to_millis();
+    for (index, pair) in candles.windows(2).enumerate() {
+        let (previous, timestamp) = (pair[0].get_timestamp(), pair[1].get_timestamp());
+        if timestamp <= previous {
+            return Err(ContinuityError::Unordered { index: index + 1, timestamp });
+        }
+        let expected = previous.saturating_add(step);
+        if timestamp > expected {
+            return Err(ContinuityError::Gap { expected, found: timestamp });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(points: &[(u64, f64)]) -> TimeSeries {
+        TimeSeries::from_points(points.iter().copied())
+    }
+
+    #[test]
+    fn test_from_points_sorts_and_dedups() {
+        let s = series(&[(3, 3.0), (1, 1.0), (2, 2.0), (1, 10.0)]);
+        assert_eq!(s.points(), &[(1, 10.0), (2, 2.0), (3, 3.0)]);
+        assert_eq!(s.get(2), Some(2.0));
+        assert_eq!(s.get(4), None);
+    }
+
+    #[test]
+    fn test_from_indicator_right_aligns() {
+        let candles: Vec<Candle> = (0..5)
+            .map(|i| Candle::new(i * 60, 1.0, 1.0, 1.0, 1.0, 1.0))
+            .collect();
+        // An indicator with a 2-bar warm-up
+        let s = TimeSeries::from_indicator(&candles, &[10.0, 11.0, 12.0]);
+        assert_eq!(s.timestamps(), vec![120, 180, 240]);
+        assert_eq!(s.values(), vec![10.0, 11.0, 12.0]);
+
+        // Longer than the candles: leading surplus dropped
+        let s = TimeSeries::from_indicator(&candles[3..], &[1.0, 2.0, 3.0]);
+        assert_eq!(s.points(), &[(180, 2.0), (240, 3.0)]);
+    }
+
+    #[test]
+    fn test_align_with_gaps_on_one_side() {
+        let full = series(&[(1, 1.0), (2, 2.0), (3, 3.0), (4, 4.0), (5, 5.0)]);
+        let gappy = series(&[(2, 20.0), (4, 40.0), (5, 50.0), (6, 60.0)]);
+
+        let (left, right) = full.align_with(&gappy);
+        assert_eq!(left.len(), 3);
+        assert_eq!(left.timestamps(), vec![2, 4, 5]);
+        assert_eq!(right.timestamps(), vec![2, 4, 5]);
+        assert_eq!(left.values(), vec![2.0, 4.0, 5.0]);
+        assert_eq!(right.values(), vec![20.0, 40.0, 50.0]);
+        assert_eq!(left.source_index(1), Some(3));
+        assert_eq!(right.get(2), Some((5, 50.0)));
+    }
+
+    #[test]
+    fn test_shift_and_slice_after() {
+        let s = series(&[(1, 1.0), (2, 2.0), (3, 3.0), (4, 4.0)]);
+        assert_eq!(s.shift(1).points(), &[(2, 1.0), (3, 2.0), (4, 3.0)]);
+        assert_eq!(s.shift(0), s);
+        assert!(s.shift(4).is_empty());
+
+        assert_eq!(s.slice_after(2).timestamps(), vec![3, 4]);
+        assert_eq!(s.slice_after(0).len(), 4);
+        assert!(s.slice_after(4).is_empty());
+    }
+
+    #[test]
+    fn test_arithmetic_on_misaligned_inputs() {
+        // Different warm-ups and a gap in b
+        let a = series(&[(1, 10.0), (2, 20.0), (3, 30.0), (4, 40.0)]);
+        let b = series(&[(2, 2.0), (4, 4.0), (5, 5.0)]);
+
+        assert_eq!((&a - &b).points(), &[(2, 18.0), (4, 36.0)]);
+        assert_eq!((&a + &b).points(), &[(2, 22.0), (4, 44.0)]);
+        assert_eq!((&a * &b).points(), &[(2, 40.0), (4, 160.0)]);
+        assert_eq!((&a / &b).points(), &[(2, 10.0), (4, 10.0)]);
+        assert_eq!((&b * 2.0).values(), vec![4.0, 8.0, 10.0]);
+    }
+
+    #[test]
+    fn test_push_rejects_out_of_order() {
+        let mut s = TimeSeries::new();
+        assert!(s.push(1, 1.0));
+        assert!(s.push(2, 2.0));
+        assert!(!s.push(2, 3.0));
+        assert!(!s.push(1, 3.0));
+        assert_eq!(s.last(), Some((2, 2.0)));
+    }
+
+    fn candle(timestamp: u64, close: f64) -> Candle {
+        Candle::new(timestamp, close, close, close, close, 1.0)
+    }
+
+    fn closes(series: &CandleSeries) -> Vec<f64> {
+        series.as_slice().iter().map(Candle::get_close).collect()
+    }
+
+    #[test]
+    fn test_candle_series_evicts_oldest() {
+        let mut series = CandleSeries::new(3);
+        for i in 0..10 {
+            assert!(series.push(candle(i * 60, i as f64)));
+            assert_eq!(series.len(), (i as usize + 1).min(3));
+        }
+        assert_eq!(closes(&series), vec![7.0, 8.0, 9.0]);
+        assert_eq!(series.window(2).len(), 2);
+        assert_eq!(series.window(2)[0].get_close(), 8.0);
+        assert_eq!(series.window(10).len(), 3);
+        assert_eq!(series.to_vec().len(), 3);
+        assert_eq!(series.last().map(Candle::get_close), Some(9.0));
+    }
+
+    #[test]
+    fn test_candle_series_updates_in_progress_candle() {
+        let mut series = CandleSeries::new(10);
+        assert!(series.push_update(candle(0, 1.0)));
+        assert!(series.push_update(candle(0, 1.5)));
+        assert!(!series.is_last_closed());
+        assert!(series.push(candle(0, 2.0)));
+        assert!(series.is_last_closed());
+        assert_eq!(closes(&series), vec![2.0]);
+
+        // The closed candle can't be reopened or repeated
+        assert!(!series.push_update(candle(0, 3.0)));
+        assert!(!series.push(candle(0, 3.0)));
+        assert!(series.push_update(candle(60, 2.5)));
+        assert!(series.push_update(candle(120, 2.6)));
+        // Older than the last candle
+        assert!(!series.push_update(candle(60, 2.7)));
+        assert_eq!(closes(&series), vec![2.0, 2.5, 2.6]);
+    }
+
+    const MINUTE: u64 = 60_000;
+
+    fn event(symbol: &str, minute: u64, close: f64, is_closed: bool) -> MarketData {
+        MarketData::candle(symbol, Timeframe::M1, candle(minute * MINUTE, close), is_closed)
+    }
+
+    #[test]
+    fn test_apply_routes_and_reports_closes() {
+        let mut series = CandleSeries::new(10).with_stream("BTCUSDT", Timeframe::M1);
+        assert_eq!(series.apply(&event("BTCUSDT", 0, 100.0, false)), AppliedUpdate::Updated);
+        assert_eq!(series.apply(&event("BTCUSDT", 0, 101.0, false)), AppliedUpdate::Updated);
+        assert_eq!(series.apply(&event("ETHUSDT", 0, 5.0, true)), AppliedUpdate::Ignored);
+        let other_interval = MarketData::candle("BTCUSDT", Timeframe::M5, Candle::new(0, 1.0, 1.0, 1.0, 1.0, 1.0), true);
+        assert_eq!(series.apply(&other_interval), AppliedUpdate::Ignored);
+        assert_eq!(series.len(), 1);
+
+        assert!(series.apply(&event("BTCUSDT", 0, 102.0, true)).closed());
+        assert_eq!(series.apply(&event("BTCUSDT", 0, 103.0, false)), AppliedUpdate::Rejected);
+        assert_eq!(series.last().map(Candle::get_close), Some(102.0));
+
+        // Minute 1 never gets its closing push: minute 2 closes it
+        assert_eq!(series.apply(&event("BTCUSDT", 1, 104.0, false)), AppliedUpdate::Updated);
+        assert_eq!(series.apply(&event("BTCUSDT", 2, 105.0, false)), AppliedUpdate::Closed);
+        assert_eq!(series.len(), 3);
+        assert!(!series.is_last_closed());
+    }
+
+    #[test]
+    fn test_apply_binds_to_first_stream() {
+        let mut series = CandleSeries::new(10);
+        assert!(series.stream().is_none());
+        series.apply(&event("ETHUSDT", 0, 5.0, false));
+        assert_eq!(series.stream().map(|(symbol, interval)| (symbol.as_str(), interval)), Some(("ETHUSDT", Timeframe::M1)));
+        assert_eq!(series.apply(&event("BTCUSDT", 0, 100.0, false)), AppliedUpdate::Ignored);
+    }
+
+    /// Minutes 0-1, 4, 5 and 9 of a 1m series: two gaps.
+    fn punctured() -> CandleSeries {
+        let mut series = CandleSeries::new(20);
+        for (i, minute) in [0, 1, 4, 5, 9].into_iter().enumerate() {
+            series.push(candle(minute * MINUTE, i as f64 + 1.0));
+        }
+        series
+    }
+
+    #[test]
+    fn test_find_gaps() {
+        let series = punctured();
+        assert_eq!(series.find_gaps(Timeframe::M1), vec![(2 * MINUTE, 4 * MINUTE), (6 * MINUTE, 9 * MINUTE)]);
+        // No two candles are more than 5 minutes apart
+        assert!(series.find_gaps(Timeframe::M5).is_empty());
+        assert!(CandleSeries::new(5).find_gaps(Timeframe::M1).is_empty());
+    }
+
+    #[test]
+    fn test_fill_gaps_policies() {
+        let mut skipped = punctured();
+        assert_eq!(skipped.fill_gaps(Timeframe::M1, GapPolicy::Skip).len(), 2);
+        assert_eq!(skipped.len(), 5);
+
+        let mut filled = punctured();
+        assert_eq!(filled.fill_gaps(Timeframe::M1, GapPolicy::FlatFromPrevClose).len(), 2);
+        assert_eq!(filled.len(), 10);
+        assert_eq!(closes(&filled), vec![1.0, 2.0, 2.0, 2.0, 3.0, 4.0, 4.0, 4.0, 4.0, 5.0]);
+        assert_eq!(filled.as_slice()[2].get_volume(), 0.0);
+        assert!(filled.find_gaps(Timeframe::M1).is_empty());
+        assert_eq!(validate_continuity(filled.as_slice(), Timeframe::M1), Ok(()));
+        // New candles still go on the end
+        assert!(filled.push(candle(10 * MINUTE, 6.0)));
+
+        // Filling past the capacity drops the oldest
+        let mut small = CandleSeries::new(4);
+        small.push(candle(0, 1.0));
+        small.push(candle(5 * MINUTE, 2.0));
+        small.fill_gaps(Timeframe::M1, GapPolicy::FlatFromPrevClose);
+        let timestamps: Vec<u64> = small.as_slice().iter().map(Candle::get_timestamp).collect();
+        assert_eq!(timestamps, vec![2 * MINUTE, 3 * MINUTE, 4 * MINUTE, 5 * MINUTE]);
+    }
+
+    #[test]
+    fn test_validate_continuity() {
+        let series = punctured();
+        assert_eq!(
+            validate_continuity(series.as_slice(), Timeframe::M1),
+            Err(ContinuityError::Gap { expected: 2 * MINUTE, found: 4 * MINUTE })
+        );
+        let unordered = [candle(0, 1.0), candle(MINUTE, 1.0), candle(MINUTE, 1.0)];
+        assert_eq!(
+            validate_continuity(&unordered, Timeframe::M1),
+            Err(ContinuityError::Unordered { index: 2, timestamp: MINUTE })
+        );
+        assert_eq!(validate_continuity(&[], Timeframe::M1), Ok(()));
+    }
+
+    #[test]
+    fn test_merge_last() {
+        let mut series = CandleSeries::new(10);
+        assert!(series.merge_last(3).is_none());
+        for (i, close) in [10.0, 12.0, 9.0, 11.0].into_iter().enumerate() {
+            series.push(Candle::new(i as u64 * MINUTE, close - 1.0, close + 1.0, close - 2.0, close, 1.0));
+        }
+        let merged = series.merge_last(3).unwrap();
+        assert_eq!(merged.get_timestamp(), MINUTE);
+        assert_eq!((merged.get_open(), merged.get_high(), merged.get_low(), merged.get_close()), (11.0, 13.0, 7.0, 11.0));
+        assert_eq!(merged.get_volume(), 3.0);
+        assert_eq!(series.merge_last(100).unwrap().get_volume(), 4.0);
+        assert!(series.merge_last(0).is_none());
+    }
+
+    #[test]
+    fn test_split_sessions() {
+        const HOUR: u64 = 3_600_000;
+        // 22:00 on day 0 through 02:00 on day 1, hourly
+        let candles: Vec<Candle> = (22..27).map(|hour| candle(hour * HOUR, hour as f64)).collect();
+
+        let utc_days: Vec<usize> = split_sessions(&candles, 0).iter().map(|session| session.len()).collect();
+        assert_eq!(utc_days, vec![2, 3]);
+        // Sessions opening at 23:00: the 23:00 candle starts a new one
+        let sessions = split_sessions(&candles, 23);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1][0].get_timestamp(), 23 * HOUR);
+        // Exactly on the boundary, and the first session before the epoch offset
+        assert_eq!(split_sessions(&candles[..1], 22).len(), 1);
+        assert!(split_sessions(&[], 0).is_empty());
+    }
+}
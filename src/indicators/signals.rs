@@ -0,0 +1,181 @@
+//! Signals that combine price structure with order-flow series
+
+use serde::{Deserialize, Serialize};
+
+use crate::indicators::candle::Candle;
+use crate::indicators::series::TimeSeries;
+use crate::indicators::swings::{SwingConfig, SwingPoint, swing_highs, swing_lows};
+
+/// Direction of a price/delta divergence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DivergenceKind {
+    /// Price made a higher swing high, but buy-sell delta at it was lower
+    Bearish,
+    /// Price made a lower swing low, but buy-sell delta at it was higher
+    Bullish,
+}
+
+/// A divergence between two consecutive swings of the same kind.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DeltaDivergence {
+    pub kind: DivergenceKind,
+    /// Earlier swing; `index` refers to the `candles` slice passed in
+    pub previous: SwingPoint,
+    /// Later swing; `index` refers to the `candles` slice passed in
+    pub current: SwingPoint,
+    pub previous_delta: f64,
+    pub current_delta: f64,
+}
+
+/// Finds price/delta divergences at consecutive swing points, oldest first.
+///
+/// `deltas[i]` is the buy-sell volume delta of candle `i`. If the two series differ
+/// in length they are right-aligned (see `TimeSeries::from_indicator`): the most
+/// recent values correspond and the surplus at the start of the longer one is ignored.
+///
+/// Swings are detected on price with `config`; `config.min_separation` keeps nearby
+/// swings from pairing with each other.
+pub fn delta_divergence(
+    candles: &[Candle],
+    deltas: &[f64],
+    config: &SwingConfig,
+) -> Vec<DeltaDivergence> {
+    delta_divergence_series(candles, &TimeSeries::from_indicator(candles, deltas), config)
+}
+
+/// Like `delta_divergence`, with deltas matched to candles by timestamp.
+/// Swings on candles without a delta are skipped.
+pub fn delta_divergence_series(
+    candles: &[Candle],
+    deltas: &TimeSeries,
+    config: &SwingConfig,
+) -> Vec<DeltaDivergence> {
+    let highs = swing_highs(candles, config);
+    let lows = swing_lows(candles, config);
+
+    let mut divergences = Vec::new();
+    for (previous, current) in paired_swings(&highs, deltas) {
+        // Higher high on weaker buying
+        if current.0.price > previous.0.price && current.1 < previous.1 {
+            divergences.push(divergence(DivergenceKind::Bearish, previous, current));
+        }
+    }
+    for (previous, current) in paired_swings(&lows, deltas) {
+        // Lower low on weaker selling
+        if current.0.price < previous.0.price && current.1 > previous.1 {
+            divergences.push(divergence(DivergenceKind::Bullish, previous, current));
+        }
+    }
+
+    divergences.sort_by_key(|d| d.current.index);
+    divergences
+}
+
+type SwingWithDelta = (SwingPoint, f64);
+
+/// Consecutive swings that both have a delta.
+fn paired_swings(swings: &[SwingPoint], deltas: &TimeSeries) -> Vec<(SwingWithDelta, SwingWithDelta)> {
+    let with_delta: Vec<SwingWithDelta> = swings
+        .iter()
+        .filter_map(|swing| deltas.get(swing.timestamp).map(|delta| (*swing, delta)))
+        .collect();
+    with_delta.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+fn divergence(kind: DivergenceKind, previous: SwingWithDelta, current: SwingWithDelta) -> DeltaDivergence {
+    DeltaDivergence {
+        kind,
+        previous: previous.0,
+        current: current.0,
+        previous_delta: previous.1,
+        current_delta: current.1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two swing highs (index 2 and 6), the second higher in price
+    fn two_swing_highs() -> Vec<Candle> {
+        [
+            (100.0, 98.0),
+            (102.0, 99.0),
+            (105.0, 101.0),
+            (103.0, 100.0),
+            (101.0, 99.0),
+            (104.0, 100.0),
+            (108.0, 103.0),
+            (106.0, 102.0),
+            (104.0, 101.0),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (high, low))| Candle::new(i as u64 * 60_000, *low, *high, *low, *high, 10.0))
+        .collect()
+    }
+
+    #[test]
+    fn test_bearish_delta_divergence() {
+        let candles = two_swing_highs();
+        // Buying at the second high is weaker than at the first
+        let deltas = [5.0, 10.0, 40.0, -5.0, -10.0, 8.0, 15.0, -3.0, -8.0];
+
+        let divergences = delta_divergence(&candles, &deltas, &SwingConfig::new(2, 2));
+        assert_eq!(divergences.len(), 1);
+        let d = divergences[0];
+        assert_eq!(d.kind, DivergenceKind::Bearish);
+        assert_eq!(d.previous.index, 2);
+        assert_eq!(d.current.index, 6);
+        assert_eq!(d.previous_delta, 40.0);
+        assert_eq!(d.current_delta, 15.0);
+    }
+
+    #[test]
+    fn test_no_divergence_when_delta_confirms() {
+        let candles = two_swing_highs();
+        let deltas = [5.0, 10.0, 40.0, -5.0, -10.0, 8.0, 55.0, -3.0, -8.0];
+        assert!(delta_divergence(&candles, &deltas, &SwingConfig::new(2, 2)).is_empty());
+    }
+
+    #[test]
+    fn test_min_separation_suppresses_pairing() {
+        let candles = two_swing_highs();
+        let deltas = [5.0, 10.0, 40.0, -5.0, -10.0, 8.0, 15.0, -3.0, -8.0];
+        let config = SwingConfig::new(2, 2).with_min_separation(5);
+        assert!(delta_divergence(&candles, &deltas, &config).is_empty());
+    }
+
+    #[test]
+    fn test_misaligned_lengths_are_right_aligned() {
+        let mut candles = vec![Candle::new(0, 1.0, 1.0, 1.0, 1.0, 1.0); 3];
+        candles.extend(two_swing_highs());
+        let deltas = [5.0, 10.0, 40.0, -5.0, -10.0, 8.0, 15.0, -3.0, -8.0];
+
+        let divergences = delta_divergence(&candles, &deltas, &SwingConfig::new(2, 2));
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].previous.index, 5);
+        assert_eq!(divergences[0].current.index, 9);
+
+        // Extra leading deltas are ignored the same way
+        let mut long_deltas = vec![999.0; 4];
+        long_deltas.extend(deltas);
+        let divergences = delta_divergence(&two_swing_highs(), &long_deltas, &SwingConfig::new(2, 2));
+        assert_eq!(divergences[0].previous_delta, 40.0);
+    }
+
+    #[test]
+    fn test_deltas_matched_by_timestamp() {
+        let candles = two_swing_highs();
+        // Only the swing candles have a delta; everything else is a gap
+        let deltas = TimeSeries::from_points([(120_000, 40.0), (360_000, 15.0)]);
+        let divergences = delta_divergence_series(&candles, &deltas, &SwingConfig::new(2, 2));
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].current.timestamp, 360_000);
+
+        // A swing without a delta can't pair
+        let deltas = TimeSeries::from_points([(120_000, 40.0)]);
+        assert!(delta_divergence_series(&candles, &deltas, &SwingConfig::new(2, 2)).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//! Swing-point detection (local highs and lows, a.k.a. fractals/pivots)
+
+use serde::{Deserialize, Serialize};
+
+use crate::indicators::candle::Candle;
+
+const DEFAULT_SWING_STRENGTH: usize = 2;
+
+/// How swing points are identified.
+///
+/// A swing high is a candle whose high is strictly above the `left` candles
+/// before it and at least as high as the `right` candles after it (so a flat top
+/// produces one swing, not several). Swing lows mirror this on the lows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwingConfig {
+    pub left: usize,
+    pub right: usize,
+    /// Minimum number of bars between two swings of the same kind; when two are
+    /// closer, only the more extreme one is kept
+    pub min_separation: usize,
+}
+
+impl SwingConfig {
+    pub fn new(left: usize, right: usize) -> Self {
+        Self {
+            left,
+            right,
+            min_separation: 1,
+        }
+    }
+
+    pub fn with_min_separation(mut self, min_separation: usize) -> Self {
+        self.min_separation = min_separation;
+        self
+    }
+}
+
+impl Default for SwingConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_SWING_STRENGTH, DEFAULT_SWING_STRENGTH)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwingKind {
+    High,
+    Low,
+}
+
+/// A confirmed swing point.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SwingPoint {
+    /// Index into the candle slice that was scanned
+    pub index: usize,
+    pub timestamp: u64,
+    /// The candle's high for swing highs, its low for swing lows
+    pub price: f64,
+    pub kind: SwingKind,
+}
+
+/// Finds swing highs, oldest first.
+/// The last `config.right` candles can't be confirmed yet and are never swings.
+pub fn swing_highs(candles: &[Candle], config: &SwingConfig) -> Vec<SwingPoint> {
+    find_swings(candles, config, SwingKind::High)
+}
+
+/// Finds swing lows, oldest first.
+/// The last `config.right` candles can't be confirmed yet and are never swings.
+pub fn swing_lows(candles: &[Candle], config: &SwingConfig) -> Vec<SwingPoint> {
+    find_swings(candles, config, SwingKind::Low)
+}
+
+fn find_swings(candles: &[Candle], config: &SwingConfig, kind: SwingKind) -> Vec<SwingPoint> {
+    let price = |candle: &Candle| match kind {
+        SwingKind::High => candle.get_high(),
+        SwingKind::Low => candle.get_low(),
+    };
+    // "a beats b" = a is more extreme in the swing's direction
+    let beats = |a: f64, b: f64| match kind {
+        SwingKind::High => a > b,
+        SwingKind::Low => a < b,
+    };
+
+    if candles.len() < config.left + config.right + 1 {
+        return Vec::new();
+    }
+
+    let mut swings: Vec<SwingPoint> = Vec::new();
+    for i in config.left..candles.len() - config.right {
+        let pivot = price(&candles[i]);
+        let left_ok = candles[i - config.left..i].iter().all(|c| beats(pivot, price(c)));
+        let right_ok = candles[i + 1..=i + config.right]
+            .iter()
+            .all(|c| !beats(price(c), pivot));
+        if !(left_ok && right_ok) {
+            continue;
+        }
+
+        let point = SwingPoint {
+            index: i,
+            timestamp: candles[i].get_timestamp(),
+            price: pivot,
+            kind,
+        };
+        match swings.last_mut() {
+            Some(last) if i - last.index < config.min_separation => {
+                if beats(pivot, last.price) {
+                    *last = point;
+                }
+            }
+            _ => swings.push(point),
+        }
+    }
+
+    swings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candles_from_highs_lows(bars: &[(f64, f64)]) -> Vec<Candle> {
+        bars.iter()
+            .enumerate()
+            .map(|(i, (high, low))| Candle::new(i as u64 * 60_000, *low, *high, *low, *high, 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_swing_highs_and_lows() {
+        let candles = candles_from_highs_lows(&[
+            (10.0, 8.0),
+            (12.0, 9.0),
+            (15.0, 11.0), // swing high
+            (13.0, 10.0),
+            (11.0, 7.0), // swing low
+            (12.0, 8.0),
+            (14.0, 9.0),
+        ]);
+        let config = SwingConfig::new(2, 2);
+
+        let highs = swing_highs(&candles, &config);
+        assert_eq!(highs.len(), 1);
+        assert_eq!(highs[0].index, 2);
+        assert_eq!(highs[0].price, 15.0);
+        assert_eq!(highs[0].timestamp, 120_000);
+        assert_eq!(highs[0].kind, SwingKind::High);
+
+        let lows = swing_lows(&candles, &config);
+        assert_eq!(lows.len(), 1);
+        assert_eq!(lows[0].index, 4);
+        assert_eq!(lows[0].price, 7.0);
+    }
+
+    #[test]
+    fn test_flat_top_is_one_swing() {
+        let candles = candles_from_highs_lows(&[
+            (10.0, 9.0),
+            (12.0, 9.0),
+            (12.0, 9.0),
+            (10.0, 9.0),
+            (9.0, 8.0),
+        ]);
+        let highs = swing_highs(&candles, &SwingConfig::new(1, 1));
+        assert_eq!(highs.iter().map(|s| s.index).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_min_separation_keeps_more_extreme() {
+        let candles = candles_from_highs_lows(&[
+            (10.0, 9.0),
+            (12.0, 9.0), // swing high
+            (11.0, 9.0),
+            (13.0, 9.0), // swing high, 2 bars later and higher
+            (10.0, 9.0),
+        ]);
+        let config = SwingConfig::new(1, 1);
+        assert_eq!(swing_highs(&candles, &config).len(), 2);
+
+        let highs = swing_highs(&candles, &config.with_min_separation(3));
+        assert_eq!(highs.len(), 1);
+        assert_eq!(highs[0].index, 3);
+    }
+
+    #[test]
+    fn test_not_enough_candles() {
+        let candles = candles_from_highs_lows(&[(10.0, 9.0), (12.0, 9.0)]);
+        assert!(swing_highs(&candles, &SwingConfig::default()).is_empty());
+    }
+}
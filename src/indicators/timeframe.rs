@@ -1,54 +1,368 @@
+pub mod session;
+
+use std::borrow::Cow;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+use crate::indicators::candle::Candle;
+use crate::market::timestamp::{civil_from_days, days_from_civil};
+
 /// Represents the timeframe/interval of candlestick data
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// Timeframes compare, order and hash by duration, so a 60-second `Custom` equals `M1`. `parse`
+/// and `from_seconds` return the named variant whenever one fits; `match` arms on
+/// named variants don't see a `Custom` of the same length.
+#[derive(Debug, Clone, Copy)]
 pub enum Timeframe {
     M1,   // 1 minute
+    M3,   // 3 minutes
     M5,   // 5 minutes
     M15,  // 15 minutes
     M30,  // 30 minutes
     H1,   // 1 hour
+    H2,   // 2 hours
     H4,   // 4 hours
+    H6,   // 6 hours
+    H8,   // 8 hours
+    H12,  // 12 hours
     D1,   // 1 day
+    D3,   // 3 days
     W1,   // 1 week
+    MN1,  // 1 month (nominal 30 days, see to_seconds)
+    /// Any other length, in seconds, e.g. 2m or 10m candles resampled locally.
+    /// `from_seconds` builds one (or the named variant of that length).
+    Custom(NonZeroU64),
 }
 
+/// The named timeframes, shortest first.
+const NAMED: [Timeframe; 15] = [
+    Timeframe::M1,
+    Timeframe::M3,
+    Timeframe::M5,
+    Timeframe::M15,
+    Timeframe::M30,
+    Timeframe::H1,
+    Timeframe::H2,
+    Timeframe::H4,
+    Timeframe::H6,
+    Timeframe::H8,
+    Timeframe::H12,
+    Timeframe::D1,
+    Timeframe::D3,
+    Timeframe::W1,
+    Timeframe::MN1,
+];
+
+const DAY_MS: u64 = 86_400_000;
+const WEEK_MS: u64 = 7 * DAY_MS;
+// The epoch was a Thursday; weeks open on Monday 00:00 UTC, 1970-01-05 being the first
+const FIRST_MONDAY_MS: u64 = 4 * DAY_MS;
+
+/// Units `as_str` and `parse` use for custom timeframes, longest first.
+const CUSTOM_UNITS: [(char, u64); 5] = [('w', 7 * 86_400), ('d', 86_400), ('h', 3600), ('m', 60), ('s', 1)];
+
 impl Timeframe {
     /// Returns the duration of this timeframe in seconds
+    ///
+    /// Months are nominal: `MN1` counts 30 days, while exchange monthly candles open
+    /// on the 1st of each calendar month and last 28 to 31 days.
     pub fn to_seconds(&self) -> u64 {
         match self {
             Timeframe::M1 => 60,
+            Timeframe::M3 => 3 * 60,
             Timeframe::M5 => 5 * 60,
             Timeframe::M15 => 15 * 60,
             Timeframe::M30 => 30 * 60,
             Timeframe::H1 => 60 * 60,
+            Timeframe::H2 => 2 * 60 * 60,
             Timeframe::H4 => 4 * 60 * 60,
+            Timeframe::H6 => 6 * 60 * 60,
+            Timeframe::H8 => 8 * 60 * 60,
+            Timeframe::H12 => 12 * 60 * 60,
             Timeframe::D1 => 24 * 60 * 60,
+            Timeframe::D3 => 3 * 24 * 60 * 60,
             Timeframe::W1 => 7 * 24 * 60 * 60,
+            Timeframe::MN1 => 30 * 24 * 60 * 60,
+            Timeframe::Custom(seconds) => seconds.get(),
         }
     }
 
+    /// Returns the duration of this timeframe in milliseconds, saturating at `u64::MAX`
+    /// for custom lengths too long to express (MN1 is nominal, as in `to_seconds`).
+    pub fn to_millis(&self) -> u64 {
+        self.to_seconds().saturating_mul(1000)
+    }
+
+    /// Open time of the candle containing `timestamp_ms`.
+    ///
+    /// Intervals up to a day (and 3d and custom ones) are counted from the epoch, so
+    /// days open at 00:00 UTC and 4h candles at 00:00, 04:00, ... Weeks open on
+    /// Monday 00:00 UTC and months on the 1st at 00:00 UTC, as on the exchanges,
+    /// rather than at a multiple of their length since the epoch.
+    pub fn align(&self, timestamp_ms: u64) -> u64 {
+        match self {
+            Timeframe::W1 => timestamp_ms.saturating_sub((timestamp_ms + WEEK_MS - FIRST_MONDAY_MS) % WEEK_MS),
+            Timeframe::MN1 => {
+                let (year, month, _) = civil_from_days((timestamp_ms / DAY_MS) as i64);
+                days_from_civil(year, month, 1) as u64 * DAY_MS
+            }
+            _ => timestamp_ms - timestamp_ms % self.to_millis(),
+        }
+    }
+
+    /// Open time of the candle after the one containing `timestamp_ms`. For a
+    /// timestamp on a boundary that is the next boundary, not the timestamp itself.
+    pub fn next_boundary(&self, timestamp_ms: u64) -> u64 {
+        let start = self.align(timestamp_ms);
+        match self {
+            Timeframe::MN1 => {
+                let (year, month, _) = civil_from_days((start / DAY_MS) as i64);
+                let (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+                days_from_civil(year, month, 1) as u64 * DAY_MS
+            }
+            _ => start.saturating_add(self.to_millis()),
+        }
+    }
+
+    /// Close time of the candle opening at `open_ms`, the way exchanges report it:
+    /// the last millisecond before the next candle, i.e. open + duration - 1ms. For
+    /// `MN1` that is the end of the calendar month.
+    pub fn close_time(&self, open_ms: u64) -> u64 {
+        let next = match self {
+            Timeframe::MN1 => self.next_boundary(open_ms),
+            _ => open_ms.saturating_add(self.to_millis()),
+        };
+        next - 1
+    }
+
+    /// Whether `timestamp_ms` is a candle open time.
+    pub fn is_aligned(&self, timestamp_ms: u64) -> bool {
+        self.align(timestamp_ms) == timestamp_ms
+    }
+
+    /// Open times of the candles overlapping `[start_ms, end_ms)`, oldest first: from
+    /// the one containing `start_ms` up to the last one opening before `end_ms`.
+    pub fn buckets_between(&self, start_ms: u64, end_ms: u64) -> impl Iterator<Item = u64> + use<> {
+        self.iter_range(start_ms, end_ms)
+    }
+
+    /// Lazy iterator over the candle open times in `[align(start_ms), end_ms)`, from
+    /// either end, e.g. to page through a REST kline backfill or spot gaps. Empty
+    /// when `end_ms <= start_ms`.
+    pub fn iter_range(&self, start_ms: u64, end_ms: u64) -> CandleOpens {
+        let range = (start_ms < end_ms).then(|| (self.align(start_ms), self.align(end_ms - 1)));
+        CandleOpens { timeframe: *self, range }
+    }
+
+    /// Number of candles `iter_range(start_ms, end_ms)` yields, without walking them.
+    pub fn count_in_range(&self, start_ms: u64, end_ms: u64) -> u64 {
+        self.iter_range(start_ms, end_ms).remaining()
+    }
+
+    /// Number of whole candles inside `[start_ms, end_ms)`: ones opening at or after
+    /// `start_ms` and closing before `end_ms`. Partial candles at either end don't count.
+    pub fn candles_between(&self, start_ms: u64, end_ms: u64) -> u64 {
+        let first = if self.is_aligned(start_ms) { start_ms } else { self.next_boundary(start_ms) };
+        self.count_in_range(first, self.align(end_ms))
+    }
+
+    /// Open time of the candle `n` whole candles before `end_ms`, so that
+    /// `candles_between(lookback_start(end_ms, n), end_ms)` is `n`, e.g. the `startTime`
+    /// of a REST kline request. `n = 0` gives `align(end_ms)`. Stops at the epoch.
+    pub fn lookback_start(&self, end_ms: u64, n: u64) -> u64 {
+        let end = self.align(end_ms);
+        match self {
+            Timeframe::MN1 => {
+                let (year, month, _) = civil_from_days((end / DAY_MS) as i64);
+                let months = (year * 12 + i64::from(month) - 1).saturating_sub(i64::try_from(n).unwrap_or(i64::MAX));
+                let days = days_from_civil(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, 1);
+                u64::try_from(days).map_or(0, |days| days * DAY_MS)
+            }
+            _ => end.saturating_sub(n.saturating_mul(self.to_millis())),
+        }
+    }
+
+    /// How far back to backfill, in ms, so an indicator over `period` candles has
+    /// settled: `period * warmup_multiplier` candles (a multiplier of 3 is a common
+    /// choice for EMA and RSI; 0 counts as 1). Use `lookback_start(end, period *
+    /// warmup_multiplier)` for an aligned start time.
+    pub fn required_history(&self, period: usize, warmup_multiplier: usize) -> u64 {
+        let candles = period.saturating_mul(warmup_multiplier.max(1)) as u64;
+        candles.saturating_mul(self.to_millis())
+    }
+
+    /// Every named timeframe, shortest first (no `Custom`), e.g. for an interval picker.
+    pub fn all() -> &'static [Timeframe] {
+        &NAMED
+    }
+
+    /// Whether `self` is a whole number of `other` candles: H1 is a multiple of M15,
+    /// H4 is not a multiple of D1.
+    pub fn is_multiple_of(&self, other: Timeframe) -> bool {
+        self.to_seconds().is_multiple_of(other.to_seconds())
+    }
+
+    /// How many `other` candles make one `self` candle: `H1.ratio(M15)` is 4. None
+    /// unless `self` is a multiple of `other`.
+    pub fn ratio(&self, other: Timeframe) -> Option<u64> {
+        self.is_multiple_of(other).then(|| self.to_seconds() / other.to_seconds())
+    }
+
+    /// How many `self` candles fit in one `larger` candle: `M15.candles_per(H1)` is 4.
+    /// The reverse of `ratio`.
+    pub fn candles_per(&self, larger: Timeframe) -> Option<u64> {
+        larger.ratio(*self)
+    }
+
+    /// The timeframe lasting `seconds`: a named one if it exists, `Custom` otherwise.
+    /// None for 0.
+    pub fn from_seconds(seconds: u64) -> Option<Self> {
+        let custom = Timeframe::Custom(NonZeroU64::new(seconds)?);
+        let named = NAMED.into_iter().find(|timeframe| timeframe.to_seconds() == seconds);
+        Some(named.unwrap_or(custom))
+    }
+
+    /// The timeframe lasting exactly `duration`, as `from_seconds`. None for 0 or a
+    /// duration with a fraction of a second; nothing is rounded.
+    pub fn try_from_duration(duration: Duration) -> Option<Self> {
+        if duration.subsec_nanos() != 0 {
+            return None;
+        }
+        Self::from_seconds(duration.as_secs())
+    }
+
+    /// The length as a chrono `TimeDelta` (`MN1` is 30 days, see `to_seconds`).
+    #[cfg(feature = "chrono")]
+    pub fn to_chrono_duration(&self) -> chrono::TimeDelta {
+        i64::try_from(self.to_seconds())
+            .ok()
+            .and_then(chrono::TimeDelta::try_seconds)
+            .unwrap_or(chrono::TimeDelta::MAX)
+    }
+
     /// Returns the duration of this timeframe in minutes
     pub fn to_minutes(&self) -> u64 {
         self.to_seconds() / 60
     }
 
-    /// Returns a human-readable string representation
-    pub fn as_str(&self) -> &'static str {
-        match self {
+    /// Returns a human-readable string representation. Custom timeframes use the
+    /// largest unit that divides them evenly: "2m", "90s", "36h".
+    pub fn as_str(&self) -> Cow<'static, str> {
+        let name = match self {
             Timeframe::M1 => "1m",
+            Timeframe::M3 => "3m",
             Timeframe::M5 => "5m",
             Timeframe::M15 => "15m",
             Timeframe::M30 => "30m",
             Timeframe::H1 => "1h",
+            Timeframe::H2 => "2h",
             Timeframe::H4 => "4h",
+            Timeframe::H6 => "6h",
+            Timeframe::H8 => "8h",
+            Timeframe::H12 => "12h",
             Timeframe::D1 => "1d",
+            Timeframe::D3 => "3d",
             Timeframe::W1 => "1w",
-        }
+            Timeframe::MN1 => "1M",
+            Timeframe::Custom(seconds) => {
+                let (unit, length) = CUSTOM_UNITS
+                    .into_iter()
+                    .find(|(_, length)| seconds.get().is_multiple_of(*length))
+                    .unwrap_or(('s', 1));
+                return Cow::Owned(format!("{}{}", seconds.get() / length, unit));
+            }
+        };
+        Cow::Borrowed(name)
     }
 
-    /// Parses a timeframe string like "1m", "5m", "1h".
+    /// Parses a timeframe string like "1m", "5m", "1h", "1M" (one month) or a custom
+    /// length such as "10m" or "90s".
+    #[allow(clippy::should_implement_trait)] // FromStr is implemented too; this is the Option shorthand
     pub fn from_str(value: &str) -> Option<Self> {
         value.parse().ok()
     }
+
+    /// Lenient parsing for config files and command-line arguments: "1min", "60",
+    /// "1H", "4 hours", "daily". `FromStr` and `from_str` stay strict.
+    ///
+    /// In order of precedence:
+    /// - the canonical forms, so "1m" is a minute and "1M" a month;
+    /// - "hourly", "daily", "weekly" and "monthly";
+    /// - a count followed by a unit, with optional space between: s/sec/second,
+    ///   m/min/minute, h/hr/hour, d/day, w/wk/week (plurals too, any case), plus
+    ///   mo/month and a capital M for exactly one month. The count defaults to 1 ("H", "day");
+    /// - a bare number of minutes ("240" is 4h), which must be at least 2: a lone "1"
+    ///   is a minute on some exchanges and a day on others, so it is rejected rather than guessed.
+    pub fn parse_flexible(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Ok(timeframe) = value.parse() {
+            return Some(timeframe);
+        }
+        match value.to_ascii_lowercase().as_str() {
+            "hourly" => return Some(Timeframe::H1),
+            "daily" => return Some(Timeframe::D1),
+            "weekly" => return Some(Timeframe::W1),
+            "monthly" => return Some(Timeframe::MN1),
+            _ => {}
+        }
+
+        let digits = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+        let (count, unit) = (&value[..digits], value[digits..].trim_start());
+        if unit.is_empty() {
+            let minutes: u64 = count.parse().ok()?;
+            return if minutes < 2 { None } else { Self::from_seconds(minutes.checked_mul(60)?) };
+        }
+        let count: u64 = if count.is_empty() { 1 } else { count.parse().ok()? };
+        // Months have no fixed length, so only a single one is a timeframe
+        if unit == "M" {
+            return (count == 1).then_some(Timeframe::MN1);
+        }
+
+        let length = match unit.to_ascii_lowercase().as_str() {
+            "mo" | "mon" | "month" | "months" => return (count == 1).then_some(Timeframe::MN1),
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "d" | "day" | "days" => 86_400,
+            "w" | "wk" | "wks" | "week" | "weeks" => 7 * 86_400,
+            _ => return None,
+        };
+        count.checked_mul(length).and_then(Self::from_seconds)
+    }
+}
+
+impl PartialEq for Timeframe {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_seconds() == other.to_seconds()
+    }
+}
+
+impl Eq for Timeframe {}
+
+impl PartialOrd for Timeframe {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timeframe {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.to_seconds().cmp(&other.to_seconds())
+    }
+}
+
+impl Hash for Timeframe {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_seconds().hash(state);
+    }
+}
+
+/// `to_seconds` as a `Duration`, e.g. for `tokio::time::sleep`.
+impl From<Timeframe> for Duration {
+    fn from(timeframe: Timeframe) -> Self {
+        Duration::from_secs(timeframe.to_seconds())
+    }
 }
 
 impl std::fmt::Display for Timeframe {
@@ -57,20 +371,568 @@ impl std::fmt::Display for Timeframe {
     }
 }
 
+/// Serializes as `as_str()`, e.g. "1m".
+impl serde::Serialize for Timeframe {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.as_str())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Timeframe {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("unknown timeframe {:?}", value)))
+    }
+}
+
 impl std::str::FromStr for Timeframe {
     type Err = ();
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         match value {
             "1m" => Ok(Timeframe::M1),
+            "3m" => Ok(Timeframe::M3),
             "5m" => Ok(Timeframe::M5),
             "15m" => Ok(Timeframe::M15),
             "30m" => Ok(Timeframe::M30),
             "1h" => Ok(Timeframe::H1),
+            "2h" => Ok(Timeframe::H2),
             "4h" => Ok(Timeframe::H4),
+            "6h" => Ok(Timeframe::H6),
+            "8h" => Ok(Timeframe::H8),
+            "12h" => Ok(Timeframe::H12),
             "1d" => Ok(Timeframe::D1),
+            "3d" => Ok(Timeframe::D3),
             "1w" => Ok(Timeframe::W1),
-            _ => Err(()),
+            // Case matters: "1M" is a month, "1m" a minute
+            "1M" => Ok(Timeframe::MN1),
+            _ => {
+                let unit = value.chars().last().ok_or(())?;
+                let (_, length) = CUSTOM_UNITS.into_iter().find(|(name, _)| *name == unit).ok_or(())?;
+                let count: u64 = value[..value.len() - unit.len_utf8()].parse().map_err(|_| ())?;
+                count.checked_mul(length).and_then(Timeframe::from_seconds).ok_or(())
+            }
+        }
+    }
+}
+
+/// Candle open times over a range, from `Timeframe::iter_range`.
+#[derive(Debug, Clone)]
+pub struct CandleOpens {
+    timeframe: Timeframe,
+    /// First and last open time still to yield, both aligned; None once exhausted
+    range: Option<(u64, u64)>,
+}
+
+impl CandleOpens {
+    fn remaining(&self) -> u64 {
+        let Some((first, last)) = self.range else {
+            return 0;
+        };
+        match self.timeframe {
+            Timeframe::MN1 => {
+                let month = |open: u64| {
+                    let (year, month, _) = civil_from_days((open / DAY_MS) as i64);
+                    year * 12 + month as i64
+                };
+                (month(last) - month(first)) as u64 + 1
+            }
+            timeframe => (last - first) / timeframe.to_millis() + 1,
+        }
+    }
+}
+
+impl Iterator for CandleOpens {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let (first, last) = self.range?;
+        self.range = (first < last).then(|| (self.timeframe.next_boundary(first), last));
+        Some(first)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.remaining()) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None),
         }
     }
 }
+
+impl DoubleEndedIterator for CandleOpens {
+    fn next_back(&mut self) -> Option<u64> {
+        let (first, last) = self.range?;
+        // Buckets are contiguous: the one before `last` holds the millisecond before it
+        self.range = (first < last).then(|| (first, self.timeframe.align(last - 1)));
+        Some(last)
+    }
+}
+
+impl std::iter::FusedIterator for CandleOpens {}
+
+/// `to` can't be built from whole `from` candles. See `resample`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResampleError {
+    pub from: Timeframe,
+    pub to: Timeframe,
+}
+
+impl std::fmt::Display for ResampleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot resample {} candles to {}: not a whole multiple", self.from, self.to)
+    }
+}
+
+impl std::error::Error for ResampleError {}
+
+/// Merges `from` candles into `to` candles aligned to `to` (timestamp = bucket
+/// start, see `Timeframe::align`): open of the first, close of the last, highest high, lowest low, summed
+/// volume. Missing candles are skipped over; a bucket is merged from whatever it
+/// has. The last bucket is left out unless its final candle arrived, so the output
+/// only holds finished candles. Candles are expected in time order.
+pub fn resample(candles: &[Candle], from: Timeframe, to: Timeframe) -> Result<Vec<Candle>, ResampleError> {
+    let mut resampler = Resampler::new(from, to)?;
+    let mut resampled: Vec<Candle> = candles.iter().filter_map(|candle| resampler.push(*candle)).collect();
+    if resampler.held.is_some_and(|bucket| resampler.is_complete(&bucket)) {
+        resampled.extend(resampler.flush());
+    }
+    Ok(resampled)
+}
+
+/// The higher-timeframe candle being merged.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    start: u64,
+    candle: Candle,
+    /// Open time of the newest candle merged in
+    last: u64,
+}
+
+/// `resample` one candle at a time, e.g. closed 1m candles from a stream into 5m.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    from: Timeframe,
+    to: Timeframe,
+    held: Option<Bucket>,
+}
+
+impl Resampler {
+    /// Fails unless `to` is a whole multiple of `from`.
+    pub fn new(from: Timeframe, to: Timeframe) -> Result<Self, ResampleError> {
+        if !to.is_multiple_of(from) {
+            return Err(ResampleError { from, to });
+        }
+        Ok(Self { from, to, held: None })
+    }
+
+    /// Adds a closed `from` candle. Returns the merged candle as soon as the last
+    /// candle of its bucket arrives, or, if that one is missing, when a candle of a
+    /// later bucket does. Candles older than the open bucket are ignored.
+    pub fn push(&mut self, candle: Candle) -> Option<Candle> {
+        let timestamp = candle.get_timestamp();
+        let start = self.to.align(timestamp);
+
+        match self.held {
+            Some(bucket) if start < bucket.start || (start == bucket.start && timestamp <= bucket.last) => return None,
+            Some(mut bucket) if start == bucket.start => {
+                let merged = bucket.candle;
+                let (high, low) = (merged.get_high().max(candle.get_high()), merged.get_low().min(candle.get_low()));
+                let volume = merged.get_volume() + candle.get_volume();
+                bucket.candle = Candle::new(start, merged.get_open(), high, low, candle.get_close(), volume);
+                bucket.last = timestamp;
+                self.held = Some(bucket);
+            }
+            previous => {
+                let (open, high, low, close) = (candle.get_open(), candle.get_high(), candle.get_low(), candle.get_close());
+                let opened = Candle::new(start, open, high, low, close, candle.get_volume());
+                self.held = Some(Bucket { start, candle: opened, last: timestamp });
+                // A new bucket that is already complete waits behind the unfinished previous one
+                if let Some(previous) = previous {
+                    return Some(previous.candle);
+                }
+            }
+        }
+        if self.held.is_some_and(|bucket| self.is_complete(&bucket)) { self.flush() } else { None }
+    }
+
+    /// The bucket being merged, not yet returned.
+    pub fn current(&self) -> Option<Candle> {
+        self.held.map(|bucket| bucket.candle)
+    }
+
+    /// Returns the held bucket as it is, e.g. at the end of a replay.
+    pub fn flush(&mut self) -> Option<Candle> {
+        self.held.take().map(|bucket| bucket.candle)
+    }
+
+    /// Whether the bucket's last `from` candle has been merged.
+    fn is_complete(&self, bucket: &Bucket) -> bool {
+        self.from.next_boundary(bucket.last) >= self.to.next_boundary(bucket.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    fn minute(i: u64, open: f64, close: f64) -> Candle {
+        Candle::new(i * MINUTE, open, open.max(close) + 1.0, open.min(close) - 1.0, close, 1.0)
+    }
+
+    fn ohlcv(candle: &Candle) -> (u64, f64, f64, f64, f64, f64) {
+        let c = candle;
+        (c.get_timestamp(), c.get_open(), c.get_high(), c.get_low(), c.get_close(), c.get_volume())
+    }
+
+    fn custom(seconds: u64) -> Timeframe {
+        Timeframe::Custom(NonZeroU64::new(seconds).unwrap())
+    }
+
+    #[test]
+    fn test_exchange_intervals_parse_and_print() {
+        let cases = [
+            (Timeframe::M3, "3m", 3 * 60),
+            (Timeframe::H2, "2h", 2 * 3600),
+            (Timeframe::H6, "6h", 6 * 3600),
+            (Timeframe::H8, "8h", 8 * 3600),
+            (Timeframe::H12, "12h", 12 * 3600),
+            (Timeframe::D3, "3d", 3 * 86_400),
+            (Timeframe::MN1, "1M", 30 * 86_400),
+        ];
+        for (timeframe, name, seconds) in cases {
+            assert_eq!(timeframe.as_str(), name);
+            assert_eq!(name.parse::<Timeframe>(), Ok(timeframe));
+            assert_eq!(timeframe.to_seconds(), seconds);
+        }
+        // "1M" is the month, "1m" still the minute
+        assert_eq!(Timeframe::from_str("1m"), Some(Timeframe::M1));
+        assert_eq!(Timeframe::MN1.to_minutes(), 43_200);
+        assert!(Timeframe::from_str("1mo").is_none());
+    }
+
+    #[test]
+    fn test_custom_timeframes() {
+        let two_minutes: Timeframe = "2m".parse().unwrap();
+        assert_eq!(two_minutes, custom(120));
+        assert_eq!(two_minutes.to_minutes(), 2);
+        assert_eq!(two_minutes.as_str(), "2m");
+        assert_eq!(custom(90).as_str(), "90s");
+        assert_eq!(custom(36 * 3600).to_string(), "36h");
+        assert_eq!(Timeframe::from_str("10m").map(|tf| tf.to_seconds()), Some(600));
+
+        // Lengths with a name come back as the named variant
+        assert!(matches!(Timeframe::from_str("60m"), Some(Timeframe::H1)));
+        assert!(matches!(Timeframe::from_seconds(7 * 86_400), Some(Timeframe::W1)));
+        assert_eq!(custom(60), Timeframe::M1);
+        let mut seen = std::collections::HashSet::new();
+        seen.insert(Timeframe::M1);
+        assert!(!seen.insert(custom(60)));
+
+        for invalid in ["0m", "m", "", "5x", "-5m", "1.5h"] {
+            assert!(Timeframe::from_str(invalid).is_none(), "{invalid}");
+        }
+        assert!(Timeframe::from_seconds(0).is_none());
+    }
+
+    #[test]
+    fn test_parse_flexible() {
+        let cases: [(&str, Option<Timeframe>); 34] = [
+            // Canonical forms first: case decides minute or month
+            ("1m", Some(Timeframe::M1)),
+            ("1M", Some(Timeframe::MN1)),
+            ("12h", Some(Timeframe::H12)),
+            ("  3d ", Some(Timeframe::D3)),
+            ("1min", Some(Timeframe::M1)),
+            ("5 mins", Some(Timeframe::M5)),
+            ("15Min", Some(Timeframe::M15)),
+            ("1H", Some(Timeframe::H1)),
+            ("4 hours", Some(Timeframe::H4)),
+            ("2HR", Some(Timeframe::H2)),
+            ("1D", Some(Timeframe::D1)),
+            ("1 day", Some(Timeframe::D1)),
+            ("7 days", Some(Timeframe::W1)),
+            ("1W", Some(Timeframe::W1)),
+            ("2 weeks", Some(custom(14 * 86_400))),
+            ("1 month", Some(Timeframe::MN1)),
+            ("M", Some(Timeframe::MN1)),
+            ("hour", Some(Timeframe::H1)),
+            ("Daily", Some(Timeframe::D1)),
+            ("WEEKLY", Some(Timeframe::W1)),
+            ("monthly", Some(Timeframe::MN1)),
+            ("90 seconds", Some(custom(90))),
+            ("60", Some(Timeframe::H1)),
+            ("240", Some(Timeframe::H4)),
+            ("10", Some(custom(600))),
+            // Ambiguous or malformed
+            ("1", None),
+            ("0", None),
+            ("0m", None),
+            ("3 months", None),
+            ("1.5h", None),
+            ("-5m", None),
+            ("5 fortnights", None),
+            ("", None),
+            ("99999999999999999999m", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Timeframe::parse_flexible(input), expected, "{:?}", input);
+        }
+        // FromStr is unchanged
+        assert_eq!(Timeframe::from_str("1H"), None);
+        assert_eq!(Timeframe::from_str("daily"), None);
+    }
+
+    #[test]
+    fn test_duration_round_trips() {
+        for &timeframe in Timeframe::all() {
+            assert_eq!(Timeframe::try_from_duration(Duration::from(timeframe)), Some(timeframe));
+        }
+        assert_eq!(Duration::from(Timeframe::H4), Duration::from_secs(4 * 3600));
+        assert_eq!(Timeframe::try_from_duration(Duration::from_secs(600)), Some(custom(600)));
+        assert_eq!(Timeframe::try_from_duration(Duration::from_millis(60_500)), None);
+        assert_eq!(Timeframe::try_from_duration(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_close_time() {
+        // The kline example in Binance's API docs: "t": 1672515780000, "T": 1672515839999
+        assert_eq!(Timeframe::M1.close_time(1_672_515_780_000), 1_672_515_839_999);
+        // February 2024 has 29 days
+        let day = 86_400_000;
+        assert_eq!(Timeframe::MN1.close_time(19_754 * day), 19_783 * day - 1);
+    }
+
+    #[test]
+    fn test_huge_custom_saturates() {
+        let huge = Timeframe::Custom(NonZeroU64::MAX);
+        let now = 1_710_417_600_000;
+        assert_eq!(huge.to_millis(), u64::MAX);
+        assert_eq!(huge.align(now), 0);
+        assert_eq!(huge.next_boundary(now), u64::MAX);
+        assert_eq!(huge.close_time(0), u64::MAX - 1);
+        assert_eq!(huge.lookback_start(now, 3), 0);
+        assert_eq!(huge.required_history(14, 3), u64::MAX);
+        assert_eq!(huge.iter_range(0, now).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(huge.candles_between(0, now), 0);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_duration() {
+        assert_eq!(Timeframe::H1.to_chrono_duration(), chrono::TimeDelta::hours(1));
+        assert_eq!(custom(90).to_chrono_duration(), chrono::TimeDelta::seconds(90));
+        assert_eq!(Timeframe::Custom(NonZeroU64::MAX).to_chrono_duration(), chrono::TimeDelta::MAX);
+    }
+
+    #[test]
+    fn test_ordering_and_multiples() {
+        let mut picked = vec![Timeframe::D1, Timeframe::M5, Timeframe::W1, custom(120), Timeframe::H4];
+        picked.sort();
+        assert_eq!(picked, vec![custom(120), Timeframe::M5, Timeframe::H4, Timeframe::D1, Timeframe::W1]);
+        assert!(Timeframe::M15 < Timeframe::H1 && Timeframe::MN1 > Timeframe::W1);
+        assert!(Timeframe::all().windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(Timeframe::all().len(), 15);
+        assert_eq!(Timeframe::all().first(), Some(&Timeframe::M1));
+
+        assert!(Timeframe::H1.is_multiple_of(Timeframe::M15));
+        assert!(Timeframe::H4.is_multiple_of(Timeframe::M30));
+        assert_eq!(Timeframe::W1.ratio(Timeframe::D1), Some(7));
+        assert_eq!(Timeframe::D1.candles_per(Timeframe::W1), Some(7));
+        assert_eq!(Timeframe::M15.candles_per(Timeframe::H1), Some(4));
+
+        // H4 is shorter than D1, and 3 days don't divide a week
+        assert!(!Timeframe::H4.is_multiple_of(Timeframe::D1));
+        assert_eq!(Timeframe::H4.ratio(Timeframe::D1), None);
+        assert_eq!(Timeframe::D1.candles_per(Timeframe::H4), None);
+        assert!(!Timeframe::W1.is_multiple_of(Timeframe::D3));
+    }
+
+    /// Milliseconds at 00:00 UTC on a date.
+    fn date(year: i64, month: u32, day: u32) -> u64 {
+        days_from_civil(year, month, day) as u64 * DAY_MS
+    }
+
+    #[test]
+    fn test_align_intraday_and_daily() {
+        const HOUR: u64 = 60 * MINUTE;
+        let noonish = date(2024, 3, 14) + 13 * HOUR + 7 * MINUTE + 5_000;
+        assert_eq!(Timeframe::M15.align(noonish), date(2024, 3, 14) + 13 * HOUR);
+        assert_eq!(Timeframe::H4.align(noonish), date(2024, 3, 14) + 12 * HOUR);
+        assert_eq!(Timeframe::D1.align(noonish), date(2024, 3, 14));
+        assert_eq!(Timeframe::D1.next_boundary(noonish), date(2024, 3, 15));
+        assert_eq!(custom(120).align(noonish), date(2024, 3, 14) + 13 * HOUR + 6 * MINUTE);
+
+        // Exactly on a boundary: aligned to itself, the next boundary is a full interval on
+        let open = date(2024, 3, 14) + 12 * HOUR;
+        assert!(Timeframe::H4.is_aligned(open));
+        assert_eq!(Timeframe::H4.align(open), open);
+        assert_eq!(Timeframe::H4.next_boundary(open), open + 4 * HOUR);
+        assert!(!Timeframe::H4.is_aligned(open + 1));
+    }
+
+    #[test]
+    fn test_align_weeks_on_monday_and_months_on_the_first() {
+        // 2024-01-01 was a Monday; a plain modulo of 7 days would land on Thursday 2023-12-28
+        let monday = date(2024, 1, 1);
+        assert_eq!(Timeframe::W1.align(monday), monday);
+        assert_eq!(Timeframe::W1.align(date(2024, 1, 7) + DAY_MS - 1), monday);
+        assert_eq!(Timeframe::W1.align(date(2024, 1, 8)), date(2024, 1, 8));
+        assert_eq!(Timeframe::W1.align(date(2024, 1, 3)), monday);
+        assert_eq!(Timeframe::W1.next_boundary(monday), date(2024, 1, 8));
+        assert!(!Timeframe::W1.is_aligned(date(2023, 12, 28)));
+
+        assert_eq!(Timeframe::MN1.align(date(2024, 2, 29) + 5), date(2024, 2, 1));
+        assert_eq!(Timeframe::MN1.next_boundary(date(2024, 2, 1)), date(2024, 3, 1));
+        assert_eq!(Timeframe::MN1.next_boundary(date(2023, 12, 31)), date(2024, 1, 1));
+        assert!(Timeframe::MN1.is_aligned(date(2024, 3, 1)));
+    }
+
+    #[test]
+    fn test_buckets_between() {
+        let start = date(2024, 1, 1);
+        let opens: Vec<u64> = Timeframe::H4.buckets_between(start + 1, start + 12 * 3_600_000).collect();
+        assert_eq!(opens, vec![start, start + 4 * 3_600_000, start + 8 * 3_600_000]);
+
+        let months: Vec<u64> = Timeframe::MN1.buckets_between(date(2023, 11, 15), date(2024, 2, 1)).collect();
+        assert_eq!(months, vec![date(2023, 11, 1), date(2023, 12, 1), date(2024, 1, 1)]);
+        assert_eq!(Timeframe::W1.buckets_between(date(2024, 1, 3), date(2024, 1, 15)).count(), 2);
+        assert_eq!(Timeframe::M1.buckets_between(start, start).count(), 0);
+    }
+
+    #[test]
+    fn test_iter_range() {
+        let day = 86_400_000;
+        let start = 19_723 * day + 3 * MINUTE;
+        let opens = Timeframe::M1.iter_range(start, start + day);
+        assert_eq!(opens.size_hint(), (1440, Some(1440)));
+        assert_eq!(opens.clone().count(), 1440);
+        assert_eq!(opens.clone().next_back(), Some(start + day - MINUTE));
+        assert_eq!(Timeframe::M1.count_in_range(start, start + day), 1440);
+
+        // Shorter than one interval: just the candle containing start
+        assert_eq!(Timeframe::H1.iter_range(start, start + MINUTE).collect::<Vec<_>>(), vec![19_723 * day]);
+        assert_eq!(Timeframe::H1.iter_range(start, start).count(), 0);
+        assert_eq!(Timeframe::H1.iter_range(start + MINUTE, start).count(), 0);
+        assert_eq!(Timeframe::H1.count_in_range(start + MINUTE, start), 0);
+
+        // Both ends meet in the middle without repeats
+        let mut hours = Timeframe::H1.iter_range(0, 5 * 60 * MINUTE);
+        let hour = 60 * MINUTE;
+        assert_eq!((hours.next(), hours.next_back(), hours.next_back()), (Some(0), Some(4 * hour), Some(3 * hour)));
+        assert_eq!(hours.size_hint(), (2, Some(2)));
+        assert_eq!(hours.rev().collect::<Vec<_>>(), vec![2 * hour, hour]);
+    }
+
+    #[test]
+    fn test_iter_range_weeks_and_months() {
+        let day = 86_400_000;
+        // 2024-01-01 was a Monday; from Wednesday 2024-01-03 to 2024-03-15
+        let (monday, end) = (19_723 * day, 19_797 * day);
+        let weeks: Vec<u64> = Timeframe::W1.iter_range(monday + 2 * day, end).collect();
+        assert_eq!(weeks.len(), 11);
+        assert!(weeks.iter().all(|open| Timeframe::W1.align(*open) == *open && (open - monday) % (7 * day) == 0));
+        assert_eq!(Timeframe::W1.count_in_range(monday + 2 * day, end), 11);
+
+        // January (31 days), February 2024 (29) and March
+        let months: Vec<u64> = Timeframe::MN1.iter_range(monday + 2 * day, end).rev().collect();
+        assert_eq!(months, vec![monday + 60 * day, monday + 31 * day, monday]);
+        assert_eq!(Timeframe::MN1.count_in_range(monday + 2 * day, end), 3);
+    }
+
+    #[test]
+    fn test_candles_between_and_lookback() {
+        let hour = 60 * MINUTE;
+        // 00:30 to 05:15: only the 01:00 to 04:00 candles are whole
+        assert_eq!(Timeframe::H1.candles_between(30 * MINUTE, 5 * hour + 15 * MINUTE), 4);
+        assert_eq!(Timeframe::H1.candles_between(hour, 5 * hour), 4);
+        assert_eq!(Timeframe::H1.candles_between(30 * MINUTE, hour + 59 * MINUTE), 0);
+        assert_eq!(Timeframe::H1.candles_between(5 * hour, hour), 0);
+
+        let end = 10 * hour + 20 * MINUTE;
+        assert_eq!(Timeframe::H1.lookback_start(end, 0), 10 * hour);
+        assert_eq!(Timeframe::H1.lookback_start(end, 4), 6 * hour);
+        assert_eq!(Timeframe::H1.candles_between(Timeframe::H1.lookback_start(end, 4), end), 4);
+        assert_eq!(Timeframe::H1.lookback_start(end, 100), 0);
+
+        // Calendar months: three back from mid-March 2024 is December 2023
+        let day = 86_400_000;
+        let december = Timeframe::MN1.lookback_start(19_797 * day, 3);
+        assert_eq!(december, 19_692 * day);
+        assert_eq!(Timeframe::MN1.candles_between(december, 19_797 * day), 3);
+        assert_eq!(Timeframe::MN1.lookback_start(19_797 * day, 0), 19_783 * day);
+
+        assert_eq!(Timeframe::H1.required_history(14, 3), 42 * hour);
+        assert_eq!(Timeframe::M1.required_history(20, 0), 20 * MINUTE);
+    }
+
+    #[test]
+    fn test_resample_merges_aligned_buckets() {
+        // 12 minutes: two full 5m buckets and a partial third
+        let candles: Vec<Candle> = (0..12).map(|i| minute(i, 100.0 + i as f64, 101.0 + i as f64)).collect();
+        let resampled = resample(&candles, Timeframe::M1, Timeframe::M5).unwrap();
+        assert_eq!(resampled.len(), 2);
+        assert_eq!(ohlcv(&resampled[0]), (0, 100.0, 106.0, 99.0, 105.0, 5.0));
+        assert_eq!(ohlcv(&resampled[1]), (5 * MINUTE, 105.0, 111.0, 104.0, 110.0, 5.0));
+
+        // The first bucket of a series starting mid-bucket is merged from what's there
+        let resampled = resample(&candles[3..10], Timeframe::M1, Timeframe::M5).unwrap();
+        assert_eq!(ohlcv(&resampled[0]), (0, 103.0, 106.0, 102.0, 105.0, 2.0));
+    }
+
+    #[test]
+    fn test_resample_skips_missing_candles() {
+        // Minutes 2, 3 and 4 missing: bucket 0 closes when minute 5 arrives
+        let candles = [minute(0, 100.0, 101.0), minute(1, 101.0, 102.0), minute(5, 110.0, 111.0), minute(9, 111.0, 112.0)];
+        let resampled = resample(&candles, Timeframe::M1, Timeframe::M5).unwrap();
+        assert_eq!(resampled.len(), 2);
+        assert_eq!(ohlcv(&resampled[0]), (0, 100.0, 103.0, 99.0, 102.0, 2.0));
+        assert_eq!(ohlcv(&resampled[1]), (5 * MINUTE, 110.0, 113.0, 109.0, 112.0, 2.0));
+    }
+
+    #[test]
+    fn test_resample_validates_timeframes() {
+        assert_eq!(
+            resample(&[], Timeframe::H1, Timeframe::M15).unwrap_err(),
+            ResampleError { from: Timeframe::H1, to: Timeframe::M15 }
+        );
+        assert!(Resampler::new(Timeframe::H4, Timeframe::D1).is_ok());
+        assert!(resample(&[], Timeframe::M1, Timeframe::M1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resampler_emits_when_bucket_completes() {
+        let mut resampler = Resampler::new(Timeframe::M1, Timeframe::M5).unwrap();
+        for i in 0..4 {
+            assert!(resampler.push(minute(i, 100.0, 100.0)).is_none());
+        }
+        assert_eq!(resampler.current().unwrap().get_volume(), 4.0);
+        // A repeated candle doesn't count twice
+        assert!(resampler.push(minute(3, 100.0, 100.0)).is_none());
+        assert_eq!(resampler.push(minute(4, 100.0, 100.0)).unwrap().get_volume(), 5.0);
+        assert!(resampler.current().is_none());
+
+        // Partial bucket held
+        assert!(resampler.push(minute(5, 100.0, 100.0)).is_none());
+        assert_eq!(resampler.flush().unwrap().get_timestamp(), 5 * MINUTE);
+    }
+
+    #[test]
+    fn test_resample_days_into_monday_weeks() {
+        // Wednesday 2024-01-03 through Sunday 2024-01-14
+        let days: Vec<Candle> = (0..12)
+            .map(|i| {
+                let close = 100.0 + i as f64;
+                Candle::new(date(2024, 1, 3) + i * DAY_MS, close, close, close, close, 1.0)
+            })
+            .collect();
+        let weeks = resample(&days, Timeframe::D1, Timeframe::W1).unwrap();
+        // The week of Jan 1 (Wed-Sun only) and the full week of Jan 8
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(ohlcv(&weeks[0]), (date(2024, 1, 1), 100.0, 104.0, 100.0, 104.0, 5.0));
+        assert_eq!(ohlcv(&weeks[1]), (date(2024, 1, 8), 105.0, 111.0, 105.0, 111.0, 7.0));
+    }
+}
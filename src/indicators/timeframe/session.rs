@@ -0,0 +1,77 @@
+//! UTC session boundaries for features that anchor to calendar periods: daily stats,
+//! weekly opens, funding epochs. Crypto trades around the clock, so a session here is
+//! just a fixed UTC window; all timestamps are ms since the Unix epoch.
+
+use crate::indicators::timeframe::Timeframe;
+
+const HOUR_MS: u64 = 3_600_000;
+
+/// Funding interval most perpetual exchanges use: 00:00, 08:00 and 16:00 UTC.
+pub const DEFAULT_FUNDING_HOURS: u64 = 8;
+
+/// Midnight UTC of the day containing `timestamp_ms`.
+pub fn day_start(timestamp_ms: u64) -> u64 {
+    Timeframe::D1.align(timestamp_ms)
+}
+
+/// Monday 00:00 UTC of the week containing `timestamp_ms`.
+pub fn week_start(timestamp_ms: u64) -> u64 {
+    Timeframe::W1.align(timestamp_ms)
+}
+
+/// Start of the funding epoch containing `timestamp_ms`. Epochs last `interval_hours`
+/// (`None` for the default 8) counted from midnight UTC, and restart every midnight,
+/// so an interval that doesn't divide 24 gives a shorter last epoch. Intervals are
+/// clamped to 1 to 24 hours.
+pub fn funding_epoch_start(timestamp_ms: u64, interval_hours: Option<u64>) -> u64 {
+    let step = interval_hours.unwrap_or(DEFAULT_FUNDING_HOURS).clamp(1, 24) * HOUR_MS;
+    let day = day_start(timestamp_ms);
+    day + (timestamp_ms - day) / step * step
+}
+
+/// Whether `a` and `b` fall in the same `timeframe` candle, e.g. the same UTC day for D1.
+pub fn is_same_session(a: u64, b: u64, timeframe: Timeframe) -> bool {
+    timeframe.align(a) == timeframe.align(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 24 * HOUR_MS;
+    // 2024-01-01, a Monday
+    const MONDAY: u64 = 19_723 * DAY_MS;
+
+    #[test]
+    fn test_day_start_at_and_around_midnight() {
+        assert_eq!(day_start(MONDAY), MONDAY);
+        assert_eq!(day_start(MONDAY - 1), MONDAY - DAY_MS);
+        assert_eq!(day_start(MONDAY + DAY_MS - 1), MONDAY);
+        assert!(is_same_session(MONDAY, MONDAY + DAY_MS - 1, Timeframe::D1));
+        assert!(!is_same_session(MONDAY - 1, MONDAY, Timeframe::D1));
+    }
+
+    #[test]
+    fn test_week_starts_on_monday() {
+        let sunday_night = MONDAY - 1;
+        assert_eq!(week_start(sunday_night), MONDAY - 7 * DAY_MS);
+        assert_eq!(week_start(MONDAY), MONDAY);
+        assert_eq!(week_start(MONDAY + 6 * DAY_MS + 12 * HOUR_MS), MONDAY);
+        assert!(!is_same_session(sunday_night, MONDAY, Timeframe::W1));
+        assert!(is_same_session(MONDAY, MONDAY + 7 * DAY_MS - 1, Timeframe::W1));
+    }
+
+    #[test]
+    fn test_funding_epochs() {
+        assert_eq!(funding_epoch_start(MONDAY + 7 * HOUR_MS, None), MONDAY);
+        assert_eq!(funding_epoch_start(MONDAY + 8 * HOUR_MS, None), MONDAY + 8 * HOUR_MS);
+        assert_eq!(funding_epoch_start(MONDAY - 1, None), MONDAY - 8 * HOUR_MS);
+
+        // 4-hour funding: 00, 04, 08, ... 20
+        assert_eq!(funding_epoch_start(MONDAY + 5 * HOUR_MS, Some(4)), MONDAY + 4 * HOUR_MS);
+        assert_eq!(funding_epoch_start(MONDAY + 23 * HOUR_MS, Some(4)), MONDAY + 20 * HOUR_MS);
+        // Restarts at midnight even when the interval doesn't divide the day
+        assert_eq!(funding_epoch_start(MONDAY + 23 * HOUR_MS, Some(5)), MONDAY + 20 * HOUR_MS);
+        assert_eq!(funding_epoch_start(MONDAY + 3 * HOUR_MS, Some(0)), MONDAY + 3 * HOUR_MS);
+    }
+}
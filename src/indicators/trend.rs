@@ -0,0 +1,670 @@
+//! Trend indicators: Wilder's directional movement system (ADX, +DI, -DI), Aroon,
+//! linear regression, and Elder's Ray and Impulse System
+
+use serde::{Deserialize, Serialize};
+
+use crate::indicators::candle::Candle;
+use crate::indicators::momentum::macd_series;
+use crate::indicators::moving_averages::{ema_series, rma_series_values};
+use crate::indicators::rolling::{rolling_max_index, rolling_min_index};
+use crate::indicators::volatility::true_range;
+
+const DEFAULT_ADX_PERIOD: usize = 14;
+const DEFAULT_AROON_PERIOD: usize = 25;
+const DEFAULT_LINREG_PERIOD: usize = 14;
+const DEFAULT_ELDER_PERIOD: usize = 13;
+
+/// One directional movement reading, all three between 0 and 100.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Adx {
+    /// Strength of the trend, whichever way it points
+    pub adx: f64,
+    /// Share of the range made by upward moves
+    pub plus_di: f64,
+    /// Share of the range made by downward moves
+    pub minus_di: f64,
+}
+
+/// ADX, +DI and -DI over time, index-aligned and oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdxSeries {
+    pub adx: Vec<f64>,
+    pub plus_di: Vec<f64>,
+    pub minus_di: Vec<f64>,
+}
+
+impl AdxSeries {
+    pub fn len(&self) -> usize {
+        self.adx.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adx.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Adx> {
+        Some(Adx {
+            adx: *self.adx.get(index)?,
+            plus_di: *self.plus_di.get(index)?,
+            minus_di: *self.minus_di.get(index)?,
+        })
+    }
+
+    pub fn last(&self) -> Option<Adx> {
+        self.get(self.len().checked_sub(1)?)
+    }
+}
+
+/// Calculates the Average Directional Index (ADX) with +DI and -DI over a slice of candles.
+///
+/// From each pair of consecutive candles:
+/// - up move = high - previous high, down move = previous low - low
+/// - +DM = up move if it is positive and larger than the down move, else 0
+/// - -DM = down move if it is positive and larger than the up move, else 0
+///
+/// +DI = 100 * RMA(+DM) / RMA(TR)
+/// -DI = 100 * RMA(-DM) / RMA(TR)
+/// DX = 100 * |+DI - -DI| / (+DI + -DI)
+/// ADX = RMA(DX)
+///
+/// Common interpretation:
+/// - ADX > 25: Trending (+DI above -DI for up, below for down)
+/// - ADX < 20: No trend
+///
+/// Pass `None` to use the default period of 14.
+/// Returns `None` if there are not enough candles (need at least 2 * period candles).
+pub fn adx(candles: &[Candle], period: Option<usize>) -> Option<Adx> {
+    adx_series(candles, period).last()
+}
+
+/// Calculates the ADX series for all calculable points.
+///
+/// The DIs start after `period` moves (`period + 1` candles) and ADX averages `period`
+/// DX values on top, so the series has length `candles.len() - 2 * period + 1`; the
+/// DIs are trimmed to start where ADX does. Empty if there are not enough candles or
+/// the period is 0.
+pub fn adx_series(candles: &[Candle], period: Option<usize>) -> AdxSeries {
+    let period = period.unwrap_or(DEFAULT_ADX_PERIOD);
+    if period == 0 || candles.len() < 2 * period {
+        return AdxSeries::default();
+    }
+
+    let mut ranges = Vec::with_capacity(candles.len() - 1);
+    let mut plus_dm = Vec::with_capacity(candles.len() - 1);
+    let mut minus_dm = Vec::with_capacity(candles.len() - 1);
+    for pair in candles.windows(2) {
+        let (prev, candle) = (&pair[0], &pair[1]);
+        let up = candle.get_high() - prev.get_high();
+        let down = prev.get_low() - candle.get_low();
+        plus_dm.push(if up > down && up > 0.0 { up } else { 0.0 });
+        minus_dm.push(if down > up && down > 0.0 { down } else { 0.0 });
+        ranges.push(true_range(candle, Some(prev.get_close())));
+    }
+
+    let ranges = rma_series_values(&ranges, period);
+    let directional_index = |dm: &[f64]| -> Vec<f64> {
+        rma_series_values(dm, period)
+            .iter()
+            .zip(&ranges)
+            .map(|(dm, range)| if *range == 0.0 { 0.0 } else { 100.0 * dm / range })
+            .collect()
+    };
+    let plus_di = directional_index(&plus_dm);
+    let minus_di = directional_index(&minus_dm);
+
+    let dx: Vec<f64> = plus_di
+        .iter()
+        .zip(&minus_di)
+        .map(|(plus, minus)| {
+            let total = plus + minus;
+            if total == 0.0 { 0.0 } else { 100.0 * (plus - minus).abs() / total }
+        })
+        .collect();
+
+    AdxSeries {
+        adx: rma_series_values(&dx, period),
+        plus_di: plus_di[period - 1..].to_vec(),
+        minus_di: minus_di[period - 1..].to_vec(),
+    }
+}
+
+/// One Aroon reading: how recent the period's high and low are.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aroon {
+    /// 100 on the bar that makes the high, down to 0 when it is `period` bars old
+    pub up: f64,
+    /// The same for the low
+    pub down: f64,
+    /// `up - down`, between -100 and 100
+    pub oscillator: f64,
+}
+
+/// Aroon up, down and oscillator over time, index-aligned and oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AroonSeries {
+    pub up: Vec<f64>,
+    pub down: Vec<f64>,
+    pub oscillator: Vec<f64>,
+}
+
+impl AroonSeries {
+    pub fn len(&self) -> usize {
+        self.up.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.up.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Aroon> {
+        Some(Aroon {
+            up: *self.up.get(index)?,
+            down: *self.down.get(index)?,
+            oscillator: *self.oscillator.get(index)?,
+        })
+    }
+
+    pub fn last(&self) -> Option<Aroon> {
+        self.get(self.len().checked_sub(1)?)
+    }
+}
+
+/// Calculates the Aroon indicator over a slice of candles.
+///
+/// Aroon Up = 100 * (period - bars since the highest high) / period
+/// Aroon Down = 100 * (period - bars since the lowest low) / period
+/// Oscillator = Aroon Up - Aroon Down
+///
+/// The high and low are searched over the last `period + 1` candles (the current one
+/// and `period` before it), so bars since runs from 0 to `period`. When the extreme
+/// was hit more than once, bars since counts from the latest time.
+///
+/// Pass `None` to use the default period of 25.
+/// Returns `None` if there are not enough candles (need at least period + 1 candles).
+pub fn aroon(candles: &[Candle], period: Option<usize>) -> Option<Aroon> {
+    aroon_series(candles, period).last()
+}
+
+/// Calculates the Aroon series for all calculable points.
+///
+/// Has length `candles.len() - period`. Empty if there are not enough candles or the
+/// period is 0.
+pub fn aroon_series(candles: &[Candle], period: Option<usize>) -> AroonSeries {
+    let period = period.unwrap_or(DEFAULT_AROON_PERIOD);
+    if period == 0 || candles.len() <= period {
+        return AroonSeries::default();
+    }
+
+    let highs: Vec<f64> = candles.iter().map(Candle::get_high).collect();
+    let lows: Vec<f64> = candles.iter().map(Candle::get_low).collect();
+    // Window `i` ends at candle `i + period`
+    let score = |indices: Vec<usize>| -> Vec<f64> {
+        indices
+            .into_iter()
+            .enumerate()
+            .map(|(i, extreme)| 100.0 * (period - (i + period - extreme)) as f64 / period as f64)
+            .collect()
+    };
+    let up = score(rolling_max_index(&highs, period + 1));
+    let down = score(rolling_min_index(&lows, period + 1));
+
+    let oscillator = up.iter().zip(&down).map(|(up, down)| up - down).collect();
+    AroonSeries { up, down, oscillator }
+}
+
+/// Least-squares line through a window of closes, with x = 0 at the oldest candle
+/// and `period - 1` at the latest.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LinReg {
+    /// Price change per candle
+    pub slope: f64,
+    /// The line's value at the oldest candle of the window
+    pub intercept: f64,
+    /// Share of the closes' variance the line explains, 0 to 1. A flat window is
+    /// fitted exactly and gives 1.
+    pub r_squared: f64,
+}
+
+impl LinReg {
+    /// The line's value at `x` candles after the oldest one of the window.
+    pub fn at(&self, x: f64) -> f64 {
+        self.intercept + self.slope * x
+    }
+}
+
+/// A regression line with parallel lines `offset` above and below it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LinRegChannel {
+    pub middle: LinReg,
+    /// `stddev_mult` standard deviations of the closes around the line
+    pub offset: f64,
+}
+
+impl LinRegChannel {
+    pub fn upper_at(&self, x: f64) -> f64 {
+        self.middle.at(x) + self.offset
+    }
+
+    pub fn lower_at(&self, x: f64) -> f64 {
+        self.middle.at(x) - self.offset
+    }
+}
+
+/// Fits a line to the closes of the last `period` candles.
+///
+/// Sums are taken around the means of x and the closes (the two-pass form), so the
+/// fit keeps its precision on long periods at prices far from zero.
+///
+/// Pass `None` to use the default period of 14.
+/// Returns `None` if there are not enough candles or the period is below 2.
+pub fn linreg(candles: &[Candle], period: Option<usize>) -> Option<LinReg> {
+    let period = period.unwrap_or(DEFAULT_LINREG_PERIOD);
+    if period < 2 || candles.len() < period {
+        return None;
+    }
+    Some(fit(&candles[candles.len() - period..]))
+}
+
+/// Calculates the regression slope of every `period`-candle window, oldest first.
+///
+/// Has length `candles.len() - period + 1`. Empty if there are not enough candles or
+/// the period is below 2.
+pub fn linreg_series(candles: &[Candle], period: Option<usize>) -> Vec<f64> {
+    let period = period.unwrap_or(DEFAULT_LINREG_PERIOD);
+    if period < 2 {
+        return Vec::new();
+    }
+    candles.windows(period).map(|window| fit(window).slope).collect()
+}
+
+/// Projects the regression line `bars_ahead` candles past the latest one (0 gives
+/// the line's value at the latest candle).
+pub fn linreg_forecast(candles: &[Candle], period: Option<usize>, bars_ahead: usize) -> Option<f64> {
+    let period = period.unwrap_or(DEFAULT_LINREG_PERIOD);
+    linreg(candles, Some(period)).map(|line| line.at((period - 1 + bars_ahead) as f64))
+}
+
+/// Regression channel: the regression line plus parallel lines `stddev_mult`
+/// standard deviations of the residuals (closes minus the line) above and below.
+pub fn linreg_channel(candles: &[Candle], period: Option<usize>, stddev_mult: f64) -> Option<LinRegChannel> {
+    let period = period.unwrap_or(DEFAULT_LINREG_PERIOD);
+    let middle = linreg(candles, Some(period))?;
+    let window = &candles[candles.len() - period..];
+    let squared: f64 =
+        window.iter().enumerate().map(|(x, candle)| (candle.get_close() - middle.at(x as f64)).powi(2)).sum();
+    Some(LinRegChannel { middle, offset: stddev_mult * (squared / period as f64).sqrt() })
+}
+
+/// Least-squares fit of a window of at least 2 candles.
+fn fit(window: &[Candle]) -> LinReg {
+    let n = window.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = window.iter().map(Candle::get_close).sum::<f64>() / n;
+
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for (x, candle) in window.iter().enumerate() {
+        let dx = x as f64 - mean_x;
+        let dy = candle.get_close() - mean_y;
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 { 1.0 } else { (sxy * sxy / (sxx * syy)).min(1.0) };
+    LinReg { slope, intercept: mean_y - slope * mean_x, r_squared }
+}
+
+/// One Elder Ray reading: how far buyers and sellers pushed price from the consensus
+/// value (the EMA).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ElderRay {
+    /// High - EMA
+    pub bull_power: f64,
+    /// Low - EMA
+    pub bear_power: f64,
+}
+
+/// Bull and bear power over time, index-aligned and oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ElderRaySeries {
+    pub bull_power: Vec<f64>,
+    pub bear_power: Vec<f64>,
+}
+
+impl ElderRaySeries {
+    pub fn len(&self) -> usize {
+        self.bull_power.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bull_power.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<ElderRay> {
+        Some(ElderRay { bull_power: *self.bull_power.get(index)?, bear_power: *self.bear_power.get(index)? })
+    }
+
+    pub fn last(&self) -> Option<ElderRay> {
+        self.get(self.len().checked_sub(1)?)
+    }
+}
+
+/// Calculates Elder Ray over a slice of candles.
+///
+/// Bull Power = High - EMA(close, period)
+/// Bear Power = Low - EMA(close, period)
+///
+/// Pass `None` to use the default period of 13.
+/// Returns `None` if there are not enough candles (need at least period candles).
+pub fn elder_ray(candles: &[Candle], period: Option<usize>) -> Option<ElderRay> {
+    elder_ray_series(candles, period).last()
+}
+
+/// Calculates the Elder Ray series for all calculable points, aligned like
+/// `ema_series`: length `candles.len() - period + 1`.
+pub fn elder_ray_series(candles: &[Candle], period: Option<usize>) -> ElderRaySeries {
+    let period = period.unwrap_or(DEFAULT_ELDER_PERIOD);
+    let ema = ema_series(candles, period);
+    if ema.is_empty() {
+        return ElderRaySeries::default();
+    }
+
+    let aligned = &candles[period - 1..];
+    ElderRaySeries {
+        bull_power: aligned.iter().zip(&ema).map(|(candle, ema)| candle.get_high() - ema).collect(),
+        bear_power: aligned.iter().zip(&ema).map(|(candle, ema)| candle.get_low() - ema).collect(),
+    }
+}
+
+/// Bar color of Elder's Impulse System.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Impulse {
+    /// EMA and MACD histogram both rising
+    Green,
+    /// EMA and MACD histogram both falling
+    Red,
+    /// Anything else
+    Blue,
+}
+
+/// Colors each candle by Elder's Impulse System: whether the EMA (inertia) and the
+/// MACD histogram (push) rose or fell from the previous candle.
+///
+/// Pass `None` for the defaults of a 13 EMA and MACD (12, 26, 9).
+/// The first color needs the previous value of both, so the result has length
+/// `candles.len() - max(ema_period, macd_slow + macd_signal - 1)`, the last entry
+/// being the latest candle. Empty if there are not enough candles or an EMA or MACD
+/// setting is invalid (see `macd_series`).
+pub fn elder_impulse(
+    candles: &[Candle],
+    ema_period: Option<usize>,
+    macd_fast: Option<usize>,
+    macd_slow: Option<usize>,
+    macd_signal: Option<usize>,
+) -> Vec<Impulse> {
+    let ema = ema_series(candles, ema_period.unwrap_or(DEFAULT_ELDER_PERIOD));
+    let histogram = macd_series(candles, macd_fast, macd_slow, macd_signal).histogram;
+
+    // Both series end on the latest candle; keep the part where each has a previous value
+    let len = ema.len().min(histogram.len()).saturating_sub(1);
+    if len == 0 {
+        return Vec::new();
+    }
+    let ema = &ema[ema.len() - len - 1..];
+    let histogram = &histogram[histogram.len() - len - 1..];
+
+    ema.windows(2)
+        .zip(histogram.windows(2))
+        .map(|(ema, histogram)| {
+            let (inertia, push) = (ema[1] - ema[0], histogram[1] - histogram[0]);
+            if inertia > 0.0 && push > 0.0 {
+                Impulse::Green
+            } else if inertia < 0.0 && push < 0.0 {
+                Impulse::Red
+            } else {
+                Impulse::Blue
+            }
+        })
+        .collect()
+}
+
+/// Candle fixtures shared by the indicator test modules.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::indicators::candle::Candle;
+
+    /// 40 one-minute candles: a rise, a dip and a second peak.
+    pub(crate) fn wave_candles() -> Vec<Candle> {
+        // Closes 100 + 10 * sin(i / 4) + 0.3 * i rounded to cents, with uneven wicks
+        let ohlc = [
+            (100.0, 101.0, 99.0), (102.77, 104.27, 101.52), (105.39, 107.39, 103.89), (107.72, 108.72, 105.97),
+            (109.61, 111.11, 108.61), (110.99, 112.99, 109.74), (111.77, 112.77, 110.27), (111.94, 113.44, 110.19),
+            (111.49, 113.49, 110.49), (110.48, 111.48, 109.23), (108.98, 110.48, 107.48), (107.12, 109.12, 105.37),
+            (105.01, 106.01, 104.01), (102.82, 104.32, 101.57), (100.69, 102.69, 99.19), (98.78, 99.78, 97.03),
+            (97.23, 98.73, 96.23), (96.15, 98.15, 94.9), (95.62, 96.62, 94.12), (95.71, 97.21, 93.96),
+            (96.41, 98.41, 95.41), (97.71, 98.71, 96.46), (99.54, 101.04, 98.04), (101.82, 103.82, 100.07),
+            (104.41, 105.41, 103.41), (107.17, 108.67, 105.92), (109.95, 111.95, 108.45), (112.6, 113.6, 110.85),
+            (114.97, 116.47, 113.97), (116.93, 118.93, 115.68), (118.38, 119.38, 116.88), (119.25, 120.75, 117.5),
+            (119.49, 121.49, 118.49), (119.13, 120.13, 117.88), (118.18, 119.68, 116.68), (116.75, 118.75, 115.0),
+            (114.92, 115.92, 113.92), (112.84, 114.34, 111.59), (110.65, 112.65, 109.15), (108.5, 109.5, 106.75),
+        ];
+        ohlc.iter()
+            .enumerate()
+            .map(|(i, &(close, high, low))| Candle::new(i as u64 * 60_000, close, high, low, close, 1.0))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::wave_candles;
+    use super::*;
+
+    fn trending_candles(step: f64) -> Vec<Candle> {
+        (0..30)
+            .map(|i| {
+                let close = 100.0 + step * i as f64;
+                Candle::new(0, close - step / 2.0, close + 1.0, close - 1.0, close, 1.0)
+            })
+            .collect()
+    }
+
+    fn assert_adx(actual: Adx, expected: (f64, f64, f64)) {
+        assert!((actual.adx - expected.0).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        assert!((actual.plus_di - expected.1).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        assert!((actual.minus_di - expected.2).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn test_adx_reference_values() {
+        // From an independent implementation using Wilder's running sums
+        // (TR14 = prior TR14 - prior TR14 / 14 + TR) rather than averages
+        let candles = wave_candles();
+        let series = adx_series(&candles, None);
+        assert_eq!(series.len(), 40 - 2 * 14 + 1);
+        assert_adx(series.get(0).unwrap(), (17.449028811320627, 38.18578694311881, 13.57029921477589));
+        assert_adx(series.get(8).unwrap(), (33.68600839126095, 33.09447242431315, 14.90854910858039));
+        assert_adx(series.last().unwrap(), (28.625250045473532, 24.351945309255328, 26.950266934434023));
+
+        let short = adx_series(&candles, Some(5));
+        assert_eq!(short.len(), 31);
+        assert_adx(short.get(29).unwrap(), (48.428093210143444, 12.246274291160018, 40.14376476631077));
+        assert_adx(adx(&candles, Some(5)).unwrap(), (51.80185928127363, 9.45260048054024, 45.024416843624934));
+    }
+
+    #[test]
+    fn test_adx_trend_and_chop() {
+        let up = adx(&trending_candles(2.0), None).unwrap();
+        assert!(up.adx > 50.0 && up.plus_di > up.minus_di, "{:?}", up);
+        let down = adx(&trending_candles(-2.0), None).unwrap();
+        assert!(down.adx > 50.0 && down.minus_di > down.plus_di, "{:?}", down);
+
+        // Swings of a few points around 100 that never build into a move
+        let chop: Vec<Candle> = (0..30)
+            .map(|i| {
+                let close = 100.0 + 3.0 * (i as f64 * 1.3).sin();
+                Candle::new(0, close, close + 1.0, close - 1.0, close, 1.0)
+            })
+            .collect();
+        let flat = adx(&chop, None).unwrap();
+        assert!(flat.adx < 10.0, "{:?}", flat);
+    }
+
+    #[test]
+    fn test_adx_insufficient_data() {
+        let candles = wave_candles();
+        assert!(adx(&candles[..27], None).is_none());
+        assert_eq!(adx_series(&candles[..28], None).len(), 1);
+        assert!(adx_series(&candles, Some(0)).is_empty());
+
+        let frozen = vec![Candle::new(0, 100.0, 100.0, 100.0, 100.0, 1.0); 30];
+        assert_adx(adx(&frozen, None).unwrap(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_aroon_fresh_extremes() {
+        let up = trending_candles(2.0);
+        let series = aroon_series(&up, Some(10));
+        assert_eq!(series.len(), 30 - 10);
+        // Every bar makes a new high, and the low is the oldest bar in the window
+        assert!(series.up.iter().all(|value| *value == 100.0));
+        assert!(series.down.iter().all(|value| *value == 0.0));
+        assert_eq!(aroon(&up, None).unwrap().oscillator, 100.0);
+
+        let down = aroon(&trending_candles(-2.0), Some(10)).unwrap();
+        assert_eq!((down.up, down.down, down.oscillator), (0.0, 100.0, -100.0));
+    }
+
+    #[test]
+    fn test_aroon_bars_since_and_ties() {
+        let bars = [(10.0, 5.0), (12.0, 4.0), (11.0, 6.0), (12.0, 7.0), (9.0, 6.0)];
+        let candles: Vec<Candle> =
+            bars.iter().map(|&(high, low)| Candle::new(0, low, high, low, low, 1.0)).collect();
+        // Window of 5: the high of 12 was hit 3 and 1 bars ago (1 counts), the low of 4 is 3 bars ago
+        let value = aroon(&candles, Some(4)).unwrap();
+        assert_eq!((value.up, value.down, value.oscillator), (75.0, 25.0, 50.0));
+        assert!(aroon(&candles, Some(5)).is_none());
+        assert!(aroon_series(&candles, Some(0)).is_empty());
+    }
+
+    #[test]
+    fn test_aroon_sideways_oscillator() {
+        // Bars bouncing between the edges of a 95-105 range: taking the latest touch
+        // keeps both the high and the low at most a bar old
+        let range: Vec<Candle> = (0..40)
+            .map(|i| if i % 2 == 0 { (105.0, 97.0) } else { (103.0, 95.0) })
+            .map(|(high, low)| Candle::new(0, 100.0, high, low, 100.0, 1.0))
+            .collect();
+        let series = aroon_series(&range, None);
+        assert_eq!(series.len(), 40 - 25);
+        assert!(series.oscillator.iter().all(|value| value.abs() <= 4.0), "{:?}", series.oscillator);
+        assert!(series.up.iter().chain(&series.down).all(|value| *value >= 96.0));
+    }
+
+    fn closes(values: &[f64]) -> Vec<Candle> {
+        values.iter().map(|&close| Candle::new(0, close, close, close, close, 1.0)).collect()
+    }
+
+    #[test]
+    fn test_linreg_exact_line() {
+        // Large prices and a long window: the two-pass sums still recover the line exactly
+        let line: Vec<f64> = (0..500).map(|i| 30_000.0 + 0.25 * i as f64).collect();
+        let candles = closes(&line);
+        let fit = linreg(&candles, Some(400)).unwrap();
+        assert_eq!(fit.slope, 0.25);
+        assert_eq!(fit.intercept, 30_025.0);
+        assert_eq!(fit.r_squared, 1.0);
+        assert_eq!(linreg_forecast(&candles, Some(400), 0), Some(30_124.75));
+        assert_eq!(linreg_forecast(&candles, Some(400), 4), Some(30_125.75));
+
+        let slopes = linreg_series(&candles, Some(400));
+        assert_eq!(slopes.len(), 500 - 400 + 1);
+        assert!(slopes.iter().all(|slope| *slope == 0.25));
+    }
+
+    #[test]
+    fn test_linreg_flat_and_noisy() {
+        let flat = linreg(&closes(&[50.0; 20]), None).unwrap();
+        assert_eq!((flat.slope, flat.intercept, flat.r_squared), (0.0, 50.0, 1.0));
+
+        // Up 1 a candle, with the middle three closes pushed off the line
+        let noisy = linreg(&closes(&[10.0, 12.0, 11.0, 14.0, 14.0]), Some(5)).unwrap();
+        assert!((noisy.slope - 1.0).abs() < 1e-12);
+        // Sxy 10, Sxx 10, Syy 12.8
+        assert!((noisy.r_squared - 0.78125).abs() < 1e-12);
+        assert!(linreg(&closes(&[1.0, 2.0]), Some(3)).is_none());
+        assert!(linreg(&closes(&[1.0, 2.0]), Some(1)).is_none());
+        assert!(linreg_series(&closes(&[1.0, 2.0]), Some(0)).is_empty());
+    }
+
+    #[test]
+    fn test_linreg_channel() {
+        // Closes alternate 1 below and above a line rising 2 a candle; the fit tilts
+        // slightly toward the zigzag, leaving residuals a little under 1
+        let zigzag: Vec<f64> = (0..10).map(|i| 100.0 + 2.0 * i as f64 + if i % 2 == 0 { -1.0 } else { 1.0 }).collect();
+        let channel = linreg_channel(&closes(&zigzag), Some(10), 2.0).unwrap();
+        let latest = channel.middle.at(9.0);
+        assert!((channel.upper_at(9.0) - latest - channel.offset).abs() < 1e-12);
+        assert!((latest - channel.lower_at(9.0) - channel.offset).abs() < 1e-12);
+        assert!(channel.offset > 1.5 && channel.offset < 2.0, "{:?}", channel);
+
+        let exact = linreg_channel(&closes(&[1.0, 2.0, 3.0]), Some(3), 2.0).unwrap();
+        assert_eq!(exact.offset, 0.0);
+    }
+
+    fn accelerating_candles(step: f64) -> Vec<Candle> {
+        // Moves grow by 10% a candle, so the MACD histogram keeps widening
+        (0..40)
+            .map(|i| {
+                let close = 1000.0 + step * (1.1f64.powi(i) - 1.0) / 0.1;
+                Candle::new(0, close, close + 1.0, close - 1.0, close, 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_elder_ray() {
+        let candles = trending_candles(2.0);
+        let series = elder_ray_series(&candles, None);
+        assert_eq!(series.len(), 30 - 13 + 1);
+        // The EMA lags below the rising closes: highs and even lows stay above it
+        assert!(series.bull_power.iter().all(|power| *power > 1.0));
+        assert!(series.bear_power[5..].iter().all(|power| *power > 0.0));
+
+        let ema = ema_series(&candles, 13);
+        let last = elder_ray(&candles, None).unwrap();
+        assert_eq!(last.bull_power, candles[29].get_high() - ema[17]);
+        assert_eq!(last.bull_power - last.bear_power, 2.0);
+        assert!(elder_ray(&candles[..12], None).is_none());
+        assert!(elder_ray_series(&candles, Some(0)).is_empty());
+    }
+
+    #[test]
+    fn test_elder_impulse_trends() {
+        let up = elder_impulse(&accelerating_candles(1.0), None, None, None, None);
+        assert_eq!(up.len(), 40 - (26 + 9 - 1));
+        assert!(up.iter().all(|impulse| *impulse == Impulse::Green), "{:?}", up);
+
+        let down = elder_impulse(&accelerating_candles(-1.0), None, None, None, None);
+        assert!(down.iter().all(|impulse| *impulse == Impulse::Red), "{:?}", down);
+
+        // Steady moves: the histogram flattens out, so the bars turn blue
+        let steady = elder_impulse(&trending_candles(2.0), Some(5), Some(3), Some(6), Some(4));
+        assert_eq!(steady.last(), Some(&Impulse::Blue));
+    }
+
+    #[test]
+    fn test_elder_impulse_alignment() {
+        let candles = accelerating_candles(1.0);
+        // The EMA is the longer warmup: 20 > 6 + 4 - 1
+        assert_eq!(elder_impulse(&candles, Some(20), Some(3), Some(6), Some(4)).len(), 40 - 20);
+        // MACD is: 5 < 9
+        assert_eq!(elder_impulse(&candles, Some(5), Some(3), Some(6), Some(4)).len(), 40 - 9);
+        assert_eq!(elder_impulse(&candles[..9], Some(5), Some(3), Some(6), Some(4)).len(), 0);
+        assert_eq!(elder_impulse(&candles[..10], Some(5), Some(3), Some(6), Some(4)).len(), 1);
+        assert!(elder_impulse(&candles, Some(5), Some(6), Some(3), Some(4)).is_empty());
+        assert_eq!(serde_json::to_string(&Impulse::Blue).unwrap(), r#""blue""#);
+    }
+}
@@ -1,9 +1,14 @@
 pub mod api;
 pub mod alerts;
 pub mod db;
+#[cfg(feature = "decimal")]
+pub mod decimal;
 pub mod engine;
+pub mod facade;
 pub mod indicators;
 pub mod market;
+#[cfg(feature = "test-util")]
+pub mod testing;
 
 pub fn run() {
     println!("Hello, world!");
@@ -0,0 +1,311 @@
+//! Trade tape analytics built on normalized market data.
+
+use std::collections::HashMap;
+
+use crate::market::market_data::{Trade, TradeSide};
+use crate::market::symbol::Symbol;
+
+// Design: a single aggressive order that walks the book is printed as several
+// trades (one per resting maker). Binance reconstructs these in aggTrade, but
+// only for fills at the *same* price. Here we rebuild the parent order from any
+// venue's raw tape, and also across price levels so sweeps show up as one event.
+
+const DEFAULT_MAX_GAP_MS: u64 = 5;
+const DEFAULT_MAX_PRICE_DRIFT: f64 = 0.001; // 0.1% from the first print
+
+/// A reconstructed aggressor order (one taker order split across makers).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggressorOrder {
+    pub symbol: String,
+    pub side: TradeSide,
+    pub total_qty: f64,
+    /// Volume-weighted average fill price
+    pub avg_price: f64,
+    pub trade_count: usize,
+    pub start_ts: u64,
+    pub end_ts: u64,
+    /// Price of the first print (best level when the order arrived)
+    pub first_price: f64,
+    /// Price of the last print (worst level the order reached)
+    pub last_price: f64,
+}
+
+impl AggressorOrder {
+    /// Returns true if the order consumed more than one price level.
+    pub fn is_sweep(&self) -> bool {
+        self.first_price != self.last_price
+    }
+
+    /// Total quote value of the order (quantity * average price).
+    pub fn notional(&self) -> f64 {
+        self.total_qty * self.avg_price
+    }
+}
+
+/// In-progress run of prints for one symbol.
+#[derive(Debug, Clone)]
+struct Run {
+    side: TradeSide,
+    total_qty: f64,
+    total_value: f64,
+    trade_count: usize,
+    start_ts: u64,
+    end_ts: u64,
+    first_price: f64,
+    last_price: f64,
+}
+
+impl Run {
+    fn start(trade: &Trade) -> Self {
+        Self {
+            side: trade.side,
+            total_qty: trade.quantity,
+            total_value: trade.price * trade.quantity,
+            trade_count: 1,
+            start_ts: trade.timestamp,
+            end_ts: trade.timestamp,
+            first_price: trade.price,
+            last_price: trade.price,
+        }
+    }
+
+    fn extend(&mut self, trade: &Trade) {
+        self.total_qty += trade.quantity;
+        self.total_value += trade.price * trade.quantity;
+        self.trade_count += 1;
+        self.end_ts = self.end_ts.max(trade.timestamp);
+        self.last_price = trade.price;
+    }
+
+    fn into_order(self, symbol: &str) -> AggressorOrder {
+        let avg_price = if self.total_qty > 0.0 {
+            self.total_value / self.total_qty
+        } else {
+            self.last_price
+        };
+
+        AggressorOrder {
+            symbol: symbol.to_string(),
+            side: self.side,
+            total_qty: self.total_qty,
+            avg_price,
+            trade_count: self.trade_count,
+            start_ts: self.start_ts,
+            end_ts: self.end_ts,
+            first_price: self.first_price,
+            last_price: self.last_price,
+        }
+    }
+}
+
+/// Groups consecutive trades into reconstructed aggressor orders.
+///
+/// A trade continues the current run for its symbol when:
+/// - it has the same side,
+/// - it arrives within `max_gap_ms` of the previous print,
+/// - its price moves monotonically in the aggressor's direction
+///   (non-decreasing for buys, non-increasing for sells),
+/// - and it stays within `max_price_drift` (fraction) of the run's first price.
+///
+/// Runs are tracked per symbol, so an interleaved multi-symbol tape is fine.
+/// The completed order is returned when its run breaks.
+#[derive(Debug, Clone)]
+pub struct AggressorAggregator {
+    max_gap_ms: u64,
+    max_price_drift: f64,
+    runs: HashMap<Symbol, Run>,
+}
+
+impl AggressorAggregator {
+    pub fn new() -> Self {
+        Self {
+            max_gap_ms: DEFAULT_MAX_GAP_MS,
+            max_price_drift: DEFAULT_MAX_PRICE_DRIFT,
+            runs: HashMap::new(),
+        }
+    }
+
+    /// Maximum time between consecutive prints of the same order (default 5ms).
+    pub fn with_max_gap_ms(mut self, max_gap_ms: u64) -> Self {
+        self.max_gap_ms = max_gap_ms;
+        self
+    }
+
+    /// Maximum distance from the first print, as a fraction of its price (default 0.001).
+    pub fn with_max_price_drift(mut self, max_price_drift: f64) -> Self {
+        debug_assert!(max_price_drift >= 0.0, "price drift must be non-negative");
+        self.max_price_drift = max_price_drift;
+        self
+    }
+
+    /// Feeds a trade. Returns the previous order for this symbol if the trade broke its run.
+    pub fn push(&mut self, trade: &Trade) -> Option<AggressorOrder> {
+        if let Some(run) = self.runs.get_mut(trade.symbol.as_str())
+            && continues_run(run, trade, self.max_gap_ms, self.max_price_drift)
+        {
+            run.extend(trade);
+            return None;
+        }
+
+        self.runs
+            .insert(trade.symbol.clone(), Run::start(trade))
+            .map(|run| run.into_order(&trade.symbol))
+    }
+
+    /// Returns the in-progress order for a symbol without closing it.
+    pub fn current(&self, symbol: &str) -> Option<AggressorOrder> {
+        self.runs
+            .get(symbol)
+            .map(|run| run.clone().into_order(symbol))
+    }
+
+    /// Closes and returns the in-progress order for a symbol.
+    pub fn flush(&mut self, symbol: &str) -> Option<AggressorOrder> {
+        self.runs.remove(symbol).map(|run| run.into_order(symbol))
+    }
+
+    /// Closes and returns every in-progress order (e.g. at end of a replay).
+    pub fn flush_all(&mut self) -> Vec<AggressorOrder> {
+        let mut orders: Vec<AggressorOrder> = self
+            .runs
+            .drain()
+            .map(|(symbol, run)| run.into_order(&symbol))
+            .collect();
+        orders.sort_by_key(|order| order.start_ts);
+        orders
+    }
+}
+
+impl Default for AggressorAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn continues_run(run: &Run, trade: &Trade, max_gap_ms: u64, max_price_drift: f64) -> bool {
+    if trade.side != run.side {
+        return false;
+    }
+
+    // Out-of-order prints within the tolerance still count as the same burst
+    if trade.timestamp.abs_diff(run.end_ts) > max_gap_ms {
+        return false;
+    }
+
+    // A buy aggressor lifts asks upward, a sell aggressor hits bids downward
+    let monotonic = match run.side {
+        TradeSide::Buy => trade.price >= run.last_price,
+        TradeSide::Sell => trade.price <= run.last_price,
+    };
+    if !monotonic {
+        return false;
+    }
+
+    let drift = (trade.price - run.first_price).abs();
+    drift <= run.first_price.abs() * max_price_drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(ts: u64, price: f64, qty: f64, side: TradeSide) -> Trade {
+        Trade::new(ts, "BTCUSDT", price, qty, ts.to_string(), side)
+    }
+
+    #[test]
+    fn test_sweep_across_three_levels_is_one_order() {
+        // Sweep spans 1% of price, so widen the default drift allowance
+        let mut aggregator = AggressorAggregator::new().with_max_price_drift(0.02);
+
+        // One buy order lifting 3 ask levels: 100.0 x2, 100.5 x2, 101.0 x1
+        let prints = vec![
+            trade(1000, 100.0, 1.0, TradeSide::Buy),
+            trade(1000, 100.0, 2.0, TradeSide::Buy),
+            trade(1000, 100.5, 1.0, TradeSide::Buy),
+            trade(1001, 100.5, 0.5, TradeSide::Buy),
+            trade(1001, 101.0, 0.5, TradeSide::Buy),
+        ];
+
+        for print in &prints {
+            assert!(aggregator.push(print).is_none());
+        }
+
+        let order = aggregator.flush("BTCUSDT").unwrap();
+        assert_eq!(order.trade_count, 5);
+        assert_eq!(order.total_qty, 5.0);
+        // (100*3 + 100.5*1.5 + 101*0.5) / 5 = 100.25
+        assert!((order.avg_price - 100.25).abs() < 1e-9);
+        assert_eq!(order.start_ts, 1000);
+        assert_eq!(order.end_ts, 1001);
+        assert_eq!(order.side, TradeSide::Buy);
+        assert!(order.is_sweep());
+        assert!(aggregator.flush("BTCUSDT").is_none());
+    }
+
+    #[test]
+    fn test_opposite_side_breaks_run() {
+        let mut aggregator = AggressorAggregator::new();
+
+        assert!(aggregator.push(&trade(1000, 100.0, 1.0, TradeSide::Buy)).is_none());
+        assert!(aggregator.push(&trade(1000, 100.0, 1.0, TradeSide::Buy)).is_none());
+
+        // Sell print interleaves: the buy run closes
+        let closed = aggregator.push(&trade(1000, 99.9, 3.0, TradeSide::Sell)).unwrap();
+        assert_eq!(closed.side, TradeSide::Buy);
+        assert_eq!(closed.trade_count, 2);
+        assert_eq!(closed.total_qty, 2.0);
+        assert!(!closed.is_sweep());
+
+        // Buy again: the sell run closes, a new buy run starts
+        let closed = aggregator.push(&trade(1001, 100.0, 1.0, TradeSide::Buy)).unwrap();
+        assert_eq!(closed.side, TradeSide::Sell);
+        assert_eq!(closed.trade_count, 1);
+
+        let current = aggregator.current("BTCUSDT").unwrap();
+        assert_eq!(current.side, TradeSide::Buy);
+        assert_eq!(current.trade_count, 1);
+    }
+
+    #[test]
+    fn test_time_gap_breaks_run() {
+        let mut aggregator = AggressorAggregator::new().with_max_gap_ms(10);
+
+        assert!(aggregator.push(&trade(1000, 100.0, 1.0, TradeSide::Sell)).is_none());
+        assert!(aggregator.push(&trade(1010, 100.0, 1.0, TradeSide::Sell)).is_none());
+        let closed = aggregator.push(&trade(1021, 100.0, 1.0, TradeSide::Sell)).unwrap();
+        assert_eq!(closed.trade_count, 2);
+    }
+
+    #[test]
+    fn test_price_reversal_and_drift_break_run() {
+        let mut aggregator = AggressorAggregator::new().with_max_price_drift(0.01);
+
+        // Sell run: price must not tick back up
+        aggregator.push(&trade(1000, 100.0, 1.0, TradeSide::Sell));
+        aggregator.push(&trade(1000, 99.5, 1.0, TradeSide::Sell));
+        let closed = aggregator.push(&trade(1000, 99.8, 1.0, TradeSide::Sell)).unwrap();
+        assert_eq!(closed.trade_count, 2);
+        assert_eq!(closed.last_price, 99.5);
+
+        // Current run starts at 99.8; 1% drift allows down to 98.802
+        assert!(aggregator.push(&trade(1000, 99.0, 1.0, TradeSide::Sell)).is_none());
+        let closed = aggregator.push(&trade(1000, 98.0, 1.0, TradeSide::Sell)).unwrap();
+        assert_eq!(closed.trade_count, 2);
+    }
+
+    #[test]
+    fn test_symbols_tracked_independently() {
+        let mut aggregator = AggressorAggregator::new();
+
+        aggregator.push(&trade(1000, 100.0, 1.0, TradeSide::Buy));
+        let eth = Trade::new(1000, "ETHUSDT", 3000.0, 2.0, "e1", TradeSide::Sell);
+        assert!(aggregator.push(&eth).is_none());
+        assert!(aggregator.push(&trade(1001, 100.0, 1.0, TradeSide::Buy)).is_none());
+
+        let orders = aggregator.flush_all();
+        assert_eq!(orders.len(), 2);
+        let btc = orders.iter().find(|o| o.symbol == "BTCUSDT").unwrap();
+        assert_eq!(btc.trade_count, 2);
+    }
+}
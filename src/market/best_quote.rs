@@ -0,0 +1,243 @@
+//! `BestQuoteTracker`: a book ticker derived from order book updates, for exchanges
+//! (or subscriptions) without a dedicated best bid/ask channel.
+//!
+//! Most book updates touch levels deep in the book. The tracker applies each one to
+//! a `LocalOrderBook` per exchange and symbol and only reports when the top of the
+//! book moved, so consumers see one event per quote change instead of the flood.
+
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::{Stream as FuturesStream, StreamExt};
+use tokio::sync::mpsc;
+
+use crate::market::data_stream::MarketDataStream;
+use crate::market::market_data::{BookTicker, ExchangeId, MarketData, OrderBookUpdate};
+use crate::market::order_book::{ApplyResult, LocalOrderBook};
+use crate::market::symbol::Symbol;
+
+/// A change of the best bid or ask.
+#[derive(Debug, Clone)]
+pub enum BestQuote {
+    Quote(BookTicker),
+    /// Best bid at or above best ask. The local book is wrong (a bad or missed
+    /// delta), so this isn't a tradable quote; resync the book from a snapshot.
+    Crossed(BookTicker),
+}
+
+impl BestQuote {
+    pub fn ticker(&self) -> &BookTicker {
+        match self {
+            BestQuote::Quote(ticker) | BestQuote::Crossed(ticker) => ticker,
+        }
+    }
+
+    pub fn is_crossed(&self) -> bool {
+        matches!(self, BestQuote::Crossed(_))
+    }
+}
+
+/// One book per (exchange, symbol) and the top of it last reported.
+#[derive(Debug, Default)]
+struct TrackedBook {
+    book: LocalOrderBook,
+    // bid price, bid quantity, ask price, ask quantity
+    last_top: Option<(f64, f64, f64, f64)>,
+}
+
+/// Turns order book updates into `BestQuote`s, only when the top of the book changes.
+#[derive(Debug, Default)]
+pub struct BestQuoteTracker {
+    books: HashMap<(ExchangeId, Symbol), TrackedBook>,
+}
+
+impl BestQuoteTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies the update to its symbol's book. Returns the new top of the book if
+    /// the best price or size on either side changed, and always after a snapshot.
+    /// None while a side is empty, and for updates the book refused (stale, or
+    /// after a sequence gap until the next snapshot).
+    pub fn apply(&mut self, update: &OrderBookUpdate) -> Option<BestQuote> {
+        let tracked = self.books.entry((update.exchange, update.symbol.clone())).or_default();
+        if tracked.book.apply(update) != ApplyResult::Applied {
+            return None;
+        }
+
+        let (bid, ask) = (tracked.book.best_bid()?, tracked.book.best_ask()?);
+        let top = (bid.price, bid.quantity, ask.price, ask.quantity);
+        if !update.is_snapshot && tracked.last_top == Some(top) {
+            return None;
+        }
+        tracked.last_top = Some(top);
+
+        let mut ticker = BookTicker::new(update.timestamp, update.symbol.clone(), top.0, top.1, top.2, top.3);
+        ticker.exchange = update.exchange;
+        ticker.received_at = update.received_at;
+        ticker.update_id = update.sequence;
+        Some(if bid.price >= ask.price { BestQuote::Crossed(ticker) } else { BestQuote::Quote(ticker) })
+    }
+
+    /// `apply` for order book items; anything else is ignored.
+    pub fn push(&mut self, market_data: &MarketData) -> Option<BestQuote> {
+        match market_data {
+            MarketData::OrderBook(update) => self.apply(update),
+            _ => None,
+        }
+    }
+
+    /// The book kept for `symbol` on `exchange`.
+    pub fn book(&self, exchange: ExchangeId, symbol: &str) -> Option<&LocalOrderBook> {
+        self.books.get(&(exchange, Symbol::from(symbol))).map(|tracked| &tracked.book)
+    }
+}
+
+/// A market data stream (or the client's receiver) reduced to best quote changes.
+/// See `BestQuoteTracker`.
+pub struct BestQuoteStream<S> {
+    inner: S,
+    tracker: BestQuoteTracker,
+    ready: VecDeque<BestQuote>,
+}
+
+impl<S: FuturesStream<Item = MarketData> + Unpin> BestQuoteStream<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, tracker: BestQuoteTracker::new(), ready: VecDeque::new() }
+    }
+
+    /// The next quote change; None once the feed ends.
+    pub async fn recv(&mut self) -> Option<BestQuote> {
+        self.next().await
+    }
+
+    pub fn tracker(&self) -> &BestQuoteTracker {
+        &self.tracker
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl BestQuoteStream<MarketDataStream> {
+    /// Wraps the receiver returned by `connect()`.
+    pub fn from_receiver(rx: mpsc::Receiver<MarketData>) -> Self {
+        Self::new(MarketDataStream::new(rx))
+    }
+}
+
+impl<S: FuturesStream<Item = MarketData> + Unpin> FuturesStream for BestQuoteStream<S> {
+    type Item = BestQuote;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<BestQuote>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(quote) = this.ready.pop_front() {
+                return Poll::Ready(Some(quote));
+            }
+            match this.inner.poll_next_unpin(cx) {
+                Poll::Ready(Some(market_data)) => this.ready.extend(this.tracker.push(&market_data)),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::PriceLevel;
+
+    fn levels(levels: &[(f64, f64)]) -> Vec<PriceLevel> {
+        levels.iter().map(|&(price, quantity)| PriceLevel::new(price, quantity)).collect()
+    }
+
+    fn snapshot() -> OrderBookUpdate {
+        OrderBookUpdate::snapshot(
+            1,
+            "BTCUSDT",
+            levels(&[(100.0, 1.0), (99.0, 2.0)]),
+            levels(&[(101.0, 1.0), (102.0, 2.0)]),
+        )
+    }
+
+    fn delta(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> OrderBookUpdate {
+        OrderBookUpdate::delta(2, "BTCUSDT", levels(bids), levels(asks))
+    }
+
+    #[test]
+    fn test_only_top_of_book_changes_emit() {
+        let mut tracker = BestQuoteTracker::new();
+        let quote = tracker.apply(&snapshot()).unwrap();
+        assert!(!quote.is_crossed());
+        assert_eq!(quote.ticker().bid_price, 100.0);
+        assert_eq!(quote.ticker().ask_price, 101.0);
+
+        // Deep levels only
+        assert!(tracker.apply(&delta(&[(98.0, 5.0)], &[(105.0, 1.0)])).is_none());
+        assert!(tracker.apply(&delta(&[(99.0, 0.0)], &[])).is_none());
+
+        // Size at the top changes
+        let quote = tracker.apply(&delta(&[(100.0, 3.0)], &[])).unwrap();
+        assert_eq!(quote.ticker().bid_quantity, 3.0);
+        assert_eq!(quote.ticker().timestamp, 2);
+        // Best ask removed: the next level becomes the top
+        let quote = tracker.apply(&delta(&[], &[(101.0, 0.0)])).unwrap();
+        assert_eq!(quote.ticker().ask_price, 102.0);
+        assert_eq!(quote.ticker().spread(), 2.0);
+    }
+
+    #[test]
+    fn test_snapshot_always_emits() {
+        let mut tracker = BestQuoteTracker::new();
+        tracker.apply(&snapshot());
+        assert!(tracker.apply(&snapshot()).is_some());
+    }
+
+    #[test]
+    fn test_crossed_book_is_flagged() {
+        let mut tracker = BestQuoteTracker::new();
+        tracker.apply(&snapshot());
+        // A bad delta puts a bid above the best ask
+        let quote = tracker.apply(&delta(&[(101.5, 1.0)], &[])).unwrap();
+        assert!(quote.is_crossed());
+        assert!(quote.ticker().spread() < 0.0);
+
+        let quote = tracker.apply(&delta(&[(101.5, 0.0)], &[])).unwrap();
+        assert!(!quote.is_crossed());
+    }
+
+    #[test]
+    fn test_books_are_per_exchange_and_symbol() {
+        let mut tracker = BestQuoteTracker::new();
+        let mut other = snapshot();
+        other.symbol = Symbol::from("ETHUSDT");
+        tracker.apply(&snapshot());
+        tracker.apply(&other);
+        // One-sided book: nothing to report yet
+        let mut one_sided = OrderBookUpdate::snapshot(1, "BTCUSDT", levels(&[(1.0, 1.0)]), vec![]);
+        one_sided.exchange = "Bitget";
+        assert!(tracker.apply(&one_sided).is_none());
+
+        assert!(tracker.book("", "BTCUSDT").is_some());
+        assert_eq!(tracker.book("Bitget", "BTCUSDT").unwrap().len(), (1, 0));
+        assert!(tracker.book("", "SOLUSDT").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_adapter() {
+        let (tx, rx) = mpsc::channel(8);
+        for update in [snapshot(), delta(&[(98.0, 1.0)], &[]), delta(&[], &[(100.5, 1.0)])] {
+            tx.send(MarketData::OrderBook(update)).await.unwrap();
+        }
+        drop(tx);
+
+        let quotes: Vec<BestQuote> = BestQuoteStream::from_receiver(rx).collect().await;
+        let asks: Vec<f64> = quotes.iter().map(|quote| quote.ticker().ask_price).collect();
+        assert_eq!(asks, vec![101.0, 100.5]);
+    }
+}
@@ -0,0 +1,213 @@
+//! `ClosedCandleFilter`: turns a feed of in-progress candle updates into one item
+//! per finished candle.
+//!
+//! Exchanges push the current candle on every trade and flag the last push as
+//! closed. Indicators should only see that last one, exactly once. Two things get
+//! in the way: some exchanges repeat the closed candle, and after a reconnect the
+//! closing push can be missed entirely (KuCoin never sends one at all). The filter
+//! drops the repeats and, when a newer candle opens while the previous one was
+//! never closed, closes the previous one itself with its last known values.
+
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::{Stream as FuturesStream, StreamExt};
+use tokio::sync::mpsc;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::data_stream::MarketDataStream;
+use crate::market::market_data::{ExchangeId, MarketData};
+use crate::market::symbol::Symbol;
+
+/// A finished candle with its streaming context.
+pub type ClosedCandle = (Symbol, Timeframe, Candle);
+
+/// The candle currently open for one (exchange, symbol, interval).
+#[derive(Debug)]
+struct OpenCandle {
+    latest: Candle,
+    closed: bool,
+}
+
+/// Yields each candle once, when it closes. State is kept per exchange, symbol and
+/// interval, keyed by candle open time; non-candle items are ignored.
+#[derive(Debug, Default)]
+pub struct ClosedCandleFilter {
+    open: HashMap<(ExchangeId, Symbol, Timeframe), OpenCandle>,
+}
+
+impl ClosedCandleFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one item. Returns the candles it closed, oldest first: none for an
+    /// in-progress update, a repeated close or a stale candle; one when a candle
+    /// closes; two when a closed candle arrives while the previous one was never
+    /// closed.
+    pub fn push(&mut self, market_data: &MarketData) -> Vec<ClosedCandle> {
+        let Some((symbol, interval, candle, is_closed)) = market_data.as_candle() else {
+            return Vec::new();
+        };
+        let mut closed = Vec::new();
+        let key = (market_data.exchange(), Symbol::from(symbol), interval);
+
+        match self.open.get_mut(&key) {
+            Some(open) if candle.get_timestamp() < open.latest.get_timestamp() => {}
+            Some(open) if candle.get_timestamp() == open.latest.get_timestamp() => {
+                if !open.closed {
+                    open.latest = *candle;
+                    open.closed = is_closed;
+                    if is_closed {
+                        closed.push((key.1.clone(), interval, *candle));
+                    }
+                }
+            }
+            previous => {
+                // A newer candle opened: the previous one is over whether or not we saw it close
+                if let Some(open) = previous
+                    && !open.closed
+                {
+                    closed.push((key.1.clone(), interval, open.latest));
+                }
+                if is_closed {
+                    closed.push((key.1.clone(), interval, *candle));
+                }
+                self.open.insert(key, OpenCandle { latest: *candle, closed: is_closed });
+            }
+        }
+        closed
+    }
+
+    /// Forgets all state, e.g. when switching feeds.
+    pub fn clear(&mut self) {
+        self.open.clear();
+    }
+}
+
+/// A market data stream (or the client's receiver) reduced to closed candles.
+/// See `ClosedCandleFilter`.
+pub struct ClosedCandleStream<S> {
+    inner: S,
+    filter: ClosedCandleFilter,
+    ready: VecDeque<ClosedCandle>,
+}
+
+impl<S: FuturesStream<Item = MarketData> + Unpin> ClosedCandleStream<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, filter: ClosedCandleFilter::new(), ready: VecDeque::new() }
+    }
+
+    /// The next closed candle; None once the feed ends.
+    pub async fn recv(&mut self) -> Option<ClosedCandle> {
+        self.next().await
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl ClosedCandleStream<MarketDataStream> {
+    /// Wraps the receiver returned by `connect()`.
+    pub fn from_receiver(rx: mpsc::Receiver<MarketData>) -> Self {
+        Self::new(MarketDataStream::new(rx))
+    }
+}
+
+impl<S: FuturesStream<Item = MarketData> + Unpin> FuturesStream for ClosedCandleStream<S> {
+    type Item = ClosedCandle;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ClosedCandle>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(candle) = this.ready.pop_front() {
+                return Poll::Ready(Some(candle));
+            }
+            match this.inner.poll_next_unpin(cx) {
+                Poll::Ready(Some(market_data)) => this.ready.extend(this.filter.push(&market_data)),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{Trade, TradeSide};
+
+    fn update(open_time: u64, close: f64, is_closed: bool) -> MarketData {
+        let candle = Candle::new(open_time, 1.0, 2.0, 0.5, close, 10.0);
+        MarketData::candle("BTCUSDT", Timeframe::M1, candle, is_closed).with_exchange("Binance")
+    }
+
+    fn closes(filter: &mut ClosedCandleFilter, items: &[MarketData]) -> Vec<(u64, f64)> {
+        items
+            .iter()
+            .flat_map(|item| filter.push(item))
+            .map(|(_, _, candle)| (candle.get_timestamp(), candle.get_close()))
+            .collect()
+    }
+
+    #[test]
+    fn test_only_the_close_comes_out() {
+        let mut filter = ClosedCandleFilter::new();
+        let items = [update(0, 1.0, false), update(0, 1.1, false), update(0, 1.2, true), update(60_000, 1.3, false)];
+        assert_eq!(closes(&mut filter, &items), vec![(0, 1.2)]);
+
+        let (symbol, interval, _) = filter.push(&update(60_000, 1.4, true)).remove(0);
+        assert_eq!(symbol, "BTCUSDT");
+        assert_eq!(interval, Timeframe::M1);
+    }
+
+    #[test]
+    fn test_duplicate_close_is_dropped() {
+        let mut filter = ClosedCandleFilter::new();
+        let items = [update(0, 1.0, false), update(0, 1.2, true), update(0, 1.2, true), update(0, 1.2, false)];
+        assert_eq!(closes(&mut filter, &items), vec![(0, 1.2)]);
+        // A stale candle after the next one opened is dropped too
+        assert_eq!(closes(&mut filter, &[update(60_000, 2.0, false), update(0, 1.2, true)]), vec![]);
+    }
+
+    #[test]
+    fn test_missed_close_is_synthesized() {
+        let mut filter = ClosedCandleFilter::new();
+        // The close of the 0 candle was lost (e.g. across a reconnect)
+        let items = [update(0, 1.0, false), update(0, 1.1, false), update(60_000, 2.0, false)];
+        assert_eq!(closes(&mut filter, &items), vec![(0, 1.1)]);
+
+        // Both missed close and a new close in one push, oldest first
+        assert_eq!(closes(&mut filter, &[update(120_000, 1.9, true)]), vec![(60_000, 2.0), (120_000, 1.9)]);
+    }
+
+    #[test]
+    fn test_state_is_per_exchange_symbol_and_interval() {
+        let mut filter = ClosedCandleFilter::new();
+        let other_exchange = update(60_000, 1.5, false).with_exchange("Bitget");
+        let candle = Candle::new(60_000, 1.0, 1.0, 1.0, 1.0, 1.0);
+        let other_symbol = MarketData::candle("ETHUSDT", Timeframe::M1, candle, false).with_exchange("Binance");
+        let other_interval = MarketData::candle("BTCUSDT", Timeframe::M5, candle, false).with_exchange("Binance");
+        let items = [update(0, 1.0, false), other_exchange, other_symbol, other_interval];
+        assert!(closes(&mut filter, &items).is_empty());
+        assert_eq!(closes(&mut filter, &[update(60_000, 2.0, false)]), vec![(0, 1.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_receiver_adapter() {
+        let (tx, rx) = mpsc::channel(8);
+        let trade = Trade::new(0, "BTCUSDT", 1.0, 1.0, "t", TradeSide::Buy);
+        for item in [update(0, 1.0, false), MarketData::Trade(trade), update(0, 1.5, true), update(0, 1.5, true)] {
+            tx.send(item).await.unwrap();
+        }
+        drop(tx);
+
+        let mut closed = ClosedCandleStream::from_receiver(rx);
+        let (_, _, candle) = closed.recv().await.unwrap();
+        assert_eq!(candle.get_close(), 1.5);
+        assert!(closed.recv().await.is_none());
+    }
+}
@@ -0,0 +1,66 @@
+//! Decompression helpers for `MessageParser::decode_binary` (feature `compression`).
+//!
+//! ```no_run
+//! # use cct::market::compression;
+//! # use cct::market::{DecodedFrame, MarketData, MessageParser, Stream};
+//! # struct HuobiParser;
+//! # impl MessageParser for HuobiParser {
+//! #     fn endpoint(&self) -> &str { "wss://api.huobi.pro/ws" }
+//! #     fn format_subscribe(&self, _stream: &Stream) -> String { String::new() }
+//! #     fn format_unsubscribe(&self, _stream: &Stream) -> String { String::new() }
+//! #     fn parse_message(&self, _msg: &str) -> Option<MarketData> { None }
+//! #     fn name(&self) -> &'static str { "huobi" }
+//! fn decode_binary(&self, data: &[u8]) -> Option<DecodedFrame> {
+//!     let text = compression::gunzip(data)?;
+//!     // Huobi: {"ping":1492420473027} must be answered with {"pong":1492420473027}
+//!     match text.strip_prefix(r#"{"ping":"#) {
+//!         Some(rest) => Some(DecodedFrame::Reply(format!(r#"{{"pong":{}"#, rest))),
+//!         None => Some(DecodedFrame::Text(text)),
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::io::Read;
+
+use flate2::read::{DeflateDecoder, GzDecoder};
+
+/// Decompresses a gzip payload (Huobi/HTX) into UTF-8 text.
+/// None if the data isn't valid gzip or the result isn't UTF-8.
+pub fn gunzip(data: &[u8]) -> Option<String> {
+    let mut text = String::new();
+    GzDecoder::new(data).read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+/// Decompresses a raw deflate payload (no zlib header, as OKX sends) into UTF-8 text.
+/// None if the data isn't valid deflate or the result isn't UTF-8.
+pub fn inflate(data: &[u8]) -> Option<String> {
+    let mut text = String::new();
+    DeflateDecoder::new(data).read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+
+    const PAYLOAD: &str = r#"{"ch":"market.btcusdt.trade.detail","ts":1630000000000}"#;
+
+    #[test]
+    fn test_round_trips() {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(PAYLOAD.as_bytes()).unwrap();
+        assert_eq!(gunzip(&gz.finish().unwrap()).as_deref(), Some(PAYLOAD));
+
+        let mut deflate = DeflateEncoder::new(Vec::new(), Compression::default());
+        deflate.write_all(PAYLOAD.as_bytes()).unwrap();
+        assert_eq!(inflate(&deflate.finish().unwrap()).as_deref(), Some(PAYLOAD));
+
+        assert_eq!(gunzip(b"not gzip"), None);
+    }
+}
@@ -0,0 +1,218 @@
+//! Client configuration: channel sizes, what happens when the consumer falls behind,
+//! and how hard `connect()` tries.
+
+use std::time::Duration;
+
+const DEFAULT_MARKET_DATA_CAPACITY: usize = 1000;
+const DEFAULT_WS_SEND_CAPACITY: usize = 100;
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Primary, then fallback
+const DEFAULT_CONNECT_ATTEMPTS: u32 = 2;
+const DEFAULT_CONNECT_RETRY_DELAY: Duration = Duration::from_millis(500);
+const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+const DEFAULT_PAUSE_BUFFER_CAPACITY: usize = 10_000;
+
+/// What the read task does with new market data when the consumer's channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Discard the message that doesn't fit. Default.
+    #[default]
+    DropNewest,
+    /// Discard the oldest buffered message to make room, so the consumer always
+    /// catches up to recent data.
+    DropOldest,
+    /// Wait for the consumer. Nothing is lost, but a slow consumer stalls the socket
+    /// reads (and eventually the exchange may disconnect).
+    Block,
+}
+
+/// What the read task does with market data that arrives while the client is paused
+/// (`WebSocketClient::pause()`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PausePolicy {
+    /// Hold up to `pause_buffer_capacity` items, evicting the oldest past that, and
+    /// deliver them in order on `resume()`. Default.
+    #[default]
+    Buffer,
+    /// Discard everything until `resume()`.
+    Drop,
+}
+
+/// Settings for `WebSocketClient::with_config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientConfig {
+    /// Capacity of the `MarketData` channel returned by `connect()`.
+    /// Order book streams need much more headroom than candles.
+    pub market_data_capacity: usize,
+    /// Capacity of the queue of outgoing frames (subscribes, pings).
+    pub ws_send_capacity: usize,
+    pub overflow: OverflowPolicy,
+    /// Limit for a single connection attempt (endpoint resolution excluded).
+    pub connect_timeout: Duration,
+    /// Attempts per connect, alternating primary and fallback endpoint.
+    pub connect_attempts: u32,
+    /// Pause between failed connection attempts.
+    pub connect_retry_delay: Duration,
+    /// Replace the connection before the exchange's limit
+    /// (`MessageParser::max_connection_duration_secs`) instead of waiting to be cut off.
+    /// The receiver from `connect()` stays the same.
+    pub auto_refresh: bool,
+    /// How long before that limit the refresh starts.
+    pub refresh_margin: Duration,
+    /// Connect here instead of `MessageParser::endpoint()` (no `resolve_endpoint` call),
+    /// e.g. a local mock server or a regional domain. Disables the parser's fallback.
+    pub endpoint_override: Option<String>,
+    /// Fallback for `endpoint_override`; also replaces the parser's fallback on its own.
+    pub fallback_override: Option<String>,
+    pub pause_policy: PausePolicy,
+    /// Items held while paused with `PausePolicy::Buffer`.
+    pub pause_buffer_capacity: usize,
+    /// Attach each frame's JSON to the items parsed from it (`MarketData::raw()`),
+    /// for fields the normalized types don't carry. Costs a second parse of every
+    /// frame, so off by default.
+    pub keep_raw: bool,
+}
+
+impl ClientConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Values below 1 are raised to 1.
+    pub fn with_market_data_capacity(mut self, capacity: usize) -> Self {
+        self.market_data_capacity = capacity.max(1);
+        self
+    }
+
+    /// Values below 1 are raised to 1.
+    pub fn with_ws_send_capacity(mut self, capacity: usize) -> Self {
+        self.ws_send_capacity = capacity.max(1);
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Values below 1 are raised to 1.
+    pub fn with_connect_attempts(mut self, attempts: u32) -> Self {
+        self.connect_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn with_connect_retry_delay(mut self, delay: Duration) -> Self {
+        self.connect_retry_delay = delay;
+        self
+    }
+
+    pub fn with_auto_refresh(mut self, auto_refresh: bool) -> Self {
+        self.auto_refresh = auto_refresh;
+        self
+    }
+
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    pub fn with_endpoint_override(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint_override = Some(endpoint.into());
+        self
+    }
+
+    pub fn with_fallback_override(mut self, endpoint: impl Into<String>) -> Self {
+        self.fallback_override = Some(endpoint.into());
+        self
+    }
+
+    pub fn with_pause_policy(mut self, policy: PausePolicy) -> Self {
+        self.pause_policy = policy;
+        self
+    }
+
+    /// Values below 1 are raised to 1.
+    pub fn with_pause_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.pause_buffer_capacity = capacity.max(1);
+        self
+    }
+
+    pub fn with_keep_raw(mut self, keep_raw: bool) -> Self {
+        self.keep_raw = keep_raw;
+        self
+    }
+
+    /// The primary endpoint to use, if overridden.
+    pub(crate) fn endpoint(&self) -> Option<&str> {
+        self.endpoint_override.as_deref()
+    }
+
+    /// The fallback endpoint to use: the override, else the parser's unless the
+    /// primary endpoint is overridden.
+    pub(crate) fn fallback<'a>(&'a self, parser_fallback: Option<&'a str>) -> Option<&'a str> {
+        match (&self.fallback_override, &self.endpoint_override) {
+            (Some(fallback), _) => Some(fallback),
+            (None, Some(_)) => None,
+            (None, None) => parser_fallback,
+        }
+    }
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            market_data_capacity: DEFAULT_MARKET_DATA_CAPACITY,
+            ws_send_capacity: DEFAULT_WS_SEND_CAPACITY,
+            overflow: OverflowPolicy::default(),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            connect_attempts: DEFAULT_CONNECT_ATTEMPTS,
+            connect_retry_delay: DEFAULT_CONNECT_RETRY_DELAY,
+            auto_refresh: false,
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            endpoint_override: None,
+            fallback_override: None,
+            pause_policy: PausePolicy::default(),
+            pause_buffer_capacity: DEFAULT_PAUSE_BUFFER_CAPACITY,
+            keep_raw: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builders_and_minimum_capacity() {
+        let config = ClientConfig::new()
+            .with_market_data_capacity(0)
+            .with_ws_send_capacity(10)
+            .with_overflow(OverflowPolicy::Block)
+            .with_connect_attempts(0)
+            .with_pause_buffer_capacity(0);
+        assert_eq!(config.market_data_capacity, 1);
+        assert_eq!(config.pause_buffer_capacity, 1);
+        assert_eq!(config.ws_send_capacity, 10);
+        assert_eq!(config.overflow, OverflowPolicy::Block);
+        assert_eq!(config.connect_attempts, 1);
+        assert_eq!(ClientConfig::default().overflow, OverflowPolicy::DropNewest);
+    }
+
+    #[test]
+    fn test_endpoint_override_replaces_fallback() {
+        let parser_fallback = Some("wss://fallback.example/ws");
+        assert_eq!(ClientConfig::new().fallback(parser_fallback), parser_fallback);
+
+        let config = ClientConfig::new().with_endpoint_override("ws://127.0.0.1:9000");
+        assert_eq!(config.endpoint(), Some("ws://127.0.0.1:9000"));
+        assert_eq!(config.fallback(parser_fallback), None);
+
+        let config = config.with_fallback_override("ws://127.0.0.1:9001");
+        assert_eq!(config.fallback(parser_fallback), Some("ws://127.0.0.1:9001"));
+    }
+}
@@ -0,0 +1,142 @@
+//! `MarketDataStream`: the client's receiver as a `futures_util::Stream`.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::Stream as FuturesStream;
+use tokio::sync::mpsc;
+
+use crate::market::best_quote::BestQuoteStream;
+use crate::market::closed_candles::ClosedCandleStream;
+use crate::market::market_data::MarketData;
+
+// Design: the filters are plain predicates kept on the stream itself rather than
+// futures combinators, so `candles_only().for_symbol(..)` still returns a
+// MarketDataStream (nameable, Unpin, and with `into_inner()` available).
+
+type Predicate = Box<dyn Fn(&MarketData) -> bool + Send + Sync>;
+
+/// Market data from `connect_stream()`. Ends when the client disconnects.
+///
+/// Works with any `StreamExt` combinator, e.g. `filter_map`, `take` or `select`
+/// to merge exchanges.
+pub struct MarketDataStream {
+    rx: mpsc::Receiver<MarketData>,
+    filters: Vec<Predicate>,
+}
+
+impl MarketDataStream {
+    pub fn new(rx: mpsc::Receiver<MarketData>) -> Self {
+        Self { rx, filters: Vec::new() }
+    }
+
+    /// Keeps only items for which `predicate` returns true. Filters stack.
+    pub fn filter_data(mut self, predicate: impl Fn(&MarketData) -> bool + Send + Sync + 'static) -> Self {
+        self.filters.push(Box::new(predicate));
+        self
+    }
+
+    pub fn candles_only(self) -> Self {
+        self.filter_data(MarketData::is_candle)
+    }
+
+    pub fn trades_only(self) -> Self {
+        self.filter_data(MarketData::is_trade)
+    }
+
+    pub fn order_books_only(self) -> Self {
+        self.filter_data(MarketData::is_order_book)
+    }
+
+    /// Keeps items whose symbol matches, ignoring case.
+    pub fn for_symbol(self, symbol: impl Into<String>) -> Self {
+        let symbol = symbol.into();
+        self.filter_data(move |market_data| market_data.symbol().eq_ignore_ascii_case(&symbol))
+    }
+
+    /// Only closed candles, each once. See `ClosedCandleFilter`.
+    pub fn closed_candles(self) -> ClosedCandleStream<MarketDataStream> {
+        ClosedCandleStream::new(self)
+    }
+
+    /// Best bid/ask changes derived from order book updates. See `BestQuoteTracker`.
+    pub fn best_quotes(self) -> BestQuoteStream<MarketDataStream> {
+        BestQuoteStream::new(self)
+    }
+
+    /// The underlying receiver. Filters are not applied to it.
+    pub fn into_inner(self) -> mpsc::Receiver<MarketData> {
+        self.rx
+    }
+
+    fn accepts(&self, market_data: &MarketData) -> bool {
+        self.filters.iter().all(|predicate| predicate(market_data))
+    }
+}
+
+impl From<mpsc::Receiver<MarketData>> for MarketDataStream {
+    fn from(rx: mpsc::Receiver<MarketData>) -> Self {
+        Self::new(rx)
+    }
+}
+
+impl FuturesStream for MarketDataStream {
+    type Item = MarketData;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<MarketData>> {
+        let this = self.get_mut();
+        loop {
+            match this.rx.poll_recv(cx) {
+                Poll::Ready(Some(market_data)) if !this.accepts(&market_data) => continue,
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::candle::Candle;
+    use crate::indicators::timeframe::Timeframe;
+    use crate::market::market_data::{Trade, TradeSide};
+    use futures_util::StreamExt;
+
+    fn trade(symbol: &str, price: f64) -> MarketData {
+        MarketData::Trade(Trade::new(0, symbol, price, 1.0, "t", TradeSide::Buy))
+    }
+
+    fn candle(symbol: &str) -> MarketData {
+        MarketData::candle(symbol, Timeframe::M1, Candle::new(0, 1.0, 1.0, 1.0, 1.0, 1.0), true)
+    }
+
+    async fn feed(items: Vec<MarketData>) -> MarketDataStream {
+        let (tx, rx) = mpsc::channel(items.len().max(1));
+        for item in items {
+            tx.send(item).await.unwrap();
+        }
+        MarketDataStream::new(rx)
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_with_channel() {
+        let stream = feed(vec![trade("BTCUSDT", 1.0), candle("BTCUSDT")]).await;
+        assert_eq!(stream.count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_filters_stack() {
+        let items = vec![
+            trade("BTCUSDT", 1.0),
+            candle("BTCUSDT"),
+            trade("ETHUSDT", 2.0),
+            trade("btcusdt", 3.0),
+        ];
+        let trades: Vec<MarketData> = feed(items.clone()).await.trades_only().for_symbol("BTCUSDT").collect().await;
+        let prices: Vec<f64> = trades.iter().filter_map(|d| d.as_trade().map(|t| t.price)).collect();
+        assert_eq!(prices, vec![1.0, 3.0]);
+
+        let candles = feed(items).await.candles_only().count().await;
+        assert_eq!(candles, 1);
+    }
+}
@@ -0,0 +1,372 @@
+//! Hands parsed market data to the consumer's channel according to an `OverflowPolicy`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, Notify};
+
+use crate::market::config::{OverflowPolicy, PausePolicy};
+use crate::market::market_data::MarketData;
+use crate::market::metrics::ClientMetrics;
+use crate::market::streams::Stream;
+
+// Design: DropNewest and Block map directly onto try_send and send. DropOldest
+// can't: only the receiver can take the oldest item out of an mpsc channel, and
+// the consumer owns it. So for DropOldest the read task pushes into an evicting
+// queue and a forwarder task moves items from there into the channel. The
+// channel itself then only needs room for one item.
+
+/// The consumer is gone; stop reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReceiverClosed;
+
+/// Sending half used by the read task.
+pub(crate) struct MarketDataSink {
+    target: Target,
+    metrics: Arc<ClientMetrics>,
+}
+
+enum Target {
+    Channel(mpsc::Sender<MarketData>, OverflowPolicy),
+    Queue(Arc<DropOldestQueue>),
+}
+
+impl MarketDataSink {
+    /// Wraps `tx`. For `DropOldest` this spawns the forwarder task, which ends
+    /// once the sink is dropped and the queue has drained.
+    pub(crate) fn new(
+        tx: mpsc::Sender<MarketData>,
+        overflow: OverflowPolicy,
+        queue_capacity: usize,
+        metrics: Arc<ClientMetrics>,
+    ) -> Self {
+        let target = match overflow {
+            OverflowPolicy::DropOldest => {
+                let queue = Arc::new(DropOldestQueue::new(queue_capacity));
+                tokio::spawn(forward(Arc::clone(&queue), tx));
+                Target::Queue(queue)
+            }
+            _ => Target::Channel(tx, overflow),
+        };
+        Self { target, metrics }
+    }
+
+    /// Delivers one item. Returns false if it had to be dropped.
+    pub(crate) async fn deliver(&self, market_data: MarketData) -> Result<bool, ReceiverClosed> {
+        match &self.target {
+            Target::Channel(tx, OverflowPolicy::Block) => {
+                tx.send(market_data).await.map_err(|_| ReceiverClosed)?;
+                Ok(true)
+            }
+            Target::Channel(tx, _) => match tx.try_send(market_data) {
+                Ok(()) => Ok(true),
+                Err(TrySendError::Full(_)) => {
+                    self.metrics.record_dropped();
+                    Ok(false)
+                }
+                Err(TrySendError::Closed(_)) => Err(ReceiverClosed),
+            },
+            Target::Queue(queue) => {
+                if queue.receiver_gone.load(Ordering::SeqCst) {
+                    return Err(ReceiverClosed);
+                }
+                let evicted = queue.push(market_data);
+                if evicted {
+                    self.metrics.record_dropped();
+                }
+                Ok(!evicted)
+            }
+        }
+    }
+}
+
+impl Drop for MarketDataSink {
+    fn drop(&mut self) {
+        if let Target::Queue(queue) = &self.target {
+            queue.sender_done.store(true, Ordering::SeqCst);
+            queue.notify.notify_one();
+        }
+    }
+}
+
+/// Holds market data back between `WebSocketClient::pause()` and `resume()`.
+/// The read task checks it before delivering and flushes it once resumed.
+pub(crate) struct PauseGate {
+    paused: AtomicBool,
+    policy: PausePolicy,
+    capacity: usize,
+    buffer: Mutex<VecDeque<MarketData>>,
+    /// Wakes the read task to flush the buffer without waiting for the next frame.
+    pub(crate) resumed: Notify,
+}
+
+impl PauseGate {
+    pub(crate) fn new(policy: PausePolicy, capacity: usize) -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            policy,
+            capacity: capacity.max(1),
+            buffer: Mutex::new(VecDeque::new()),
+            resumed: Notify::new(),
+        }
+    }
+
+    fn buffer(&self) -> MutexGuard<'_, VecDeque<MarketData>> {
+        self.buffer.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.resumed.notify_one();
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Buffers or drops `market_data` while paused; otherwise hands it back.
+    pub(crate) fn hold(&self, market_data: MarketData, metrics: &ClientMetrics) -> Option<MarketData> {
+        if !self.is_paused() {
+            return Some(market_data);
+        }
+        match self.policy {
+            PausePolicy::Drop => metrics.record_paused_dropped(),
+            PausePolicy::Buffer => {
+                let mut buffer = self.buffer();
+                if buffer.len() >= self.capacity && buffer.pop_front().is_some() {
+                    metrics.record_paused_dropped();
+                }
+                buffer.push_back(market_data);
+                metrics.record_paused_buffered();
+            }
+        }
+        None
+    }
+
+    /// Everything buffered, oldest first. Empty while still paused.
+    pub(crate) fn take_buffered(&self) -> VecDeque<MarketData> {
+        if self.is_paused() {
+            return VecDeque::new();
+        }
+        std::mem::take(&mut *self.buffer())
+    }
+}
+
+type Route = (Stream, Arc<MarketDataSink>);
+
+/// Dedicated sinks for streams subscribed with `WebSocketClient::subscribe_routed()`,
+/// shared by the client (which adds and removes routes) and the read task.
+#[derive(Clone, Default)]
+pub(crate) struct Routes(Arc<Mutex<Vec<Route>>>);
+
+impl Routes {
+    fn routes(&self) -> MutexGuard<'_, Vec<Route>> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Routes `stream` to `sink`, replacing (and so closing) an earlier route for it.
+    pub(crate) fn insert(&self, stream: Stream, sink: MarketDataSink) {
+        let mut routes = self.routes();
+        routes.retain(|(routed, _)| routed != &stream);
+        routes.push((stream, Arc::new(sink)));
+    }
+
+    /// Drops the route for `stream`; its receiver ends once drained.
+    pub(crate) fn remove(&self, stream: &Stream) {
+        self.routes().retain(|(routed, _)| routed != stream);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.routes().clear();
+    }
+
+    /// The first route whose stream matches `market_data`.
+    pub(crate) fn find(&self, market_data: &MarketData) -> Option<Route> {
+        self.routes()
+            .iter()
+            .find(|(stream, _)| stream.matches(market_data))
+            .map(|(stream, sink)| (stream.clone(), Arc::clone(sink)))
+    }
+}
+
+/// Bounded queue that evicts its oldest item to make room.
+struct DropOldestQueue {
+    items: Mutex<VecDeque<MarketData>>,
+    capacity: usize,
+    notify: Notify,
+    sender_done: AtomicBool,
+    receiver_gone: AtomicBool,
+}
+
+impl DropOldestQueue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1),
+            notify: Notify::new(),
+            sender_done: AtomicBool::new(false),
+            receiver_gone: AtomicBool::new(false),
+        }
+    }
+
+    fn items(&self) -> MutexGuard<'_, VecDeque<MarketData>> {
+        self.items.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns true if the oldest item was evicted to make room.
+    fn push(&self, market_data: MarketData) -> bool {
+        let evicted = {
+            let mut items = self.items();
+            let evicted = items.len() >= self.capacity && items.pop_front().is_some();
+            items.push_back(market_data);
+            evicted
+        };
+        self.notify.notify_one();
+        evicted
+    }
+}
+
+async fn forward(queue: Arc<DropOldestQueue>, tx: mpsc::Sender<MarketData>) {
+    loop {
+        let next = queue.items().pop_front();
+        match next {
+            Some(market_data) => {
+                if tx.send(market_data).await.is_err() {
+                    queue.receiver_gone.store(true, Ordering::SeqCst);
+                    return;
+                }
+            }
+            None if queue.sender_done.load(Ordering::SeqCst) => return,
+            // A notify_one without a waiter is stored, so a push between the
+            // pop above and this await is not missed
+            None => queue.notify.notified().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{Trade, TradeSide};
+
+    fn trade(price: f64) -> MarketData {
+        MarketData::Trade(Trade::new(0, "BTCUSDT", price, 1.0, "t", TradeSide::Buy))
+    }
+
+    fn price(market_data: Option<MarketData>) -> f64 {
+        match market_data {
+            Some(MarketData::Trade(trade)) => trade.price,
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_newest_counts_drops() {
+        let (tx, mut rx) = mpsc::channel(2);
+        let metrics = Arc::new(ClientMetrics::default());
+        let sink = MarketDataSink::new(tx, OverflowPolicy::DropNewest, 2, Arc::clone(&metrics));
+
+        for p in [1.0, 2.0, 3.0] {
+            sink.deliver(trade(p)).await.unwrap();
+        }
+        assert_eq!(metrics.snapshot().dropped_messages, 1);
+        assert_eq!(price(rx.recv().await), 1.0);
+        assert_eq!(price(rx.recv().await), 2.0);
+
+        drop(rx);
+        assert_eq!(sink.deliver(trade(4.0)).await, Err(ReceiverClosed));
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_keeps_most_recent() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let metrics = Arc::new(ClientMetrics::default());
+        let sink = MarketDataSink::new(tx, OverflowPolicy::DropOldest, 3, Arc::clone(&metrics));
+
+        // The forwarder can't run until we yield, so all ten land in the queue
+        for p in 1..=10 {
+            sink.deliver(trade(p as f64)).await.unwrap();
+        }
+        drop(sink);
+
+        let mut prices = Vec::new();
+        while let Some(market_data) = rx.recv().await {
+            prices.push(price(Some(market_data)));
+        }
+        assert_eq!(prices, vec![8.0, 9.0, 10.0]);
+        assert_eq!(metrics.snapshot().dropped_messages, 7);
+    }
+
+    #[test]
+    fn test_pause_gate_buffers_until_resumed() {
+        let metrics = ClientMetrics::default();
+        let gate = PauseGate::new(PausePolicy::Buffer, 2);
+        assert!(gate.hold(trade(1.0), &metrics).is_some());
+
+        gate.pause();
+        for p in [2.0, 3.0, 4.0] {
+            assert!(gate.hold(trade(p), &metrics).is_none());
+        }
+        assert!(gate.take_buffered().is_empty());
+        gate.resume();
+        let prices: Vec<f64> = gate.take_buffered().into_iter().map(|data| price(Some(data))).collect();
+        assert_eq!(prices, vec![3.0, 4.0]);
+
+        let stats = metrics.snapshot();
+        assert_eq!((stats.paused_buffered, stats.paused_dropped), (3, 1));
+    }
+
+    #[test]
+    fn test_pause_gate_drop_policy() {
+        let metrics = ClientMetrics::default();
+        let gate = PauseGate::new(PausePolicy::Drop, 2);
+        gate.pause();
+        assert!(gate.hold(trade(1.0), &metrics).is_none());
+        gate.resume();
+        assert!(gate.take_buffered().is_empty());
+        assert_eq!(metrics.snapshot().paused_dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_routes_match_and_close_on_remove() {
+        let metrics = Arc::new(ClientMetrics::default());
+        let routes = Routes::default();
+        let (tx, mut rx) = mpsc::channel(4);
+        routes.insert(Stream::trades("BTCUSDT"), MarketDataSink::new(tx, OverflowPolicy::DropNewest, 4, metrics));
+
+        assert!(routes.find(&MarketData::Trade(Trade::new(0, "ETHUSDT", 1.0, 1.0, "t", TradeSide::Buy))).is_none());
+        let (stream, sink) = routes.find(&trade(1.0)).unwrap();
+        assert_eq!(stream, Stream::trades("BTCUSDT"));
+        sink.deliver(trade(1.0)).await.unwrap();
+        drop(sink);
+
+        routes.remove(&stream);
+        assert!(routes.find(&trade(2.0)).is_none());
+        assert_eq!(price(rx.recv().await), 1.0);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_consumer() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let sink = MarketDataSink::new(tx, OverflowPolicy::Block, 1, Arc::default());
+
+        let producer = tokio::spawn(async move {
+            for p in 1..=5 {
+                sink.deliver(trade(p as f64)).await.unwrap();
+            }
+        });
+        let mut prices = Vec::new();
+        for _ in 0..5 {
+            prices.push(price(rx.recv().await));
+        }
+        producer.await.unwrap();
+        assert_eq!(prices, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+}
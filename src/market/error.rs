@@ -0,0 +1,121 @@
+//! Error type returned by the market module's clients and providers.
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite;
+
+use crate::market::streams::Stream;
+
+/// Everything that can go wrong talking to an exchange.
+/// Match on the variant to tell, e.g., a missing `connect()` from a failed handshake.
+#[derive(Debug)]
+pub enum CctError {
+    /// The operation needs an open connection: `connect()` wasn't called, or the
+    /// connection dropped before the operation completed.
+    NotConnected,
+    /// Resolving the endpoint or the WebSocket handshake failed.
+    ConnectFailed {
+        endpoint: String,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The connection's outgoing queue is closed (its write task has ended).
+    SendFailed,
+    /// WebSocket protocol or I/O error on an established connection.
+    WebSocket(tungstenite::Error),
+    /// An exchange response could not be understood.
+    ParseFailed { exchange: &'static str, message: String },
+    /// The stream can't be subscribed, either because the exchange doesn't offer it
+    /// or because the exchange refused the request.
+    SubscriptionRejected { stream: Stream, reason: String },
+    /// The exchange didn't acknowledge a subscription in time.
+    AckTimeout { stream: Stream, timeout: Duration },
+    /// The `MarketData` receiver returned by `connect()` was dropped or the feed ended.
+    ReceiverClosed,
+    /// Invalid client or parser configuration.
+    InvalidConfig(String),
+    /// No exchange with this name was added to the `MultiExchangeManager`.
+    UnknownExchange(String),
+}
+
+impl fmt::Display for CctError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CctError::NotConnected => write!(f, "not connected"),
+            CctError::ConnectFailed { endpoint, source } => {
+                write!(f, "failed to connect to {}: {}", endpoint, source)
+            }
+            CctError::SendFailed => write!(f, "connection is closed; message not sent"),
+            CctError::WebSocket(e) => write!(f, "websocket error: {}", e),
+            CctError::ParseFailed { exchange, message } => {
+                write!(f, "failed to parse {} response: {}", exchange, message)
+            }
+            CctError::SubscriptionRejected { stream, reason } => {
+                write!(f, "subscription to {:?} rejected: {}", stream, reason)
+            }
+            CctError::AckTimeout { stream, timeout } => {
+                write!(f, "no acknowledgement for {:?} within {:?}", stream, timeout)
+            }
+            CctError::ReceiverClosed => write!(f, "market data receiver is closed"),
+            CctError::InvalidConfig(message) => write!(f, "invalid configuration: {}", message),
+            CctError::UnknownExchange(name) => write!(f, "unknown exchange: {}", name),
+        }
+    }
+}
+
+impl Error for CctError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CctError::ConnectFailed { source, .. } => Some(source.as_ref()),
+            CctError::WebSocket(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<tungstenite::Error> for CctError {
+    fn from(e: tungstenite::Error) -> Self {
+        CctError::WebSocket(e)
+    }
+}
+
+impl<T> From<mpsc::error::SendError<T>> for CctError {
+    fn from(_: mpsc::error::SendError<T>) -> Self {
+        CctError::SendFailed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_source() {
+        let err = CctError::ConnectFailed {
+            endpoint: "wss://example.invalid/ws".to_string(),
+            source: "handshake refused".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "failed to connect to wss://example.invalid/ws: handshake refused"
+        );
+        assert_eq!(err.source().unwrap().to_string(), "handshake refused");
+        assert!(CctError::NotConnected.source().is_none());
+
+        let rejected = CctError::SubscriptionRejected {
+            stream: Stream::trades("BTCUSDT"),
+            reason: "unknown symbol".to_string(),
+        };
+        assert!(rejected.to_string().contains("unknown symbol"));
+    }
+
+    #[tokio::test]
+    async fn test_from_closed_channel() {
+        let (tx, rx) = mpsc::channel::<u8>(1);
+        drop(rx);
+        let err: CctError = tx.send(1).await.unwrap_err().into();
+        assert!(matches!(err, CctError::SendFailed));
+    }
+}
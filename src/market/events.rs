@@ -0,0 +1,48 @@
+//! Connection-level events, delivered separately from market data.
+
+use std::time::Instant;
+
+use crate::market::streams::Stream;
+
+/// Something happened to the connection or one of its subscriptions.
+/// Obtain a receiver with `WebSocketClient::subscribe_events()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    /// A connection attempt is starting (1-based within one connect or reconnect).
+    Connecting { attempt: u32, endpoint: String },
+    /// A WebSocket connection was established (initially or after a reconnect).
+    Connected { endpoint: String },
+    /// The connection ended: closed by either side, errored, or `disconnect()` was called.
+    Disconnected { reason: String },
+    /// A reconnect attempt is about to start (1-based, counted since the last healthy connection).
+    Reconnecting { attempt: u32 },
+    /// Subscriptions were re-sent on a new connection.
+    SubscriptionRestored { count: usize },
+    /// A subscribed stream kept producing messages that failed to parse
+    /// (see `StreamHealthPolicy`). Usually means the exchange changed the payload
+    /// or renamed the channel. `sample_error` is why the last message was rejected
+    /// (`MessageParser::parse_failure`) followed by the start of its payload.
+    StreamBroken { stream: Stream, sample_error: String },
+}
+
+/// Where a client's connection is now. Unlike the events, this is kept up to date by
+/// the background tasks as well: a connection dropped by the server reads as
+/// `Disconnected` (or `Connecting` while auto-reconnect retries) right away.
+/// Watch it with `WebSocketClient::watch_state()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    /// Opening a socket: `connect()`, `reconnect()` or an automatic reconnect.
+    Connecting,
+    /// The current socket has been open since `since` (reset by reconnects and refreshes).
+    Connected { since: Instant },
+    /// `disconnect()` is closing the connection.
+    Closing,
+}
+
+impl ConnectionState {
+    pub fn is_connected(&self) -> bool {
+        matches!(self, ConnectionState::Connected { .. })
+    }
+}
@@ -0,0 +1,152 @@
+//! `LatencyTracker`: how far behind the exchange a feed is running.
+//!
+//! Latency here is `received_at - event time` for each delivered item, so it
+//! includes the exchange's own publishing delay and any clock skew between the
+//! exchange and this machine. Skew can make the difference negative; those samples
+//! count as zero and are reported separately in `LatencyStats::negative`.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::market::market_data::MarketData;
+
+// Design: a window of the most recent samples rather than a cumulative histogram,
+// so the percentiles follow the feed when it falls behind and recovers. Sorting a
+// copy of the window on `stats()` is cheap next to how often stats are read.
+
+/// Samples kept by `LatencyTracker::default()`.
+pub const DEFAULT_LATENCY_WINDOW: usize = 1024;
+
+/// Percentiles over the most recent samples. All zero before the first sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyStats {
+    /// Samples in the window the percentiles were taken over.
+    pub samples: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// Samples with a receive time before the event time (clock skew), over the
+    /// tracker's lifetime. Recorded as zero latency.
+    pub negative: u64,
+}
+
+/// Rolling latency percentiles over the last `window` items.
+#[derive(Debug, Clone)]
+pub struct LatencyTracker {
+    window: usize,
+    samples_ms: VecDeque<u64>,
+    negative: u64,
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_LATENCY_WINDOW)
+    }
+}
+
+impl LatencyTracker {
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            samples_ms: VecDeque::with_capacity(window),
+            negative: 0,
+        }
+    }
+
+    /// Records one sample; both times are ms since the Unix epoch.
+    pub fn record(&mut self, received_at: u64, event_time: u64) {
+        if received_at < event_time {
+            self.negative += 1;
+        }
+        if self.samples_ms.len() == self.window {
+            self.samples_ms.pop_front();
+        }
+        self.samples_ms.push_back(received_at.saturating_sub(event_time));
+    }
+
+    /// Records `market_data` if it has both a receive time and an event time.
+    pub fn record_data(&mut self, market_data: &MarketData) {
+        let received_at = market_data.received_at();
+        if received_at == 0 {
+            return;
+        }
+        if let Some(event_time) = market_data.event_time() {
+            self.record(received_at, event_time);
+        }
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        let mut sorted: Vec<u64> = self.samples_ms.iter().copied().collect();
+        sorted.sort_unstable();
+        // Nearest-rank percentile
+        let percentile = |p: f64| match sorted.len() {
+            0 => Duration::ZERO,
+            len => {
+                let rank = ((p * len as f64).ceil() as usize).clamp(1, len);
+                Duration::from_millis(sorted[rank - 1])
+            }
+        };
+        LatencyStats {
+            samples: sorted.len(),
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: percentile(1.0),
+            negative: self.negative,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{Trade, TradeSide};
+
+    #[test]
+    fn test_percentiles_over_window() {
+        let mut tracker = LatencyTracker::new(100);
+        assert_eq!(tracker.stats(), LatencyStats::default());
+
+        for latency in 1..=100 {
+            tracker.record(1_000 + latency, 1_000);
+        }
+        let stats = tracker.stats();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.p50, Duration::from_millis(50));
+        assert_eq!(stats.p95, Duration::from_millis(95));
+        assert_eq!(stats.p99, Duration::from_millis(99));
+        assert_eq!(stats.max, Duration::from_millis(100));
+
+        // Older samples roll out of the window
+        for _ in 0..100 {
+            tracker.record(1_005, 1_000);
+        }
+        let stats = tracker.stats();
+        assert_eq!((stats.p50, stats.max), (Duration::from_millis(5), Duration::from_millis(5)));
+    }
+
+    #[test]
+    fn test_clock_skew_clamped_and_counted() {
+        let mut tracker = LatencyTracker::new(10);
+        tracker.record(900, 1_000);
+        tracker.record(1_010, 1_000);
+        let stats = tracker.stats();
+        assert_eq!(stats.negative, 1);
+        assert_eq!(stats.p50, Duration::ZERO);
+        assert_eq!(stats.max, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_record_data_needs_both_times() {
+        let mut tracker = LatencyTracker::default();
+        let mut trade = MarketData::Trade(Trade::new(1_000, "BTCUSDT", 1.0, 1.0, "t", TradeSide::Buy));
+        tracker.record_data(&trade);
+        assert_eq!(tracker.stats().samples, 0);
+
+        trade.set_received_at(1_250);
+        tracker.record_data(&trade);
+        assert_eq!(tracker.stats().p50, Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,325 @@
+//! Ordered shutdown for composite pipelines (clients, processors, sinks).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use tokio::sync::Mutex;
+
+use crate::engine::callback::panic_message;
+use crate::market::manager::MultiExchangeManager;
+use crate::market::message_parser::MessageParser;
+use crate::market::recorder::RecorderHandle;
+use crate::market::sharded_client::ShardedClient;
+use crate::market::websocket_client::WebSocketClient;
+
+// Design: shutting down in the wrong order loses data - if a recorder closes
+// before the client stops, the last messages in flight are dropped. Components
+// register with a stage; stages run in order (Intake -> Processing -> Sink) and
+// components within a stage run in registration order. Each hook runs in its own
+// task so a panic or a hang is contained to that component.
+
+/// When a component shuts down relative to the others. Lower stages go first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShutdownStage {
+    /// Stops data entering the pipeline (exchange clients, replays)
+    Intake,
+    /// Transforms data (aggregators, routers, indicator engines)
+    Processing,
+    /// Persists or forwards data (recorders, stores) - flushed last
+    Sink,
+}
+
+/// A component with an async shutdown hook.
+/// The returned future is boxed so hooks can be stored as trait objects.
+pub trait ShutdownHook: Send + 'static {
+    fn shutdown(&mut self) -> BoxFuture<'_, ()>;
+}
+
+impl<P: MessageParser> ShutdownHook for WebSocketClient<P> {
+    fn shutdown(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(self.disconnect())
+    }
+}
+
+impl<P: MessageParser> ShutdownHook for ShardedClient<P> {
+    fn shutdown(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(self.disconnect())
+    }
+}
+
+impl ShutdownHook for MultiExchangeManager {
+    fn shutdown(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(MultiExchangeManager::shutdown(self))
+    }
+}
+
+/// Register at `ShutdownStage::Sink`, so the client has stopped and its last
+/// messages are recorded before the file is closed.
+impl ShutdownHook for RecorderHandle {
+    fn shutdown(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            if let Err(e) = self.stop().await {
+                eprintln!("[Recorder] {}", e);
+            }
+        })
+    }
+}
+
+/// Lets a component stay usable elsewhere while registered with a Pipeline.
+impl<T: ShutdownHook> ShutdownHook for Arc<Mutex<T>> {
+    fn shutdown(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.lock().await.shutdown().await;
+        })
+    }
+}
+
+/// Result of shutting down one component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    Completed,
+    TimedOut,
+    /// The hook panicked; holds the panic message if it was a string
+    Panicked(String),
+}
+
+/// Per-component outcomes, in the order the hooks were invoked.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    pub outcomes: Vec<(String, ShutdownStage, ShutdownOutcome)>,
+}
+
+impl ShutdownReport {
+    /// True if every component completed within its timeout.
+    pub fn all_completed(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|(_, _, outcome)| *outcome == ShutdownOutcome::Completed)
+    }
+
+    pub fn completed(&self) -> Vec<&str> {
+        self.names_with(|outcome| *outcome == ShutdownOutcome::Completed)
+    }
+
+    pub fn timed_out(&self) -> Vec<&str> {
+        self.names_with(|outcome| *outcome == ShutdownOutcome::TimedOut)
+    }
+
+    pub fn panicked(&self) -> Vec<&str> {
+        self.names_with(|outcome| matches!(outcome, ShutdownOutcome::Panicked(_)))
+    }
+
+    fn names_with(&self, predicate: impl Fn(&ShutdownOutcome) -> bool) -> Vec<&str> {
+        self.outcomes
+            .iter()
+            .filter(|(_, _, outcome)| predicate(outcome))
+            .map(|(name, _, _)| name.as_str())
+            .collect()
+    }
+}
+
+struct Registration {
+    name: String,
+    stage: ShutdownStage,
+    component: Box<dyn ShutdownHook>,
+}
+
+/// Registry of components that need coordinated shutdown.
+#[derive(Default)]
+pub struct Pipeline {
+    components: Vec<Registration>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a component under a name (used in the report).
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        stage: ShutdownStage,
+        component: impl ShutdownHook,
+    ) -> &mut Self {
+        self.components.push(Registration {
+            name: name.into(),
+            stage,
+            component: Box::new(component),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Shuts every component down in stage order, giving each up to `timeout`.
+    /// A component that hangs or panics is reported and skipped; the rest still run.
+    pub async fn shutdown(self, timeout: Duration) -> ShutdownReport {
+        let mut components = self.components;
+        // Stable sort keeps registration order within a stage
+        components.sort_by_key(|registration| registration.stage);
+
+        let mut report = ShutdownReport::default();
+        for Registration { name, stage, mut component } in components {
+            let handle = tokio::spawn(async move {
+                component.shutdown().await;
+            });
+            let abort = handle.abort_handle();
+
+            let outcome = match tokio::time::timeout(timeout, handle).await {
+                Ok(Ok(())) => ShutdownOutcome::Completed,
+                Ok(Err(join_error)) if join_error.is_panic() => {
+                    ShutdownOutcome::Panicked(panic_message(join_error.into_panic()))
+                }
+                Ok(Err(_cancelled)) => ShutdownOutcome::TimedOut,
+                Err(_elapsed) => {
+                    abort.abort();
+                    ShutdownOutcome::TimedOut
+                }
+            };
+
+            if outcome != ShutdownOutcome::Completed {
+                eprintln!("[Pipeline] {} did not shut down cleanly: {:?}", name, outcome);
+            }
+            report.outcomes.push((name, stage, outcome));
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::MarketData;
+    use crate::market::streams::Stream;
+
+    type Log = Arc<std::sync::Mutex<Vec<String>>>;
+
+    enum Behavior {
+        Ok,
+        Hang,
+        Panic,
+    }
+
+    struct MockComponent {
+        name: &'static str,
+        log: Log,
+        behavior: Behavior,
+    }
+
+    impl ShutdownHook for MockComponent {
+        fn shutdown(&mut self) -> BoxFuture<'_, ()> {
+            Box::pin(async move {
+                self.log.lock().unwrap().push(self.name.to_string());
+                match self.behavior {
+                    Behavior::Ok => {}
+                    Behavior::Hang => tokio::time::sleep(Duration::from_secs(60)).await,
+                    Behavior::Panic => panic!("{} exploded", self.name),
+                }
+            })
+        }
+    }
+
+    fn mock(name: &'static str, log: &Log, behavior: Behavior) -> MockComponent {
+        MockComponent {
+            name,
+            log: Arc::clone(log),
+            behavior,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_runs_in_stage_order() {
+        let log: Log = Arc::default();
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .register("recorder", ShutdownStage::Sink, mock("recorder", &log, Behavior::Ok))
+            .register("engine", ShutdownStage::Processing, mock("engine", &log, Behavior::Ok))
+            .register("client", ShutdownStage::Intake, mock("client", &log, Behavior::Ok))
+            .register("router", ShutdownStage::Processing, mock("router", &log, Behavior::Ok));
+
+        let report = pipeline.shutdown(Duration::from_secs(1)).await;
+
+        assert_eq!(*log.lock().unwrap(), vec!["client", "engine", "router", "recorder"]);
+        assert!(report.all_completed());
+        assert_eq!(report.completed(), vec!["client", "engine", "router", "recorder"]);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_is_reported_and_rest_continue() {
+        let log: Log = Arc::default();
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .register("client", ShutdownStage::Intake, mock("client", &log, Behavior::Hang))
+            .register("recorder", ShutdownStage::Sink, mock("recorder", &log, Behavior::Ok));
+
+        let report = pipeline.shutdown(Duration::from_millis(20)).await;
+
+        assert_eq!(report.timed_out(), vec!["client"]);
+        assert_eq!(report.completed(), vec!["recorder"]);
+        assert!(!report.all_completed());
+    }
+
+    #[tokio::test]
+    async fn test_panicking_component_does_not_stop_others() {
+        let log: Log = Arc::default();
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .register("engine", ShutdownStage::Processing, mock("engine", &log, Behavior::Panic))
+            .register("recorder", ShutdownStage::Sink, mock("recorder", &log, Behavior::Ok));
+
+        let report = pipeline.shutdown(Duration::from_secs(1)).await;
+
+        assert_eq!(report.panicked(), vec!["engine"]);
+        assert_eq!(report.completed(), vec!["recorder"]);
+        match &report.outcomes[0].2 {
+            ShutdownOutcome::Panicked(message) => assert_eq!(message, "engine exploded"),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert_eq!(*log.lock().unwrap(), vec!["engine", "recorder"]);
+    }
+
+    struct NullParser;
+
+    impl MessageParser for NullParser {
+        fn endpoint(&self) -> &str {
+            "wss://example.invalid/ws"
+        }
+
+        fn format_subscribe(&self, _stream: &Stream) -> String {
+            String::new()
+        }
+
+        fn format_unsubscribe(&self, _stream: &Stream) -> String {
+            String::new()
+        }
+
+        fn parse_message(&self, _msg: &str) -> Option<MarketData> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "Null"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shared_client_implements_hook() {
+        let client = Arc::new(Mutex::new(WebSocketClient::new(NullParser)));
+        let mut pipeline = Pipeline::new();
+        pipeline.register("client", ShutdownStage::Intake, Arc::clone(&client));
+
+        let report = pipeline.shutdown(Duration::from_secs(1)).await;
+
+        assert!(report.all_completed());
+        assert!(!client.lock().await.is_connected());
+    }
+}
@@ -0,0 +1,503 @@
+//! Runs clients for several exchanges behind one merged receiver.
+
+use std::sync::{Arc, Mutex as StdMutex};
+
+use futures_util::future::BoxFuture;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::market::error::CctError;
+use crate::market::market_data::{ExchangeId, MarketData};
+use crate::market::message_parser::MessageParser;
+use crate::market::replay::ReplayClient;
+use crate::market::sharded_client::ShardedClient;
+use crate::market::streams::Stream;
+use crate::market::websocket_client::{Backoff, ReconnectPolicy, WebSocketClient};
+
+// Design: each exchange gets a supervisor task that owns the client's receiver,
+// tags every item with the exchange name and forwards it to the merged channel.
+// When a client's feed ends without `shutdown()`, the supervisor reopens it
+// (with backoff) and keeps forwarding - so clients don't need their own
+// auto-reconnect, though it does no harm. Clients sit behind an async Mutex
+// because both the supervisor and the manager's methods drive them.
+
+const DEFAULT_CAPACITY: usize = 1000;
+
+/// Object-safe view of a client, so clients for different exchanges (different
+/// parser types) can be managed together. Implemented for `WebSocketClient` and
+/// `ShardedClient`.
+pub trait ExchangeClient: Send + 'static {
+    fn name(&self) -> ExchangeId;
+    fn is_connected(&self) -> bool;
+    fn subscriptions(&self) -> Vec<Stream>;
+    fn connect(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>>;
+    fn subscribe(&mut self, stream: Stream) -> BoxFuture<'_, Result<(), CctError>>;
+    fn subscribe_many(&mut self, streams: Vec<Stream>) -> BoxFuture<'_, Result<(), CctError>>;
+    fn unsubscribe<'a>(&'a mut self, stream: &'a Stream) -> BoxFuture<'a, Result<(), CctError>>;
+    /// Replaces the connection; data keeps arriving on the same receiver.
+    fn reconnect(&mut self) -> BoxFuture<'_, Result<(), CctError>>;
+    /// Replaces a connection whose feed has ended, restoring its subscriptions.
+    fn reopen(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>>;
+    fn disconnect(&mut self) -> BoxFuture<'_, ()>;
+}
+
+impl<P: MessageParser> ExchangeClient for WebSocketClient<P> {
+    fn name(&self) -> ExchangeId {
+        WebSocketClient::name(self)
+    }
+
+    fn is_connected(&self) -> bool {
+        WebSocketClient::is_connected(self)
+    }
+
+    fn subscriptions(&self) -> Vec<Stream> {
+        WebSocketClient::subscriptions(self)
+    }
+
+    fn connect(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>> {
+        Box::pin(WebSocketClient::connect(self))
+    }
+
+    fn subscribe(&mut self, stream: Stream) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(WebSocketClient::subscribe(self, stream))
+    }
+
+    fn subscribe_many(&mut self, streams: Vec<Stream>) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(WebSocketClient::subscribe_many(self, streams))
+    }
+
+    fn unsubscribe<'a>(&'a mut self, stream: &'a Stream) -> BoxFuture<'a, Result<(), CctError>> {
+        Box::pin(WebSocketClient::unsubscribe(self, stream))
+    }
+
+    fn reconnect(&mut self) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(WebSocketClient::reconnect(self))
+    }
+
+    fn reopen(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>> {
+        Box::pin(WebSocketClient::reopen(self))
+    }
+
+    fn disconnect(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(WebSocketClient::disconnect(self))
+    }
+}
+
+impl<P: MessageParser> ExchangeClient for ShardedClient<P> {
+    fn name(&self) -> ExchangeId {
+        ShardedClient::name(self)
+    }
+
+    fn is_connected(&self) -> bool {
+        ShardedClient::is_connected(self)
+    }
+
+    fn subscriptions(&self) -> Vec<Stream> {
+        ShardedClient::subscriptions(self)
+    }
+
+    fn connect(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>> {
+        Box::pin(ShardedClient::connect(self))
+    }
+
+    fn subscribe(&mut self, stream: Stream) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(ShardedClient::subscribe(self, stream))
+    }
+
+    fn subscribe_many(&mut self, streams: Vec<Stream>) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(ShardedClient::subscribe_many(self, streams))
+    }
+
+    fn unsubscribe<'a>(&'a mut self, stream: &'a Stream) -> BoxFuture<'a, Result<(), CctError>> {
+        Box::pin(ShardedClient::unsubscribe(self, stream))
+    }
+
+    fn reconnect(&mut self) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(ShardedClient::reconnect(self))
+    }
+
+    fn reopen(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>> {
+        Box::pin(async move {
+            let subs = ShardedClient::subscriptions(self);
+            ShardedClient::disconnect(self).await;
+            let market_data_rx = ShardedClient::connect(self).await?;
+            ShardedClient::subscribe_many(self, subs).await?;
+            Ok(market_data_rx)
+        })
+    }
+
+    fn disconnect(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(ShardedClient::disconnect(self))
+    }
+}
+
+/// Lets a capture stand in for a live exchange. Reopening restarts the replay, so
+/// under a `MultiExchangeManager` a finished replay starts over after the backoff delay.
+impl ExchangeClient for ReplayClient {
+    fn name(&self) -> ExchangeId {
+        ReplayClient::name(self)
+    }
+
+    fn is_connected(&self) -> bool {
+        ReplayClient::is_connected(self)
+    }
+
+    fn subscriptions(&self) -> Vec<Stream> {
+        ReplayClient::subscriptions(self)
+    }
+
+    fn connect(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>> {
+        Box::pin(ReplayClient::connect(self))
+    }
+
+    fn subscribe(&mut self, stream: Stream) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(ReplayClient::subscribe(self, stream))
+    }
+
+    fn subscribe_many(&mut self, streams: Vec<Stream>) -> BoxFuture<'_, Result<(), CctError>> {
+        Box::pin(ReplayClient::subscribe_many(self, streams))
+    }
+
+    fn unsubscribe<'a>(&'a mut self, stream: &'a Stream) -> BoxFuture<'a, Result<(), CctError>> {
+        Box::pin(ReplayClient::unsubscribe(self, stream))
+    }
+
+    fn reconnect(&mut self) -> BoxFuture<'_, Result<(), CctError>> {
+        // The receiver is tied to one replay run, so there is nothing to swap underneath it
+        Box::pin(async { Ok(()) })
+    }
+
+    fn reopen(&mut self) -> BoxFuture<'_, Result<mpsc::Receiver<MarketData>, CctError>> {
+        Box::pin(ReplayClient::connect(self))
+    }
+
+    fn disconnect(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(ReplayClient::disconnect(self))
+    }
+}
+
+/// Connection status of one exchange, as seen by its supervisor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeStatus {
+    Connected,
+    /// The feed ended; reopening it, `attempt` counting from 1.
+    Reconnecting { attempt: u32 },
+    /// The reconnect policy's `max_attempts` ran out; the exchange delivers nothing more.
+    Failed,
+    /// `shutdown()` was called.
+    Stopped,
+}
+
+type SharedClient = Arc<Mutex<Box<dyn ExchangeClient>>>;
+type SharedStatus = Arc<StdMutex<ExchangeStatus>>;
+
+struct Exchange {
+    id: ExchangeId,
+    client: SharedClient,
+    status: SharedStatus,
+    supervisor: JoinHandle<()>,
+}
+
+/// Clients for several exchanges, consumed through one receiver of
+/// `(ExchangeId, MarketData)`.
+pub struct MultiExchangeManager {
+    exchanges: Vec<Exchange>,
+    merged_tx: mpsc::Sender<(ExchangeId, MarketData)>,
+    merged_rx: Option<mpsc::Receiver<(ExchangeId, MarketData)>>,
+    reconnect_policy: ReconnectPolicy,
+}
+
+impl Default for MultiExchangeManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiExchangeManager {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// `capacity` is the merged channel's size. A full channel makes every
+    /// exchange wait, so size it for the combined rate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (merged_tx, merged_rx) = mpsc::channel(capacity.max(1));
+        Self {
+            exchanges: Vec::new(),
+            merged_tx,
+            merged_rx: Some(merged_rx),
+            reconnect_policy: ReconnectPolicy::default(),
+        }
+    }
+
+    /// Backoff used when an exchange's feed ends. Default: `ReconnectPolicy::default()`.
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    /// The merged receiver. Returns None after the first call.
+    pub fn take_receiver(&mut self) -> Option<mpsc::Receiver<(ExchangeId, MarketData)>> {
+        self.merged_rx.take()
+    }
+
+    /// Connects `client` and starts forwarding its data. Exchanges are identified by
+    /// `name()`, so two clients with the same parser can't be added together.
+    pub async fn add_exchange(&mut self, mut client: impl ExchangeClient) -> Result<ExchangeId, CctError> {
+        let id = client.name();
+        if self.find(id).is_ok() {
+            return Err(CctError::InvalidConfig(format!("exchange {} already added", id)));
+        }
+        let market_data_rx = client.connect().await?;
+
+        let client: SharedClient = Arc::new(Mutex::new(Box::new(client)));
+        let status = Arc::new(StdMutex::new(ExchangeStatus::Connected));
+        let supervisor = tokio::spawn(supervise(
+            id,
+            Arc::clone(&client),
+            market_data_rx,
+            self.merged_tx.clone(),
+            Arc::clone(&status),
+            self.reconnect_policy,
+        ));
+        self.exchanges.push(Exchange { id, client, status, supervisor });
+        Ok(id)
+    }
+
+    /// Names of the added exchanges, in the order they were added.
+    pub fn exchanges(&self) -> Vec<ExchangeId> {
+        self.exchanges.iter().map(|exchange| exchange.id).collect()
+    }
+
+    pub fn status(&self, exchange: &str) -> Option<ExchangeStatus> {
+        self.find(exchange).ok().map(|exchange| *lock(&exchange.status))
+    }
+
+    pub fn statuses(&self) -> Vec<(ExchangeId, ExchangeStatus)> {
+        self.exchanges
+            .iter()
+            .map(|exchange| (exchange.id, *lock(&exchange.status)))
+            .collect()
+    }
+
+    pub async fn subscribe(&self, exchange: &str, stream: Stream) -> Result<(), CctError> {
+        self.find(exchange)?.client.lock().await.subscribe(stream).await
+    }
+
+    pub async fn subscribe_many(&self, exchange: &str, streams: Vec<Stream>) -> Result<(), CctError> {
+        self.find(exchange)?.client.lock().await.subscribe_many(streams).await
+    }
+
+    pub async fn unsubscribe(&self, exchange: &str, stream: &Stream) -> Result<(), CctError> {
+        self.find(exchange)?.client.lock().await.unsubscribe(stream).await
+    }
+
+    pub async fn subscriptions(&self, exchange: &str) -> Result<Vec<Stream>, CctError> {
+        Ok(self.find(exchange)?.client.lock().await.subscriptions())
+    }
+
+    pub async fn reconnect(&self, exchange: &str) -> Result<(), CctError> {
+        self.find(exchange)?.client.lock().await.reconnect().await
+    }
+
+    /// Reconnects every exchange, continuing past failures.
+    /// Returns the exchanges that failed, with their errors.
+    pub async fn reconnect_all(&self) -> Vec<(ExchangeId, CctError)> {
+        let mut failed = Vec::new();
+        for exchange in &self.exchanges {
+            if let Err(e) = exchange.client.lock().await.reconnect().await {
+                failed.push((exchange.id, e));
+            }
+        }
+        failed
+    }
+
+    /// Disconnects every exchange and waits for the supervisors to forward what
+    /// they already received. The merged receiver ends once the manager is dropped.
+    pub async fn shutdown(&mut self) {
+        for exchange in &self.exchanges {
+            // Set first, so the supervisor sees the ended feed as intentional
+            *lock(&exchange.status) = ExchangeStatus::Stopped;
+            exchange.client.lock().await.disconnect().await;
+        }
+        for exchange in &mut self.exchanges {
+            let _ = (&mut exchange.supervisor).await;
+        }
+    }
+
+    fn find(&self, exchange: &str) -> Result<&Exchange, CctError> {
+        self.exchanges
+            .iter()
+            .find(|candidate| candidate.id.eq_ignore_ascii_case(exchange))
+            .ok_or_else(|| CctError::UnknownExchange(exchange.to_string()))
+    }
+}
+
+fn lock(status: &SharedStatus) -> std::sync::MutexGuard<'_, ExchangeStatus> {
+    status.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Forwards one exchange's data, reopening its feed whenever it ends.
+async fn supervise(
+    id: ExchangeId,
+    client: SharedClient,
+    mut market_data_rx: mpsc::Receiver<MarketData>,
+    merged_tx: mpsc::Sender<(ExchangeId, MarketData)>,
+    status: SharedStatus,
+    policy: ReconnectPolicy,
+) {
+    let mut backoff = Backoff::new(policy);
+    loop {
+        let connected_at = std::time::Instant::now();
+        while let Some(market_data) = market_data_rx.recv().await {
+            if merged_tx.send((id, market_data)).await.is_err() {
+                return; // merged receiver dropped
+            }
+        }
+        if *lock(&status) == ExchangeStatus::Stopped {
+            return;
+        }
+        backoff.connection_lasted(connected_at.elapsed());
+
+        market_data_rx = loop {
+            let Some(delay) = backoff.next_delay() else {
+                eprintln!("[{}] Giving up reconnecting", id);
+                let mut status = lock(&status);
+                if *status != ExchangeStatus::Stopped {
+                    *status = ExchangeStatus::Failed;
+                }
+                return;
+            };
+            {
+                let mut status = lock(&status);
+                *status = match *status {
+                    ExchangeStatus::Stopped => return,
+                    ExchangeStatus::Reconnecting { attempt } => ExchangeStatus::Reconnecting { attempt: attempt + 1 },
+                    _ => ExchangeStatus::Reconnecting { attempt: 1 },
+                };
+            }
+            tokio::time::sleep(delay).await;
+            let mut client = client.lock().await;
+            // shutdown() may have run while we slept
+            if *lock(&status) == ExchangeStatus::Stopped {
+                return;
+            }
+            match client.reopen().await {
+                Ok(market_data_rx) => break market_data_rx,
+                Err(e) => eprintln!("[{}] Reopening feed failed: {}", id, e),
+            }
+        };
+        let mut status = lock(&status);
+        if *status != ExchangeStatus::Stopped {
+            *status = ExchangeStatus::Connected;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{Trade, TradeSide};
+    use futures_util::{SinkExt, StreamExt};
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Answers "sub:SYMBOL" with a trade {"s":SYMBOL,"p":price}.
+    struct EchoParser {
+        name: &'static str,
+        endpoint: String,
+    }
+
+    impl MessageParser for EchoParser {
+        fn endpoint(&self) -> &str {
+            &self.endpoint
+        }
+
+        fn format_subscribe(&self, stream: &Stream) -> String {
+            format!("sub:{}", stream.symbol())
+        }
+
+        fn format_unsubscribe(&self, stream: &Stream) -> String {
+            format!("unsub:{}", stream.symbol())
+        }
+
+        fn parse_message(&self, msg: &str) -> Option<MarketData> {
+            let value: serde_json::Value = serde_json::from_str(msg).ok()?;
+            let trade = Trade::new(0, value["s"].as_str()?, value["p"].as_f64()?, 1.0, "t", TradeSide::Buy);
+            Some(MarketData::Trade(trade))
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+    }
+
+    /// Replies to each subscribe with one trade at `price`. The first connection
+    /// is closed by the server after `drop_first_after` subscribes (0 = never).
+    async fn spawn_exchange(price: f64, drop_first_after: usize) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for connection in 0.. {
+                let Ok((socket, _)) = listener.accept().await else { break };
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+                    let mut subscribes = 0;
+                    while let Some(Ok(Message::Text(text))) = ws.next().await {
+                        let Some(symbol) = text.strip_prefix("sub:") else { continue };
+                        let trade = format!(r#"{{"s":"{}","p":{}}}"#, symbol, price);
+                        ws.send(Message::Text(trade.into())).await.unwrap();
+                        subscribes += 1;
+                        if connection == 0 && subscribes == drop_first_after {
+                            let _ = ws.close(None).await;
+                        }
+                    }
+                });
+            }
+        });
+        endpoint
+    }
+
+    async fn next(rx: &mut mpsc::Receiver<(ExchangeId, MarketData)>) -> (ExchangeId, String, f64) {
+        let (id, data) = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        let trade = data.as_trade().expect("trade").clone();
+        (id, trade.symbol.to_string(), trade.price)
+    }
+
+    #[tokio::test]
+    async fn test_merges_exchanges_and_reopens_ended_feeds() {
+        let policy = ReconnectPolicy::new(Duration::from_millis(10), Duration::from_millis(50)).with_jitter(0.0);
+        let mut manager = MultiExchangeManager::new().with_reconnect_policy(policy);
+        let mut rx = manager.take_receiver().unwrap();
+        assert!(manager.take_receiver().is_none());
+
+        let alpha = EchoParser { name: "Alpha", endpoint: spawn_exchange(1.0, 1).await };
+        let beta = EchoParser { name: "Beta", endpoint: spawn_exchange(2.0, 0).await };
+        manager.add_exchange(WebSocketClient::new(alpha)).await.unwrap();
+        manager.add_exchange(WebSocketClient::new(beta)).await.unwrap();
+        assert_eq!(manager.exchanges(), vec!["Alpha", "Beta"]);
+
+        let duplicate = EchoParser { name: "Alpha", endpoint: String::new() };
+        let err = manager.add_exchange(WebSocketClient::new(duplicate)).await.unwrap_err();
+        assert!(matches!(err, CctError::InvalidConfig(_)));
+        let err = manager.subscribe("Gamma", Stream::trades("BTC")).await.unwrap_err();
+        assert!(matches!(err, CctError::UnknownExchange(_)));
+
+        manager.subscribe("beta", Stream::trades("ETH")).await.unwrap();
+        assert_eq!(next(&mut rx).await, ("Beta", "ETH".to_string(), 2.0));
+
+        // Alpha's server drops the connection after the first subscribe; the
+        // supervisor reopens it and the restored subscription yields another trade
+        manager.subscribe("Alpha", Stream::trades("BTC")).await.unwrap();
+        assert_eq!(next(&mut rx).await, ("Alpha", "BTC".to_string(), 1.0));
+        assert_eq!(next(&mut rx).await, ("Alpha", "BTC".to_string(), 1.0));
+        assert_eq!(manager.status("Alpha"), Some(ExchangeStatus::Connected));
+        assert_eq!(manager.subscriptions("Alpha").await.unwrap(), vec![Stream::trades("BTC")]);
+
+        manager.shutdown().await;
+        assert_eq!(
+            manager.statuses(),
+            vec![("Alpha", ExchangeStatus::Stopped), ("Beta", ExchangeStatus::Stopped)]
+        );
+    }
+}
@@ -1,9 +1,44 @@
 //! Market data types for WebSocket streams.
 //! See docs/market/MARKET_DATA.md for detailed documentation.
 
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[cfg(feature = "decimal")]
+use crate::decimal::{self, Decimal};
 use crate::indicators::candle::Candle;
 use crate::indicators::timeframe::Timeframe;
+use crate::market::symbol::Symbol;
 
+/// Current wall-clock time in ms since the Unix epoch.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Exchange name as reported by `MessageParser::name()`, e.g. "Binance".
+pub type ExchangeId = &'static str;
+
+/// Maps a deserialized exchange name back to an `ExchangeId`. Each distinct name is
+/// leaked once; there are only as many as there are exchanges.
+fn de_exchange<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ExchangeId, D::Error> {
+    static NAMES: Mutex<Option<HashSet<ExchangeId>>> = Mutex::new(None);
+    let name = String::deserialize(deserializer)?;
+    let mut names = NAMES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let names = names.get_or_insert_with(HashSet::new);
+    if let Some(known) = names.get(name.as_str()) {
+        return Ok(known);
+    }
+    let leaked: ExchangeId = Box::leak(name.into_boxed_str());
+    names.insert(leaked);
+    Ok(leaked)
+}
 
 // Fields use Option<T> when only some exchanges provide them.
 // This allows adding new exchanges without breaking existing code - just set
@@ -11,19 +46,24 @@ use crate::indicators::timeframe::Timeframe;
 // Examples: is_buyer_maker (Binance), num_orders (Hyperliquid), sequence (varies)
 
 /// Side of a trade (buyer or seller initiated).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TradeSide {
     Buy,
     Sell,
 }
 
 /// A single price level in an order book.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceLevel {
     pub price: f64,
     pub quantity: f64,
     // Option<T> because only Hyperliquid provides order count at each level
     pub num_orders: Option<u32>,
+    /// Exact price and quantity as the exchange sent them, see `price_decimal()`
+    #[cfg(feature = "decimal")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact: Option<(Decimal, Decimal)>,
 }
 
 impl PriceLevel {
@@ -32,26 +72,55 @@ impl PriceLevel {
             price,
             quantity,
             num_orders: None,
+            #[cfg(feature = "decimal")]
+            exact: None,
         }
     }
 
     /// Creates a new price level with order count (Hyperliquid).
     pub fn with_order_count(price: f64, quantity: f64, num_orders: u32) -> Self {
         Self {
-            price,
-            quantity,
             num_orders: Some(num_orders),
+            ..Self::new(price, quantity)
         }
     }
+
+    /// Attaches the exact price and quantity the f64 fields were parsed from.
+    #[cfg(feature = "decimal")]
+    pub fn with_exact(mut self, price: Decimal, quantity: Decimal) -> Self {
+        self.exact = Some((price, quantity));
+        self
+    }
+
+    /// Exact price when the parser kept it, otherwise converted from `price`.
+    #[cfg(feature = "decimal")]
+    pub fn price_decimal(&self) -> Decimal {
+        self.exact.map_or_else(|| decimal::from_f64(self.price), |(price, _)| price)
+    }
+
+    /// Exact quantity when the parser kept it, otherwise converted from `quantity`.
+    #[cfg(feature = "decimal")]
+    pub fn quantity_decimal(&self) -> Decimal {
+        self.exact.map_or_else(|| decimal::from_f64(self.quantity), |(_, quantity)| quantity)
+    }
 }
 
 /// A single trade event from the exchange.
 /// Design: Trade has symbol baked in because trades are discrete events -
 /// each happens once, for one symbol. You can't process a trade without knowing its symbol.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub timestamp: u64,
-    pub symbol: String,  // baked in - trades are discrete events that need symbol context
+    pub symbol: Symbol,  // baked in - trades are discrete events that need symbol context
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
     pub price: f64,
     pub quantity: f64,
     pub trade_id: String,
@@ -59,12 +128,19 @@ pub struct Trade {
     // Option<T> because only Binance provides this field
     // true = buyer was maker, so taker sold; false = buyer was taker, so taker bought
     pub is_buyer_maker: Option<bool>,
+    // Aggregated trades only: range of exchange trade ids folded into this one
+    pub first_trade_id: Option<u64>,
+    pub last_trade_id: Option<u64>,
+    /// Exact price and quantity as the exchange sent them, see `price_decimal()`
+    #[cfg(feature = "decimal")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact: Option<(Decimal, Decimal)>,
 }
 
 impl Trade {
     pub fn new(
         timestamp: u64,
-        symbol: impl Into<String>,
+        symbol: impl Into<Symbol>,
         price: f64,
         quantity: f64,
         trade_id: impl Into<String>,
@@ -73,11 +149,18 @@ impl Trade {
         Self {
             timestamp,
             symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
             price,
             quantity,
             trade_id: trade_id.into(),
             side,
             is_buyer_maker: None,
+            first_trade_id: None,
+            last_trade_id: None,
+            #[cfg(feature = "decimal")]
+            exact: None,
         }
     }
 
@@ -86,14 +169,49 @@ impl Trade {
         self.is_buyer_maker = Some(is_buyer_maker);
         self
     }
+
+    /// Sets the first and last underlying trade ids of an aggregated trade.
+    pub fn with_trade_id_range(mut self, first_trade_id: u64, last_trade_id: u64) -> Self {
+        self.first_trade_id = Some(first_trade_id);
+        self.last_trade_id = Some(last_trade_id);
+        self
+    }
+
+    /// Attaches the exact price and quantity the f64 fields were parsed from.
+    #[cfg(feature = "decimal")]
+    pub fn with_exact(mut self, price: Decimal, quantity: Decimal) -> Self {
+        self.exact = Some((price, quantity));
+        self
+    }
+
+    /// Exact price when the parser kept it, otherwise converted from `price`.
+    #[cfg(feature = "decimal")]
+    pub fn price_decimal(&self) -> Decimal {
+        self.exact.map_or_else(|| decimal::from_f64(self.price), |(price, _)| price)
+    }
+
+    /// Exact quantity when the parser kept it, otherwise converted from `quantity`.
+    #[cfg(feature = "decimal")]
+    pub fn quantity_decimal(&self) -> Decimal {
+        self.exact.map_or_else(|| decimal::from_f64(self.quantity), |(_, quantity)| quantity)
+    }
 }
 
 /// Order book snapshot or delta update.
 /// Design: Like Trade, OrderBookUpdate has symbol baked in - it's a discrete event.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBookUpdate {
     pub timestamp: u64,
-    pub symbol: String,  // baked in - order book updates are discrete events
+    pub symbol: Symbol,  // baked in - order book updates are discrete events
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
     /// Bid levels (buy orders), sorted by price descending
     pub bids: Vec<PriceLevel>,
     /// Ask levels (sell orders), sorted by price ascending
@@ -102,38 +220,57 @@ pub struct OrderBookUpdate {
     pub is_snapshot: bool,
     // Option<T> because not all exchanges provide sequence numbers
     pub sequence: Option<u64>,
+    /// First and last update id covered by this update, for exchanges that send a
+    /// range (Binance "U"/"u", Gate.io). `sequence` is then the last one.
+    pub first_sequence: Option<u64>,
+    pub final_sequence: Option<u64>,
+    /// CRC32 of the top of the book after this update (Kraken, OKX, Bitget; OKX's and
+    /// Bitget's signed values reinterpreted as u32). See `LocalOrderBook::verify_checksum`.
+    pub checksum: Option<u32>,
 }
 
 impl OrderBookUpdate {
     pub fn snapshot(
         timestamp: u64,
-        symbol: impl Into<String>,
+        symbol: impl Into<Symbol>,
         bids: Vec<PriceLevel>,
         asks: Vec<PriceLevel>,
     ) -> Self {
         Self {
             timestamp,
             symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
             bids,
             asks,
             is_snapshot: true,
             sequence: None,
+            first_sequence: None,
+            final_sequence: None,
+            checksum: None,
         }
     }
 
     pub fn delta(
         timestamp: u64,
-        symbol: impl Into<String>,
+        symbol: impl Into<Symbol>,
         bids: Vec<PriceLevel>,
         asks: Vec<PriceLevel>,
     ) -> Self {
         Self {
             timestamp,
             symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
             bids,
             asks,
             is_snapshot: false,
             sequence: None,
+            first_sequence: None,
+            final_sequence: None,
+            checksum: None,
         }
     }
 
@@ -141,14 +278,36 @@ impl OrderBookUpdate {
         self.sequence = Some(sequence);
         self
     }
+
+    pub fn with_checksum(mut self, checksum: u32) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+
+    /// Sets the update id range, `first..=last`; `sequence` becomes `last`.
+    pub fn with_sequence_range(mut self, first: u64, last: u64) -> Self {
+        self.first_sequence = Some(first);
+        self.final_sequence = Some(last);
+        self.sequence = Some(last);
+        self
+    }
 }
 
 /// Funding rate event for perpetual futures.
 /// Design: Like Trade, FundingRate has symbol baked in - it's a discrete event.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FundingRate {
     pub timestamp: u64,
-    pub symbol: String,  // baked in - funding events are discrete events
+    pub symbol: Symbol,  // baked in - funding events are discrete events
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
     /// Positive = longs pay shorts
     pub rate: f64,
     // Option<T> because not all exchanges provide these fields
@@ -157,10 +316,13 @@ pub struct FundingRate {
 }
 
 impl FundingRate {
-    pub fn new(timestamp: u64, symbol: impl Into<String>, rate: f64) -> Self {
+    pub fn new(timestamp: u64, symbol: impl Into<Symbol>, rate: f64) -> Self {
         Self {
             timestamp,
             symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
             rate,
             next_funding_time: None,
             mark_price: None,
@@ -178,6 +340,279 @@ impl FundingRate {
     }
 }
 
+/// Mark price update for a futures or perpetual symbol.
+/// Design: split from FundingRate so mark price subscribers don't have to dig it
+/// out of a funding event; exchanges that push both together emit both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkPrice {
+    pub timestamp: u64,
+    pub symbol: Symbol,
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
+    pub mark_price: f64,
+    // Option<T> because not all exchanges provide these fields
+    pub index_price: Option<f64>,
+    /// Price the contract would settle at if funding/delivery happened now
+    pub estimated_settle_price: Option<f64>,
+}
+
+impl MarkPrice {
+    pub fn new(timestamp: u64, symbol: impl Into<Symbol>, mark_price: f64) -> Self {
+        Self {
+            timestamp,
+            symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
+            mark_price,
+            index_price: None,
+            estimated_settle_price: None,
+        }
+    }
+
+    pub fn with_index_price(mut self, index_price: f64) -> Self {
+        self.index_price = Some(index_price);
+        self
+    }
+
+    pub fn with_estimated_settle_price(mut self, estimated_settle_price: f64) -> Self {
+        self.estimated_settle_price = Some(estimated_settle_price);
+        self
+    }
+}
+
+/// Forced liquidation order (futures).
+/// Design: a discrete event like Trade, so the symbol is baked in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Liquidation {
+    pub timestamp: u64,
+    pub symbol: Symbol,
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
+    /// Side of the liquidation order: Sell = a long was liquidated, Buy = a short
+    pub side: TradeSide,
+    pub price: f64,
+    pub quantity: f64,
+    /// Quote value (price * quantity)
+    pub value: f64,
+    /// Exchange order type (e.g. "LIMIT"); not all exchanges report it
+    pub order_type: Option<String>,
+}
+
+impl Liquidation {
+    pub fn new(
+        timestamp: u64,
+        symbol: impl Into<Symbol>,
+        side: TradeSide,
+        price: f64,
+        quantity: f64,
+    ) -> Self {
+        Self {
+            timestamp,
+            symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
+            side,
+            price,
+            quantity,
+            value: price * quantity,
+            order_type: None,
+        }
+    }
+
+    pub fn with_order_type(mut self, order_type: impl Into<String>) -> Self {
+        self.order_type = Some(order_type.into());
+        self
+    }
+}
+
+/// Open interest: contracts outstanding for a futures or perpetual symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenInterest {
+    pub timestamp: u64,
+    pub symbol: Symbol,
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
+    /// In the exchange's contract units (base asset, or USD for inverse contracts)
+    pub open_interest: f64,
+    /// Quote value of the open interest, when the exchange reports it
+    pub open_interest_value: Option<f64>,
+}
+
+impl OpenInterest {
+    pub fn new(timestamp: u64, symbol: impl Into<Symbol>, open_interest: f64) -> Self {
+        Self {
+            timestamp,
+            symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
+            open_interest,
+            open_interest_value: None,
+        }
+    }
+
+    pub fn with_value(mut self, open_interest_value: f64) -> Self {
+        self.open_interest_value = Some(open_interest_value);
+        self
+    }
+}
+
+/// Best bid/ask (top of book) update.
+/// Design: a discrete event like Trade, so the symbol is baked in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookTicker {
+    pub timestamp: u64,
+    pub symbol: Symbol,
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
+    pub bid_price: f64,
+    pub bid_quantity: f64,
+    pub ask_price: f64,
+    pub ask_quantity: f64,
+    // Option<T> because not all exchanges provide an update id
+    pub update_id: Option<u64>,
+}
+
+impl BookTicker {
+    pub fn new(
+        timestamp: u64,
+        symbol: impl Into<Symbol>,
+        bid_price: f64,
+        bid_quantity: f64,
+        ask_price: f64,
+        ask_quantity: f64,
+    ) -> Self {
+        Self {
+            timestamp,
+            symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
+            bid_price,
+            bid_quantity,
+            ask_price,
+            ask_quantity,
+            update_id: None,
+        }
+    }
+
+    pub fn with_update_id(mut self, update_id: u64) -> Self {
+        self.update_id = Some(update_id);
+        self
+    }
+
+    /// Ask minus bid.
+    pub fn spread(&self) -> f64 {
+        self.ask_price - self.bid_price
+    }
+
+    /// Midpoint between best bid and best ask.
+    pub fn mid_price(&self) -> f64 {
+        (self.bid_price + self.ask_price) / 2.0
+    }
+}
+
+/// Rolling 24h statistics for a symbol.
+/// Design: a discrete event like Trade, so the symbol is baked in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ticker {
+    pub timestamp: u64,
+    pub symbol: Symbol,
+    /// Source exchange, see `MarketData::exchange()`
+    #[serde(default, deserialize_with = "de_exchange")]
+    pub exchange: ExchangeId,
+    /// Local receive time in ms, see `MarketData::received_at()`
+    #[serde(default)]
+    pub received_at: u64,
+    /// The frame this was parsed from, see `MarketData::raw()`
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
+    pub last_price: f64,
+    /// 24h base asset volume
+    pub volume: f64,
+    // Option<T> because not all exchanges provide these fields
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    /// 24h quote asset volume
+    pub quote_volume: Option<f64>,
+    /// 24h change in percent (2.5 = +2.5%)
+    pub price_change_percent: Option<f64>,
+    pub weighted_avg_price: Option<f64>,
+}
+
+impl Ticker {
+    pub fn new(timestamp: u64, symbol: impl Into<Symbol>, last_price: f64, volume: f64) -> Self {
+        Self {
+            timestamp,
+            symbol: symbol.into(),
+            exchange: "",
+            received_at: 0,
+            raw: None,
+            last_price,
+            volume,
+            open: None,
+            high: None,
+            low: None,
+            quote_volume: None,
+            price_change_percent: None,
+            weighted_avg_price: None,
+        }
+    }
+
+    /// Sets the 24h open, high and low.
+    pub fn with_range(mut self, open: f64, high: f64, low: f64) -> Self {
+        self.open = Some(open);
+        self.high = Some(high);
+        self.low = Some(low);
+        self
+    }
+
+    pub fn with_quote_volume(mut self, quote_volume: f64) -> Self {
+        self.quote_volume = Some(quote_volume);
+        self
+    }
+
+    pub fn with_price_change_percent(mut self, price_change_percent: f64) -> Self {
+        self.price_change_percent = Some(price_change_percent);
+        self
+    }
+
+    pub fn with_weighted_avg_price(mut self, weighted_avg_price: f64) -> Self {
+        self.weighted_avg_price = Some(weighted_avg_price);
+        self
+    }
+}
 
 // - Candle is a *calculation primitive* used by indicators (is_doji, atr, ema).
 //   It doesn't need symbol/interval for calculations - that's streaming context.
@@ -188,33 +623,198 @@ impl FundingRate {
 
 /// Unified market data enum for all stream types.
 /// Allows a single channel to carry all types of market data.
-#[derive(Debug, Clone)]
+/// Serializes adjacently tagged: `{"type":"trade","data":{...}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum MarketData {
     /// Candle wrapped with streaming context (symbol, interval, is_closed).
     /// The inner Candle is a calculation primitive - doesn't need symbol for indicators.
     /// WARNING: If is_closed=false, candle is still updating - don't use for calculations yet.
     Candle {
-        symbol: String,    // streaming context, not needed for indicator calculations
+        symbol: Symbol,    // streaming context, not needed for indicator calculations
         interval: Timeframe,  // streaming context, not needed for indicator calculations
         data: Candle,      // the actual calculation primitive
         is_closed: bool,   // IMPORTANT: only use for calculations when true
+        #[serde(default, deserialize_with = "de_exchange")]
+        exchange: ExchangeId,
+        #[serde(default)]
+        received_at: u64,
+        #[serde(skip)]
+        raw: Option<Arc<serde_json::Value>>,
     },
     // These types have symbol baked in - they're discrete events
     Trade(Trade),
     OrderBook(OrderBookUpdate),
     Funding(FundingRate),
+    MarkPrice(MarkPrice),
+    Liquidation(Liquidation),
+    BookTicker(BookTicker),
+    Ticker(Ticker),
+    OpenInterest(OpenInterest),
 }
 
 impl MarketData {
+    /// A closed or still-updating candle, not yet tagged with an exchange.
+    pub fn candle(symbol: impl Into<Symbol>, interval: Timeframe, data: Candle, is_closed: bool) -> Self {
+        MarketData::Candle {
+            symbol: symbol.into(),
+            interval,
+            data,
+            is_closed,
+            exchange: "",
+            received_at: 0,
+            raw: None,
+        }
+    }
+
     pub fn symbol(&self) -> &str {
         match self {
             MarketData::Candle { symbol, .. } => symbol,
             MarketData::Trade(trade) => &trade.symbol,
             MarketData::OrderBook(book) => &book.symbol,
             MarketData::Funding(funding) => &funding.symbol,
+            MarketData::MarkPrice(mark) => &mark.symbol,
+            MarketData::Liquidation(liquidation) => &liquidation.symbol,
+            MarketData::BookTicker(ticker) => &ticker.symbol,
+            MarketData::Ticker(ticker) => &ticker.symbol,
+            MarketData::OpenInterest(open_interest) => &open_interest.symbol,
+        }
+    }
+
+    /// The exchange this came from (`MessageParser::name()`), so feeds merged from
+    /// several clients can be told apart. Set by `WebSocketClient` for everything it
+    /// delivers; empty for items built by hand or parsed outside a client.
+    pub fn exchange(&self) -> ExchangeId {
+        match self {
+            MarketData::Candle { exchange, .. } => exchange,
+            MarketData::Trade(trade) => trade.exchange,
+            MarketData::OrderBook(book) => book.exchange,
+            MarketData::Funding(funding) => funding.exchange,
+            MarketData::MarkPrice(mark) => mark.exchange,
+            MarketData::Liquidation(liquidation) => liquidation.exchange,
+            MarketData::BookTicker(ticker) => ticker.exchange,
+            MarketData::Ticker(ticker) => ticker.exchange,
+            MarketData::OpenInterest(open_interest) => open_interest.exchange,
         }
     }
 
+    pub fn set_exchange(&mut self, id: ExchangeId) {
+        let slot = match self {
+            MarketData::Candle { exchange, .. } => exchange,
+            MarketData::Trade(trade) => &mut trade.exchange,
+            MarketData::OrderBook(book) => &mut book.exchange,
+            MarketData::Funding(funding) => &mut funding.exchange,
+            MarketData::MarkPrice(mark) => &mut mark.exchange,
+            MarketData::Liquidation(liquidation) => &mut liquidation.exchange,
+            MarketData::BookTicker(ticker) => &mut ticker.exchange,
+            MarketData::Ticker(ticker) => &mut ticker.exchange,
+            MarketData::OpenInterest(open_interest) => &mut open_interest.exchange,
+        };
+        *slot = id;
+    }
+
+    pub fn with_exchange(mut self, id: ExchangeId) -> Self {
+        self.set_exchange(id);
+        self
+    }
+
+    /// When this was read off the socket, in ms since the Unix epoch (local clock).
+    /// Set by `WebSocketClient` for everything it delivers; 0 otherwise.
+    pub fn received_at(&self) -> u64 {
+        match self {
+            MarketData::Candle { received_at, .. } => *received_at,
+            MarketData::Trade(trade) => trade.received_at,
+            MarketData::OrderBook(book) => book.received_at,
+            MarketData::Funding(funding) => funding.received_at,
+            MarketData::MarkPrice(mark) => mark.received_at,
+            MarketData::Liquidation(liquidation) => liquidation.received_at,
+            MarketData::BookTicker(ticker) => ticker.received_at,
+            MarketData::Ticker(ticker) => ticker.received_at,
+            MarketData::OpenInterest(open_interest) => open_interest.received_at,
+        }
+    }
+
+    pub fn set_received_at(&mut self, ms: u64) {
+        let slot = match self {
+            MarketData::Candle { received_at, .. } => received_at,
+            MarketData::Trade(trade) => &mut trade.received_at,
+            MarketData::OrderBook(book) => &mut book.received_at,
+            MarketData::Funding(funding) => &mut funding.received_at,
+            MarketData::MarkPrice(mark) => &mut mark.received_at,
+            MarketData::Liquidation(liquidation) => &mut liquidation.received_at,
+            MarketData::BookTicker(ticker) => &mut ticker.received_at,
+            MarketData::Ticker(ticker) => &mut ticker.received_at,
+            MarketData::OpenInterest(open_interest) => &mut open_interest.received_at,
+        };
+        *slot = ms;
+    }
+
+    /// The JSON frame this was parsed from, when the client runs with
+    /// `ClientConfig::keep_raw`; None otherwise. It is the whole frame, wrapper
+    /// included, and items parsed from one frame share it. Not serialized.
+    pub fn raw(&self) -> Option<&serde_json::Value> {
+        let raw = match self {
+            MarketData::Candle { raw, .. } => raw,
+            MarketData::Trade(trade) => &trade.raw,
+            MarketData::OrderBook(book) => &book.raw,
+            MarketData::Funding(funding) => &funding.raw,
+            MarketData::MarkPrice(mark) => &mark.raw,
+            MarketData::Liquidation(liquidation) => &liquidation.raw,
+            MarketData::BookTicker(ticker) => &ticker.raw,
+            MarketData::Ticker(ticker) => &ticker.raw,
+            MarketData::OpenInterest(open_interest) => &open_interest.raw,
+        };
+        raw.as_deref()
+    }
+
+    pub fn set_raw(&mut self, frame: Option<Arc<serde_json::Value>>) {
+        let slot = match self {
+            MarketData::Candle { raw, .. } => raw,
+            MarketData::Trade(trade) => &mut trade.raw,
+            MarketData::OrderBook(book) => &mut book.raw,
+            MarketData::Funding(funding) => &mut funding.raw,
+            MarketData::MarkPrice(mark) => &mut mark.raw,
+            MarketData::Liquidation(liquidation) => &mut liquidation.raw,
+            MarketData::BookTicker(ticker) => &mut ticker.raw,
+            MarketData::Ticker(ticker) => &mut ticker.raw,
+            MarketData::OpenInterest(open_interest) => &mut open_interest.raw,
+        };
+        *slot = frame;
+    }
+
+    /// A field of `raw()` by dot-separated path, with numbers indexing arrays:
+    /// `raw_field("k.q")` is a Binance kline's quote asset volume, `"data.0.p"` the
+    /// first entry's "p" of an array payload. None without a raw frame or a match.
+    pub fn raw_field(&self, path: &str) -> Option<&serde_json::Value> {
+        path.split('.').try_fold(self.raw()?, |value, key| match value {
+            serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => value.get(key),
+        })
+    }
+
+    /// The exchange's event time in ms since the Unix epoch (candle open time for
+    /// candles), 0 when the exchange didn't send one. Providers normalize to ms
+    /// with `market::timestamp`, so this compares across exchanges.
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            MarketData::Candle { data, .. } => data.get_timestamp(),
+            MarketData::Trade(trade) => trade.timestamp,
+            MarketData::OrderBook(book) => book.timestamp,
+            MarketData::Funding(funding) => funding.timestamp,
+            MarketData::MarkPrice(mark) => mark.timestamp,
+            MarketData::Liquidation(liquidation) => liquidation.timestamp,
+            MarketData::BookTicker(ticker) => ticker.timestamp,
+            MarketData::Ticker(ticker) => ticker.timestamp,
+            MarketData::OpenInterest(open_interest) => open_interest.timestamp,
+        }
+    }
+
+    /// `timestamp()`, None when the exchange didn't send one.
+    pub(crate) fn event_time(&self) -> Option<u64> {
+        let time = self.timestamp();
+        (time > 0).then_some(time)
+    }
+
     pub fn is_candle(&self) -> bool {
         matches!(self, MarketData::Candle { .. })
     }
@@ -231,6 +831,26 @@ impl MarketData {
         matches!(self, MarketData::Funding(_))
     }
 
+    pub fn is_mark_price(&self) -> bool {
+        matches!(self, MarketData::MarkPrice(_))
+    }
+
+    pub fn is_book_ticker(&self) -> bool {
+        matches!(self, MarketData::BookTicker(_))
+    }
+
+    pub fn is_ticker(&self) -> bool {
+        matches!(self, MarketData::Ticker(_))
+    }
+
+    pub fn is_liquidation(&self) -> bool {
+        matches!(self, MarketData::Liquidation(_))
+    }
+
+    pub fn is_open_interest(&self) -> bool {
+        matches!(self, MarketData::OpenInterest(_))
+    }
+
     pub fn as_candle(&self) -> Option<(&str, Timeframe, &Candle, bool)> {
         match self {
             MarketData::Candle {
@@ -238,6 +858,7 @@ impl MarketData {
                 interval,
                 data,
                 is_closed,
+                ..
             } => Some((symbol, *interval, data, *is_closed)),
             _ => None,
         }
@@ -263,6 +884,161 @@ impl MarketData {
             _ => None,
         }
     }
+
+    pub fn as_mark_price(&self) -> Option<&MarkPrice> {
+        match self {
+            MarketData::MarkPrice(mark) => Some(mark),
+            _ => None,
+        }
+    }
+
+    pub fn as_book_ticker(&self) -> Option<&BookTicker> {
+        match self {
+            MarketData::BookTicker(ticker) => Some(ticker),
+            _ => None,
+        }
+    }
+
+    pub fn as_ticker(&self) -> Option<&Ticker> {
+        match self {
+            MarketData::Ticker(ticker) => Some(ticker),
+            _ => None,
+        }
+    }
+
+    pub fn as_liquidation(&self) -> Option<&Liquidation> {
+        match self {
+            MarketData::Liquidation(liquidation) => Some(liquidation),
+            _ => None,
+        }
+    }
+
+    pub fn as_open_interest(&self) -> Option<&OpenInterest> {
+        match self {
+            MarketData::OpenInterest(open_interest) => Some(open_interest),
+            _ => None,
+        }
+    }
+}
+
+// Display: one-line summaries for logs. `{:?}` still shows every field.
+
+impl fmt::Display for TradeSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TradeSide::Buy => "BUY",
+            TradeSide::Sell => "SELL",
+        })
+    }
+}
+
+/// `BTCUSDT BUY 0.5 @ 50000`
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} @ {}", self.symbol, self.side, self.quantity, self.price)
+    }
+}
+
+/// `BTCUSDT snapshot 5 bids / 5 asks, best 49900/50100`; a missing side shows as "-".
+impl fmt::Display for OrderBookUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_snapshot { "snapshot" } else { "delta" };
+        // Deltas aren't necessarily sorted, so look at every level
+        let best_bid = self.bids.iter().map(|level| level.price).reduce(f64::max);
+        let best_ask = self.asks.iter().map(|level| level.price).reduce(f64::min);
+        let price = |price: Option<f64>| price.map_or_else(|| "-".to_string(), |price| price.to_string());
+        write!(
+            f,
+            "{} {} {} bids / {} asks, best {}/{}",
+            self.symbol,
+            kind,
+            self.bids.len(),
+            self.asks.len(),
+            price(best_bid),
+            price(best_ask)
+        )
+    }
+}
+
+/// `BTCUSDT rate 0.0001`, plus the mark price when known.
+impl fmt::Display for FundingRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rate {}", self.symbol, self.rate)?;
+        if let Some(mark_price) = self.mark_price {
+            write!(f, " mark {}", mark_price)?;
+        }
+        Ok(())
+    }
+}
+
+/// `BTCUSDT mark 50000`, plus the index price when known.
+impl fmt::Display for MarkPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} mark {}", self.symbol, self.mark_price)?;
+        if let Some(index_price) = self.index_price {
+            write!(f, " index {}", index_price)?;
+        }
+        Ok(())
+    }
+}
+
+/// `BTCUSDT SELL 0.2 @ 49000`
+impl fmt::Display for Liquidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} @ {}", self.symbol, self.side, self.quantity, self.price)
+    }
+}
+
+/// `BTCUSDT 12500`
+impl fmt::Display for OpenInterest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.symbol, self.open_interest)
+    }
+}
+
+/// `BTCUSDT 49999 x 1 / 50001 x 2` (bid price x size / ask price x size)
+impl fmt::Display for BookTicker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} x {} / {} x {}",
+            self.symbol, self.bid_price, self.bid_quantity, self.ask_price, self.ask_quantity
+        )
+    }
+}
+
+/// `BTCUSDT last 50000 vol 1234`
+impl fmt::Display for Ticker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} last {} vol {}", self.symbol, self.last_price, self.volume)
+    }
+}
+
+/// The inner type's `Display` behind a variant prefix, e.g. `Trade BTCUSDT BUY 0.5 @ 50000`
+/// or `Candle BTCUSDT 1m closed [ts] O:.. H:.. L:.. C:.. V:..`.
+impl fmt::Display for MarketData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarketData::Candle {
+                symbol,
+                interval,
+                data,
+                is_closed,
+                ..
+            } => {
+                let state = if *is_closed { "closed" } else { "open" };
+                write!(f, "Candle {} {} {} {}", symbol, interval, state, data)
+            }
+            MarketData::Trade(trade) => write!(f, "Trade {}", trade),
+            MarketData::OrderBook(book) => write!(f, "OrderBook {}", book),
+            MarketData::Funding(funding) => write!(f, "Funding {}", funding),
+            MarketData::MarkPrice(mark) => write!(f, "MarkPrice {}", mark),
+            MarketData::Liquidation(liquidation) => write!(f, "Liquidation {}", liquidation),
+            MarketData::BookTicker(ticker) => write!(f, "BookTicker {}", ticker),
+            MarketData::Ticker(ticker) => write!(f, "Ticker {}", ticker),
+            MarketData::OpenInterest(open_interest) => write!(f, "OpenInterest {}", open_interest),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -334,15 +1110,85 @@ mod tests {
         assert_eq!(funding.mark_price, Some(50000.0));
     }
 
+    #[test]
+    fn test_mark_price_variant() {
+        let mark = MarkPrice::new(1638747660000, "BTCUSDT", 50000.0)
+            .with_index_price(49990.0)
+            .with_estimated_settle_price(49995.0);
+        let md = MarketData::MarkPrice(mark);
+        assert!(md.is_mark_price());
+        assert!(!md.is_funding());
+        assert_eq!(md.symbol(), "BTCUSDT");
+
+        let mark = md.as_mark_price().unwrap();
+        assert_eq!(mark.mark_price, 50000.0);
+        assert_eq!(mark.index_price, Some(49990.0));
+        assert_eq!(mark.estimated_settle_price, Some(49995.0));
+        assert!(md.as_funding().is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(TradeSide::Sell.to_string(), "SELL");
+        let trade = Trade::new(0, "BTCUSDT", 50000.0, 0.5, "1", TradeSide::Buy);
+        assert_eq!(trade.to_string(), "BTCUSDT BUY 0.5 @ 50000");
+        assert_eq!(MarketData::Trade(trade).to_string(), "Trade BTCUSDT BUY 0.5 @ 50000");
+
+        let candle = Candle::new(1638747660000, 100.0, 110.0, 90.0, 105.0, 1000.0);
+        assert_eq!(candle.to_string(), "[1638747660000] O:100 H:110 L:90 C:105 V:1000");
+        assert_eq!(
+            MarketData::candle("BTCUSDT", Timeframe::M1, candle, false).to_string(),
+            "Candle BTCUSDT 1m open [1638747660000] O:100 H:110 L:90 C:105 V:1000"
+        );
+
+        let bids = vec![PriceLevel::new(49800.0, 1.0), PriceLevel::new(49900.0, 2.0)];
+        let asks = vec![PriceLevel::new(50100.0, 1.5)];
+        let book = OrderBookUpdate::snapshot(0, "BTCUSDT", bids, asks);
+        assert_eq!(book.to_string(), "BTCUSDT snapshot 2 bids / 1 asks, best 49900/50100");
+        let empty = OrderBookUpdate::delta(0, "BTCUSDT", vec![], vec![]);
+        assert_eq!(empty.to_string(), "BTCUSDT delta 0 bids / 0 asks, best -/-");
+
+        let funding = FundingRate::new(0, "BTCUSDT", 0.0001).with_mark_price(50000.0);
+        assert_eq!(MarketData::Funding(funding).to_string(), "Funding BTCUSDT rate 0.0001 mark 50000");
+    }
+
+    #[test]
+    fn test_liquidation_variant() {
+        let liquidation = Liquidation::new(1638747660000, "BTCUSDT", TradeSide::Sell, 50000.0, 0.2)
+            .with_order_type("LIMIT");
+        assert_eq!(liquidation.value, 10000.0);
+
+        let md = MarketData::Liquidation(liquidation);
+        assert!(md.is_liquidation());
+        assert!(!md.is_trade());
+        assert_eq!(md.symbol(), "BTCUSDT");
+        match &md {
+            MarketData::Liquidation(liquidation) => assert_eq!(liquidation.side, TradeSide::Sell),
+            other => panic!("Expected MarketData::Liquidation, got {:?}", other),
+        }
+        assert_eq!(md.as_liquidation().unwrap().order_type.as_deref(), Some("LIMIT"));
+        assert!(MarketData::candle("BTCUSDT", Timeframe::M1, Candle::new(0, 1.0, 1.0, 1.0, 1.0, 1.0), true)
+            .as_liquidation()
+            .is_none());
+    }
+
+    #[test]
+    fn test_open_interest_variant() {
+        let open_interest = OpenInterest::new(1638747660000, "BTCUSDT", 12_500.0).with_value(625_000_000.0);
+        let md = MarketData::OpenInterest(open_interest);
+        assert!(md.is_open_interest());
+        assert!(!md.is_funding());
+        assert_eq!(md.symbol(), "BTCUSDT");
+        let open_interest = md.as_open_interest().unwrap();
+        assert_eq!(open_interest.open_interest, 12_500.0);
+        assert_eq!(open_interest.open_interest_value, Some(625_000_000.0));
+        assert_eq!(md.event_time(), Some(1638747660000));
+    }
+
     #[test]
     fn test_market_data_symbol() {
         let candle = Candle::new(0, 100.0, 110.0, 90.0, 105.0, 1000.0);
-        let md_candle = MarketData::Candle {
-            symbol: "BTCUSDT".to_string(),
-            interval: Timeframe::M1,
-            data: candle,
-            is_closed: true,
-        };
+        let md_candle = MarketData::candle("BTCUSDT", Timeframe::M1, candle, true);
         assert_eq!(md_candle.symbol(), "BTCUSDT");
 
         let trade = Trade::new(0, "ETHUSDT", 3000.0, 1.0, "1", TradeSide::Buy);
@@ -350,15 +1196,54 @@ mod tests {
         assert_eq!(md_trade.symbol(), "ETHUSDT");
     }
 
+    #[test]
+    fn test_market_data_timestamp() {
+        let candle = Candle::new(1_717_200_000_000, 100.0, 110.0, 90.0, 105.0, 1000.0);
+        let md_candle = MarketData::candle("BTCUSDT", Timeframe::M1, candle, false);
+        assert_eq!(md_candle.timestamp(), 1_717_200_000_000);
+
+        // An RFC 3339 trade time lines up with a millisecond one
+        let rfc3339 = crate::market::timestamp::parse_timestamp("2024-06-01T00:00:03.827Z").unwrap();
+        let md_trade = MarketData::Trade(Trade::new(rfc3339, "BTCUSDT", 1.0, 1.0, "1", TradeSide::Buy));
+        assert_eq!(md_trade.timestamp(), 1_717_200_003_827);
+
+        let unset = MarketData::Ticker(Ticker::new(0, "BTCUSDT", 1.0, 1.0));
+        assert_eq!(unset.timestamp(), 0);
+        assert_eq!(unset.event_time(), None);
+    }
+
+    #[test]
+    fn test_raw_frame_lookup() {
+        let mut md = MarketData::Trade(Trade::new(1, "BTCUSDT", 1.0, 1.0, "1", TradeSide::Buy));
+        assert!(md.raw().is_none());
+        assert!(md.raw_field("p").is_none());
+
+        let frame = serde_json::json!({"s": "BTCUSDT", "k": {"q": "12.5"}, "data": [{"p": "1"}, {"p": "2"}]});
+        md.set_raw(Some(Arc::new(frame)));
+        assert_eq!(md.raw_field("k.q"), Some(&serde_json::json!("12.5")));
+        assert_eq!(md.raw_field("data.1.p"), Some(&serde_json::json!("2")));
+        assert!(md.raw_field("data.x").is_none());
+        assert!(md.raw_field("k.missing").is_none());
+        // Not part of the serialized form
+        assert!(!serde_json::to_string(&md).unwrap().contains("raw"));
+    }
+
+    #[test]
+    fn test_market_data_exchange_tag() {
+        let candle = Candle::new(0, 100.0, 110.0, 90.0, 105.0, 1000.0);
+        let md_candle = MarketData::candle("BTCUSDT", Timeframe::M1, candle, true);
+        assert_eq!(md_candle.exchange(), "");
+        assert_eq!(md_candle.with_exchange("Binance").exchange(), "Binance");
+
+        let mut md_trade = MarketData::Trade(Trade::new(0, "ETHUSDT", 3000.0, 1.0, "1", TradeSide::Buy));
+        md_trade.set_exchange("Bitget");
+        assert_eq!(md_trade.as_trade().unwrap().exchange, "Bitget");
+    }
+
     #[test]
     fn test_market_data_type_checks() {
         let candle = Candle::new(0, 100.0, 110.0, 90.0, 105.0, 1000.0);
-        let md = MarketData::Candle {
-            symbol: "BTCUSDT".to_string(),
-            interval: Timeframe::M1,
-            data: candle,
-            is_closed: true,
-        };
+        let md = MarketData::candle("BTCUSDT", Timeframe::M1, candle, true);
 
         assert!(md.is_candle());
         assert!(!md.is_trade());
@@ -369,12 +1254,7 @@ mod tests {
     #[test]
     fn test_market_data_as_candle() {
         let candle = Candle::new(1000, 100.0, 110.0, 90.0, 105.0, 1000.0);
-        let md = MarketData::Candle {
-            symbol: "BTCUSDT".to_string(),
-            interval: Timeframe::M5,
-            data: candle,
-            is_closed: false,
-        };
+        let md = MarketData::candle("BTCUSDT", Timeframe::M5, candle, false);
 
         let (symbol, interval, data, is_closed) = md.as_candle().unwrap();
         assert_eq!(symbol, "BTCUSDT");
@@ -1,9 +1,24 @@
 //! MessageParser trait for exchange-specific message handling.
 //! See docs/market/IMPLEMENTING_EXCHANGES.md for how to implement this trait.
 
+use std::future::Future;
+use std::time::Duration;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::error::CctError;
 use crate::market::market_data::MarketData;
 use crate::market::streams::Stream;
 
+/// Result of `MessageParser::decode_binary`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedFrame {
+    /// Decoded payload to parse as if it had arrived as a text frame.
+    Text(String),
+    /// Send this back right away (e.g. a pong for a compressed ping); nothing to parse.
+    Reply(String),
+}
+
 // This trait is the key abstraction that makes WebSocketClient exchange-agnostic.
 // Each exchange implements the follwing methods, WebSocketClient handles everything else.
 // Adding a new exchange = implement this trait, no changes to WebSocketClient.
@@ -20,19 +35,157 @@ pub trait MessageParser: Send + Sync + 'static {
         None
     }
 
+    /// Resolves the URL to connect to. Called by `WebSocketClient::connect` on every connect.
+    /// Default: `endpoint()` as-is. Override for exchanges that hand out a tokenized URL
+    /// over REST before connecting (e.g. KuCoin's bullet-public).
+    fn resolve_endpoint(
+        &self,
+    ) -> impl Future<Output = Result<String, CctError>> + Send {
+        let endpoint = self.endpoint().to_string();
+        async move { Ok(endpoint) }
+    }
+
+    /// Closed `interval` candles of `symbol` opening in `[start_ms, end_ms)`, oldest
+    /// first, from the exchange's REST API. Used to warm up history before live data
+    /// (`facade::App` backfill). Report HTTP failures as `CctError::ConnectFailed` and
+    /// unreadable bodies as `ParseFailed`. Default: no history (empty).
+    fn fetch_candles(
+        &self,
+        _symbol: &str,
+        _interval: Timeframe,
+        _start_ms: u64,
+        _end_ms: u64,
+    ) -> impl Future<Output = Result<Vec<Candle>, CctError>> + Send {
+        async { Ok(Vec::new()) }
+    }
+
+    /// Application-level ping payload and interval, for exchanges that drop the
+    /// connection without a JSON ping (WS-level pings are answered by tungstenite).
+    /// Default: None (no ping task).
+    fn ping_message(&self) -> Option<(String, Duration)> {
+        None
+    }
+
+    /// The exchange's name for a candle interval, as used in its candle channel.
+    /// None means the exchange has no such candles: `format_subscribe` then returns an
+    /// empty frame and the client rejects the subscription. Default: Binance's names
+    /// ("1m", "4h", "1M"), which exist for every named timeframe but no custom one.
+    fn interval_to_exchange(&self, interval: Timeframe) -> Option<String> {
+        match interval {
+            Timeframe::Custom(_) => None,
+            named => Some(named.as_str().into_owned()),
+        }
+    }
+
+    /// The timeframe for an interval name found in the exchange's candle messages;
+    /// the reverse of `interval_to_exchange`. Default: Binance's names.
+    fn interval_from_exchange(&self, value: &str) -> Option<Timeframe> {
+        Timeframe::from_str(value).filter(|interval| !matches!(interval, Timeframe::Custom(_)))
+    }
+
     // Each exchange has different JSON formats for subscribe/unsubscribe
     fn format_subscribe(&self, stream: &Stream) -> String;
     fn format_unsubscribe(&self, stream: &Stream) -> String;
 
+    /// Like `format_subscribe`, tagged with a request id the exchange echoes in its reply.
+    /// When this returns Some, `WebSocketClient::subscribe` waits for `parse_response`
+    /// to report the outcome for `id`. Default: None (send `format_subscribe`, no ack).
+    fn format_subscribe_with_id(&self, _stream: &Stream, _id: u64) -> Option<String> {
+        None
+    }
+
+    /// One frame subscribing to all of `streams`, for exchanges that accept several
+    /// channels per request (saves messages against rate limits). Default: empty
+    /// string, meaning the client sends one `format_subscribe` frame per stream.
+    fn format_subscribe_many(&self, _streams: &[Stream]) -> String {
+        String::new()
+    }
+
+    /// Like `format_subscribe_many`, tagged with a request id (see `format_subscribe_with_id`).
+    /// Default: None.
+    fn format_subscribe_many_with_id(&self, _streams: &[Stream], _id: u64) -> Option<String> {
+        None
+    }
+
+    /// Like `format_unsubscribe`, tagged with a request id. Default: None.
+    fn format_unsubscribe_with_id(&self, _stream: &Stream, _id: u64) -> Option<String> {
+        None
+    }
+
+    /// One frame unsubscribing from all of `streams`, used by `unsubscribe_all()` and
+    /// `unsubscribe_symbol()`. Default: empty string (one `format_unsubscribe` frame per stream).
+    fn format_unsubscribe_many(&self, _streams: &[Stream]) -> String {
+        String::new()
+    }
+
+    /// Like `format_unsubscribe_many`, tagged with a request id. Default: None.
+    fn format_unsubscribe_many_with_id(&self, _streams: &[Stream], _id: u64) -> Option<String> {
+        None
+    }
+
+    /// Recognizes a reply to a request sent with an id: `Some((id, Ok(())))` for an ack,
+    /// `Some((id, Err(reason)))` for a rejection, None for anything else.
+    /// Replies are consumed by the client and never reach `parse_messages`. Default: None.
+    fn parse_response(&self, _msg: &str) -> Option<(u64, Result<(), String>)> {
+        None
+    }
+
     /// Parses exchange-specific JSON into normalized MarketData.
     /// This is where exchange differences are absorbed - output is always MarketData.
     /// Returns Some(MarketData) for valid data, None for control messages.
     fn parse_message(&self, msg: &str) -> Option<MarketData>;
 
+    /// Parses a frame that may carry several items (e.g. an array of tickers).
+    /// This is what `WebSocketClient` calls. Default: `parse_message` wrapped in a Vec;
+    /// override for exchanges that batch data into one frame.
+    fn parse_messages(&self, msg: &str) -> Vec<MarketData> {
+        self.parse_message(msg).into_iter().collect()
+    }
+
+    /// Decodes a binary frame, for exchanges that compress their payloads (Huobi gzip,
+    /// some OKX endpoints deflate). `DecodedFrame::Text` goes through `parse_response`
+    /// and `parse_messages` like a text frame; `DecodedFrame::Reply` is sent back as a
+    /// text frame. See `market::compression` (feature `compression`). Default: None
+    /// (binary frames are ignored).
+    fn decode_binary(&self, _data: &[u8]) -> Option<DecodedFrame> {
+        None
+    }
+
+    /// Why `msg` produced no data, reported in `ConnectionEvent::StreamBroken` once a
+    /// stream trips `StreamHealthPolicy`. Default: the JSON syntax error, or a note that
+    /// nothing was recognized. Override to name the field that failed to deserialize.
+    fn parse_failure(&self, msg: &str) -> String {
+        match serde_json::from_str::<serde_json::Value>(msg) {
+            Err(e) => format!("invalid JSON: {}", e),
+            Ok(_) => "valid JSON, but no market data recognized".to_string(),
+        }
+    }
+
+    /// Identifies which stream a data message belongs to without fully parsing it
+    /// (typically from the event type and symbol fields). Lets the client attribute
+    /// parse failures to a subscription. Symbol case and order book depth may differ
+    /// from the subscribed Stream. Default: None (failures are not attributed).
+    fn stream_hint(&self, _msg: &str) -> Option<Stream> {
+        None
+    }
+
     fn name(&self) -> &'static str;
 
+    /// Most streams one connection may carry. `ShardedClient` opens another connection
+    /// beyond this. Default: no limit.
+    fn max_streams_per_connection(&self) -> usize {
+        usize::MAX
+    }
+
     /// Most exchanges have 24h connection limit. Default: 23 hours (safe margin).
     fn max_connection_duration_secs(&self) -> u64 {
         23 * 60 * 60
     }
+
+    /// How long a connection may go without receiving any frame before the stale-connection
+    /// watchdog (`WebSocketClient::with_stale_watchdog`) treats it as dead. Default: 60s.
+    /// Raise it for quiet streams that carry no server pings.
+    fn idle_timeout_secs(&self) -> u64 {
+        60
+    }
 }
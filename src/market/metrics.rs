@@ -0,0 +1,222 @@
+//! Runtime counters for a `WebSocketClient`.
+
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::market::latency::{LatencyStats, LatencyTracker};
+use crate::market::market_data::MarketData;
+
+// Design: the read task bumps these on every message, so they are plain atomics
+// shared through an Arc; `WebSocketClient::stats()` copies them into a
+// ClientStats snapshot without taking any lock. Latency percentiles need the
+// samples themselves, kept behind an uncontended mutex, so they are read on their
+// own through `WebSocketClient::latency()` and stay out of the snapshot.
+
+/// Counters shared between a client and its background tasks.
+#[derive(Debug)]
+pub(crate) struct ClientMetrics {
+    epoch: Instant,
+    frames_received: AtomicU64,
+    bytes_received: AtomicU64,
+    candles: AtomicU64,
+    trades: AtomicU64,
+    order_books: AtomicU64,
+    funding: AtomicU64,
+    other_parsed: AtomicU64,
+    parse_misses: AtomicU64,
+    dropped_messages: AtomicU64,
+    reconnects: AtomicU64,
+    paused_buffered: AtomicU64,
+    paused_dropped: AtomicU64,
+    // Milliseconds since `epoch`, plus one so that 0 can mean "not connected"
+    connected_at_ms: AtomicU64,
+    latency: Mutex<LatencyTracker>,
+}
+
+impl Default for ClientMetrics {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+            frames_received: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            candles: AtomicU64::new(0),
+            trades: AtomicU64::new(0),
+            order_books: AtomicU64::new(0),
+            funding: AtomicU64::new(0),
+            other_parsed: AtomicU64::new(0),
+            parse_misses: AtomicU64::new(0),
+            dropped_messages: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            paused_buffered: AtomicU64::new(0),
+            paused_dropped: AtomicU64::new(0),
+            connected_at_ms: AtomicU64::new(0),
+            latency: Mutex::new(LatencyTracker::default()),
+        }
+    }
+}
+
+impl ClientMetrics {
+    pub(crate) fn record_frame(&self, bytes: usize) {
+        self.frames_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_parsed(&self, market_data: &MarketData) {
+        let counter = match market_data {
+            MarketData::Candle { .. } => &self.candles,
+            MarketData::Trade(_) => &self.trades,
+            MarketData::OrderBook(_) => &self.order_books,
+            MarketData::Funding(_) => &self.funding,
+            _ => &self.other_parsed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.latency().record_data(market_data);
+    }
+
+    pub(crate) fn record_parse_miss(&self) {
+        self.parse_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dropped(&self) {
+        self.dropped_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_paused_buffered(&self) {
+        self.paused_buffered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_paused_dropped(&self) {
+        self.paused_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_connected(&self) {
+        let now_ms = self.epoch.elapsed().as_millis() as u64;
+        self.connected_at_ms.store(now_ms + 1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_disconnected(&self) {
+        self.connected_at_ms.store(0, Ordering::Relaxed);
+    }
+
+    fn latency(&self) -> MutexGuard<'_, LatencyTracker> {
+        self.latency.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn latency_stats(&self) -> LatencyStats {
+        self.latency().stats()
+    }
+
+    fn uptime(&self) -> Option<Duration> {
+        match self.connected_at_ms.load(Ordering::Relaxed) {
+            0 => None,
+            connected_ms => {
+                let connected = Duration::from_millis(connected_ms - 1);
+                Some(self.epoch.elapsed().saturating_sub(connected))
+            }
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ClientStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        ClientStats {
+            frames_received: load(&self.frames_received),
+            bytes_received: load(&self.bytes_received),
+            candles: load(&self.candles),
+            trades: load(&self.trades),
+            order_books: load(&self.order_books),
+            funding: load(&self.funding),
+            other_parsed: load(&self.other_parsed),
+            parse_misses: load(&self.parse_misses),
+            dropped_messages: load(&self.dropped_messages),
+            reconnects: load(&self.reconnects),
+            paused_buffered: load(&self.paused_buffered),
+            paused_dropped: load(&self.paused_dropped),
+            uptime: self.uptime(),
+        }
+    }
+}
+
+/// Point-in-time copy of a client's counters, see `WebSocketClient::stats()`.
+/// Counters accumulate over the client's lifetime, across reconnects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClientStats {
+    /// WebSocket frames of any kind, including pings and exchange replies.
+    pub frames_received: u64,
+    /// Payload bytes of those frames.
+    pub bytes_received: u64,
+    pub candles: u64,
+    pub trades: u64,
+    pub order_books: u64,
+    pub funding: u64,
+    /// Every other variant: mark prices, open interest, liquidations, tickers and
+    /// book tickers.
+    pub other_parsed: u64,
+    /// Text frames that produced no `MarketData` and weren't a reply to a request.
+    /// Includes exchange control messages, so compare the rate rather than expect zero;
+    /// a jump usually means the exchange changed a payload format.
+    pub parse_misses: u64,
+    /// Market data discarded because the consumer's channel was full
+    /// (see `OverflowPolicy`). A growing number means the consumer can't keep up.
+    pub dropped_messages: u64,
+    /// Successful reconnects, automatic or via `reconnect()`.
+    pub reconnects: u64,
+    /// Market data held back while paused (`PausePolicy::Buffer`), delivered on `resume()`.
+    pub paused_buffered: u64,
+    /// Market data discarded while paused: everything under `PausePolicy::Drop`,
+    /// buffer evictions under `PausePolicy::Buffer`.
+    pub paused_dropped: u64,
+    /// Time since the current connection opened, None while disconnected.
+    pub uptime: Option<Duration>,
+}
+
+impl ClientStats {
+    /// Total `MarketData` items parsed, across all variants.
+    pub fn parsed(&self) -> u64 {
+        self.candles + self.trades + self.order_books + self.funding + self.other_parsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{MarkPrice, OpenInterest, Trade, TradeSide};
+
+    #[test]
+    fn test_snapshot_counts_by_variant() {
+        let metrics = ClientMetrics::default();
+        assert_eq!(metrics.snapshot(), ClientStats::default());
+
+        let mut trade = MarketData::Trade(Trade::new(1_000, "BTCUSDT", 1.0, 1.0, "t", TradeSide::Buy));
+        trade.set_received_at(1_040);
+        metrics.record_frame(40);
+        metrics.record_parsed(&trade);
+        metrics.record_frame(10);
+        metrics.record_parse_miss();
+        metrics.record_connected();
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.frames_received, 2);
+        assert_eq!(stats.bytes_received, 50);
+        assert_eq!(stats.trades, 1);
+        assert_eq!(stats.parsed(), 1);
+        assert_eq!(stats.parse_misses, 1);
+        assert!(stats.uptime.is_some());
+        let latency = metrics.latency_stats();
+        assert_eq!(latency.samples, 1);
+        assert_eq!(latency.p99, Duration::from_millis(40));
+
+        metrics.record_disconnected();
+        assert_eq!(metrics.snapshot().uptime, None);
+
+        metrics.record_parsed(&MarketData::MarkPrice(MarkPrice::new(2_000, "BTCUSDT", 1.0)));
+        metrics.record_parsed(&MarketData::OpenInterest(OpenInterest::new(2_000, "BTCUSDT", 5.0)));
+        let stats = metrics.snapshot();
+        assert_eq!((stats.funding, stats.other_parsed), (0, 2));
+        assert_eq!(stats.parsed(), 3);
+    }
+}
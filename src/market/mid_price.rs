@@ -0,0 +1,340 @@
+//! Candles synthesized from the order book mid price instead of trades.
+
+use std::collections::HashMap;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::{MarketData, OrderBookUpdate};
+use crate::market::order_book::{ApplyResult, LocalOrderBook};
+use crate::market::symbol::Symbol;
+
+// Design: thin pairs can go minutes without a print, so trade candles have holes
+// exactly where spread-sensitive strategies need data. Sampling the mid on every
+// book change gives a continuous series. Each symbol keeps a LocalOrderBook (so
+// replayed or gapped deltas can't skew the mid) and one open bucket; a sample in
+// a later bucket closes the open one.
+
+/// What to do with timeframe buckets in which the mid never changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyBuckets {
+    /// Skip them; the output has gaps like a trade-built series would.
+    Omit,
+    /// Emit a flat candle at the previous close (zero volume).
+    CarryForward,
+}
+
+/// OHLC of the mid within one bucket.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    start: u64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    samples: u64,
+}
+
+impl Bucket {
+    fn start(start: u64, mid: f64) -> Self {
+        Self {
+            start,
+            open: mid,
+            high: mid,
+            low: mid,
+            close: mid,
+            samples: 1,
+        }
+    }
+
+    fn sample(&mut self, mid: f64) {
+        self.high = self.high.max(mid);
+        self.low = self.low.min(mid);
+        self.close = mid;
+        self.samples += 1;
+    }
+}
+
+/// Builds closed OHLC candles from order book mid prices, per symbol.
+///
+/// Feed it `OrderBookUpdate`s with `apply` (snapshots and deltas), or top-of-book
+/// quotes with `push_quote` / `push_mid`. Every call that moves into a later bucket
+/// returns the candles it closed as `MarketData::Candle { is_closed: true, .. }`,
+/// so the output plugs into anything that consumes trade candles.
+///
+/// Volume is 0 by default; `with_sample_volume(true)` sets it to the number of
+/// mid samples in the bucket instead (a rough activity measure, not traded size).
+#[derive(Debug, Clone)]
+pub struct MidPriceCandleBuilder {
+    timeframe: Timeframe,
+    empty_buckets: EmptyBuckets,
+    sample_volume: bool,
+    books: HashMap<Symbol, LocalOrderBook>,
+    buckets: HashMap<String, Bucket>,
+}
+
+impl MidPriceCandleBuilder {
+    pub fn new(timeframe: Timeframe) -> Self {
+        Self {
+            timeframe,
+            empty_buckets: EmptyBuckets::Omit,
+            sample_volume: false,
+            books: HashMap::new(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// How buckets without samples are handled (default: `EmptyBuckets::Omit`).
+    pub fn with_empty_buckets(mut self, empty_buckets: EmptyBuckets) -> Self {
+        self.empty_buckets = empty_buckets;
+        self
+    }
+
+    /// Use the sample count as candle volume instead of 0 (default: false).
+    pub fn with_sample_volume(mut self, sample_volume: bool) -> Self {
+        self.sample_volume = sample_volume;
+        self
+    }
+
+    pub fn timeframe(&self) -> Timeframe {
+        self.timeframe
+    }
+
+    /// Applies a book update and samples the resulting mid.
+    /// One-sided or empty books produce no sample, and neither do updates the book
+    /// rejects (stale, or after a sequence gap until the next snapshot; see
+    /// `LocalOrderBook::apply`). Candles carry the update's exchange.
+    pub fn apply(&mut self, update: &OrderBookUpdate) -> Vec<MarketData> {
+        let book = self.books.entry(update.symbol.clone()).or_default();
+        if book.apply(update) != ApplyResult::Applied {
+            return Vec::new();
+        }
+        let Some(mid) = book.mid_price() else { return Vec::new() };
+        let mut candles = self.push_mid(&update.symbol, update.timestamp, mid);
+        for candle in &mut candles {
+            candle.set_exchange(update.exchange);
+        }
+        candles
+    }
+
+    /// Samples the mid of a best bid/ask quote (e.g. from a book ticker stream).
+    pub fn push_quote(&mut self, symbol: &str, timestamp: u64, bid: f64, ask: f64) -> Vec<MarketData> {
+        self.push_mid(symbol, timestamp, (bid + ask) / 2.0)
+    }
+
+    /// Records one mid sample. Returns the candles closed by moving to a later bucket.
+    /// Samples older than the open bucket are ignored.
+    pub fn push_mid(&mut self, symbol: &str, timestamp: u64, mid: f64) -> Vec<MarketData> {
+        let step = self.timeframe.to_millis();
+        let start = timestamp - timestamp % step;
+
+        let Some(bucket) = self.buckets.get_mut(symbol) else {
+            self.buckets.insert(symbol.to_string(), Bucket::start(start, mid));
+            return Vec::new();
+        };
+
+        if start == bucket.start {
+            bucket.sample(mid);
+            return Vec::new();
+        }
+        if start < bucket.start {
+            return Vec::new();
+        }
+
+        let closed = *bucket;
+        *bucket = Bucket::start(start, mid);
+
+        let mut output = vec![self.to_market_data(symbol, &closed)];
+        if self.empty_buckets == EmptyBuckets::CarryForward {
+            let mut gap_start = closed.start.saturating_add(step);
+            while gap_start < start {
+                let flat = Bucket {
+                    start: gap_start,
+                    samples: 0,
+                    ..Bucket::start(gap_start, closed.close)
+                };
+                output.push(self.to_market_data(symbol, &flat));
+                gap_start = gap_start.saturating_add(step);
+            }
+        }
+        output
+    }
+
+    /// The open (unfinished) candle for a symbol.
+    pub fn current(&self, symbol: &str) -> Option<Candle> {
+        self.buckets.get(symbol).map(|bucket| self.to_candle(bucket))
+    }
+
+    /// Closes and returns the open candle for a symbol (e.g. at end of a replay).
+    pub fn flush(&mut self, symbol: &str) -> Option<MarketData> {
+        let bucket = self.buckets.remove(symbol)?;
+        Some(self.to_market_data(symbol, &bucket))
+    }
+
+    fn to_candle(&self, bucket: &Bucket) -> Candle {
+        let volume = if self.sample_volume {
+            bucket.samples as f64
+        } else {
+            0.0
+        };
+        Candle::new(bucket.start, bucket.open, bucket.high, bucket.low, bucket.close, volume)
+    }
+
+    fn to_market_data(&self, symbol: &str, bucket: &Bucket) -> MarketData {
+        MarketData::candle(symbol, self.timeframe, self.to_candle(bucket), true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::moving_averages::sma;
+    use crate::market::market_data::PriceLevel;
+
+    const MINUTE: u64 = 60_000;
+
+    fn book(ts: u64, bid: f64, ask: f64) -> OrderBookUpdate {
+        OrderBookUpdate::snapshot(
+            ts,
+            "BTCUSDT",
+            vec![PriceLevel::new(bid, 1.0), PriceLevel::new(bid - 1.0, 2.0)],
+            vec![PriceLevel::new(ask, 1.0), PriceLevel::new(ask + 1.0, 2.0)],
+        )
+    }
+
+    fn candles(output: Vec<MarketData>) -> Vec<Candle> {
+        output
+            .into_iter()
+            .map(|data| match data {
+                MarketData::Candle { data, is_closed: true, .. } => data,
+                other => panic!("Expected closed candle, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ohlc_across_three_buckets() {
+        let mut builder = MidPriceCandleBuilder::new(Timeframe::M1).with_sample_volume(true);
+        let mut closed = Vec::new();
+
+        // Bucket 0: mids 100, 102, 99, 101
+        closed.extend(candles(builder.apply(&book(0, 99.0, 101.0))));
+        closed.extend(candles(builder.apply(&book(10_000, 101.0, 103.0))));
+        closed.extend(candles(builder.apply(&book(20_000, 98.0, 100.0))));
+        closed.extend(candles(builder.apply(&book(50_000, 100.0, 102.0))));
+        assert!(closed.is_empty());
+
+        // Bucket 1: mids 105, 104
+        closed.extend(candles(builder.apply(&book(MINUTE, 104.0, 106.0))));
+        closed.extend(candles(builder.apply(&book(MINUTE + 30_000, 103.0, 105.0))));
+
+        // Bucket 2 opens and closes bucket 1
+        closed.extend(candles(builder.apply(&book(2 * MINUTE + 5_000, 106.0, 108.0))));
+
+        assert_eq!(closed.len(), 2);
+        let first = closed[0];
+        assert_eq!(first.get_timestamp(), 0);
+        assert_eq!(
+            (first.get_open(), first.get_high(), first.get_low(), first.get_close()),
+            (100.0, 102.0, 99.0, 101.0)
+        );
+        assert_eq!(first.get_volume(), 4.0);
+
+        let second = closed[1];
+        assert_eq!(second.get_timestamp(), MINUTE);
+        assert_eq!(
+            (second.get_open(), second.get_high(), second.get_low(), second.get_close()),
+            (105.0, 105.0, 104.0, 104.0)
+        );
+
+        let current = builder.current("BTCUSDT").unwrap();
+        assert_eq!(current.get_timestamp(), 2 * MINUTE);
+        assert_eq!(current.get_close(), 107.0);
+    }
+
+    #[test]
+    fn test_deltas_move_best_levels() {
+        let mut builder = MidPriceCandleBuilder::new(Timeframe::M1);
+        builder.apply(&book(0, 99.0, 101.0));
+
+        // Best ask removed: next ask 102 -> mid 100.5
+        let delta = OrderBookUpdate::delta(1_000, "BTCUSDT", vec![], vec![PriceLevel::new(101.0, 0.0)]);
+        builder.apply(&delta);
+        // New best bid 100 -> mid 101
+        let delta = OrderBookUpdate::delta(2_000, "BTCUSDT", vec![PriceLevel::new(100.0, 3.0)], vec![]);
+        builder.apply(&delta);
+
+        let current = builder.current("BTCUSDT").unwrap();
+        assert_eq!(current.get_open(), 100.0);
+        assert_eq!(current.get_low(), 100.0);
+        assert_eq!(current.get_close(), 101.0);
+        assert_eq!(current.get_volume(), 0.0);
+    }
+
+    #[test]
+    fn test_rejected_deltas_are_not_sampled() {
+        let mut builder = MidPriceCandleBuilder::new(Timeframe::M1);
+        builder.apply(&book(0, 99.0, 101.0).with_sequence(10));
+
+        // Replayed id 10, then a gap (12 after 10): the book rejects both
+        let replay = OrderBookUpdate::delta(1_000, "BTCUSDT", vec![PriceLevel::new(100.5, 1.0)], vec![]);
+        builder.apply(&replay.with_sequence(10));
+        let gapped = OrderBookUpdate::delta(2_000, "BTCUSDT", vec![], vec![PriceLevel::new(90.0, 1.0)]);
+        builder.apply(&gapped.with_sequence_range(12, 12));
+        assert_eq!(builder.current("BTCUSDT").unwrap().get_low(), 100.0);
+
+        // A snapshot resumes sampling
+        builder.apply(&book(3_000, 103.0, 105.0).with_sequence(20));
+        assert_eq!(builder.current("BTCUSDT").unwrap().get_close(), 104.0);
+    }
+
+    #[test]
+    fn test_empty_buckets_omitted_or_carried_forward() {
+        let script = [(0, 99.0, 101.0), (10_000, 100.0, 102.0), (3 * MINUTE, 110.0, 112.0)];
+
+        let mut omit = MidPriceCandleBuilder::new(Timeframe::M1);
+        let mut carry = MidPriceCandleBuilder::new(Timeframe::M1).with_empty_buckets(EmptyBuckets::CarryForward);
+        let (mut omitted, mut carried) = (Vec::new(), Vec::new());
+        for (ts, bid, ask) in script {
+            omitted.extend(candles(omit.apply(&book(ts, bid, ask))));
+            carried.extend(candles(carry.apply(&book(ts, bid, ask))));
+        }
+
+        assert_eq!(omitted.len(), 1);
+        assert_eq!(omitted[0].get_close(), 101.0);
+
+        // Bucket 0 plus flat candles for minutes 1 and 2
+        assert_eq!(carried.len(), 3);
+        for (i, flat) in carried[1..].iter().enumerate() {
+            assert_eq!(flat.get_timestamp(), (i as u64 + 1) * MINUTE);
+            assert_eq!(
+                (flat.get_open(), flat.get_high(), flat.get_low(), flat.get_close()),
+                (101.0, 101.0, 101.0, 101.0)
+            );
+            assert_eq!(flat.get_volume(), 0.0);
+        }
+
+        // Output feeds indicator functions directly
+        assert_eq!(sma(&carried, 3), Some(101.0));
+    }
+
+    #[test]
+    fn test_one_sided_book_and_late_samples_ignored() {
+        let mut builder = MidPriceCandleBuilder::new(Timeframe::M1);
+        let bids_only = OrderBookUpdate::snapshot(0, "BTCUSDT", vec![PriceLevel::new(99.0, 1.0)], vec![]);
+        assert!(builder.apply(&bids_only).is_empty());
+        assert!(builder.current("BTCUSDT").is_none());
+
+        builder.push_quote("BTCUSDT", MINUTE, 99.0, 101.0);
+        assert!(builder.push_mid("BTCUSDT", 1_000, 50.0).is_empty());
+        assert_eq!(builder.current("BTCUSDT").unwrap().get_low(), 100.0);
+
+        match builder.flush("BTCUSDT") {
+            Some(MarketData::Candle { interval, is_closed, .. }) => {
+                assert_eq!(interval, Timeframe::M1);
+                assert!(is_closed);
+            }
+            other => panic!("Expected closed candle, got {:?}", other),
+        }
+        assert!(builder.current("BTCUSDT").is_none());
+    }
+}
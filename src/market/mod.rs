@@ -1,24 +1,79 @@
 //! Market data module for exchange connections.
 //! See docs/market/README.md for detailed documentation.
 
+pub mod analytics;
+pub mod best_quote;
+pub mod closed_candles;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod config;
+pub mod data_stream;
+mod delivery;
+pub mod error;
+pub mod events;
+pub mod latency;
+pub mod lifecycle;
+pub mod manager;
 pub mod market_data;
 pub mod message_parser;
+pub mod metrics;
+pub mod mid_price;
+pub mod order_book;
+pub mod partial_book;
+pub mod recorder;
+pub mod replay;
+pub mod sharded_client;
 pub mod websocket_client;
 pub mod streams;
+pub mod symbol;
+pub mod timestamp;
+pub mod trade_candles;
 pub mod providers;
 
 // Re-exports for convenience
 pub use market_data::{
+    ExchangeId,
     MarketData,
     Trade,
     OrderBookUpdate,
     FundingRate,
+    MarkPrice,
+    Liquidation,
+    OpenInterest,
+    BookTicker,
+    Ticker,
     TradeSide,
     PriceLevel,
 };
-pub use message_parser::MessageParser;
-pub use websocket_client::WebSocketClient;
+pub use best_quote::{BestQuote, BestQuoteStream, BestQuoteTracker};
+pub use closed_candles::{ClosedCandle, ClosedCandleFilter, ClosedCandleStream};
+pub use config::{ClientConfig, OverflowPolicy, PausePolicy};
+pub use data_stream::MarketDataStream;
+pub use error::CctError;
+pub use events::{ConnectionEvent, ConnectionState};
+pub use latency::{LatencyStats, LatencyTracker};
+pub use message_parser::{DecodedFrame, MessageParser};
+pub use manager::{ExchangeClient, ExchangeStatus, MultiExchangeManager};
+pub use metrics::ClientStats;
+pub use order_book::{
+    ApplyResult, BookView, ChecksumFormat, ChecksumMismatch, FillEstimate, ImbalanceSeries, LocalOrderBook, PriceBuckets,
+};
+pub use partial_book::PartialBook;
+pub use recorder::{MarketDataRecorder, RecorderHandle};
+pub use replay::{ReplayClient, ReplaySpeed};
+pub use sharded_client::ShardedClient;
+pub use trade_candles::CandleAggregator;
+pub use websocket_client::{ReconnectPolicy, WebSocketClient};
 pub use streams::Stream;
+pub use symbol::{Symbol, SymbolCache};
 
 // Re-export provider convenience functions
-pub use providers::binance::new_binance_client;
+pub use providers::binance::{
+    new_binance_client, new_binance_client_combined, new_binance_testnet_client,
+};
+pub use providers::binance_futures::new_binance_futures_client;
+pub use providers::bitget::new_bitget_client;
+pub use providers::deribit::new_deribit_client;
+pub use providers::gateio::new_gateio_client;
+pub use providers::kucoin::new_kucoin_client;
+pub use providers::mexc::new_mexc_client;
@@ -0,0 +1,1085 @@
+//! `LocalOrderBook`: a full order book maintained from `OrderBookUpdate` snapshots
+//! and deltas.
+//!
+//! Deltas only make sense on top of the exact state they were computed from. For
+//! exchanges that send update id ranges (`first_sequence`/`final_sequence`), a
+//! missing range means the book is wrong, and it stays unusable until a snapshot.
+//! Binance's procedure for syncing a diff-depth stream with a REST snapshot:
+//!
+//! ```
+//! use cct::market::market_data::{OrderBookUpdate, PriceLevel};
+//! use cct::market::order_book::{ApplyResult, LocalOrderBook};
+//!
+//! fn delta(first: u64, last: u64, bid: f64) -> OrderBookUpdate {
+//!     OrderBookUpdate::delta(0, "BTCUSDT", vec![PriceLevel::new(bid, 1.0)], vec![])
+//!         .with_sequence_range(first, last)
+//! }
+//!
+//! // 1. Subscribe to <symbol>@depth and buffer the deltas while fetching the snapshot
+//! let buffered = vec![delta(95, 99, 10.0), delta(100, 104, 11.0), delta(105, 107, 12.0)];
+//!
+//! // 2. GET /api/v3/depth; its "lastUpdateId" is the snapshot's sequence
+//! let snapshot = OrderBookUpdate::snapshot(0, "BTCUSDT", vec![PriceLevel::new(9.0, 1.0)], vec![])
+//!     .with_sequence(102);
+//! let mut book = LocalOrderBook::new();
+//! book.apply(&snapshot);
+//!
+//! // 3. Drop buffered deltas the snapshot already contains (last id <= lastUpdateId).
+//! //    The first one kept straddles it: first <= lastUpdateId + 1 <= last.
+//! for update in buffered.iter().filter(|update| update.final_sequence > snapshot.sequence) {
+//!     assert_eq!(book.apply(update), ApplyResult::Applied);
+//! }
+//! assert_eq!(book.best_bid().map(|level| level.price), Some(12.0));
+//!
+//! // 4. Keep applying live deltas. A gap means updates were lost: start over from 1.
+//! let result = book.apply(&delta(110, 112, 13.0));
+//! assert_eq!(result, ApplyResult::GapDetected { expected: 108, got: 110 });
+//! assert!(!book.is_valid());
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::series::TimeSeries;
+use crate::market::market_data::{OrderBookUpdate, PriceLevel, TradeSide};
+
+/// An f64 price usable as a map key, ordered by `f64::total_cmp`.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderedPrice(pub f64);
+
+impl PartialEq for OrderedPrice {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedPrice {}
+
+impl PartialOrd for OrderedPrice {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedPrice {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl From<f64> for OrderedPrice {
+    fn from(price: f64) -> Self {
+        OrderedPrice(price)
+    }
+}
+
+/// How an exchange builds the string its book checksum is the CRC32 of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumFormat {
+    /// Kraken (WebSocket v2): asks ascending, then bids descending. Each price and
+    /// quantity is printed with the instrument's precision (trailing zeros kept),
+    /// then stripped of the decimal point and leading zeros, and everything is
+    /// concatenated: 0.05005 / 0.00000500 at precisions 5 / 8 becomes "5005500".
+    Kraken { price_precision: usize, qty_precision: usize },
+    /// OKX (and Bitget's "books" channel): "bid price:bid size:ask price:ask size:..."
+    /// alternating level by level, continuing with the longer side alone. Numbers are
+    /// printed in their shortest form, which matches OKX's strings as long as it
+    /// doesn't pad with zeros.
+    Okx,
+}
+
+impl ChecksumFormat {
+    /// Levels per side the exchange checksums: 10 for Kraken, 25 for OKX.
+    pub fn depth(&self) -> usize {
+        match self {
+            ChecksumFormat::Kraken { .. } => 10,
+            ChecksumFormat::Okx => 25,
+        }
+    }
+}
+
+/// The local book doesn't match the exchange's checksum: it missed or misapplied
+/// an update and needs a new snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    pub expected: u32,
+    pub computed: u32,
+    pub format: ChecksumFormat,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order book checksum mismatch: exchange sent {}, local book gives {} ({:?})",
+            self.expected, self.computed, self.format
+        )
+    }
+}
+
+impl Error for ChecksumMismatch {}
+
+/// One side of a book grouped into price bands: (bucket price, total quantity),
+/// best first. See `LocalOrderBook::aggregate`.
+pub type PriceBuckets = Vec<(f64, f64)>;
+
+/// What a market order would get if it hit the book now. See
+/// `LocalOrderBook::cost_to_buy`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FillEstimate {
+    pub side: TradeSide,
+    /// Base quantity filled; less than asked for when the book is too shallow.
+    pub filled_quantity: f64,
+    /// Quote notional paid (buy) or received (sell) for `filled_quantity`.
+    pub cost: f64,
+    /// `cost / filled_quantity`; None when nothing filled.
+    pub average_price: Option<f64>,
+    /// How much worse than the mid the average price is, in percent (positive for
+    /// both sides). None when nothing filled or the book has no mid.
+    pub slippage_pct: Option<f64>,
+    /// Whether the whole order fit in the book.
+    pub complete: bool,
+}
+
+/// Read access to the top of a book, shared by `LocalOrderBook` and `PartialBook`
+/// so code that only reads prices works with either.
+pub trait BookView {
+    fn best_bid(&self) -> Option<PriceLevel>;
+
+    fn best_ask(&self) -> Option<PriceLevel>;
+
+    /// The best `n` levels per side: bids by price descending, asks ascending.
+    fn depth(&self, n: usize) -> (Vec<PriceLevel>, Vec<PriceLevel>);
+
+    fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Best ask minus best bid.
+    fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+}
+
+/// What `LocalOrderBook::apply` did with an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyResult {
+    Applied,
+    /// Nothing newer than the book: ignored.
+    Stale,
+    /// Update ids `expected..got` never arrived. The book is invalid and ignores
+    /// deltas until the next snapshot; each one reports the gap again.
+    GapDetected { expected: u64, got: u64 },
+}
+
+/// Price -> quantity for both sides of one symbol's book.
+///
+/// A snapshot replaces the whole book; a delta sets the quantity of each level it
+/// lists, 0 removing the level. Deltas whose `sequence` is not newer than the last
+/// applied one are rejected, so a replayed or reordered update can't roll the book
+/// back. Deltas with an id range must continue right after the last applied id.
+#[derive(Debug, Clone)]
+pub struct LocalOrderBook {
+    bids: BTreeMap<OrderedPrice, f64>,
+    asks: BTreeMap<OrderedPrice, f64>,
+    last_update_id: Option<u64>,
+    timestamp: u64,
+    valid: bool,
+}
+
+impl Default for LocalOrderBook {
+    fn default() -> Self {
+        Self {
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            last_update_id: None,
+            timestamp: 0,
+            valid: true,
+        }
+    }
+}
+
+impl LocalOrderBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a snapshot or delta. Snapshots always apply and make the book valid again.
+    pub fn apply(&mut self, update: &OrderBookUpdate) -> ApplyResult {
+        if !update.is_snapshot
+            && let Some(last) = self.last_update_id
+        {
+            let result = self.check_delta(update, last);
+            if result != ApplyResult::Applied {
+                return result;
+            }
+        }
+
+        if update.is_snapshot {
+            self.bids.clear();
+            self.asks.clear();
+            self.valid = true;
+        }
+        apply_levels(&mut self.bids, &update.bids);
+        apply_levels(&mut self.asks, &update.asks);
+        if update.sequence.is_some() {
+            self.last_update_id = update.sequence;
+        }
+        self.timestamp = update.timestamp;
+        ApplyResult::Applied
+    }
+
+    /// Whether a delta can go on top of `last`, the book's last update id.
+    fn check_delta(&mut self, update: &OrderBookUpdate, last: u64) -> ApplyResult {
+        let expected = last + 1;
+        let gap = |got| ApplyResult::GapDetected { expected, got };
+        let final_sequence = update.final_sequence.or(update.sequence);
+
+        if !self.valid {
+            return gap(update.first_sequence.or(final_sequence).unwrap_or(expected));
+        }
+        if final_sequence.is_some_and(|final_sequence| final_sequence <= last) {
+            return ApplyResult::Stale;
+        }
+        if let Some(first) = update.first_sequence
+            && first > expected
+        {
+            self.valid = false;
+            return gap(first);
+        }
+        ApplyResult::Applied
+    }
+
+    /// False after a gap, until the next snapshot. An invalid book still answers
+    /// queries with its last good state.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// Sequence of the last applied update that had one.
+    pub fn last_update_id(&self) -> Option<u64> {
+        self.last_update_id
+    }
+
+    /// Exchange timestamp of the last applied update, 0 before the first.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.iter().next_back().map(|(price, quantity)| PriceLevel::new(price.0, *quantity))
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.iter().next().map(|(price, quantity)| PriceLevel::new(price.0, *quantity))
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Best ask minus best bid.
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// The best `n` levels per side: bids by price descending, asks ascending.
+    pub fn depth(&self, n: usize) -> (Vec<PriceLevel>, Vec<PriceLevel>) {
+        let level = |(price, quantity): (&OrderedPrice, &f64)| PriceLevel::new(price.0, *quantity);
+        let bids = self.bids.iter().rev().take(n).map(level).collect();
+        let asks = self.asks.iter().take(n).map(level).collect();
+        (bids, asks)
+    }
+
+    /// Total (bid, ask) quantity priced within `pct_of_mid` percent of the mid,
+    /// e.g. 0.5 sums bids down to 0.5% below the mid and asks up to 0.5% above it.
+    /// (0, 0) while either side is empty.
+    pub fn total_quantity_within(&self, pct_of_mid: f64) -> (f64, f64) {
+        let Some(mid) = self.mid_price() else {
+            return (0.0, 0.0);
+        };
+        let band = mid * pct_of_mid / 100.0;
+        let bids = self.bids.range(OrderedPrice(mid - band)..).map(|(_, quantity)| quantity).sum();
+        let asks = self.asks.range(..=OrderedPrice(mid + band)).map(|(_, quantity)| quantity).sum();
+        (bids, asks)
+    }
+
+    /// Quantity summed into price buckets of `bucket_size`: (bids, asks) as
+    /// (bucket price, quantity), best first. A bid counts toward the bucket at or
+    /// below it (floor(price / size) * size), an ask toward the one at or above it
+    /// (ceil), so no bucket straddles the spread. When `bucket_size` is below the
+    /// smallest gap between levels each level would get its own bucket anyway, and
+    /// the raw levels are returned with their exact prices.
+    pub fn aggregate(&self, bucket_size: f64) -> (PriceBuckets, PriceBuckets) {
+        if bucket_size.is_nan() || bucket_size <= 0.0 || bucket_size < self.min_level_gap() {
+            let raw = |(price, quantity): (&OrderedPrice, &f64)| (price.0, *quantity);
+            return (self.bids.iter().rev().map(raw).collect(), self.asks.iter().map(raw).collect());
+        }
+
+        let bids = bucket_quantities(&self.bids, bucket_size, f64::floor);
+        let asks = bucket_quantities(&self.asks, bucket_size, f64::ceil);
+        let bucket = |(index, quantity): (i64, f64)| (index as f64 * bucket_size, quantity);
+        (bids.into_iter().rev().map(bucket).collect(), asks.into_iter().map(bucket).collect())
+    }
+
+    /// `bands` consecutive buckets of `bucket_size` on each side of the mid, nearest
+    /// first, empty buckets included with quantity 0: a fixed-width histogram for
+    /// depth charts. Both sides are empty while either side of the book is.
+    pub fn depth_profile(&self, bands: usize, bucket_size: f64) -> (PriceBuckets, PriceBuckets) {
+        let Some(mid) = self.mid_price().filter(|_| bucket_size > 0.0) else {
+            return (Vec::new(), Vec::new());
+        };
+        let bids = bucket_quantities(&self.bids, bucket_size, f64::floor);
+        let asks = bucket_quantities(&self.asks, bucket_size, f64::ceil);
+
+        let (bid_start, ask_start) = (bucket_index(mid, bucket_size, f64::floor), bucket_index(mid, bucket_size, f64::ceil));
+        let band = |buckets: &BTreeMap<i64, f64>, index: i64| {
+            (index as f64 * bucket_size, buckets.get(&index).copied().unwrap_or(0.0))
+        };
+        (
+            (0..bands as i64).map(|k| band(&bids, bid_start - k)).collect(),
+            (0..bands as i64).map(|k| band(&asks, ask_start + k)).collect(),
+        )
+    }
+
+    /// Smallest distance between two adjacent levels on either side; infinite with
+    /// fewer than two levels per side.
+    fn min_level_gap(&self) -> f64 {
+        let gaps = |side: &BTreeMap<OrderedPrice, f64>| {
+            side.keys().zip(side.keys().skip(1)).map(|(low, high)| high.0 - low.0).fold(f64::INFINITY, f64::min)
+        };
+        gaps(&self.bids).min(gaps(&self.asks))
+    }
+
+    // Microstructure metrics: all None while either side is empty.
+
+    /// (bid qty - ask qty) / (bid qty + ask qty) over the best `depth` levels per side:
+    /// +1 is all bids, -1 all asks.
+    pub fn imbalance(&self, depth: usize) -> Option<f64> {
+        let (bid_qty, ask_qty) = self.top_quantities(depth)?;
+        ratio(bid_qty, ask_qty)
+    }
+
+    /// Mid weighted by the opposite side's size at the top of the book, so it leans
+    /// toward the side more likely to be taken out next.
+    pub fn microprice(&self) -> Option<f64> {
+        self.weighted_mid(1)
+    }
+
+    /// `microprice()` over the best `depth` levels: each side's volume-weighted price,
+    /// weighted by the other side's total quantity.
+    pub fn weighted_mid(&self, depth: usize) -> Option<f64> {
+        let (bids, asks) = self.depth(depth);
+        let (bid_vwap, bid_qty) = vwap(&bids)?;
+        let (ask_vwap, ask_qty) = vwap(&asks)?;
+        Some((bid_vwap * ask_qty + ask_vwap * bid_qty) / (bid_qty + ask_qty))
+    }
+
+    /// `imbalance` over the levels within `pct_of_mid` percent of the mid (see
+    /// `total_quantity_within`) instead of a level count. None when the band holds
+    /// no levels.
+    pub fn book_pressure(&self, pct_of_mid: f64) -> Option<f64> {
+        self.mid_price()?;
+        let (bid_qty, ask_qty) = self.total_quantity_within(pct_of_mid);
+        ratio(bid_qty, ask_qty)
+    }
+
+    /// Walks the asks for a market buy of `quantity`, taking part of the last level
+    /// it reaches. If the asks run out first the estimate covers what they hold and
+    /// `complete` is false.
+    pub fn cost_to_buy(&self, quantity: f64) -> FillEstimate {
+        let asks = self.asks.iter().map(|(price, available)| (price.0, *available));
+        self.fill(TradeSide::Buy, asks, Fill::Quantity(quantity))
+    }
+
+    /// `cost_to_buy` for a market sell, walking the bids.
+    pub fn cost_to_sell(&self, quantity: f64) -> FillEstimate {
+        let bids = self.bids.iter().rev().map(|(price, available)| (price.0, *available));
+        self.fill(TradeSide::Sell, bids, Fill::Quantity(quantity))
+    }
+
+    /// The inverse of `cost_to_buy`: how much a market buy spending `notional` (in
+    /// the quote currency) gets.
+    pub fn quantity_for_notional(&self, notional: f64) -> FillEstimate {
+        let asks = self.asks.iter().map(|(price, available)| (price.0, *available));
+        self.fill(TradeSide::Buy, asks, Fill::Notional(notional))
+    }
+
+    fn fill(&self, side: TradeSide, levels: impl Iterator<Item = (f64, f64)>, target: Fill) -> FillEstimate {
+        let (mut filled_quantity, mut cost) = (0.0, 0.0);
+        // Best and worst price touched, to keep the average's rounding inside them
+        let mut touched: Option<(f64, f64)> = None;
+        let mut complete = match target {
+            Fill::Quantity(quantity) | Fill::Notional(quantity) => quantity <= 0.0,
+        };
+        for (price, available) in levels {
+            if complete {
+                break;
+            }
+            touched = Some((touched.map_or(price, |(best, _)| best), price));
+            // The last level is only partly consumed; finishing on the target itself
+            // keeps float error out of the totals
+            match target {
+                Fill::Quantity(quantity) if available >= quantity - filled_quantity => {
+                    cost += (quantity - filled_quantity) * price;
+                    filled_quantity = quantity;
+                    complete = true;
+                }
+                Fill::Notional(notional) if available * price >= notional - cost => {
+                    filled_quantity += (notional - cost) / price;
+                    cost = notional;
+                    complete = true;
+                }
+                _ => {
+                    filled_quantity += available;
+                    cost += available * price;
+                }
+            }
+        }
+
+        let average_price = touched.filter(|_| filled_quantity > 0.0).map(|(best, worst)| {
+            let average = cost / filled_quantity;
+            average.clamp(best.min(worst), best.max(worst))
+        });
+        let slippage_pct = average_price.zip(self.mid_price()).map(|(average, mid)| match side {
+            TradeSide::Buy => (average - mid) / mid * 100.0,
+            TradeSide::Sell => (mid - average) / mid * 100.0,
+        });
+        FillEstimate { side, filled_quantity, cost, average_price, slippage_pct, complete }
+    }
+
+    /// Summed (bid, ask) quantity of the best `depth` levels, None if a side is empty.
+    fn top_quantities(&self, depth: usize) -> Option<(f64, f64)> {
+        if self.bids.is_empty() || self.asks.is_empty() {
+            return None;
+        }
+        let bids = self.bids.values().rev().take(depth).sum();
+        let asks = self.asks.values().take(depth).sum();
+        Some((bids, asks))
+    }
+
+    /// CRC32 over the best `depth` levels per side, built the way `format` says.
+    pub fn checksum(&self, depth: usize, format: ChecksumFormat) -> u32 {
+        crc32fast::hash(self.checksum_string(depth, format).as_bytes())
+    }
+
+    /// The string `checksum` hashes, for comparing against the exchange's when they differ.
+    pub fn checksum_string(&self, depth: usize, format: ChecksumFormat) -> String {
+        let (bids, asks) = self.depth(depth);
+        match format {
+            ChecksumFormat::Kraken { price_precision, qty_precision } => asks
+                .iter()
+                .chain(&bids)
+                .map(|level| {
+                    kraken_digits(level.price, price_precision) + &kraken_digits(level.quantity, qty_precision)
+                })
+                .collect(),
+            ChecksumFormat::Okx => {
+                let mut fields = Vec::with_capacity(4 * depth);
+                for i in 0..bids.len().max(asks.len()) {
+                    for level in [bids.get(i), asks.get(i)].into_iter().flatten() {
+                        fields.push(level.price.to_string());
+                        fields.push(level.quantity.to_string());
+                    }
+                }
+                fields.join(":")
+            }
+        }
+    }
+
+    /// Checks the book against an exchange checksum (`OrderBookUpdate::checksum`),
+    /// over `format.depth()` levels.
+    pub fn verify_checksum(&self, expected: u32, format: ChecksumFormat) -> Result<(), ChecksumMismatch> {
+        let computed = self.checksum(format.depth(), format);
+        if computed == expected {
+            Ok(())
+        } else {
+            Err(ChecksumMismatch { expected, computed, format })
+        }
+    }
+
+    /// Number of (bid, ask) levels.
+    pub fn len(&self) -> (usize, usize) {
+        (self.bids.len(), self.asks.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Empties the book and forgets the last update id, e.g. before a resync.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl BookView for LocalOrderBook {
+    fn best_bid(&self) -> Option<PriceLevel> {
+        LocalOrderBook::best_bid(self)
+    }
+
+    fn best_ask(&self) -> Option<PriceLevel> {
+        LocalOrderBook::best_ask(self)
+    }
+
+    fn depth(&self, n: usize) -> (Vec<PriceLevel>, Vec<PriceLevel>) {
+        LocalOrderBook::depth(self, n)
+    }
+}
+
+/// How much of the book a `FillEstimate` walks through.
+#[derive(Debug, Clone, Copy)]
+enum Fill {
+    /// Base quantity.
+    Quantity(f64),
+    /// Quote currency to spend.
+    Notional(f64),
+}
+
+/// (a - b) / (a + b), None when both are 0.
+fn ratio(a: f64, b: f64) -> Option<f64> {
+    let total = a + b;
+    (total > 0.0).then(|| (a - b) / total)
+}
+
+/// Volume-weighted price and total quantity of `levels`, None if there are none.
+fn vwap(levels: &[PriceLevel]) -> Option<(f64, f64)> {
+    let quantity: f64 = levels.iter().map(|level| level.quantity).sum();
+    let notional: f64 = levels.iter().map(|level| level.price * level.quantity).sum();
+    (quantity > 0.0).then(|| (notional / quantity, quantity))
+}
+
+/// Rolling order book imbalance, sampled after every update applied to its book.
+///
+/// Samples are kept per book timestamp (a later update in the same ms replaces the
+/// sample) and the oldest are dropped past `capacity`. `to_series()` and
+/// `to_candles()` hand them to the indicator functions, e.g.
+/// `sma(&imbalance.to_candles(), 20)`.
+#[derive(Debug, Clone)]
+pub struct ImbalanceSeries {
+    book: LocalOrderBook,
+    depth: usize,
+    capacity: usize,
+    samples: VecDeque<(u64, f64)>,
+}
+
+impl ImbalanceSeries {
+    /// Imbalance over the best `depth` levels; keeps the last `capacity` samples
+    /// (at least 1).
+    pub fn new(depth: usize, capacity: usize) -> Self {
+        Self {
+            book: LocalOrderBook::new(),
+            depth,
+            capacity: capacity.max(1),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Applies the update to the book and, if it applied and neither side is empty,
+    /// records the new imbalance.
+    pub fn apply(&mut self, update: &OrderBookUpdate) -> ApplyResult {
+        let result = self.book.apply(update);
+        if result == ApplyResult::Applied
+            && let Some(imbalance) = self.book.imbalance(self.depth)
+        {
+            let timestamp = self.book.timestamp();
+            match self.samples.back_mut() {
+                Some(last) if last.0 >= timestamp => last.1 = imbalance,
+                _ => {
+                    if self.samples.len() == self.capacity {
+                        self.samples.pop_front();
+                    }
+                    self.samples.push_back((timestamp, imbalance));
+                }
+            }
+        }
+        result
+    }
+
+    pub fn book(&self) -> &LocalOrderBook {
+        &self.book
+    }
+
+    /// The latest sample.
+    pub fn last(&self) -> Option<f64> {
+        self.samples.back().map(|(_, imbalance)| *imbalance)
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn to_series(&self) -> TimeSeries {
+        TimeSeries::from_points(self.samples.iter().copied())
+    }
+
+    /// One flat candle per sample (open = high = low = close = imbalance, no volume),
+    /// for the candle-based indicator functions.
+    pub fn to_candles(&self) -> Vec<Candle> {
+        self.samples
+            .iter()
+            .map(|&(timestamp, imbalance)| Candle::new(timestamp, imbalance, imbalance, imbalance, imbalance, 0.0))
+            .collect()
+    }
+}
+
+/// `value` at `precision` decimals without the point or leading zeros.
+fn kraken_digits(value: f64, precision: usize) -> String {
+    let digits: String = format!("{:.*}", precision, value).chars().filter(|c| *c != '.').collect();
+    match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Quantity per bucket index, `round` (floor or ceil) picking a price's bucket.
+fn bucket_quantities(side: &BTreeMap<OrderedPrice, f64>, bucket_size: f64, round: fn(f64) -> f64) -> BTreeMap<i64, f64> {
+    let mut buckets = BTreeMap::new();
+    for (price, quantity) in side {
+        *buckets.entry(bucket_index(price.0, bucket_size, round)).or_insert(0.0) += quantity;
+    }
+    buckets
+}
+
+/// `round(price / bucket_size)`, treating prices within float error of a bucket
+/// boundary as on it (100.3 / 0.1 is 1002.9999999999999).
+fn bucket_index(price: f64, bucket_size: f64, round: fn(f64) -> f64) -> i64 {
+    let buckets = price / bucket_size;
+    let nearest = buckets.round();
+    if (buckets - nearest).abs() < 1e-9 { nearest as i64 } else { round(buckets) as i64 }
+}
+
+fn apply_levels(side: &mut BTreeMap<OrderedPrice, f64>, levels: &[PriceLevel]) {
+    for level in levels {
+        if level.quantity == 0.0 {
+            side.remove(&OrderedPrice(level.price));
+        } else {
+            side.insert(OrderedPrice(level.price), level.quantity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[(f64, f64)]) -> Vec<PriceLevel> {
+        levels.iter().map(|&(price, quantity)| PriceLevel::new(price, quantity)).collect()
+    }
+
+    fn prices(levels: &[PriceLevel]) -> Vec<(f64, f64)> {
+        levels.iter().map(|level| (level.price, level.quantity)).collect()
+    }
+
+    fn snapshot(sequence: u64) -> OrderBookUpdate {
+        OrderBookUpdate::snapshot(
+            1_000,
+            "BTCUSDT",
+            levels(&[(99.0, 1.0), (100.0, 2.0), (98.0, 3.0)]),
+            levels(&[(101.0, 1.5), (102.0, 2.5), (103.0, 4.0)]),
+        )
+        .with_sequence(sequence)
+    }
+
+    #[test]
+    fn test_snapshot_then_deltas() {
+        let mut book = LocalOrderBook::new();
+        assert_eq!(book.apply(&snapshot(10)), ApplyResult::Applied);
+        assert_eq!(book.best_bid().map(|level| level.price), Some(100.0));
+        assert_eq!(book.best_ask().map(|level| level.price), Some(101.0));
+        assert_eq!(book.mid_price(), Some(100.5));
+        assert_eq!(book.spread(), Some(1.0));
+
+        // Remove the best bid, change a quantity, add a level inside the spread
+        let delta = OrderBookUpdate::delta(
+            2_000,
+            "BTCUSDT",
+            levels(&[(100.0, 0.0), (98.0, 5.0)]),
+            levels(&[(100.5, 0.7), (103.0, 0.0)]),
+        )
+        .with_sequence(11);
+        assert_eq!(book.apply(&delta), ApplyResult::Applied);
+
+        let (bids, asks) = book.depth(5);
+        assert_eq!(prices(&bids), vec![(99.0, 1.0), (98.0, 5.0)]);
+        assert_eq!(prices(&asks), vec![(100.5, 0.7), (101.0, 1.5), (102.0, 2.5)]);
+        assert_eq!(prices(&book.depth(1).1), vec![(100.5, 0.7)]);
+        assert_eq!(book.last_update_id(), Some(11));
+        assert_eq!(book.timestamp(), 2_000);
+        assert_eq!(book.len(), (2, 3));
+
+        // Removing a level that isn't there is a no-op
+        let unsequenced = OrderBookUpdate::delta(3_000, "BTCUSDT", levels(&[(50.0, 0.0)]), vec![]);
+        assert_eq!(book.apply(&unsequenced), ApplyResult::Applied);
+        assert_eq!(book.len(), (2, 3));
+    }
+
+    #[test]
+    fn test_stale_deltas_are_rejected_and_snapshots_replace() {
+        let mut book = LocalOrderBook::new();
+        book.apply(&snapshot(10));
+        let old = OrderBookUpdate::delta(0, "BTCUSDT", levels(&[(100.0, 0.0)]), vec![]).with_sequence(10);
+        assert_eq!(book.apply(&old), ApplyResult::Stale);
+        assert_eq!(book.best_bid().map(|level| level.price), Some(100.0));
+
+        let replacement = OrderBookUpdate::snapshot(0, "BTCUSDT", levels(&[(90.0, 1.0)]), levels(&[(91.0, 1.0)]))
+            .with_sequence(5);
+        assert_eq!(book.apply(&replacement), ApplyResult::Applied);
+        assert_eq!(book.len(), (1, 1));
+        assert_eq!(book.last_update_id(), Some(5));
+
+        book.clear();
+        assert!(book.is_empty());
+        assert_eq!(book.mid_price(), None);
+        assert_eq!(book.last_update_id(), None);
+    }
+
+    #[test]
+    fn test_sequence_ranges_detect_gaps() {
+        let range = |first, last| {
+            OrderBookUpdate::delta(0, "BTCUSDT", levels(&[(97.0, 1.0)]), vec![]).with_sequence_range(first, last)
+        };
+        let mut book = LocalOrderBook::new();
+        book.apply(&snapshot(10));
+
+        assert_eq!(book.apply(&range(5, 10)), ApplyResult::Stale);
+        // Straddling the snapshot id is fine, then each range must follow the last
+        assert_eq!(book.apply(&range(8, 12)), ApplyResult::Applied);
+        assert_eq!(book.apply(&range(13, 13)), ApplyResult::Applied);
+        assert_eq!(book.apply(&range(16, 20)), ApplyResult::GapDetected { expected: 14, got: 16 });
+        assert!(!book.is_valid());
+        assert_eq!(book.last_update_id(), Some(13));
+
+        // Invalid until a snapshot, even for the missing range
+        assert_eq!(book.apply(&range(14, 15)), ApplyResult::GapDetected { expected: 14, got: 14 });
+        assert_eq!(book.apply(&snapshot(30)), ApplyResult::Applied);
+        assert!(book.is_valid());
+        assert_eq!(book.apply(&range(31, 32)), ApplyResult::Applied);
+    }
+
+    fn small_book() -> LocalOrderBook {
+        let mut book = LocalOrderBook::new();
+        book.apply(&OrderBookUpdate::snapshot(
+            1_000,
+            "BTCUSDT",
+            levels(&[(100.0, 2.0), (99.0, 3.0)]),
+            levels(&[(101.0, 1.0), (102.0, 4.0)]),
+        ));
+        book
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("a value");
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_imbalance_and_microprice() {
+        let book = small_book();
+        // Top level: (2 - 1) / 3
+        assert_close(book.imbalance(1), 1.0 / 3.0);
+        // Two levels: 5 vs 5
+        assert_close(book.imbalance(2), 0.0);
+        // (100 * 1 + 101 * 2) / 3: pulled toward the ask, the thinner side
+        assert_close(book.microprice(), 302.0 / 3.0);
+        // Bid VWAP (200 + 297) / 5 = 99.4, ask VWAP (101 + 408) / 5 = 101.8, equal sizes
+        assert_close(book.weighted_mid(2), 100.6);
+        assert_eq!(book.weighted_mid(1), book.microprice());
+
+        // Mid 100.5; 1% reaches 99.495..101.505: 2 bid vs 1 ask
+        assert_close(book.book_pressure(1.0), 1.0 / 3.0);
+        assert_eq!(book.book_pressure(0.1), None);
+    }
+
+    #[test]
+    fn test_metrics_need_both_sides() {
+        let mut book = LocalOrderBook::new();
+        assert_eq!(book.imbalance(5), None);
+        book.apply(&OrderBookUpdate::snapshot(0, "BTCUSDT", levels(&[(100.0, 1.0)]), vec![]));
+        assert_eq!(book.imbalance(5), None);
+        assert_eq!(book.microprice(), None);
+        assert_eq!(book.weighted_mid(5), None);
+        assert_eq!(book.book_pressure(5.0), None);
+    }
+
+    #[test]
+    fn test_imbalance_series() {
+        let mut series = ImbalanceSeries::new(1, 2);
+        let update = |timestamp, bid_qty| {
+            OrderBookUpdate::delta(timestamp, "BTCUSDT", levels(&[(100.0, bid_qty)]), vec![])
+        };
+        series.apply(&OrderBookUpdate::snapshot(
+            1_000,
+            "BTCUSDT",
+            levels(&[(100.0, 1.0)]),
+            levels(&[(101.0, 1.0)]),
+        ));
+        assert_close(series.last(), 0.0);
+        // Same ms: replaces the sample
+        series.apply(&update(1_000, 3.0));
+        assert_eq!(series.len(), 1);
+        assert_close(series.last(), 0.5);
+
+        series.apply(&update(2_000, 1.0));
+        series.apply(&update(3_000, 3.0));
+        assert_eq!(series.to_series().points(), &[(2_000, 0.0), (3_000, 0.5)]);
+        let candles = series.to_candles();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(crate::indicators::moving_averages::sma(&candles, 2), Some(0.25));
+        assert_eq!(series.book().best_bid().map(|level| level.quantity), Some(3.0));
+    }
+
+    #[test]
+    fn test_cost_to_buy_and_sell() {
+        let book = small_book();
+        // 1 @ 101 then 1.5 of the 4 @ 102
+        let buy = book.cost_to_buy(2.5);
+        assert!(buy.complete);
+        assert_eq!(buy.filled_quantity, 2.5);
+        assert_eq!(buy.cost, 101.0 + 153.0);
+        assert_close(buy.average_price, 101.6);
+        // Mid 100.5
+        assert_close(buy.slippage_pct, (101.6 - 100.5) / 100.5 * 100.0);
+
+        // 2 @ 100 then 1 @ 99
+        let sell = book.cost_to_sell(3.0);
+        assert_eq!(sell.side, TradeSide::Sell);
+        assert_eq!(sell.cost, 299.0);
+        assert_close(sell.slippage_pct, (100.5 - 299.0 / 3.0) / 100.5 * 100.0);
+
+        // Inside the best level: no walk, slippage is half the spread
+        assert_close(book.cost_to_buy(0.5).average_price, 101.0);
+    }
+
+    #[test]
+    fn test_shallow_book_reports_partial_fill() {
+        let book = small_book();
+        let buy = book.cost_to_buy(10.0);
+        assert!(!buy.complete);
+        assert_eq!(buy.filled_quantity, 5.0);
+        assert_eq!(buy.cost, 101.0 + 408.0);
+        assert_close(buy.average_price, 101.8);
+
+        let nothing = LocalOrderBook::new().cost_to_sell(1.0);
+        assert!(!nothing.complete);
+        assert_eq!((nothing.filled_quantity, nothing.cost), (0.0, 0.0));
+        assert_eq!(nothing.average_price, None);
+        assert_eq!(nothing.slippage_pct, None);
+
+        let zero = book.cost_to_buy(0.0);
+        assert!(zero.complete);
+        assert_eq!(zero.average_price, None);
+    }
+
+    #[test]
+    fn test_quantity_for_notional() {
+        let book = small_book();
+        // 101 buys the first level; the other 153 buys 1.5 @ 102
+        let buy = book.quantity_for_notional(254.0);
+        assert!(buy.complete);
+        assert_eq!(buy.cost, 254.0);
+        assert_close(Some(buy.filled_quantity), 2.5);
+        assert_eq!(book.cost_to_buy(buy.filled_quantity).cost, 254.0);
+
+        let too_much = book.quantity_for_notional(1_000.0);
+        assert!(!too_much.complete);
+        assert_eq!((too_much.filled_quantity, too_much.cost), (5.0, 509.0));
+    }
+
+    #[test]
+    fn test_fill_properties_on_generated_books() {
+        // xorshift: deterministic pseudo-random books
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 10_000) as f64 / 100.0
+        };
+
+        for _ in 0..50 {
+            let bids: Vec<(f64, f64)> = (0..20).map(|i| (1_000.0 - i as f64 - next() / 100.0, next() + 0.01)).collect();
+            let asks: Vec<(f64, f64)> = (0..20).map(|i| (1_001.0 + i as f64 + next() / 100.0, next() + 0.01)).collect();
+            let mut book = LocalOrderBook::new();
+            book.apply(&OrderBookUpdate::snapshot(0, "BTCUSDT", levels(&bids), levels(&asks)));
+            let best_ask = book.best_ask().unwrap().price;
+            let best_bid = book.best_bid().unwrap().price;
+
+            let mut previous_buy = 0.0;
+            let mut previous_sell = 0.0;
+            for step in 1..=200 {
+                let quantity = step as f64 * 0.5;
+                let buy = book.cost_to_buy(quantity);
+                let sell = book.cost_to_sell(quantity);
+                assert!(buy.cost >= previous_buy, "buy cost decreased at {}", quantity);
+                assert!(sell.cost >= previous_sell, "sell proceeds decreased at {}", quantity);
+                if buy.complete {
+                    assert!(buy.cost > previous_buy);
+                }
+                assert!(buy.average_price.unwrap() >= best_ask);
+                assert!(sell.average_price.unwrap() <= best_bid);
+                assert!(buy.slippage_pct.unwrap() > 0.0 && sell.slippage_pct.unwrap() > 0.0);
+                previous_buy = buy.cost;
+                previous_sell = sell.cost;
+            }
+        }
+    }
+
+    // The example book from Kraken's WebSocket book checksum guide: ten levels a
+    // side, 0.00000500 each. Its published checksum is 974947235.
+    const KRAKEN_EXAMPLE_ASKS: [f64; 10] =
+        [0.05005, 0.0501, 0.05015, 0.0502, 0.05025, 0.0503, 0.05035, 0.0504, 0.05045, 0.0505];
+    const KRAKEN_EXAMPLE_BIDS: [f64; 10] =
+        [0.05, 0.04995, 0.0499, 0.0498, 0.04975, 0.0497, 0.04965, 0.0496, 0.04955, 0.0495];
+    const KRAKEN_EXAMPLE_CHECKSUM: u32 = 974_947_235;
+
+    fn kraken_example_levels(prices: &[f64]) -> Vec<PriceLevel> {
+        prices.iter().map(|&price| PriceLevel::new(price, 0.000005)).collect()
+    }
+
+    #[test]
+    fn test_kraken_checksum() {
+        let format = ChecksumFormat::Kraken { price_precision: 5, qty_precision: 8 };
+        let mut book = LocalOrderBook::new();
+        book.apply(&OrderBookUpdate::snapshot(
+            0,
+            "XBT/USD",
+            kraken_example_levels(&KRAKEN_EXAMPLE_BIDS),
+            kraken_example_levels(&KRAKEN_EXAMPLE_ASKS),
+        ));
+        // "0.05005" "0.00000500" -> "5005" "500", asks first
+        assert_eq!(
+            book.checksum_string(10, format),
+            "50055005010500501550050205005025500503050050355005040500504550050505005000500499550049905004980500\
+             497550049705004965500496050049555004950500"
+        );
+        assert_eq!(book.checksum(10, format), KRAKEN_EXAMPLE_CHECKSUM);
+        assert_eq!(book.verify_checksum(KRAKEN_EXAMPLE_CHECKSUM, format), Ok(()));
+
+        // Precision keeps trailing zeros: 0.001 at 8 decimals is "100000", not "1"
+        let format = ChecksumFormat::Kraken { price_precision: 1, qty_precision: 8 };
+        let mut book = LocalOrderBook::new();
+        book.apply(&OrderBookUpdate::snapshot(
+            0,
+            "XBT/USD",
+            levels(&[(45283.5, 0.1), (45280.0, 2.0)]),
+            levels(&[(45285.2, 0.001), (45286.4, 1.5), (45290.0, 0.25)]),
+        ));
+        assert_eq!(
+            book.checksum_string(10, format),
+            "4528521000004528641500000004529002500000045283510000000452800200000000"
+        );
+    }
+
+    #[test]
+    fn test_okx_checksum() {
+        // The example from OKX's order book checksum guide
+        let mut book = LocalOrderBook::new();
+        book.apply(&OrderBookUpdate::snapshot(
+            0,
+            "BTC-USDT",
+            levels(&[(3366.1, 7.0), (3366.0, 6.0)]),
+            levels(&[(3366.8, 9.0), (3368.0, 8.0)]),
+        ));
+        assert_eq!(book.checksum_string(25, ChecksumFormat::Okx), "3366.1:7:3366.8:9:3366:6:3368:8");
+        // OKX sends it signed: -1881014294
+        assert_eq!(book.checksum(25, ChecksumFormat::Okx), -1_881_014_294_i32 as u32);
+
+        // The longer side carries on alone
+        book.apply(&OrderBookUpdate::delta(0, "BTC-USDT", vec![], levels(&[(3369.0, 1.5)])));
+        assert_eq!(
+            book.checksum_string(25, ChecksumFormat::Okx),
+            "3366.1:7:3366.8:9:3366:6:3368:8:3369:1.5"
+        );
+
+        let err = book.verify_checksum(1, ChecksumFormat::Okx).unwrap_err();
+        assert_eq!(err.expected, 1);
+        assert_eq!(err.computed, 2_502_219_684);
+        assert!(err.to_string().contains("local book gives 2502219684"));
+    }
+
+    #[test]
+    fn test_checksum_depth_is_capped() {
+        // Levels past the top 10 don't change Kraken's checksum
+        let mut book = LocalOrderBook::new();
+        book.apply(&OrderBookUpdate::snapshot(
+            0,
+            "XBT/USD",
+            kraken_example_levels(&KRAKEN_EXAMPLE_BIDS),
+            kraken_example_levels(&KRAKEN_EXAMPLE_ASKS),
+        ));
+        book.apply(&OrderBookUpdate::delta(0, "XBT/USD", levels(&[(0.0494, 3.0)]), levels(&[(0.0506, 3.0)])));
+        let format = ChecksumFormat::Kraken { price_precision: 5, qty_precision: 8 };
+        assert_eq!(book.checksum(format.depth(), format), KRAKEN_EXAMPLE_CHECKSUM);
+        assert_ne!(book.checksum(11, format), KRAKEN_EXAMPLE_CHECKSUM);
+    }
+
+    #[test]
+    fn test_total_quantity_within() {
+        let mut book = LocalOrderBook::new();
+        book.apply(&snapshot(1));
+        // Mid 100.5: 1% is 1.005, so bids from 99.495 and asks up to 101.505
+        assert_eq!(book.total_quantity_within(1.0), (2.0, 1.5));
+        // 2.5% reaches 97.99 / 103.01: every level
+        assert_eq!(book.total_quantity_within(2.5), (6.0, 8.0));
+        assert_eq!(LocalOrderBook::new().total_quantity_within(1.0), (0.0, 0.0));
+    }
+
+    fn btc_book() -> LocalOrderBook {
+        let mut book = LocalOrderBook::new();
+        book.apply(&OrderBookUpdate::snapshot(
+            0,
+            "BTCUSDT",
+            levels(&[(30_005.0, 1.0), (30_001.5, 2.0), (29_999.0, 0.5), (29_990.0, 3.0)]),
+            levels(&[(30_006.0, 1.5), (30_010.0, 1.0), (30_010.5, 2.0), (30_031.0, 4.0)]),
+        ));
+        book
+    }
+
+    #[test]
+    fn test_aggregate_into_price_buckets() {
+        let (bids, asks) = btc_book().aggregate(10.0);
+        // Bids floor: 30005 and 30001.5 -> 30000; 29999 and 29990 -> 29990
+        assert_eq!(bids, vec![(30_000.0, 3.0), (29_990.0, 3.5)]);
+        // Asks ceil: 30006 and 30010 -> 30010; 30010.5 -> 30020; 30031 -> 30040
+        assert_eq!(asks, vec![(30_010.0, 2.5), (30_020.0, 2.0), (30_040.0, 4.0)]);
+
+        // Boundaries within float error of a bucket stay in it
+        let mut book = LocalOrderBook::new();
+        book.apply(&OrderBookUpdate::snapshot(0, "X", levels(&[(100.3, 1.0), (100.25, 1.0)]), levels(&[(100.4, 1.0)])));
+        let (bids, asks) = book.aggregate(0.1);
+        assert_eq!(bids.len(), 2);
+        assert_close(Some(bids[0].0), 100.3);
+        assert_close(Some(bids[1].0), 100.2);
+        assert_close(Some(asks[0].0), 100.4);
+    }
+
+    #[test]
+    fn test_aggregate_edge_cases() {
+        // Smaller than the 0.5 gap between 30010 and 30010.5: the raw levels
+        let (bids, asks) = btc_book().aggregate(0.1);
+        assert_eq!(bids, vec![(30_005.0, 1.0), (30_001.5, 2.0), (29_999.0, 0.5), (29_990.0, 3.0)]);
+        assert_eq!(asks.len(), 4);
+        assert_eq!(btc_book().aggregate(0.0).0.len(), 4);
+
+        let mut one_sided = LocalOrderBook::new();
+        one_sided.apply(&OrderBookUpdate::snapshot(0, "X", levels(&[(15.0, 1.0), (12.0, 2.0)]), vec![]));
+        assert_eq!(one_sided.aggregate(10.0), (vec![(10.0, 3.0)], vec![]));
+        assert_eq!(one_sided.depth_profile(3, 10.0), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_depth_profile_is_centered_on_mid() {
+        // Mid 30005.5: bid bands 30000, 29990, 29980; ask bands 30010, 30020, 30030
+        let (bids, asks) = btc_book().depth_profile(3, 10.0);
+        assert_eq!(bids, vec![(30_000.0, 3.0), (29_990.0, 3.5), (29_980.0, 0.0)]);
+        assert_eq!(asks, vec![(30_010.0, 2.5), (30_020.0, 2.0), (30_030.0, 0.0)]);
+    }
+}
@@ -0,0 +1,157 @@
+//! `PartialBook`: the top N levels of a book, for partial depth streams
+//! (Binance `@depth20`, Bitget `books15`, ...).
+//!
+//! Each message of those streams is already a complete top-N snapshot, so there is
+//! nothing to merge: the book is two sorted `Vec`s overwritten in place. After the
+//! first update their capacity covers N levels and later ones don't allocate.
+
+use crate::market::market_data::{OrderBookUpdate, PriceLevel};
+use crate::market::order_book::BookView;
+
+/// A fixed-depth book replaced wholesale by every update.
+#[derive(Debug, Clone, Default)]
+pub struct PartialBook {
+    bids: Vec<PriceLevel>,
+    asks: Vec<PriceLevel>,
+    timestamp: u64,
+    sequence: Option<u64>,
+}
+
+impl PartialBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Preallocates `depth` levels per side.
+    pub fn with_depth(depth: usize) -> Self {
+        Self { bids: Vec::with_capacity(depth), asks: Vec::with_capacity(depth), ..Self::default() }
+    }
+
+    /// Replaces both sides with the update's levels, reusing the buffers. Levels sent
+    /// out of order are sorted: bids descending, asks ascending. Snapshot and delta
+    /// flags are not looked at, since partial streams send the whole top every time.
+    pub fn replace(&mut self, update: &OrderBookUpdate) {
+        self.replace_levels(&update.bids, &update.asks);
+        self.timestamp = update.timestamp;
+        self.sequence = update.sequence;
+    }
+
+    /// `replace` from bare levels; the timestamp and sequence are left as they were.
+    pub fn replace_levels(&mut self, bids: &[PriceLevel], asks: &[PriceLevel]) {
+        self.bids.clear();
+        self.bids.extend_from_slice(bids);
+        self.asks.clear();
+        self.asks.extend_from_slice(asks);
+
+        if !self.bids.is_sorted_by(|a, b| a.price >= b.price) {
+            self.bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+        }
+        if !self.asks.is_sorted_by(|a, b| a.price <= b.price) {
+            self.asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+        }
+    }
+
+    /// Bids, best (highest) first.
+    pub fn bids(&self) -> &[PriceLevel] {
+        &self.bids
+    }
+
+    /// Asks, best (lowest) first.
+    pub fn asks(&self) -> &[PriceLevel] {
+        &self.asks
+    }
+
+    /// Exchange timestamp of the last update, 0 before the first.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Sequence of the last update, if it had one.
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// Levels held on each side: (bids, asks).
+    pub fn len(&self) -> (usize, usize) {
+        (self.bids.len(), self.asks.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+}
+
+impl BookView for PartialBook {
+    fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.first().cloned()
+    }
+
+    fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.first().cloned()
+    }
+
+    fn depth(&self, n: usize) -> (Vec<PriceLevel>, Vec<PriceLevel>) {
+        (self.bids.iter().take(n).cloned().collect(), self.asks.iter().take(n).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::order_book::LocalOrderBook;
+
+    fn levels(levels: &[(f64, f64)]) -> Vec<PriceLevel> {
+        levels.iter().map(|&(price, quantity)| PriceLevel::new(price, quantity)).collect()
+    }
+
+    fn prices(levels: &[PriceLevel]) -> Vec<f64> {
+        levels.iter().map(|level| level.price).collect()
+    }
+
+    fn update(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> OrderBookUpdate {
+        OrderBookUpdate::snapshot(1000, "BTCUSDT", levels(bids), levels(asks)).with_sequence(7)
+    }
+
+    #[test]
+    fn test_replace_overwrites_without_reallocating() {
+        let mut book = PartialBook::with_depth(3);
+        book.replace(&update(&[(100.0, 1.0), (99.0, 2.0), (98.0, 1.0)], &[(101.0, 1.0), (102.0, 2.0)]));
+        let buffers = (book.bids().as_ptr(), book.asks().as_ptr());
+
+        book.replace(&update(&[(100.5, 1.0)], &[(101.5, 3.0), (103.0, 1.0), (104.0, 1.0)]));
+        assert_eq!(prices(book.bids()), vec![100.5]);
+        assert_eq!(prices(book.asks()), vec![101.5, 103.0, 104.0]);
+        assert_eq!((book.bids().as_ptr(), book.asks().as_ptr()), buffers);
+        assert_eq!(book.len(), (1, 3));
+        assert_eq!(book.timestamp(), 1000);
+        assert_eq!(book.sequence(), Some(7));
+    }
+
+    #[test]
+    fn test_unsorted_levels_are_sorted() {
+        let mut book = PartialBook::new();
+        book.replace(&update(&[(98.0, 1.0), (100.0, 1.0), (99.0, 1.0)], &[(103.0, 1.0), (101.0, 1.0)]));
+        assert_eq!(prices(book.bids()), vec![100.0, 99.0, 98.0]);
+        assert_eq!(prices(book.asks()), vec![101.0, 103.0]);
+        assert_eq!(book.best_bid().unwrap().price, 100.0);
+        assert_eq!(book.best_ask().unwrap().price, 101.0);
+    }
+
+    #[test]
+    fn test_reads_match_local_order_book() {
+        fn summary(book: &impl BookView) -> (Option<f64>, Option<f64>, usize, usize) {
+            let (bids, asks) = book.depth(2);
+            (book.mid_price(), book.spread(), bids.len(), asks.len())
+        }
+
+        let snapshot = update(&[(100.0, 2.0), (99.0, 3.0), (98.0, 1.0)], &[(101.0, 1.0), (102.0, 4.0)]);
+        let mut partial = PartialBook::new();
+        partial.replace(&snapshot);
+        let mut local = LocalOrderBook::new();
+        local.apply(&snapshot);
+        assert_eq!(summary(&partial), summary(&local));
+        assert_eq!(summary(&partial), (Some(100.5), Some(1.0), 2, 2));
+
+        assert_eq!(summary(&PartialBook::new()), (None, None, 0, 0));
+    }
+}
@@ -3,9 +3,15 @@
 
 use crate::indicators::candle::Candle;
 use crate::indicators::timeframe::Timeframe;
-use crate::market::market_data::{MarketData, Trade, TradeSide};
+use std::borrow::Cow;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::market::error::CctError;
+use crate::market::market_data::{BookTicker, MarketData, Ticker, Trade, TradeSide};
 use crate::market::message_parser::MessageParser;
 use crate::market::streams::Stream;
+use crate::market::symbol::{Symbol, SymbolCache};
+use crate::market::providers::common::{ExactF64, de_f64, parse_f64, parse_u64};
 use crate::market::websocket_client::WebSocketClient;
 use serde::Deserialize;
 
@@ -13,6 +19,14 @@ pub const BINANCE_WSS_BASE_ENDPOINT: &str = "wss://stream.binance.com:443/ws";
 pub const BINANCE_WSS_FALLBACK_ENDPOINT: &str = "wss://stream.binance.com:9443/ws";
 pub const BINANCE_API_BASE_ENDPOINT: &str = "wss://ws-api.binance.com:443/ws-api/v3";
 pub const BINANCE_API_FALLBACK_ENDPOINT: &str = "wss://ws-api.binance.com:9443/ws-api/v3";
+pub const BINANCE_TESTNET_WSS_ENDPOINT: &str = "wss://stream.testnet.binance.vision/ws";
+pub const BINANCE_REST_ENDPOINT: &str = "https://api.binance.com";
+pub const BINANCE_TESTNET_REST_ENDPOINT: &str = "https://testnet.binance.vision";
+
+// Most klines GET /api/v3/klines returns per request
+const KLINES_LIMIT: usize = 1000;
+
+pub(crate) const MAX_STREAMS_PER_CONNECTION: usize = 1024;
 
 
 // This is an example of how to implement MessageParser for an exchange.
@@ -23,36 +37,148 @@ pub const BINANCE_API_FALLBACK_ENDPOINT: &str = "wss://ws-api.binance.com:9443/w
 
 /// Binance-specific message parser.
 /// Implements MessageParser to convert Binance JSON -> normalized MarketData.
+/// Endpoints are stored so the same parser works against production, the spot
+/// testnet, or a local mock server.
 #[derive(Debug, Clone)]
-pub struct BinanceParser;
+pub struct BinanceParser {
+    endpoint: String,
+    fallback_endpoint: Option<String>,
+    // REST base for kline backfill; None = no backfill (fetch_candles returns nothing)
+    rest_endpoint: Option<String>,
+    symbols: SymbolCache,
+}
 
 impl BinanceParser {
+    /// Production endpoints (port 443, falling back to 9443).
     pub fn new() -> Self {
-        Self
+        Self {
+            endpoint: BINANCE_WSS_BASE_ENDPOINT.to_string(),
+            fallback_endpoint: Some(BINANCE_WSS_FALLBACK_ENDPOINT.to_string()),
+            rest_endpoint: Some(BINANCE_REST_ENDPOINT.to_string()),
+            symbols: SymbolCache::default(),
+        }
+    }
+
+    /// Spot testnet (stream.testnet.binance.vision). No fallback endpoint.
+    pub fn testnet() -> Self {
+        Self::with_endpoint(BINANCE_TESTNET_WSS_ENDPOINT).with_rest_endpoint(BINANCE_TESTNET_REST_ENDPOINT)
+    }
+
+    /// Custom endpoint, e.g. a proxy or a local mock server. No fallback endpoint,
+    /// and no REST backfill unless `with_rest_endpoint` is set too.
+    pub fn with_endpoint(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            fallback_endpoint: None,
+            rest_endpoint: None,
+            symbols: SymbolCache::default(),
+        }
+    }
+
+    /// REST base URL (`https://api.binance.com`) that `fetch_candles` reads klines from.
+    pub fn with_rest_endpoint(mut self, rest_endpoint: impl Into<String>) -> Self {
+        self.rest_endpoint = Some(rest_endpoint.into());
+        self
+    }
+
+    /// Interns symbols in `symbols` instead of this parser's own cache, e.g. to
+    /// share one cache between parsers. `SymbolCache::new(0)` turns interning off.
+    pub fn with_symbol_cache(mut self, symbols: SymbolCache) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// The shared allocation for `name`, see `with_symbol_cache`.
+    pub(crate) fn intern(&self, name: &str) -> Symbol {
+        self.symbols.intern(name)
+    }
+
+    /// Switches to the combined-stream endpoint (`/stream?streams=a/b/c`), subscribed
+    /// to `streams` as soon as the connection opens. Frames then arrive wrapped as
+    /// `{"stream":...,"data":...}`, which the parser unwraps. Further streams can
+    /// still be added with SUBSCRIBE on the same connection.
+    ///
+    /// Fails for an empty list, more than the 1024 streams Binance allows per
+    /// connection, or a stream Binance doesn't offer (such as a custom kline interval).
+    pub fn with_combined_streams(mut self, streams: &[Stream]) -> Result<Self, CctError> {
+        if streams.is_empty() || streams.len() > MAX_STREAMS_PER_CONNECTION {
+            return Err(CctError::InvalidConfig(format!(
+                "combined stream URL needs 1 to {} streams, got {}",
+                MAX_STREAMS_PER_CONNECTION,
+                streams.len()
+            )));
+        }
+        let Some(names) = self.stream_names(streams) else {
+            return Err(CctError::InvalidConfig(format!(
+                "Binance has no stream for {:?}",
+                streams.iter().find(|stream| self.stream_name(stream).is_none())
+            )));
+        };
+        self.endpoint = combined_url(&self.endpoint, &names);
+        self.fallback_endpoint = self
+            .fallback_endpoint
+            .map(|fallback| combined_url(&fallback, &names));
+        Ok(self)
+    }
+
+    /// Parses one event; `parse_message` adds the exchange tag.
+    fn parse_event(&self, msg: &str) -> Option<MarketData> {
+        // Combined-stream frames wrap the event: {"stream":"...","data":{...}}
+        if let Some(data) = unwrap_combined(msg) {
+            return self.parse_event(&data);
+        }
+
+        // Detect message type by "e" field
+        if msg.contains(r#""e":"kline""#) {
+            return self.parse_kline(msg);
+        }
+
+        if msg.contains(r#""e":"trade""#) {
+            return self.parse_trade(msg);
+        }
+
+        if msg.contains(r#""e":"aggTrade""#) {
+            return self.parse_agg_trade(msg);
+        }
+
+        if msg.contains(r#""e":"24hrTicker""#) {
+            return self.parse_ticker(msg);
+        }
+
+        if msg.contains(r#""e":"24hrMiniTicker""#) {
+            let event: BinanceMiniTickerEvent = serde_json::from_str(msg).ok()?;
+            return Some(self.mini_ticker(event));
+        }
+
+        if is_book_ticker(msg) {
+            return self.parse_book_ticker(msg);
+        }
+
+        // TODO: Add more message types
+        // - Order book: "e":"depthUpdate"
+        // - Mark price/funding: "e":"markPriceUpdate"
+
+        None // Unknown or control message
     }
 
     /// Parses a Binance kline message into MarketData::Candle.
     /// Normalization: Wraps the simple Candle with symbol/interval/is_closed context.
     fn parse_kline(&self, msg: &str) -> Option<MarketData> {
         let event: BinanceKlineEvent = serde_json::from_str(msg).ok()?;
-        let interval = Timeframe::from_str(event.k.i.as_str())?;
+        let interval = self.interval_from_exchange(&event.k.i)?;
 
         // Create simple Candle (calculation primitive) and wrap with streaming context
         let candle = Candle::new(
             event.k.t,
-            event.k.o,
-            event.k.h,
-            event.k.l,
-            event.k.c,
-            event.k.v,
+            event.k.o.value,
+            event.k.h.value,
+            event.k.l.value,
+            event.k.c.value,
+            event.k.v.value,
         );
+        let candle = exact_candle(candle, &event.k);
 
-        Some(MarketData::Candle {
-            symbol: event.s,
-            interval,
-            data: candle,
-            is_closed: event.k.x,
-        })
+        Some(MarketData::candle(self.symbols.intern(&event.s), interval, candle, event.k.x))
     }
 
     /// Parses a Binance trade message into MarketData::Trade.
@@ -74,193 +200,537 @@ impl BinanceParser {
         // Keep is_buyer_maker for Binance-specific use cases
         let trade = Trade::new(
             event.trade_time,
-            event.s,
-            event.p,
-            event.q,
+            self.symbols.intern(&event.s),
+            event.p.value,
+            event.q.value,
             event.t.to_string(),
             side,
         ).with_buyer_maker(is_buyer_maker);
+        let trade = exact_trade(trade, event.p, event.q);
 
         Some(MarketData::Trade(trade))
     }
-}
 
-impl Default for BinanceParser {
-    fn default() -> Self {
-        Self::new()
+    /// Parses a Binance aggTrade message into MarketData::Trade.
+    /// The aggregate id becomes trade_id; the underlying trade ids are kept as a range.
+    fn parse_agg_trade(&self, msg: &str) -> Option<MarketData> {
+        let event: BinanceAggTradeEvent = serde_json::from_str(msg).ok()?;
+        // Same maker/taker convention as raw trades
+        let side = if event.m { TradeSide::Sell } else { TradeSide::Buy };
+
+        let trade = Trade::new(
+            event.trade_time,
+            self.symbols.intern(&event.s),
+            event.p.value,
+            event.q.value,
+            event.a.to_string(),
+            side,
+        )
+        .with_buyer_maker(event.m)
+        .with_trade_id_range(event.f, event.l);
+        let trade = exact_trade(trade, event.p, event.q);
+
+        Some(MarketData::Trade(trade))
     }
-}
 
-impl MessageParser for BinanceParser {
-    fn endpoint(&self) -> &str {
-        BINANCE_WSS_BASE_ENDPOINT
+    /// Parses a 24hrTicker message into MarketData::Ticker.
+    fn parse_ticker(&self, msg: &str) -> Option<MarketData> {
+        let event: BinanceTickerEvent = serde_json::from_str(msg).ok()?;
+
+        let ticker = Ticker::new(event.event_time, self.symbols.intern(&event.s), event.last_price, event.volume)
+            .with_range(event.open, event.high, event.low)
+            .with_quote_volume(event.quote_volume)
+            .with_price_change_percent(event.price_change_percent)
+            .with_weighted_avg_price(event.weighted_avg_price);
+
+        Some(MarketData::Ticker(ticker))
     }
 
-    fn fallback_endpoint(&self) -> Option<&str> {
-        Some(BINANCE_WSS_FALLBACK_ENDPOINT)
+    /// Mini tickers carry no change percent or weighted average price.
+    fn mini_ticker(&self, event: BinanceMiniTickerEvent) -> MarketData {
+        let ticker = Ticker::new(event.event_time, self.symbols.intern(&event.s), event.last_price, event.volume)
+            .with_range(event.open, event.high, event.low)
+            .with_quote_volume(event.quote_volume);
+        MarketData::Ticker(ticker)
     }
 
-    fn name(&self) -> &'static str {
-        "Binance"
+    /// Parses a bookTicker message into MarketData::BookTicker.
+    /// Spot payloads carry no event time, so the receive time is used instead.
+    fn parse_book_ticker(&self, msg: &str) -> Option<MarketData> {
+        let event: BinanceBookTickerEvent = serde_json::from_str(msg).ok()?;
+        let timestamp = event.event_time.unwrap_or_else(unix_time_ms);
+
+        let ticker = BookTicker::new(
+            timestamp,
+            self.symbols.intern(&event.s),
+            event.bid_price,
+            event.bid_qty,
+            event.ask_price,
+            event.ask_qty,
+        )
+        .with_update_id(event.u);
+
+        Some(MarketData::BookTicker(ticker))
     }
 
-    fn format_subscribe(&self, stream: &Stream) -> String {
-        let stream_name = match stream {
+    /// Stream name as used in SUBSCRIBE params and combined-stream URLs. None for a
+    /// kline interval Binance doesn't offer, and for the derivatives streams (funding,
+    /// mark price, open interest, liquidations) that spot doesn't have; use
+    /// `BinanceFuturesParser` for those.
+    fn stream_name(&self, stream: &Stream) -> Option<String> {
+        let name = match stream {
             Stream::Candles { symbol, interval } => {
-                format!("{}@kline_{}", symbol.to_lowercase(), interval.as_str())
+                format!("{}@kline_{}", symbol.to_lowercase(), self.interval_to_exchange(*interval)?)
             }
             Stream::Trades { symbol } => {
                 format!("{}@trade", symbol.to_lowercase())
             }
-            Stream::Funding { symbol } => {
-                format!("{}@markPrice", symbol.to_lowercase())
-            }
-            Stream::MarkPrice { symbol } => {
-                format!("{}@markPrice", symbol.to_lowercase())
+            Stream::AggTrades { symbol } => {
+                format!("{}@aggTrade", symbol.to_lowercase())
             }
             Stream::OrderBook { symbol, depth } => {
                 format!("{}@depth{}", symbol.to_lowercase(), depth)
             }
-            Stream::OpenInterest { symbol } => {
-                format!("{}@openInterest", symbol.to_lowercase())
+            Stream::BookTicker { symbol } => {
+                format!("{}@bookTicker", symbol.to_lowercase())
             }
-            Stream::Liquidations { symbol } => {
-                format!("{}@forceOrder", symbol.to_lowercase())
+            Stream::Ticker { symbol } => {
+                format!("{}@ticker", symbol.to_lowercase())
             }
+            Stream::AllMiniTickers => "!miniTicker@arr".to_string(),
+            Stream::Funding { .. }
+            | Stream::MarkPrice { .. }
+            | Stream::OpenInterest { .. }
+            | Stream::Liquidations { .. } => return None,
         };
+        Some(name)
+    }
 
-        format!(
-            r#"{{"method":"SUBSCRIBE","params":["{}"],"id":1}}"#,
-            stream_name
-        )
+    fn stream_names(&self, streams: &[Stream]) -> Option<Vec<String>> {
+        streams.iter().map(|stream| self.stream_name(stream)).collect()
     }
+}
 
-    fn format_unsubscribe(&self, stream: &Stream) -> String {
-        let stream_name = match stream {
-            Stream::Candles { symbol, interval } => {
-                format!("{}@kline_{}", symbol.to_lowercase(), interval.as_str())
-            }
-            Stream::Trades { symbol } => {
-                format!("{}@trade", symbol.to_lowercase())
-            }
-            Stream::Funding { symbol } => {
-                format!("{}@markPrice", symbol.to_lowercase())
-            }
-            Stream::MarkPrice { symbol } => {
-                format!("{}@markPrice", symbol.to_lowercase())
-            }
-            Stream::OrderBook { symbol, depth } => {
-                format!("{}@depth{}", symbol.to_lowercase(), depth)
-            }
-            Stream::OpenInterest { symbol } => {
-                format!("{}@openInterest", symbol.to_lowercase())
-            }
-            Stream::Liquidations { symbol } => {
-                format!("{}@forceOrder", symbol.to_lowercase())
-            }
+pub(crate) fn format_request(method: &str, stream_name: &str, id: u64) -> String {
+    format_request_many(method, &[stream_name.to_string()], id)
+}
+
+/// One request for several streams; Binance takes any number of params.
+pub(crate) fn format_request_many(method: &str, stream_names: &[String], id: u64) -> String {
+    let params: Vec<String> = stream_names.iter().map(|name| format!(r#""{}""#, name)).collect();
+    format!(r#"{{"method":"{}","params":[{}],"id":{}}}"#, method, params.join(","), id)
+}
+
+/// Reads a reply to a SUBSCRIBE/UNSUBSCRIBE request: `{"result":null,"id":N}` on
+/// success; failures come as `{"error":{"code":..,"msg":..},"id":N}` or, in the
+/// older format, with `code`/`msg` at the top level.
+pub(crate) fn parse_response(msg: &str) -> Option<(u64, Result<(), String>)> {
+    // Cheap pre-check: data events carry "e", replies never do
+    if !msg.contains(r#""id":"#) || msg.contains(r#""e":"#) {
+        return None;
+    }
+    let response: BinanceResponse = serde_json::from_str(msg).ok()?;
+    let id = response.id?;
+    let result = match (response.error, response.msg) {
+        (Some(error), _) => Err(format!("{} (code {})", error.msg, error.code)),
+        (None, Some(msg)) => Err(format!("{} (code {})", msg, response.code.unwrap_or_default())),
+        (None, None) => Ok(()),
+    };
+    Some((id, result))
+}
+
+/// Turns a raw-stream URL (".../ws") into a combined-stream URL for `names`.
+fn combined_url(endpoint: &str, names: &[String]) -> String {
+    let base = endpoint.strip_suffix("/ws").unwrap_or(endpoint);
+    format!("{}/stream?streams={}", base, names.join("/"))
+}
+
+/// Returns the inner event of a combined-stream frame, or None for a raw frame.
+fn unwrap_combined(msg: &str) -> Option<String> {
+    if !msg.trim_start().starts_with(r#"{"stream":"#) {
+        return None;
+    }
+    let frame: BinanceCombinedFrame = serde_json::from_str(msg).ok()?;
+    serde_json::to_string(&frame.data).ok()
+}
+
+/// Spot bookTicker payloads have no "e" field - they are recognized by shape
+/// ("u" plus "b"/"B"/"a"/"A"). Futures send "e":"bookTicker".
+fn is_book_ticker(msg: &str) -> bool {
+    if msg.contains(r#""e":"bookTicker""#) {
+        return true;
+    }
+    !msg.contains(r#""e":"#)
+        && [r#""u":"#, r#""b":"#, r#""B":"#, r#""a":"#, r#""A":"#]
+            .iter()
+            .all(|key| msg.contains(key))
+}
+
+/// Reads a GET /api/v3/klines body: rows of [open time, "open", "high", "low",
+/// "close", "volume", close time, ...].
+fn parse_rest_klines(body: &str) -> Option<Vec<Candle>> {
+    let rows: Vec<Vec<serde_json::Value>> = serde_json::from_str(body).ok()?;
+    rows.iter()
+        .map(|row| {
+            let [open_time, open, high, low, close, volume, ..] = row.as_slice() else {
+                return None;
+            };
+            Some(Candle::new(
+                parse_u64(open_time)?,
+                parse_f64(open)?,
+                parse_f64(high)?,
+                parse_f64(low)?,
+                parse_f64(close)?,
+                parse_f64(volume)?,
+            ))
+        })
+        .collect()
+}
+
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Default for BinanceParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageParser for BinanceParser {
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn fallback_endpoint(&self) -> Option<&str> {
+        self.fallback_endpoint.as_deref()
+    }
+
+    fn name(&self) -> &'static str {
+        "Binance"
+    }
+
+    // Pages through /api/v3/klines, KLINES_LIMIT rows at a time
+    async fn fetch_candles(
+        &self,
+        symbol: &str,
+        interval: Timeframe,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<Vec<Candle>, CctError> {
+        let Some(base) = self.rest_endpoint.as_deref() else {
+            return Ok(Vec::new());
+        };
+        let Some(name) = self.interval_to_exchange(interval) else {
+            return Err(CctError::InvalidConfig(format!("Binance has no {} klines", interval)));
         };
 
-        format!(
-            r#"{{"method":"UNSUBSCRIBE","params":["{}"],"id":1}}"#,
-            stream_name
-        )
+        let client = reqwest::Client::new();
+        let mut candles = Vec::new();
+        let mut from = start_ms;
+        while from < end_ms {
+            let url = format!(
+                "{}/api/v3/klines?symbol={}&interval={}&startTime={}&endTime={}&limit={}",
+                base,
+                symbol.to_uppercase(),
+                name,
+                from,
+                end_ms - 1,
+                KLINES_LIMIT
+            );
+            let failed = |e: reqwest::Error| CctError::ConnectFailed { endpoint: url.clone(), source: e.into() };
+            let response = client.get(&url).send().await.map_err(failed)?;
+            let status = response.status();
+            let body = response.text().await.map_err(failed)?;
+            let page = parse_rest_klines(&body).filter(|_| status.is_success()).ok_or_else(|| {
+                CctError::ParseFailed { exchange: "Binance", message: format!("klines ({}): {}", status, body) }
+            })?;
+
+            let full = page.len() == KLINES_LIMIT;
+            let Some(last) = page.last() else { break };
+            from = last.get_timestamp() + 1;
+            candles.extend(page);
+            if !full {
+                break;
+            }
+        }
+        Ok(candles)
+    }
+
+    fn max_streams_per_connection(&self) -> usize {
+        MAX_STREAMS_PER_CONNECTION
+    }
+
+    // Streams Binance doesn't offer give an empty frame, which the client reports
+    // as unsupported
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        self.stream_name(stream).map(|name| format_request("SUBSCRIBE", &name, 1)).unwrap_or_default()
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        self.stream_name(stream).map(|name| format_request("UNSUBSCRIBE", &name, 1)).unwrap_or_default()
+    }
+
+    fn format_subscribe_with_id(&self, stream: &Stream, id: u64) -> Option<String> {
+        self.stream_name(stream).map(|name| format_request("SUBSCRIBE", &name, id))
+    }
+
+    fn format_unsubscribe_with_id(&self, stream: &Stream, id: u64) -> Option<String> {
+        self.stream_name(stream).map(|name| format_request("UNSUBSCRIBE", &name, id))
+    }
+
+    fn format_subscribe_many(&self, streams: &[Stream]) -> String {
+        self.stream_names(streams).map(|names| format_request_many("SUBSCRIBE", &names, 1)).unwrap_or_default()
+    }
+
+    fn format_subscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+        self.stream_names(streams).map(|names| format_request_many("SUBSCRIBE", &names, id))
+    }
+
+    fn format_unsubscribe_many(&self, streams: &[Stream]) -> String {
+        self.stream_names(streams).map(|names| format_request_many("UNSUBSCRIBE", &names, 1)).unwrap_or_default()
+    }
+
+    fn format_unsubscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+        self.stream_names(streams).map(|names| format_request_many("UNSUBSCRIBE", &names, id))
+    }
+
+    fn parse_response(&self, msg: &str) -> Option<(u64, Result<(), String>)> {
+        parse_response(msg)
     }
 
     fn parse_message(&self, msg: &str) -> Option<MarketData> {
-        // Detect message type by "e" field
-        if msg.contains(r#""e":"kline""#) {
-            return self.parse_kline(msg);
+        self.parse_event(msg).map(|data| data.with_exchange(self.name()))
+    }
+
+    fn parse_messages(&self, msg: &str) -> Vec<MarketData> {
+        if let Some(data) = unwrap_combined(msg) {
+            return self.parse_messages(&data);
+        }
+        // !miniTicker@arr delivers a JSON array of mini tickers in one frame
+        if msg.trim_start().starts_with('[') {
+            let events: Vec<BinanceMiniTickerEvent> = serde_json::from_str(msg).unwrap_or_default();
+            return events
+                .into_iter()
+                .map(|event| self.mini_ticker(event).with_exchange(self.name()))
+                .collect();
         }
+        self.parse_message(msg).into_iter().collect()
+    }
 
-        if msg.contains(r#""e":"trade""#) {
-            return self.parse_trade(msg);
+    fn stream_hint(&self, msg: &str) -> Option<Stream> {
+        // Only event types parse_message handles; attributing the rest would
+        // flag every depth/markPrice message as a failure
+        if let Some(data) = unwrap_combined(msg) {
+            return self.stream_hint(&data);
         }
+        if msg.trim_start().starts_with('[') && msg.contains(r#""e":"24hrMiniTicker""#) {
+            return Some(Stream::AllMiniTickers);
+        }
+        let value: serde_json::Value = serde_json::from_str(msg).ok()?;
+        let symbol = value.get("s")?.as_str()?;
+        if is_book_ticker(msg) {
+            return Some(Stream::book_ticker(symbol));
+        }
+        match value.get("e")?.as_str()? {
+            "kline" => {
+                let interval = self.interval_from_exchange(value.get("k")?.get("i")?.as_str()?)?;
+                Some(Stream::candles(symbol, interval))
+            }
+            "trade" => Some(Stream::trades(symbol)),
+            "aggTrade" => Some(Stream::agg_trades(symbol)),
+            "24hrTicker" => Some(Stream::ticker(symbol)),
+            _ => None,
+        }
+    }
 
-        // TODO: Add more message types
-        // - Order book: "e":"depthUpdate"
-        // - Mark price/funding: "e":"markPriceUpdate"
+    /// The serde error for the event type `msg` claims to be, e.g. a missing field.
+    fn parse_failure(&self, msg: &str) -> String {
+        if let Some(data) = unwrap_combined(msg) {
+            return self.parse_failure(&data);
+        }
+        let error = if msg.contains(r#""e":"kline""#) {
+            deserialize_error::<BinanceKlineEvent>("kline", msg)
+        } else if msg.contains(r#""e":"trade""#) {
+            deserialize_error::<BinanceTradeEvent>("trade", msg)
+        } else if msg.contains(r#""e":"aggTrade""#) {
+            deserialize_error::<BinanceAggTradeEvent>("aggTrade", msg)
+        } else if msg.contains(r#""e":"24hrTicker""#) {
+            deserialize_error::<BinanceTickerEvent>("24hrTicker", msg)
+        } else if is_book_ticker(msg) {
+            deserialize_error::<BinanceBookTickerEvent>("bookTicker", msg)
+        } else {
+            None
+        };
+        error.unwrap_or_else(|| "not a Binance event this parser handles".to_string())
+    }
+}
 
-        None // Unknown or control message
+fn deserialize_error<'a, T: Deserialize<'a>>(event: &str, msg: &'a str) -> Option<String> {
+    serde_json::from_str::<T>(msg).err().map(|e| format!("bad {} event: {}", event, e))
+}
+
+#[cfg(feature = "decimal")]
+fn exact_trade(trade: Trade, price: ExactF64, quantity: ExactF64) -> Trade {
+    match (price.exact, quantity.exact) {
+        (Some(price), Some(quantity)) => trade.with_exact(price, quantity),
+        _ => trade,
     }
 }
 
+#[cfg(not(feature = "decimal"))]
+fn exact_trade(trade: Trade, _price: ExactF64, _quantity: ExactF64) -> Trade {
+    trade
+}
+
+#[cfg(feature = "decimal")]
+fn exact_candle(candle: Candle, kline: &BinanceKline) -> Candle {
+    use crate::decimal::DecimalOhlcv;
+    match (kline.o.exact, kline.h.exact, kline.l.exact, kline.c.exact, kline.v.exact) {
+        (Some(open), Some(high), Some(low), Some(close), Some(volume)) => {
+            candle.with_exact(DecimalOhlcv { open, high, low, close, volume })
+        }
+        _ => candle,
+    }
+}
+
+#[cfg(not(feature = "decimal"))]
+fn exact_candle(candle: Candle, _kline: &BinanceKline) -> Candle {
+    candle
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceResponse {
+    id: Option<u64>,
+    error: Option<BinanceErrorBody>,
+    // Older error format
+    code: Option<i64>,
+    msg: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceErrorBody {
+    code: i64,
+    msg: String,
+}
+
 #[derive(Debug, Deserialize)]
-struct BinanceKlineEvent {
-    s: String,
-    k: BinanceKline,
+struct BinanceCombinedFrame {
+    data: serde_json::Value,
 }
 
+// Symbols (and the kline interval) borrow from the frame; the parser interns
+// them so parsed items share one allocation per symbol.
 #[derive(Debug, Deserialize)]
-struct BinanceKline {
+struct BinanceKlineEvent<'a> {
+    #[serde(borrow)]
+    s: Cow<'a, str>,
+    #[serde(borrow)]
+    k: BinanceKline<'a>,
+}
+
+// Klines and trades keep the exact strings (ExactF64) for the decimal feature
+#[derive(Debug, Deserialize)]
+struct BinanceKline<'a> {
     t: u64,
-    i: String,
-    #[serde(deserialize_with = "de_f64")]
-    o: f64,
-    #[serde(deserialize_with = "de_f64")]
-    h: f64,
-    #[serde(deserialize_with = "de_f64")]
-    l: f64,
-    #[serde(deserialize_with = "de_f64")]
-    c: f64,
-    #[serde(deserialize_with = "de_f64")]
-    v: f64,
+    #[serde(borrow)]
+    i: Cow<'a, str>,
+    o: ExactF64,
+    h: ExactF64,
+    l: ExactF64,
+    c: ExactF64,
+    v: ExactF64,
     x: bool,
 }
 
 #[derive(Debug, Deserialize)]
-struct BinanceTradeEvent {
-    s: String,
+struct BinanceTradeEvent<'a> {
+    #[serde(borrow)]
+    s: Cow<'a, str>,
     t: u64,
-    #[serde(deserialize_with = "de_f64")]
-    p: f64,
-    #[serde(deserialize_with = "de_f64")]
-    q: f64,
+    p: ExactF64,
+    q: ExactF64,
     #[serde(rename = "T")]
     trade_time: u64,
     m: bool,
 }
 
-fn de_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    struct F64Visitor;
-
-    impl<'de> serde::de::Visitor<'de> for F64Visitor {
-        type Value = f64;
-
-        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-            formatter.write_str("a string or number representing a float")
-        }
-
-        fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
-            Ok(value)
-        }
-
-        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E> {
-            Ok(value as f64)
-        }
-
-        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
-            Ok(value as f64)
-        }
+#[derive(Debug, Deserialize)]
+struct BinanceAggTradeEvent<'a> {
+    #[serde(borrow)]
+    s: Cow<'a, str>,
+    a: u64,
+    p: ExactF64,
+    q: ExactF64,
+    f: u64,
+    l: u64,
+    #[serde(rename = "T")]
+    trade_time: u64,
+    m: bool,
+}
 
-        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-        where
-            E: serde::de::Error,
-        {
-            value.parse::<f64>().map_err(E::custom)
-        }
+#[derive(Debug, Deserialize)]
+struct BinanceTickerEvent<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(borrow)]
+    s: Cow<'a, str>,
+    #[serde(rename = "P", deserialize_with = "de_f64")]
+    price_change_percent: f64,
+    #[serde(rename = "w", deserialize_with = "de_f64")]
+    weighted_avg_price: f64,
+    #[serde(rename = "c", deserialize_with = "de_f64")]
+    last_price: f64,
+    #[serde(rename = "o", deserialize_with = "de_f64")]
+    open: f64,
+    #[serde(rename = "h", deserialize_with = "de_f64")]
+    high: f64,
+    #[serde(rename = "l", deserialize_with = "de_f64")]
+    low: f64,
+    #[serde(rename = "v", deserialize_with = "de_f64")]
+    volume: f64,
+    #[serde(rename = "q", deserialize_with = "de_f64")]
+    quote_volume: f64,
+}
 
-        fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
-        where
-            E: serde::de::Error,
-        {
-            value.parse::<f64>().map_err(E::custom)
-        }
-    }
+#[derive(Debug, Deserialize)]
+struct BinanceMiniTickerEvent<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(borrow)]
+    s: Cow<'a, str>,
+    #[serde(rename = "c", deserialize_with = "de_f64")]
+    last_price: f64,
+    #[serde(rename = "o", deserialize_with = "de_f64")]
+    open: f64,
+    #[serde(rename = "h", deserialize_with = "de_f64")]
+    high: f64,
+    #[serde(rename = "l", deserialize_with = "de_f64")]
+    low: f64,
+    #[serde(rename = "v", deserialize_with = "de_f64")]
+    volume: f64,
+    #[serde(rename = "q", deserialize_with = "de_f64")]
+    quote_volume: f64,
+}
 
-    deserializer.deserialize_any(F64Visitor)
+#[derive(Debug, Deserialize)]
+struct BinanceBookTickerEvent<'a> {
+    u: u64,
+    #[serde(borrow)]
+    s: Cow<'a, str>,
+    #[serde(rename = "E")]
+    event_time: Option<u64>,
+    #[serde(rename = "b", deserialize_with = "de_f64")]
+    bid_price: f64,
+    #[serde(rename = "B", deserialize_with = "de_f64")]
+    bid_qty: f64,
+    #[serde(rename = "a", deserialize_with = "de_f64")]
+    ask_price: f64,
+    #[serde(rename = "A", deserialize_with = "de_f64")]
+    ask_qty: f64,
 }
 
 pub type BinanceClient = WebSocketClient<BinanceParser>;
@@ -269,10 +739,70 @@ pub fn new_binance_client() -> BinanceClient {
     WebSocketClient::new(BinanceParser::new())
 }
 
+/// Client on the combined-stream endpoint, subscribed to `streams` from the start.
+/// Call `subscribe` for the same streams too if you want the client to track them
+/// (health, resubscription); Binance treats the repeat as a no-op.
+pub fn new_binance_client_combined(streams: &[Stream]) -> Result<BinanceClient, CctError> {
+    Ok(WebSocketClient::new(BinanceParser::new().with_combined_streams(streams)?))
+}
+
+/// Client for the Binance spot testnet.
+pub fn new_binance_testnet_client() -> BinanceClient {
+    WebSocketClient::new(BinanceParser::testnet())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_endpoints() {
+        let production = BinanceParser::new();
+        assert_eq!(production.endpoint(), BINANCE_WSS_BASE_ENDPOINT);
+        assert_eq!(production.fallback_endpoint(), Some(BINANCE_WSS_FALLBACK_ENDPOINT));
+
+        let testnet = BinanceParser::testnet();
+        assert_eq!(testnet.endpoint(), "wss://stream.testnet.binance.vision/ws");
+        assert_eq!(testnet.fallback_endpoint(), None);
+
+        let custom = BinanceParser::with_endpoint("ws://127.0.0.1:9000/ws");
+        assert_eq!(custom.endpoint(), "ws://127.0.0.1:9000/ws");
+        assert_eq!(custom.fallback_endpoint(), None);
+
+        assert!(matches!(
+            BinanceParser::new().with_combined_streams(&[]),
+            Err(CctError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_rest_klines() {
+        // Example response from the GET /api/v3/klines docs
+        let body = r#"[[1499040000000,"0.01634790","0.80000000","0.01575800","0.01577100","148976.11427815",1499644799999,"2434.19055334",308,"1756.87402397","28.46694368","0"]]"#;
+        let candles = parse_rest_klines(body).unwrap();
+        assert_eq!(candles.len(), 1);
+        let candle = &candles[0];
+        assert_eq!(candle.get_timestamp(), 1499040000000);
+        assert_eq!(candle.get_open(), 0.0163479);
+        assert_eq!(candle.get_high(), 0.8);
+        assert_eq!(candle.get_low(), 0.015758);
+        assert_eq!(candle.get_close(), 0.015771);
+        assert_eq!(candle.get_volume(), 148976.11427815);
+
+        assert_eq!(parse_rest_klines("[]").unwrap().len(), 0);
+        assert!(parse_rest_klines(r#"{"code":-1121,"msg":"Invalid symbol."}"#).is_none());
+        assert!(parse_rest_klines(r#"[[1499040000000,"1.0"]]"#).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_no_backfill_without_rest_endpoint() {
+        let parser = BinanceParser::with_endpoint("ws://127.0.0.1:9000/ws");
+        let candles = parser.fetch_candles("BTCUSDT", Timeframe::M1, 0, 60_000).await.unwrap();
+        assert!(candles.is_empty());
+        assert_eq!(BinanceParser::new().rest_endpoint.as_deref(), Some(BINANCE_REST_ENDPOINT));
+        assert_eq!(BinanceParser::testnet().rest_endpoint.as_deref(), Some(BINANCE_TESTNET_REST_ENDPOINT));
+    }
+
     #[test]
     fn test_format_subscribe_candles() {
         let parser = BinanceParser::new();
@@ -313,8 +843,9 @@ mod tests {
         assert!(result.is_some());
         
         match result.unwrap() {
-            MarketData::Candle { symbol, interval, data, is_closed } => {
+            MarketData::Candle { symbol, interval, data, is_closed, exchange, .. } => {
                 assert_eq!(symbol, "BTCUSDT");
+                assert_eq!(exchange, "Binance");
                 assert_eq!(interval, Timeframe::M1);
                 assert_eq!(data.get_timestamp(), 1638747660000);
                 assert_eq!(data.get_open(), 50000.00);
@@ -344,6 +875,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_longer_and_odd_kline_intervals() {
+        let parser = BinanceParser::new();
+        for (interval, name) in [(Timeframe::M3, "3m"), (Timeframe::H12, "12h"), (Timeframe::D3, "3d"), (Timeframe::MN1, "1M")] {
+            let msg = parser.format_subscribe(&Stream::candles("BTCUSDT", interval));
+            assert!(msg.contains(&format!("btcusdt@kline_{}", name)), "{}", msg);
+
+            let kline = format!(
+                r#"{{"e":"kline","E":1,"s":"BTCUSDT","k":{{"t":1,"T":2,"s":"BTCUSDT","i":"{}","o":"1","c":"1","h":"1","l":"1","v":"1","x":true}}}}"#,
+                name
+            );
+            match parser.parse_message(&kline) {
+                Some(MarketData::Candle { interval: parsed, .. }) => assert_eq!(parsed, interval),
+                other => panic!("Expected MarketData::Candle for {}, got {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_derivatives_streams_unsupported_on_spot() {
+        let parser = BinanceParser::new();
+        let streams = [
+            Stream::funding("BTCUSDT"),
+            Stream::mark_price("BTCUSDT"),
+            Stream::open_interest("BTCUSDT"),
+            Stream::liquidations("BTCUSDT"),
+        ];
+        for stream in &streams {
+            assert!(parser.format_subscribe(stream).is_empty(), "{:?}", stream);
+            assert!(parser.format_unsubscribe(stream).is_empty(), "{:?}", stream);
+            assert!(parser.format_subscribe_with_id(stream, 3).is_none(), "{:?}", stream);
+        }
+        assert!(parser.format_subscribe_many(&[Stream::trades("BTCUSDT"), streams[0].clone()]).is_empty());
+    }
+
+    #[test]
+    fn test_custom_interval_has_no_stream() {
+        let parser = BinanceParser::new();
+        let custom = Stream::candles("BTCUSDT", Timeframe::from_seconds(120).unwrap());
+        assert!(parser.format_subscribe(&custom).is_empty());
+        assert!(parser.format_subscribe_with_id(&custom, 7).is_none());
+        assert!(parser.format_subscribe_many(&[Stream::trades("BTCUSDT"), custom.clone()]).is_empty());
+        assert!(matches!(
+            BinanceParser::new().with_combined_streams(&[custom]),
+            Err(CctError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_default_interval_names() {
+        let parser = BinanceParser::new();
+        for &interval in Timeframe::all() {
+            let name = parser.interval_to_exchange(interval).unwrap();
+            assert_eq!(parser.interval_from_exchange(&name), Some(interval));
+        }
+        assert_eq!(parser.interval_to_exchange(Timeframe::H4).as_deref(), Some("4h"));
+        assert_eq!(parser.interval_to_exchange(Timeframe::MN1).as_deref(), Some("1M"));
+        assert_eq!(parser.interval_to_exchange(Timeframe::from_seconds(420).unwrap()), None);
+        // Parsable as a custom timeframe, but not a Binance interval
+        assert_eq!(parser.interval_from_exchange("7m"), None);
+    }
+
     #[test]
     fn test_parse_trade_message() {
         let parser = BinanceParser::new();
@@ -367,6 +960,56 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_prices_are_exact() {
+        use crate::decimal::Decimal;
+
+        let parser = BinanceParser::new();
+        let msg = r#"{"e":"trade","E":1672515782136,"s":"BTCUSDT","t":1,"p":"50000.123456789","q":"0.000000123456789","T":1672515782136,"m":false}"#;
+        let trade = parser.parse_message(msg).unwrap().as_trade().unwrap().clone();
+        assert_eq!(trade.price_decimal().to_string(), "50000.123456789");
+        assert_eq!(trade.quantity_decimal().to_string(), "0.000000123456789");
+        assert_eq!(trade.price, 50000.123456789);
+
+        // Survives serialization too
+        let json = serde_json::to_string(&MarketData::Trade(trade)).unwrap();
+        let back: MarketData = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.as_trade().unwrap().price_decimal(), "50000.123456789".parse::<Decimal>().unwrap());
+
+        let kline = r#"{"e":"kline","E":1,"s":"BTCUSDT","k":{"t":1,"T":2,"s":"BTCUSDT","i":"1m","o":"0.1","c":"0.3","h":"0.30000000000000001","l":"0.1","v":"1234567.891011121314","x":true}}"#;
+        let data = parser.parse_message(kline).unwrap();
+        let ohlcv = data.as_candle().unwrap().2.get_decimal_ohlcv();
+        assert_eq!(ohlcv.high.to_string(), "0.30000000000000001");
+        assert_eq!(ohlcv.volume.to_string(), "1234567.891011121314");
+        assert_eq!(ohlcv.open + ohlcv.close, "0.4".parse::<Decimal>().unwrap());
+
+        // Hand-built items fall back to the f64
+        let trade = Trade::new(0, "BTCUSDT", 0.1, 2.0, "t", TradeSide::Buy);
+        assert_eq!(trade.price_decimal().to_string(), "0.1");
+    }
+
+    #[test]
+    fn test_symbols_are_interned() {
+        let parser = BinanceParser::new();
+        let trade = r#"{"e":"trade","E":1,"s":"BTCUSDT","t":1,"p":"1","q":"1","T":1,"m":false}"#;
+        let kline = r#"{"e":"kline","E":1,"s":"BTCUSDT","k":{"t":1,"T":2,"s":"BTCUSDT","i":"1m","o":"1","c":"1","h":"1","l":"1","v":"1","x":true}}"#;
+        let first = parser.parse_message(trade).unwrap().as_trade().unwrap().symbol.clone();
+        let second = parser.parse_message(trade).unwrap().as_trade().unwrap().symbol.clone();
+        assert!(first.ptr_eq(&second));
+        let MarketData::Candle { symbol, .. } = parser.parse_message(kline).unwrap() else {
+            panic!("Expected MarketData::Candle");
+        };
+        assert!(symbol.ptr_eq(&first));
+
+        // Clones share the cache; a zero-capacity cache allocates every time
+        assert!(parser.clone().parse_message(trade).unwrap().as_trade().unwrap().symbol.ptr_eq(&first));
+        let uncached = BinanceParser::new().with_symbol_cache(SymbolCache::new(0));
+        let a = uncached.parse_message(trade).unwrap().as_trade().unwrap().symbol.clone();
+        let b = uncached.parse_message(trade).unwrap().as_trade().unwrap().symbol.clone();
+        assert!(!a.ptr_eq(&b));
+    }
+
     #[test]
     fn test_parse_trade_sell() {
         let parser = BinanceParser::new();
@@ -385,6 +1028,174 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_agg_trades_subscribe_and_parse() {
+        let parser = BinanceParser::new();
+        assert_eq!(
+            parser.format_subscribe(&Stream::agg_trades("BTCUSDT")),
+            r#"{"method":"SUBSCRIBE","params":["btcusdt@aggTrade"],"id":1}"#
+        );
+        assert_eq!(
+            parser.format_unsubscribe(&Stream::agg_trades("BTCUSDT")),
+            r#"{"method":"UNSUBSCRIBE","params":["btcusdt@aggTrade"],"id":1}"#
+        );
+
+        let msg = r#"{"e":"aggTrade","E":1672515782136,"s":"BNBBTC","a":12345,"p":"0.001","q":"100","f":100,"l":105,"T":1672515782136,"m":false,"M":true}"#;
+        match parser.parse_message(msg) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.trade_id, "12345");
+                assert_eq!(trade.first_trade_id, Some(100));
+                assert_eq!(trade.last_trade_id, Some(105));
+                assert_eq!(trade.side, TradeSide::Buy);
+                assert_eq!(trade.quantity, 100.0);
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+        assert_eq!(parser.stream_hint(msg), Some(Stream::agg_trades("BNBBTC")));
+    }
+
+    #[test]
+    fn test_book_ticker_subscribe_and_parse() {
+        let parser = BinanceParser::new();
+        assert_eq!(
+            parser.format_subscribe(&Stream::book_ticker("BNBUSDT")),
+            r#"{"method":"SUBSCRIBE","params":["bnbusdt@bookTicker"],"id":1}"#
+        );
+
+        // Spot payload: no "e" field
+        let msg = r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+        match parser.parse_message(msg) {
+            Some(MarketData::BookTicker(ticker)) => {
+                assert_eq!(ticker.symbol, "BNBUSDT");
+                assert_eq!(ticker.bid_price, 25.3519);
+                assert_eq!(ticker.bid_quantity, 31.21);
+                assert_eq!(ticker.ask_price, 25.3652);
+                assert_eq!(ticker.ask_quantity, 40.66);
+                assert_eq!(ticker.update_id, Some(400900217));
+                assert!(ticker.timestamp > 0);
+                assert!((ticker.spread() - 0.0133).abs() < 1e-9);
+                assert!((ticker.mid_price() - 25.35855).abs() < 1e-9);
+            }
+            other => panic!("Expected MarketData::BookTicker, got {:?}", other),
+        }
+        assert_eq!(parser.stream_hint(msg), Some(Stream::book_ticker("BNBUSDT")));
+
+        // Futures payload carries "e" and an event time
+        let futures = r#"{"e":"bookTicker","u":400900217,"E":1568014460893,"T":1568014460891,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+        let ticker = parser.parse_message(futures).and_then(|d| d.as_book_ticker().cloned());
+        assert_eq!(ticker.map(|t| t.timestamp), Some(1568014460893));
+    }
+
+    #[test]
+    fn test_ticker_subscribe_and_parse() {
+        let parser = BinanceParser::new();
+        assert_eq!(
+            parser.format_subscribe(&Stream::ticker("BNBBTC")),
+            r#"{"method":"SUBSCRIBE","params":["bnbbtc@ticker"],"id":1}"#
+        );
+
+        let msg = r#"{"e":"24hrTicker","E":1672515782136,"s":"BNBBTC","p":"0.0015","P":"250.00","w":"0.0018","x":"0.0009","c":"0.0025","Q":"10","b":"0.0024","B":"10","a":"0.0026","A":"100","o":"0.0010","h":"0.0025","l":"0.0010","v":"10000","q":"18","O":0,"C":86400000,"F":0,"L":18150,"n":18151}"#;
+        match parser.parse_message(msg) {
+            Some(MarketData::Ticker(ticker)) => {
+                assert_eq!(ticker.timestamp, 1672515782136);
+                assert_eq!(ticker.symbol, "BNBBTC");
+                assert_eq!(ticker.last_price, 0.0025);
+                assert_eq!(ticker.open, Some(0.0010));
+                assert_eq!(ticker.high, Some(0.0025));
+                assert_eq!(ticker.low, Some(0.0010));
+                assert_eq!(ticker.volume, 10000.0);
+                assert_eq!(ticker.quote_volume, Some(18.0));
+                assert_eq!(ticker.price_change_percent, Some(250.0));
+                assert_eq!(ticker.weighted_avg_price, Some(0.0018));
+            }
+            other => panic!("Expected MarketData::Ticker, got {:?}", other),
+        }
+        assert_eq!(parser.stream_hint(msg), Some(Stream::ticker("BNBBTC")));
+    }
+
+    #[test]
+    fn test_mini_ticker_array_yields_one_ticker_per_symbol() {
+        let parser = BinanceParser::new();
+        assert_eq!(
+            parser.format_subscribe(&Stream::AllMiniTickers),
+            r#"{"method":"SUBSCRIBE","params":["!miniTicker@arr"],"id":1}"#
+        );
+
+        let frame = r#"[{"e":"24hrMiniTicker","E":1672515782136,"s":"BTCUSDT","c":"42000.5","o":"41000","h":"42500","l":"40800","v":"1234.5","q":"51000000"},{"e":"24hrMiniTicker","E":1672515782136,"s":"ETHUSDT","c":"2250","o":"2200","h":"2275","l":"2190","v":"9000","q":"20000000"}]"#;
+        let items = parser.parse_messages(frame);
+        assert_eq!(items.len(), 2);
+
+        let btc = items[0].as_ticker().unwrap();
+        assert_eq!(btc.symbol, "BTCUSDT");
+        assert_eq!(btc.last_price, 42000.5);
+        assert_eq!(btc.open, Some(41000.0));
+        assert_eq!(btc.quote_volume, Some(51000000.0));
+        assert_eq!(btc.price_change_percent, None);
+        assert_eq!(items[1].as_ticker().unwrap().symbol, "ETHUSDT");
+        assert_eq!(parser.stream_hint(frame), Some(Stream::AllMiniTickers));
+
+        // A single mini ticker parses through parse_message too
+        let single = r#"{"e":"24hrMiniTicker","E":1672515782136,"s":"BNBBTC","c":"0.0025","o":"0.0010","h":"0.0025","l":"0.0010","v":"10000","q":"18"}"#;
+        assert!(parser.parse_message(single).is_some_and(|d| d.is_ticker()));
+        assert_eq!(parser.parse_messages(single).len(), 1);
+        assert!(parser.parse_messages(r#"{"result":null,"id":1}"#).is_empty());
+    }
+
+    #[test]
+    fn test_combined_endpoint_url() {
+        let streams = [
+            Stream::candles("BTCUSDT", Timeframe::M1),
+            Stream::trades("ETHUSDT"),
+        ];
+        let parser = BinanceParser::new().with_combined_streams(&streams).unwrap();
+        assert_eq!(
+            parser.endpoint(),
+            "wss://stream.binance.com:443/stream?streams=btcusdt@kline_1m/ethusdt@trade"
+        );
+        assert_eq!(
+            parser.fallback_endpoint(),
+            Some("wss://stream.binance.com:9443/stream?streams=btcusdt@kline_1m/ethusdt@trade")
+        );
+
+        let testnet = BinanceParser::testnet().with_combined_streams(&streams[1..]).unwrap();
+        assert_eq!(
+            testnet.endpoint(),
+            "wss://stream.testnet.binance.vision/stream?streams=ethusdt@trade"
+        );
+    }
+
+    #[test]
+    fn test_parse_wrapped_kline_and_trade() {
+        let parser = BinanceParser::new();
+
+        let kline = r#"{"stream":"btcusdt@kline_1m","data":{"e":"kline","E":1672515782136,"s":"BTCUSDT","k":{"t":1672515780000,"T":1672515839999,"s":"BTCUSDT","i":"1m","f":100,"L":200,"o":"16500.00","c":"16510.00","h":"16520.00","l":"16490.00","v":"12.5","n":100,"x":false,"q":"206000","V":"6","Q":"99000","B":"0"}}}"#;
+        match parser.parse_message(kline) {
+            Some(MarketData::Candle { symbol, interval, data, is_closed, .. }) => {
+                assert_eq!(symbol, "BTCUSDT");
+                assert_eq!(interval, Timeframe::M1);
+                assert_eq!(data.get_close(), 16510.0);
+                assert!(!is_closed);
+            }
+            other => panic!("Expected MarketData::Candle, got {:?}", other),
+        }
+        assert_eq!(parser.stream_hint(kline), Some(Stream::candles("BTCUSDT", Timeframe::M1)));
+
+        let trade = r#"{"stream":"ethusdt@trade","data":{"e":"trade","E":1672515782136,"s":"ETHUSDT","t":12345,"p":"1200.50","q":"0.5","T":1672515782136,"m":false,"M":true}}"#;
+        match parser.parse_message(trade) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.symbol, "ETHUSDT");
+                assert_eq!(trade.price, 1200.5);
+                assert_eq!(trade.side, TradeSide::Buy);
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+        assert_eq!(parser.parse_messages(trade).len(), 1);
+
+        // Wrapped mini ticker arrays still fan out
+        let arr = r#"{"stream":"!miniTicker@arr","data":[{"e":"24hrMiniTicker","E":1,"s":"BTCUSDT","c":"1","o":"1","h":"1","l":"1","v":"1","q":"1"},{"e":"24hrMiniTicker","E":1,"s":"ETHUSDT","c":"1","o":"1","h":"1","l":"1","v":"1","q":"1"}]}"#;
+        assert_eq!(parser.parse_messages(arr).len(), 2);
+    }
+
     #[test]
     fn test_parse_subscription_confirmation() {
         let parser = BinanceParser::new();
@@ -395,6 +1206,42 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_request_ids_and_responses() {
+        let parser = BinanceParser::new();
+        assert_eq!(
+            parser.format_subscribe_with_id(&Stream::trades("BTCUSDT"), 42).unwrap(),
+            r#"{"method":"SUBSCRIBE","params":["btcusdt@trade"],"id":42}"#
+        );
+        assert_eq!(
+            parser.format_unsubscribe_with_id(&Stream::trades("BTCUSDT"), 43).unwrap(),
+            r#"{"method":"UNSUBSCRIBE","params":["btcusdt@trade"],"id":43}"#
+        );
+
+        let streams = [Stream::trades("BTCUSDT"), Stream::candles("ETHUSDT", Timeframe::M1)];
+        assert_eq!(
+            parser.format_subscribe_many_with_id(&streams, 44).unwrap(),
+            r#"{"method":"SUBSCRIBE","params":["btcusdt@trade","ethusdt@kline_1m"],"id":44}"#
+        );
+        assert_eq!(
+            parser.format_unsubscribe_many_with_id(&streams, 45).unwrap(),
+            r#"{"method":"UNSUBSCRIBE","params":["btcusdt@trade","ethusdt@kline_1m"],"id":45}"#
+        );
+
+        assert_eq!(parser.parse_response(r#"{"result":null,"id":42}"#), Some((42, Ok(()))));
+        assert_eq!(
+            parser.parse_response(r#"{"error":{"code":2,"msg":"Invalid request: unknown variable"},"id":7}"#),
+            Some((7, Err("Invalid request: unknown variable (code 2)".to_string())))
+        );
+        assert_eq!(
+            parser.parse_response(r#"{"code":0,"msg":"Unknown property","id":8}"#),
+            Some((8, Err("Unknown property (code 0)".to_string())))
+        );
+        // Data events are not replies
+        let trade = r#"{"e":"trade","E":1,"s":"BTCUSDT","t":1,"p":"1","q":"1","T":1,"m":true,"M":true}"#;
+        assert_eq!(parser.parse_response(trade), None);
+    }
+
     #[test]
     fn test_parse_unknown_message() {
         let parser = BinanceParser::new();
@@ -404,4 +1251,37 @@ mod tests {
         let result = parser.parse_message(msg);
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_stream_hint() {
+        let parser = BinanceParser::new();
+
+        // Hint survives payload drift inside "k" as long as e/s/k.i are intact
+        let kline = r#"{"e":"kline","s":"BTCUSDT","k":{"i":"1m","o":{"renamed":true}}}"#;
+        assert!(parser.parse_message(kline).is_none());
+        assert_eq!(parser.stream_hint(kline), Some(Stream::candles("BTCUSDT", Timeframe::M1)));
+
+        let trade = r#"{"e":"trade","s":"ETHUSDT","p":"not-a-number"}"#;
+        assert_eq!(parser.stream_hint(trade), Some(Stream::trades("ETHUSDT")));
+
+        assert_eq!(parser.stream_hint(r#"{"result":null,"id":1}"#), None);
+        assert_eq!(parser.stream_hint(r#"{"e":"depthUpdate","s":"BTCUSDT"}"#), None);
+    }
+
+    #[test]
+    fn test_parse_failure_reason() {
+        let parser = BinanceParser::new();
+
+        // Trade without its price
+        let trade = r#"{"e":"trade","E":1672515782136,"s":"BNBBTC","t":12345,"q":"100","T":1672515782136,"m":true,"M":true}"#;
+        assert!(parser.parse_message(trade).is_none());
+        assert!(parser.parse_failure(trade).starts_with("bad trade event: missing field `p`"));
+
+        let combined = format!(r#"{{"stream":"bnbbtc@trade","data":{}}}"#, trade);
+        assert!(parser.parse_failure(&combined).contains("missing field `p`"));
+        assert_eq!(
+            parser.parse_failure(r#"{"e":"depthUpdate","s":"BTCUSDT"}"#),
+            "not a Binance event this parser handles"
+        );
+    }
 }
@@ -0,0 +1,356 @@
+//! Binance USD-M futures implementation (fstream).
+//! See docs/market/BINANCE.md for message formats and details.
+
+use std::borrow::Cow;
+
+use serde::Deserialize;
+
+use crate::market::market_data::{FundingRate, Liquidation, MarkPrice, MarketData, TradeSide};
+use crate::market::message_parser::MessageParser;
+use crate::market::providers::binance::{self, BinanceParser};
+use crate::market::providers::common::de_f64;
+use crate::market::streams::Stream;
+use crate::market::websocket_client::WebSocketClient;
+
+pub const BINANCE_FUTURES_WSS_ENDPOINT: &str = "wss://fstream.binance.com/ws";
+
+// Futures share the spot message shapes for klines, aggTrades, bookTicker and
+// (mini) tickers, so those are delegated to BinanceParser. What differs:
+// - there is no raw @trade stream; Stream::Trades maps to @aggTrade
+// - @markPrice exists (funding rate, next funding time, mark, index and
+//   estimated settle price). Stream::Funding and Stream::MarkPrice share it, and
+//   the frame doesn't say which was subscribed, so every markPriceUpdate yields a
+//   MarketData::Funding followed by a MarketData::MarkPrice; filter on the variant.
+// - @forceOrder pushes liquidation orders
+// Open interest is REST-only on Binance, so Stream::OpenInterest is unsupported.
+
+/// Binance USD-M futures message parser.
+#[derive(Debug, Clone, Default)]
+pub struct BinanceFuturesParser {
+    spot: BinanceParser,
+}
+
+impl BinanceFuturesParser {
+    pub fn new() -> Self {
+        Self {
+            spot: BinanceParser::new(),
+        }
+    }
+
+    /// Returns the fstream stream name, or None if futures have no such stream.
+    fn stream_name(&self, stream: &Stream) -> Option<String> {
+        let name = match stream {
+            Stream::Candles { symbol, interval } => {
+                format!("{}@kline_{}", symbol.to_lowercase(), self.interval_to_exchange(*interval)?)
+            }
+            Stream::Trades { symbol } | Stream::AggTrades { symbol } => {
+                format!("{}@aggTrade", symbol.to_lowercase())
+            }
+            Stream::Funding { symbol } | Stream::MarkPrice { symbol } => {
+                format!("{}@markPrice@1s", symbol.to_lowercase())
+            }
+            Stream::OrderBook { symbol, depth } => {
+                format!("{}@depth{}", symbol.to_lowercase(), depth)
+            }
+            Stream::BookTicker { symbol } => format!("{}@bookTicker", symbol.to_lowercase()),
+            Stream::Ticker { symbol } => format!("{}@ticker", symbol.to_lowercase()),
+            Stream::AllMiniTickers => "!miniTicker@arr".to_string(),
+            Stream::Liquidations { symbol } => format!("{}@forceOrder", symbol.to_lowercase()),
+            Stream::OpenInterest { .. } => return None,
+        };
+        Some(name)
+    }
+
+    fn format_request(&self, method: &str, stream: &Stream, id: u64) -> String {
+        self.format_request_many(method, std::slice::from_ref(stream), id)
+    }
+
+    /// Empty if any of the streams has no futures equivalent.
+    fn format_request_many(&self, method: &str, streams: &[Stream], id: u64) -> String {
+        let names: Option<Vec<String>> = streams.iter().map(|stream| self.stream_name(stream)).collect();
+        match names {
+            Some(names) => binance::format_request_many(method, &names, id),
+            None => String::new(),
+        }
+    }
+
+    /// Parses a markPriceUpdate event into MarketData::Funding followed by
+    /// MarketData::MarkPrice.
+    fn parse_mark_price(&self, msg: &str) -> Vec<MarketData> {
+        let Ok(event) = serde_json::from_str::<BinanceMarkPriceEvent>(msg) else {
+            return Vec::new();
+        };
+        let symbol = self.spot.intern(&event.s);
+        let funding = FundingRate::new(event.event_time, symbol.clone(), event.r)
+            .with_next_funding_time(event.next_funding_time)
+            .with_mark_price(event.p);
+        let mark = MarkPrice::new(event.event_time, symbol, event.p)
+            .with_index_price(event.i)
+            .with_estimated_settle_price(event.settle);
+        vec![MarketData::Funding(funding), MarketData::MarkPrice(mark)]
+    }
+
+    /// Parses a forceOrder event into MarketData::Liquidation.
+    /// Uses the average fill price and filled quantity once the order has filled.
+    fn parse_force_order(&self, msg: &str) -> Option<MarketData> {
+        let event: BinanceForceOrderEvent = serde_json::from_str(msg).ok()?;
+        let order = event.o;
+        let side = match order.side.as_str() {
+            "BUY" => TradeSide::Buy,
+            "SELL" => TradeSide::Sell,
+            _ => return None,
+        };
+        let price = if order.avg_price > 0.0 { order.avg_price } else { order.price };
+        let quantity = if order.filled_qty > 0.0 { order.filled_qty } else { order.qty };
+
+        let liquidation = Liquidation::new(order.trade_time, self.spot.intern(&order.symbol), side, price, quantity)
+            .with_order_type(order.order_type);
+        Some(MarketData::Liquidation(liquidation))
+    }
+}
+
+impl MessageParser for BinanceFuturesParser {
+    fn endpoint(&self) -> &str {
+        BINANCE_FUTURES_WSS_ENDPOINT
+    }
+
+    fn name(&self) -> &'static str {
+        "Binance Futures"
+    }
+
+    fn max_streams_per_connection(&self) -> usize {
+        binance::MAX_STREAMS_PER_CONNECTION
+    }
+
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        self.format_request("SUBSCRIBE", stream, 1)
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        self.format_request("UNSUBSCRIBE", stream, 1)
+    }
+
+    fn format_subscribe_with_id(&self, stream: &Stream, id: u64) -> Option<String> {
+        Some(self.format_request("SUBSCRIBE", stream, id))
+    }
+
+    fn format_unsubscribe_with_id(&self, stream: &Stream, id: u64) -> Option<String> {
+        Some(self.format_request("UNSUBSCRIBE", stream, id))
+    }
+
+    fn format_subscribe_many(&self, streams: &[Stream]) -> String {
+        self.format_request_many("SUBSCRIBE", streams, 1)
+    }
+
+    fn format_subscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+        Some(self.format_request_many("SUBSCRIBE", streams, id))
+    }
+
+    fn format_unsubscribe_many(&self, streams: &[Stream]) -> String {
+        self.format_request_many("UNSUBSCRIBE", streams, 1)
+    }
+
+    fn format_unsubscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+        Some(self.format_request_many("UNSUBSCRIBE", streams, id))
+    }
+
+    fn parse_response(&self, msg: &str) -> Option<(u64, Result<(), String>)> {
+        binance::parse_response(msg)
+    }
+
+    /// The first item of `parse_messages`: funding rather than mark price for
+    /// markPriceUpdate events.
+    fn parse_message(&self, msg: &str) -> Option<MarketData> {
+        let parsed = if msg.contains(r#""e":"markPriceUpdate""#) {
+            self.parse_mark_price(msg).into_iter().next()
+        } else if msg.contains(r#""e":"forceOrder""#) {
+            self.parse_force_order(msg)
+        } else {
+            // Everything else has the same shape as on spot
+            self.spot.parse_message(msg)
+        };
+        parsed.map(|data| data.with_exchange(self.name()))
+    }
+
+    fn parse_messages(&self, msg: &str) -> Vec<MarketData> {
+        if msg.trim_start().starts_with('[') {
+            let parsed = self.spot.parse_messages(msg);
+            return parsed.into_iter().map(|data| data.with_exchange(self.name())).collect();
+        }
+        if msg.contains(r#""e":"markPriceUpdate""#) {
+            let parsed = self.parse_mark_price(msg);
+            return parsed.into_iter().map(|data| data.with_exchange(self.name())).collect();
+        }
+        self.parse_message(msg).into_iter().collect()
+    }
+
+    fn stream_hint(&self, msg: &str) -> Option<Stream> {
+        self.spot.stream_hint(msg)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceMarkPriceEvent<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(borrow)]
+    s: Cow<'a, str>,
+    #[serde(deserialize_with = "de_f64")]
+    p: f64,
+    #[serde(deserialize_with = "de_f64")]
+    i: f64,
+    #[serde(rename = "P", deserialize_with = "de_f64")]
+    settle: f64,
+    #[serde(deserialize_with = "de_f64")]
+    r: f64,
+    #[serde(rename = "T")]
+    next_funding_time: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceForceOrderEvent<'a> {
+    #[serde(borrow)]
+    o: BinanceForceOrder<'a>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceForceOrder<'a> {
+    #[serde(rename = "s", borrow)]
+    symbol: Cow<'a, str>,
+    #[serde(rename = "S")]
+    side: String,
+    #[serde(rename = "o")]
+    order_type: String,
+    #[serde(rename = "q", deserialize_with = "de_f64")]
+    qty: f64,
+    #[serde(rename = "p", deserialize_with = "de_f64")]
+    price: f64,
+    #[serde(rename = "ap", deserialize_with = "de_f64")]
+    avg_price: f64,
+    #[serde(rename = "z", deserialize_with = "de_f64")]
+    filled_qty: f64,
+    #[serde(rename = "T")]
+    trade_time: u64,
+}
+
+pub type BinanceFuturesClient = WebSocketClient<BinanceFuturesParser>;
+
+pub fn new_binance_futures_client() -> BinanceFuturesClient {
+    WebSocketClient::new(BinanceFuturesParser::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::timeframe::Timeframe;
+
+    #[test]
+    fn test_format_subscribe_futures_streams() {
+        let parser = BinanceFuturesParser::new();
+        assert_eq!(
+            parser.format_subscribe(&Stream::Funding { symbol: "BTCUSDT".to_string() }),
+            r#"{"method":"SUBSCRIBE","params":["btcusdt@markPrice@1s"],"id":1}"#
+        );
+        assert_eq!(
+            parser.format_subscribe(&Stream::liquidations("BTCUSDT")),
+            r#"{"method":"SUBSCRIBE","params":["btcusdt@forceOrder"],"id":1}"#
+        );
+        assert_eq!(
+            parser.format_unsubscribe(&Stream::trades("ETHUSDT")),
+            r#"{"method":"UNSUBSCRIBE","params":["ethusdt@aggTrade"],"id":1}"#
+        );
+        assert!(parser
+            .format_subscribe(&Stream::OpenInterest { symbol: "BTCUSDT".to_string() })
+            .is_empty());
+        assert_eq!(
+            parser.format_subscribe_many(&[Stream::trades("BTCUSDT"), Stream::book_ticker("BTCUSDT")]),
+            r#"{"method":"SUBSCRIBE","params":["btcusdt@aggTrade","btcusdt@bookTicker"],"id":1}"#
+        );
+        // One unsupported stream spoils the batch
+        assert!(parser
+            .format_subscribe_many(&[Stream::trades("BTCUSDT"), Stream::OpenInterest { symbol: "BTCUSDT".to_string() }])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_mark_price_update() {
+        let parser = BinanceFuturesParser::new();
+        let msg = r#"{"e":"markPriceUpdate","E":1562305380000,"s":"BTCUSDT","p":"11794.15000000","i":"11784.62659091","P":"11784.25641265","r":"0.00038167","T":1562306400000}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Funding(funding)) => {
+                assert_eq!(funding.symbol, "BTCUSDT");
+                assert_eq!(funding.timestamp, 1562305380000);
+                assert_eq!(funding.rate, 0.00038167);
+                assert_eq!(funding.next_funding_time, Some(1562306400000));
+                assert_eq!(funding.mark_price, Some(11794.15));
+            }
+            other => panic!("Expected MarketData::Funding, got {:?}", other),
+        }
+
+        let parsed = parser.parse_messages(msg);
+        assert_eq!(parsed.len(), 2);
+        assert!(parsed[0].is_funding());
+        let mark = parsed[1].as_mark_price().unwrap();
+        assert_eq!(mark.symbol, "BTCUSDT");
+        assert_eq!(mark.timestamp, 1562305380000);
+        assert_eq!(mark.mark_price, 11794.15);
+        assert_eq!(mark.index_price, Some(11784.62659091));
+        assert_eq!(mark.estimated_settle_price, Some(11784.25641265));
+        assert_eq!(parsed[1].exchange(), "Binance Futures");
+        assert!(Stream::mark_price("BTCUSDT").matches(&parsed[1]));
+    }
+
+    #[test]
+    fn test_parse_force_order() {
+        let parser = BinanceFuturesParser::new();
+        let msg = r#"{"e":"forceOrder","E":1568014460893,"o":{"s":"BTCUSDT","S":"SELL","o":"LIMIT","f":"IOC","q":"0.014","p":"9910","ap":"9910","X":"FILLED","l":"0.014","z":"0.014","T":1568014460893}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Liquidation(liquidation)) => {
+                assert_eq!(liquidation.symbol, "BTCUSDT");
+                assert_eq!(liquidation.timestamp, 1568014460893);
+                assert_eq!(liquidation.side, TradeSide::Sell);
+                assert_eq!(liquidation.price, 9910.0);
+                assert_eq!(liquidation.quantity, 0.014);
+                assert!((liquidation.value - 138.74).abs() < 1e-9);
+                assert_eq!(liquidation.order_type.as_deref(), Some("LIMIT"));
+            }
+            other => panic!("Expected MarketData::Liquidation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_agg_trade() {
+        let parser = BinanceFuturesParser::new();
+        let msg = r#"{"e":"aggTrade","E":123456789,"s":"BTCUSDT","a":5933014,"p":"0.001","q":"100","f":100,"l":105,"T":123456785,"m":true}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.trade_id, "5933014");
+                assert_eq!(trade.timestamp, 123456785);
+                assert_eq!(trade.side, TradeSide::Sell);
+                assert_eq!(trade.is_buyer_maker, Some(true));
+                assert_eq!(trade.first_trade_id, Some(100));
+                assert_eq!(trade.last_trade_id, Some(105));
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_kline_delegates_to_spot() {
+        let parser = BinanceFuturesParser::new();
+        let msg = r#"{"e":"kline","E":1638747660000,"s":"BTCUSDT","k":{"t":1638747660000,"T":1638747719999,"s":"BTCUSDT","i":"1m","f":100,"L":200,"o":"50000.00","c":"50100.00","h":"50200.00","l":"49900.00","v":"100.5","n":100,"x":true,"q":"5025000.00","V":"50.25","Q":"2512500.00","B":"0"}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Candle { interval, is_closed, exchange, .. }) => {
+                assert_eq!(interval, Timeframe::M1);
+                assert!(is_closed);
+                assert_eq!(exchange, "Binance Futures");
+            }
+            other => panic!("Expected MarketData::Candle, got {:?}", other),
+        }
+        assert!(parser.parse_message(r#"{"result":null,"id":1}"#).is_none());
+    }
+}
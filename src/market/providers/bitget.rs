@@ -0,0 +1,559 @@
+//! Bitget exchange implementation (v2 public WebSocket).
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::{FundingRate, MarkPrice, MarketData, OrderBookUpdate, Trade, TradeSide};
+use crate::market::message_parser::MessageParser;
+use crate::market::providers::common::{de_f64, de_u64, parse_f64, parse_levels, parse_u64};
+use crate::market::streams::Stream;
+use crate::market::websocket_client::WebSocketClient;
+
+pub const BITGET_WSS_PUBLIC_ENDPOINT: &str = "wss://ws.bitget.com/v2/ws/public";
+pub const BITGET_DEFAULT_INST_TYPE: &str = "USDT-FUTURES";
+
+// Bitget closes connections that don't send a literal "ping" within 2 minutes
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+// Bitget pushes every channel as {"action","arg":{instType,channel,instId},"data":[...]}.
+// Funding has no dedicated channel: it rides on the futures "ticker" channel
+// together with the mark price, so Stream::Funding and Stream::MarkPrice both
+// map to "ticker". parse_messages yields MarketData::Funding followed by
+// MarketData::MarkPrice for each ticker push; filter on the variant.
+//
+// Frames can carry several entries in "data"; parse_message returns one item,
+// so only the newest entry is emitted. Per channel:
+// - trade: newest first (snapshot of recent trades), so the first entry
+// - candle: the snapshot replays history; the row with the latest start time
+//   wins, so the result doesn't depend on the array order
+// - ticker, books*: one entry per push
+
+/// Bitget-specific message parser.
+/// Implements MessageParser to convert Bitget JSON -> normalized MarketData.
+#[derive(Debug, Clone)]
+pub struct BitgetParser {
+    inst_type: String,
+}
+
+impl BitgetParser {
+    /// Parser for USDT-margined futures (supports funding).
+    pub fn new() -> Self {
+        Self {
+            inst_type: BITGET_DEFAULT_INST_TYPE.to_string(),
+        }
+    }
+
+    /// Parser for another product line, e.g. "SPOT" or "COIN-FUTURES".
+    pub fn with_inst_type(inst_type: impl Into<String>) -> Self {
+        Self {
+            inst_type: inst_type.into(),
+        }
+    }
+
+    fn channel(&self, stream: &Stream) -> Option<(String, String)> {
+        match stream {
+            Stream::Candles { symbol, interval } => Some((
+                format!("candle{}", self.interval_to_exchange(*interval)?),
+                symbol.clone(),
+            )),
+            Stream::Trades { symbol } => Some(("trade".to_string(), symbol.clone())),
+            Stream::Funding { symbol } | Stream::MarkPrice { symbol } => {
+                Some(("ticker".to_string(), symbol.clone()))
+            }
+            Stream::OrderBook { symbol, depth } => {
+                // Fixed-depth snapshot channels; anything deeper uses the incremental book
+                let channel = match depth {
+                    1 => "books1",
+                    2..=5 => "books5",
+                    6..=15 => "books15",
+                    _ => "books",
+                };
+                Some((channel.to_string(), symbol.clone()))
+            }
+            Stream::AggTrades { .. }
+            | Stream::BookTicker { .. }
+            | Stream::Ticker { .. }
+            | Stream::AllMiniTickers
+            | Stream::OpenInterest { .. }
+            | Stream::Liquidations { .. } => None,
+        }
+    }
+
+    fn format_request(&self, op: &str, stream: &Stream) -> String {
+        match self.channel(stream) {
+            Some((channel, inst_id)) => format!(
+                r#"{{"op":"{}","args":[{{"instType":"{}","channel":"{}","instId":"{}"}}]}}"#,
+                op, self.inst_type, channel, inst_id
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// Parses a candle push, keeping the row with the latest start time.
+    /// Row: [ts, open, high, low, close, baseVol, quoteVol, usdtVol].
+    fn parse_candle(&self, arg: &BitgetArg, data: &serde_json::Value) -> Option<MarketData> {
+        let interval = self.interval_from_exchange(arg.channel.strip_prefix("candle")?)?;
+        let candle = data
+            .as_array()?
+            .iter()
+            .filter_map(|row| {
+                let values = row.as_array()?;
+                if values.len() < 6 {
+                    return None;
+                }
+                Some(Candle::new(
+                    parse_u64(&values[0])?,
+                    parse_f64(&values[1])?,
+                    parse_f64(&values[2])?,
+                    parse_f64(&values[3])?,
+                    parse_f64(&values[4])?,
+                    parse_f64(&values[5])?,
+                ))
+            })
+            .max_by_key(Candle::get_timestamp)?;
+
+        // Bitget does not flag closed candles; a new start timestamp means the previous one closed
+        Some(MarketData::candle(arg.inst_id.clone(), interval, candle, false))
+    }
+
+    /// Parses a trade push. Normalization: Bitget's "side" is the taker side.
+    fn parse_trade(&self, arg: &BitgetArg, data: &serde_json::Value) -> Option<MarketData> {
+        let entry = data.as_array()?.first()?;
+        let event: BitgetTrade = serde_json::from_value(entry.clone()).ok()?;
+        let side = match event.side.as_str() {
+            "buy" => TradeSide::Buy,
+            "sell" => TradeSide::Sell,
+            _ => return None,
+        };
+
+        let trade = Trade::new(
+            event.ts,
+            arg.inst_id.clone(),
+            event.price,
+            event.size,
+            event.trade_id,
+            side,
+        );
+        Some(MarketData::Trade(trade))
+    }
+
+    /// Parses a futures ticker push into MarketData::Funding followed by
+    /// MarketData::MarkPrice. Spot tickers carry neither.
+    fn parse_ticker(&self, arg: &BitgetArg, data: &serde_json::Value) -> Vec<MarketData> {
+        let Some(entry) = data.as_array().and_then(|entries| entries.first()) else {
+            return Vec::new();
+        };
+        let Ok(event) = serde_json::from_value::<BitgetTicker>(entry.clone()) else {
+            return Vec::new();
+        };
+        let mark_price = event.mark_price.as_ref().and_then(parse_f64);
+        let mut parsed = Vec::with_capacity(2);
+
+        if let Some(rate) = event.funding_rate.as_ref().and_then(parse_f64) {
+            let mut funding = FundingRate::new(event.ts, arg.inst_id.clone(), rate);
+            if let Some(next) = event.next_funding_time.as_ref().and_then(parse_u64) {
+                funding = funding.with_next_funding_time(next);
+            }
+            if let Some(mark) = mark_price {
+                funding = funding.with_mark_price(mark);
+            }
+            parsed.push(MarketData::Funding(funding));
+        }
+        if let Some(mark_price) = mark_price {
+            let mut mark = MarkPrice::new(event.ts, arg.inst_id.clone(), mark_price);
+            if let Some(index) = event.index_price.as_ref().and_then(parse_f64) {
+                mark = mark.with_index_price(index);
+            }
+            parsed.push(MarketData::MarkPrice(mark));
+        }
+        parsed
+    }
+
+    /// Parses a books/booksN push into MarketData::OrderBook. "books" frames carry a
+    /// checksum of the top 25 levels (`ChecksumFormat::Okx`) for `verify_checksum`.
+    fn parse_book(
+        &self,
+        action: Option<&str>,
+        arg: &BitgetArg,
+        data: &serde_json::Value,
+    ) -> Option<MarketData> {
+        let entry = data.as_array()?.first()?;
+        let event: BitgetBook = serde_json::from_value(entry.clone()).ok()?;
+        let bids = parse_levels(&event.bids);
+        let asks = parse_levels(&event.asks);
+
+        // Fixed-depth channels are always snapshots; "books" sends snapshot then "update"
+        let mut update = if action == Some("update") {
+            OrderBookUpdate::delta(event.ts, arg.inst_id.clone(), bids, asks)
+        } else {
+            OrderBookUpdate::snapshot(event.ts, arg.inst_id.clone(), bids, asks)
+        };
+        if let Some(seq) = event.seq {
+            update = update.with_sequence(seq);
+        }
+        if arg.channel == "books"
+            && let Some(checksum) = event.checksum
+        {
+            update = update.with_checksum(checksum as i32 as u32);
+        }
+        Some(MarketData::OrderBook(update))
+    }
+}
+
+impl Default for BitgetParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageParser for BitgetParser {
+    fn endpoint(&self) -> &str {
+        BITGET_WSS_PUBLIC_ENDPOINT
+    }
+
+    fn name(&self) -> &'static str {
+        "Bitget"
+    }
+
+    fn interval_to_exchange(&self, interval: Timeframe) -> Option<String> {
+        bitget_interval(interval).map(str::to_string)
+    }
+
+    fn interval_from_exchange(&self, value: &str) -> Option<Timeframe> {
+        timeframe_from_bitget(value)
+    }
+
+    fn ping_message(&self) -> Option<(String, Duration)> {
+        Some(("ping".to_string(), PING_INTERVAL))
+    }
+
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        self.format_request("subscribe", stream)
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        self.format_request("unsubscribe", stream)
+    }
+
+    /// The first item of `parse_messages`: funding rather than mark price for
+    /// futures tickers.
+    fn parse_message(&self, msg: &str) -> Option<MarketData> {
+        self.parse_messages(msg).into_iter().next()
+    }
+
+    fn parse_messages(&self, msg: &str) -> Vec<MarketData> {
+        // Control messages: "pong" (plain string) and {"event":"subscribe"|"error",...} acks
+        if msg == "pong" || msg.contains(r#""event":"#) {
+            return Vec::new();
+        }
+
+        let Ok(envelope) = serde_json::from_str::<BitgetEnvelope>(msg) else {
+            return Vec::new();
+        };
+        let channel = envelope.arg.channel.as_str();
+
+        if channel.starts_with("candle") {
+            return self.parse_candle(&envelope.arg, &envelope.data).into_iter().collect();
+        }
+
+        let parsed = match channel {
+            "trade" => self.parse_trade(&envelope.arg, &envelope.data),
+            "ticker" => return self.parse_ticker(&envelope.arg, &envelope.data),
+            "books" | "books1" | "books5" | "books15" => {
+                self.parse_book(envelope.action.as_deref(), &envelope.arg, &envelope.data)
+            }
+            _ => None,
+        };
+        parsed.into_iter().collect()
+    }
+}
+
+/// Maps Timeframe to Bitget's candle channel suffix. Bitget has no 3m, 2h or 8h
+/// candle channels.
+fn bitget_interval(interval: Timeframe) -> Option<&'static str> {
+    match interval {
+        Timeframe::M1 => Some("1m"),
+        Timeframe::M5 => Some("5m"),
+        Timeframe::M15 => Some("15m"),
+        Timeframe::M30 => Some("30m"),
+        Timeframe::H1 => Some("1H"),
+        Timeframe::H4 => Some("4H"),
+        Timeframe::H6 => Some("6H"),
+        Timeframe::H12 => Some("12H"),
+        Timeframe::D1 => Some("1D"),
+        Timeframe::D3 => Some("3D"),
+        Timeframe::W1 => Some("1W"),
+        Timeframe::MN1 => Some("1M"),
+        Timeframe::M3 | Timeframe::H2 | Timeframe::H8 | Timeframe::Custom(_) => None,
+    }
+}
+
+fn timeframe_from_bitget(value: &str) -> Option<Timeframe> {
+    match value {
+        "1m" => Some(Timeframe::M1),
+        "5m" => Some(Timeframe::M5),
+        "15m" => Some(Timeframe::M15),
+        "30m" => Some(Timeframe::M30),
+        "1H" => Some(Timeframe::H1),
+        "4H" => Some(Timeframe::H4),
+        "6H" => Some(Timeframe::H6),
+        "12H" => Some(Timeframe::H12),
+        "1D" => Some(Timeframe::D1),
+        "3D" => Some(Timeframe::D3),
+        "1W" => Some(Timeframe::W1),
+        "1M" => Some(Timeframe::MN1),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BitgetEnvelope {
+    action: Option<String>,
+    arg: BitgetArg,
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitgetArg {
+    channel: String,
+    #[serde(rename = "instId")]
+    inst_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitgetTrade {
+    #[serde(deserialize_with = "de_u64")]
+    ts: u64,
+    #[serde(deserialize_with = "de_f64")]
+    price: f64,
+    #[serde(deserialize_with = "de_f64")]
+    size: f64,
+    side: String,
+    #[serde(rename = "tradeId")]
+    trade_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitgetTicker {
+    #[serde(deserialize_with = "de_u64")]
+    ts: u64,
+    #[serde(rename = "fundingRate")]
+    funding_rate: Option<serde_json::Value>,
+    #[serde(rename = "nextFundingTime")]
+    next_funding_time: Option<serde_json::Value>,
+    #[serde(rename = "markPrice")]
+    mark_price: Option<serde_json::Value>,
+    #[serde(rename = "indexPrice")]
+    index_price: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitgetBook {
+    #[serde(deserialize_with = "de_u64")]
+    ts: u64,
+    bids: Vec<Vec<serde_json::Value>>,
+    asks: Vec<Vec<serde_json::Value>>,
+    seq: Option<u64>,
+    // Signed CRC32 in OKX's format; 0 on the fixed-depth channels
+    checksum: Option<i64>,
+}
+
+pub type BitgetClient = WebSocketClient<BitgetParser>;
+
+pub fn new_bitget_client() -> BitgetClient {
+    WebSocketClient::new(BitgetParser::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::order_book::{ChecksumFormat, LocalOrderBook};
+
+    #[test]
+    fn test_format_subscribe_candles() {
+        let parser = BitgetParser::new();
+        let msg = parser.format_subscribe(&Stream::candles("BTCUSDT", Timeframe::M1));
+        assert_eq!(
+            msg,
+            r#"{"op":"subscribe","args":[{"instType":"USDT-FUTURES","channel":"candle1m","instId":"BTCUSDT"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_format_subscribe_streams() {
+        let parser = BitgetParser::new();
+        assert!(parser.format_subscribe(&Stream::candles("BTCUSDT", Timeframe::H4)).contains(r#""channel":"candle4H""#));
+        assert!(parser.format_subscribe(&Stream::candles("BTCUSDT", Timeframe::D3)).contains(r#""channel":"candle3D""#));
+        assert!(parser.format_subscribe(&Stream::candles("BTCUSDT", Timeframe::H8)).is_empty());
+        assert!(parser.format_subscribe(&Stream::trades("BTCUSDT")).contains(r#""channel":"trade""#));
+        assert!(parser
+            .format_subscribe(&Stream::Funding { symbol: "BTCUSDT".to_string() })
+            .contains(r#""channel":"ticker""#));
+        assert!(parser.format_subscribe(&Stream::order_book("BTCUSDT", 5)).contains(r#""channel":"books5""#));
+        assert!(parser.format_subscribe(&Stream::order_book("BTCUSDT", 100)).contains(r#""channel":"books""#));
+        assert!(parser
+            .format_subscribe(&Stream::Liquidations { symbol: "BTCUSDT".to_string() })
+            .is_empty());
+    }
+
+    #[test]
+    fn test_format_unsubscribe_spot() {
+        let parser = BitgetParser::with_inst_type("SPOT");
+        let msg = parser.format_unsubscribe(&Stream::trades("ETHUSDT"));
+        assert!(msg.contains(r#""op":"unsubscribe""#));
+        assert!(msg.contains(r#""instType":"SPOT""#));
+    }
+
+    #[test]
+    fn test_parse_candle_message() {
+        let parser = BitgetParser::new();
+        let msg = r#"{"action":"snapshot","arg":{"instType":"USDT-FUTURES","channel":"candle1m","instId":"BTCUSDT"},"data":[["1695685500000","27000","27000.5","27000","27000.5","0.057","1539.0155","1539.0155"]],"ts":1695715462250}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Candle { symbol, interval, data, is_closed, .. }) => {
+                assert_eq!(symbol, "BTCUSDT");
+                assert_eq!(interval, Timeframe::M1);
+                assert_eq!(data.get_timestamp(), 1695685500000);
+                assert_eq!(data.get_open(), 27000.0);
+                assert_eq!(data.get_high(), 27000.5);
+                assert_eq!(data.get_low(), 27000.0);
+                assert_eq!(data.get_close(), 27000.5);
+                assert_eq!(data.get_volume(), 0.057);
+                assert!(!is_closed);
+            }
+            other => panic!("Expected MarketData::Candle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_candle_snapshot_keeps_latest_row() {
+        let parser = BitgetParser::new();
+        let rows = [
+            r#"["1695685380000","26990","27000","26985","26995","0.120","3239.4","3239.4"]"#,
+            r#"["1695685440000","26995","27000","26990","27000","0.080","2159.6","2159.6"]"#,
+            r#"["1695685500000","27000","27000.5","27000","27000.5","0.057","1539.0155","1539.0155"]"#,
+        ];
+        let frame = |rows: &[&str]| {
+            format!(
+                r#"{{"action":"snapshot","arg":{{"instType":"USDT-FUTURES","channel":"candle1m","instId":"BTCUSDT"}},"data":[{}],"ts":1695715462250}}"#,
+                rows.join(",")
+            )
+        };
+        let oldest_first = frame(&rows);
+        let newest_first = frame(&[rows[2], rows[1], rows[0]]);
+
+        for msg in [oldest_first, newest_first] {
+            let parsed = parser.parse_message(&msg).unwrap();
+            let candle = parsed.as_candle().unwrap().2;
+            assert_eq!(candle.get_timestamp(), 1695685500000);
+            assert_eq!(candle.get_close(), 27000.5);
+        }
+    }
+
+    #[test]
+    fn test_parse_trade_message() {
+        let parser = BitgetParser::new();
+        let msg = r#"{"action":"snapshot","arg":{"instType":"USDT-FUTURES","channel":"trade","instId":"BTCUSDT"},"data":[{"ts":"1695716760565","price":"27000.5","size":"0.001","side":"buy","tradeId":"1111111111"},{"ts":"1695716759514","price":"27000.0","size":"0.001","side":"sell","tradeId":"1111111110"}],"ts":1695716761589}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.symbol, "BTCUSDT");
+                assert_eq!(trade.timestamp, 1695716760565);
+                assert_eq!(trade.price, 27000.5);
+                assert_eq!(trade.quantity, 0.001);
+                assert_eq!(trade.side, TradeSide::Buy);
+                assert_eq!(trade.trade_id, "1111111111");
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ticker_funding() {
+        let parser = BitgetParser::new();
+        let msg = r#"{"action":"snapshot","arg":{"instType":"USDT-FUTURES","channel":"ticker","instId":"BTCUSDT"},"data":[{"instId":"BTCUSDT","lastPr":"27000.5","bidPr":"27000","askPr":"27000.5","bidSz":"2.71","askSz":"8.76","open24h":"27000.5","high24h":"30668.5","low24h":"26999.0","change24h":"-0.00002","fundingRate":"0.000010","nextFundingTime":"1695722400000","markPrice":"27000.0","indexPrice":"25702.4","holdingAmount":"929.502","baseVolume":"368.900","quoteVolume":"10152429.961","openUtc":"27000.5","symbolType":1,"symbol":"BTCUSDT","deliveryPrice":"0","ts":"1695715383021"}],"ts":1695715383039}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Funding(funding)) => {
+                assert_eq!(funding.symbol, "BTCUSDT");
+                assert_eq!(funding.timestamp, 1695715383021);
+                assert_eq!(funding.rate, 0.00001);
+                assert_eq!(funding.next_funding_time, Some(1695722400000));
+                assert_eq!(funding.mark_price, Some(27000.0));
+            }
+            other => panic!("Expected MarketData::Funding, got {:?}", other),
+        }
+
+        let parsed = parser.parse_messages(msg);
+        assert_eq!(parsed.len(), 2);
+        let mark = parsed[1].as_mark_price().unwrap();
+        assert_eq!(mark.symbol, "BTCUSDT");
+        assert_eq!(mark.mark_price, 27000.0);
+        assert_eq!(mark.index_price, Some(25702.4));
+    }
+
+    #[test]
+    fn test_parse_books_snapshot_and_update() {
+        let parser = BitgetParser::new();
+        let snapshot = r#"{"action":"snapshot","arg":{"instType":"USDT-FUTURES","channel":"books5","instId":"BTCUSDT"},"data":[{"asks":[["27000.5","8.760"],["27001.0","0.400"]],"bids":[["27000.0","2.710"],["26999.5","1.460"]],"checksum":0,"seq":123,"ts":"1695716059516"}],"ts":1695716059516}"#;
+
+        match parser.parse_message(snapshot) {
+            Some(MarketData::OrderBook(book)) => {
+                assert!(book.is_snapshot);
+                assert_eq!(book.symbol, "BTCUSDT");
+                assert_eq!(book.bids.len(), 2);
+                assert_eq!(book.asks[0].price, 27000.5);
+                assert_eq!(book.asks[0].quantity, 8.76);
+                assert_eq!(book.sequence, Some(123));
+                assert_eq!(book.checksum, None);
+            }
+            other => panic!("Expected MarketData::OrderBook, got {:?}", other),
+        }
+
+        let update = r#"{"action":"update","arg":{"instType":"USDT-FUTURES","channel":"books","instId":"BTCUSDT"},"data":[{"asks":[["27000.5","0"]],"bids":[],"checksum":0,"seq":124,"ts":"1695716059600"}],"ts":1695716059600}"#;
+        match parser.parse_message(update) {
+            Some(MarketData::OrderBook(book)) => {
+                assert!(!book.is_snapshot);
+                assert_eq!(book.asks[0].quantity, 0.0);
+            }
+            other => panic!("Expected MarketData::OrderBook, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_books_checksum_verifies() {
+        // OKX's checksum guide example book; Bitget uses the same format
+        let parser = BitgetParser::new();
+        let snapshot = r#"{"action":"snapshot","arg":{"instType":"USDT-FUTURES","channel":"books","instId":"BTCUSDT"},"data":[{"asks":[["3366.8","9"],["3368","8"]],"bids":[["3366.1","7"],["3366","6"]],"checksum":-1881014294,"seq":1,"ts":"1695716059516"}],"ts":1695716059516}"#;
+
+        let Some(MarketData::OrderBook(update)) = parser.parse_message(snapshot) else {
+            panic!("Expected MarketData::OrderBook");
+        };
+        assert_eq!(update.checksum, Some(-1_881_014_294_i32 as u32));
+
+        let mut book = LocalOrderBook::new();
+        book.apply(&update);
+        assert_eq!(book.verify_checksum(update.checksum.unwrap(), ChecksumFormat::Okx), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_control_messages() {
+        let parser = BitgetParser::new();
+        assert!(parser.parse_message("pong").is_none());
+        assert!(parser
+            .parse_message(r#"{"event":"subscribe","arg":{"instType":"USDT-FUTURES","channel":"candle1m","instId":"BTCUSDT"}}"#)
+            .is_none());
+        assert!(parser
+            .parse_message(r#"{"event":"error","code":30001,"msg":"instType:USDT-FUTURES,channel:candle1m,instId:FOO doesn't exist"}"#)
+            .is_none());
+    }
+
+    #[test]
+    fn test_ping_message() {
+        let parser = BitgetParser::new();
+        assert_eq!(parser.ping_message(), Some(("ping".to_string(), PING_INTERVAL)));
+    }
+}
@@ -0,0 +1,194 @@
+//! Deserialization helpers shared by exchange providers.
+
+// Exchanges are inconsistent about numeric encoding: Binance sends prices as
+// strings ("50000.00") but timestamps as numbers, KuCoin sends nanosecond
+// timestamps as strings, etc. These visitors accept either form.
+
+use crate::market::market_data::PriceLevel;
+use crate::market::timestamp;
+
+/// Deserializes an f64 from either a JSON number or a numeric string.
+pub(crate) fn de_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct F64Visitor;
+
+    impl<'de> serde::de::Visitor<'de> for F64Visitor {
+        type Value = f64;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a string or number representing a float")
+        }
+
+        fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            value.parse::<f64>().map_err(E::custom)
+        }
+
+        fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            value.parse::<f64>().map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(F64Visitor)
+}
+
+/// A float from either a JSON number or a numeric string that, with the
+/// `decimal` feature, also keeps the string's exact value.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExactF64 {
+    pub(crate) value: f64,
+    /// None when the exchange sent a JSON number
+    #[cfg(feature = "decimal")]
+    pub(crate) exact: Option<crate::decimal::Decimal>,
+}
+
+impl ExactF64 {
+    fn from_str<E: serde::de::Error>(value: &str) -> Result<Self, E> {
+        Ok(Self {
+            value: value.parse::<f64>().map_err(E::custom)?,
+            #[cfg(feature = "decimal")]
+            exact: crate::decimal::Decimal::from_str_exact(value).ok(),
+        })
+    }
+
+    fn from_number(value: f64) -> Self {
+        Self {
+            value,
+            #[cfg(feature = "decimal")]
+            exact: None,
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ExactF64 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ExactF64Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for ExactF64Visitor {
+            type Value = ExactF64;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a string or number representing a float")
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
+                Ok(ExactF64::from_number(value))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E> {
+                Ok(ExactF64::from_number(value as f64))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+                Ok(ExactF64::from_number(value as f64))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                ExactF64::from_str(value)
+            }
+        }
+
+        deserializer.deserialize_any(ExactF64Visitor)
+    }
+}
+
+/// Deserializes a u64 from either a JSON number or a numeric string.
+pub(crate) fn de_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct U64Visitor;
+
+    impl<'de> serde::de::Visitor<'de> for U64Visitor {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a string or number representing an unsigned integer")
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            u64::try_from(value).map_err(E::custom)
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            value.parse::<u64>().map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(U64Visitor)
+}
+
+/// Parses a numeric string field (for array-encoded payloads that skip serde structs).
+pub(crate) fn parse_f64(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_f64(),
+        _ => None,
+    }
+}
+
+/// Parses [["price","size"], ...] level arrays, skipping malformed entries.
+pub(crate) fn parse_levels(levels: &[Vec<serde_json::Value>]) -> Vec<PriceLevel> {
+    levels
+        .iter()
+        .filter_map(|level| {
+            let price = parse_f64(level.first()?)?;
+            let quantity = parse_f64(level.get(1)?)?;
+            Some(PriceLevel::new(price, quantity))
+        })
+        .collect()
+}
+
+/// Parses an unsigned integer that may be encoded as a string.
+pub(crate) fn parse_u64(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_u64(),
+        _ => None,
+    }
+}
+
+/// Parses a timestamp in any unit, or an RFC 3339 string, to ms. See
+/// `market::timestamp::parse_timestamp`.
+pub(crate) fn parse_timestamp_ms(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::String(s) => timestamp::parse_timestamp(s),
+        serde_json::Value::Number(n) => match n.as_u64() {
+            Some(integer) => Some(timestamp::auto_to_ms(integer)),
+            None => timestamp::parse_timestamp(&n.to_string()),
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,550 @@
+//! Deribit exchange implementation (JSON-RPC 2.0 over WebSocket).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Deserialize;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::{
+    FundingRate, MarkPrice, MarketData, OpenInterest, OrderBookUpdate, PriceLevel, Trade, TradeSide,
+};
+use crate::market::message_parser::MessageParser;
+use crate::market::providers::common::parse_f64;
+use crate::market::streams::Stream;
+use crate::market::websocket_client::WebSocketClient;
+
+pub const DERIBIT_WSS_ENDPOINT: &str = "wss://www.deribit.com/ws/api/v2";
+pub const DERIBIT_TESTNET_WSS_ENDPOINT: &str = "wss://test.deribit.com/ws/api/v2";
+
+// Public (unauthenticated) connections can't use the "raw" interval for books
+// and tickers; 100ms is the fastest aggregated interval available to them.
+const PUBLIC_INTERVAL: &str = "100ms";
+
+// Everything is JSON-RPC 2.0. Requests are {"jsonrpc","id","method","params"};
+// responses echo the id with "result" or "error" and are control messages here.
+// Data arrives as notifications: {"method":"subscription","params":{"channel","data"}}.
+//
+// Instrument names ("BTC-PERPETUAL", "ETH-27DEC24-3000-C") are case-sensitive
+// and passed through verbatim. Funding, mark price and open interest ride on the
+// ticker channel, so all three map to "ticker.{instrument}.100ms". Each ticker
+// notification yields MarketData::Funding (perpetuals), MarketData::MarkPrice and
+// MarketData::OpenInterest, whichever of those streams was subscribed; filter on
+// the variant.
+
+/// Deribit-specific message parser.
+/// Implements MessageParser to convert Deribit JSON-RPC notifications -> normalized MarketData.
+#[derive(Debug)]
+pub struct DeribitParser {
+    endpoint: &'static str,
+    request_id: AtomicU64,
+}
+
+impl DeribitParser {
+    pub fn new() -> Self {
+        Self {
+            endpoint: DERIBIT_WSS_ENDPOINT,
+            request_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Parser for Deribit's testnet (test.deribit.com).
+    pub fn testnet() -> Self {
+        Self {
+            endpoint: DERIBIT_TESTNET_WSS_ENDPOINT,
+            request_id: AtomicU64::new(1),
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        self.request_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Returns the channel name for a stream, or None if Deribit has no equivalent.
+    fn channel(&self, stream: &Stream) -> Option<String> {
+        match stream {
+            Stream::Candles { symbol, interval } => Some(format!(
+                "chart.trades.{}.{}",
+                symbol,
+                self.interval_to_exchange(*interval)?
+            )),
+            Stream::Trades { symbol } => Some(format!("trades.{}.raw", symbol)),
+            Stream::Funding { symbol } | Stream::MarkPrice { symbol } | Stream::OpenInterest { symbol } => {
+                Some(format!("ticker.{}.{}", symbol, PUBLIC_INTERVAL))
+            }
+            // Incremental book (snapshot first, then changes); depth isn't selectable here
+            Stream::OrderBook { symbol, .. } => Some(format!("book.{}.{}", symbol, PUBLIC_INTERVAL)),
+            Stream::AggTrades { .. }
+            | Stream::BookTicker { .. }
+            | Stream::Ticker { .. }
+            | Stream::AllMiniTickers
+            | Stream::Liquidations { .. } => None,
+        }
+    }
+
+    fn format_request(&self, method: &str, stream: &Stream) -> String {
+        let Some(channel) = self.channel(stream) else {
+            return String::new();
+        };
+
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id(),
+            "method": method,
+            "params": { "channels": [channel] },
+        });
+        request.to_string()
+    }
+
+    /// Parses a chart.trades.{instrument}.{resolution} notification.
+    fn parse_candle(&self, channel: &str, data: serde_json::Value) -> Option<MarketData> {
+        // Instrument names contain dashes but never dots
+        let rest = channel.strip_prefix("chart.trades.")?;
+        let (symbol, resolution) = rest.rsplit_once('.')?;
+        let interval = self.interval_from_exchange(resolution)?;
+        let event: DeribitCandle = serde_json::from_value(data).ok()?;
+
+        let candle = Candle::new(
+            event.tick,
+            event.open,
+            event.high,
+            event.low,
+            event.close,
+            event.volume,
+        );
+
+        // Deribit pushes the forming candle without a closed flag
+        Some(MarketData::candle(symbol, interval, candle, false))
+    }
+
+    /// Parses a trades.{instrument}.raw notification. "direction" is the taker side.
+    /// Only the first trade of a batch is emitted.
+    fn parse_trade(&self, data: serde_json::Value) -> Option<MarketData> {
+        let entry = data.as_array()?.first()?.clone();
+        let event: DeribitTrade = serde_json::from_value(entry).ok()?;
+        let side = match event.direction.as_str() {
+            "buy" => TradeSide::Buy,
+            "sell" => TradeSide::Sell,
+            _ => return None,
+        };
+
+        let trade = Trade::new(
+            event.timestamp,
+            event.instrument_name,
+            event.price,
+            event.amount,
+            event.trade_id,
+            side,
+        );
+        Some(MarketData::Trade(trade))
+    }
+
+    /// Parses a ticker notification into MarketData::Funding (perpetuals only),
+    /// MarketData::MarkPrice and MarketData::OpenInterest (futures; in USD for
+    /// inverse contracts), in that order.
+    fn parse_ticker(&self, data: serde_json::Value) -> Vec<MarketData> {
+        let Ok(event) = serde_json::from_value::<DeribitTicker>(data) else {
+            return Vec::new();
+        };
+        let mut parsed = Vec::with_capacity(3);
+        // Futures and options tickers have no funding
+        if let Some(rate) = event.current_funding {
+            let mut funding = FundingRate::new(event.timestamp, event.instrument_name.clone(), rate);
+            if let Some(mark) = event.mark_price {
+                funding = funding.with_mark_price(mark);
+            }
+            parsed.push(MarketData::Funding(funding));
+        }
+        if let Some(mark_price) = event.mark_price {
+            let mut mark = MarkPrice::new(event.timestamp, event.instrument_name.clone(), mark_price);
+            if let Some(index) = event.index_price {
+                mark = mark.with_index_price(index);
+            }
+            if let Some(settle) = event.estimated_delivery_price {
+                mark = mark.with_estimated_settle_price(settle);
+            }
+            parsed.push(MarketData::MarkPrice(mark));
+        }
+        if let Some(open_interest) = event.open_interest {
+            let open_interest = OpenInterest::new(event.timestamp, event.instrument_name, open_interest);
+            parsed.push(MarketData::OpenInterest(open_interest));
+        }
+        parsed
+    }
+
+    /// Parses a subscription notification; only ticker notifications yield more than one item.
+    fn parse_notification(&self, msg: &str) -> Vec<MarketData> {
+        let Ok(envelope) = serde_json::from_str::<DeribitEnvelope>(msg) else {
+            return Vec::new();
+        };
+
+        // Responses ({"id","result"} / {"id","error"}) and heartbeats are control messages
+        if envelope.method.as_deref() != Some("subscription") {
+            return Vec::new();
+        }
+        let Some(params) = envelope.params else {
+            return Vec::new();
+        };
+
+        let channel = params.channel.as_str();
+        if channel.starts_with("chart.trades.") {
+            return self.parse_candle(channel, params.data).into_iter().collect();
+        }
+
+        let parsed = match channel.split('.').next() {
+            Some("trades") => self.parse_trade(params.data),
+            Some("ticker") => return self.parse_ticker(params.data),
+            Some("book") => self.parse_book(params.data),
+            _ => None,
+        };
+        parsed.into_iter().collect()
+    }
+
+    /// Parses a book.{instrument}.{interval} notification.
+    fn parse_book(&self, data: serde_json::Value) -> Option<MarketData> {
+        let event: DeribitBook = serde_json::from_value(data).ok()?;
+        let bids = parse_levels(&event.bids);
+        let asks = parse_levels(&event.asks);
+
+        let update = if event.kind.as_deref() == Some("snapshot") {
+            OrderBookUpdate::snapshot(event.timestamp, event.instrument_name, bids, asks)
+        } else {
+            OrderBookUpdate::delta(event.timestamp, event.instrument_name, bids, asks)
+        };
+        Some(MarketData::OrderBook(update.with_sequence(event.change_id)))
+    }
+}
+
+impl Default for DeribitParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageParser for DeribitParser {
+    fn endpoint(&self) -> &str {
+        self.endpoint
+    }
+
+    fn name(&self) -> &'static str {
+        "Deribit"
+    }
+
+    fn interval_to_exchange(&self, interval: Timeframe) -> Option<String> {
+        deribit_resolution(interval).map(str::to_string)
+    }
+
+    fn interval_from_exchange(&self, value: &str) -> Option<Timeframe> {
+        timeframe_from_deribit(value)
+    }
+
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        self.format_request("public/subscribe", stream)
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        self.format_request("public/unsubscribe", stream)
+    }
+
+    /// The first item of `parse_messages`: funding rather than mark price or open
+    /// interest for perpetual tickers.
+    fn parse_message(&self, msg: &str) -> Option<MarketData> {
+        self.parse_notification(msg).into_iter().next()
+    }
+
+    fn parse_messages(&self, msg: &str) -> Vec<MarketData> {
+        self.parse_notification(msg)
+    }
+}
+
+/// Maps Timeframe to a chart.trades resolution. Deribit has no 4h, 8h, 3-day, weekly
+/// or monthly resolution.
+fn deribit_resolution(interval: Timeframe) -> Option<&'static str> {
+    match interval {
+        Timeframe::M1 => Some("1"),
+        Timeframe::M3 => Some("3"),
+        Timeframe::M5 => Some("5"),
+        Timeframe::M15 => Some("15"),
+        Timeframe::M30 => Some("30"),
+        Timeframe::H1 => Some("60"),
+        Timeframe::H2 => Some("120"),
+        Timeframe::H6 => Some("360"),
+        Timeframe::H12 => Some("720"),
+        Timeframe::D1 => Some("1D"),
+        Timeframe::H4 | Timeframe::H8 | Timeframe::D3 | Timeframe::W1 | Timeframe::MN1 | Timeframe::Custom(_) => None,
+    }
+}
+
+fn timeframe_from_deribit(value: &str) -> Option<Timeframe> {
+    match value {
+        "1" => Some(Timeframe::M1),
+        "3" => Some(Timeframe::M3),
+        "5" => Some(Timeframe::M5),
+        "15" => Some(Timeframe::M15),
+        "30" => Some(Timeframe::M30),
+        "60" => Some(Timeframe::H1),
+        "120" => Some(Timeframe::H2),
+        "360" => Some(Timeframe::H6),
+        "720" => Some(Timeframe::H12),
+        "1D" => Some(Timeframe::D1),
+        _ => None,
+    }
+}
+
+/// Parses book levels. Incremental books send ["new"|"change"|"delete", price, amount];
+/// grouped books send [price, amount]. Deletes become quantity 0.
+fn parse_levels(levels: &[Vec<serde_json::Value>]) -> Vec<PriceLevel> {
+    levels
+        .iter()
+        .filter_map(|level| match level.as_slice() {
+            [action, price, amount] => {
+                let price = parse_f64(price)?;
+                let quantity = if action.as_str() == Some("delete") {
+                    0.0
+                } else {
+                    parse_f64(amount)?
+                };
+                Some(PriceLevel::new(price, quantity))
+            }
+            [price, amount] => Some(PriceLevel::new(parse_f64(price)?, parse_f64(amount)?)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct DeribitEnvelope {
+    method: Option<String>,
+    params: Option<DeribitNotification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeribitNotification {
+    channel: String,
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeribitCandle {
+    tick: u64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeribitTrade {
+    timestamp: u64,
+    instrument_name: String,
+    price: f64,
+    amount: f64,
+    direction: String,
+    trade_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeribitTicker {
+    timestamp: u64,
+    instrument_name: String,
+    current_funding: Option<f64>,
+    mark_price: Option<f64>,
+    index_price: Option<f64>,
+    estimated_delivery_price: Option<f64>,
+    open_interest: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeribitBook {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    timestamp: u64,
+    instrument_name: String,
+    change_id: u64,
+    #[serde(default)]
+    bids: Vec<Vec<serde_json::Value>>,
+    #[serde(default)]
+    asks: Vec<Vec<serde_json::Value>>,
+}
+
+pub type DeribitClient = WebSocketClient<DeribitParser>;
+
+pub fn new_deribit_client() -> DeribitClient {
+    WebSocketClient::new(DeribitParser::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(msg: &str) -> serde_json::Value {
+        serde_json::from_str(msg).unwrap()
+    }
+
+    #[test]
+    fn test_format_subscribe_trades() {
+        let parser = DeribitParser::new();
+        let msg = request(&parser.format_subscribe(&Stream::trades("BTC-PERPETUAL")));
+        assert_eq!(msg["jsonrpc"], "2.0");
+        assert_eq!(msg["method"], "public/subscribe");
+        assert_eq!(msg["params"]["channels"], serde_json::json!(["trades.BTC-PERPETUAL.raw"]));
+    }
+
+    #[test]
+    fn test_format_channels() {
+        let parser = DeribitParser::new();
+        let channel = |stream: Stream| request(&parser.format_subscribe(&stream))["params"]["channels"][0].clone();
+
+        assert_eq!(channel(Stream::candles("BTC-PERPETUAL", Timeframe::H1)), "chart.trades.BTC-PERPETUAL.60");
+        assert_eq!(channel(Stream::candles("ETH-PERPETUAL", Timeframe::D1)), "chart.trades.ETH-PERPETUAL.1D");
+        assert_eq!(channel(Stream::Funding { symbol: "BTC-PERPETUAL".to_string() }), "ticker.BTC-PERPETUAL.100ms");
+        assert_eq!(channel(Stream::order_book("BTC-PERPETUAL", 20)), "book.BTC-PERPETUAL.100ms");
+        assert_eq!(channel(Stream::open_interest("BTC-PERPETUAL")), "ticker.BTC-PERPETUAL.100ms");
+
+        // No 4h resolution and no liquidation channel
+        assert!(parser.format_subscribe(&Stream::candles("BTC-PERPETUAL", Timeframe::H4)).is_empty());
+        assert!(parser
+            .format_subscribe(&Stream::Liquidations { symbol: "BTC-PERPETUAL".to_string() })
+            .is_empty());
+    }
+
+    #[test]
+    fn test_format_unsubscribe_and_request_ids() {
+        let parser = DeribitParser::new();
+        let first = request(&parser.format_subscribe(&Stream::trades("BTC-PERPETUAL")));
+        let second = request(&parser.format_unsubscribe(&Stream::trades("BTC-PERPETUAL")));
+        assert_eq!(second["method"], "public/unsubscribe");
+        assert_eq!(second["id"].as_u64().unwrap(), first["id"].as_u64().unwrap() + 1);
+    }
+
+    #[test]
+    fn test_parse_trade_notification() {
+        let parser = DeribitParser::new();
+        let msg = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"trades.BTC-PERPETUAL.raw","data":[{"trade_seq":30289442,"trade_id":"48079269","timestamp":1590484156350,"tick_direction":2,"price":8950.0,"mark_price":8948.9,"instrument_name":"BTC-PERPETUAL","index_price":8955.88,"direction":"sell","amount":10.0}]}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.symbol, "BTC-PERPETUAL");
+                assert_eq!(trade.timestamp, 1590484156350);
+                assert_eq!(trade.price, 8950.0);
+                assert_eq!(trade.quantity, 10.0);
+                assert_eq!(trade.side, TradeSide::Sell);
+                assert_eq!(trade.trade_id, "48079269");
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_candle_notification() {
+        let parser = DeribitParser::new();
+        let msg = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"chart.trades.BTC-PERPETUAL.1","data":{"volume":0.05219351,"tick":1573645080000,"open":8869.79,"low":8788.25,"high":8870.31,"cost":460,"close":8791.25}}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Candle { symbol, interval, data, is_closed, .. }) => {
+                assert_eq!(symbol, "BTC-PERPETUAL");
+                assert_eq!(interval, Timeframe::M1);
+                assert_eq!(data.get_timestamp(), 1573645080000);
+                assert_eq!(data.get_open(), 8869.79);
+                assert_eq!(data.get_high(), 8870.31);
+                assert_eq!(data.get_low(), 8788.25);
+                assert_eq!(data.get_close(), 8791.25);
+                assert_eq!(data.get_volume(), 0.05219351);
+                assert!(!is_closed);
+            }
+            other => panic!("Expected MarketData::Candle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ticker_funding() {
+        let parser = DeribitParser::new();
+        let msg = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"ticker.BTC-PERPETUAL.100ms","data":{"timestamp":1623060194301,"state":"open","open_interest":502097590,"mark_price":36225.21,"last_price":36230.0,"instrument_name":"BTC-PERPETUAL","index_price":36212.85,"funding_8h":0.00003069,"current_funding":0.00001}}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Funding(funding)) => {
+                assert_eq!(funding.symbol, "BTC-PERPETUAL");
+                assert_eq!(funding.timestamp, 1623060194301);
+                assert_eq!(funding.rate, 0.00001);
+                assert_eq!(funding.mark_price, Some(36225.21));
+            }
+            other => panic!("Expected MarketData::Funding, got {:?}", other),
+        }
+
+        let mark = parser.parse_messages(msg).into_iter().find(|data| data.is_mark_price()).unwrap();
+        let mark = mark.as_mark_price().unwrap();
+        assert_eq!(mark.mark_price, 36225.21);
+        assert_eq!(mark.index_price, Some(36212.85));
+        assert!(mark.estimated_settle_price.is_none());
+
+        // Dated futures have no funding
+        let future = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"ticker.BTC-27DEC24.100ms","data":{"timestamp":1623060194301,"mark_price":36225.21,"estimated_delivery_price":36210.5,"instrument_name":"BTC-27DEC24"}}}"#;
+        let parsed = parser.parse_messages(future);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].as_mark_price().unwrap().estimated_settle_price, Some(36210.5));
+    }
+
+    #[test]
+    fn test_parse_ticker_open_interest() {
+        let parser = DeribitParser::new();
+        let msg = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"ticker.BTC-PERPETUAL.100ms","data":{"timestamp":1623060194301,"state":"open","open_interest":502097590,"mark_price":36225.21,"instrument_name":"BTC-PERPETUAL","current_funding":0.00001}}}"#;
+
+        let parsed = parser.parse_messages(msg);
+        assert_eq!(parsed.len(), 3);
+        assert!(parsed[0].is_funding());
+        assert!(parsed[1].is_mark_price());
+        let open_interest = parsed[2].as_open_interest().unwrap();
+        assert_eq!(open_interest.symbol, "BTC-PERPETUAL");
+        assert_eq!(open_interest.timestamp, 1623060194301);
+        assert_eq!(open_interest.open_interest, 502097590.0);
+        assert!(open_interest.open_interest_value.is_none());
+
+        // Dated futures: open interest only
+        let future = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"ticker.BTC-27DEC24.100ms","data":{"timestamp":1623060194301,"open_interest":1200.5,"instrument_name":"BTC-27DEC24"}}}"#;
+        let parsed = parser.parse_messages(future);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].as_open_interest().unwrap().open_interest, 1200.5);
+    }
+
+    #[test]
+    fn test_parse_book_snapshot_and_change() {
+        let parser = DeribitParser::new();
+        let snapshot = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"book.BTC-PERPETUAL.100ms","data":{"type":"snapshot","timestamp":1554373962454,"instrument_name":"BTC-PERPETUAL","change_id":297217,"bids":[["new",5042.34,30],["new",5041.94,20]],"asks":[["new",5042.64,40]]}}}"#;
+
+        match parser.parse_message(snapshot) {
+            Some(MarketData::OrderBook(book)) => {
+                assert!(book.is_snapshot);
+                assert_eq!(book.symbol, "BTC-PERPETUAL");
+                assert_eq!(book.bids.len(), 2);
+                assert_eq!(book.asks[0].price, 5042.64);
+                assert_eq!(book.sequence, Some(297217));
+            }
+            other => panic!("Expected MarketData::OrderBook, got {:?}", other),
+        }
+
+        let change = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"book.BTC-PERPETUAL.100ms","data":{"type":"change","timestamp":1554373911330,"prev_change_id":297217,"instrument_name":"BTC-PERPETUAL","change_id":297218,"bids":[["delete",5041.94,0]],"asks":[["change",5042.64,31]]}}}"#;
+        match parser.parse_message(change) {
+            Some(MarketData::OrderBook(book)) => {
+                assert!(!book.is_snapshot);
+                assert_eq!(book.bids[0].quantity, 0.0);
+                assert_eq!(book.asks[0].quantity, 31.0);
+            }
+            other => panic!("Expected MarketData::OrderBook, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_rpc_responses_are_control() {
+        let parser = DeribitParser::new();
+        assert!(parser
+            .parse_message(r#"{"jsonrpc":"2.0","id":1,"result":["trades.BTC-PERPETUAL.raw"],"usIn":1,"usOut":2,"usDiff":1,"testnet":false}"#)
+            .is_none());
+        assert!(parser
+            .parse_message(r#"{"jsonrpc":"2.0","id":2,"error":{"message":"Invalid params","code":-32602}}"#)
+            .is_none());
+        assert!(parser
+            .parse_message(r#"{"jsonrpc":"2.0","method":"heartbeat","params":{"type":"test_request"}}"#)
+            .is_none());
+    }
+}
@@ -0,0 +1,454 @@
+//! Gate.io exchange implementation (spot WebSocket v4).
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::{MarketData, OrderBookUpdate, Trade, TradeSide};
+use crate::market::message_parser::MessageParser;
+use crate::market::providers::common::{de_f64, parse_levels, parse_timestamp_ms, parse_u64};
+use crate::market::streams::Stream;
+use crate::market::timestamp::{TimestampUnit, normalize_to_ms};
+use crate::market::websocket_client::WebSocketClient;
+
+pub const GATEIO_WSS_SPOT_ENDPOINT: &str = "wss://api.gateio.ws/ws/v4/";
+
+// Fastest update speed Gate.io offers for the incremental book on all pairs
+const ORDER_BOOK_UPDATE_SPEED: &str = "100ms";
+
+// Gate.io closes connections that send nothing; spot.ping keeps them open
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+
+// Gate.io wraps every push in {"time","channel","event","result"}. Only
+// event == "update" carries data; "subscribe"/"unsubscribe" acks and
+// spot.pong replies are control messages.
+//
+// Candlestick results don't echo the subscription: interval and pair are packed
+// into "n" ("1m_BTC_USDT"). Pairs contain underscores too, so split only on the
+// first one.
+
+/// Gate.io-specific message parser.
+/// Implements MessageParser to convert Gate.io JSON -> normalized MarketData.
+#[derive(Debug, Clone, Default)]
+pub struct GateioParser;
+
+impl GateioParser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Returns (channel, payload) for a stream, or None if Gate.io spot has no equivalent.
+    fn channel(&self, stream: &Stream) -> Option<(&'static str, Vec<String>)> {
+        match stream {
+            Stream::Candles { symbol, interval } => Some((
+                "spot.candlesticks",
+                vec![self.interval_to_exchange(*interval)?, symbol.clone()],
+            )),
+            Stream::Trades { symbol } => Some(("spot.trades", vec![symbol.clone()])),
+            Stream::OrderBook { symbol, .. } => Some((
+                "spot.order_book_update",
+                vec![symbol.clone(), ORDER_BOOK_UPDATE_SPEED.to_string()],
+            )),
+            // Spot-only: no funding, mark price, open interest or liquidations
+            Stream::Funding { .. }
+            | Stream::MarkPrice { .. }
+            | Stream::OpenInterest { .. }
+            | Stream::Liquidations { .. }
+            | Stream::AggTrades { .. }
+            | Stream::BookTicker { .. }
+            | Stream::Ticker { .. }
+            | Stream::AllMiniTickers => None,
+        }
+    }
+
+    fn format_request(&self, event: &str, stream: &Stream) -> String {
+        let Some((channel, payload)) = self.channel(stream) else {
+            return String::new();
+        };
+
+        let request = serde_json::json!({
+            "time": unix_time_secs(),
+            "channel": channel,
+            "event": event,
+            "payload": payload,
+        });
+        request.to_string()
+    }
+
+    /// Parses a spot.candlesticks result. "w" is true once the window has closed.
+    fn parse_candle(&self, result: serde_json::Value) -> Option<MarketData> {
+        let event: GateioCandle = serde_json::from_value(result).ok()?;
+        let (interval, symbol) = split_candle_name(&event.name)?;
+
+        let candle = Candle::new(
+            normalize_to_ms(parse_u64(&event.start)?, TimestampUnit::Seconds),
+            event.open,
+            event.high,
+            event.low,
+            event.close,
+            event.volume,
+        );
+
+        Some(MarketData::candle(symbol, interval, candle, event.window_closed.unwrap_or(false)))
+    }
+
+    /// Parses a spot.trades result. Normalization: Gate.io's "side" is the taker side.
+    fn parse_trade(&self, result: serde_json::Value) -> Option<MarketData> {
+        let event: GateioTrade = serde_json::from_value(result).ok()?;
+        let side = match event.side.as_str() {
+            "buy" => TradeSide::Buy,
+            "sell" => TradeSide::Sell,
+            _ => return None,
+        };
+
+        // create_time_ms is fractional ("1606292218213.4578"); fall back to seconds
+        let timestamp = event
+            .create_time_ms
+            .as_ref()
+            .and_then(parse_timestamp_ms)
+            .or_else(|| event.create_time.map(|secs| normalize_to_ms(secs, TimestampUnit::Seconds)))?;
+
+        let trade = Trade::new(
+            timestamp,
+            event.currency_pair,
+            event.price,
+            event.amount,
+            event.id.to_string(),
+            side,
+        );
+        Some(MarketData::Trade(trade))
+    }
+
+    /// Parses a spot.order_book_update result. Updates are deltas unless flagged "full".
+    fn parse_book(&self, result: serde_json::Value) -> Option<MarketData> {
+        let event: GateioBookUpdate = serde_json::from_value(result).ok()?;
+        let bids = parse_levels(&event.bids);
+        let asks = parse_levels(&event.asks);
+
+        let update = if event.full.unwrap_or(false) {
+            OrderBookUpdate::snapshot(event.timestamp, event.symbol, bids, asks)
+        } else {
+            OrderBookUpdate::delta(event.timestamp, event.symbol, bids, asks)
+        };
+        Some(MarketData::OrderBook(update.with_sequence_range(event.first_update_id, event.last_update_id)))
+    }
+}
+
+impl MessageParser for GateioParser {
+    fn endpoint(&self) -> &str {
+        GATEIO_WSS_SPOT_ENDPOINT
+    }
+
+    fn name(&self) -> &'static str {
+        "Gate.io"
+    }
+
+    fn interval_to_exchange(&self, interval: Timeframe) -> Option<String> {
+        gateio_interval(interval).map(str::to_string)
+    }
+
+    fn interval_from_exchange(&self, value: &str) -> Option<Timeframe> {
+        timeframe_from_gateio(value)
+    }
+
+    /// The payload is built once per connection, so its "time" goes stale; Gate.io
+    /// only uses it to echo back in spot.pong.
+    fn ping_message(&self) -> Option<(String, Duration)> {
+        let ping = serde_json::json!({
+            "time": unix_time_secs(),
+            "channel": "spot.ping",
+        });
+        Some((ping.to_string(), PING_INTERVAL))
+    }
+
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        self.format_request("subscribe", stream)
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        self.format_request("unsubscribe", stream)
+    }
+
+    fn parse_message(&self, msg: &str) -> Option<MarketData> {
+        let envelope: GateioEnvelope = serde_json::from_str(msg).ok()?;
+
+        // Subscription acks, errors and pongs
+        if envelope.event.as_deref() != Some("update") {
+            return None;
+        }
+
+        let result = envelope.result?;
+        match envelope.channel.as_str() {
+            "spot.candlesticks" => self.parse_candle(result),
+            "spot.trades" => self.parse_trade(result),
+            "spot.order_book_update" => self.parse_book(result),
+            _ => None,
+        }
+    }
+}
+
+/// Maps Timeframe to Gate.io's candlestick interval. Gate.io spot has no 3m, 2h,
+/// 6h, 12h or 3d candles.
+fn gateio_interval(interval: Timeframe) -> Option<&'static str> {
+    match interval {
+        Timeframe::M1 => Some("1m"),
+        Timeframe::M5 => Some("5m"),
+        Timeframe::M15 => Some("15m"),
+        Timeframe::M30 => Some("30m"),
+        Timeframe::H1 => Some("1h"),
+        Timeframe::H4 => Some("4h"),
+        Timeframe::H8 => Some("8h"),
+        Timeframe::D1 => Some("1d"),
+        Timeframe::W1 => Some("7d"),
+        Timeframe::MN1 => Some("30d"),
+        Timeframe::M3 | Timeframe::H2 | Timeframe::H6 | Timeframe::H12 | Timeframe::D3 | Timeframe::Custom(_) => None,
+    }
+}
+
+fn timeframe_from_gateio(value: &str) -> Option<Timeframe> {
+    match value {
+        "1m" => Some(Timeframe::M1),
+        "5m" => Some(Timeframe::M5),
+        "15m" => Some(Timeframe::M15),
+        "30m" => Some(Timeframe::M30),
+        "1h" => Some(Timeframe::H1),
+        "4h" => Some(Timeframe::H4),
+        "8h" => Some(Timeframe::H8),
+        "1d" => Some(Timeframe::D1),
+        "7d" => Some(Timeframe::W1),
+        "30d" => Some(Timeframe::MN1),
+        _ => None,
+    }
+}
+
+/// Splits "1m_BTC_USDT" into (Timeframe::M1, "BTC_USDT").
+fn split_candle_name(name: &str) -> Option<(Timeframe, &str)> {
+    let (interval, symbol) = name.split_once('_')?;
+    if symbol.is_empty() {
+        return None;
+    }
+    Some((timeframe_from_gateio(interval)?, symbol))
+}
+
+fn unix_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Deserialize)]
+struct GateioEnvelope {
+    channel: String,
+    event: Option<String>,
+    result: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GateioCandle {
+    #[serde(rename = "t")]
+    start: serde_json::Value,
+    #[serde(rename = "o", deserialize_with = "de_f64")]
+    open: f64,
+    #[serde(rename = "h", deserialize_with = "de_f64")]
+    high: f64,
+    #[serde(rename = "l", deserialize_with = "de_f64")]
+    low: f64,
+    #[serde(rename = "c", deserialize_with = "de_f64")]
+    close: f64,
+    /// Base currency volume ("v" is quote volume on Gate.io spot)
+    #[serde(rename = "a", deserialize_with = "de_f64")]
+    volume: f64,
+    #[serde(rename = "n")]
+    name: String,
+    #[serde(rename = "w")]
+    window_closed: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GateioTrade {
+    id: u64,
+    create_time: Option<u64>,
+    create_time_ms: Option<serde_json::Value>,
+    side: String,
+    currency_pair: String,
+    #[serde(deserialize_with = "de_f64")]
+    amount: f64,
+    #[serde(deserialize_with = "de_f64")]
+    price: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GateioBookUpdate {
+    #[serde(rename = "t")]
+    timestamp: u64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "U")]
+    first_update_id: u64,
+    #[serde(rename = "u")]
+    last_update_id: u64,
+    #[serde(rename = "b", default)]
+    bids: Vec<Vec<serde_json::Value>>,
+    #[serde(rename = "a", default)]
+    asks: Vec<Vec<serde_json::Value>>,
+    full: Option<bool>,
+}
+
+pub type GateioClient = WebSocketClient<GateioParser>;
+
+pub fn new_gateio_client() -> GateioClient {
+    WebSocketClient::new(GateioParser::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(msg: &str) -> serde_json::Value {
+        serde_json::from_str(msg).unwrap()
+    }
+
+    #[test]
+    fn test_format_subscribe_candles() {
+        let parser = GateioParser::new();
+        let msg = request(&parser.format_subscribe(&Stream::candles("BTC_USDT", Timeframe::M1)));
+        assert_eq!(msg["channel"], "spot.candlesticks");
+        assert_eq!(msg["event"], "subscribe");
+        assert_eq!(msg["payload"], serde_json::json!(["1m", "BTC_USDT"]));
+        assert!(msg["time"].as_u64().unwrap() > 0);
+
+        let monthly = request(&parser.format_subscribe(&Stream::candles("BTC_USDT", Timeframe::MN1)));
+        assert_eq!(monthly["payload"], serde_json::json!(["30d", "BTC_USDT"]));
+        // No 2h candles on Gate.io spot
+        assert!(parser.format_subscribe(&Stream::candles("BTC_USDT", Timeframe::H2)).is_empty());
+    }
+
+    #[test]
+    fn test_format_subscribe_streams() {
+        let parser = GateioParser::new();
+
+        let trades = request(&parser.format_subscribe(&Stream::trades("ETH_USDT")));
+        assert_eq!(trades["channel"], "spot.trades");
+        assert_eq!(trades["payload"], serde_json::json!(["ETH_USDT"]));
+
+        let book = request(&parser.format_unsubscribe(&Stream::order_book("BTC_USDT", 20)));
+        assert_eq!(book["channel"], "spot.order_book_update");
+        assert_eq!(book["event"], "unsubscribe");
+        assert_eq!(book["payload"], serde_json::json!(["BTC_USDT", "100ms"]));
+
+        assert!(parser
+            .format_subscribe(&Stream::Funding { symbol: "BTC_USDT".to_string() })
+            .is_empty());
+    }
+
+    #[test]
+    fn test_interval_names() {
+        let parser = GateioParser::new();
+        assert_eq!(parser.interval_to_exchange(Timeframe::W1).as_deref(), Some("7d"));
+        assert_eq!(parser.interval_from_exchange("30d"), Some(Timeframe::MN1));
+        for &interval in Timeframe::all() {
+            if let Some(name) = parser.interval_to_exchange(interval) {
+                assert_eq!(parser.interval_from_exchange(&name), Some(interval));
+            }
+        }
+        // Binance names that mean something else, or nothing, here
+        assert_eq!(parser.interval_from_exchange("1M"), None);
+        assert_eq!(parser.interval_to_exchange(Timeframe::H12), None);
+    }
+
+    #[test]
+    fn test_split_candle_name() {
+        assert_eq!(split_candle_name("1m_BTC_USDT"), Some((Timeframe::M1, "BTC_USDT")));
+        assert_eq!(split_candle_name("7d_GT_USDT"), Some((Timeframe::W1, "GT_USDT")));
+        assert_eq!(split_candle_name("10s_BTC_USDT"), None);
+        assert_eq!(split_candle_name("1m_"), None);
+        assert_eq!(split_candle_name("BTCUSDT"), None);
+    }
+
+    #[test]
+    fn test_parse_candle_message() {
+        let parser = GateioParser::new();
+        let msg = r#"{"time":1606292600,"time_ms":1606292600376,"channel":"spot.candlesticks","event":"update","result":{"t":"1606292580","v":"2362.32035","c":"19128.1","h":"19128.5","l":"19127.9","o":"19128.0","n":"1m_BTC_USDT","a":"3.8283","w":true}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Candle { symbol, interval, data, is_closed, .. }) => {
+                assert_eq!(symbol, "BTC_USDT");
+                assert_eq!(interval, Timeframe::M1);
+                assert_eq!(data.get_timestamp(), 1606292580000);
+                assert_eq!(data.get_open(), 19128.0);
+                assert_eq!(data.get_high(), 19128.5);
+                assert_eq!(data.get_low(), 19127.9);
+                assert_eq!(data.get_close(), 19128.1);
+                assert_eq!(data.get_volume(), 3.8283);
+                assert!(is_closed);
+            }
+            other => panic!("Expected MarketData::Candle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_trade_message() {
+        let parser = GateioParser::new();
+        let msg = r#"{"time":1606292218,"time_ms":1606292218231,"channel":"spot.trades","event":"update","result":{"id":309143071,"create_time":1606292218,"create_time_ms":"1606292218213.4578","side":"sell","currency_pair":"GT_USDT","amount":"16.47","price":"0.4705","range":"2390902-2390902"}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.symbol, "GT_USDT");
+                assert_eq!(trade.timestamp, 1606292218213);
+                assert_eq!(trade.price, 0.4705);
+                assert_eq!(trade.quantity, 16.47);
+                assert_eq!(trade.side, TradeSide::Sell);
+                assert_eq!(trade.trade_id, "309143071");
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_order_book_update() {
+        let parser = GateioParser::new();
+        let msg = r#"{"time":1606294781,"time_ms":1606294781236,"channel":"spot.order_book_update","event":"update","result":{"t":1606294781123,"e":"depthUpdate","E":1606294781,"s":"BTC_USDT","U":48776301,"u":48776306,"b":[["19137.74","0.0001"],["19088.37","0"]],"a":[["19137.75","0.6135"]]}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::OrderBook(book)) => {
+                assert!(!book.is_snapshot);
+                assert_eq!(book.symbol, "BTC_USDT");
+                assert_eq!(book.timestamp, 1606294781123);
+                assert_eq!(book.bids.len(), 2);
+                assert_eq!(book.bids[1].quantity, 0.0);
+                assert_eq!(book.asks[0].price, 19137.75);
+                assert_eq!(book.sequence, Some(48776306));
+                assert_eq!(book.first_sequence, Some(48776301));
+                assert_eq!(book.final_sequence, Some(48776306));
+            }
+            other => panic!("Expected MarketData::OrderBook, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ping_message() {
+        let parser = GateioParser::new();
+        let (payload, interval) = parser.ping_message().unwrap();
+        let ping: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(ping["channel"], "spot.ping");
+        assert!(ping["time"].as_u64().is_some());
+        assert_eq!(interval, PING_INTERVAL);
+    }
+
+    #[test]
+    fn test_parse_control_messages() {
+        let parser = GateioParser::new();
+        assert!(parser
+            .parse_message(r#"{"time":1606292218,"channel":"spot.trades","event":"subscribe","result":{"status":"success"}}"#)
+            .is_none());
+        assert!(parser
+            .parse_message(r#"{"time":1606292218,"channel":"spot.pong","event":"","result":null}"#)
+            .is_none());
+        assert!(parser
+            .parse_message(r#"{"time":1606292218,"channel":"spot.trades","event":"subscribe","error":{"code":2,"message":"unknown currency pair"},"result":null}"#)
+            .is_none());
+    }
+}
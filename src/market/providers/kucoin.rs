@@ -0,0 +1,495 @@
+//! KuCoin exchange implementation (spot): candles, trades and top-of-book depth.
+//! KuCoin requires a REST call to obtain a connection token before the WebSocket handshake.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::error::CctError;
+use crate::market::market_data::{MarketData, OrderBookUpdate, Trade, TradeSide};
+use crate::market::message_parser::MessageParser;
+use crate::market::providers::common::{de_f64, de_u64, parse_f64, parse_levels, parse_u64};
+use crate::market::streams::Stream;
+use crate::market::timestamp::{TimestampUnit, normalize_to_ms};
+use crate::market::websocket_client::WebSocketClient;
+
+pub const KUCOIN_WSS_BASE_ENDPOINT: &str = "wss://ws-api-spot.kucoin.com/";
+pub const KUCOIN_BULLET_PUBLIC_URL: &str = "https://api.kucoin.com/api/v1/bullet-public";
+
+// KuCoin documents 18s; used until the bullet response tells us otherwise
+const DEFAULT_PING_INTERVAL_MS: u64 = 18_000;
+
+// Connection bootstrap: POST /api/v1/bullet-public returns a token plus the
+// instance server to connect to and the ping interval it expects. The final URL
+// is "{endpoint}?token={token}&connectId={id}". This runs on every connect
+// (resolve_endpoint), so reconnects always use a fresh token.
+//
+// Symbols are passed through verbatim ("BTC-USDT"), KuCoin's native format.
+
+/// KuCoin-specific message parser.
+/// Implements MessageParser to convert KuCoin JSON -> normalized MarketData.
+#[derive(Debug)]
+pub struct KucoinParser {
+    bullet_url: String,
+    request_id: AtomicU64,
+    // Set by resolve_endpoint from the bullet response, read by ping_message
+    ping_interval: Mutex<Duration>,
+}
+
+impl KucoinParser {
+    pub fn new() -> Self {
+        Self {
+            bullet_url: KUCOIN_BULLET_PUBLIC_URL.to_string(),
+            request_id: AtomicU64::new(1),
+            ping_interval: Mutex::new(Duration::from_millis(DEFAULT_PING_INTERVAL_MS)),
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        self.request_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Builds the connect URL from a bullet-public response body.
+    /// Returns the URL and the server's ping interval.
+    fn connect_url_from_bullet(&self, body: &str) -> Option<(String, Duration)> {
+        let response: KucoinBulletResponse = serde_json::from_str(body).ok()?;
+        if response.code != "200000" {
+            return None;
+        }
+
+        let server = response.data.instance_servers.first()?;
+        // connectId only has to be unique per client; time-based is enough
+        let connect_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+
+        let url = format!(
+            "{}?token={}&connectId={}",
+            server.endpoint, response.data.token, connect_id
+        );
+        Some((url, Duration::from_millis(server.ping_interval)))
+    }
+
+    fn topic(&self, stream: &Stream) -> Option<String> {
+        match stream {
+            Stream::Candles { symbol, interval } => Some(format!(
+                "/market/candles:{}_{}",
+                symbol,
+                self.interval_to_exchange(*interval)?
+            )),
+            Stream::Trades { symbol } => Some(format!("/market/match:{}", symbol)),
+            Stream::OrderBook { symbol, depth } => {
+                // Only fixed-depth snapshots are pushed on public spot channels
+                let depth = if *depth <= 5 { 5 } else { 50 };
+                Some(format!("/spotMarket/level2Depth{}:{}", depth, symbol))
+            }
+            // Spot has no funding, mark price, open interest or liquidations
+            Stream::Funding { .. }
+            | Stream::MarkPrice { .. }
+            | Stream::OpenInterest { .. }
+            | Stream::Liquidations { .. }
+            | Stream::AggTrades { .. }
+            | Stream::BookTicker { .. }
+            | Stream::Ticker { .. }
+            | Stream::AllMiniTickers => None,
+        }
+    }
+
+    fn format_request(&self, kind: &str, stream: &Stream) -> String {
+        match self.topic(stream) {
+            Some(topic) => format!(
+                r#"{{"id":"{}","type":"{}","topic":"{}","privateChannel":false,"response":true}}"#,
+                self.next_id(),
+                kind,
+                topic
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// Parses a `/market/candles` message into MarketData::Candle.
+    /// KuCoin's candle array is [start(s), open, close, high, low, volume, turnover].
+    fn parse_candles(&self, topic: &str, data: &serde_json::Value) -> Option<MarketData> {
+        // Topic: /market/candles:BTC-USDT_1min
+        let (symbol, interval) = topic.strip_prefix("/market/candles:")?.rsplit_once('_')?;
+        let interval = self.interval_from_exchange(interval)?;
+
+        let values = data.get("candles")?.as_array()?;
+        if values.len() < 6 {
+            return None;
+        }
+
+        let start_secs = parse_u64(&values[0])?;
+        let open = parse_f64(&values[1])?;
+        let close = parse_f64(&values[2])?;
+        let high = parse_f64(&values[3])?;
+        let low = parse_f64(&values[4])?;
+        let volume = parse_f64(&values[5])?;
+
+        let candle = Candle::new(normalize_to_ms(start_secs, TimestampUnit::Seconds), open, high, low, close, volume);
+
+        // KuCoin never flags a candle as final: "trade.candles.add" opens the next
+        // one instead. Consumers should treat the previous candle as closed then.
+        Some(MarketData::candle(symbol, interval, candle, false))
+    }
+
+    /// Parses a `/market/match` message into MarketData::Trade.
+    /// Normalization: KuCoin's "side" is already the taker side; time is in nanoseconds.
+    fn parse_match(&self, data: &serde_json::Value) -> Option<MarketData> {
+        let event: KucoinMatch = serde_json::from_value(data.clone()).ok()?;
+        let side = match event.side.as_str() {
+            "buy" => TradeSide::Buy,
+            "sell" => TradeSide::Sell,
+            _ => return None,
+        };
+
+        let trade = Trade::new(
+            normalize_to_ms(event.time, TimestampUnit::Nanos),
+            event.symbol,
+            event.price,
+            event.size,
+            event.trade_id,
+            side,
+        );
+
+        Some(MarketData::Trade(trade))
+    }
+
+    /// Parses a `/spotMarket/level2Depth5` or `level2Depth50` message. Each push is the
+    /// whole top of the book, so it is emitted as a snapshot.
+    fn parse_depth(&self, topic: &str, data: serde_json::Value) -> Option<MarketData> {
+        // Topic: /spotMarket/level2Depth5:BTC-USDT
+        let (_, symbol) = topic.split_once(':')?;
+        let event: KucoinDepth = serde_json::from_value(data).ok()?;
+
+        let (bids, asks) = (parse_levels(&event.bids), parse_levels(&event.asks));
+        Some(MarketData::OrderBook(OrderBookUpdate::snapshot(event.timestamp, symbol, bids, asks)))
+    }
+}
+
+impl Default for KucoinParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageParser for KucoinParser {
+    /// Base endpoint only; the usable URL (with token) comes from resolve_endpoint.
+    fn endpoint(&self) -> &str {
+        KUCOIN_WSS_BASE_ENDPOINT
+    }
+
+    async fn resolve_endpoint(&self) -> Result<String, CctError> {
+        let bullet_failed = |e: reqwest::Error| CctError::ConnectFailed {
+            endpoint: self.bullet_url.clone(),
+            source: e.into(),
+        };
+        let body = reqwest::Client::new()
+            .post(&self.bullet_url)
+            .send()
+            .await
+            .map_err(bullet_failed)?
+            .text()
+            .await
+            .map_err(bullet_failed)?;
+
+        let (url, ping_interval) = self
+            .connect_url_from_bullet(&body)
+            .ok_or_else(|| CctError::ParseFailed {
+                exchange: "KuCoin",
+                message: format!("bullet-public returned an unusable response: {}", body),
+            })?;
+
+        *self.ping_interval.lock().unwrap_or_else(|e| e.into_inner()) = ping_interval;
+        Ok(url)
+    }
+
+    fn ping_message(&self) -> Option<(String, Duration)> {
+        let interval = *self.ping_interval.lock().unwrap_or_else(|e| e.into_inner());
+        Some((
+            format!(r#"{{"id":"{}","type":"ping"}}"#, self.next_id()),
+            interval,
+        ))
+    }
+
+    fn name(&self) -> &'static str {
+        "KuCoin"
+    }
+
+    fn interval_to_exchange(&self, interval: Timeframe) -> Option<String> {
+        kucoin_interval(interval).map(str::to_string)
+    }
+
+    fn interval_from_exchange(&self, value: &str) -> Option<Timeframe> {
+        timeframe_from_kucoin(value)
+    }
+
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        self.format_request("subscribe", stream)
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        self.format_request("unsubscribe", stream)
+    }
+
+    fn parse_message(&self, msg: &str) -> Option<MarketData> {
+        // Control messages: welcome, ack, pong, error all have a non-"message" type
+        if !msg.contains(r#""type":"message""#) {
+            return None;
+        }
+
+        let envelope: KucoinEnvelope = serde_json::from_str(msg).ok()?;
+
+        if envelope.topic.starts_with("/market/candles:") {
+            return self.parse_candles(&envelope.topic, &envelope.data);
+        }
+
+        if envelope.topic.starts_with("/market/match:") {
+            return self.parse_match(&envelope.data);
+        }
+
+        if envelope.topic.starts_with("/spotMarket/level2Depth") {
+            return self.parse_depth(&envelope.topic, envelope.data);
+        }
+
+        None
+    }
+}
+
+/// Maps Timeframe to KuCoin's candle type names. KuCoin has no 3-day candles.
+fn kucoin_interval(interval: Timeframe) -> Option<&'static str> {
+    match interval {
+        Timeframe::M1 => Some("1min"),
+        Timeframe::M3 => Some("3min"),
+        Timeframe::M5 => Some("5min"),
+        Timeframe::M15 => Some("15min"),
+        Timeframe::M30 => Some("30min"),
+        Timeframe::H1 => Some("1hour"),
+        Timeframe::H2 => Some("2hour"),
+        Timeframe::H4 => Some("4hour"),
+        Timeframe::H6 => Some("6hour"),
+        Timeframe::H8 => Some("8hour"),
+        Timeframe::H12 => Some("12hour"),
+        Timeframe::D1 => Some("1day"),
+        Timeframe::W1 => Some("1week"),
+        Timeframe::MN1 => Some("1month"),
+        Timeframe::D3 | Timeframe::Custom(_) => None,
+    }
+}
+
+fn timeframe_from_kucoin(value: &str) -> Option<Timeframe> {
+    match value {
+        "1min" => Some(Timeframe::M1),
+        "3min" => Some(Timeframe::M3),
+        "5min" => Some(Timeframe::M5),
+        "15min" => Some(Timeframe::M15),
+        "30min" => Some(Timeframe::M30),
+        "1hour" => Some(Timeframe::H1),
+        "2hour" => Some(Timeframe::H2),
+        "4hour" => Some(Timeframe::H4),
+        "6hour" => Some(Timeframe::H6),
+        "8hour" => Some(Timeframe::H8),
+        "12hour" => Some(Timeframe::H12),
+        "1day" => Some(Timeframe::D1),
+        "1week" => Some(Timeframe::W1),
+        "1month" => Some(Timeframe::MN1),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KucoinBulletResponse {
+    code: String,
+    data: KucoinBulletData,
+}
+
+#[derive(Debug, Deserialize)]
+struct KucoinBulletData {
+    token: String,
+    #[serde(rename = "instanceServers")]
+    instance_servers: Vec<KucoinInstanceServer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KucoinInstanceServer {
+    endpoint: String,
+    #[serde(rename = "pingInterval")]
+    ping_interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct KucoinEnvelope {
+    topic: String,
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct KucoinMatch {
+    symbol: String,
+    side: String,
+    #[serde(deserialize_with = "de_f64")]
+    price: f64,
+    #[serde(deserialize_with = "de_f64")]
+    size: f64,
+    #[serde(rename = "tradeId")]
+    trade_id: String,
+    #[serde(deserialize_with = "de_u64")]
+    time: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct KucoinDepth {
+    bids: Vec<Vec<serde_json::Value>>,
+    asks: Vec<Vec<serde_json::Value>>,
+    #[serde(deserialize_with = "de_u64")]
+    timestamp: u64,
+}
+
+pub type KucoinClient = WebSocketClient<KucoinParser>;
+
+pub fn new_kucoin_client() -> KucoinClient {
+    WebSocketClient::new(KucoinParser::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_subscribe_candles() {
+        let parser = KucoinParser::new();
+        let stream = Stream::candles("BTC-USDT", Timeframe::M1);
+        let msg = parser.format_subscribe(&stream);
+
+        assert!(msg.contains(r#""type":"subscribe""#));
+        assert!(msg.contains(r#""topic":"/market/candles:BTC-USDT_1min""#));
+    }
+
+    #[test]
+    fn test_format_unsubscribe_trades() {
+        let parser = KucoinParser::new();
+        let msg = parser.format_unsubscribe(&Stream::trades("ETH-USDT"));
+
+        assert!(msg.contains(r#""type":"unsubscribe""#));
+        assert!(msg.contains(r#""topic":"/market/match:ETH-USDT""#));
+    }
+
+    #[test]
+    fn test_format_subscribe_unsupported_stream_is_empty() {
+        let parser = KucoinParser::new();
+        let stream = Stream::Funding { symbol: "BTC-USDT".to_string() };
+        assert!(parser.format_subscribe(&stream).is_empty());
+        assert!(parser.format_subscribe(&Stream::candles("BTC-USDT", Timeframe::D3)).is_empty());
+        assert!(parser.format_subscribe(&Stream::candles("BTC-USDT", Timeframe::H12)).contains("BTC-USDT_12hour"));
+    }
+
+    #[test]
+    fn test_request_ids_increment() {
+        let parser = KucoinParser::new();
+        let first = parser.format_subscribe(&Stream::trades("BTC-USDT"));
+        let second = parser.format_subscribe(&Stream::trades("BTC-USDT"));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_connect_url_from_bullet() {
+        let parser = KucoinParser::new();
+        let body = r#"{"code":"200000","data":{"token":"2neAiuYvAU61ZDXANAGAsiL4","instanceServers":[{"endpoint":"wss://ws-api-spot.kucoin.com/","encrypt":true,"protocol":"websocket","pingInterval":18000,"pingTimeout":10000}]}}"#;
+
+        let (url, ping_interval) = parser.connect_url_from_bullet(body).unwrap();
+        assert!(url.starts_with("wss://ws-api-spot.kucoin.com/?token=2neAiuYvAU61ZDXANAGAsiL4&connectId="));
+        assert_eq!(ping_interval, Duration::from_millis(18000));
+    }
+
+    #[test]
+    fn test_connect_url_from_bullet_error() {
+        let parser = KucoinParser::new();
+        let body = r#"{"code":"400100","msg":"error"}"#;
+        assert!(parser.connect_url_from_bullet(body).is_none());
+    }
+
+    #[test]
+    fn test_ping_message() {
+        let parser = KucoinParser::new();
+        let (payload, interval) = parser.ping_message().unwrap();
+        assert!(payload.contains(r#""type":"ping""#));
+        assert_eq!(interval, Duration::from_millis(DEFAULT_PING_INTERVAL_MS));
+    }
+
+    #[test]
+    fn test_parse_candle_message() {
+        let parser = KucoinParser::new();
+        let msg = r#"{"type":"message","topic":"/market/candles:BTC-USDT_1hour","subject":"trade.candles.update","data":{"symbol":"BTC-USDT","candles":["1589968800","9786.9","9740.8","9806.1","9732","27.45649579","268280.09830877"],"time":1589970010253893337}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Candle { symbol, interval, data, is_closed, .. }) => {
+                assert_eq!(symbol, "BTC-USDT");
+                assert_eq!(interval, Timeframe::H1);
+                assert_eq!(data.get_timestamp(), 1589968800000);
+                assert_eq!(data.get_open(), 9786.9);
+                assert_eq!(data.get_close(), 9740.8);
+                assert_eq!(data.get_high(), 9806.1);
+                assert_eq!(data.get_low(), 9732.0);
+                assert_eq!(data.get_volume(), 27.45649579);
+                assert!(!is_closed);
+            }
+            other => panic!("Expected MarketData::Candle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_match_message() {
+        let parser = KucoinParser::new();
+        let msg = r#"{"type":"message","topic":"/market/match:BTC-USDT","subject":"trade.l3match","data":{"sequence":"1545896669145","type":"match","symbol":"BTC-USDT","side":"sell","price":"0.08200000000000000000","size":"0.01022222000000000000","tradeId":"5c24c5da03aa673885cd67aa","takerOrderId":"5c24c5d903aa6772d55b371e","makerOrderId":"5c2187d003aa677bd09d5c93","time":"1545913818099033203"}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.symbol, "BTC-USDT");
+                assert_eq!(trade.price, 0.082);
+                assert_eq!(trade.quantity, 0.01022222);
+                assert_eq!(trade.trade_id, "5c24c5da03aa673885cd67aa");
+                assert_eq!(trade.side, TradeSide::Sell);
+                assert_eq!(trade.timestamp, 1545913818099);
+                assert!(trade.is_buyer_maker.is_none());
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_level2_depth_message() {
+        let parser = KucoinParser::new();
+        // Example from KuCoin's "Level2 - 5 best ask/bid orders" docs
+        let msg = r#"{"type":"message","topic":"/spotMarket/level2Depth5:BTC-USDT","subject":"level2","data":{"asks":[["9989","8"],["9990","32"],["9991","47"],["9992","3"],["9993","3"]],"bids":[["9988","56"],["9987","15"],["9986","100"],["9985","10"],["9984","10"]],"timestamp":1586948108193}}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::OrderBook(update)) => {
+                assert_eq!(update.symbol, "BTC-USDT");
+                assert_eq!(update.timestamp, 1586948108193);
+                assert!(update.is_snapshot);
+                assert_eq!(update.bids.len(), 5);
+                assert_eq!(update.asks.len(), 5);
+                assert_eq!((update.bids[0].price, update.bids[0].quantity), (9988.0, 56.0));
+                assert_eq!((update.asks[0].price, update.asks[0].quantity), (9989.0, 8.0));
+                assert_eq!((update.asks[4].price, update.asks[4].quantity), (9993.0, 3.0));
+            }
+            other => panic!("Expected MarketData::OrderBook, got {:?}", other),
+        }
+
+        let topic = parser.format_subscribe(&Stream::OrderBook { symbol: "BTC-USDT".to_string(), depth: 20 });
+        assert!(topic.contains(r#""topic":"/spotMarket/level2Depth50:BTC-USDT""#));
+    }
+
+    #[test]
+    fn test_parse_control_messages() {
+        let parser = KucoinParser::new();
+        assert!(parser.parse_message(r#"{"id":"hQvf8jkno","type":"welcome"}"#).is_none());
+        assert!(parser.parse_message(r#"{"id":"1545910660739","type":"ack"}"#).is_none());
+        assert!(parser.parse_message(r#"{"id":"1545910590801","type":"pong"}"#).is_none());
+    }
+}
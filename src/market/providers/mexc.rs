@@ -0,0 +1,347 @@
+//! MEXC exchange implementation (spot WebSocket v3, JSON channels).
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::{MarketData, Trade, TradeSide};
+use crate::market::message_parser::MessageParser;
+use crate::market::providers::common::de_f64;
+use crate::market::streams::Stream;
+use crate::market::timestamp::{TimestampUnit, normalize_to_ms};
+use crate::market::websocket_client::WebSocketClient;
+
+pub const MEXC_WSS_ENDPOINT: &str = "wss://wbs.mexc.com/ws";
+
+// MEXC drops connections without traffic for 60s
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+// Channels are "spot@public.<kind>.v3.api@SYMBOL[@Interval]" and every push
+// echoes the channel in "c" with the payload in "d". Acks and pongs come back
+// as {"id","code","msg"} without "c".
+//
+// Only the JSON channels are handled. MEXC's protobuf (".pb") variants arrive as
+// binary frames, which the client doesn't hand to parsers.
+//
+// MEXC symbols are uppercase ("BTCUSDT"); stream symbols are uppercased when
+// formatting, like Binance lowercases them.
+
+/// MEXC-specific message parser.
+/// Implements MessageParser to convert MEXC JSON -> normalized MarketData.
+#[derive(Debug, Clone, Default)]
+pub struct MexcParser;
+
+impl MexcParser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Returns the channel for a stream, or None if MEXC spot has no JSON equivalent.
+    fn channel(&self, stream: &Stream) -> Option<String> {
+        match stream {
+            Stream::Candles { symbol, interval } => Some(format!(
+                "spot@public.kline.v3.api@{}@{}",
+                symbol.to_uppercase(),
+                self.interval_to_exchange(*interval)?
+            )),
+            Stream::Trades { symbol } => Some(format!(
+                "spot@public.deals.v3.api@{}",
+                symbol.to_uppercase()
+            )),
+            Stream::Funding { .. }
+            | Stream::MarkPrice { .. }
+            | Stream::OrderBook { .. }
+            | Stream::OpenInterest { .. }
+            | Stream::Liquidations { .. }
+            | Stream::AggTrades { .. }
+            | Stream::BookTicker { .. }
+            | Stream::Ticker { .. }
+            | Stream::AllMiniTickers => None,
+        }
+    }
+
+    fn format_request(&self, method: &str, stream: &Stream) -> String {
+        match self.channel(stream) {
+            Some(channel) => format!(r#"{{"method":"{}","params":["{}"]}}"#, method, channel),
+            None => String::new(),
+        }
+    }
+
+    /// Parses a kline push. "t" is the window start in seconds, "v" the base volume.
+    fn parse_kline(&self, symbol: String, data: serde_json::Value) -> Option<MarketData> {
+        let event: MexcKlineData = serde_json::from_value(data).ok()?;
+        let kline = event.k;
+        let interval = self.interval_from_exchange(&kline.interval)?;
+
+        let candle = Candle::new(
+            normalize_to_ms(kline.start, TimestampUnit::Seconds),
+            kline.open,
+            kline.high,
+            kline.low,
+            kline.close,
+            kline.volume,
+        );
+
+        // MEXC pushes the forming candle without a closed flag
+        Some(MarketData::candle(symbol, interval, candle, false))
+    }
+
+    /// Parses a deals push. Normalization: "S" is the taker side, 1 = buy, 2 = sell.
+    /// Only the first deal of a batch is emitted.
+    fn parse_deal(&self, symbol: String, data: serde_json::Value) -> Option<MarketData> {
+        let event: MexcDealsData = serde_json::from_value(data).ok()?;
+        let deal = event.deals.into_iter().next()?;
+        let side = match deal.side {
+            1 => TradeSide::Buy,
+            2 => TradeSide::Sell,
+            _ => return None,
+        };
+
+        // Deals carry no id; the timestamp is the closest thing MEXC provides
+        let trade = Trade::new(
+            deal.timestamp,
+            symbol,
+            deal.price,
+            deal.quantity,
+            deal.timestamp.to_string(),
+            side,
+        );
+        Some(MarketData::Trade(trade))
+    }
+}
+
+impl MessageParser for MexcParser {
+    fn endpoint(&self) -> &str {
+        MEXC_WSS_ENDPOINT
+    }
+
+    fn name(&self) -> &'static str {
+        "MEXC"
+    }
+
+    fn interval_to_exchange(&self, interval: Timeframe) -> Option<String> {
+        mexc_interval(interval).map(str::to_string)
+    }
+
+    fn interval_from_exchange(&self, value: &str) -> Option<Timeframe> {
+        timeframe_from_mexc(value)
+    }
+
+    fn ping_message(&self) -> Option<(String, Duration)> {
+        Some((r#"{"method":"PING"}"#.to_string(), PING_INTERVAL))
+    }
+
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        self.format_request("SUBSCRIPTION", stream)
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        self.format_request("UNSUBSCRIPTION", stream)
+    }
+
+    fn parse_message(&self, msg: &str) -> Option<MarketData> {
+        // Acks and pongs ({"id":0,"code":0,"msg":...}) have no channel and fail here
+        let envelope: MexcEnvelope = serde_json::from_str(msg).ok()?;
+
+        if envelope.channel.starts_with("spot@public.kline.v3.api@") {
+            return self.parse_kline(envelope.symbol, envelope.data);
+        }
+        if envelope.channel.starts_with("spot@public.deals.v3.api@") {
+            return self.parse_deal(envelope.symbol, envelope.data);
+        }
+        None
+    }
+}
+
+/// Maps Timeframe to MEXC's kline interval name. MEXC has no 3m, 2h, 6h, 12h or
+/// 3d klines.
+fn mexc_interval(interval: Timeframe) -> Option<&'static str> {
+    match interval {
+        Timeframe::M1 => Some("Min1"),
+        Timeframe::M5 => Some("Min5"),
+        Timeframe::M15 => Some("Min15"),
+        Timeframe::M30 => Some("Min30"),
+        Timeframe::H1 => Some("Min60"),
+        Timeframe::H4 => Some("Hour4"),
+        Timeframe::H8 => Some("Hour8"),
+        Timeframe::D1 => Some("Day1"),
+        Timeframe::W1 => Some("Week1"),
+        Timeframe::MN1 => Some("Month1"),
+        Timeframe::M3 | Timeframe::H2 | Timeframe::H6 | Timeframe::H12 | Timeframe::D3 | Timeframe::Custom(_) => None,
+    }
+}
+
+fn timeframe_from_mexc(value: &str) -> Option<Timeframe> {
+    match value {
+        "Min1" => Some(Timeframe::M1),
+        "Min5" => Some(Timeframe::M5),
+        "Min15" => Some(Timeframe::M15),
+        "Min30" => Some(Timeframe::M30),
+        "Min60" => Some(Timeframe::H1),
+        "Hour4" => Some(Timeframe::H4),
+        "Hour8" => Some(Timeframe::H8),
+        "Day1" => Some(Timeframe::D1),
+        "Week1" => Some(Timeframe::W1),
+        "Month1" => Some(Timeframe::MN1),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MexcEnvelope {
+    #[serde(rename = "c")]
+    channel: String,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "d")]
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct MexcKlineData {
+    k: MexcKline,
+}
+
+#[derive(Debug, Deserialize)]
+struct MexcKline {
+    #[serde(rename = "t")]
+    start: u64,
+    #[serde(rename = "i")]
+    interval: String,
+    #[serde(rename = "o", deserialize_with = "de_f64")]
+    open: f64,
+    #[serde(rename = "h", deserialize_with = "de_f64")]
+    high: f64,
+    #[serde(rename = "l", deserialize_with = "de_f64")]
+    low: f64,
+    #[serde(rename = "c", deserialize_with = "de_f64")]
+    close: f64,
+    #[serde(rename = "v", deserialize_with = "de_f64")]
+    volume: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MexcDealsData {
+    deals: Vec<MexcDeal>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MexcDeal {
+    #[serde(rename = "S")]
+    side: u8,
+    #[serde(rename = "p", deserialize_with = "de_f64")]
+    price: f64,
+    #[serde(rename = "v", deserialize_with = "de_f64")]
+    quantity: f64,
+    #[serde(rename = "t")]
+    timestamp: u64,
+}
+
+pub type MexcClient = WebSocketClient<MexcParser>;
+
+pub fn new_mexc_client() -> MexcClient {
+    WebSocketClient::new(MexcParser::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_subscribe_candles() {
+        let parser = MexcParser::new();
+        let msg = parser.format_subscribe(&Stream::candles("btcusdt", Timeframe::M1));
+        assert_eq!(
+            msg,
+            r#"{"method":"SUBSCRIPTION","params":["spot@public.kline.v3.api@BTCUSDT@Min1"]}"#
+        );
+    }
+
+    #[test]
+    fn test_format_subscribe_trades_and_unsubscribe() {
+        let parser = MexcParser::new();
+        assert_eq!(
+            parser.format_subscribe(&Stream::trades("BTCUSDT")),
+            r#"{"method":"SUBSCRIPTION","params":["spot@public.deals.v3.api@BTCUSDT"]}"#
+        );
+        assert_eq!(
+            parser.format_unsubscribe(&Stream::candles("ETHUSDT", Timeframe::H4)),
+            r#"{"method":"UNSUBSCRIPTION","params":["spot@public.kline.v3.api@ETHUSDT@Hour4"]}"#
+        );
+        assert!(parser.format_subscribe(&Stream::order_book("BTCUSDT", 5)).is_empty());
+    }
+
+    #[test]
+    fn test_interval_mapping_round_trips() {
+        for tf in [
+            Timeframe::M1,
+            Timeframe::M5,
+            Timeframe::M15,
+            Timeframe::M30,
+            Timeframe::H1,
+            Timeframe::H4,
+            Timeframe::H8,
+            Timeframe::D1,
+            Timeframe::W1,
+            Timeframe::MN1,
+        ] {
+            assert_eq!(mexc_interval(tf).and_then(timeframe_from_mexc), Some(tf));
+        }
+        assert_eq!(mexc_interval(Timeframe::H1), Some("Min60"));
+        assert_eq!(mexc_interval(Timeframe::H12), None);
+        assert_eq!(timeframe_from_mexc("Hour12"), None);
+    }
+
+    #[test]
+    fn test_parse_kline_message() {
+        let parser = MexcParser::new();
+        let msg = r#"{"c":"spot@public.kline.v3.api@BTCUSDT@Min15","d":{"k":{"T":1678642200,"a":29208.34,"c":20219.74,"h":20219.74,"i":"Min15","l":20212.41,"o":20212.41,"t":1678641300,"v":1.445},"e":"spot@public.kline.v3.api"},"s":"BTCUSDT","t":1678642261407}"#;
+
+        match parser.parse_message(msg) {
+            Some(MarketData::Candle { symbol, interval, data, is_closed, .. }) => {
+                assert_eq!(symbol, "BTCUSDT");
+                assert_eq!(interval, Timeframe::M15);
+                assert_eq!(data.get_timestamp(), 1678641300000);
+                assert_eq!(data.get_open(), 20212.41);
+                assert_eq!(data.get_high(), 20219.74);
+                assert_eq!(data.get_low(), 20212.41);
+                assert_eq!(data.get_close(), 20219.74);
+                assert_eq!(data.get_volume(), 1.445);
+                assert!(!is_closed);
+            }
+            other => panic!("Expected MarketData::Candle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_deal_buy_and_sell() {
+        let parser = MexcParser::new();
+        let sell = r#"{"c":"spot@public.deals.v3.api@BTCUSDT","d":{"deals":[{"S":2,"p":"20233.84","t":1678783924136,"v":"0.001028"}],"e":"spot@public.deals.v3.api"},"s":"BTCUSDT","t":1678783924139}"#;
+
+        match parser.parse_message(sell) {
+            Some(MarketData::Trade(trade)) => {
+                assert_eq!(trade.symbol, "BTCUSDT");
+                assert_eq!(trade.timestamp, 1678783924136);
+                assert_eq!(trade.price, 20233.84);
+                assert_eq!(trade.quantity, 0.001028);
+                assert_eq!(trade.side, TradeSide::Sell);
+            }
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+
+        let buy = r#"{"c":"spot@public.deals.v3.api@BTCUSDT","d":{"deals":[{"S":1,"p":"20234.00","t":1678783924200,"v":"0.5"}],"e":"spot@public.deals.v3.api"},"s":"BTCUSDT","t":1678783924201}"#;
+        match parser.parse_message(buy) {
+            Some(MarketData::Trade(trade)) => assert_eq!(trade.side, TradeSide::Buy),
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_control_messages() {
+        let parser = MexcParser::new();
+        assert!(parser.parse_message(r#"{"id":0,"code":0,"msg":"spot@public.deals.v3.api@BTCUSDT"}"#).is_none());
+        assert!(parser.parse_message(r#"{"id":0,"code":0,"msg":"PONG"}"#).is_none());
+    }
+}
@@ -1,6 +1,23 @@
 //! Exchange provider implementations.
 
+pub(crate) mod common;
+
 pub mod binance;
+pub mod binance_futures;
+pub mod bitget;
+pub mod deribit;
+pub mod gateio;
+pub mod kucoin;
+pub mod mexc;
 
 // Re-export for convenience
-pub use binance::{BinanceClient, BinanceParser, new_binance_client};
\ No newline at end of file
+pub use binance::{
+    BinanceClient, BinanceParser, new_binance_client, new_binance_client_combined,
+    new_binance_testnet_client,
+};
+pub use binance_futures::{BinanceFuturesClient, BinanceFuturesParser, new_binance_futures_client};
+pub use bitget::{BitgetClient, BitgetParser, new_bitget_client};
+pub use deribit::{DeribitClient, DeribitParser, new_deribit_client};
+pub use gateio::{GateioClient, GateioParser, new_gateio_client};
+pub use kucoin::{KucoinClient, KucoinParser, new_kucoin_client};
+pub use mexc::{MexcClient, MexcParser, new_mexc_client};
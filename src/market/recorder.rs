@@ -0,0 +1,384 @@
+//! Records market data to NDJSON files, one event per line.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::market::market_data::{MarketData, now_ms};
+
+// Design: the recorder is a plain async writer; `record_from` and `attach` run it
+// in a task fed by a client's receiver. Lines go through a tokio BufWriter, which
+// is flushed every `flush_interval` (also while the feed is quiet), on rotation,
+// and when the feed ends. Drop can't await, so it hands the writer to a task that
+// flushes it - best effort; call `close()` or stop the task to be sure.
+
+const DEFAULT_MAX_FILE_BYTES: u64 = 500 * 1024 * 1024;
+const DEFAULT_MAX_FILE_AGE: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const FORWARD_CAPACITY: usize = 1000;
+
+/// One line of a capture:
+/// `{"received_at":..,"exchange":"Binance","symbol":"BTCUSDT","type":"trade","data":{..}}`.
+#[derive(Serialize)]
+struct RecordLine<'a> {
+    /// When the client received the event (or, if it wasn't stamped, when the
+    /// recorder saw it), ms since the Unix epoch
+    received_at: u64,
+    exchange: &'a str,
+    symbol: &'a str,
+    #[serde(flatten)]
+    data: &'a MarketData,
+}
+
+struct Segment {
+    writer: BufWriter<File>,
+    bytes: u64,
+    opened_at: Instant,
+}
+
+/// Writes `MarketData` to `path` as NDJSON, rotating to `<stem>.1.<ext>`,
+/// `<stem>.2.<ext>`, ... once a file reaches `max_file_bytes` or `max_file_age`.
+/// Existing files are appended to, never truncated.
+pub struct MarketDataRecorder {
+    path: PathBuf,
+    max_file_bytes: u64,
+    max_file_age: Duration,
+    flush_interval: Duration,
+    segment: Option<Segment>,
+    segment_index: u32,
+    last_flush: Instant,
+    records: u64,
+}
+
+impl MarketDataRecorder {
+    /// Nothing is opened until the first event is recorded.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_file_age: DEFAULT_MAX_FILE_AGE,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            segment: None,
+            segment_index: 0,
+            last_flush: Instant::now(),
+            records: 0,
+        }
+    }
+
+    /// Starts a new file once the current one would exceed this size. Default: 500MB.
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes.max(1);
+        self
+    }
+
+    /// Starts a new file once the current one has been open this long. Default: 1 hour.
+    pub fn with_max_file_age(mut self, max_file_age: Duration) -> Self {
+        self.max_file_age = max_file_age;
+        self
+    }
+
+    /// Upper bound on how long a recorded line may sit in the buffer. Default: 1s.
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Events recorded so far.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// The file currently written to (or the next one, before the first event).
+    pub fn current_path(&self) -> PathBuf {
+        segment_path(&self.path, self.segment_index)
+    }
+
+    pub async fn record(&mut self, market_data: &MarketData) -> io::Result<()> {
+        let line = RecordLine {
+            received_at: match market_data.received_at() {
+                0 => now_ms(),
+                received_at => received_at,
+            },
+            exchange: market_data.exchange(),
+            symbol: market_data.symbol(),
+            data: market_data,
+        };
+        let mut bytes = serde_json::to_vec(&line)?;
+        bytes.push(b'\n');
+
+        let segment = self.segment_for(bytes.len() as u64).await?;
+        segment.writer.write_all(&bytes).await?;
+        segment.bytes += bytes.len() as u64;
+        self.records += 1;
+
+        if self.last_flush.elapsed() >= self.flush_interval {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Writes buffered lines to the file.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.last_flush = Instant::now();
+        match self.segment.as_mut() {
+            Some(segment) => segment.writer.flush().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Flushes and closes the current file. A later `record` opens the next one.
+    pub async fn close(&mut self) -> io::Result<()> {
+        if let Some(mut segment) = self.segment.take() {
+            segment.writer.shutdown().await?;
+            self.segment_index += 1;
+        }
+        Ok(())
+    }
+
+    /// The open segment, rotated first if `incoming` more bytes would overflow it or
+    /// it is too old. An empty segment always accepts, so oversized lines still land.
+    async fn segment_for(&mut self, incoming: u64) -> io::Result<&mut Segment> {
+        if let Some(segment) = &self.segment {
+            let full = segment.bytes > 0 && segment.bytes + incoming > self.max_file_bytes;
+            if full || segment.opened_at.elapsed() >= self.max_file_age {
+                self.close().await?;
+            }
+        }
+        if self.segment.is_none() {
+            let path = self.current_path();
+            let file = OpenOptions::new().create(true).append(true).open(&path).await?;
+            let bytes = file.metadata().await?.len();
+            self.segment = Some(Segment {
+                writer: BufWriter::new(file),
+                bytes,
+                opened_at: Instant::now(),
+            });
+        }
+        Ok(self.segment.as_mut().expect("opened above"))
+    }
+
+    /// Records everything from `rx` until the feed ends, then closes the file.
+    /// Returns the number of events recorded.
+    pub async fn record_from(mut self, mut rx: mpsc::Receiver<MarketData>) -> io::Result<u64> {
+        loop {
+            match tokio::time::timeout(self.flush_interval, rx.recv()).await {
+                Ok(Some(market_data)) => self.record(&market_data).await?,
+                Ok(None) => break,
+                // Quiet feed: don't leave the last lines in the buffer
+                Err(_) => self.flush().await?,
+            }
+        }
+        self.close().await?;
+        Ok(self.records)
+    }
+
+    /// Records `rx` in a background task while passing every event on to the
+    /// returned receiver, so the recorder can sit between a client and its consumer:
+    ///
+    /// ```no_run
+    /// # use cct::market::{MarketDataRecorder, new_binance_client};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = new_binance_client();
+    /// let rx = client.connect().await?;
+    /// let (rx, recorder) = MarketDataRecorder::new("capture.ndjson").attach(rx);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Recording continues if the consumer drops its receiver. A write error stops
+    /// recording (it is returned by `RecorderHandle::stop`) but not forwarding.
+    pub fn attach(self, rx: mpsc::Receiver<MarketData>) -> (mpsc::Receiver<MarketData>, RecorderHandle) {
+        let (forward_tx, forward_rx) = mpsc::channel(FORWARD_CAPACITY);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let task = tokio::spawn(self.run_attached(rx, forward_tx, stop_rx));
+        let handle = RecorderHandle {
+            stop: Some(stop_tx),
+            task: Some(task),
+        };
+        (forward_rx, handle)
+    }
+
+    async fn run_attached(
+        mut self,
+        mut rx: mpsc::Receiver<MarketData>,
+        forward_tx: mpsc::Sender<MarketData>,
+        mut stop_rx: oneshot::Receiver<()>,
+    ) -> io::Result<u64> {
+        let mut result = Ok(());
+        let mut stopping = false;
+        // A dropped RecorderHandle leaves the recorder running until the feed ends
+        let mut handle_alive = true;
+        loop {
+            let next = if stopping {
+                // Drain what the client already delivered, then finish
+                rx.try_recv().ok()
+            } else {
+                tokio::select! {
+                    next = rx.recv() => next,
+                    stop = &mut stop_rx, if handle_alive => {
+                        stopping = stop.is_ok();
+                        handle_alive = false;
+                        continue;
+                    }
+                    _ = tokio::time::sleep(self.flush_interval) => {
+                        if result.is_ok() {
+                            result = self.flush().await;
+                        }
+                        continue;
+                    }
+                }
+            };
+            let Some(market_data) = next else { break };
+            if result.is_ok() {
+                result = self.record(&market_data).await;
+                if let Err(e) = &result {
+                    eprintln!("[Recorder] Recording stopped: {}", e);
+                }
+            }
+            // A consumer that went away doesn't stop the recording
+            let _ = forward_tx.send(market_data).await;
+        }
+        result?;
+        self.close().await?;
+        Ok(self.records)
+    }
+}
+
+impl Drop for MarketDataRecorder {
+    fn drop(&mut self) {
+        let Some(mut segment) = self.segment.take() else { return };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = segment.writer.shutdown().await;
+            });
+        }
+    }
+}
+
+/// Controls a recorder started with `MarketDataRecorder::attach`.
+pub struct RecorderHandle {
+    stop: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<io::Result<u64>>>,
+}
+
+impl RecorderHandle {
+    /// Records what the client has already delivered, flushes and closes the file.
+    /// Returns the number of events recorded, or the error that stopped recording.
+    /// The recorder also finishes on its own when the client's feed ends.
+    pub async fn stop(&mut self) -> io::Result<u64> {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        match self.task.take() {
+            Some(task) => task.await.map_err(io::Error::other)?,
+            None => Ok(0),
+        }
+    }
+}
+
+fn segment_path(path: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, index),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{Trade, TradeSide};
+
+    fn trade(price: f64) -> MarketData {
+        MarketData::Trade(Trade::new(1, "BTCUSDT", price, 1.0, "t", TradeSide::Buy)).with_exchange("Binance")
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cct-recorder-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn lines(path: &Path) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_segment_paths() {
+        let path = Path::new("/data/capture.ndjson");
+        assert_eq!(segment_path(path, 0), PathBuf::from("/data/capture.ndjson"));
+        assert_eq!(segment_path(path, 2), PathBuf::from("/data/capture.2.ndjson"));
+        assert_eq!(segment_path(Path::new("capture"), 1), PathBuf::from("capture.1"));
+    }
+
+    #[tokio::test]
+    async fn test_lines_and_size_rotation() {
+        let dir = temp_dir("rotation");
+        let path = dir.join("capture.ndjson");
+        let mut recorder = MarketDataRecorder::new(&path).with_max_file_bytes(300);
+        for price in [1.0, 2.0, 3.0] {
+            recorder.record(&trade(price)).await.unwrap();
+        }
+        recorder.close().await.unwrap();
+
+        let first = lines(&path);
+        assert_eq!(first[0]["type"], "trade");
+        assert_eq!(first[0]["exchange"], "Binance");
+        assert_eq!(first[0]["symbol"], "BTCUSDT");
+        assert_eq!(first[0]["data"]["price"], 1.0);
+        assert_eq!(first[0]["data"]["side"], "buy");
+        assert!(first[0]["received_at"].as_u64().unwrap() > 0);
+
+        // Each line is over 150 bytes, so every line after the first rotates
+        assert_eq!(first.len(), 1);
+        assert_eq!(lines(&dir.join("capture.1.ndjson")).len(), 1);
+        assert_eq!(lines(&dir.join("capture.2.ndjson"))[0]["data"]["price"], 3.0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_attach_forwards_and_flushes_on_stop() {
+        let dir = temp_dir("attach");
+        let path = dir.join("capture.ndjson");
+        let (tx, rx) = mpsc::channel(10);
+        let (mut rx, mut recorder) = MarketDataRecorder::new(&path).attach(rx);
+
+        tx.send(trade(1.0)).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().as_trade().unwrap().price, 1.0);
+        // Delivered but not yet picked up when stop() is called: still recorded
+        tx.send(trade(2.0)).await.unwrap();
+        assert_eq!(recorder.stop().await.unwrap(), 2);
+
+        let prices: Vec<f64> = lines(&path).iter().map(|line| line["data"]["price"].as_f64().unwrap()).collect();
+        assert_eq!(prices, vec![1.0, 2.0]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_from_ends_with_feed() {
+        let dir = temp_dir("record-from");
+        let path = dir.join("capture.ndjson");
+        let (tx, rx) = mpsc::channel(10);
+        let recording = tokio::spawn(MarketDataRecorder::new(&path).record_from(rx));
+        tx.send(trade(1.0)).await.unwrap();
+        drop(tx);
+        assert_eq!(recording.await.unwrap().unwrap(), 1);
+        assert_eq!(lines(&path).len(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,376 @@
+//! Replays captured market data through the same receiver interface as `WebSocketClient`.
+
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::market::data_stream::MarketDataStream;
+use crate::market::error::CctError;
+use crate::market::market_data::MarketData;
+use crate::market::message_parser::MessageParser;
+use crate::market::streams::Stream;
+
+// Design: captures are loaded up front, so a replay can loop and be restarted
+// without touching the file again. Each event carries the time used for pacing and
+// for the start/end window: the recorder's `received_at` for NDJSON captures, the
+// exchange's event time for raw frames (which have no receive time).
+
+const DEFAULT_CAPACITY: usize = 1000;
+
+/// How fast a replay runs relative to the recorded timestamps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// No pauses between events.
+    AsFastAsPossible,
+    /// The recorded gaps between events.
+    RealTime,
+    /// The recorded gaps divided by this factor (2.0 = twice as fast).
+    /// Factors of 0 or below replay as fast as possible.
+    Multiplier(f64),
+}
+
+/// One line of a `MarketDataRecorder` capture. Only the fields needed to rebuild
+/// the event are read.
+#[derive(Deserialize)]
+struct CaptureLine {
+    received_at: Option<u64>,
+    #[serde(flatten)]
+    data: MarketData,
+}
+
+#[derive(Debug, Clone)]
+struct ReplayEvent {
+    time: Option<u64>,
+    data: MarketData,
+}
+
+/// Stand-in for a live client that feeds a capture to `connect()`'s receiver.
+///
+/// ```no_run
+/// # use cct::market::{ReplayClient, ReplaySpeed, Stream};
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// // let mut client = new_binance_client();
+/// let mut client = ReplayClient::from_file("capture.ndjson")?.with_speed(ReplaySpeed::Multiplier(10.0));
+/// let mut rx = client.connect().await?;
+/// client.subscribe(Stream::trades("BTCUSDT")).await?;
+/// while let Some(data) = rx.recv().await { /* unchanged */ }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The capture is replayed as recorded: `subscribe()` succeeds and is reflected
+/// by `subscriptions()`, but doesn't filter what is replayed.
+pub struct ReplayClient {
+    name: &'static str,
+    events: Arc<Vec<ReplayEvent>>,
+    speed: ReplaySpeed,
+    start: Option<u64>,
+    end: Option<u64>,
+    looping: bool,
+    capacity: usize,
+    subscriptions: Vec<Stream>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ReplayClient {
+    fn with_events(name: &'static str, events: Vec<ReplayEvent>) -> Self {
+        Self {
+            name,
+            events: Arc::new(events),
+            speed: ReplaySpeed::AsFastAsPossible,
+            start: None,
+            end: None,
+            looping: false,
+            capacity: DEFAULT_CAPACITY,
+            subscriptions: Vec::new(),
+            task: None,
+        }
+    }
+
+    /// Loads a `MarketDataRecorder` capture. Blank lines are skipped; a line that
+    /// isn't a recorded event fails with `InvalidData`, naming the line.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut events = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let capture: CaptureLine = serde_json::from_str(line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, e))
+            })?;
+            events.push(ReplayEvent {
+                time: capture.received_at.or_else(|| capture.data.event_time()),
+                data: capture.data,
+            });
+        }
+        Ok(Self::with_events("Replay", events))
+    }
+
+    /// Replays already-built events, paced by their event timestamps.
+    pub fn from_market_data(items: impl IntoIterator<Item = MarketData>) -> Self {
+        let events = items
+            .into_iter()
+            .map(|data| ReplayEvent { time: data.event_time(), data })
+            .collect();
+        Self::with_events("Replay", events)
+    }
+
+    /// Parses raw exchange frames with `parser`, as a live client would, and replays
+    /// the result paced by the exchange's event timestamps. Frames that parse to
+    /// nothing (acks, pings) are dropped.
+    pub fn from_frames<P: MessageParser>(parser: &P, frames: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let events = frames
+            .into_iter()
+            .flat_map(|frame| parser.parse_messages(frame.as_ref()))
+            .map(|data| {
+                let data = data.with_exchange(parser.name());
+                ReplayEvent { time: data.event_time(), data }
+            })
+            .collect();
+        Self::with_events(parser.name(), events)
+    }
+
+    /// `from_frames` for a file with one raw frame per line.
+    pub fn from_frames_file<P: MessageParser>(parser: &P, path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let frames = content.lines().filter(|line| !line.trim().is_empty());
+        Ok(Self::from_frames(parser, frames))
+    }
+
+    /// Default: `ReplaySpeed::AsFastAsPossible`.
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Skips events before `start_ms` (Unix ms, same clock as the capture's times).
+    pub fn with_start(mut self, start_ms: u64) -> Self {
+        self.start = Some(start_ms);
+        self
+    }
+
+    /// Stops at events after `end_ms` (inclusive bound).
+    pub fn with_end(mut self, end_ms: u64) -> Self {
+        self.end = Some(end_ms);
+        self
+    }
+
+    /// Starts over after the last event until `disconnect()` or the receiver is dropped.
+    pub fn with_loop(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Capacity of the receiver's channel. Default: 1000.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Events in the capture, before the start/end window is applied.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// True while events are being replayed.
+    pub fn is_connected(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    pub fn subscriptions(&self) -> Vec<Stream> {
+        self.subscriptions.clone()
+    }
+
+    /// Starts the replay. Calling it again restarts from the beginning on a new receiver.
+    pub async fn connect(&mut self) -> Result<mpsc::Receiver<MarketData>, CctError> {
+        self.stop();
+        let (tx, rx) = mpsc::channel(self.capacity);
+        let window: Vec<ReplayEvent> = self
+            .events
+            .iter()
+            .filter(|event| self.in_window(event))
+            .cloned()
+            .collect();
+        self.task = Some(tokio::spawn(replay(window, self.speed, self.looping, tx)));
+        Ok(rx)
+    }
+
+    /// `connect()`, with the receiver wrapped as a `futures_util::Stream`.
+    pub async fn connect_stream(&mut self) -> Result<MarketDataStream, CctError> {
+        self.connect().await.map(MarketDataStream::new)
+    }
+
+    pub async fn subscribe(&mut self, stream: Stream) -> Result<(), CctError> {
+        if !self.subscriptions.contains(&stream) {
+            self.subscriptions.push(stream);
+        }
+        Ok(())
+    }
+
+    pub async fn subscribe_many(&mut self, streams: Vec<Stream>) -> Result<(), CctError> {
+        for stream in streams {
+            self.subscribe(stream).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&mut self, stream: &Stream) -> Result<(), CctError> {
+        self.subscriptions.retain(|subscribed| subscribed != stream);
+        Ok(())
+    }
+
+    /// Stops the replay; the receiver sees the end of the feed.
+    pub async fn disconnect(&mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    fn in_window(&self, event: &ReplayEvent) -> bool {
+        let Some(time) = event.time else { return true };
+        self.start.is_none_or(|start| time >= start) && self.end.is_none_or(|end| time <= end)
+    }
+}
+
+impl Drop for ReplayClient {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Sends `events` (once, or forever when looping), pacing them by their times.
+async fn replay(events: Vec<ReplayEvent>, speed: ReplaySpeed, looping: bool, tx: mpsc::Sender<MarketData>) {
+    let factor = match speed {
+        ReplaySpeed::AsFastAsPossible => None,
+        ReplaySpeed::RealTime => Some(1.0),
+        ReplaySpeed::Multiplier(factor) if factor > 0.0 => Some(factor),
+        ReplaySpeed::Multiplier(_) => None,
+    };
+    loop {
+        let started = tokio::time::Instant::now();
+        let first_time = events.iter().find_map(|event| event.time);
+        for event in &events {
+            if let (Some(factor), Some(first), Some(time)) = (factor, first_time, event.time) {
+                let offset = Duration::from_millis(time.saturating_sub(first)).div_f64(factor);
+                tokio::time::sleep_until(started + offset).await;
+            }
+            if tx.send(event.data.clone()).await.is_err() {
+                return;
+            }
+        }
+        if !looping || events.is_empty() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{Trade, TradeSide};
+    use crate::market::providers::binance::BinanceParser;
+    use crate::market::recorder::MarketDataRecorder;
+
+    fn trade(timestamp: u64, price: f64) -> MarketData {
+        MarketData::Trade(Trade::new(timestamp, "BTCUSDT", price, 1.0, "t", TradeSide::Buy))
+    }
+
+    async fn prices(rx: &mut mpsc::Receiver<MarketData>, count: usize) -> Vec<f64> {
+        let mut prices = Vec::new();
+        for _ in 0..count {
+            let data = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+            prices.push(data.as_trade().unwrap().price);
+        }
+        prices
+    }
+
+    #[tokio::test]
+    async fn test_window_and_loop() {
+        let items = (1..=5).map(|i| trade(i * 1000, i as f64));
+        let mut client = ReplayClient::from_market_data(items).with_start(2000).with_end(3000).with_loop(true);
+        let mut rx = client.connect().await.unwrap();
+        assert_eq!(prices(&mut rx, 5).await, vec![2.0, 3.0, 2.0, 3.0, 2.0]);
+        assert!(client.is_connected());
+        client.disconnect().await;
+        assert!(!client.is_connected());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pacing_follows_timestamps() {
+        let items = vec![trade(10_000, 1.0), trade(12_000, 2.0), trade(16_000, 3.0)];
+        let mut client = ReplayClient::from_market_data(items).with_speed(ReplaySpeed::Multiplier(2.0));
+        let mut rx = client.connect().await.unwrap();
+
+        let started = tokio::time::Instant::now();
+        assert_eq!(prices(&mut rx, 1).await, vec![1.0]);
+        assert_eq!(prices(&mut rx, 1).await, vec![2.0]);
+        assert_eq!(started.elapsed(), Duration::from_secs(1));
+        assert_eq!(prices(&mut rx, 1).await, vec![3.0]);
+        assert_eq!(started.elapsed(), Duration::from_secs(3));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replays_a_recorder_capture() {
+        let dir = std::env::temp_dir().join(format!("cct-replay-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("capture.ndjson");
+        let _ = std::fs::remove_file(&path);
+
+        let mut recorder = MarketDataRecorder::new(&path);
+        for price in [1.0, 2.0] {
+            recorder.record(&trade(1, price).with_exchange("Binance")).await.unwrap();
+        }
+        recorder.close().await.unwrap();
+
+        let mut client = ReplayClient::from_file(&path).unwrap();
+        assert_eq!(client.len(), 2);
+        let mut rx = client.connect().await.unwrap();
+        let first = rx.recv().await.unwrap();
+        assert_eq!(first.exchange(), "Binance");
+        assert_eq!(first.as_trade().unwrap().side, TradeSide::Buy);
+        assert_eq!(prices(&mut rx, 1).await, vec![2.0]);
+        assert!(rx.recv().await.is_none());
+
+        std::fs::write(&path, "{\"type\":\"trade\"}\nnot json\n").unwrap();
+        let err = ReplayClient::from_file(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 1"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_raw_frames_are_parsed_and_tagged() {
+        let frames = [
+            r#"{"result":null,"id":1}"#,
+            r#"{"e":"trade","E":1672515782136,"s":"BTCUSDT","t":12345,"p":"0.001","q":"100","T":1672515782136,"m":true}"#,
+        ];
+        let mut client = ReplayClient::from_frames(&BinanceParser::new(), frames);
+        assert_eq!(client.name(), "Binance");
+        client.subscribe(Stream::trades("BTCUSDT")).await.unwrap();
+        let mut rx = client.connect().await.unwrap();
+        let data = rx.recv().await.unwrap();
+        assert_eq!(data.exchange(), "Binance");
+        assert_eq!(data.as_trade().unwrap().price, 0.001);
+        assert!(rx.recv().await.is_none());
+        assert_eq!(client.subscriptions(), vec![Stream::trades("BTCUSDT")]);
+    }
+}
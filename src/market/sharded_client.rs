@@ -0,0 +1,333 @@
+//! Spreads subscriptions over several connections when one connection's stream limit
+//! (`MessageParser::max_streams_per_connection`) isn't enough.
+
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::market::config::ClientConfig;
+use crate::market::data_stream::MarketDataStream;
+use crate::market::error::CctError;
+use crate::market::market_data::MarketData;
+use crate::market::message_parser::MessageParser;
+use crate::market::streams::Stream;
+use crate::market::websocket_client::{ReconnectPolicy, WebSocketClient};
+
+// Design: each shard is an ordinary WebSocketClient with its own receiver; a small
+// forwarding task per shard feeds the single receiver handed out by connect(). Shards
+// are only added, never merged: a shard emptied by unsubscribes is reused first.
+
+type ParserFactory<P> = Box<dyn Fn() -> P + Send + Sync>;
+
+/// One logical client over as many connections as the subscriptions need.
+pub struct ShardedClient<P: MessageParser> {
+    make_parser: ParserFactory<P>,
+    name: &'static str,
+    config: ClientConfig,
+    reconnect_policy: Option<ReconnectPolicy>,
+    max_streams_per_shard: usize,
+    shards: Vec<Shard<P>>,
+    // Weak, like WebSocketClient's: the merged feed ends once every forwarder is gone
+    market_data_tx: Option<mpsc::WeakSender<MarketData>>,
+}
+
+struct Shard<P: MessageParser> {
+    client: WebSocketClient<P>,
+    forwarder: JoinHandle<()>,
+}
+
+impl<P: MessageParser> ShardedClient<P> {
+    /// `make_parser` builds the parser for each new connection, e.g. `BinanceParser::new`.
+    pub fn new(make_parser: impl Fn() -> P + Send + Sync + 'static) -> Self {
+        Self::with_config(make_parser, ClientConfig::default())
+    }
+
+    /// Every shard uses `config`; the merged channel gets `config.market_data_capacity`.
+    pub fn with_config(make_parser: impl Fn() -> P + Send + Sync + 'static, config: ClientConfig) -> Self {
+        let probe = make_parser();
+        let max_streams_per_shard = probe.max_streams_per_connection().max(1);
+        Self {
+            name: probe.name(),
+            make_parser: Box::new(make_parser),
+            config,
+            reconnect_policy: None,
+            max_streams_per_shard,
+            shards: Vec::new(),
+            market_data_tx: None,
+        }
+    }
+
+    /// Applies `WebSocketClient::with_auto_reconnect` to every shard.
+    pub fn with_auto_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = Some(policy);
+        self
+    }
+
+    /// Caps streams per connection below the exchange's limit, e.g. to spread load.
+    /// Values below 1 are raised to 1.
+    pub fn with_max_streams_per_shard(mut self, max_streams: usize) -> Self {
+        self.max_streams_per_shard = max_streams.max(1);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !self.shards.is_empty() && self.shards.iter().all(|shard| shard.client.is_connected())
+    }
+
+    /// Union of all shards' subscriptions, in shard order.
+    pub fn subscriptions(&self) -> Vec<Stream> {
+        self.shards.iter().flat_map(|shard| shard.client.subscriptions()).collect()
+    }
+
+    /// Opens the first connection. Returns the receiver for every shard's data.
+    pub async fn connect(&mut self) -> Result<mpsc::Receiver<MarketData>, CctError> {
+        let (market_data_tx, market_data_rx) = mpsc::channel(self.config.market_data_capacity);
+        self.market_data_tx = Some(market_data_tx.downgrade());
+        self.open_shard(market_data_tx).await?;
+        Ok(market_data_rx)
+    }
+
+    /// `connect()`, with the receiver wrapped as a `futures_util::Stream`.
+    pub async fn connect_stream(&mut self) -> Result<MarketDataStream, CctError> {
+        self.connect().await.map(MarketDataStream::new)
+    }
+
+    async fn open_shard(&mut self, market_data_tx: mpsc::Sender<MarketData>) -> Result<usize, CctError> {
+        let mut client = WebSocketClient::with_config((self.make_parser)(), self.config.clone());
+        if let Some(policy) = self.reconnect_policy {
+            client = client.with_auto_reconnect(policy);
+        }
+        let mut shard_rx = client.connect().await?;
+        let forwarder = tokio::spawn(async move {
+            while let Some(market_data) = shard_rx.recv().await {
+                if market_data_tx.send(market_data).await.is_err() {
+                    break;
+                }
+            }
+        });
+        self.shards.push(Shard { client, forwarder });
+        println!("[{}] Opened shard {}", self.name(), self.shards.len());
+        Ok(self.shards.len() - 1)
+    }
+
+    /// A shard with room for one more stream, opening a new connection if all are full.
+    async fn shard_with_room(&mut self) -> Result<usize, CctError> {
+        let limit = self.max_streams_per_shard;
+        if let Some(index) = self
+            .shards
+            .iter()
+            .position(|shard| shard.client.subscriptions().len() < limit)
+        {
+            return Ok(index);
+        }
+        let market_data_tx = self
+            .market_data_tx
+            .as_ref()
+            .and_then(mpsc::WeakSender::upgrade)
+            .ok_or(CctError::NotConnected)?;
+        self.open_shard(market_data_tx).await
+    }
+
+    fn owner(&self, stream: &Stream) -> Option<usize> {
+        self.shards.iter().position(|shard| shard.client.has_subscription(stream))
+    }
+
+    /// Subscribes on a shard with room, opening a connection if every shard is full.
+    pub async fn subscribe(&mut self, stream: Stream) -> Result<(), CctError> {
+        if self.shards.is_empty() {
+            return Err(CctError::NotConnected);
+        }
+        if self.owner(&stream).is_some() {
+            return Ok(());
+        }
+        let index = self.shard_with_room().await?;
+        self.shards[index].client.subscribe(stream).await
+    }
+
+    /// Fills shards in order, batching each shard's share into as few frames as the
+    /// parser allows. Stops at the first shard that fails.
+    pub async fn subscribe_many(&mut self, streams: Vec<Stream>) -> Result<(), CctError> {
+        if self.shards.is_empty() {
+            return Err(CctError::NotConnected);
+        }
+        let mut pending: Vec<Stream> = Vec::with_capacity(streams.len());
+        for stream in streams {
+            if self.owner(&stream).is_none() && !pending.contains(&stream) {
+                pending.push(stream);
+            }
+        }
+
+        while !pending.is_empty() {
+            let index = self.shard_with_room().await?;
+            let room = self.max_streams_per_shard - self.shards[index].client.subscriptions().len();
+            let rest = pending.split_off(room.min(pending.len()));
+            self.shards[index].client.subscribe_many(pending).await?;
+            pending = rest;
+        }
+        Ok(())
+    }
+
+    /// Unsubscribes on the shard that owns `stream`; a no-op if none does.
+    pub async fn unsubscribe(&mut self, stream: &Stream) -> Result<(), CctError> {
+        match self.owner(stream) {
+            Some(index) => self.shards[index].client.unsubscribe(stream).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Reconnects every shard; each keeps its subscriptions. Stops at the first failure.
+    pub async fn reconnect(&mut self) -> Result<(), CctError> {
+        for shard in &mut self.shards {
+            shard.client.reconnect().await?;
+        }
+        Ok(())
+    }
+
+    /// Disconnects every shard, waiting up to `timeout` for each (see
+    /// `WebSocketClient::disconnect_with_timeout`), and ends the merged feed.
+    pub async fn disconnect_with_timeout(&mut self, timeout: Duration) -> bool {
+        let mut finished = true;
+        for shard in &mut self.shards {
+            finished &= shard.client.disconnect_with_timeout(timeout).await;
+        }
+        for shard in self.shards.drain(..) {
+            // Anything already forwarded stays in the merged channel
+            let _ = shard.forwarder.await;
+        }
+        self.market_data_tx = None;
+        finished
+    }
+
+    pub async fn disconnect(&mut self) {
+        for shard in &mut self.shards {
+            shard.client.disconnect().await;
+        }
+        for shard in self.shards.drain(..) {
+            let _ = shard.forwarder.await;
+        }
+        self.market_data_tx = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::{Trade, TradeSide};
+    use futures_util::{SinkExt, StreamExt};
+    use std::sync::{Arc, Mutex};
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// "sub:SYMBOL" / "unsub:SYMBOL"; parses {"s":SYMBOL,"p":PRICE}. Two streams per connection.
+    struct TinyParser {
+        endpoint: String,
+    }
+
+    impl MessageParser for TinyParser {
+        fn endpoint(&self) -> &str {
+            &self.endpoint
+        }
+
+        fn format_subscribe(&self, stream: &Stream) -> String {
+            format!("sub:{}", stream.symbol())
+        }
+
+        fn format_unsubscribe(&self, stream: &Stream) -> String {
+            format!("unsub:{}", stream.symbol())
+        }
+
+        fn parse_message(&self, msg: &str) -> Option<MarketData> {
+            let value: serde_json::Value = serde_json::from_str(msg).ok()?;
+            let trade = Trade::new(0, value["s"].as_str()?, value["p"].as_f64()?, 1.0, "t", TradeSide::Buy);
+            Some(MarketData::Trade(trade))
+        }
+
+        fn max_streams_per_connection(&self) -> usize {
+            2
+        }
+
+        fn name(&self) -> &'static str {
+            "Tiny"
+        }
+    }
+
+    /// Every request received, as (connection index, frame). Each subscribe is answered
+    /// with a trade whose price is the connection index.
+    type Log = Arc<Mutex<Vec<(usize, String)>>>;
+
+    async fn spawn_server() -> (String, Log) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let log: Log = Arc::default();
+        let server_log = Arc::clone(&log);
+        tokio::spawn(async move {
+            for index in 0.. {
+                let Ok((socket, _)) = listener.accept().await else { break };
+                let log = Arc::clone(&server_log);
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+                    while let Some(Ok(Message::Text(text))) = ws.next().await {
+                        log.lock().unwrap().push((index, text.to_string()));
+                        if let Some(symbol) = text.strip_prefix("sub:") {
+                            let trade = format!(r#"{{"s":"{}","p":{}.0}}"#, symbol, index);
+                            ws.send(Message::Text(trade.into())).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        (endpoint, log)
+    }
+
+    #[tokio::test]
+    async fn test_opens_shards_past_the_limit_and_routes_unsubscribes() {
+        let (endpoint, log) = spawn_server().await;
+        let mut client = ShardedClient::new(move || TinyParser { endpoint: endpoint.clone() });
+        assert!(matches!(client.subscribe(Stream::trades("A")).await, Err(CctError::NotConnected)));
+
+        let mut rx = client.connect().await.unwrap();
+        client.subscribe(Stream::trades("A")).await.unwrap();
+        client
+            .subscribe_many(vec![Stream::trades("B"), Stream::trades("C"), Stream::trades("A")])
+            .await
+            .unwrap();
+        client.subscribe(Stream::trades("D")).await.unwrap();
+        client.subscribe(Stream::trades("E")).await.unwrap();
+        assert_eq!(client.shard_count(), 3);
+        let symbols: Vec<String> = client.subscriptions().iter().map(|s| s.symbol().to_string()).collect();
+        assert_eq!(symbols, vec!["A", "B", "C", "D", "E"]);
+
+        // One trade per subscription, from whichever connection owns it
+        let mut prices = std::collections::HashMap::new();
+        for _ in 0..5 {
+            let data = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap();
+            let Some(MarketData::Trade(trade)) = data else { panic!("expected a trade") };
+            prices.insert(trade.symbol, trade.price);
+        }
+        assert_eq!(prices["A"], 0.0);
+        assert_eq!(prices["C"], 1.0);
+        assert_eq!(prices["E"], 2.0);
+
+        // Freed room is reused before opening another connection
+        client.unsubscribe(&Stream::trades("C")).await.unwrap();
+        client.subscribe(Stream::trades("F")).await.unwrap();
+        assert_eq!(client.shard_count(), 3);
+
+        client.disconnect().await;
+        assert_eq!(client.shard_count(), 0);
+        assert!(rx.recv().await.is_some()); // F's trade was already forwarded
+        assert!(rx.recv().await.is_none());
+
+        let log = log.lock().unwrap().clone();
+        assert!(log.contains(&(1, "unsub:C".to_string())));
+        assert!(log.contains(&(1, "sub:F".to_string())));
+    }
+}
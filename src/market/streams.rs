@@ -1,24 +1,39 @@
 //! Stream types for WebSocket subscriptions.
 
+use serde::{Deserialize, Serialize};
+
 use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::MarketData;
 
 /// Represents different types of market data streams.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Serializes internally tagged, e.g. `{"type":"candles","symbol":"BTCUSDT","interval":"1m"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Stream {
     /// Candlestick/Kline data stream
     Candles { symbol: String, interval: Timeframe },
     /// Real-time trade stream
     Trades { symbol: String },
+    /// Aggregated trade stream: fills of one taker order at the same price
+    /// arrive as a single trade. Lighter than `Trades` on busy symbols.
+    AggTrades { symbol: String },
     /// Funding rate stream (futures).
     /// Note: Some exchanges (e.g., Binance) provide funding via the mark price stream.
     Funding { symbol: String },
-
-    // Future use
     /// Mark price stream (futures).
-    /// Some exchanges may map this to the same underlying channel as funding.
+    /// Some exchanges map this to the same underlying channel as funding; those
+    /// deliver both `MarketData::Funding` and `MarketData::MarkPrice` for either.
     MarkPrice { symbol: String },
     /// Order book depth stream
     OrderBook { symbol: String, depth: u16 },
+    /// Best bid/ask stream (top of book only)
+    BookTicker { symbol: String },
+    /// Rolling 24h statistics stream
+    Ticker { symbol: String },
+    /// Market-wide 24h mini tickers: every symbol that changed, in one frame.
+    /// Has no symbol of its own.
+    AllMiniTickers,
     /// Open interest stream (futures)
     OpenInterest { symbol: String },
     /// Liquidation stream (futures)
@@ -41,6 +56,27 @@ impl Stream {
         }
     }
 
+    /// Creates a new aggregated trades stream subscription.
+    pub fn agg_trades(symbol: impl Into<String>) -> Self {
+        Self::AggTrades {
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Creates a new funding rate stream subscription (futures).
+    pub fn funding(symbol: impl Into<String>) -> Self {
+        Self::Funding {
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Creates a new mark price stream subscription (futures).
+    pub fn mark_price(symbol: impl Into<String>) -> Self {
+        Self::MarkPrice {
+            symbol: symbol.into(),
+        }
+    }
+
     /// Creates a new order book stream subscription.
     pub fn order_book(symbol: impl Into<String>, depth: u16) -> Self {
         debug_assert!(depth > 0, "order book depth must be greater than zero");
@@ -50,16 +86,130 @@ impl Stream {
         }
     }
 
-    /// Returns the symbol for this stream.
+    /// Creates a new best bid/ask stream subscription.
+    pub fn book_ticker(symbol: impl Into<String>) -> Self {
+        Self::BookTicker {
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Creates a new 24h ticker stream subscription.
+    pub fn ticker(symbol: impl Into<String>) -> Self {
+        Self::Ticker {
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Creates a new open interest stream subscription (futures).
+    pub fn open_interest(symbol: impl Into<String>) -> Self {
+        Self::OpenInterest {
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Creates a new liquidation stream subscription (futures).
+    pub fn liquidations(symbol: impl Into<String>) -> Self {
+        Self::Liquidations {
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Returns the symbol for this stream, or "" for market-wide streams.
     pub fn symbol(&self) -> &str {
         match self {
             Stream::Candles { symbol, .. } => symbol,
             Stream::Trades { symbol } => symbol,
+            Stream::AggTrades { symbol } => symbol,
             Stream::Funding { symbol } => symbol,
             Stream::MarkPrice { symbol } => symbol,
             Stream::OrderBook { symbol, .. } => symbol,
+            Stream::BookTicker { symbol } => symbol,
+            Stream::Ticker { symbol } => symbol,
+            Stream::AllMiniTickers => "",
             Stream::OpenInterest { symbol } => symbol,
             Stream::Liquidations { symbol } => symbol,
         }
     }
+
+    /// Whether `data` is an item this stream delivers: same kind and symbol
+    /// (ignoring case), and for candles the same interval.
+    ///
+    /// Parsed data can't tell some streams apart: `Trades` and `AggTrades` both
+    /// match any trade, and `AllMiniTickers` any ticker. Order book depth is not checked.
+    pub fn matches(&self, data: &MarketData) -> bool {
+        let kind_matches = match (self, data) {
+            (Stream::Candles { interval, .. }, MarketData::Candle { interval: candle_interval, .. }) => {
+                interval == candle_interval
+            }
+            (Stream::Trades { .. } | Stream::AggTrades { .. }, MarketData::Trade(_)) => true,
+            (Stream::Funding { .. }, MarketData::Funding(_)) => true,
+            (Stream::MarkPrice { .. }, MarketData::MarkPrice(_)) => true,
+            (Stream::OrderBook { .. }, MarketData::OrderBook(_)) => true,
+            (Stream::BookTicker { .. }, MarketData::BookTicker(_)) => true,
+            (Stream::Ticker { .. }, MarketData::Ticker(_)) => true,
+            (Stream::AllMiniTickers, MarketData::Ticker(_)) => return true,
+            (Stream::Liquidations { .. }, MarketData::Liquidation(_)) => true,
+            (Stream::OpenInterest { .. }, MarketData::OpenInterest(_)) => true,
+            _ => false,
+        };
+        kind_matches && self.symbol().eq_ignore_ascii_case(data.symbol())
+    }
+}
+
+/// Human-readable stream name, e.g. "BTCUSDT candles 1m" or "BTCUSDT order book 20".
+impl std::fmt::Display for Stream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stream::Candles { symbol, interval } => write!(f, "{} candles {}", symbol, interval),
+            Stream::Trades { symbol } => write!(f, "{} trades", symbol),
+            Stream::AggTrades { symbol } => write!(f, "{} agg trades", symbol),
+            Stream::Funding { symbol } => write!(f, "{} funding", symbol),
+            Stream::MarkPrice { symbol } => write!(f, "{} mark price", symbol),
+            Stream::OrderBook { symbol, depth } => write!(f, "{} order book {}", symbol, depth),
+            Stream::BookTicker { symbol } => write!(f, "{} book ticker", symbol),
+            Stream::Ticker { symbol } => write!(f, "{} ticker", symbol),
+            Stream::AllMiniTickers => f.write_str("all mini tickers"),
+            Stream::OpenInterest { symbol } => write!(f, "{} open interest", symbol),
+            Stream::Liquidations { symbol } => write!(f, "{} liquidations", symbol),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::candle::Candle;
+    use crate::market::market_data::{FundingRate, MarkPrice, OrderBookUpdate, Trade, TradeSide};
+
+    #[test]
+    fn test_matches_kind_symbol_and_interval() {
+        let candle = MarketData::candle("BTCUSDT", Timeframe::M1, Candle::new(0, 1.0, 1.0, 1.0, 1.0, 1.0), true);
+        assert!(Stream::candles("btcusdt", Timeframe::M1).matches(&candle));
+        assert!(!Stream::candles("BTCUSDT", Timeframe::M5).matches(&candle));
+        assert!(!Stream::trades("BTCUSDT").matches(&candle));
+
+        let trade = MarketData::Trade(Trade::new(0, "ETHUSDT", 1.0, 1.0, "t", TradeSide::Buy));
+        assert!(Stream::trades("ETHUSDT").matches(&trade));
+        assert!(Stream::agg_trades("ETHUSDT").matches(&trade));
+        assert!(!Stream::trades("BTCUSDT").matches(&trade));
+
+        let book = MarketData::OrderBook(OrderBookUpdate::delta(0, "BTCUSDT", vec![], vec![]));
+        assert!(Stream::order_book("BTCUSDT", 20).matches(&book));
+        assert!(!Stream::book_ticker("BTCUSDT").matches(&book));
+
+        let funding = MarketData::Funding(FundingRate::new(0, "BTCUSDT", 0.0001));
+        let mark = MarketData::MarkPrice(MarkPrice::new(0, "BTCUSDT", 50000.0));
+        assert!(Stream::funding("BTCUSDT").matches(&funding));
+        assert!(!Stream::funding("BTCUSDT").matches(&mark));
+        assert!(Stream::mark_price("BTCUSDT").matches(&mark));
+        assert!(!Stream::mark_price("BTCUSDT").matches(&funding));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Stream::candles("BTCUSDT", Timeframe::M1).to_string(), "BTCUSDT candles 1m");
+        assert_eq!(Stream::order_book("ETHUSDT", 20).to_string(), "ETHUSDT order book 20");
+        assert_eq!(Stream::mark_price("BTCUSDT").to_string(), "BTCUSDT mark price");
+        assert_eq!(Stream::AllMiniTickers.to_string(), "all mini tickers");
+    }
 }
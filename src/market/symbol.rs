@@ -0,0 +1,217 @@
+//! `Symbol`: a shared, immutable symbol name, and `SymbolCache` to intern them.
+//!
+//! A feed carries a handful of distinct symbols but every parsed item names one,
+//! so items share one `Arc<str>` per symbol instead of allocating a `String` each.
+//! Cloning a `Symbol` is a reference count bump.
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Distinct symbols a `SymbolCache::default()` keeps. Binance lists a few
+/// thousand, so even `AllMiniTickers` fits.
+pub const DEFAULT_SYMBOL_CACHE_CAPACITY: usize = 4096;
+
+/// Symbol name, e.g. "BTCUSDT". Derefs to `str` and compares equal to string types.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether both share one allocation (e.g. came from the same `SymbolCache`).
+    pub fn ptr_eq(&self, other: &Symbol) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Symbol(Arc::from(value))
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Self {
+        Symbol(Arc::from(value))
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(value: &String) -> Self {
+        Symbol::from(value.as_str())
+    }
+}
+
+impl From<Arc<str>> for Symbol {
+    fn from(value: Arc<str>) -> Self {
+        Symbol(value)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(value: Symbol) -> Self {
+        value.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other.as_str()
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        self.as_str() == &*other.0
+    }
+}
+
+/// Same as `str`'s, so `Symbol`s print like the `String`s they replaced.
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Symbol::from)
+    }
+}
+
+/// Interns symbol names so repeated ones share a single allocation.
+///
+/// Clones share the cache. Once `capacity` distinct symbols are held, new ones
+/// are still returned but no longer cached, so a misbehaving feed can't grow it
+/// without bound.
+#[derive(Debug, Clone)]
+pub struct SymbolCache {
+    symbols: Arc<Mutex<HashSet<Symbol>>>,
+    capacity: usize,
+}
+
+impl Default for SymbolCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SYMBOL_CACHE_CAPACITY)
+    }
+}
+
+impl SymbolCache {
+    /// A cache holding up to `capacity` symbols; 0 turns interning off.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            symbols: Arc::new(Mutex::new(HashSet::new())),
+            capacity,
+        }
+    }
+
+    pub fn intern(&self, name: &str) -> Symbol {
+        let mut symbols = self.symbols.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(symbol) = symbols.get(name) {
+            return symbol.clone();
+        }
+        let symbol = Symbol::from(name);
+        if symbols.len() < self.capacity {
+            symbols.insert(symbol.clone());
+        }
+        symbol
+    }
+
+    /// Number of cached symbols.
+    pub fn len(&self) -> usize {
+        self.symbols.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_compares_and_prints_like_a_string() {
+        let symbol = Symbol::from("BTCUSDT");
+        assert_eq!(symbol, "BTCUSDT");
+        assert_eq!("BTCUSDT", symbol);
+        assert_eq!(symbol, "BTCUSDT".to_string());
+        assert_eq!(format!("{:?}", symbol), "\"BTCUSDT\"");
+        assert_eq!(symbol.to_string(), "BTCUSDT");
+        assert!(symbol.eq_ignore_ascii_case("btcusdt"));
+        assert_eq!(serde_json::to_string(&symbol).unwrap(), "\"BTCUSDT\"");
+    }
+
+    #[test]
+    fn test_cache_shares_allocations_up_to_capacity() {
+        let cache = SymbolCache::new(1);
+        let first = cache.intern("BTCUSDT");
+        assert!(first.ptr_eq(&cache.clone().intern("BTCUSDT")));
+
+        // Full: still returned, just not shared
+        let eth = cache.intern("ETHUSDT");
+        assert_eq!(eth, "ETHUSDT");
+        assert!(!eth.ptr_eq(&cache.intern("ETHUSDT")));
+        assert_eq!(cache.len(), 1);
+
+        assert!(SymbolCache::new(0).intern("BTCUSDT") == "BTCUSDT");
+    }
+}
@@ -0,0 +1,218 @@
+//! Timestamp normalization to the crate's convention: ms since the Unix epoch.
+//!
+//! Exchanges send seconds (Gate.io, MEXC klines), milliseconds (Binance),
+//! nanoseconds (KuCoin trades) or RFC 3339 strings. Providers convert with
+//! `normalize_to_ms` when the unit is documented, and `parse_timestamp` /
+//! `auto_to_ms` when it isn't.
+
+/// Unit of an integer timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampUnit {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimestampUnit {
+    /// Guesses the unit from the magnitude of a present-day timestamp:
+    /// 10 digits is seconds, 13 milliseconds, 16 microseconds, 19 nanoseconds.
+    /// The cut-offs sit between those, so anything from 1973 to 2286 is read right.
+    pub fn detect(value: u64) -> TimestampUnit {
+        match value {
+            0..100_000_000_000 => TimestampUnit::Seconds,
+            100_000_000_000..100_000_000_000_000 => TimestampUnit::Millis,
+            100_000_000_000_000..100_000_000_000_000_000 => TimestampUnit::Micros,
+            _ => TimestampUnit::Nanos,
+        }
+    }
+}
+
+/// Converts `value` in `unit` to ms, truncating sub-ms precision. Saturates
+/// instead of overflowing.
+pub fn normalize_to_ms(value: u64, unit: TimestampUnit) -> u64 {
+    match unit {
+        TimestampUnit::Seconds => value.saturating_mul(1_000),
+        TimestampUnit::Millis => value,
+        TimestampUnit::Micros => value / 1_000,
+        TimestampUnit::Nanos => value / 1_000_000,
+    }
+}
+
+/// `normalize_to_ms` with the unit from `TimestampUnit::detect`.
+pub fn auto_to_ms(value: u64) -> u64 {
+    normalize_to_ms(value, TimestampUnit::detect(value))
+}
+
+/// Parses a timestamp string to ms: an integer in any unit (detected), a
+/// fractional one ("1606292218.123", detected on the integer part), or RFC 3339
+/// ("2024-06-01T00:00:03.827Z", "2024-06-01T02:00:03+02:00").
+pub fn parse_timestamp(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(integer) = value.parse::<u64>() {
+        return Some(auto_to_ms(integer));
+    }
+    if let Some((whole, fraction)) = value.split_once('.')
+        && let Ok(whole) = whole.parse::<u64>()
+        && !fraction.is_empty()
+        && fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        let unit = TimestampUnit::detect(whole);
+        let millis = normalize_to_ms(whole, unit);
+        // Only seconds have a fraction that reaches into whole milliseconds
+        let fraction_ms = match unit {
+            TimestampUnit::Seconds => fraction_to_ms(fraction),
+            _ => 0,
+        };
+        return Some(millis + fraction_ms);
+    }
+    parse_rfc3339(value)
+}
+
+/// Parses an RFC 3339 / ISO 8601 date-time with a "Z" or "±HH:MM" offset to ms.
+/// Fractional seconds beyond ms are truncated.
+pub fn parse_rfc3339(value: &str) -> Option<u64> {
+    let bytes = value.as_bytes();
+    if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-' || !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    let year = digits(value, 0, 4)?;
+    let month = digits(value, 5, 7)?;
+    let day = digits(value, 8, 10)?;
+    if bytes[13] != b':' || bytes[16] != b':' {
+        return None;
+    }
+    let hour = digits(value, 11, 13)?;
+    let minute = digits(value, 14, 16)?;
+    let second = digits(value, 17, 19)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut rest = &value[19..];
+    let mut millis = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return None;
+        }
+        millis = fraction_to_ms(&fraction[..len]);
+        rest = &fraction[len..];
+    }
+
+    let offset_secs: i64 = match rest.as_bytes() {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let hours = digits(rest, 1, 3)? as i64;
+            let minutes = digits(rest, 4, 6)? as i64;
+            let offset = hours * 3_600 + minutes * 60;
+            if *sign == b'+' { offset } else { -offset }
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year as i64, month, day);
+    let secs = days * 86_400 + (hour * 3_600 + minute * 60 + second) as i64 - offset_secs;
+    u64::try_from(secs).ok().map(|secs| secs * 1_000 + millis)
+}
+
+/// The numeric value of `value[start..end]`, which must be all ASCII digits.
+fn digits(value: &str, start: usize, end: usize) -> Option<u32> {
+    let field = value.get(start..end)?;
+    if !field.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    field.parse().ok()
+}
+
+/// Milliseconds in a decimal fraction of a second, e.g. "5" -> 500, "123456" -> 123.
+fn fraction_to_ms(fraction: &str) -> u64 {
+    let padded = format!("{:0<3}", &fraction[..fraction.len().min(3)]);
+    padded.parse().unwrap_or(0)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// (year, month, day) of a day count since 1970-01-01; the inverse of `days_from_civil`.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-06-01T00:00:03.827Z
+    const MS: u64 = 1_717_200_003_827;
+
+    #[test]
+    fn test_detect_and_normalize_every_unit() {
+        assert_eq!(TimestampUnit::detect(1_717_200_003), TimestampUnit::Seconds);
+        assert_eq!(TimestampUnit::detect(MS), TimestampUnit::Millis);
+        assert_eq!(TimestampUnit::detect(1_717_200_003_827_123), TimestampUnit::Micros);
+        assert_eq!(TimestampUnit::detect(1_717_200_003_827_123_456), TimestampUnit::Nanos);
+
+        assert_eq!(auto_to_ms(1_717_200_003), 1_717_200_003_000);
+        assert_eq!(auto_to_ms(MS), MS);
+        assert_eq!(auto_to_ms(1_717_200_003_827_123), MS);
+        assert_eq!(auto_to_ms(1_717_200_003_827_123_456), MS);
+
+        assert_eq!(normalize_to_ms(5, TimestampUnit::Seconds), 5_000);
+        assert_eq!(normalize_to_ms(u64::MAX, TimestampUnit::Seconds), u64::MAX);
+    }
+
+    #[test]
+    fn test_parse_numeric_strings() {
+        assert_eq!(parse_timestamp("1717200003"), Some(1_717_200_003_000));
+        assert_eq!(parse_timestamp(" 1717200003827 "), Some(MS));
+        assert_eq!(parse_timestamp("1717200003827123456"), Some(MS));
+        assert_eq!(parse_timestamp("1717200003.827"), Some(MS));
+        assert_eq!(parse_timestamp("1717200003.8"), Some(1_717_200_003_800));
+        // Fractional ms (Gate.io create_time_ms)
+        assert_eq!(parse_timestamp("1717200003827.4578"), Some(MS));
+        assert_eq!(parse_timestamp("not a time"), None);
+    }
+
+    #[test]
+    fn test_civil_from_days_inverts_days_from_civil() {
+        for (year, month, day) in [(1970, 1, 1), (2000, 2, 29), (2023, 12, 31), (2024, 3, 1), (1969, 12, 31), (2100, 3, 1)] {
+            assert_eq!(civil_from_days(days_from_civil(year, month, day)), (year, month, day));
+        }
+        assert_eq!(civil_from_days(MS as i64 / 86_400_000), (2024, 6, 1));
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_timestamp("2024-06-01T00:00:03.827Z"), Some(MS));
+        assert_eq!(parse_rfc3339("2024-06-01T00:00:03.827123Z"), Some(MS));
+        assert_eq!(parse_rfc3339("2024-06-01T02:00:03.827+02:00"), Some(MS));
+        assert_eq!(parse_rfc3339("2024-05-31T19:00:03.827-05:00"), Some(MS));
+        assert_eq!(parse_rfc3339("2024-06-01T00:00:03Z"), Some(1_717_200_003_000));
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        // Leap day
+        assert_eq!(parse_rfc3339("2024-02-29T00:00:00Z"), Some(1_709_164_800_000));
+
+        assert_eq!(parse_rfc3339("2024-06-01T00:00:03"), None);
+        assert_eq!(parse_rfc3339("2024-13-01T00:00:03Z"), None);
+        assert_eq!(parse_rfc3339("2024-06-01T00:00:03.Z"), None);
+        assert_eq!(parse_rfc3339("1969-12-31T23:59:59Z"), None);
+    }
+}
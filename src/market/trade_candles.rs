@@ -0,0 +1,211 @@
+//! Candles built locally from a trade stream, for symbols or exchanges without a
+//! candle channel.
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::Trade;
+
+/// The candle being built: OHLCV plus the times of the trades that set open and close.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    start: u64,
+    open: (u64, f64),
+    close: (u64, f64),
+    high: f64,
+    low: f64,
+    volume: f64,
+}
+
+impl Bucket {
+    fn start(start: u64, trade: &Trade) -> Self {
+        Self {
+            start,
+            open: (trade.timestamp, trade.price),
+            close: (trade.timestamp, trade.price),
+            high: trade.price,
+            low: trade.price,
+            volume: trade.quantity,
+        }
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        // A trade that arrives late still belongs to the bucket: it moves open or
+        // close only if it is earlier or later than the ones that set them
+        if trade.timestamp < self.open.0 {
+            self.open = (trade.timestamp, trade.price);
+        }
+        if trade.timestamp >= self.close.0 {
+            self.close = (trade.timestamp, trade.price);
+        }
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.volume += trade.quantity;
+    }
+
+    fn to_candle(self) -> Candle {
+        Candle::new(self.start, self.open.1, self.high, self.low, self.close.1, self.volume)
+    }
+}
+
+/// Builds OHLCV candles of one symbol from its trades.
+///
+/// Trades are bucketed by `Timeframe::align` of their timestamp. The candle for a
+/// bucket is returned when the first trade of a later bucket arrives, so it is
+/// final; `current()` shows the one still open. Intervals without trades produce
+/// no candle, unless trades are fed with `push_trade_with_gaps`.
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    timeframe: Timeframe,
+    bucket: Option<Bucket>,
+}
+
+impl CandleAggregator {
+    pub fn new(timeframe: Timeframe) -> Self {
+        Self { timeframe, bucket: None }
+    }
+
+    pub fn timeframe(&self) -> Timeframe {
+        self.timeframe
+    }
+
+    /// Adds a trade. Returns the previous candle once a trade crosses into a later
+    /// bucket. Trades older than the open bucket are ignored; late trades within it
+    /// count toward high, low and volume.
+    pub fn push_trade(&mut self, trade: &Trade) -> Option<Candle> {
+        let start = self.timeframe.align(trade.timestamp);
+
+        let Some(bucket) = &mut self.bucket else {
+            self.bucket = Some(Bucket::start(start, trade));
+            return None;
+        };
+        if start < bucket.start {
+            return None;
+        }
+        if start == bucket.start {
+            bucket.add(trade);
+            return None;
+        }
+
+        let closed = bucket.to_candle();
+        *bucket = Bucket::start(start, trade);
+        Some(closed)
+    }
+
+    /// `push_trade`, plus a flat zero-volume candle at the previous close for every
+    /// interval the trade skipped over. Keeps the output one candle per interval.
+    pub fn push_trade_with_gaps(&mut self, trade: &Trade) -> Vec<Candle> {
+        let Some(closed) = self.push_trade(trade) else {
+            return Vec::new();
+        };
+        let next = self.bucket.map_or(closed.get_timestamp(), |bucket| bucket.start);
+        let close = closed.get_close();
+
+        let skipped = self.timeframe.buckets_between(self.timeframe.next_boundary(closed.get_timestamp()), next);
+        let flat = skipped.map(|open| Candle::new(open, close, close, close, close, 0.0));
+        std::iter::once(closed).chain(flat).collect()
+    }
+
+    /// The open (unfinished) candle.
+    pub fn current(&self) -> Option<Candle> {
+        self.bucket.map(Bucket::to_candle)
+    }
+
+    /// Closes and returns the open candle, e.g. at the end of a replay.
+    pub fn flush(&mut self) -> Option<Candle> {
+        self.bucket.take().map(Bucket::to_candle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::market_data::TradeSide;
+
+    const MINUTE: u64 = 60_000;
+
+    fn trade(timestamp: u64, price: f64, quantity: f64) -> Trade {
+        Trade::new(timestamp, "BTCUSDT", price, quantity, "t", TradeSide::Buy)
+    }
+
+    fn ohlcv(candle: &Candle) -> (u64, f64, f64, f64, f64, f64) {
+        let c = candle;
+        (c.get_timestamp(), c.get_open(), c.get_high(), c.get_low(), c.get_close(), c.get_volume())
+    }
+
+    #[test]
+    fn test_trades_across_three_buckets() {
+        let mut aggregator = CandleAggregator::new(Timeframe::M1);
+        let script = [
+            trade(1_000, 100.0, 1.0),
+            trade(20_000, 103.0, 0.5),
+            trade(45_000, 98.0, 2.0),
+            trade(59_999, 101.0, 1.0),
+            // Bucket 1 closes bucket 0
+            trade(MINUTE, 102.0, 1.0),
+            trade(MINUTE + 30_000, 104.0, 1.0),
+            // Bucket 2 closes bucket 1
+            trade(2 * MINUTE + 10, 105.0, 3.0),
+        ];
+        let closed: Vec<Candle> = script.iter().filter_map(|t| aggregator.push_trade(t)).collect();
+
+        assert_eq!(closed.len(), 2);
+        assert_eq!(ohlcv(&closed[0]), (0, 100.0, 103.0, 98.0, 101.0, 4.5));
+        assert_eq!(ohlcv(&closed[1]), (MINUTE, 102.0, 104.0, 102.0, 104.0, 2.0));
+        assert_eq!(ohlcv(&aggregator.current().unwrap()), (2 * MINUTE, 105.0, 105.0, 105.0, 105.0, 3.0));
+
+        assert!(aggregator.flush().is_some());
+        assert!(aggregator.current().is_none());
+    }
+
+    #[test]
+    fn test_out_of_order_trades() {
+        let mut aggregator = CandleAggregator::new(Timeframe::M1);
+        aggregator.push_trade(&trade(30_000, 100.0, 1.0));
+        // Late but within the bucket: earlier than the open, so it becomes the open
+        aggregator.push_trade(&trade(10_000, 95.0, 1.0));
+        // Earlier than the last trade: not the close
+        aggregator.push_trade(&trade(40_000, 101.0, 1.0));
+        aggregator.push_trade(&trade(35_000, 110.0, 1.0));
+        assert_eq!(ohlcv(&aggregator.current().unwrap()), (0, 95.0, 110.0, 95.0, 101.0, 4.0));
+
+        aggregator.push_trade(&trade(MINUTE, 100.0, 1.0));
+        // Older than the open bucket: dropped
+        assert!(aggregator.push_trade(&trade(50_000, 1.0, 1.0)).is_none());
+        assert_eq!(aggregator.current().unwrap().get_low(), 100.0);
+    }
+
+    #[test]
+    fn test_gaps_omitted_or_filled() {
+        let mut omit = CandleAggregator::new(Timeframe::M1);
+        omit.push_trade(&trade(0, 100.0, 1.0));
+        let closed = omit.push_trade(&trade(3 * MINUTE, 110.0, 1.0)).unwrap();
+        assert_eq!(closed.get_timestamp(), 0);
+
+        let mut fill = CandleAggregator::new(Timeframe::M1);
+        assert!(fill.push_trade_with_gaps(&trade(0, 100.0, 1.0)).is_empty());
+        let candles = fill.push_trade_with_gaps(&trade(3 * MINUTE, 110.0, 1.0));
+        let timestamps: Vec<u64> = candles.iter().map(Candle::get_timestamp).collect();
+        assert_eq!(timestamps, vec![0, MINUTE, 2 * MINUTE]);
+        assert_eq!(ohlcv(&candles[2]), (2 * MINUTE, 100.0, 100.0, 100.0, 100.0, 0.0));
+    }
+
+    #[test]
+    fn test_weekly_buckets_open_on_monday() {
+        const DAY: u64 = 86_400_000;
+        // 2024-01-01 (Monday) at 00:00 UTC
+        let monday = 19_723 * DAY;
+        let mut aggregator = CandleAggregator::new(Timeframe::W1);
+        aggregator.push_trade(&trade(monday + 2 * DAY, 100.0, 1.0));
+        // Sunday 23:59:59.999 is still the same week
+        aggregator.push_trade(&trade(monday + 7 * DAY - 1, 90.0, 1.0));
+        assert_eq!(aggregator.current().unwrap().get_timestamp(), monday);
+
+        // Exactly on the next Monday: a new bucket, and no flat weeks in between
+        let closed = aggregator.push_trade_with_gaps(&trade(monday + 7 * DAY, 95.0, 1.0));
+        assert_eq!(closed.len(), 1);
+        assert_eq!(ohlcv(&closed[0]), (monday, 100.0, 100.0, 90.0, 90.0, 2.0));
+        let skipped = aggregator.push_trade_with_gaps(&trade(monday + 21 * DAY + 5, 97.0, 1.0));
+        let opens: Vec<u64> = skipped.iter().map(Candle::get_timestamp).collect();
+        assert_eq!(opens, vec![monday + 7 * DAY, monday + 14 * DAY]);
+    }
+}
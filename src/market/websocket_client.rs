@@ -1,18 +1,30 @@
 //! Generic WebSocket client for exchange connections.
 //! See docs/market/README.md for architecture overview.
 
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
 
+use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::error::TrySendError;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Notify};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 
-use crate::market::market_data::MarketData;
-use crate::market::message_parser::MessageParser;
+use crate::market::config::{ClientConfig, OverflowPolicy};
+use crate::market::data_stream::MarketDataStream;
+use crate::market::delivery::{MarketDataSink, PauseGate, Routes};
+use crate::market::error::CctError;
+use crate::market::events::{ConnectionEvent, ConnectionState};
+use crate::market::latency::LatencyStats;
+use crate::market::market_data::{MarketData, now_ms};
+use crate::market::message_parser::{DecodedFrame, MessageParser};
+use crate::market::metrics::{ClientMetrics, ClientStats};
 use crate::market::streams::Stream;
 
 // Design: WebSocketClient<P: MessageParser> is generic over the parser type.
@@ -20,45 +32,296 @@ use crate::market::streams::Stream;
 // subscription tracking) while each exchange only implements MessageParser.
 // Adding a new exchange = implement ~6 methods in MessageParser, done.
 
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+// How long disconnect() waits for queued frames to go out and the server to acknowledge the close
+const DEFAULT_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+// Wait before trying again when opening the replacement socket for a refresh fails
+const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(30);
+// Streams per batched subscribe frame; keeps frames well below exchange size limits
+const SUBSCRIBE_BATCH_SIZE: usize = 100;
+
+// Subscriptions are shared with the read task so it can drop streams it gives up on
+type SharedSubscriptions = Arc<std::sync::Mutex<Vec<Stream>>>;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsWrite = SplitSink<WsStream, Message>;
+type WsRead = SplitStream<WsStream>;
+
+// Outgoing frames queue here for the lifetime of the client, not of one socket:
+// after an automatic reconnect the new write task picks up the same receiver,
+// so `ws_sender` (and everything holding a clone of it) keeps working.
+type Outbox = Arc<Mutex<mpsc::Receiver<Message>>>;
+
+/// What to do when a subscribed stream keeps failing to parse.
+/// Failures are attributed to a stream via `MessageParser::stream_hint`, so
+/// parsers without a hint are never flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamHealthPolicy {
+    /// A stream is broken after more than this many consecutive parse failures.
+    pub max_consecutive_failures: u32,
+    /// Unsubscribe the broken stream instead of only reporting it.
+    pub auto_unsubscribe: bool,
+}
+
+impl StreamHealthPolicy {
+    pub fn new(max_consecutive_failures: u32) -> Self {
+        Self {
+            max_consecutive_failures,
+            auto_unsubscribe: false,
+        }
+    }
+
+    pub fn with_auto_unsubscribe(mut self, auto_unsubscribe: bool) -> Self {
+        self.auto_unsubscribe = auto_unsubscribe;
+        self
+    }
+}
+
+impl Default for StreamHealthPolicy {
+    fn default() -> Self {
+        Self::new(50)
+    }
+}
+
+/// Backoff settings for automatic reconnection, see `WebSocketClient::with_auto_reconnect`.
+///
+/// The n-th consecutive attempt waits `base_delay * 2^n`, capped at `max_delay`,
+/// shortened by up to `jitter` (a fraction, 0.0..=1.0) so that many clients dropped
+/// at once don't reconnect in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Give up after this many consecutive failed attempts; None retries forever.
+    pub max_attempts: Option<u32>,
+    pub jitter: f64,
+    /// A connection that stayed up this long counts as healthy and resets the backoff.
+    pub reset_after: Duration,
+}
+
+impl ReconnectPolicy {
+    pub fn new(base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            base_delay,
+            max_delay,
+            max_attempts: None,
+            jitter: 0.2,
+            reset_after: Duration::from_secs(60),
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_reset_after(mut self, reset_after: Duration) -> Self {
+        self.reset_after = reset_after;
+        self
+    }
+
+    /// Delay before attempt `attempt` (0-based). `sample` in [0, 1) picks the jitter.
+    pub fn delay(&self, attempt: u32, sample: f64) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        delay.mul_f64(1.0 - self.jitter * sample.clamp(0.0, 1.0))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
 /// Generic WebSocket client that works with any exchange.
 /// Exchange-specific logic is provided by the MessageParser implementation.
 pub struct WebSocketClient<P: MessageParser> {
     parser: Arc<P>,
-    subscriptions: Vec<Stream>,
+    subscriptions: SharedSubscriptions,
     connected_at: Option<Instant>,  // for 24h reconnection limit tracking
-    is_connected: bool,
+    is_open: bool, // connect() succeeded and disconnect() hasn't been called since
+    state: watch::Sender<ConnectionState>,
     ws_sender: Option<mpsc::Sender<Message>>,
     read_handle: Option<JoinHandle<()>>, // handle for tasks
     write_handle: Option<JoinHandle<()>>, // handle for tasks
+    ping_handle: Option<JoinHandle<()>>, // only for exchanges with application-level pings
+    events_tx: broadcast::Sender<ConnectionEvent>,
+    stream_health: Option<StreamHealthPolicy>,
+    reconnect_policy: Option<ReconnectPolicy>,
+    // Weak so the caller's receiver still sees the end of the feed once the read task is gone
+    market_data_tx: Option<mpsc::WeakSender<MarketData>>,
+    closing: Arc<AtomicBool>, // set by disconnect() so the read task doesn't reconnect
+    heartbeat: Arc<Heartbeat>,
+    stale_after: Option<Duration>,
+    requests: Arc<PendingRequests>,
+    ack_timeout: Duration,
+    config: ClientConfig,
+    metrics: Arc<ClientMetrics>,
+    routes: Routes,
+    pause: Arc<PauseGate>,
 }
 // This WebSocket client works with any parser type, as long as that parser knows how to parse messages
 impl<P: MessageParser> WebSocketClient<P> {
     pub fn new(parser: P) -> Self {
+        Self::with_config(parser, ClientConfig::default())
+    }
+
+    /// Creates a client with custom channel sizes and overflow handling.
+    pub fn with_config(parser: P, config: ClientConfig) -> Self {
         Self {
             parser: Arc::new(parser),
-            subscriptions: Vec::new(),
+            subscriptions: Arc::default(),
             connected_at: None,
-            is_connected: false,
+            is_open: false,
+            state: watch::channel(ConnectionState::Disconnected).0,
             ws_sender: None,
             read_handle: None,
             write_handle: None,
+            ping_handle: None,
+            events_tx: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            stream_health: None,
+            reconnect_policy: None,
+            market_data_tx: None,
+            closing: Arc::new(AtomicBool::new(false)),
+            heartbeat: Arc::new(Heartbeat::new()),
+            stale_after: None,
+            requests: Arc::new(PendingRequests::new()),
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            pause: Arc::new(PauseGate::new(config.pause_policy, config.pause_buffer_capacity)),
+            config,
+            metrics: Arc::default(),
+            routes: Routes::default(),
         }
     }
 
+    /// Connects to `endpoint` instead of the parser's endpoint, e.g. a local mock
+    /// server or a regional domain. See `ClientConfig::endpoint_override`.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.config.endpoint_override = Some(endpoint.into());
+        self
+    }
+
+    /// How long `subscribe` waits for the exchange to acknowledge a request, for
+    /// parsers that tag requests with ids (`MessageParser::format_subscribe_with_id`).
+    /// Default: 10s.
+    pub fn with_ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
+
+    /// Drops the connection when no frame (data, control or ping) arrives for the
+    /// parser's `idle_timeout_secs()`. See `with_stale_after`.
+    pub fn with_stale_watchdog(self) -> Self {
+        let stale_after = Duration::from_secs(self.parser.idle_timeout_secs());
+        self.with_stale_after(stale_after)
+    }
+
+    /// Drops the connection when no frame arrives for `stale_after`, catching sockets
+    /// that stay open but have stopped delivering. The connection then ends like any
+    /// other dropped connection: `ConnectionEvent::Disconnected`, followed by a
+    /// reconnect if `with_auto_reconnect` is set.
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = Some(stale_after);
+        self
+    }
+
+    /// Reconnects automatically when the connection drops, restoring subscriptions
+    /// and feeding the receiver returned by `connect()`. `disconnect()` stops it.
+    pub fn with_auto_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = Some(policy);
+        self
+    }
+
+    /// Enables detection of streams whose messages repeatedly fail to parse.
+    /// Broken streams are reported as `ConnectionEvent::StreamBroken`.
+    pub fn with_stream_health(mut self, policy: StreamHealthPolicy) -> Self {
+        self.stream_health = Some(policy);
+        self
+    }
+
+    /// Returns a receiver for connection events. Events sent before this call are not replayed.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events_tx.subscribe()
+    }
+
     pub fn name(&self) -> &'static str {
         self.parser.name()
     }
 
+    /// The parser this client was built with, e.g. for its REST hooks (`fetch_candles`).
+    pub fn parser(&self) -> &P {
+        &self.parser
+    }
+
+    /// Whether the socket is up right now (see `state()`). False as soon as the
+    /// read task sees the connection drop, without waiting for a call on the client.
     pub fn is_connected(&self) -> bool {
-        self.is_connected
+        self.state().is_connected()
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *self.state.borrow()
+    }
+
+    /// A receiver for connection state changes, updated by the background tasks;
+    /// `changed().await` wakes as soon as the connection drops or comes back.
+    pub fn watch_state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.subscribe()
+    }
+
+    /// Time since the last frame of any kind was received, None before the first one.
+    pub fn last_message_age(&self) -> Option<Duration> {
+        self.heartbeat.age()
+    }
+
+    /// Counters for this client, accumulated across reconnects. Lock-free.
+    pub fn stats(&self) -> ClientStats {
+        self.metrics.snapshot()
+    }
+
+    /// Receive time minus exchange event time over the last `DEFAULT_LATENCY_WINDOW`
+    /// parsed items (see `LatencyTracker`). Sorts the window, so read it less often
+    /// than `stats()` on a hot path.
+    pub fn latency(&self) -> LatencyStats {
+        self.metrics.latency_stats()
+    }
+
+    /// Returns a copy of the active subscriptions.
+    pub fn subscriptions(&self) -> Vec<Stream> {
+        self.subscriptions_guard().clone()
     }
 
-    pub fn subscriptions(&self) -> &[Stream] {
-        &self.subscriptions
+    pub fn has_subscription(&self, stream: &Stream) -> bool {
+        self.subscriptions_guard().contains(stream)
     }
 
-    /// Checks if connection needs refresh (approaching 24h limit).
+    /// Active subscriptions for `symbol` (case-insensitive), in subscription order.
+    /// Returns copies, since the list is shared with the read task.
+    pub fn subscriptions_for_symbol(&self, symbol: &str) -> Vec<Stream> {
+        self.subscriptions_guard()
+            .iter()
+            .filter(|stream| stream.symbol().eq_ignore_ascii_case(symbol))
+            .cloned()
+            .collect()
+    }
+
+    fn subscriptions_guard(&self) -> MutexGuard<'_, Vec<Stream>> {
+        lock_subscriptions(&self.subscriptions)
+    }
+
+    /// Checks if connection needs refresh (approaching 24h limit), or has dropped
+    /// since `connect()` without an automatic reconnect under way.
     pub fn needs_reconnect(&self) -> bool {
+        if self.is_open && self.state() == ConnectionState::Disconnected {
+            return true;
+        }
         if let Some(connected_at) = self.connected_at {
             let max_duration = Duration::from_secs(self.parser.max_connection_duration_secs());
             connected_at.elapsed() > max_duration
@@ -70,193 +333,538 @@ impl<P: MessageParser> WebSocketClient<P> {
     /// Connects to the WebSocket endpoint.
     /// Spawns background tasks for message handling.
     /// Returns a receiver channel for market data.
-    pub async fn connect(&mut self) -> Result<mpsc::Receiver<MarketData>, Box<dyn std::error::Error + Send + Sync>> {
-        let endpoint = self.parser.endpoint();
-        
-        println!("[{}] Connecting to {}...", self.parser.name(), endpoint);
-
-        // Connect to the WebSocket endpoint (fallback if primary fails)
-        let (ws_stream, _response) = match connect_async(endpoint).await {
-            Ok(result) => result,
-            Err(primary_err) => {
-                if let Some(fallback) = self.parser.fallback_endpoint() {
-                    eprintln!(
-                        "[{}] Primary connection failed ({}). Trying fallback {}...",
-                        self.parser.name(),
-                        primary_err,
-                        fallback
-                    );
-                    connect_async(fallback).await?
-                } else {
-                    return Err(primary_err.into());
-                }
+    pub async fn connect(&mut self) -> Result<mpsc::Receiver<MarketData>, CctError> {
+        // Channel for market data FROM the WebSocket
+        // With DropOldest the buffering happens in front of the channel (see delivery.rs)
+        let capacity = match self.config.overflow {
+            OverflowPolicy::DropOldest => 1,
+            _ => self.config.market_data_capacity,
+        };
+        let (market_data_tx, market_data_rx) = mpsc::channel::<MarketData>(capacity);
+        self.open(market_data_tx).await?;
+        Ok(market_data_rx)
+    }
+
+    /// `connect()`, with the receiver wrapped as a `futures_util::Stream`.
+    pub async fn connect_stream(&mut self) -> Result<MarketDataStream, CctError> {
+        self.connect().await.map(MarketDataStream::new)
+    }
+
+    /// Opens the socket and spawns the tasks, sending parsed data to `market_data_tx`.
+    async fn open(&mut self, market_data_tx: mpsc::Sender<MarketData>) -> Result<(), CctError> {
+        self.state.send_replace(ConnectionState::Connecting);
+        let (ws_stream, endpoint) = match open_socket(self.parser.as_ref(), &self.config, &self.events_tx).await {
+            Ok(opened) => opened,
+            Err(e) => {
+                self.state.send_replace(ConnectionState::Disconnected);
+                return Err(e);
             }
         };
         let (write, read) = ws_stream.split();
+        let _ = self.events_tx.send(ConnectionEvent::Connected { endpoint });
 
         // Channel for sending messages TO the WebSocket
-        let (ws_tx, mut ws_rx) = mpsc::channel::<Message>(100);
+        let (ws_tx, ws_rx) = mpsc::channel::<Message>(self.config.ws_send_capacity);
+        let outbox: Outbox = Arc::new(Mutex::new(ws_rx));
         self.ws_sender = Some(ws_tx);
 
-        // Channel for market data FROM the WebSocket
-        let (market_data_tx, market_data_rx) = mpsc::channel::<MarketData>(1000);
-
-        self.is_connected = true;
+        self.is_open = true;
         self.connected_at = Some(Instant::now());
+        self.state.send_replace(ConnectionState::Connected { since: Instant::now() });
+        self.metrics.record_connected();
+        self.market_data_tx = Some(market_data_tx.downgrade());
+        self.closing.store(false, Ordering::SeqCst);
 
-        let parser = Arc::clone(&self.parser);
+        let mut reader = ReadLoop {
+            parser: Arc::clone(&self.parser),
+            subscriptions: Arc::clone(&self.subscriptions),
+            events_tx: self.events_tx.clone(),
+            outbox_tx: self.ws_sender.clone().expect("ws_sender set above"),
+            health: self.stream_health.map(StreamHealth::new),
+            sink: MarketDataSink::new(
+                market_data_tx,
+                self.config.overflow,
+                self.config.market_data_capacity,
+                Arc::clone(&self.metrics),
+            ),
+            heartbeat: Arc::clone(&self.heartbeat),
+            stale_after: self.stale_after,
+            requests: Arc::clone(&self.requests),
+            metrics: Arc::clone(&self.metrics),
+            config: self.config.clone(),
+            routes: self.routes.clone(),
+            pause: Arc::clone(&self.pause),
+            state: self.state.clone(),
+        };
 
         // Task: handle outgoing messages (write to WebSocket)
-        let write = Arc::new(Mutex::new(write));
-        let write_clone = Arc::clone(&write);
-        
-        // This spawns a background async task whose only job is to forward messages from a channel to a WebSocket writer.
-        let write_handle = tokio::spawn(async move {
-            let mut write = write_clone.lock().await;
-            while let Some(msg) = ws_rx.recv().await {
-                if let Err(e) = write.send(msg).await {
-                    eprintln!("Failed to send WebSocket message: {}", e);
-                    break;
+        let writer = WriterTask::spawn(write, Arc::clone(&outbox));
+
+        // Task: application-level keepalive for exchanges that require a JSON ping
+        if let Some((payload, interval)) = self.parser.ping_message() {
+            let ping_tx = self.ws_sender.clone().expect("ws_sender set above");
+            self.ping_handle = Some(tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.tick().await; // first tick completes immediately
+                loop {
+                    ticker.tick().await;
+                    // Write task gone = connection gone, stop pinging
+                    if ping_tx.send(Message::Text(payload.clone().into())).await.is_err() {
+                        break;
+                    }
                 }
-            }
-        });
+            }));
+        }
 
-        // Task: handle incoming messages (read from WebSocket)
-        let read_handle = tokio::spawn(async move {
-            let mut read = read;
-            while let Some(msg_result) = read.next().await {
-                match msg_result {
-                    Ok(Message::Text(text)) => {
-                        // Parse and send market data
-                        if let Some(market_data) = parser.parse_message(&text) {
-                            match market_data_tx.try_send(market_data) {
-                                Ok(_) => {}
-                                Err(TrySendError::Full(_)) => {
-                                    eprintln!(
-                                        "[{}] Market data channel full; dropping message",
-                                        parser.name()
-                                    );
+        // Task: handle incoming messages (read from WebSocket). With auto-reconnect or
+        // auto-refresh it also supervises the connection, replacing the socket and the
+        // writer, so it owns the writer and `self.write_handle` stays empty.
+        let reconnect_policy = self.reconnect_policy;
+        let refresh_after = self.config.auto_refresh.then(|| {
+            Duration::from_secs(self.parser.max_connection_duration_secs())
+                .saturating_sub(self.config.refresh_margin)
+                .max(Duration::from_secs(1))
+        });
+        let closing = Arc::clone(&self.closing);
+        let read_handle = if reconnect_policy.is_some() || refresh_after.is_some() {
+            tokio::spawn(async move {
+                let mut backoff = reconnect_policy.map(Backoff::new);
+                let mut read = read;
+                let mut writer = writer;
+                let mut connected_at = Instant::now();
+                let next_refresh = || refresh_after.map(|after| tokio::time::Instant::now() + after);
+                let mut refresh_at = next_refresh();
+                loop {
+                    let end = match reader.run(&mut read, refresh_at).await {
+                        ReadEnd::RefreshDue => {
+                            // Keep reading the old socket while the new one opens
+                            let (parser, config, events_tx) =
+                                (Arc::clone(&reader.parser), reader.config.clone(), reader.events_tx.clone());
+                            let opening = open_socket(parser.as_ref(), &config, &events_tx);
+                            let opened = tokio::select! {
+                                opened = opening => Ok(opened),
+                                end = reader.run(&mut read, None) => Err(end),
+                            };
+                            match opened {
+                                Ok(Ok((ws_stream, endpoint))) => {
+                                    let _ = reader.events_tx.send(ConnectionEvent::Connected { endpoint });
+                                    let (mut write, new_read) = ws_stream.split();
+                                    // Stop the old writer first so nothing queued from here on
+                                    // goes to the old socket and misses the new one
+                                    writer.retire(reader.config.connect_timeout).await;
+                                    if reader.restore(&mut write).await {
+                                        println!("[{}] Connection refreshed", reader.parser.name());
+                                        reader.metrics.record_connected();
+                                        reader.metrics.record_reconnect();
+                                        reader.state.send_replace(ConnectionState::Connected { since: Instant::now() });
+                                        read = new_read;
+                                        writer = WriterTask::spawn(write, Arc::clone(&outbox));
+                                        connected_at = Instant::now();
+                                        refresh_at = next_refresh();
+                                        continue;
+                                    }
+                                    // The old writer is gone; treat it like a dropped connection
+                                    ReadEnd::ConnectionLost("refresh failed".to_string())
                                 }
-                                Err(TrySendError::Closed(_)) => {
-                                    eprintln!(
-                                        "[{}] Market data channel closed; stopping read loop",
-                                        parser.name()
-                                    );
-                                    break;
+                                Ok(Err(e)) => {
+                                    eprintln!("[{}] Connection refresh failed: {}", reader.parser.name(), e);
+                                    refresh_at = Some(tokio::time::Instant::now() + REFRESH_RETRY_DELAY);
+                                    continue;
                                 }
+                                Err(end) => end,
                             }
                         }
-                        // Control messages (subscription confirmations, etc.) are ignored
-                    }
-                    Ok(Message::Ping(_data)) => {
-                        println!("[{}] Ping received", parser.name());
-                        // Pong handled automatically by tungstenite
-                    }
-                    Ok(Message::Pong(_)) => {
-                        // Connection alive
-                    }
-                    Ok(Message::Close(frame)) => {
-                        println!("[{}] Connection closed: {:?}", parser.name(), frame);
+                        end => end,
+                    };
+                    writer.abort();
+                    reader.report_end(&end, &closing);
+                    if end == ReadEnd::ReceiverDropped || closing.load(Ordering::SeqCst) {
                         break;
                     }
-                    Ok(Message::Binary(_)) => {
-                        // Binary messages not used for market data
-                    }
-                    Err(e) => {
-                        eprintln!("[{}] WebSocket error: {}", parser.name(), e);
-                        break;
+                    let Some(backoff) = backoff.as_mut() else { break };
+                    backoff.connection_lasted(connected_at.elapsed());
+
+                    match reconnect_with_backoff(&reader, &outbox, backoff, &closing).await {
+                        Some((new_read, new_writer)) => {
+                            read = new_read;
+                            writer = new_writer;
+                            connected_at = Instant::now();
+                            refresh_at = next_refresh();
+                        }
+                        None => break,
                     }
-                    _ => {}
                 }
-            }
-            println!("[{}] Read task ended", parser.name());
-        });
-
-        self.write_handle = Some(write_handle);
+                // Routed feeds end with the main one
+                reader.routes.clear();
+                println!("[{}] Read task ended", reader.parser.name());
+            })
+        } else {
+            let writer_abort = writer.handle.abort_handle();
+            self.write_handle = Some(writer.handle);
+            tokio::spawn(async move {
+                let mut read = read;
+                let end = reader.run(&mut read, None).await;
+                // Drops the outbox with the writer, so later sends fail fast with SendFailed
+                writer_abort.abort();
+                reader.report_end(&end, &closing);
+                // Routed feeds end with the main one
+                reader.routes.clear();
+                println!("[{}] Read task ended", reader.parser.name());
+            })
+        };
         self.read_handle = Some(read_handle);
 
         println!("[{}] Connected successfully!", self.parser.name());
 
-        Ok(market_data_rx)
+        Ok(())
+    }
+
+    /// Stops delivering market data without touching subscriptions: incoming items are
+    /// buffered or dropped per `ClientConfig::pause_policy` until `resume()`.
+    /// Counted in `ClientStats::paused_buffered` / `paused_dropped`.
+    pub fn pause(&self) {
+        self.pause.pause();
+    }
+
+    /// Resumes delivery. Items buffered while paused are delivered first, in order.
+    pub fn resume(&self) {
+        self.pause.resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
     }
 
-    pub async fn subscribe(&mut self, stream: Stream) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if !self.is_connected {
-            return Err("Not connected".into());
+    /// Subscribes to `stream`. If the parser tags requests with ids, this resolves once
+    /// the exchange acknowledges the request and fails with `SubscriptionRejected` (or
+    /// `AckTimeout`) otherwise; a failed stream is not kept in `subscriptions()`.
+    ///
+    /// A stream the parser can't express (an empty `format_subscribe`, e.g. funding on
+    /// a spot exchange or an interval it has no candles for) fails with
+    /// `SubscriptionRejected` without sending anything.
+    pub async fn subscribe(&mut self, stream: Stream) -> Result<(), CctError> {
+        if !self.is_open {
+            return Err(CctError::NotConnected);
         }
 
-        if self.subscriptions.contains(&stream) {
+        if self.subscriptions_guard().contains(&stream) {
             return Ok(());
         }
 
         // each client will have its own subscribe format
-        let msg = self.parser.format_subscribe(&stream);
-        
-        if let Some(sender) = &self.ws_sender {
-            sender.send(Message::Text(msg.into())).await?; // into to build Utf8Bytes
-            self.subscriptions.push(stream.clone());
-            println!("[{}] Subscribed to {:?}", self.parser.name(), stream);
+        let id = self.requests.next_id();
+        let (msg, awaits_ack) = subscribe_frame(self.parser.as_ref(), &stream, id);
+        if msg.is_empty() {
+            return Err(unsupported(self.parser.as_ref(), stream));
+        }
+
+        self.send_subscription(vec![stream], msg, id, awaits_ack).await
+    }
+
+    /// Subscribes to `stream` like `subscribe()`, but delivers its data on a dedicated
+    /// receiver instead of the one from `connect()`, so a busy stream can't hold up the
+    /// others. Items are routed with `Stream::matches`; anything no route claims goes to
+    /// the main receiver. The channel has the main channel's capacity and overflow policy.
+    ///
+    /// The receiver ends on `unsubscribe()`, on `disconnect()` or when the feed ends.
+    /// Routing an already-routed stream again closes the earlier receiver.
+    pub async fn subscribe_routed(&mut self, stream: Stream) -> Result<mpsc::Receiver<MarketData>, CctError> {
+        if !self.is_open {
+            return Err(CctError::NotConnected);
+        }
+        let (tx, rx) = mpsc::channel(self.config.market_data_capacity);
+        let sink = MarketDataSink::new(tx, self.config.overflow, self.config.market_data_capacity, Arc::clone(&self.metrics));
+        // Route first so data arriving right after the acknowledgement isn't missed
+        self.routes.insert(stream.clone(), sink);
+        if let Err(e) = self.subscribe(stream.clone()).await {
+            self.routes.remove(&stream);
+            return Err(e);
+        }
+        Ok(rx)
+    }
+
+    /// Subscribes to several streams using as few frames as the parser allows
+    /// (`MessageParser::format_subscribe_many`), `SUBSCRIBE_BATCH_SIZE` streams per frame.
+    /// Streams already subscribed, and repeats within `streams`, are skipped.
+    ///
+    /// Nothing is sent if any stream is unsupported. If the exchange rejects a frame,
+    /// none of its streams are kept and the error names the first of them; frames
+    /// sent before it stay subscribed.
+    pub async fn subscribe_many(&mut self, streams: Vec<Stream>) -> Result<(), CctError> {
+        if !self.is_open {
+            return Err(CctError::NotConnected);
+        }
+
+        let mut pending: Vec<Stream> = Vec::with_capacity(streams.len());
+        {
+            let subscribed = self.subscriptions_guard();
+            for stream in streams {
+                if !subscribed.contains(&stream) && !pending.contains(&stream) {
+                    pending.push(stream);
+                }
+            }
+        }
+        if let Some(stream) = pending.iter().find(|s| self.parser.format_subscribe(s).is_empty()) {
+            return Err(unsupported(self.parser.as_ref(), stream.clone()));
         }
 
+        for chunk in pending.chunks(SUBSCRIBE_BATCH_SIZE) {
+            let id = self.requests.next_id();
+            match subscribe_many_frame(self.parser.as_ref(), chunk, id) {
+                Some((msg, awaits_ack)) => self.send_subscription(chunk.to_vec(), msg, id, awaits_ack).await?,
+                // No batch format: one frame per stream
+                None => {
+                    for stream in chunk {
+                        self.subscribe(stream.clone()).await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `streams`, sends the frame and, if the exchange acknowledges requests,
+    /// waits for the reply to `id`. On failure the streams are forgotten again.
+    async fn send_subscription(
+        &mut self,
+        streams: Vec<Stream>,
+        msg: String,
+        id: u64,
+        awaits_ack: bool,
+    ) -> Result<(), CctError> {
+        let Some(sender) = self.ws_sender.clone() else {
+            return Ok(());
+        };
+        let forget = |client: &Self| client.subscriptions_guard().retain(|s| !streams.contains(s));
+
+        // Register before sending so a fast reply can't be missed
+        let ack = awaits_ack.then(|| self.requests.register(id));
+        // Record first so data arriving right after the frame can be attributed to it
+        self.subscriptions_guard().extend(streams.iter().cloned());
+        if sender.send(Message::Text(msg.into())).await.is_err() { // into to build Utf8Bytes
+            forget(self);
+            self.requests.forget(id);
+            return Err(CctError::SendFailed);
+        }
+
+        if let Some(ack) = ack {
+            let stream = streams[0].clone();
+            let failure = match tokio::time::timeout(self.ack_timeout, ack).await {
+                Ok(Ok(Ok(()))) => None,
+                Ok(Ok(Err(reason))) => Some(CctError::SubscriptionRejected { stream, reason }),
+                // The read task dropped the waiter: the connection ended first
+                Ok(Err(_)) => Some(CctError::NotConnected),
+                Err(_) => {
+                    self.requests.forget(id);
+                    Some(CctError::AckTimeout { stream, timeout: self.ack_timeout })
+                }
+            };
+            if let Some(failure) = failure {
+                forget(self);
+                eprintln!("[{}] Subscription to {:?} failed: {}", self.parser.name(), streams, failure);
+                return Err(failure);
+            }
+        }
+        println!("[{}] Subscribed to {:?}", self.parser.name(), streams);
+
         Ok(())
     }
 
-    pub async fn unsubscribe(&mut self, stream: &Stream) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if !self.is_connected {
-            return Err("Not connected".into());
+    pub async fn unsubscribe(&mut self, stream: &Stream) -> Result<(), CctError> {
+        if !self.is_open {
+            return Err(CctError::NotConnected);
         }
 
         // each client will have its own unsubscribe format
-        let msg = self.parser.format_unsubscribe(stream);
+        let msg = unsubscribe_frame(self.parser.as_ref(), stream, self.requests.next_id());
         
         if let Some(sender) = &self.ws_sender {
             sender.send(Message::Text(msg.into())).await?;
-            self.subscriptions.retain(|s| s != stream);
+            self.subscriptions_guard().retain(|s| s != stream);
+            self.routes.remove(stream);
             println!("[{}] Unsubscribed from {:?}", self.parser.name(), stream);
         }
 
         Ok(())
     }
 
+    /// Unsubscribes from every active stream, batched where the parser allows it
+    /// (`MessageParser::format_unsubscribe_many`). See `unsubscribe_symbol` for the result.
+    pub async fn unsubscribe_all(&mut self) -> Result<Vec<Stream>, CctError> {
+        let streams = self.subscriptions();
+        self.unsubscribe_streams(streams).await
+    }
+
+    /// Unsubscribes from every stream for `symbol` (case-insensitive).
+    /// Returns the streams actually removed. If a frame can't be sent, the streams it
+    /// covered and any after it stay subscribed; that is only an error when nothing
+    /// could be sent at all.
+    pub async fn unsubscribe_symbol(&mut self, symbol: &str) -> Result<Vec<Stream>, CctError> {
+        let streams = self.subscriptions_for_symbol(symbol);
+        self.unsubscribe_streams(streams).await
+    }
+
+    async fn unsubscribe_streams(&mut self, streams: Vec<Stream>) -> Result<Vec<Stream>, CctError> {
+        if !self.is_open {
+            return Err(CctError::NotConnected);
+        }
+        let sender = self.ws_sender.clone().ok_or(CctError::NotConnected)?;
+        let parser = Arc::clone(&self.parser);
+
+        let mut removed = Vec::with_capacity(streams.len());
+        for chunk in streams.chunks(SUBSCRIBE_BATCH_SIZE) {
+            let frames = match unsubscribe_many_frame(parser.as_ref(), chunk, self.requests.next_id()) {
+                Some(msg) => vec![(chunk, msg)],
+                None => chunk
+                    .iter()
+                    .map(|stream| {
+                        let msg = unsubscribe_frame(parser.as_ref(), stream, self.requests.next_id());
+                        (std::slice::from_ref(stream), msg)
+                    })
+                    .collect(),
+            };
+            for (streams, msg) in frames {
+                if !msg.is_empty()
+                    && let Err(e) = sender.send(Message::Text(msg.into())).await
+                {
+                    if removed.is_empty() {
+                        return Err(e.into());
+                    }
+                    eprintln!("[{}] Unsubscribe stopped after {} streams: {}", parser.name(), removed.len(), e);
+                    return Ok(removed);
+                }
+                self.subscriptions_guard().retain(|s| !streams.contains(s));
+                for stream in streams {
+                    self.routes.remove(stream);
+                }
+                removed.extend_from_slice(streams);
+            }
+        }
+        println!("[{}] Unsubscribed from {:?}", parser.name(), removed);
+        Ok(removed)
+    }
+
+    /// Closes the connection gracefully, waiting up to 5s. See `disconnect_with_timeout`.
     pub async fn disconnect(&mut self) {
-        if let Some(sender) = &self.ws_sender {
-            let _ = sender.send(Message::Close(None)).await;
+        self.disconnect_with_timeout(DEFAULT_CLOSE_TIMEOUT).await;
+    }
+
+    /// Sends everything already queued (e.g. a `subscribe()` that just returned),
+    /// then a Close frame, and waits for the server to acknowledge it and for the
+    /// background tasks to finish. Tasks still running after `timeout` are aborted.
+    /// Returns true if everything finished in time.
+    pub async fn disconnect_with_timeout(&mut self, timeout: Duration) -> bool {
+        self.routes.clear();
+        self.close("disconnect() called", Some(timeout)).await
+    }
+
+    /// Aborts the background tasks immediately. Queued frames, including the Close
+    /// frame, may never reach the server.
+    pub async fn force_disconnect(&mut self) {
+        self.routes.clear();
+        self.close("force_disconnect() called", None).await;
+    }
+
+    /// Stops the connection tasks, reporting `reason` as the disconnect reason.
+    /// With a timeout the tasks get that long to flush and finish before being aborted;
+    /// returns whether they did.
+    async fn close(&mut self, reason: &str, timeout: Option<Duration>) -> bool {
+        self.closing.store(true, Ordering::SeqCst);
+        if self.is_open {
+            self.state.send_replace(ConnectionState::Closing);
         }
-        self.ws_sender = None;
-        if let Some(handle) = self.read_handle.take() {
-            handle.abort();
+        if self.is_open {
+            let _ = self.events_tx.send(ConnectionEvent::Disconnected {
+                reason: reason.to_string(),
+            });
         }
-        if let Some(handle) = self.write_handle.take() {
+        // No pings after the Close frame
+        if let Some(handle) = self.ping_handle.take() {
             handle.abort();
         }
-        self.is_connected = false;
+        let deadline = timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+        if let Some(sender) = self.ws_sender.take() {
+            match deadline {
+                // Queued behind whatever is already in the outbox
+                Some(deadline) => {
+                    let _ = tokio::time::timeout_at(deadline, sender.send(Message::Close(None))).await;
+                }
+                None => {
+                    let _ = sender.try_send(Message::Close(None));
+                }
+            }
+        }
+
+        let handles = [self.read_handle.take(), self.write_handle.take()];
+        let mut finished = true;
+        for mut handle in handles.into_iter().flatten() {
+            match deadline {
+                // The writer stops after the Close frame; the reader once the server acks it
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, &mut handle).await.is_err() {
+                        handle.abort();
+                        finished = false;
+                    }
+                }
+                None => handle.abort(),
+            }
+        }
+        if !finished {
+            eprintln!("[{}] Connection tasks did not finish in time; aborted", self.parser.name());
+        }
+        self.is_open = false;
         self.connected_at = None;
+        self.state.send_replace(ConnectionState::Disconnected);
+        self.metrics.record_disconnected();
         println!("[{}] Disconnected", self.parser.name());
+        finished
     }
 
     /// Reconnects and restores all subscriptions.
-    pub async fn reconnect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// Data keeps flowing to the receiver returned by `connect()`; fails with
+    /// `CctError::ReceiverClosed` if that feed has already ended.
+    pub async fn reconnect(&mut self) -> Result<(), CctError> {
+        // Hold a sender across the gap, otherwise the receiver would see the feed end
+        let market_data_tx = self
+            .market_data_tx
+            .as_ref()
+            .and_then(mpsc::WeakSender::upgrade)
+            .ok_or(CctError::ReceiverClosed)?;
+
         println!("[{}] Reconnecting...", self.parser.name());
+        let _ = self.events_tx.send(ConnectionEvent::Reconnecting { attempt: 1 });
         
-        let subs = self.subscriptions.clone();
+        let subs = self.subscriptions();
         
-        self.disconnect().await;
-        self.subscriptions.clear();
-        self.connect().await?;
+        // The old socket is being replaced; nothing on it is worth waiting for
+        self.close("reconnect() called", None).await;
+        self.subscriptions_guard().clear();
+        self.open(market_data_tx).await?;
+        self.metrics.record_reconnect();
         
-        // Restore subscriptions
-        for stream in subs {
-            self.subscribe(stream).await?;
-        }
+        // Restore subscriptions, batched where the exchange allows it
+        self.subscribe_many(subs).await?;
 
-        println!("[{}] Reconnected and restored {} subscriptions", 
-                 self.parser.name(), self.subscriptions.len());
+        let count = self.subscriptions_guard().len();
+        println!("[{}] Reconnected and restored {} subscriptions", self.parser.name(), count);
+        let _ = self.events_tx.send(ConnectionEvent::SubscriptionRestored { count });
         
         Ok(())
     }
 
+    /// Replaces a connection whose feed has already ended: unlike `reconnect()` this
+    /// returns a new receiver. Subscriptions are restored on the new connection.
+    pub(crate) async fn reopen(&mut self) -> Result<mpsc::Receiver<MarketData>, CctError> {
+        let subs = self.subscriptions();
+        self.close("reopening", None).await;
+        self.subscriptions_guard().clear();
+        let market_data_rx = self.connect().await?;
+        self.subscribe_many(subs).await?;
+        Ok(market_data_rx)
+    }
+
     /// Reconnects if the connection is nearing the exchange's maximum duration.
-    pub async fn reconnect_if_needed(&mut self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn reconnect_if_needed(&mut self) -> Result<bool, CctError> {
         if self.needs_reconnect() {
             self.reconnect().await?;
             return Ok(true);
@@ -265,72 +873,1695 @@ impl<P: MessageParser> WebSocketClient<P> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[derive(Debug, Clone)]
-    struct TestParser;
+fn lock_subscriptions(subscriptions: &SharedSubscriptions) -> MutexGuard<'_, Vec<Stream>> {
+    // A panic while holding the lock can't leave the Vec half-updated
+    subscriptions.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
-    impl MessageParser for TestParser {
-        fn endpoint(&self) -> &str {
-            "wss://example.invalid/ws"
+/// Connects within `config`'s attempt budget, alternating primary and fallback endpoint
+/// (when the parser has one) with a timeout per attempt.
+/// Returns the socket and the endpoint it is connected to, or the last attempt's error.
+async fn open_socket<P: MessageParser>(
+    parser: &P,
+    config: &ClientConfig,
+    events_tx: &broadcast::Sender<ConnectionEvent>,
+) -> Result<(WsStream, String), CctError> {
+    let mut last_err = None;
+    for attempt in 1..=config.connect_attempts.max(1) {
+        if attempt > 1 {
+            tokio::time::sleep(config.connect_retry_delay).await;
         }
+        let endpoint = match (config.fallback(parser.fallback_endpoint()), config.endpoint()) {
+            (Some(fallback), _) if attempt % 2 == 0 => fallback.to_string(),
+            (_, Some(endpoint)) => endpoint.to_string(),
+            // Most exchanges return endpoint() as-is; some need a REST call first (KuCoin token)
+            _ => match parser.resolve_endpoint().await {
+                Ok(endpoint) => endpoint,
+                Err(e) => {
+                    eprintln!("[{}] Resolving endpoint failed: {}", parser.name(), e);
+                    last_err = Some(e);
+                    continue;
+                }
+            },
+        };
 
-        fn format_subscribe(&self, _stream: &Stream) -> String {
-            "{\"op\":\"subscribe\"}".to_string()
-        }
+        let _ = events_tx.send(ConnectionEvent::Connecting { attempt, endpoint: endpoint.clone() });
+        println!("[{}] Connecting to {} (attempt {})...", parser.name(), endpoint, attempt);
 
-        fn format_unsubscribe(&self, _stream: &Stream) -> String {
-            "{\"op\":\"unsubscribe\"}".to_string()
-        }
+        let source: Box<dyn std::error::Error + Send + Sync> =
+            match tokio::time::timeout(config.connect_timeout, connect_async(endpoint.as_str())).await {
+                Ok(Ok((ws_stream, _response))) => return Ok((ws_stream, endpoint)),
+                Ok(Err(e)) => e.into(),
+                Err(elapsed) => elapsed.into(),
+            };
+        eprintln!("[{}] Connection to {} failed: {}", parser.name(), endpoint, source);
+        last_err = Some(CctError::ConnectFailed { endpoint, source });
+    }
+    Err(last_err.expect("at least one attempt is made"))
+}
 
-        fn parse_message(&self, _msg: &str) -> Option<MarketData> {
-            None
-        }
+/// The task writing queued outgoing messages to one socket.
+struct WriterTask {
+    handle: JoinHandle<()>,
+    retire: Arc<Notify>,
+}
 
-        fn name(&self) -> &'static str {
-            "Test"
-        }
+impl WriterTask {
+    /// Forwards queued outgoing messages to the socket until it has written a Close frame,
+    /// the queue closes, a write fails or the task is retired.
+    fn spawn(mut write: WsWrite, outbox: Outbox) -> Self {
+        let retire = Arc::new(Notify::new());
+        let retired = Arc::clone(&retire);
+        let handle = tokio::spawn(async move {
+            let mut outbox = outbox.lock().await;
+            loop {
+                let msg = tokio::select! {
+                    biased;
+                    _ = retired.notified() => Message::Close(None),
+                    msg = outbox.recv() => match msg {
+                        Some(msg) => msg,
+                        None => break,
+                    },
+                };
+                let is_close = matches!(msg, Message::Close(_));
+                if let Err(e) = write.send(msg).await {
+                    eprintln!("Failed to send WebSocket message: {}", e);
+                    break;
+                }
+                if is_close {
+                    break;
+                }
+            }
+        });
+        Self { handle, retire }
+    }
 
-        fn max_connection_duration_secs(&self) -> u64 {
-            1
+    /// Closes the socket and stops, leaving anything still queued for the next writer.
+    /// Aborts if that takes longer than `timeout`.
+    async fn retire(&mut self, timeout: Duration) {
+        self.retire.notify_one();
+        if tokio::time::timeout(timeout, &mut self.handle).await.is_err() {
+            self.handle.abort();
         }
     }
 
-    #[tokio::test]
-    async fn test_subscribe_dedup() {
-        let mut client = WebSocketClient::new(TestParser);
-        let (tx, _rx) = mpsc::channel::<Message>(10);
-        client.ws_sender = Some(tx);
-        client.is_connected = true;
-
-        let stream = Stream::candles("BTCUSDT", crate::indicators::timeframe::Timeframe::M1);
-        client.subscribe(stream.clone()).await.unwrap();
-        client.subscribe(stream).await.unwrap();
-
-        assert_eq!(client.subscriptions.len(), 1);
+    fn abort(&self) {
+        self.handle.abort();
     }
+}
 
-    #[tokio::test]
-    async fn test_disconnect_resets_state() {
-        let mut client = WebSocketClient::new(TestParser);
-        let (tx, _rx) = mpsc::channel::<Message>(10);
-        client.ws_sender = Some(tx);
-        client.is_connected = true;
-        client.connected_at = Some(Instant::now());
-
-        client.disconnect().await;
+/// Why a read loop stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReadEnd {
+    /// Server closed the socket or it errored
+    ConnectionLost(String),
+    /// Nobody is listening for market data anymore
+    ReceiverDropped,
+    /// The connection is due to be replaced (`ClientConfig::auto_refresh`)
+    RefreshDue,
+}
 
-        assert!(!client.is_connected);
-        assert!(client.ws_sender.is_none());
-        assert!(client.connected_at.is_none());
+impl ReadEnd {
+    fn reason(&self) -> String {
+        match self {
+            ReadEnd::ConnectionLost(reason) => reason.clone(),
+            ReadEnd::ReceiverDropped => "market data receiver dropped".to_string(),
+            ReadEnd::RefreshDue => "refreshing connection".to_string(),
+        }
     }
+}
 
-    #[test]
-    fn test_needs_reconnect_true() {
-        let mut client = WebSocketClient::new(TestParser);
-        client.connected_at = Some(Instant::now() - Duration::from_secs(2));
-        assert!(client.needs_reconnect());
+/// State the read task keeps across reconnects.
+struct ReadLoop<P: MessageParser> {
+    parser: Arc<P>,
+    subscriptions: SharedSubscriptions,
+    events_tx: broadcast::Sender<ConnectionEvent>,
+    outbox_tx: mpsc::Sender<Message>, // auto-unsubscribes and replies
+    health: Option<StreamHealth>,
+    sink: MarketDataSink,
+    routes: Routes,
+    pause: Arc<PauseGate>,
+    state: watch::Sender<ConnectionState>,
+    heartbeat: Arc<Heartbeat>,
+    stale_after: Option<Duration>,
+    requests: Arc<PendingRequests>,
+    metrics: Arc<ClientMetrics>,
+    config: ClientConfig,
+}
+
+impl<P: MessageParser> ReadLoop<P> {
+    /// Reads until the connection ends, or until `refresh_at`.
+    async fn run(&mut self, read: &mut WsRead, refresh_at: Option<tokio::time::Instant>) -> ReadEnd {
+        let parser = Arc::clone(&self.parser);
+        loop {
+            let stale_after = self.stale_after;
+            let frame = async {
+                match stale_after {
+                    Some(stale_after) => tokio::time::timeout(stale_after, read.next()).await.map_err(|_| stale_after),
+                    None => Ok(read.next().await),
+                }
+            };
+            let refresh_due = async {
+                match refresh_at {
+                    Some(refresh_at) => tokio::time::sleep_until(refresh_at).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let next = tokio::select! {
+                frame = frame => match frame {
+                    Ok(next) => next,
+                    Err(stale_after) => {
+                        eprintln!("[{}] No message for {:?}; dropping stale connection", parser.name(), stale_after);
+                        return ReadEnd::ConnectionLost(format!("stale: no message for {:?}", stale_after));
+                    }
+                },
+                _ = refresh_due => return ReadEnd::RefreshDue,
+                _ = self.pause.resumed.notified() => {
+                    if let Some(end) = self.flush_paused().await {
+                        return end;
+                    }
+                    continue;
+                }
+            };
+            let Some(msg_result) = next else { break };
+            if let Ok(msg) = &msg_result {
+                self.heartbeat.record();
+                self.metrics.record_frame(msg.len());
+            }
+            match msg_result {
+                Ok(Message::Text(text)) => {
+                    if let Some(end) = self.handle_text(&text).await {
+                        return end;
+                    }
+                }
+                Ok(Message::Ping(_data)) => {
+                    println!("[{}] Ping received", parser.name());
+                    // Pong handled automatically by tungstenite
+                }
+                Ok(Message::Pong(_)) => {
+                    // Connection alive
+                }
+                Ok(Message::Close(frame)) => {
+                    println!("[{}] Connection closed: {:?}", parser.name(), frame);
+                    let reason = match frame {
+                        Some(frame) => format!("closed by server: {} {}", frame.code, frame.reason),
+                        None => "closed by server".to_string(),
+                    };
+                    return ReadEnd::ConnectionLost(reason);
+                }
+                Ok(Message::Binary(data)) => match parser.decode_binary(&data) {
+                    Some(DecodedFrame::Text(text)) => {
+                        if let Some(end) = self.handle_text(&text).await {
+                            return end;
+                        }
+                    }
+                    // e.g. Huobi's compressed pings, which must be answered to stay connected
+                    Some(DecodedFrame::Reply(payload)) => {
+                        let _ = self.outbox_tx.send(Message::Text(payload.into())).await;
+                    }
+                    None => {}
+                },
+                Err(e) => {
+                    eprintln!("[{}] WebSocket error: {}", parser.name(), e);
+                    return ReadEnd::ConnectionLost(format!("websocket error: {}", e));
+                }
+                _ => {}
+            }
+        }
+        ReadEnd::ConnectionLost("connection ended".to_string())
+    }
+
+    /// Handles one text frame. Returns why reading must stop, if it must.
+    async fn handle_text(&mut self, text: &str) -> Option<ReadEnd> {
+        let parser = Arc::clone(&self.parser);
+        // Replies to our own requests are neither data nor parse failures
+        if let Some((id, result)) = parser.parse_response(text) {
+            if let Err(reason) = &result {
+                eprintln!("[{}] Request {} rejected: {}", parser.name(), id, reason);
+            }
+            self.requests.resolve(id, result);
+            return None;
+        }
+
+        // One frame can carry several items (e.g. Binance's !miniTicker@arr)
+        let parsed = parser.parse_messages(text);
+        if parsed.is_empty() {
+            self.metrics.record_parse_miss();
+        }
+
+        if let Some(health) = self.health.as_mut()
+            && let Some(stream) = attribute(parser.as_ref(), text, &self.subscriptions)
+            && health.observe(&stream, !parsed.is_empty())
+        {
+            let sample_error = format!(
+                "{} (payload: {})",
+                parser.parse_failure(text),
+                text.chars().take(200).collect::<String>()
+            );
+            eprintln!("[{}] Stream {:?} looks broken ({})", parser.name(), stream, sample_error);
+
+            if health.policy.auto_unsubscribe {
+                // Never block the read loop on a full outbox. If the frame can't be
+                // queued the stream stays subscribed, and the next trip retries.
+                let msg = unsubscribe_frame(parser.as_ref(), &stream, self.requests.next_id());
+                match self.outbox_tx.try_send(Message::Text(msg.into())) {
+                    Ok(()) => lock_subscriptions(&self.subscriptions).retain(|s| s != &stream),
+                    Err(e) => eprintln!("[{}] Could not unsubscribe {:?}: {}", parser.name(), stream, e),
+                }
+            }
+            let _ = self.events_tx.send(ConnectionEvent::StreamBroken { stream, sample_error });
+        }
+
+        // Send market data
+        let received_at = now_ms();
+        let raw = if self.config.keep_raw && !parsed.is_empty() {
+            serde_json::from_str(text).ok().map(Arc::new)
+        } else {
+            None
+        };
+        for mut market_data in parsed {
+            market_data.set_exchange(parser.name());
+            market_data.set_received_at(received_at);
+            market_data.set_raw(raw.clone());
+            self.metrics.record_parsed(&market_data);
+            let Some(market_data) = self.pause.hold(market_data, &self.metrics) else {
+                continue;
+            };
+            // Anything held while paused goes out first
+            if let Some(end) = self.flush_paused().await {
+                return Some(end);
+            }
+            if let Some(end) = self.deliver(market_data).await {
+                return Some(end);
+            }
+        }
+        // Control messages (subscription confirmations, etc.) are ignored
+        None
+    }
+
+    /// Delivers what was buffered while paused, oldest first. Nothing while still paused.
+    async fn flush_paused(&mut self) -> Option<ReadEnd> {
+        for market_data in self.pause.take_buffered() {
+            if let Some(end) = self.deliver(market_data).await {
+                return Some(end);
+            }
+        }
+        None
+    }
+
+    /// Sends one item to its route, or to the main channel.
+    async fn deliver(&mut self, market_data: MarketData) -> Option<ReadEnd> {
+        let name = self.parser.name();
+        let route = self.routes.find(&market_data);
+        let delivered = match &route {
+            Some((_, sink)) => sink.deliver(market_data).await,
+            None => self.sink.deliver(market_data).await,
+        };
+        match delivered {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!("[{}] Market data channel full; dropping message", name);
+            }
+            // A dropped routed receiver only ends its route; later items go to the main channel
+            Err(_) if let Some((stream, _)) = &route => {
+                eprintln!("[{}] Routed receiver for {:?} closed; unrouting", name, stream);
+                self.routes.remove(stream);
+            }
+            Err(_) => {
+                eprintln!("[{}] Market data channel closed; stopping read loop", name);
+                return Some(ReadEnd::ReceiverDropped);
+            }
+        }
+        None
+    }
+
+    /// Replays the current subscriptions on a new socket, before its writer starts.
+    async fn restore(&self, write: &mut WsWrite) -> bool {
+        let parser = self.parser.as_ref();
+        let subscriptions = lock_subscriptions(&self.subscriptions).clone();
+        for msg in restore_frames(parser, &subscriptions, &self.requests) {
+            if let Err(e) = write.send(Message::Text(msg.into())).await {
+                eprintln!("[{}] Failed to restore subscriptions: {}", parser.name(), e);
+                return false;
+            }
+        }
+        let _ = self.events_tx.send(ConnectionEvent::SubscriptionRestored { count: subscriptions.len() });
+        true
+    }
+
+    /// Reports the end of a connection, unless `disconnect()` already did.
+    /// Requests still waiting for a reply will never get one on this connection.
+    fn report_end(&self, end: &ReadEnd, closing: &AtomicBool) {
+        self.requests.clear();
+        self.metrics.record_disconnected();
+        if !closing.load(Ordering::SeqCst) {
+            self.state.send_replace(ConnectionState::Disconnected);
+            let _ = self.events_tx.send(ConnectionEvent::Disconnected { reason: end.reason() });
+        }
+    }
+}
+
+/// Reconnects after the connection dropped, waiting out the backoff between attempts.
+/// Restores the current subscriptions on the new socket before resuming the writer.
+/// Returns None when attempts are exhausted or the client is disconnecting.
+async fn reconnect_with_backoff<P: MessageParser>(
+    reader: &ReadLoop<P>,
+    outbox: &Outbox,
+    backoff: &mut Backoff,
+    closing: &AtomicBool,
+) -> Option<(WsRead, WriterTask)> {
+    let parser = reader.parser.as_ref();
+    loop {
+        let Some(delay) = backoff.next_delay() else {
+            eprintln!("[{}] Giving up after {} reconnect attempts", parser.name(), backoff.attempt);
+            reader.state.send_replace(ConnectionState::Disconnected);
+            return None;
+        };
+        eprintln!(
+            "[{}] Connection lost; reconnecting in {:?} (attempt {})",
+            parser.name(),
+            delay,
+            backoff.attempt
+        );
+        let _ = reader.events_tx.send(ConnectionEvent::Reconnecting { attempt: backoff.attempt });
+        reader.state.send_replace(ConnectionState::Connecting);
+        tokio::time::sleep(delay).await;
+        if closing.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        let (ws_stream, endpoint) = match open_socket(parser, &reader.config, &reader.events_tx).await {
+            Ok(opened) => opened,
+            Err(e) => {
+                eprintln!("[{}] Reconnect failed: {}", parser.name(), e);
+                continue;
+            }
+        };
+        let (mut write, read) = ws_stream.split();
+        let _ = reader.events_tx.send(ConnectionEvent::Connected { endpoint });
+        reader.state.send_replace(ConnectionState::Connected { since: Instant::now() });
+
+        // Frames queued while offline are stale: subscription changes are already
+        // reflected in the subscription list, which is replayed below.
+        {
+            let mut queued = outbox.lock().await;
+            while queued.try_recv().is_ok() {}
+        }
+
+        if !reader.restore(&mut write).await {
+            continue;
+        }
+
+        println!("[{}] Reconnected and restored subscriptions", parser.name());
+        reader.metrics.record_connected();
+        reader.metrics.record_reconnect();
+        return Some((read, WriterTask::spawn(write, Arc::clone(outbox))));
+    }
+}
+
+/// The subscribe frame for `stream`, and whether the exchange will acknowledge it by id.
+fn subscribe_frame<P: MessageParser>(parser: &P, stream: &Stream, id: u64) -> (String, bool) {
+    match parser.format_subscribe_with_id(stream, id) {
+        Some(msg) => (msg, true),
+        None => (parser.format_subscribe(stream), false),
+    }
+}
+
+/// A single frame for `streams`, if the parser has a batch format.
+fn subscribe_many_frame<P: MessageParser>(parser: &P, streams: &[Stream], id: u64) -> Option<(String, bool)> {
+    if let Some(msg) = parser.format_subscribe_many_with_id(streams, id).filter(|msg| !msg.is_empty()) {
+        return Some((msg, true));
+    }
+    Some((parser.format_subscribe_many(streams), false)).filter(|(msg, _)| !msg.is_empty())
+}
+
+/// Frames that re-subscribe `streams` on a new connection, batched where possible.
+/// Replies are not awaited; rejections only show up in the log.
+fn restore_frames<P: MessageParser>(parser: &P, streams: &[Stream], requests: &PendingRequests) -> Vec<String> {
+    let mut frames = Vec::new();
+    for chunk in streams.chunks(SUBSCRIBE_BATCH_SIZE) {
+        match subscribe_many_frame(parser, chunk, requests.next_id()) {
+            Some((msg, _)) => frames.push(msg),
+            None => frames.extend(
+                chunk
+                    .iter()
+                    .map(|stream| subscribe_frame(parser, stream, requests.next_id()).0)
+                    .filter(|msg| !msg.is_empty()),
+            ),
+        }
+    }
+    frames
+}
+
+fn unsupported<P: MessageParser>(parser: &P, stream: Stream) -> CctError {
+    let reason = match &stream {
+        // Candles are offered on every exchange; what's missing is the interval
+        Stream::Candles { interval, .. } => format!("{} has no {} candles", parser.name(), interval),
+        _ => format!("{} does not support this stream", parser.name()),
+    };
+    CctError::SubscriptionRejected { reason, stream }
+}
+
+fn unsubscribe_frame<P: MessageParser>(parser: &P, stream: &Stream, id: u64) -> String {
+    parser
+        .format_unsubscribe_with_id(stream, id)
+        .unwrap_or_else(|| parser.format_unsubscribe(stream))
+}
+
+/// A single unsubscribe frame for `streams`, if the parser has a batch format.
+fn unsubscribe_many_frame<P: MessageParser>(parser: &P, streams: &[Stream], id: u64) -> Option<String> {
+    parser
+        .format_unsubscribe_many_with_id(streams, id)
+        .filter(|msg| !msg.is_empty())
+        .or_else(|| Some(parser.format_unsubscribe_many(streams)).filter(|msg| !msg.is_empty()))
+}
+
+type AckResult = Result<(), String>;
+
+/// Request ids, and the subscribe calls still waiting for their reply.
+struct PendingRequests {
+    next_id: AtomicU64,
+    waiting: std::sync::Mutex<HashMap<u64, oneshot::Sender<AckResult>>>,
+}
+
+impl PendingRequests {
+    fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            waiting: std::sync::Mutex::default(),
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn waiting(&self) -> MutexGuard<'_, HashMap<u64, oneshot::Sender<AckResult>>> {
+        self.waiting.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn register(&self, id: u64) -> oneshot::Receiver<AckResult> {
+        let (tx, rx) = oneshot::channel();
+        self.waiting().insert(id, tx);
+        rx
+    }
+
+    /// Hands the reply to whoever waits for `id`; replies nobody waits for are dropped.
+    fn resolve(&self, id: u64, result: AckResult) {
+        if let Some(waiter) = self.waiting().remove(&id) {
+            let _ = waiter.send(result);
+        }
+    }
+
+    fn forget(&self, id: u64) {
+        self.waiting().remove(&id);
+    }
+
+    /// Drops every waiter; their receivers see the sender gone.
+    fn clear(&self) {
+        self.waiting().clear();
+    }
+}
+
+/// When the last frame arrived, shared between the client and its read task.
+struct Heartbeat {
+    epoch: Instant,
+    // Milliseconds since `epoch`, plus one so that 0 can mean "nothing received yet"
+    last_frame_ms: AtomicU64,
+}
+
+impl Heartbeat {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last_frame_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self) {
+        let now_ms = self.epoch.elapsed().as_millis() as u64 + 1;
+        self.last_frame_ms.store(now_ms, Ordering::Relaxed);
+    }
+
+    fn age(&self) -> Option<Duration> {
+        match self.last_frame_ms.load(Ordering::Relaxed) {
+            0 => None,
+            last_ms => {
+                let last = Duration::from_millis(last_ms - 1);
+                Some(self.epoch.elapsed().saturating_sub(last))
+            }
+        }
+    }
+}
+
+/// Consecutive reconnect attempts under a `ReconnectPolicy`.
+pub(crate) struct Backoff {
+    policy: ReconnectPolicy,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub(crate) fn new(policy: ReconnectPolicy) -> Self {
+        Self { policy, attempt: 0 }
+    }
+
+    /// Delay before the next attempt, or None once `max_attempts` is used up.
+    pub(crate) fn next_delay(&mut self) -> Option<Duration> {
+        if self.policy.max_attempts.is_some_and(|max| self.attempt >= max) {
+            return None;
+        }
+        let delay = self.policy.delay(self.attempt, jitter_sample());
+        self.attempt += 1;
+        Some(delay)
+    }
+
+    /// Starts over if the connection that just dropped had been up long enough.
+    pub(crate) fn connection_lasted(&mut self, uptime: Duration) {
+        if uptime >= self.policy.reset_after {
+            self.attempt = 0;
+        }
+    }
+}
+
+/// Uniform sample in [0, 1). Only spreads reconnects out, so std's randomly keyed hasher is enough.
+fn jitter_sample() -> f64 {
+    let bits = RandomState::new().hash_one(SystemTime::now());
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Maps a message to the subscribed stream it belongs to, using the parser's hint.
+fn attribute<P: MessageParser>(parser: &P, msg: &str, subscriptions: &SharedSubscriptions) -> Option<Stream> {
+    let hint = parser.stream_hint(msg)?;
+    lock_subscriptions(subscriptions)
+        .iter()
+        .find(|subscribed| hint_matches(subscribed, &hint))
+        .cloned()
+}
+
+/// Hints may differ in symbol case (Binance echoes uppercase) and carry no depth.
+fn hint_matches(subscribed: &Stream, hint: &Stream) -> bool {
+    if std::mem::discriminant(subscribed) != std::mem::discriminant(hint)
+        || !subscribed.symbol().eq_ignore_ascii_case(hint.symbol())
+    {
+        return false;
+    }
+    match (subscribed, hint) {
+        (Stream::Candles { interval: a, .. }, Stream::Candles { interval: b, .. }) => a == b,
+        _ => true,
+    }
+}
+
+/// Consecutive parse failures per subscribed stream.
+struct StreamHealth {
+    policy: StreamHealthPolicy,
+    failures: HashMap<Stream, u32>,
+}
+
+impl StreamHealth {
+    fn new(policy: StreamHealthPolicy) -> Self {
+        Self {
+            policy,
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Records a message for `stream`. Returns true when the stream just crossed the threshold.
+    fn observe(&mut self, stream: &Stream, parsed: bool) -> bool {
+        if parsed {
+            self.failures.remove(stream);
+            return false;
+        }
+
+        let count = self.failures.entry(stream.clone()).or_insert(0);
+        *count += 1;
+        if *count > self.policy.max_consecutive_failures {
+            // Start over so a stream that stays subscribed is reported again later, not on every message
+            self.failures.remove(stream);
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct TestParser;
+
+    impl MessageParser for TestParser {
+        fn endpoint(&self) -> &str {
+            "wss://example.invalid/ws"
+        }
+
+        fn format_subscribe(&self, _stream: &Stream) -> String {
+            "{\"op\":\"subscribe\"}".to_string()
+        }
+
+        fn format_unsubscribe(&self, _stream: &Stream) -> String {
+            "{\"op\":\"unsubscribe\"}".to_string()
+        }
+
+        fn parse_message(&self, _msg: &str) -> Option<MarketData> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "Test"
+        }
+
+        fn max_connection_duration_secs(&self) -> u64 {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_dedup() {
+        let mut client = WebSocketClient::new(TestParser);
+        let (tx, _rx) = mpsc::channel::<Message>(10);
+        client.ws_sender = Some(tx);
+        client.is_open = true;
+
+        let stream = Stream::candles("BTCUSDT", crate::indicators::timeframe::Timeframe::M1);
+        client.subscribe(stream.clone()).await.unwrap();
+        client.subscribe(stream).await.unwrap();
+
+        assert_eq!(client.subscriptions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_unoffered_interval_is_rejected_before_sending() {
+        use crate::indicators::timeframe::Timeframe;
+        use crate::market::providers::binance::BinanceParser;
+
+        let mut client = WebSocketClient::new(BinanceParser::new());
+        let (tx, mut sent) = mpsc::channel::<Message>(10);
+        client.ws_sender = Some(tx);
+        client.is_open = true;
+
+        let stream = Stream::candles("BTCUSDT", Timeframe::from_seconds(120).unwrap());
+        match client.subscribe(stream.clone()).await {
+            Err(CctError::SubscriptionRejected { stream: rejected, reason }) => {
+                assert_eq!(rejected, stream);
+                assert_eq!(reason, "Binance has no 2m candles");
+            }
+            other => panic!("Expected SubscriptionRejected, got {:?}", other),
+        }
+        assert!(sent.try_recv().is_err());
+        assert!(client.subscriptions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_resets_state() {
+        let mut client = WebSocketClient::new(TestParser);
+        let (tx, _rx) = mpsc::channel::<Message>(10);
+        client.ws_sender = Some(tx);
+        client.is_open = true;
+        client.connected_at = Some(Instant::now());
+
+        client.disconnect().await;
+
+        assert!(!client.is_open);
+        assert!(client.ws_sender.is_none());
+        assert!(client.connected_at.is_none());
+    }
+
+    #[test]
+    fn test_needs_reconnect_true() {
+        let mut client = WebSocketClient::new(TestParser);
+        client.connected_at = Some(Instant::now() - Duration::from_secs(2));
+        assert!(client.needs_reconnect());
+    }
+
+    #[test]
+    fn test_stream_health_threshold_and_reset() {
+        let mut health = StreamHealth::new(StreamHealthPolicy::new(2));
+        let stream = Stream::trades("BTCUSDT");
+
+        assert!(!health.observe(&stream, false));
+        assert!(!health.observe(&stream, false));
+        // Success resets the run
+        assert!(!health.observe(&stream, true));
+        assert!(!health.observe(&stream, false));
+        assert!(!health.observe(&stream, false));
+        assert!(health.observe(&stream, false));
+        // Counter restarts after reporting
+        assert!(!health.observe(&stream, false));
+    }
+
+    #[test]
+    fn test_hint_matches() {
+        use crate::indicators::timeframe::Timeframe;
+
+        assert!(hint_matches(&Stream::trades("btcusdt"), &Stream::trades("BTCUSDT")));
+        assert!(!hint_matches(&Stream::trades("BTCUSDT"), &Stream::trades("ETHUSDT")));
+        assert!(!hint_matches(
+            &Stream::candles("BTCUSDT", Timeframe::M1),
+            &Stream::candles("BTCUSDT", Timeframe::M5)
+        ));
+        assert!(hint_matches(
+            &Stream::order_book("BTCUSDT", 20),
+            &Stream::OrderBook { symbol: "BTCUSDT".to_string(), depth: 0 }
+        ));
+        assert!(!hint_matches(&Stream::trades("BTCUSDT"), &Stream::order_book("BTCUSDT", 5)));
+    }
+
+    /// Parses {"s":SYMBOL,"p":PRICE} into trades; hints by "s" alone.
+    struct ScriptedParser {
+        endpoint: String,
+    }
+
+    impl MessageParser for ScriptedParser {
+        fn endpoint(&self) -> &str {
+            &self.endpoint
+        }
+
+        fn format_subscribe(&self, stream: &Stream) -> String {
+            format!("sub:{}", stream.symbol())
+        }
+
+        fn format_unsubscribe(&self, stream: &Stream) -> String {
+            format!("unsub:{}", stream.symbol())
+        }
+
+        fn parse_message(&self, msg: &str) -> Option<MarketData> {
+            let value: serde_json::Value = serde_json::from_str(msg).ok()?;
+            let trade = crate::market::market_data::Trade::new(
+                0,
+                value["s"].as_str()?,
+                value["p"].as_f64()?,
+                1.0,
+                "t",
+                crate::market::market_data::TradeSide::Buy,
+            );
+            Some(MarketData::Trade(trade))
+        }
+
+        fn stream_hint(&self, msg: &str) -> Option<Stream> {
+            let value: serde_json::Value = serde_json::from_str(msg).ok()?;
+            Some(Stream::trades(value["s"].as_str()?))
+        }
+
+        fn name(&self) -> &'static str {
+            "Scripted"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broken_stream_reported_and_unsubscribed() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let (unsub_tx, unsub_rx) = tokio::sync::oneshot::channel::<String>();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            // Wait for both subscriptions before streaming
+            for _ in 0..2 {
+                ws.next().await.unwrap().unwrap();
+            }
+
+            let good = r#"{"s":"GOOD","p":1.0}"#;
+            let bad = r#"{"s":"BAD","p":"renamed"}"#;
+            let script = [bad, good, bad, r#"{"s":"BAD","p":2.0}"#, bad, good, bad, bad, good];
+            for frame in script {
+                ws.send(Message::Text(frame.into())).await.unwrap();
+            }
+
+            let reply = ws.next().await.unwrap().unwrap();
+            let _ = unsub_tx.send(reply.into_text().unwrap().to_string());
+            // Keep the socket open until the client is done
+            let _ = ws.next().await;
+        });
+
+        let parser = ScriptedParser { endpoint };
+        let mut client = WebSocketClient::new(parser)
+            .with_stream_health(StreamHealthPolicy::new(2).with_auto_unsubscribe(true));
+        let mut events = client.subscribe_events();
+        let mut data = client.connect().await.unwrap();
+        client.subscribe(Stream::trades("GOOD")).await.unwrap();
+        client.subscribe(Stream::trades("BAD")).await.unwrap();
+
+        let broken = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let ConnectionEvent::StreamBroken { stream, sample_error } = events.recv().await.unwrap() {
+                    return (stream, sample_error);
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(broken.0, Stream::trades("BAD"));
+        assert!(broken.1.contains("no market data recognized"));
+        assert!(broken.1.contains("renamed"));
+
+        let unsubscribe = tokio::time::timeout(Duration::from_secs(5), unsub_rx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(unsubscribe, "unsub:BAD");
+        assert_eq!(client.subscriptions(), vec![Stream::trades("GOOD")]);
+
+        // Healthy stream keeps flowing: 3 GOOD frames plus the one valid BAD frame
+        let mut symbols = Vec::new();
+        for _ in 0..4 {
+            match tokio::time::timeout(Duration::from_secs(5), data.recv()).await.unwrap() {
+                Some(MarketData::Trade(trade)) => symbols.push(trade.symbol),
+                other => panic!("Expected MarketData::Trade, got {:?}", other),
+            }
+        }
+        assert_eq!(symbols.iter().filter(|s| s.as_str() == "GOOD").count(), 3);
+
+        client.disconnect().await;
+        server.abort();
+    }
+
+    #[test]
+    fn test_reconnect_policy_delay() {
+        let policy = ReconnectPolicy::new(Duration::from_millis(100), Duration::from_secs(1)).with_jitter(0.0);
+        let delays: Vec<u128> = (0..6).map(|n| policy.delay(n, 0.5).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+        // Huge attempt counts saturate instead of overflowing
+        assert_eq!(policy.delay(100, 0.0), Duration::from_secs(1));
+
+        let jittered = policy.with_jitter(0.5);
+        assert_eq!(jittered.delay(1, 0.0), Duration::from_millis(200));
+        assert_eq!(jittered.delay(1, 1.0), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_backoff_max_attempts_and_reset() {
+        let policy = ReconnectPolicy::new(Duration::from_millis(10), Duration::from_secs(1))
+            .with_jitter(0.0)
+            .with_max_attempts(2)
+            .with_reset_after(Duration::from_secs(30));
+        let mut backoff = Backoff::new(policy);
+
+        assert_eq!(backoff.next_delay(), Some(Duration::from_millis(10)));
+        // A short-lived connection does not reset the backoff
+        backoff.connection_lasted(Duration::from_secs(1));
+        assert_eq!(backoff.next_delay(), Some(Duration::from_millis(20)));
+        assert_eq!(backoff.next_delay(), None);
+
+        // A healthy one does
+        backoff.connection_lasted(Duration::from_secs(30));
+        assert_eq!(backoff.next_delay(), Some(Duration::from_millis(10)));
+    }
+
+    fn expect_price(data: Option<MarketData>) -> f64 {
+        match data {
+            Some(MarketData::Trade(trade)) => trade.price,
+            other => panic!("Expected MarketData::Trade, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_reconnect_restores_subscriptions() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            // First connection: one trade, then the server drops it
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            ws.send(Message::Text(r#"{"s":"BTC","p":1.0}"#.into())).await.unwrap();
+            ws.close(None).await.unwrap();
+
+            // Second connection: the client must resubscribe on its own
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let resubscribe = ws.next().await.unwrap().unwrap().into_text().unwrap().to_string();
+            ws.send(Message::Text(r#"{"s":"BTC","p":2.0}"#.into())).await.unwrap();
+
+            // Runs until the client disconnects, after which nobody should come back
+            while let Some(Ok(_)) = ws.next().await {}
+            let came_back = tokio::time::timeout(Duration::from_millis(300), listener.accept())
+                .await
+                .is_ok();
+            (resubscribe, came_back)
+        });
+
+        let parser = ScriptedParser { endpoint };
+        let policy = ReconnectPolicy::new(Duration::from_millis(10), Duration::from_millis(50));
+        let mut client = WebSocketClient::new(parser).with_auto_reconnect(policy);
+        let mut events = client.subscribe_events();
+        let mut data = client.connect().await.unwrap();
+        client.subscribe(Stream::trades("BTC")).await.unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(expect_price(tokio::time::timeout(timeout, data.recv()).await.unwrap()), 1.0);
+        // Same receiver, fed by the new connection
+        assert_eq!(expect_price(tokio::time::timeout(timeout, data.recv()).await.unwrap()), 2.0);
+        assert_eq!(client.subscriptions(), vec![Stream::trades("BTC")]);
+        let stats = client.stats();
+        assert_eq!(stats.reconnects, 1);
+        assert_eq!(stats.trades, 2);
+        assert!(stats.frames_received >= 2 && stats.bytes_received > 0);
+        assert!(stats.uptime.is_some());
+
+        client.disconnect().await;
+        assert_eq!(client.stats().uptime, None);
+        let (resubscribe, came_back) = tokio::time::timeout(timeout, server).await.unwrap().unwrap();
+        assert_eq!(resubscribe, "sub:BTC");
+        assert!(!came_back, "client reconnected after a deliberate disconnect");
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        let endpoint = client.parser.endpoint().to_string();
+        let connecting = ConnectionEvent::Connecting { attempt: 1, endpoint: endpoint.clone() };
+        let connected = ConnectionEvent::Connected { endpoint };
+        assert_eq!(
+            received,
+            vec![
+                connecting.clone(),
+                connected.clone(),
+                ConnectionEvent::Disconnected { reason: "closed by server".to_string() },
+                ConnectionEvent::Reconnecting { attempt: 1 },
+                connecting,
+                connected,
+                ConnectionEvent::SubscriptionRestored { count: 1 },
+                ConnectionEvent::Disconnected { reason: "disconnect() called".to_string() },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_delivers_latest_and_counts_drops() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let (sent_tx, sent_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            for price in 1..=20 {
+                let frame = format!(r#"{{"s":"BTC","p":{}.0}}"#, price);
+                ws.send(Message::Text(frame.into())).await.unwrap();
+            }
+            let _ = sent_tx.send(());
+            let _ = ws.next().await;
+        });
+
+        let config = ClientConfig::new()
+            .with_market_data_capacity(3)
+            .with_overflow(OverflowPolicy::DropOldest);
+        let mut client = WebSocketClient::with_config(ScriptedParser { endpoint }, config);
+        let mut data = client.connect().await.unwrap();
+        client.subscribe(Stream::trades("BTC")).await.unwrap();
+
+        // Slow consumer: read nothing until everything has arrived
+        sent_rx.await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut prices = Vec::new();
+        while let Ok(market_data) = tokio::time::timeout(Duration::from_millis(100), data.recv()).await {
+            prices.push(expect_price(market_data));
+        }
+        let dropped = client.stats().dropped_messages;
+        assert_eq!(prices.last(), Some(&20.0));
+        assert!(prices.len() <= 5, "kept {:?}", prices);
+        assert_eq!(prices.len() as u64 + dropped, 20);
+
+        client.disconnect().await;
+        server.abort();
+    }
+
+    /// Accepts one connection and returns every frame received until (and including) Close.
+    fn spawn_recording_server(listener: tokio::net::TcpListener) -> JoinHandle<Vec<Message>> {
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let mut frames = Vec::new();
+            while let Some(Ok(msg)) = ws.next().await {
+                let is_close = msg.is_close();
+                frames.push(msg);
+                if is_close {
+                    break;
+                }
+            }
+            // Let tungstenite finish the close handshake
+            let _ = ws.next().await;
+            frames
+        })
+    }
+
+    #[tokio::test]
+    async fn test_graceful_disconnect_flushes_and_joins_tasks() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let server = spawn_recording_server(listener);
+
+        let mut client = WebSocketClient::new(ScriptedParser { endpoint });
+        let _data = client.connect().await.unwrap();
+        for symbol in ["BTC", "ETH", "SOL"] {
+            client.subscribe(Stream::trades(symbol)).await.unwrap();
+        }
+        let read_task = client.read_handle.as_ref().unwrap().abort_handle();
+        let write_task = client.write_handle.as_ref().unwrap().abort_handle();
+
+        assert!(client.disconnect_with_timeout(Duration::from_secs(2)).await);
+        assert!(read_task.is_finished());
+        assert!(write_task.is_finished());
+        assert!(!client.is_connected());
+
+        let frames = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        let texts: Vec<String> = frames
+            .iter()
+            .filter_map(|msg| msg.to_text().ok().filter(|t| !t.is_empty()).map(str::to_string))
+            .collect();
+        assert_eq!(texts, vec!["sub:BTC", "sub:ETH", "sub:SOL"]);
+        assert!(frames.last().unwrap().is_close());
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_times_out_and_force_disconnect_aborts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            // Completes the handshake but never reads, so the close is never acknowledged
+            let (socket, _) = listener.accept().await.unwrap();
+            let ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(ws);
+        });
+
+        let mut client = WebSocketClient::new(ScriptedParser { endpoint });
+        let _data = client.connect().await.unwrap();
+        let read_task = client.read_handle.as_ref().unwrap().abort_handle();
+        assert!(!client.disconnect_with_timeout(Duration::from_millis(100)).await);
+        tokio::task::yield_now().await;
+        assert!(read_task.is_finished());
+        server.abort();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let _server = spawn_recording_server(listener);
+        let mut client = WebSocketClient::new(ScriptedParser { endpoint });
+        let mut events = client.subscribe_events();
+        let _data = client.connect().await.unwrap();
+        client.force_disconnect().await;
+        assert!(!client.is_connected());
+        assert!(client.read_handle.is_none() && client.write_handle.is_none());
+        let mut last = None;
+        while let Ok(event) = events.try_recv() {
+            last = Some(event);
+        }
+        assert_eq!(
+            last,
+            Some(ConnectionEvent::Disconnected { reason: "force_disconnect() called".to_string() })
+        );
+    }
+
+    /// ScriptedParser for an exchange that cuts connections after one second.
+    struct ShortLivedParser(ScriptedParser);
+
+    impl MessageParser for ShortLivedParser {
+        fn endpoint(&self) -> &str {
+            self.0.endpoint()
+        }
+
+        fn format_subscribe(&self, stream: &Stream) -> String {
+            self.0.format_subscribe(stream)
+        }
+
+        fn format_unsubscribe(&self, stream: &Stream) -> String {
+            self.0.format_unsubscribe(stream)
+        }
+
+        fn parse_message(&self, msg: &str) -> Option<MarketData> {
+            self.0.parse_message(msg)
+        }
+
+        fn max_connection_duration_secs(&self) -> u64 {
+            1
+        }
+
+        fn name(&self) -> &'static str {
+            "ShortLived"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_refresh_swaps_connection_under_same_receiver() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut old = tokio_tungstenite::accept_async(socket).await.unwrap();
+            old.next().await.unwrap().unwrap();
+            old.send(Message::Text(r#"{"s":"BTC","p":1.0}"#.into())).await.unwrap();
+
+            // The replacement opens while the old connection is still up
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut new = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let resubscribe = new.next().await.unwrap().unwrap().into_text().unwrap().to_string();
+            let old_closed = old.next().await.unwrap().unwrap().is_close();
+            new.send(Message::Text(r#"{"s":"BTC","p":2.0}"#.into())).await.unwrap();
+            while let Some(Ok(_)) = new.next().await {}
+            (resubscribe, old_closed)
+        });
+
+        let config = ClientConfig::new()
+            .with_auto_refresh(true)
+            .with_refresh_margin(Duration::from_millis(500));
+        let mut client = WebSocketClient::with_config(ShortLivedParser(ScriptedParser { endpoint }), config);
+        let mut data = client.connect().await.unwrap();
+        client.subscribe(Stream::trades("BTC")).await.unwrap();
+        let mut events = client.subscribe_events();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(expect_price(tokio::time::timeout(timeout, data.recv()).await.unwrap()), 1.0);
+        assert_eq!(expect_price(tokio::time::timeout(timeout, data.recv()).await.unwrap()), 2.0);
+        assert_eq!(client.stats().reconnects, 1);
+
+        client.disconnect().await;
+        let (resubscribe, old_closed) = tokio::time::timeout(timeout, server).await.unwrap().unwrap();
+        assert_eq!(resubscribe, "sub:BTC");
+        assert!(old_closed);
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        let endpoint = client.parser.endpoint().to_string();
+        assert_eq!(
+            received,
+            vec![
+                ConnectionEvent::Connecting { attempt: 1, endpoint: endpoint.clone() },
+                ConnectionEvent::Connected { endpoint },
+                ConnectionEvent::SubscriptionRestored { count: 1 },
+                ConnectionEvent::Disconnected { reason: "disconnect() called".to_string() },
+            ]
+        );
+    }
+
+    /// ScriptedParser over binary frames: "ping:N" must be answered with "pong:N".
+    struct BinaryParser(ScriptedParser);
+
+    impl MessageParser for BinaryParser {
+        fn endpoint(&self) -> &str {
+            self.0.endpoint()
+        }
+
+        fn format_subscribe(&self, stream: &Stream) -> String {
+            self.0.format_subscribe(stream)
+        }
+
+        fn format_unsubscribe(&self, stream: &Stream) -> String {
+            self.0.format_unsubscribe(stream)
+        }
+
+        fn parse_message(&self, msg: &str) -> Option<MarketData> {
+            self.0.parse_message(msg)
+        }
+
+        fn decode_binary(&self, data: &[u8]) -> Option<DecodedFrame> {
+            let text = std::str::from_utf8(data).ok()?;
+            match text.strip_prefix("ping:") {
+                Some(n) => Some(DecodedFrame::Reply(format!("pong:{}", n))),
+                None => Some(DecodedFrame::Text(text.to_string())),
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            "Binary"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_binary_frames_are_decoded_and_pings_answered() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            ws.send(Message::Binary(b"ping:7".to_vec().into())).await.unwrap();
+            let pong = ws.next().await.unwrap().unwrap().into_text().unwrap().to_string();
+            ws.send(Message::Binary(br#"{"s":"BTC","p":3.0}"#.to_vec().into())).await.unwrap();
+            let _ = ws.next().await;
+            pong
+        });
+
+        let mut client = WebSocketClient::new(BinaryParser(ScriptedParser { endpoint }));
+        let mut data = client.connect().await.unwrap();
+        client.subscribe(Stream::trades("BTC")).await.unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(expect_price(tokio::time::timeout(timeout, data.recv()).await.unwrap()), 3.0);
+        client.disconnect().await;
+        assert_eq!(tokio::time::timeout(timeout, server).await.unwrap().unwrap(), "pong:7");
+    }
+
+    #[tokio::test]
+    async fn test_connect_stream_filters_and_ends_on_disconnect() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            for frame in [r#"{"s":"ETH","p":1.0}"#, r#"{"s":"BTC","p":2.0}"#] {
+                ws.send(Message::Text(frame.into())).await.unwrap();
+            }
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let mut client = WebSocketClient::new(ScriptedParser { endpoint });
+        let mut stream = client.connect_stream().await.unwrap().for_symbol("btc");
+        client.subscribe(Stream::trades("BTC")).await.unwrap();
+
+        let timeout = Duration::from_secs(5);
+        let data = tokio::time::timeout(timeout, stream.next()).await.unwrap();
+        // Tagged by the client, though ScriptedParser leaves it empty
+        assert_eq!(data.as_ref().map(MarketData::exchange), Some("Scripted"));
+        assert_eq!(expect_price(data), 2.0);
+        client.disconnect().await;
+        assert!(tokio::time::timeout(timeout, stream.next()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_auto_reconnect_gives_up_after_max_attempts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.close(None).await.unwrap();
+            // Dropping the listener refuses every reconnect attempt
+        });
+
+        let parser = ScriptedParser { endpoint };
+        let policy = ReconnectPolicy::new(Duration::from_millis(5), Duration::from_millis(10)).with_max_attempts(2);
+        let config = ClientConfig::new().with_connect_attempts(1);
+        let mut client = WebSocketClient::with_config(parser, config).with_auto_reconnect(policy);
+        let mut data = client.connect().await.unwrap();
+        server.await.unwrap();
+
+        // Once attempts run out the feed ends instead of going quiet forever
+        let end = tokio::time::timeout(Duration::from_secs(5), data.recv()).await.unwrap();
+        assert!(end.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_requires_open_receiver() {
+        let mut client = WebSocketClient::new(TestParser);
+        assert!(client.reconnect().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_typed_errors() {
+        let mut client = WebSocketClient::new(TestParser);
+        assert!(matches!(
+            client.subscribe(Stream::trades("BTCUSDT")).await,
+            Err(CctError::NotConnected)
+        ));
+
+        // Nothing listens on a port we just released
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let mut client = WebSocketClient::new(ScriptedParser { endpoint: endpoint.clone() });
+        match client.connect().await {
+            Err(CctError::ConnectFailed { endpoint: failed, .. }) => assert_eq!(failed, endpoint),
+            other => panic!("Expected CctError::ConnectFailed, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    struct FallbackParser {
+        primary: String,
+        fallback: String,
+    }
+
+    impl MessageParser for FallbackParser {
+        fn endpoint(&self) -> &str {
+            &self.primary
+        }
+
+        fn fallback_endpoint(&self) -> Option<&str> {
+            Some(&self.fallback)
+        }
+
+        fn format_subscribe(&self, stream: &Stream) -> String {
+            format!("sub:{}", stream.symbol())
+        }
+
+        fn format_unsubscribe(&self, stream: &Stream) -> String {
+            format!("unsub:{}", stream.symbol())
+        }
+
+        fn parse_message(&self, _msg: &str) -> Option<MarketData> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "Fallback"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_times_out_and_alternates_endpoints() {
+        // Accepts TCP (via the backlog) but never answers the WebSocket handshake
+        let blackhole = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let primary = format!("ws://{}", blackhole.local_addr().unwrap());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let fallback = format!("ws://{}", listener.local_addr().unwrap());
+        let server = spawn_recording_server(listener);
+
+        let config = ClientConfig::new()
+            .with_connect_timeout(Duration::from_millis(100))
+            .with_connect_attempts(3)
+            .with_connect_retry_delay(Duration::from_millis(10));
+        let parser = FallbackParser { primary: primary.clone(), fallback: fallback.clone() };
+        let mut client = WebSocketClient::with_config(parser, config.clone());
+        let mut events = client.subscribe_events();
+        let _data = tokio::time::timeout(Duration::from_secs(2), client.connect()).await.unwrap().unwrap();
+        assert_eq!(events.try_recv().unwrap(), ConnectionEvent::Connecting { attempt: 1, endpoint: primary.clone() });
+        assert_eq!(events.try_recv().unwrap(), ConnectionEvent::Connecting { attempt: 2, endpoint: fallback.clone() });
+        assert_eq!(events.try_recv().unwrap(), ConnectionEvent::Connected { endpoint: fallback.clone() });
+        client.disconnect().await;
+        server.await.unwrap();
+
+        // Every attempt blackholed: gives up with the last endpoint tried
+        let parser = FallbackParser { primary: primary.clone(), fallback: primary.clone() };
+        let mut client = WebSocketClient::with_config(parser, config.with_connect_attempts(2));
+        let started = Instant::now();
+        match client.connect().await {
+            Err(CctError::ConnectFailed { endpoint, .. }) => assert_eq!(endpoint, primary),
+            other => panic!("Expected CctError::ConnectFailed, got {:?}", other.map(|_| ())),
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(blackhole);
+    }
+
+    #[tokio::test]
+    async fn test_stale_watchdog_drops_silent_connection() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            // One frame, then silence with the socket left open
+            ws.send(Message::Ping(Vec::new().into())).await.unwrap();
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        // The parser never produces data; only the ping counts as activity
+        let parser = ScriptedParser { endpoint };
+        let mut client = WebSocketClient::new(parser).with_stale_after(Duration::from_millis(200));
+        let mut events = client.subscribe_events();
+        let mut data = client.connect().await.unwrap();
+
+        let reason = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let ConnectionEvent::Disconnected { reason } = events.recv().await.unwrap() {
+                    return reason;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(reason.starts_with("stale"), "unexpected reason: {}", reason);
+        assert!(client.last_message_age().unwrap() >= Duration::from_millis(200));
+
+        // Without auto-reconnect the feed ends
+        assert!(tokio::time::timeout(Duration::from_secs(5), data.recv()).await.unwrap().is_none());
+        server.abort();
+    }
+
+    #[test]
+    fn test_stale_watchdog_uses_parser_idle_timeout() {
+        let client = WebSocketClient::new(TestParser).with_stale_watchdog();
+        assert_eq!(client.stale_after, Some(Duration::from_secs(60)));
+        assert!(client.last_message_age().is_none());
+    }
+
+    /// Sends `{"op":"ping"}` every 20ms.
+    struct PingingParser {
+        endpoint: String,
+    }
+
+    impl MessageParser for PingingParser {
+        fn endpoint(&self) -> &str {
+            &self.endpoint
+        }
+
+        fn ping_message(&self) -> Option<(String, Duration)> {
+            Some((r#"{"op":"ping"}"#.to_string(), Duration::from_millis(20)))
+        }
+
+        fn format_subscribe(&self, _stream: &Stream) -> String {
+            String::new()
+        }
+
+        fn format_unsubscribe(&self, _stream: &Stream) -> String {
+            String::new()
+        }
+
+        fn parse_message(&self, _msg: &str) -> Option<MarketData> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "Pinging"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ping_task_sends_payload_and_stops_on_disconnect() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let (pinged_tx, pinged_rx) = tokio::sync::oneshot::channel::<Vec<String>>();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let mut pings = Vec::new();
+            while pings.len() < 3 {
+                if let Some(Ok(Message::Text(text))) = ws.next().await {
+                    pings.push(text.to_string());
+                }
+            }
+            let _ = pinged_tx.send(pings);
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let mut client = WebSocketClient::new(PingingParser { endpoint });
+        let _data = client.connect().await.unwrap();
+        let ping_task = client.ping_handle.as_ref().unwrap().abort_handle();
+
+        let pings = tokio::time::timeout(Duration::from_secs(5), pinged_rx).await.unwrap().unwrap();
+        assert!(pings.iter().all(|ping| ping == r#"{"op":"ping"}"#));
+
+        client.disconnect().await;
+        assert!(client.ping_handle.is_none());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(ping_task.is_finished());
+        server.abort();
+    }
+
+    /// Tags requests with ids; replies are {"id":N} for an ack or {"id":N,"error":"..."}.
+    struct AckingParser {
+        endpoint: String,
+    }
+
+    impl MessageParser for AckingParser {
+        fn endpoint(&self) -> &str {
+            &self.endpoint
+        }
+
+        fn format_subscribe(&self, stream: &Stream) -> String {
+            format!("0:sub:{}", stream.symbol())
+        }
+
+        fn format_unsubscribe(&self, stream: &Stream) -> String {
+            format!("0:unsub:{}", stream.symbol())
+        }
+
+        fn format_subscribe_with_id(&self, stream: &Stream, id: u64) -> Option<String> {
+            Some(format!("{}:sub:{}", id, stream.symbol()))
+        }
+
+        fn format_subscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+            let symbols: Vec<&str> = streams.iter().map(Stream::symbol).collect();
+            Some(format!("{}:sub:{}", id, symbols.join(",")))
+        }
+
+        fn format_unsubscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+            let symbols: Vec<&str> = streams.iter().map(Stream::symbol).collect();
+            Some(format!("{}:unsub:{}", id, symbols.join(",")))
+        }
+
+        fn parse_response(&self, msg: &str) -> Option<(u64, Result<(), String>)> {
+            let value: serde_json::Value = serde_json::from_str(msg).ok()?;
+            let id = value["id"].as_u64()?;
+            match value["error"].as_str() {
+                Some(error) => Some((id, Err(error.to_string()))),
+                None => Some((id, Ok(()))),
+            }
+        }
+
+        fn parse_message(&self, _msg: &str) -> Option<MarketData> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "Acking"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_waits_for_ack_and_surfaces_rejection() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let mut ids = Vec::new();
+            while let Some(Ok(Message::Text(request))) = ws.next().await {
+                let (id, rest) = request.split_once(':').unwrap();
+                ids.push(id.parse::<u64>().unwrap());
+                let reply = match rest {
+                    "sub:GOOD" => format!(r#"{{"id":{}}}"#, id),
+                    "sub:TYPO" => format!(r#"{{"id":{},"error":"Invalid symbol"}}"#, id),
+                    _ => continue, // never acknowledged
+                };
+                ws.send(Message::Text(reply.into())).await.unwrap();
+            }
+            ids
+        });
+
+        let parser = AckingParser { endpoint };
+        let mut client = WebSocketClient::new(parser).with_ack_timeout(Duration::from_millis(200));
+        let _data = client.connect().await.unwrap();
+
+        client.subscribe(Stream::trades("GOOD")).await.unwrap();
+
+        match client.subscribe(Stream::trades("TYPO")).await {
+            Err(CctError::SubscriptionRejected { stream, reason }) => {
+                assert_eq!(stream, Stream::trades("TYPO"));
+                assert_eq!(reason, "Invalid symbol");
+            }
+            other => panic!("Expected SubscriptionRejected, got {:?}", other),
+        }
+
+        match client.subscribe(Stream::trades("SILENT")).await {
+            Err(CctError::AckTimeout { stream, .. }) => assert_eq!(stream, Stream::trades("SILENT")),
+            other => panic!("Expected AckTimeout, got {:?}", other),
+        }
+
+        // Only the acknowledged stream is tracked
+        assert_eq!(client.subscriptions(), vec![Stream::trades("GOOD")]);
+
+        client.disconnect().await;
+        let ids = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_many_sends_one_deduplicated_frame() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let mut requests = Vec::new();
+            while let Some(Ok(Message::Text(request))) = ws.next().await {
+                let (id, rest) = request.split_once(':').unwrap();
+                requests.push(rest.to_string());
+                ws.send(Message::Text(format!(r#"{{"id":{}}}"#, id).into())).await.unwrap();
+            }
+            requests
+        });
+
+        let parser = AckingParser { endpoint };
+        let mut client = WebSocketClient::new(parser);
+        let _data = client.connect().await.unwrap();
+
+        client.subscribe(Stream::trades("A")).await.unwrap();
+        let streams = vec![
+            Stream::trades("A"),
+            Stream::trades("B"),
+            Stream::trades("C"),
+            Stream::trades("B"),
+        ];
+        client.subscribe_many(streams).await.unwrap();
+        assert_eq!(
+            client.subscriptions(),
+            vec![Stream::trades("A"), Stream::trades("B"), Stream::trades("C")]
+        );
+        // Everything already subscribed: no frame at all
+        client.subscribe_many(vec![Stream::trades("C")]).await.unwrap();
+
+        client.disconnect().await;
+        let requests = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        assert_eq!(requests, vec!["sub:A", "sub:B,C"]);
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_symbol_and_all() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let server = spawn_recording_server(listener);
+
+        let mut client = WebSocketClient::new(AckingParser { endpoint });
+        assert!(matches!(client.unsubscribe_all().await, Err(CctError::NotConnected)));
+        let _data = client.connect().await.unwrap();
+        // Subscribed without waiting for acks, so the frames are all fire-and-forget
+        client.subscriptions_guard().extend([
+            Stream::trades("BTC"),
+            Stream::book_ticker("btc"),
+            Stream::trades("ETH"),
+            Stream::trades("SOL"),
+        ]);
+
+        assert!(client.has_subscription(&Stream::trades("ETH")));
+        assert_eq!(
+            client.subscriptions_for_symbol("BTC"),
+            vec![Stream::trades("BTC"), Stream::book_ticker("btc")]
+        );
+
+        let removed = client.unsubscribe_symbol("BTC").await.unwrap();
+        assert_eq!(removed, vec![Stream::trades("BTC"), Stream::book_ticker("btc")]);
+        assert!(client.subscriptions_for_symbol("btc").is_empty());
+
+        let removed = client.unsubscribe_all().await.unwrap();
+        assert_eq!(removed, vec![Stream::trades("ETH"), Stream::trades("SOL")]);
+        assert!(client.subscriptions().is_empty());
+        assert!(client.unsubscribe_all().await.unwrap().is_empty());
+
+        client.disconnect().await;
+        let frames = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        let texts: Vec<String> = frames
+            .iter()
+            .filter_map(|msg| msg.to_text().ok().filter(|t| !t.is_empty()))
+            .map(|text| text.split_once(':').unwrap().1.to_string())
+            .collect();
+        assert_eq!(texts, vec!["unsub:BTC,btc", "unsub:ETH,SOL"]);
+    }
+
+    #[test]
+    fn test_restore_frames_batch_when_supported() {
+        let requests = PendingRequests::new();
+        let streams: Vec<Stream> = (0..SUBSCRIBE_BATCH_SIZE + 1).map(|i| Stream::trades(i.to_string())).collect();
+
+        let batched = restore_frames(&AckingParser { endpoint: String::new() }, &streams, &requests);
+        assert_eq!(batched.len(), 2);
+        assert!(batched[1].ends_with(&format!("sub:{}", SUBSCRIBE_BATCH_SIZE)));
+
+        let single = restore_frames(&ScriptedParser { endpoint: String::new() }, &streams[..3], &requests);
+        assert_eq!(single, vec!["sub:0", "sub:1", "sub:2"]);
     }
 }
@@ -0,0 +1,153 @@
+//! `MessageParser` for `MockExchangeServer`'s protocol.
+//!
+//! Requests:  `{"op":"subscribe","id":1,"streams":["trades:BTCUSDT"]}` (or "unsubscribe")
+//! Replies:   `{"id":1,"ok":true}` or `{"id":1,"ok":false,"error":"..."}`
+//! Data:      a serialized `MarketData`, e.g. `{"type":"trade","data":{...}}`
+//! History:   `GET /candles?symbol=BTCUSDT&interval=1m&start=..&end=..` on the same
+//!            port over HTTP, answered with a JSON array of `Candle`
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::error::CctError;
+use crate::market::market_data::MarketData;
+use crate::market::message_parser::MessageParser;
+use crate::market::streams::Stream;
+
+/// Stable text form of a stream used on the wire, e.g. "trades:BTCUSDT",
+/// "candles:BTCUSDT:1m", "order_book:BTCUSDT:10".
+pub fn stream_key(stream: &Stream) -> String {
+    match stream {
+        Stream::Candles { symbol, interval } => format!("candles:{}:{}", symbol, interval),
+        Stream::Trades { symbol } => format!("trades:{}", symbol),
+        Stream::AggTrades { symbol } => format!("agg_trades:{}", symbol),
+        Stream::Funding { symbol } => format!("funding:{}", symbol),
+        Stream::MarkPrice { symbol } => format!("mark_price:{}", symbol),
+        Stream::OrderBook { symbol, depth } => format!("order_book:{}:{}", symbol, depth),
+        Stream::BookTicker { symbol } => format!("book_ticker:{}", symbol),
+        Stream::Ticker { symbol } => format!("ticker:{}", symbol),
+        Stream::AllMiniTickers => "all_mini_tickers".to_string(),
+        Stream::OpenInterest { symbol } => format!("open_interest:{}", symbol),
+        Stream::Liquidations { symbol } => format!("liquidations:{}", symbol),
+    }
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    id: u64,
+    ok: bool,
+    #[serde(default)]
+    error: String,
+}
+
+/// Parser for a `MockExchangeServer` at `endpoint` (see `MockExchangeServer::parser`).
+#[derive(Debug, Clone)]
+pub struct MockParser {
+    endpoint: String,
+}
+
+impl MockParser {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into() }
+    }
+
+    fn request(op: &str, streams: &[Stream], id: u64) -> String {
+        let streams: Vec<String> = streams.iter().map(stream_key).collect();
+        json!({ "op": op, "id": id, "streams": streams }).to_string()
+    }
+}
+
+impl MessageParser for MockParser {
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    async fn fetch_candles(
+        &self,
+        symbol: &str,
+        interval: Timeframe,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<Vec<Candle>, CctError> {
+        let base = self.endpoint.replacen("ws://", "http://", 1);
+        let url = format!("{}/candles?symbol={}&interval={}&start={}&end={}", base, symbol, interval, start_ms, end_ms);
+        let failed = |e: reqwest::Error| CctError::ConnectFailed { endpoint: url.clone(), source: e.into() };
+        let body = reqwest::get(&url).await.map_err(failed)?.text().await.map_err(failed)?;
+        serde_json::from_str(&body)
+            .map_err(|e| CctError::ParseFailed { exchange: "Mock", message: format!("{}: {}", e, body) })
+    }
+
+    fn format_subscribe(&self, stream: &Stream) -> String {
+        Self::request("subscribe", std::slice::from_ref(stream), 0)
+    }
+
+    fn format_unsubscribe(&self, stream: &Stream) -> String {
+        Self::request("unsubscribe", std::slice::from_ref(stream), 0)
+    }
+
+    fn format_subscribe_with_id(&self, stream: &Stream, id: u64) -> Option<String> {
+        Some(Self::request("subscribe", std::slice::from_ref(stream), id))
+    }
+
+    fn format_unsubscribe_with_id(&self, stream: &Stream, id: u64) -> Option<String> {
+        Some(Self::request("unsubscribe", std::slice::from_ref(stream), id))
+    }
+
+    fn format_subscribe_many(&self, streams: &[Stream]) -> String {
+        Self::request("subscribe", streams, 0)
+    }
+
+    fn format_subscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+        Some(Self::request("subscribe", streams, id))
+    }
+
+    fn format_unsubscribe_many(&self, streams: &[Stream]) -> String {
+        Self::request("unsubscribe", streams, 0)
+    }
+
+    fn format_unsubscribe_many_with_id(&self, streams: &[Stream], id: u64) -> Option<String> {
+        Some(Self::request("unsubscribe", streams, id))
+    }
+
+    fn parse_response(&self, msg: &str) -> Option<(u64, Result<(), String>)> {
+        let reply: Reply = serde_json::from_str(msg).ok()?;
+        Some((reply.id, if reply.ok { Ok(()) } else { Err(reply.error) }))
+    }
+
+    fn parse_message(&self, msg: &str) -> Option<MarketData> {
+        serde_json::from_str(msg).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::timeframe::Timeframe;
+    use crate::market::market_data::{Trade, TradeSide};
+
+    #[test]
+    fn test_requests_and_replies() {
+        let parser = MockParser::new("ws://127.0.0.1:1");
+        let streams = [Stream::trades("BTCUSDT"), Stream::candles("ETHUSDT", Timeframe::M5)];
+        let request: serde_json::Value =
+            serde_json::from_str(&parser.format_subscribe_many_with_id(&streams, 7).unwrap()).unwrap();
+        assert_eq!(request, json!({"op": "subscribe", "id": 7, "streams": ["trades:BTCUSDT", "candles:ETHUSDT:5m"]}));
+
+        assert_eq!(parser.parse_response(r#"{"id":7,"ok":true}"#), Some((7, Ok(()))));
+        assert_eq!(
+            parser.parse_response(r#"{"id":8,"ok":false,"error":"unknown symbol"}"#),
+            Some((8, Err("unknown symbol".to_string())))
+        );
+
+        let trade = MarketData::Trade(Trade::new(1, "BTCUSDT", 2.0, 1.0, "t", TradeSide::Sell));
+        let parsed = parser.parse_message(&serde_json::to_string(&trade).unwrap()).unwrap();
+        assert_eq!(parsed.as_trade().unwrap().price, 2.0);
+        assert!(parser.parse_message(r#"{"id":7,"ok":true}"#).is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! `MockExchangeServer`: a local WebSocket exchange speaking `MockParser`'s protocol.
+//!
+//! Every request is acknowledged (unless its stream was passed to `reject`), and
+//! the scripted frames are replayed to a connection after its first subscribe.
+//! `push` and `close_all` drive the open connections from the test.
+//!
+//! The same port also answers `GET /candles?symbol=..&interval=..&start=..&end=..`
+//! over plain HTTP with the candles set by `candle_history`, for REST backfill
+//! (`MockParser::fetch_candles`).
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::indicators::candle::Candle;
+use crate::indicators::timeframe::Timeframe;
+use crate::market::market_data::MarketData;
+use crate::market::streams::Stream;
+use crate::testing::mock_parser::{MockParser, stream_key};
+
+/// A request received by the server, in arrival order across connections.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MockRequest {
+    pub op: String,
+    pub id: u64,
+    pub streams: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Command {
+    Frame(String),
+    Close,
+}
+
+#[derive(Default)]
+struct Shared {
+    script: Mutex<Vec<String>>,
+    rejected: Mutex<HashSet<String>>,
+    requests: Mutex<Vec<MockRequest>>,
+    /// Candles served over REST, by candle stream key.
+    history: Mutex<HashMap<String, Vec<Candle>>>,
+    /// Subscribed stream keys per open connection.
+    subscribed: Mutex<HashMap<usize, BTreeSet<String>>>,
+    next_connection: AtomicUsize,
+}
+
+pub struct MockExchangeServer {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    commands: broadcast::Sender<Command>,
+    accept_task: JoinHandle<()>,
+}
+
+impl MockExchangeServer {
+    /// Binds to a free port on 127.0.0.1 and starts accepting connections.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared::default());
+        let (commands, _) = broadcast::channel(1024);
+
+        let accept_task = tokio::spawn({
+            let shared = Arc::clone(&shared);
+            let commands = commands.clone();
+            async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    tokio::spawn(route(socket, Arc::clone(&shared), commands.subscribe()));
+                }
+            }
+        });
+
+        Ok(Self { addr, shared, commands, accept_task })
+    }
+
+    /// `ws://` URL of the server.
+    pub fn endpoint(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// A parser pointed at this server.
+    pub fn parser(&self) -> MockParser {
+        MockParser::new(self.endpoint())
+    }
+
+    /// Frames sent to each connection right after its first subscribe request is
+    /// acknowledged. Applies to connections that have not subscribed yet.
+    pub fn script(&self, frames: impl IntoIterator<Item = impl Into<String>>) {
+        *self.shared.script.lock().unwrap() = frames.into_iter().map(Into::into).collect();
+    }
+
+    /// Same as `script`, serializing each item into the `MockParser` wire format.
+    pub fn script_market_data(&self, items: &[MarketData]) {
+        self.script(items.iter().map(to_frame));
+    }
+
+    /// Candles returned by `GET /candles` for `symbol` and `interval` (those opening
+    /// in the requested range), e.g. history for a backfill before the live feed.
+    pub fn candle_history(&self, symbol: &str, interval: Timeframe, candles: Vec<Candle>) {
+        let key = stream_key(&Stream::candles(symbol, interval));
+        self.shared.history.lock().unwrap().insert(key, candles);
+    }
+
+    /// Makes requests that include `stream_key` fail with an error reply.
+    pub fn reject(&self, stream_key: impl Into<String>) {
+        self.shared.rejected.lock().unwrap().insert(stream_key.into());
+    }
+
+    /// Sends a raw text frame to every open connection.
+    pub fn push(&self, frame: impl Into<String>) {
+        let _ = self.commands.send(Command::Frame(frame.into()));
+    }
+
+    pub fn push_market_data(&self, market_data: &MarketData) {
+        self.push(to_frame(market_data));
+    }
+
+    /// Closes every open connection with a close frame. New connections are still
+    /// accepted, so a client with auto-reconnect comes back.
+    pub fn close_all(&self) {
+        let _ = self.commands.send(Command::Close);
+    }
+
+    /// Number of open connections.
+    pub fn connection_count(&self) -> usize {
+        self.shared.subscribed.lock().unwrap().len()
+    }
+
+    /// Every request received so far.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.shared.requests.lock().unwrap().clone()
+    }
+
+    /// Stream keys subscribed on the open connections, sorted.
+    pub fn subscribed(&self) -> Vec<String> {
+        let subscribed = self.shared.subscribed.lock().unwrap();
+        let all: BTreeSet<&String> = subscribed.values().flatten().collect();
+        all.into_iter().cloned().collect()
+    }
+}
+
+impl Drop for MockExchangeServer {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+        let _ = self.commands.send(Command::Close);
+    }
+}
+
+fn to_frame(market_data: &MarketData) -> String {
+    serde_json::to_string(market_data).expect("market data serializes")
+}
+
+/// Sends `GET /candles` requests to the REST handler, anything else to the WebSocket one.
+async fn route(socket: TcpStream, shared: Arc<Shared>, commands: broadcast::Receiver<Command>) {
+    const PREFIX: &[u8] = b"GET /candles";
+    let mut head = [0u8; PREFIX.len()];
+    let is_rest = loop {
+        match socket.peek(&mut head).await {
+            Ok(n) if n >= PREFIX.len() || !PREFIX.starts_with(&head[..n]) => break head.starts_with(PREFIX),
+            // Closed, or only part of the prefix has arrived yet
+            Ok(0) | Err(_) => return,
+            Ok(_) => tokio::task::yield_now().await,
+        }
+    };
+    if is_rest {
+        serve_candles(socket, &shared).await;
+    } else {
+        serve(socket, shared, commands).await;
+    }
+}
+
+/// Answers one `GET /candles?symbol=..&interval=..&start=..&end=..` with a JSON array.
+async fn serve_candles(mut socket: TcpStream, shared: &Shared) {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let target = request.split_whitespace().nth(1).unwrap_or_default();
+    let query: HashMap<&str, &str> = target
+        .split_once('?')
+        .map(|(_, query)| query.split('&').filter_map(|pair| pair.split_once('=')).collect())
+        .unwrap_or_default();
+    let bound = |name: &str| query.get(name).and_then(|value| value.parse::<u64>().ok());
+
+    let (status, body) = match (query.get("symbol"), query.get("interval"), bound("start"), bound("end")) {
+        (Some(symbol), Some(interval), Some(start), Some(end)) => {
+            let key = format!("candles:{}:{}", symbol, interval);
+            let history = shared.history.lock().unwrap();
+            let candles: Vec<&Candle> = history
+                .get(&key)
+                .into_iter()
+                .flatten()
+                .filter(|candle| (start..end).contains(&candle.get_timestamp()))
+                .collect();
+            ("200 OK", serde_json::to_string(&candles).expect("candles serialize"))
+        }
+        _ => ("400 Bad Request", json!({ "error": "expected symbol, interval, start and end" }).to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+async fn serve(socket: TcpStream, shared: Arc<Shared>, mut commands: broadcast::Receiver<Command>) {
+    let Ok(mut ws) = tokio_tungstenite::accept_async(socket).await else {
+        return;
+    };
+    let id = shared.next_connection.fetch_add(1, Ordering::Relaxed);
+    shared.subscribed.lock().unwrap().insert(id, BTreeSet::new());
+    let mut script_sent = false;
+
+    loop {
+        tokio::select! {
+            msg = ws.next() => {
+                let Some(Ok(msg)) = msg else { break };
+                let Message::Text(text) = msg else { continue };
+                let Ok(request) = serde_json::from_str::<MockRequest>(&text) else { continue };
+
+                let rejected = {
+                    let rejected = shared.rejected.lock().unwrap();
+                    request.streams.iter().find(|key| rejected.contains(*key)).cloned()
+                };
+                let reply = match &rejected {
+                    Some(key) => json!({ "id": request.id, "ok": false, "error": format!("rejected: {}", key) }),
+                    None => json!({ "id": request.id, "ok": true }),
+                };
+                if rejected.is_none() {
+                    let mut subscribed = shared.subscribed.lock().unwrap();
+                    let set = subscribed.entry(id).or_default();
+                    for key in &request.streams {
+                        match request.op.as_str() {
+                            "subscribe" => { set.insert(key.clone()); }
+                            "unsubscribe" => { set.remove(key); }
+                            _ => {}
+                        }
+                    }
+                }
+                let is_subscribe = request.op == "subscribe";
+                shared.requests.lock().unwrap().push(request);
+                if ws.send(Message::Text(reply.to_string().into())).await.is_err() {
+                    break;
+                }
+
+                if is_subscribe && rejected.is_none() && !script_sent {
+                    script_sent = true;
+                    let script = shared.script.lock().unwrap().clone();
+                    for frame in script {
+                        if ws.send(Message::Text(frame.into())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            command = commands.recv() => match command {
+                Ok(Command::Frame(frame)) => {
+                    if ws.send(Message::Text(frame.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(Command::Close) | Err(broadcast::error::RecvError::Closed) => {
+                    let _ = ws.close(None).await;
+                    break;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+            },
+        }
+    }
+
+    shared.subscribed.lock().unwrap().remove(&id);
+}
@@ -0,0 +1,9 @@
+//! Test support: an in-process exchange (`MockExchangeServer`) and the parser that
+//! talks to it (`MockParser`), for end-to-end tests of `WebSocketClient` without a
+//! network. Enabled by the `test-util` feature.
+
+pub mod mock_parser;
+pub mod mock_server;
+
+pub use mock_parser::{MockParser, stream_key};
+pub use mock_server::{MockExchangeServer, MockRequest};
@@ -0,0 +1,132 @@
+//! End-to-end test of `new_binance_client()` against a local WebSocket server:
+//! subscribe, replay captured frames, and check what comes out of the receiver.
+//!
+//! The frames are the golden pipeline fixture (tests/fixtures/golden), so the client's
+//! output must match parsing the same frames directly with `BinanceParser`.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use cct::indicators::timeframe::Timeframe;
+use cct::market::providers::binance::BinanceParser;
+use cct::market::{ClientConfig, ConnectionEvent, MessageParser, Stream, WebSocketClient, new_binance_client};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn captured_frames() -> Vec<String> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/golden/binance_pipeline.ndjson");
+    std::fs::read_to_string(path)
+        .expect("fixture exists")
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Acknowledges every request, then replays `frames` once the first `subscriptions`
+/// requests have arrived. Returns the requests it received.
+async fn mock_binance(listener: TcpListener, subscriptions: usize, frames: Vec<String>) -> Vec<Value> {
+    let (socket, _) = listener.accept().await.unwrap();
+    let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+    let mut requests = Vec::new();
+    while let Some(Ok(msg)) = ws.next().await {
+        let Message::Text(text) = msg else { continue };
+        let request: Value = serde_json::from_str(&text).unwrap();
+        let ack = format!(r#"{{"result":null,"id":{}}}"#, request["id"]);
+        ws.send(Message::Text(ack.into())).await.unwrap();
+        requests.push(request);
+
+        if requests.len() == subscriptions {
+            for frame in &frames {
+                ws.send(Message::Text(frame.as_str().into())).await.unwrap();
+            }
+        }
+    }
+    requests
+}
+
+#[tokio::test]
+async fn binance_client_against_mock_server() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("ws://{}/ws", listener.local_addr().unwrap());
+    let frames = captured_frames();
+    let server = tokio::spawn(mock_binance(listener, 2, frames.clone()));
+
+    let mut client = new_binance_client().with_endpoint(&endpoint);
+    let mut events = client.subscribe_events();
+    let mut rx = client.connect().await.unwrap();
+    client.subscribe(Stream::trades("BTCUSDT")).await.unwrap();
+    client.subscribe(Stream::candles("BTCUSDT", Timeframe::M1)).await.unwrap();
+
+    let parser = BinanceParser::new();
+    let expected: Vec<String> = frames
+        .iter()
+        .flat_map(|frame| parser.parse_messages(frame))
+        .map(|data| format!("{:?}", data))
+        .collect();
+    assert!(!expected.is_empty());
+
+    let mut received = Vec::with_capacity(expected.len());
+    while received.len() < expected.len() {
+        let mut data = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+        // Stamped by the client on receipt; the rest must match direct parsing
+        assert!(data.received_at() > 0);
+        data.set_received_at(0);
+        received.push(format!("{:?}", data));
+    }
+    assert_eq!(received, expected);
+    // The fixture's own acks ({"result":null,"id":1}) are replies, not data
+    assert!(tokio::time::timeout(Duration::from_millis(100), rx.recv()).await.is_err());
+
+    client.disconnect().await;
+    let requests = tokio::time::timeout(TIMEOUT, server).await.unwrap().unwrap();
+    let params: Vec<&str> = requests.iter().map(|r| r["params"][0].as_str().unwrap()).collect();
+    assert_eq!(params, vec!["btcusdt@trade", "btcusdt@kline_1m"]);
+    assert!(requests.iter().all(|r| r["method"] == "SUBSCRIBE"));
+
+    // Connected to the override, not to stream.binance.com
+    let connected = std::iter::from_fn(|| events.try_recv().ok())
+        .find_map(|event| match event {
+            ConnectionEvent::Connected { endpoint } => Some(endpoint),
+            _ => None,
+        });
+    assert_eq!(connected, Some(endpoint));
+}
+
+#[tokio::test]
+async fn binance_client_keeps_raw_frames_only_when_asked() {
+    let klines: Vec<String> =
+        captured_frames().into_iter().filter(|frame| frame.contains(r#""e":"kline""#)).take(3).collect();
+
+    for keep_raw in [true, false] {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}/ws", listener.local_addr().unwrap());
+        let server = tokio::spawn(mock_binance(listener, 1, klines.clone()));
+
+        let config = ClientConfig::new().with_keep_raw(keep_raw);
+        let mut client = WebSocketClient::with_config(BinanceParser::new(), config).with_endpoint(&endpoint);
+        let mut rx = client.connect().await.unwrap();
+        client.subscribe(Stream::candles("BTCUSDT", Timeframe::M1)).await.unwrap();
+
+        for frame in &klines {
+            let data = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+            assert!(data.is_candle());
+            let expected: Value = serde_json::from_str(frame).unwrap();
+            if keep_raw {
+                // Quote asset volume: sent by Binance, not carried by Candle
+                assert_eq!(data.raw_field("k.q"), Some(&expected["k"]["q"]));
+                assert_eq!(data.raw(), Some(&expected));
+            } else {
+                assert!(data.raw().is_none());
+                assert!(data.raw_field("k.q").is_none());
+            }
+        }
+
+        client.disconnect().await;
+        tokio::time::timeout(TIMEOUT, server).await.unwrap().unwrap();
+    }
+}
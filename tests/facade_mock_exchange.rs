@@ -0,0 +1,97 @@
+//! End-to-end tests of the `App` facade against `cct::testing::MockExchangeServer`:
+//! REST warm-up backfill, then live data applied on top.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cct::facade::{App, AppEvent, Indicator};
+use cct::indicators::candle::Candle;
+use cct::indicators::timeframe::Timeframe;
+use cct::market::market_data::{Trade, TradeSide};
+use cct::market::{MarketData, Stream, WebSocketClient};
+use cct::testing::{MockExchangeServer, stream_key};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+fn candle(ts: u64, close: f64) -> Candle {
+    Candle::new(ts, close, close + 1.0, close - 1.0, close, 10.0)
+}
+
+#[tokio::test]
+async fn test_app_backfills_then_applies_live_data() {
+    let server = MockExchangeServer::start().await.unwrap();
+    // Five closed M1 candles before the current one
+    let open = Timeframe::M1.align(now_ms());
+    let history = (1..=5u64).rev().map(|k| candle(open - k * 60_000, 105.0 - k as f64)).collect();
+    server.candle_history("BTCUSDT", Timeframe::M1, history);
+
+    let app = App::builder()
+        .symbols(["BTCUSDT"])
+        .timeframes([Timeframe::M1])
+        .indicators([Indicator::Sma(3)])
+        .build_with_client(WebSocketClient::new(server.parser()))
+        .await
+        .unwrap();
+    let mut events = app.events();
+    assert_eq!(
+        server.subscribed(),
+        vec![stream_key(&Stream::candles("BTCUSDT", Timeframe::M1)), stream_key(&Stream::trades("BTCUSDT"))]
+    );
+
+    // Warmed up before any live data: SMA(3) over 102, 103, 104
+    let snapshot = app.snapshot("BTCUSDT").unwrap();
+    assert_eq!(snapshot.timeframes[&Timeframe::M1].history_len, 5);
+    assert_eq!(snapshot.indicator(Timeframe::M1, Indicator::Sma(3)), Some(103.0));
+    assert_eq!(snapshot.candle(Timeframe::M1).unwrap().get_timestamp(), open - 60_000);
+    assert_eq!(snapshot.staleness, None);
+
+    server.push_market_data(&MarketData::candle("BTCUSDT", Timeframe::M1, candle(open, 108.0), true));
+    server.push_market_data(&MarketData::Trade(Trade::new(open + 500, "BTCUSDT", 108.5, 0.1, "1", TradeSide::Buy)));
+
+    let (closed, values, trade) = tokio::time::timeout(TIMEOUT, async {
+        let mut closed = None;
+        let mut values = None;
+        loop {
+            match events.recv().await.unwrap() {
+                AppEvent::CandleClosed { candle, .. } => closed = Some(candle),
+                AppEvent::IndicatorsUpdated { values: updated, .. } => values = Some(updated),
+                AppEvent::Trade(trade) => return (closed, values, trade),
+                AppEvent::Alert(_) => {}
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(closed.unwrap().get_close(), 108.0);
+    // SMA(3) over 103, 104 from the backfill and the live 108
+    assert_eq!(values.unwrap(), vec![(Indicator::Sma(3), 105.0)]);
+    assert_eq!(trade.price, 108.5);
+
+    let snapshot = app.snapshot("BTCUSDT").unwrap();
+    assert_eq!(snapshot.timeframes[&Timeframe::M1].history_len, 6);
+    assert_eq!(snapshot.last_trade.unwrap().price, 108.5);
+    assert!(!snapshot.is_stale);
+
+    app.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_app_without_backfill_starts_empty() {
+    let server = MockExchangeServer::start().await.unwrap();
+    let open = Timeframe::M1.align(now_ms());
+    server.candle_history("BTCUSDT", Timeframe::M1, vec![candle(open - 60_000, 100.0)]);
+
+    let app = App::builder()
+        .symbols(["BTCUSDT"])
+        .indicators([Indicator::Sma(3)])
+        .backfill(false)
+        .build_with_client(WebSocketClient::new(server.parser()))
+        .await
+        .unwrap();
+    assert!(app.snapshot("BTCUSDT").is_none());
+
+    app.shutdown().await;
+}
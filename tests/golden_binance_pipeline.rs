@@ -0,0 +1,247 @@
+//! Golden-file test for the Binance pipeline: raw frames -> BinanceParser -> facade
+//! (routing, candle history, indicators) plus trade-tape aggregates.
+//!
+//! The expected output lives in tests/fixtures/golden/binance_pipeline.json. After an
+//! intentional change to numeric results, regenerate it with:
+//!
+//!     CCT_BLESS=1 cargo test golden_binance_pipeline
+//!
+//! and review the diff before committing.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use cct::facade::{App, Indicator};
+use cct::indicators::candle::Candle;
+use cct::indicators::momentum::rsi;
+use cct::indicators::moving_averages::{ema, sma};
+use cct::indicators::timeframe::Timeframe;
+use cct::indicators::volatility::atr;
+use cct::market::analytics::AggressorAggregator;
+use cct::market::providers::binance::BinanceParser;
+use cct::market::{MarketData, MessageParser, TradeSide};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+
+const BLESS_ENV: &str = "CCT_BLESS";
+const SYMBOLS: [&str; 2] = ["BTCUSDT", "ETHUSDT"];
+const INDICATORS: [Indicator; 4] = [
+    Indicator::Sma(10),
+    Indicator::Ema(10),
+    Indicator::Rsi(14),
+    Indicator::Atr(14),
+];
+// Relative tolerance for floats; absolute for values near zero
+const TOLERANCE: f64 = 1e-9;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/golden")
+        .join(name)
+}
+
+/// Closed candles and trade-tape aggregates for one symbol, built independently of the facade.
+#[derive(Default)]
+struct SymbolTape {
+    closed: Vec<Candle>,
+    trade_count: u64,
+    buy_volume: f64,
+    sell_volume: f64,
+    aggressor_orders: u64,
+}
+
+/// Replays the raw log through the parser, feeding the facade and the reference tape.
+async fn replay(frames: &str) -> (App, BTreeMap<String, SymbolTape>, u64) {
+    let parser = BinanceParser::new();
+    let (tx, rx) = mpsc::channel::<MarketData>(frames.lines().count().max(1));
+    let mut app = App::builder()
+        .symbols(SYMBOLS)
+        .timeframes([Timeframe::M1])
+        .indicators(INDICATORS)
+        .build_with_receiver(rx);
+
+    let mut tapes: BTreeMap<String, SymbolTape> = BTreeMap::new();
+    // Fixture prints are ~13s apart; a wide gap groups same-side bursts into orders
+    let mut aggregator = AggressorAggregator::new().with_max_gap_ms(15_000);
+    let mut control_frames = 0;
+
+    for frame in frames.lines().filter(|line| !line.trim().is_empty()) {
+        let Some(data) = parser.parse_message(frame) else {
+            control_frames += 1;
+            continue;
+        };
+
+        match &data {
+            MarketData::Candle { symbol, data: candle, is_closed: true, .. } => {
+                tapes.entry(symbol.to_string()).or_default().closed.push(*candle);
+            }
+            MarketData::Trade(trade) => {
+                let tape = tapes.entry(trade.symbol.to_string()).or_default();
+                tape.trade_count += 1;
+                match trade.side {
+                    TradeSide::Buy => tape.buy_volume += trade.quantity,
+                    TradeSide::Sell => tape.sell_volume += trade.quantity,
+                }
+                if let Some(order) = aggregator.push(trade) {
+                    tapes.entry(order.symbol).or_default().aggressor_orders += 1;
+                }
+            }
+            _ => {}
+        }
+
+        tx.send(data).await.expect("facade feed closed early");
+    }
+
+    for order in aggregator.flush_all() {
+        tapes.entry(order.symbol).or_default().aggressor_orders += 1;
+    }
+
+    drop(tx);
+    app.wait_for_feed_end().await;
+    (app, tapes, control_frames)
+}
+
+fn candle_json(candle: &Candle) -> Value {
+    json!([
+        candle.get_timestamp(),
+        candle.get_open(),
+        candle.get_high(),
+        candle.get_low(),
+        candle.get_close(),
+        candle.get_volume(),
+    ])
+}
+
+fn indicator_name(indicator: Indicator) -> String {
+    format!("{:?}", indicator)
+}
+
+/// Builds the golden document from the replay results.
+fn pipeline_output(app: &App, tapes: &BTreeMap<String, SymbolTape>, control_frames: u64) -> Value {
+    let mut symbols = serde_json::Map::new();
+
+    for symbol in SYMBOLS {
+        let tape = &tapes[symbol];
+        let snapshot = app.snapshot(symbol).expect("facade saw no data for symbol");
+        let timeframe = &snapshot.timeframes[&Timeframe::M1];
+
+        // Indicators straight from the indicator layer over the reference series
+        let reference = json!({
+            "Sma(10)": sma(&tape.closed, 10),
+            "Ema(10)": ema(&tape.closed, 10),
+            "Rsi(14)": rsi(&tape.closed, Some(14)),
+            "Atr(14)": atr(&tape.closed, Some(14)),
+        });
+
+        // Indicators as maintained by the facade's routing/history layer
+        let facade: serde_json::Map<String, Value> = INDICATORS
+            .iter()
+            .map(|indicator| (indicator_name(*indicator), json!(timeframe.indicator(*indicator))))
+            .collect();
+
+        symbols.insert(
+            symbol.to_string(),
+            json!({
+                "closed_candles": tape.closed.iter().map(candle_json).collect::<Vec<_>>(),
+                "facade_history_len": timeframe.history_len,
+                "facade_last_candle": timeframe.candle.as_ref().map(candle_json),
+                "facade_indicators": facade,
+                "indicators": reference,
+                "trade_count": tape.trade_count,
+                "buy_volume": tape.buy_volume,
+                "sell_volume": tape.sell_volume,
+                "cvd": tape.buy_volume - tape.sell_volume,
+                "aggressor_orders": tape.aggressor_orders,
+                "last_trade_price": snapshot.last_trade.as_ref().map(|trade| trade.price),
+            }),
+        );
+    }
+
+    json!({
+        "control_frames": control_frames,
+        "symbols": symbols,
+    })
+}
+
+fn floats_match(expected: f64, actual: f64) -> bool {
+    let diff = (expected - actual).abs();
+    diff <= TOLERANCE || diff <= expected.abs().max(actual.abs()) * TOLERANCE
+}
+
+/// Recursively compares two documents, collecting every mismatch with its JSON path.
+fn compare(path: &str, expected: &Value, actual: &Value, mismatches: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Number(e), Value::Number(a)) => {
+            let (e, a) = (e.as_f64().unwrap_or(f64::NAN), a.as_f64().unwrap_or(f64::NAN));
+            if !floats_match(e, a) {
+                mismatches.push(format!("{}: expected {}, got {}", path, e, a));
+            }
+        }
+        (Value::Array(e), Value::Array(a)) => {
+            if e.len() != a.len() {
+                mismatches.push(format!("{}: expected {} items, got {}", path, e.len(), a.len()));
+            }
+            for (i, (e, a)) in e.iter().zip(a).enumerate() {
+                compare(&format!("{}[{}]", path, i), e, a, mismatches);
+            }
+        }
+        (Value::Object(e), Value::Object(a)) => {
+            for key in e.keys().chain(a.keys().filter(|k| !e.contains_key(*k))) {
+                let child = format!("{}.{}", path, key);
+                match (e.get(key), a.get(key)) {
+                    (Some(e), Some(a)) => compare(&child, e, a, mismatches),
+                    (Some(_), None) => mismatches.push(format!("{}: missing from output", child)),
+                    (None, Some(_)) => mismatches.push(format!("{}: not in golden file", child)),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (e, a) if e != a => mismatches.push(format!("{}: expected {}, got {}", path, e, a)),
+        _ => {}
+    }
+}
+
+#[tokio::test]
+async fn golden_binance_pipeline() {
+    let frames = std::fs::read_to_string(fixture("binance_pipeline.ndjson"))
+        .expect("missing tests/fixtures/golden/binance_pipeline.ndjson");
+    let (app, tapes, control_frames) = replay(&frames).await;
+    let actual = pipeline_output(&app, &tapes, control_frames);
+    app.shutdown().await;
+
+    let golden_path = fixture("binance_pipeline.json");
+    if std::env::var_os(BLESS_ENV).is_some() {
+        let pretty = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+        std::fs::write(&golden_path, pretty).expect("failed to write golden file");
+        eprintln!("Blessed {}", golden_path.display());
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&golden_path).unwrap_or_else(|_| {
+        panic!(
+            "missing {}; run `{}=1 cargo test golden_binance_pipeline` to create it",
+            golden_path.display(),
+            BLESS_ENV
+        )
+    });
+    let expected: Value = serde_json::from_str(&golden).expect("golden file is not valid JSON");
+
+    let mut mismatches = Vec::new();
+    compare("$", &expected, &actual, &mut mismatches);
+    assert!(
+        mismatches.is_empty(),
+        "pipeline output drifted from {} ({} mismatches, rerun with {}=1 if intended):\n{}",
+        golden_path.display(),
+        mismatches.len(),
+        BLESS_ENV,
+        mismatches.join("\n")
+    );
+
+    // Sanity checks so a blessed-but-empty golden can't pass silently
+    for symbol in SYMBOLS {
+        let entry = &expected["symbols"][symbol];
+        assert_eq!(entry["closed_candles"].as_array().map(Vec::len), Some(40));
+        assert!(entry["indicators"]["Atr(14)"].is_number());
+        assert_eq!(entry["facade_indicators"], entry["indicators"]);
+    }
+}
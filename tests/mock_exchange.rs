@@ -0,0 +1,180 @@
+//! End-to-end tests of `WebSocketClient` against `cct::testing::MockExchangeServer`:
+//! connect, subscribe, receive parsed market data, and what happens when the
+//! server goes away.
+
+use std::time::Duration;
+
+use cct::indicators::candle::Candle;
+use cct::indicators::timeframe::Timeframe;
+use cct::market::{ConnectionEvent, ConnectionState, CctError, MarketData, ReconnectPolicy, Stream, WebSocketClient};
+use cct::market::market_data::{Trade, TradeSide};
+use cct::testing::{MockExchangeServer, MockRequest, stream_key};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn trade(price: f64) -> MarketData {
+    MarketData::Trade(Trade::new(1_700_000_000_000, "BTCUSDT", price, 0.5, "1", TradeSide::Buy))
+}
+
+fn candle(close: f64) -> MarketData {
+    MarketData::candle("BTCUSDT", Timeframe::M1, Candle::new(1_700_000_000_000, 1.0, 2.0, 0.5, close, 10.0), true)
+}
+
+#[tokio::test]
+async fn test_subscribe_receive_then_server_closes() {
+    let server = MockExchangeServer::start().await.unwrap();
+    server.script_market_data(&[trade(100.0), candle(1.5)]);
+
+    let mut client = WebSocketClient::new(server.parser());
+    let mut events = client.subscribe_events();
+    let mut rx = client.connect().await.unwrap();
+    client.subscribe(Stream::trades("BTCUSDT")).await.unwrap();
+    assert_eq!(server.subscribed(), vec![stream_key(&Stream::trades("BTCUSDT"))]);
+
+    let first = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+    assert_eq!(first.as_trade().unwrap().price, 100.0);
+    assert_eq!(first.exchange(), "Mock");
+    assert!(first.received_at() >= 1_700_000_000_000);
+    let second = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+    assert_eq!(second.as_candle().unwrap().2.get_close(), 1.5);
+
+    server.push_market_data(&trade(101.0));
+    let pushed = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+    assert_eq!(pushed.as_trade().unwrap().price, 101.0);
+    assert_eq!(client.latency().samples, 3);
+
+    let mut state = client.watch_state();
+    assert!(matches!(*state.borrow(), ConnectionState::Connected { .. }));
+    server.close_all();
+    // The read task reports the drop; no call on the client needed
+    tokio::time::timeout(TIMEOUT, state.wait_for(|state| *state == ConnectionState::Disconnected))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!client.is_connected());
+    assert!(client.needs_reconnect());
+    assert!(tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().is_none());
+    let disconnected = tokio::time::timeout(TIMEOUT, async {
+        loop {
+            if let ConnectionEvent::Disconnected { reason } = events.recv().await.unwrap() {
+                return reason;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(disconnected, "closed by server");
+    let err = client.subscribe(Stream::trades("ETHUSDT")).await.unwrap_err();
+    assert!(matches!(err, CctError::SendFailed), "got {:?}", err);
+}
+
+#[tokio::test]
+async fn test_rejected_subscription_surfaces_reason() {
+    let server = MockExchangeServer::start().await.unwrap();
+    server.reject(stream_key(&Stream::trades("NOPE")));
+
+    let mut client = WebSocketClient::new(server.parser());
+    let _rx = client.connect().await.unwrap();
+    let err = client.subscribe(Stream::trades("NOPE")).await.unwrap_err();
+    match err {
+        CctError::SubscriptionRejected { stream, reason } => {
+            assert_eq!(stream, Stream::trades("NOPE"));
+            assert_eq!(reason, "rejected: trades:NOPE");
+        }
+        other => panic!("expected SubscriptionRejected, got {:?}", other),
+    }
+    assert!(client.subscriptions().is_empty());
+}
+
+#[tokio::test]
+async fn test_auto_reconnect_restores_subscriptions() {
+    let server = MockExchangeServer::start().await.unwrap();
+    let mut client = WebSocketClient::new(server.parser())
+        .with_auto_reconnect(ReconnectPolicy::new(Duration::from_millis(10), Duration::from_millis(50)));
+    let mut events = client.subscribe_events();
+    let mut rx = client.connect().await.unwrap();
+    client.subscribe(Stream::trades("BTCUSDT")).await.unwrap();
+
+    server.close_all();
+    let restored = tokio::time::timeout(TIMEOUT, async {
+        loop {
+            if let ConnectionEvent::SubscriptionRestored { count } = events.recv().await.unwrap() {
+                return count;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(restored, 1);
+    assert_eq!(server.connection_count(), 1);
+    assert!(client.is_connected());
+    assert!(!client.needs_reconnect());
+
+    server.push_market_data(&trade(42.0));
+    let after = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+    assert_eq!(after.as_trade().unwrap().price, 42.0);
+
+    let subscribes: Vec<MockRequest> = server.requests().into_iter().filter(|r| r.op == "subscribe").collect();
+    assert_eq!(subscribes.len(), 2);
+    assert!(subscribes.iter().all(|r| r.streams == vec!["trades:BTCUSDT".to_string()]));
+    client.disconnect().await;
+    assert_eq!(client.state(), ConnectionState::Disconnected);
+}
+
+#[tokio::test]
+async fn test_routed_receiver_takes_its_stream_until_unsubscribed() {
+    let server = MockExchangeServer::start().await.unwrap();
+    let mut client = WebSocketClient::new(server.parser());
+    let mut rx = client.connect().await.unwrap();
+    client.subscribe(Stream::trades("BTCUSDT")).await.unwrap();
+    let candles_stream = Stream::candles("BTCUSDT", Timeframe::M1);
+    let mut candles = client.subscribe_routed(candles_stream.clone()).await.unwrap();
+
+    server.push_market_data(&candle(1.0));
+    server.push_market_data(&trade(100.0));
+    let routed = tokio::time::timeout(TIMEOUT, candles.recv()).await.unwrap().unwrap();
+    assert_eq!(routed.as_candle().unwrap().2.get_close(), 1.0);
+    let main = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+    assert_eq!(main.as_trade().unwrap().price, 100.0);
+
+    client.unsubscribe(&candles_stream).await.unwrap();
+    assert!(tokio::time::timeout(TIMEOUT, candles.recv()).await.unwrap().is_none());
+
+    // Unrouted again: falls back to the main receiver
+    server.push_market_data(&candle(2.0));
+    let main = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+    assert_eq!(main.as_candle().unwrap().2.get_close(), 2.0);
+    client.disconnect().await;
+}
+
+#[tokio::test]
+async fn test_pause_buffers_and_resume_flushes_in_order() {
+    let server = MockExchangeServer::start().await.unwrap();
+    let mut client = WebSocketClient::new(server.parser());
+    let mut rx = client.connect().await.unwrap();
+    client.subscribe(Stream::trades("BTCUSDT")).await.unwrap();
+
+    client.pause();
+    for price in [1.0, 2.0, 3.0] {
+        server.push_market_data(&trade(price));
+    }
+    tokio::time::timeout(TIMEOUT, async {
+        while client.stats().paused_buffered < 3 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap();
+    assert!(rx.try_recv().is_err());
+
+    client.resume();
+    server.push_market_data(&trade(4.0));
+    let mut prices = Vec::new();
+    for _ in 0..4 {
+        let data = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+        prices.push(data.as_trade().unwrap().price);
+    }
+    assert_eq!(prices, vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(client.stats().paused_dropped, 0);
+    client.disconnect().await;
+}
@@ -0,0 +1,178 @@
+//! Every public market data and indicator type survives a JSON round trip, and the
+//! enums keep their documented wire forms.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+use cct::indicators::candle::Candle;
+use cct::indicators::series::TimeSeries;
+use cct::indicators::signals::{DeltaDivergence, DivergenceKind};
+use cct::indicators::swings::{SwingConfig, SwingKind, SwingPoint};
+use cct::indicators::timeframe::Timeframe;
+use cct::market::market_data::{
+    BookTicker, FundingRate, Liquidation, MarkPrice, MarketData, OpenInterest, OrderBookUpdate, PriceLevel,
+    Ticker, Trade, TradeSide,
+};
+use cct::market::Stream;
+
+/// Serializes, deserializes and serializes again; both JSON forms must match.
+/// Returns the deserialized value and its JSON.
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> (T, Value) {
+    let json = serde_json::to_value(value).unwrap();
+    let back: T = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&back).unwrap(), json);
+    (back, json)
+}
+
+fn candle() -> Candle {
+    Candle::new(1_700_000_000_000, 100.0, 110.0, 90.0, 105.0, 1000.0)
+}
+
+#[test]
+fn test_candle_and_timeframe() {
+    let (back, json) = round_trip(&candle());
+    assert_eq!(json["close"], 105.0);
+    assert_eq!(back.get_timestamp(), 1_700_000_000_000);
+    assert_eq!(back.get_volume(), 1000.0);
+
+    for timeframe in [Timeframe::M1, Timeframe::H4, Timeframe::W1, Timeframe::from_seconds(7 * 60).unwrap()] {
+        let (back, json) = round_trip(&timeframe);
+        assert_eq!(back, timeframe);
+        assert_eq!(json, json!(timeframe.as_str()));
+    }
+    assert_eq!(round_trip(&Timeframe::from_seconds(7 * 60).unwrap()).1, json!("7m"));
+    assert!(serde_json::from_value::<Timeframe>(json!("7x")).is_err());
+}
+
+#[test]
+fn test_trade_side_and_price_level() {
+    assert_eq!(round_trip(&TradeSide::Buy).1, json!("buy"));
+    assert_eq!(round_trip(&TradeSide::Sell).1, json!("sell"));
+
+    let (level, _) = round_trip(&PriceLevel::with_order_count(50_000.0, 1.5, 3));
+    assert_eq!(level.num_orders, Some(3));
+}
+
+#[test]
+fn test_market_data_structs() {
+    let trade = Trade::new(1, "BTCUSDT", 50_000.0, 0.5, "42", TradeSide::Buy)
+        .with_buyer_maker(false)
+        .with_trade_id_range(40, 42);
+    let (trade, _) = round_trip(&trade);
+    assert_eq!(trade.last_trade_id, Some(42));
+
+    let book = OrderBookUpdate::snapshot(
+        1,
+        "BTCUSDT",
+        vec![PriceLevel::new(49_900.0, 2.0)],
+        vec![PriceLevel::new(50_100.0, 1.0)],
+    )
+    .with_sequence(7);
+    let (book, _) = round_trip(&book);
+    assert!(book.is_snapshot);
+    assert_eq!(book.asks[0].price, 50_100.0);
+
+    let funding = FundingRate::new(1, "BTCUSDT", 0.0001).with_next_funding_time(2).with_mark_price(50_000.0);
+    assert_eq!(round_trip(&funding).0.next_funding_time, Some(2));
+
+    let mark = MarkPrice::new(1, "BTCUSDT", 50_000.0).with_index_price(49_990.0);
+    assert_eq!(round_trip(&mark).0.index_price, Some(49_990.0));
+
+    let liquidation = Liquidation::new(1, "BTCUSDT", TradeSide::Sell, 49_000.0, 0.2).with_order_type("LIMIT");
+    assert_eq!(round_trip(&liquidation).0.order_type.as_deref(), Some("LIMIT"));
+
+    let open_interest = OpenInterest::new(1, "BTCUSDT", 12_500.0).with_value(625_000_000.0);
+    assert_eq!(round_trip(&open_interest).0.open_interest_value, Some(625_000_000.0));
+
+    let book_ticker = BookTicker::new(1, "BTCUSDT", 49_999.0, 1.0, 50_001.0, 2.0).with_update_id(9);
+    assert_eq!(round_trip(&book_ticker).0.update_id, Some(9));
+
+    let ticker = Ticker::new(1, "BTCUSDT", 50_000.0, 1_234.0).with_range(49_000.0, 51_000.0, 48_500.0);
+    assert_eq!(round_trip(&ticker).0.high, Some(51_000.0));
+}
+
+#[test]
+fn test_market_data_is_adjacently_tagged() {
+    let items = [
+        MarketData::candle("BTCUSDT", Timeframe::M1, candle(), true).with_exchange("Binance"),
+        MarketData::Trade(Trade::new(1, "BTCUSDT", 1.0, 1.0, "t", TradeSide::Sell)),
+        MarketData::OrderBook(OrderBookUpdate::delta(1, "BTCUSDT", vec![], vec![])),
+        MarketData::Funding(FundingRate::new(1, "BTCUSDT", 0.0001)),
+        MarketData::MarkPrice(MarkPrice::new(1, "BTCUSDT", 50_000.0)),
+        MarketData::Liquidation(Liquidation::new(1, "BTCUSDT", TradeSide::Buy, 1.0, 1.0)),
+        MarketData::BookTicker(BookTicker::new(1, "BTCUSDT", 1.0, 1.0, 2.0, 1.0)),
+        MarketData::Ticker(Ticker::new(1, "BTCUSDT", 1.0, 1.0)),
+        MarketData::OpenInterest(OpenInterest::new(1, "BTCUSDT", 1.0)),
+    ];
+    let tags = [
+        "candle", "trade", "order_book", "funding", "mark_price", "liquidation", "book_ticker", "ticker",
+        "open_interest",
+    ];
+    for (item, tag) in items.iter().zip(tags) {
+        let (back, json) = round_trip(item);
+        assert_eq!(json["type"], tag);
+        assert!(json["data"].is_object(), "{} has no data object", tag);
+        assert_eq!(back.symbol(), "BTCUSDT");
+        assert_eq!(back.exchange(), item.exchange());
+    }
+
+    let (_, json) = round_trip(&items[0]);
+    assert_eq!(json["data"]["interval"], "1m");
+    assert_eq!(json["data"]["exchange"], "Binance");
+}
+
+#[test]
+fn test_stream_is_internally_tagged() {
+    let streams = [
+        Stream::candles("BTCUSDT", Timeframe::M5),
+        Stream::trades("BTCUSDT"),
+        Stream::agg_trades("BTCUSDT"),
+        Stream::funding("BTCUSDT"),
+        Stream::mark_price("BTCUSDT"),
+        Stream::order_book("BTCUSDT", 20),
+        Stream::book_ticker("BTCUSDT"),
+        Stream::ticker("BTCUSDT"),
+        Stream::AllMiniTickers,
+        Stream::open_interest("BTCUSDT"),
+        Stream::liquidations("BTCUSDT"),
+    ];
+    for stream in &streams {
+        assert_eq!(&round_trip(stream).0, stream);
+    }
+
+    assert_eq!(
+        serde_json::to_value(&streams[0]).unwrap(),
+        json!({"type": "candles", "symbol": "BTCUSDT", "interval": "5m"})
+    );
+    assert_eq!(serde_json::to_value(&Stream::AllMiniTickers).unwrap(), json!({"type": "all_mini_tickers"}));
+}
+
+#[test]
+fn test_indicator_types() {
+    let series = TimeSeries::from_points([(1, 1.0), (2, 2.5)]);
+    let (back, json) = round_trip(&series);
+    assert_eq!(back, series);
+    assert_eq!(json, json!([[1, 1.0], [2, 2.5]]));
+    // Unordered input is sorted like from_points
+    let unordered: TimeSeries = serde_json::from_value(json!([[2, 2.5], [1, 1.0]])).unwrap();
+    assert_eq!(unordered, series);
+
+    let config = SwingConfig::new(3, 2).with_min_separation(4);
+    assert_eq!(round_trip(&config).0, config);
+
+    let previous = SwingPoint { index: 2, timestamp: 10, price: 100.0, kind: SwingKind::High };
+    let current = SwingPoint { index: 8, timestamp: 40, price: 105.0, kind: SwingKind::High };
+    assert_eq!(round_trip(&previous).1["kind"], "high");
+
+    let divergence = DeltaDivergence {
+        kind: DivergenceKind::Bearish,
+        previous,
+        current,
+        previous_delta: 50.0,
+        current_delta: 20.0,
+    };
+    let (back, json) = round_trip(&divergence);
+    assert_eq!(back, divergence);
+    assert_eq!(json["kind"], "bearish");
+}